@@ -0,0 +1,163 @@
+import { realpathSync } from 'node:fs'
+import { basename, dirname, isAbsolute, join, relative, resolve } from 'node:path'
+import { SERVER_TOKEN } from './auth'
+
+// The app reads lines with this prefix from our stdout as approval requests
+// and answers each with a {"id", "allowed"} line on stdin
+const APPROVAL_PREFIX = '@incito:approval '
+
+const SHELL_TOOLS = new Set(['Bash'])
+const WRITE_TOOLS = new Set(['Write', 'Edit', 'MultiEdit', 'NotebookEdit'])
+
+type PermissionResult =
+  | { behavior: 'allow'; updatedInput: Record<string, unknown> }
+  | { behavior: 'deny'; message: string }
+
+interface ApprovalRequest {
+  // Empty outside a project, where "always allow" rules don't apply
+  project_path: string
+  kind: 'shell' | 'write'
+  tool: string
+  subject: string
+  description?: string
+}
+
+const pending = new Map<string, (allowed: boolean) => void>()
+let listening = false
+
+// With a token the server is remote and the app isn't on our stdin, so
+// requests go out to its GET /approvals streams instead, and answers come
+// back as POST /approvals/:id. Both need the token.
+type Subscriber = (event: string, data: unknown) => void
+const subscribers = new Set<Subscriber>()
+const asked = new Map<string, ApprovalRequest & { id: string }>()
+
+function answer(id: string, allowed: boolean): boolean {
+  const settle = pending.get(id)
+  if (!settle) return false
+  pending.delete(id)
+  if (asked.delete(id)) {
+    for (const send of subscribers) send('resolved', { id, allowed })
+  }
+  settle(allowed)
+  return true
+}
+
+// Sends every request still waiting, then new ones as they come. When the
+// last app goes away, whatever it was asked is denied.
+export function subscribeApprovals(send: Subscriber): () => void {
+  subscribers.add(send)
+  for (const request of asked.values()) send('approval', request)
+  return () => {
+    subscribers.delete(send)
+    if (subscribers.size > 0) return
+    for (const id of [...asked.keys()]) answer(id, false)
+  }
+}
+
+export function answerApproval(id: string, allowed: boolean): boolean {
+  return asked.has(id) && answer(id, allowed)
+}
+
+function listen() {
+  if (listening) return
+  listening = true
+
+  let buffer = ''
+  process.stdin.setEncoding('utf8')
+  process.stdin.on('data', (chunk: string) => {
+    buffer += chunk
+    let newline: number
+    while ((newline = buffer.indexOf('\n')) !== -1) {
+      const line = buffer.slice(0, newline).trim()
+      buffer = buffer.slice(newline + 1)
+      if (!line) continue
+      try {
+        const { id, allowed } = JSON.parse(line) as { id: string; allowed: boolean }
+        answer(id, Boolean(allowed))
+      } catch {
+        // Not an answer
+      }
+    }
+  })
+}
+
+// Outside the app (e.g. `bun run dev`, or a remote server no app is
+// connected to) there's no one to ask, so nothing dangerous is allowed
+function requestApproval(request: ApprovalRequest): Promise<boolean> {
+  const id = crypto.randomUUID()
+  if (SERVER_TOKEN) {
+    if (subscribers.size === 0) return Promise.resolve(false)
+    return new Promise(resolve => {
+      pending.set(id, resolve)
+      asked.set(id, { id, ...request })
+      for (const send of subscribers) send('approval', { id, ...request })
+    })
+  }
+  if (process.stdin.isTTY) return Promise.resolve(false)
+  listen()
+  return new Promise(resolve => {
+    pending.set(id, resolve)
+    console.log(APPROVAL_PREFIX + JSON.stringify({ id, ...request }))
+  })
+}
+
+// Where `path` really is, through symlinks, even when it doesn't exist yet:
+// the deepest part that does is resolved and the rest added on. Mirrors
+// policy::resolve in src-tauri.
+function realPath(path: string): string {
+  const rest: string[] = []
+  let existing = resolve(path)
+  for (;;) {
+    try {
+      return join(realpathSync(existing), ...rest.reverse())
+    } catch {
+      const parent = dirname(existing)
+      if (parent === existing) return resolve(path)
+      rest.push(basename(existing))
+      existing = parent
+    }
+  }
+}
+
+// By where the file really is, so a symlink in the project pointing out of
+// it counts as outside
+function isInside(projectPath: string, file: string): boolean {
+  const path = relative(realPath(projectPath), realPath(resolve(projectPath, file)))
+  return path === '' || (!path.startsWith('..') && !isAbsolute(path))
+}
+
+// Shell commands, and writes outside the project, wait on the app's
+// approval; everything else goes ahead. Without a project every write asks.
+export async function canUseTool(
+  toolName: string,
+  input: Record<string, unknown>,
+  projectPath?: string
+): Promise<PermissionResult> {
+  const base = projectPath || process.cwd()
+  let request: ApprovalRequest | null = null
+  if (SHELL_TOOLS.has(toolName) && typeof input.command === 'string') {
+    request = {
+      project_path: projectPath || '',
+      kind: 'shell',
+      tool: toolName,
+      subject: input.command,
+      description: typeof input.description === 'string' ? input.description : undefined,
+    }
+  } else if (WRITE_TOOLS.has(toolName)) {
+    const file = input.file_path ?? input.notebook_path
+    if (typeof file === 'string' && !(projectPath && isInside(projectPath, file))) {
+      request = {
+        project_path: projectPath || '',
+        kind: 'write',
+        tool: toolName,
+        subject: realPath(resolve(base, file)),
+      }
+    }
+  }
+
+  if (request && !(await requestApproval(request))) {
+    return { behavior: 'deny', message: 'The user did not allow this in Incito' }
+  }
+  return { behavior: 'allow', updatedInput: input }
+}
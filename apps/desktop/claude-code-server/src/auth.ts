@@ -0,0 +1,14 @@
+import { timingSafeEqual } from 'node:crypto'
+
+// When set, every request needs it as a bearer token. The app sets nothing
+// for the server it starts itself, which only listens on loopback.
+export const SERVER_TOKEN = process.env.CLAUDE_CODE_SERVER_TOKEN || ''
+
+export function isAuthorized(req: Request): boolean {
+  if (!SERVER_TOKEN || req.method === 'OPTIONS') return true
+  const header = req.headers.get('Authorization') || ''
+  if (!header.startsWith('Bearer ')) return false
+  const given = Buffer.from(header.slice('Bearer '.length).trim())
+  const expected = Buffer.from(SERVER_TOKEN)
+  return given.length === expected.length && timingSafeEqual(given, expected)
+}
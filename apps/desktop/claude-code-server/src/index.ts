@@ -3,8 +3,29 @@ import { generateText, streamText } from 'ai'
 import { handleListPrompts, handleGetPrompt, handleCompilePrompt } from './prompts-handler'
 import { handleRunLLMJudge, handleTestGrader } from './graders-handler'
 import { openApiSpec } from './openapi'
+import { answerApproval, canUseTool, subscribeApprovals } from './approvals'
+import { captureLogs, recentLogs } from './logs'
+import { isAuthorized, SERVER_TOKEN } from './auth'
+import { version } from '../package.json'
+import { isAbsolute } from 'node:path'
+
+captureLogs()
 
 const PORT = parseInt(process.env.CLAUDE_CODE_PORT || '3457', 10)
+// Set to e.g. 0.0.0.0 to serve other machines, with CLAUDE_CODE_SERVER_TOKEN
+// and a certificate
+const HOST = process.env.CLAUDE_CODE_HOST || '127.0.0.1'
+const TLS_CERT = process.env.CLAUDE_CODE_TLS_CERT
+const TLS_KEY = process.env.CLAUDE_CODE_TLS_KEY
+const MAX_LOG_LINES = 5000
+// Keeps an idle approvals stream open: Bun closes connections idle for 10
+// seconds, and proxies in between may too
+const APPROVALS_KEEPALIVE_MS = 5_000
+
+if (!['127.0.0.1', 'localhost', '::1'].includes(HOST) && !SERVER_TOKEN) {
+  console.error('Refusing to listen on ' + HOST + ' without CLAUDE_CODE_SERVER_TOKEN')
+  process.exit(1)
+}
 
 // Allowed origins for CORS (localhost only for security)
 const ALLOWED_ORIGINS = new Set([
@@ -70,7 +91,9 @@ const claudeCodePath = getClaudePathFromArgs() || process.env.CLAUDE_CODE_EXECUT
 const claudeCodeProvider = createClaudeCode()
 
 // Helper to get model with settings
-function getModel(modelName: 'opus' | 'sonnet' | 'haiku') {
+// `projectPath` is the session's project: Claude Code runs there, and
+// approvals are judged and remembered for it
+function getModel(modelName: 'opus' | 'sonnet' | 'haiku', projectPath?: string) {
   // When running as Bun-compiled binary, we must provide a valid executable path
   // Otherwise the SDK falls back to an internal default that doesn't work in bundled binaries
   if (!claudeCodePath) {
@@ -82,6 +105,8 @@ function getModel(modelName: 'opus' | 'sonnet' | 'haiku') {
 
   return claudeCodeProvider(modelName, {
     pathToClaudeCodeExecutable: claudeCodePath,
+    ...(projectPath ? { cwd: projectPath } : {}),
+    canUseTool: (toolName, input) => canUseTool(toolName, input, projectPath),
   })
 }
 
@@ -89,6 +114,7 @@ interface GenerateRequest {
   model?: 'opus' | 'sonnet' | 'haiku'
   system?: string
   prompt: string
+  projectPath?: string
 }
 
 interface StreamRequest {
@@ -98,6 +124,11 @@ interface StreamRequest {
     role: 'user' | 'assistant' | 'system'
     content: string
   }>
+  projectPath?: string
+}
+
+function invalidProjectPath(projectPath: unknown): boolean {
+  return projectPath !== undefined && projectPath !== null && (typeof projectPath !== 'string' || !isAbsolute(projectPath))
 }
 
 function getCorsHeaders(req: Request): Record<string, string> {
@@ -116,7 +147,19 @@ function getCorsHeaders(req: Request): Record<string, string> {
   }
 }
 
-async function handleRequest(req: Request): Promise<Response> {
+function clientIp(req: Request, server: { requestIP(req: Request): { address: string } | null }): string {
+  // Forwarded headers are only trusted from a proxy on this machine
+  const direct = server.requestIP(req)?.address || 'unknown'
+  if (direct !== '127.0.0.1' && direct !== '::1' && direct !== '::ffff:127.0.0.1') return direct
+  return req.headers.get('X-Forwarded-For')?.split(',')[0]?.trim()
+    || req.headers.get('X-Real-IP')
+    || direct
+}
+
+async function handleRequest(
+  req: Request,
+  server: { requestIP(req: Request): { address: string } | null }
+): Promise<Response> {
   const url = new URL(req.url)
   const corsHeaders = getCorsHeaders(req)
 
@@ -125,12 +168,14 @@ async function handleRequest(req: Request): Promise<Response> {
     return new Response(null, { headers: corsHeaders })
   }
 
-  // Rate limiting (use forwarded IP or connection IP)
-  const clientIp = req.headers.get('X-Forwarded-For')?.split(',')[0]?.trim()
-    || req.headers.get('X-Real-IP')
-    || 'unknown'
+  if (!isAuthorized(req)) {
+    return Response.json(
+      { error: 'Missing or invalid token', code: 'UNAUTHORIZED' },
+      { status: 401, headers: { ...corsHeaders, 'WWW-Authenticate': 'Bearer' } }
+    )
+  }
 
-  if (!checkRateLimit(clientIp)) {
+  if (!checkRateLimit(clientIp(req, server))) {
     return Response.json(
       { error: 'Too many requests', code: 'RATE_LIMITED' },
       { status: 429, headers: { ...corsHeaders, 'Retry-After': '60' } }
@@ -148,6 +193,7 @@ async function handleRequest(req: Request): Promise<Response> {
       {
         status: 'ok',
         provider: 'claude-code',
+        version,
         timestamp: new Date().toISOString(),
         executablePath: claudeCodePath || 'default (system PATH)',
       },
@@ -155,6 +201,12 @@ async function handleRequest(req: Request): Promise<Response> {
     )
   }
 
+  // Recent output, for the app to show when the server runs elsewhere
+  if (url.pathname === '/logs' && req.method === 'GET') {
+    const lines = Math.min(Math.max(parseInt(url.searchParams.get('lines') || '200', 10) || 200, 1), MAX_LOG_LINES)
+    return Response.json({ lines: recentLogs(lines) }, { headers: corsHeaders })
+  }
+
   // Check Claude CLI authentication
   if (url.pathname === '/auth-status') {
     try {
@@ -177,11 +229,56 @@ async function handleRequest(req: Request): Promise<Response> {
     }
   }
 
+  // Approval requests for the app, when it isn't on our stdin. Only with a
+  // token: without one anything on this machine could answer them.
+  if (url.pathname === '/approvals' && req.method === 'GET' && SERVER_TOKEN) {
+    const encoder = new TextEncoder()
+    let unsubscribe = () => {}
+    let keepalive: ReturnType<typeof setInterval> | undefined
+    const stream = new ReadableStream({
+      start(controller) {
+        const write = (text: string) => {
+          try {
+            controller.enqueue(encoder.encode(text))
+          } catch {
+            // Closed; `cancel` unsubscribes
+          }
+        }
+        unsubscribe = subscribeApprovals((event, data) => write(`event: ${event}\ndata: ${JSON.stringify(data)}\n\n`))
+        keepalive = setInterval(() => write(': keepalive\n\n'), APPROVALS_KEEPALIVE_MS)
+      },
+      cancel() {
+        clearInterval(keepalive)
+        unsubscribe()
+      },
+    })
+    return new Response(stream, {
+      headers: {
+        ...corsHeaders,
+        'Content-Type': 'text/event-stream',
+        'Cache-Control': 'no-cache',
+        'Connection': 'keep-alive',
+      },
+    })
+  }
+
+  const approvalMatch = url.pathname.match(/^\/approvals\/([^/]+)$/)
+  if (approvalMatch && req.method === 'POST' && SERVER_TOKEN) {
+    const body = await req.json().catch(() => null) as { allowed?: unknown } | null
+    if (typeof body?.allowed !== 'boolean') {
+      return Response.json({ error: 'allowed must be true or false' }, { status: 400, headers: corsHeaders })
+    }
+    if (!answerApproval(approvalMatch[1], body.allowed)) {
+      return Response.json({ error: 'No such approval request' }, { status: 404, headers: corsHeaders })
+    }
+    return Response.json({ ok: true }, { headers: corsHeaders })
+  }
+
   // Generate text (non-streaming)
   if (url.pathname === '/generate' && req.method === 'POST') {
     try {
       const body = await req.json() as GenerateRequest
-      const { model = 'sonnet', system, prompt } = body
+      const { model = 'sonnet', system, prompt, projectPath } = body
 
       if (!prompt) {
         return Response.json(
@@ -189,9 +286,15 @@ async function handleRequest(req: Request): Promise<Response> {
           { status: 400, headers: corsHeaders }
         )
       }
+      if (invalidProjectPath(projectPath)) {
+        return Response.json(
+          { error: 'projectPath must be an absolute path' },
+          { status: 400, headers: corsHeaders }
+        )
+      }
 
       const result = await generateText({
-        model: getModel(model),
+        model: getModel(model, projectPath || undefined),
         system,
         prompt,
       })
@@ -220,7 +323,7 @@ async function handleRequest(req: Request): Promise<Response> {
   if (url.pathname === '/stream' && req.method === 'POST') {
     try {
       const body = await req.json() as StreamRequest
-      const { model = 'sonnet', system, messages } = body
+      const { model = 'sonnet', system, messages, projectPath } = body
 
       if (!messages || messages.length === 0) {
         return Response.json(
@@ -228,9 +331,15 @@ async function handleRequest(req: Request): Promise<Response> {
           { status: 400, headers: corsHeaders }
         )
       }
+      if (invalidProjectPath(projectPath)) {
+        return Response.json(
+          { error: 'projectPath must be an absolute path' },
+          { status: 400, headers: corsHeaders }
+        )
+      }
 
       const result = streamText({
-        model: getModel(model),
+        model: getModel(model, projectPath || undefined),
         system,
         messages,
       })
@@ -316,6 +425,9 @@ async function handleRequest(req: Request): Promise<Response> {
         '/openapi.json',
         '/health',
         '/auth-status',
+        '/logs',
+        '/approvals',
+        '/approvals/:id',
         '/generate',
         '/stream',
         '/prompts',
@@ -332,14 +444,22 @@ async function handleRequest(req: Request): Promise<Response> {
 // Start the server
 const server = Bun.serve({
   port: PORT,
+  hostname: HOST,
   fetch: handleRequest,
+  ...(TLS_CERT && TLS_KEY ? { tls: { cert: Bun.file(TLS_CERT), key: Bun.file(TLS_KEY) } } : {}),
 })
 
-console.log(`Claude Code server running on http://localhost:${server.port}`)
+const scheme = TLS_CERT && TLS_KEY ? 'https' : 'http'
+console.log(`Claude Code server running on ${scheme}://${HOST}:${server.port}`)
 console.log('Endpoints:')
 console.log('  GET  /openapi.json        - OpenAPI 3.0 specification')
 console.log('  GET  /health              - Health check')
 console.log('  GET  /auth-status         - Check Claude CLI authentication')
+console.log('  GET  /logs                - Recent server output (?lines=)')
+if (SERVER_TOKEN) {
+  console.log('  GET  /approvals           - Approval requests for the app (SSE)')
+  console.log('  POST /approvals/:id       - Answer an approval request')
+}
 console.log('  POST /generate            - Generate text (non-streaming)')
 console.log('  POST /stream              - Stream text (SSE)')
 console.log('  GET  /prompts             - List prompts (?tag=, ?search=)')
@@ -0,0 +1,36 @@
+import { format } from 'node:util'
+
+// The most recent console output, for GET /logs
+const MAX_LINES = 5000
+const lines: string[] = []
+
+function remember(stream: string, args: unknown[]) {
+  for (const line of format(...args).split('\n')) {
+    lines.push(`${new Date().toISOString()} [${stream}] ${line}`)
+  }
+  if (lines.length > MAX_LINES) lines.splice(0, lines.length - MAX_LINES)
+}
+
+// Keeps printing as before; approval requests on stdout are left out since
+// they're for the app
+export function captureLogs() {
+  const log = console.log.bind(console)
+  const error = console.error.bind(console)
+  const warn = console.warn.bind(console)
+  console.log = (...args: unknown[]) => {
+    if (!(typeof args[0] === 'string' && args[0].startsWith('@incito:approval '))) remember('stdout', args)
+    log(...args)
+  }
+  console.error = (...args: unknown[]) => {
+    remember('stderr', args)
+    error(...args)
+  }
+  console.warn = (...args: unknown[]) => {
+    remember('stderr', args)
+    warn(...args)
+  }
+}
+
+export function recentLogs(count: number): string[] {
+  return lines.slice(-count)
+}
@@ -1,3 +1,76 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn git_commit() -> String {
+    Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn cargo_home() -> Option<PathBuf> {
+    std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cargo")))
+        .or_else(|| std::env::var_os("USERPROFILE").map(|home| Path::new(&home).join(".cargo")))
+}
+
+fn quoted_value(line: &str, key: &str) -> Option<String> {
+    let rest = line.strip_prefix(key)?.trim_start().strip_prefix('=')?.trim();
+    Some(rest.trim_matches('"').to_string())
+}
+
+// The license field from the crate's manifest in the registry cache
+fn crate_license(registry: &[PathBuf], name: &str, version: &str) -> String {
+    registry
+        .iter()
+        .map(|dir| dir.join(format!("{}-{}", name, version)).join("Cargo.toml"))
+        .find_map(|manifest| std::fs::read_to_string(manifest).ok())
+        .and_then(|manifest| manifest.lines().find_map(|line| quoted_value(line, "license")))
+        .unwrap_or_else(|| "see crate".to_string())
+}
+
+// One "name version: license" line for every registry crate in Cargo.lock
+fn third_party_licenses() -> String {
+    let Ok(lock) = std::fs::read_to_string("Cargo.lock") else { return String::new() };
+    let registry: Vec<PathBuf> = cargo_home()
+        .and_then(|home| std::fs::read_dir(home.join("registry").join("src")).ok())
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+
+    let mut lines = Vec::new();
+    for package in lock.split("[[package]]").skip(1) {
+        let field = |key: &str| package.lines().find_map(|line| quoted_value(line, key));
+        let (Some(name), Some(version)) = (field("name"), field("version")) else { continue };
+        // Workspace crates have no source
+        if field("source").is_none() {
+            continue;
+        }
+        let license = crate_license(&registry, &name, &version);
+        lines.push(format!("{} {}: {}", name, version, license));
+    }
+    lines.join("\n")
+}
+
 fn main() {
+    println!("cargo:rustc-env=INCITO_GIT_COMMIT={}", git_commit());
+    // Sidecar builds are published per target triple, like the bundled ones
+    println!(
+        "cargo:rustc-env=INCITO_TARGET={}",
+        std::env::var("TARGET").expect("TARGET is set by cargo")
+    );
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    // sqlx::migrate! embeds these, but only notices new files through this
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=../../../.git/HEAD");
+    println!("cargo:rerun-if-changed=../../../.git/refs/heads");
+
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").expect("OUT_DIR is set by cargo"));
+    std::fs::write(out_dir.join("third_party_licenses.txt"), third_party_licenses())
+        .expect("failed to write third-party licenses");
+
     tauri_build::build()
 }
@@ -0,0 +1,298 @@
+-- Schema as the frontend used to create it on first load, with the columns
+-- it later added through ALTER TABLE folded in. Databases from before this
+-- migration are brought up to the same shape by `migrations::upgrade_legacy`
+-- before it runs.
+
+CREATE TABLE IF NOT EXISTS settings (
+  key TEXT PRIMARY KEY,
+  value TEXT
+);
+
+CREATE TABLE IF NOT EXISTS tags (
+  id TEXT PRIMARY KEY,
+  name TEXT UNIQUE NOT NULL,
+  color TEXT DEFAULT '#6b7280',
+  created_at TEXT,
+  updated_at TEXT,
+  sync_id TEXT
+);
+
+CREATE TABLE IF NOT EXISTS prompt_tags (
+  prompt_path TEXT NOT NULL,
+  tag_id TEXT NOT NULL,
+  PRIMARY KEY (prompt_path, tag_id),
+  FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS prompt_versions (
+  id TEXT PRIMARY KEY,
+  prompt_path TEXT NOT NULL,
+  version_number INTEGER NOT NULL,
+  content TEXT NOT NULL,
+  created_at TEXT NOT NULL,
+  description TEXT,
+  run_config TEXT,
+  UNIQUE(prompt_path, version_number)
+);
+
+CREATE INDEX IF NOT EXISTS idx_prompt_versions_path ON prompt_versions(prompt_path);
+
+CREATE TABLE IF NOT EXISTS prompt_drafts (
+  prompt_id TEXT PRIMARY KEY,
+  variable_values TEXT NOT NULL,
+  updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS recent_prompts (
+  prompt_id TEXT PRIMARY KEY,
+  last_used_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS pinned_prompts (
+  prompt_id TEXT PRIMARY KEY,
+  pinned_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS translation_cache (
+  hash TEXT PRIMARY KEY,
+  source_lang TEXT NOT NULL,
+  target_lang TEXT NOT NULL,
+  source_text TEXT NOT NULL,
+  translated_text TEXT NOT NULL,
+  confidence TEXT NOT NULL,
+  created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_translation_cache_created ON translation_cache(created_at);
+
+CREATE TABLE IF NOT EXISTS prompt_runs (
+  id TEXT PRIMARY KEY,
+  prompt_id TEXT NOT NULL,
+  prompt_path TEXT NOT NULL,
+  prompt_name TEXT NOT NULL,
+  launcher_id TEXT NOT NULL,
+  status TEXT NOT NULL DEFAULT 'pending',
+  error_message TEXT,
+  started_at TEXT NOT NULL,
+  completed_at TEXT,
+  execution_time_ms INTEGER,
+  run_file_path TEXT,
+  created_at TEXT NOT NULL,
+  input_tokens INTEGER,
+  output_tokens INTEGER,
+  total_tokens INTEGER,
+  model_id TEXT,
+  provider TEXT,
+  estimated_cost_usd REAL,
+  sync_id TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_prompt_runs_prompt_id ON prompt_runs(prompt_id);
+
+CREATE INDEX IF NOT EXISTS idx_prompt_runs_created_at ON prompt_runs(created_at);
+
+CREATE TABLE IF NOT EXISTS run_variables (
+  id TEXT PRIMARY KEY,
+  run_id TEXT NOT NULL,
+  variable_key TEXT NOT NULL,
+  variable_value TEXT NOT NULL,
+  variable_type TEXT NOT NULL,
+  FOREIGN KEY (run_id) REFERENCES prompt_runs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_variables_run_id ON run_variables(run_id);
+
+CREATE TABLE IF NOT EXISTS run_analytics_daily (
+  id TEXT PRIMARY KEY,
+  prompt_id TEXT NOT NULL,
+  date TEXT NOT NULL,
+  run_count INTEGER NOT NULL DEFAULT 0,
+  success_count INTEGER NOT NULL DEFAULT 0,
+  error_count INTEGER NOT NULL DEFAULT 0,
+  total_execution_time_ms INTEGER NOT NULL DEFAULT 0,
+  avg_execution_time_ms INTEGER NOT NULL DEFAULT 0,
+  total_input_tokens INTEGER NOT NULL DEFAULT 0,
+  total_output_tokens INTEGER NOT NULL DEFAULT 0,
+  total_estimated_cost_usd REAL NOT NULL DEFAULT 0,
+  UNIQUE(prompt_id, date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_analytics_prompt_id ON run_analytics_daily(prompt_id);
+
+CREATE TABLE IF NOT EXISTS chat_sessions (
+  id TEXT PRIMARY KEY,
+  agent_id TEXT NOT NULL,
+  title TEXT NOT NULL,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  sync_id TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_sessions_agent_id ON chat_sessions(agent_id);
+
+CREATE TABLE IF NOT EXISTS chat_messages (
+  id TEXT PRIMARY KEY,
+  session_id TEXT NOT NULL,
+  role TEXT NOT NULL,
+  content TEXT NOT NULL,
+  timestamp TEXT NOT NULL,
+  tool_calls TEXT,
+  FOREIGN KEY (session_id) REFERENCES chat_sessions(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_chat_messages_session_id ON chat_messages(session_id);
+
+CREATE TABLE IF NOT EXISTS pinned_agents (
+  agent_id TEXT PRIMARY KEY,
+  pinned_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS resources (
+  id TEXT PRIMARY KEY,
+  file_name TEXT NOT NULL,
+  file_path TEXT NOT NULL UNIQUE,
+  file_type TEXT NOT NULL,
+  mime_type TEXT NOT NULL,
+  file_size INTEGER NOT NULL,
+  uploaded_at TEXT NOT NULL,
+  indexed INTEGER NOT NULL DEFAULT 0,
+  indexed_at TEXT,
+  chunk_count INTEGER DEFAULT 0,
+  thumbnail_base64 TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_resources_file_type ON resources(file_type);
+
+CREATE INDEX IF NOT EXISTS idx_resources_uploaded_at ON resources(uploaded_at);
+
+CREATE TABLE IF NOT EXISTS resource_chunks (
+  id TEXT PRIMARY KEY,
+  resource_id TEXT NOT NULL,
+  chunk_index INTEGER NOT NULL,
+  content TEXT NOT NULL,
+  embedding TEXT,
+  created_at TEXT NOT NULL,
+  FOREIGN KEY (resource_id) REFERENCES resources(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_resource_chunks_resource_id ON resource_chunks(resource_id);
+
+CREATE TABLE IF NOT EXISTS graders (
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  description TEXT,
+  type TEXT NOT NULL CHECK (type IN ('assertion', 'llm_judge')),
+  config TEXT NOT NULL,
+  is_builtin INTEGER NOT NULL DEFAULT 0,
+  enabled INTEGER NOT NULL DEFAULT 1,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  sync_id TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_graders_type ON graders(type);
+
+CREATE TABLE IF NOT EXISTS prompt_graders (
+  id TEXT PRIMARY KEY,
+  prompt_id TEXT NOT NULL,
+  grader_id TEXT NOT NULL,
+  enabled INTEGER NOT NULL DEFAULT 1,
+  created_at TEXT NOT NULL,
+  UNIQUE(prompt_id, grader_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_prompt_graders_prompt ON prompt_graders(prompt_id);
+
+CREATE TABLE IF NOT EXISTS grader_results (
+  id TEXT PRIMARY KEY,
+  run_id TEXT NOT NULL,
+  grader_id TEXT NOT NULL,
+  score REAL NOT NULL,
+  passed INTEGER NOT NULL,
+  reason TEXT,
+  raw_score REAL,
+  execution_time_ms INTEGER NOT NULL,
+  created_at TEXT NOT NULL,
+  FOREIGN KEY (run_id) REFERENCES prompt_runs(id) ON DELETE CASCADE,
+  FOREIGN KEY (grader_id) REFERENCES graders(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_grader_results_run ON grader_results(run_id);
+
+CREATE INDEX IF NOT EXISTS idx_grader_results_passed ON grader_results(passed);
+
+CREATE TABLE IF NOT EXISTS run_feedback (
+  id TEXT PRIMARY KEY,
+  run_id TEXT NOT NULL UNIQUE,
+  rating INTEGER,
+  pass_fail TEXT CHECK (pass_fail IN ('pass', 'fail')),
+  notes TEXT,
+  tags TEXT,
+  time_spent_ms INTEGER,
+  reviewed_at TEXT NOT NULL,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  FOREIGN KEY (run_id) REFERENCES prompt_runs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_run_feedback_run_id ON run_feedback(run_id);
+
+CREATE TABLE IF NOT EXISTS playbooks (
+  id TEXT PRIMARY KEY,
+  name TEXT NOT NULL,
+  description TEXT,
+  enabled INTEGER NOT NULL DEFAULT 1,
+  rule_count INTEGER NOT NULL DEFAULT 0,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  sync_id TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_playbooks_enabled ON playbooks(enabled);
+
+CREATE TABLE IF NOT EXISTS playbook_rules (
+  id TEXT PRIMARY KEY,
+  playbook_id TEXT NOT NULL,
+  trigger_context TEXT NOT NULL,
+  instruction TEXT NOT NULL,
+  bad_example_input TEXT,
+  bad_example_output TEXT,
+  golden_output TEXT,
+  source_run_id TEXT,
+  priority INTEGER NOT NULL DEFAULT 100,
+  enabled INTEGER NOT NULL DEFAULT 1,
+  created_at TEXT NOT NULL,
+  updated_at TEXT NOT NULL,
+  sync_id TEXT,
+  FOREIGN KEY (playbook_id) REFERENCES playbooks(id) ON DELETE CASCADE,
+  FOREIGN KEY (source_run_id) REFERENCES prompt_runs(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_playbook_rules_playbook ON playbook_rules(playbook_id);
+
+CREATE INDEX IF NOT EXISTS idx_playbook_rules_priority ON playbook_rules(priority);
+
+CREATE TABLE IF NOT EXISTS prompt_playbooks (
+  id TEXT PRIMARY KEY,
+  prompt_id TEXT NOT NULL,
+  playbook_id TEXT NOT NULL,
+  "order" INTEGER NOT NULL DEFAULT 0,
+  enabled INTEGER NOT NULL DEFAULT 1,
+  created_at TEXT NOT NULL,
+  UNIQUE(prompt_id, playbook_id),
+  FOREIGN KEY (playbook_id) REFERENCES playbooks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_prompt_playbooks_prompt ON prompt_playbooks(prompt_id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tags_sync_id ON tags(sync_id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_graders_sync_id ON graders(sync_id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_playbooks_sync_id ON playbooks(sync_id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_playbook_rules_sync_id ON playbook_rules(sync_id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_chat_sessions_sync_id ON chat_sessions(sync_id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_prompt_runs_sync_id ON prompt_runs(sync_id);
@@ -0,0 +1,12 @@
+-- Projects the user opened, registered from Rust. Where the table was
+-- created on first use, before this migration, its missing columns are
+-- added by `migrations::upgrade_first_use` before it runs.
+CREATE TABLE IF NOT EXISTS projects (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    path TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    last_opened_at TEXT,
+    kind TEXT,
+    pinned INTEGER NOT NULL DEFAULT 0
+);
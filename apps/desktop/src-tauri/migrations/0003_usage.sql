@@ -0,0 +1,42 @@
+-- Token usage rolled up from Claude Code transcripts, which Rust used to
+-- create on first use. Older usage_files tables get `per_file` from
+-- `migrations::upgrade_first_use` before this runs, left at 0 for the
+-- transcripts counted before their share was kept.
+
+CREATE TABLE IF NOT EXISTS usage_daily (
+    date TEXT NOT NULL,
+    model TEXT NOT NULL,
+    project_path TEXT NOT NULL,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+    cost_usd REAL NOT NULL DEFAULT 0,
+    message_count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (date, model, project_path)
+);
+
+-- Where aggregation stopped in each transcript, so reruns only read new lines
+CREATE TABLE IF NOT EXISTS usage_files (
+    file_path TEXT PRIMARY KEY,
+    file_size INTEGER NOT NULL,
+    indexed_offset INTEGER NOT NULL,
+    last_message_id TEXT,
+    per_file INTEGER NOT NULL DEFAULT 0
+);
+
+-- Each transcript's share of usage_daily, taken back out before a rewritten
+-- transcript is counted again
+CREATE TABLE IF NOT EXISTS usage_file_daily (
+    file_path TEXT NOT NULL,
+    date TEXT NOT NULL,
+    model TEXT NOT NULL,
+    project_path TEXT NOT NULL,
+    input_tokens INTEGER NOT NULL DEFAULT 0,
+    output_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+    cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+    cost_usd REAL NOT NULL DEFAULT 0,
+    message_count INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (file_path, date, model, project_path)
+);
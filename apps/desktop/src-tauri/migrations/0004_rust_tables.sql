@@ -0,0 +1,112 @@
+-- The tables Rust used to create on first use. Where one was created that
+-- way before this migration, its missing columns are added by
+-- `migrations::upgrade_first_use` before it runs.
+
+-- Claude Code sessions indexed from ~/.claude/projects
+CREATE TABLE IF NOT EXISTS claude_sessions (
+    id TEXT PRIMARY KEY,
+    project_path TEXT NOT NULL,
+    file_path TEXT NOT NULL,
+    first_prompt TEXT,
+    started_at TEXT,
+    updated_at TEXT,
+    message_count INTEGER NOT NULL DEFAULT 0,
+    file_size INTEGER NOT NULL DEFAULT 0,
+    file_mtime INTEGER NOT NULL DEFAULT 0,
+    indexed_offset INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_claude_sessions_updated_at ON claude_sessions(updated_at);
+
+-- Sessions removed by a retention rule, so the disk scan doesn't bring
+-- them back unless the transcript changes
+CREATE TABLE IF NOT EXISTS claude_sessions_pruned (
+    id TEXT PRIMARY KEY,
+    file_mtime INTEGER NOT NULL
+);
+
+-- Sessions indexed before messages were searchable are parsed again
+DELETE FROM claude_sessions
+WHERE NOT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'claude_messages_fts');
+
+CREATE VIRTUAL TABLE IF NOT EXISTS claude_messages_fts USING fts5(
+    content,
+    session_id UNINDEXED,
+    message_uuid UNINDEXED,
+    role UNINDEXED,
+    timestamp UNINDEXED,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+CREATE TABLE IF NOT EXISTS command_runs (
+    id TEXT PRIMARY KEY,
+    project_path TEXT NOT NULL,
+    command TEXT NOT NULL,
+    args TEXT NOT NULL DEFAULT '[]',
+    started_at TEXT NOT NULL,
+    exit_code INTEGER,
+    duration_ms INTEGER NOT NULL,
+    cancelled INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS approval_rules (
+    id TEXT PRIMARY KEY,
+    project_path TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    pattern TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    UNIQUE (project_path, kind, pattern)
+);
+
+CREATE TABLE IF NOT EXISTS approval_decisions (
+    id TEXT PRIMARY KEY,
+    project_path TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    tool TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    allowed INTEGER NOT NULL,
+    decided_by TEXT NOT NULL,
+    decided_at TEXT NOT NULL
+);
+
+-- Entries can only be added: the triggers refuse any change or removal
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    occurred_at TEXT NOT NULL,
+    category TEXT NOT NULL,
+    action TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    origin TEXT
+);
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+BEGIN SELECT RAISE(ABORT, 'The audit log is append-only'); END;
+
+CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+BEGIN SELECT RAISE(ABORT, 'The audit log is append-only'); END;
+
+CREATE TABLE IF NOT EXISTS claude_profiles (
+    id TEXT PRIMARY KEY,
+    name TEXT UNIQUE NOT NULL,
+    executable_path TEXT NOT NULL,
+    env TEXT NOT NULL DEFAULT '{}',
+    active INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS project_sandbox (
+    project_path TEXT PRIMARY KEY,
+    enabled INTEGER NOT NULL DEFAULT 0,
+    allow_network INTEGER NOT NULL DEFAULT 0,
+    allow_project_writes INTEGER NOT NULL DEFAULT 0,
+    cpu_seconds INTEGER,
+    memory_mb INTEGER,
+    accept_limits_only INTEGER NOT NULL DEFAULT 0
+);
+
+-- The folders operations in a project may touch; none is the whole project
+CREATE TABLE IF NOT EXISTS project_allowed_dirs (
+    project_path TEXT NOT NULL,
+    dir TEXT NOT NULL,
+    PRIMARY KEY (project_path, dir)
+);
@@ -0,0 +1,81 @@
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind, MessageDialogResult};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::{help, i18n};
+
+// Collected from Cargo.lock by build.rs
+const THIRD_PARTY_LICENSES: &str = include_str!(concat!(env!("OUT_DIR"), "/third_party_licenses.txt"));
+
+#[derive(Clone, serde::Serialize)]
+pub struct AboutInfo {
+    pub name: String,
+    pub version: String,
+    pub commit: String,
+    pub tauri_version: String,
+    pub claude_version: Option<String>,
+    pub os: String,
+    pub licenses: String,
+}
+
+pub async fn about_info(app: &AppHandle) -> AboutInfo {
+    AboutInfo {
+        name: app.package_info().name.clone(),
+        version: app.package_info().version.to_string(),
+        commit: env!("INCITO_GIT_COMMIT").to_string(),
+        tauri_version: tauri::VERSION.to_string(),
+        claude_version: help::claude_version(app).await,
+        os: format!(
+            "{} {} ({})",
+            tauri_plugin_os::type_(),
+            tauri_plugin_os::version(),
+            tauri_plugin_os::arch()
+        ),
+        licenses: THIRD_PARTY_LICENSES.to_string(),
+    }
+}
+
+// Licenses are too long for a dialog, so they're written out and opened in
+// the default text viewer
+fn open_licenses(app: &AppHandle) -> Result<(), String> {
+    let dir = app.path().app_cache_dir().map_err(|e| e.to_string())?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join("THIRD_PARTY_LICENSES.txt");
+    std::fs::write(&path, THIRD_PARTY_LICENSES).map_err(|e| format!("Failed to write licenses: {}", e))?;
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open licenses: {}", e))
+}
+
+pub async fn show_about(app: &AppHandle) {
+    let info = about_info(app).await;
+    let crates = info.licenses.lines().count();
+    let message = format!(
+        "{}\nTauri {}\nClaude Code {}\n{}\n\n{}",
+        i18n::t_with(app, "dialogs.version", &[("version", &info.version), ("commit", &info.commit)]),
+        info.tauri_version,
+        info.claude_version.unwrap_or_else(|| i18n::t(app, "dialogs.notFound")),
+        info.os,
+        i18n::t_with(app, "dialogs.builtWith", &[("count", &crates.to_string())]),
+    );
+
+    let licenses_button = i18n::t(app, "dialogs.thirdPartyLicenses");
+    let handle = app.clone();
+    app.dialog()
+        .message(message)
+        .title(i18n::t_with(app, "dialogs.aboutTitle", &[("app", &info.name)]))
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom(i18n::t(app, "dialogs.ok"), licenses_button.clone()))
+        .show_with_result(move |result| {
+            if result == MessageDialogResult::Custom(licenses_button) {
+                if let Err(e) = open_licenses(&handle) {
+                    eprintln!("{}", e);
+                }
+            }
+        });
+}
+
+#[tauri::command]
+pub async fn get_about_info(app: AppHandle) -> Result<AboutInfo, String> {
+    Ok(about_info(&app).await)
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use tauri::menu::MenuItemKind;
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::settings;
+
+#[cfg(target_os = "macos")]
+const FULLSCREEN: &str = "Ctrl+Cmd+F";
+#[cfg(not(target_os = "macos"))]
+const FULLSCREEN: &str = "F11";
+
+// Every custom menu item and its default shortcut; an empty string means
+// none by default. Predefined items (copy, quit...) use the OS shortcuts.
+const DEFAULTS: &[(&str, &str)] = &[
+    ("about", ""),
+    ("check-updates", "CmdOrCtrl+Shift+U"),
+    ("rollback-update", ""),
+    ("new-session", "CmdOrCtrl+N"),
+    ("open-project", "CmdOrCtrl+O"),
+    ("clear-recent", ""),
+    ("stop-task", "CmdOrCtrl+."),
+    ("find", "CmdOrCtrl+F"),
+    ("zoom-in", "CmdOrCtrl+="),
+    ("zoom-out", "CmdOrCtrl+-"),
+    ("zoom-reset", "CmdOrCtrl+0"),
+    ("reload", "CmdOrCtrl+R"),
+    ("toggle-fullscreen", FULLSCREEN),
+    ("toggle-devtools", "Alt+CmdOrCtrl+I"),
+    ("maximize", "Alt+CmdOrCtrl+M"),
+    ("always-on-top", "Alt+CmdOrCtrl+T"),
+    ("mini-mode", "CmdOrCtrl+Shift+M"),
+    ("open-logs", ""),
+    ("show-config", ""),
+    ("restart-server", "CmdOrCtrl+Shift+R"),
+    ("report-issue", ""),
+];
+
+// User overrides from the settings store, keyed by menu item id. An empty
+// value turns the item's shortcut off.
+#[derive(Default)]
+pub struct AcceleratorState(Mutex<HashMap<String, String>>);
+
+#[derive(Clone, serde::Serialize)]
+pub struct MenuAccelerator {
+    pub id: String,
+    pub default_accelerator: Option<String>,
+    pub accelerator: Option<String>,
+}
+
+fn default_accelerator(id: &str) -> Option<&'static str> {
+    DEFAULTS
+        .iter()
+        .find(|(item, _)| *item == id)
+        .map(|(_, accelerator)| *accelerator)
+        .filter(|accelerator| !accelerator.is_empty())
+}
+
+fn resolve(overrides: &HashMap<String, String>, id: &str) -> Option<String> {
+    match overrides.get(id) {
+        Some(accelerator) if accelerator.is_empty() => None,
+        Some(accelerator) => Some(accelerator.clone()),
+        None => default_accelerator(id).map(str::to_string),
+    }
+}
+
+// The shortcut a menu item should be built with. Overrides aren't loaded
+// yet when the menu is first created, so `load_saved` applies them after.
+pub fn current(app: &AppHandle, id: &str) -> Option<String> {
+    let overrides = app
+        .try_state::<AcceleratorState>()
+        .and_then(|state| state.0.lock().ok().map(|overrides| overrides.clone()))
+        .unwrap_or_default();
+    resolve(&overrides, id)
+}
+
+fn find_in(items: Vec<MenuItemKind<Wry>>, id: &str) -> Option<MenuItemKind<Wry>> {
+    for item in items {
+        if item.id() == id {
+            return Some(item);
+        }
+        if let Some(found) = item.as_submenu().and_then(|submenu| find_in(submenu.items().unwrap_or_default(), id)) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+fn apply(app: &AppHandle, id: &str, accelerator: Option<&str>) -> Result<(), String> {
+    let Some(menu) = app.menu() else { return Ok(()) };
+    let Some(item) = find_in(menu.items().map_err(|e| e.to_string())?, id) else { return Ok(()) };
+    let result = match &item {
+        MenuItemKind::MenuItem(item) => item.set_accelerator(accelerator),
+        MenuItemKind::Check(item) => item.set_accelerator(accelerator),
+        MenuItemKind::Icon(item) => item.set_accelerator(accelerator),
+        _ => Ok(()),
+    };
+    result.map_err(|e| format!("Invalid shortcut \"{}\": {}", accelerator.unwrap_or_default(), e))
+}
+
+pub fn is_known(id: &str) -> bool {
+    DEFAULTS.iter().any(|(item, _)| *item == id)
+}
+
+// Apply the shortcuts saved in the settings store to the app menu. Items
+// that lost their override since the last load get their default back.
+pub async fn load_saved(app: &AppHandle) -> Result<(), String> {
+    let overrides = settings::get(app).shortcuts.menu;
+    let previous = app.state::<AcceleratorState>().0.lock().map_err(|e| e.to_string())?.clone();
+    for id in overrides.keys().chain(previous.keys().filter(|id| !overrides.contains_key(*id))) {
+        if let Err(e) = apply(app, id, resolve(&overrides, id).as_deref()) {
+            eprintln!("Skipping saved shortcut for {}: {}", id, e);
+        }
+    }
+    *app.state::<AcceleratorState>().0.lock().map_err(|e| e.to_string())? = overrides;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_menu_accelerators(state: tauri::State<'_, AcceleratorState>) -> Result<Vec<MenuAccelerator>, String> {
+    let overrides = state.0.lock().map_err(|e| e.to_string())?;
+    Ok(DEFAULTS
+        .iter()
+        .map(|(id, _)| MenuAccelerator {
+            id: id.to_string(),
+            default_accelerator: default_accelerator(id).map(str::to_string),
+            accelerator: resolve(&overrides, id),
+        })
+        .collect())
+}
+
+// Rebind a menu item. `None` restores the default and an empty string
+// removes the shortcut.
+#[tauri::command]
+pub async fn set_menu_accelerator(app: AppHandle, id: String, accelerator: Option<String>) -> Result<(), String> {
+    if !is_known(&id) {
+        return Err(format!("Unknown menu item: {}", id));
+    }
+    let accelerator = accelerator.map(|accelerator| accelerator.trim().to_string());
+
+    let saved = {
+        let state = app.state::<AcceleratorState>();
+        let mut overrides = state.0.lock().map_err(|e| e.to_string())?;
+        let mut next = overrides.clone();
+        match &accelerator {
+            Some(accelerator) => next.insert(id.clone(), accelerator.clone()),
+            None => next.remove(&id),
+        };
+
+        let resolved = resolve(&next, &id);
+        if let Some(ref shortcut) = resolved {
+            let taken = DEFAULTS
+                .iter()
+                .find(|(other, _)| *other != id && resolve(&next, other).is_some_and(|other| other.eq_ignore_ascii_case(shortcut)));
+            if let Some((other, _)) = taken {
+                return Err(format!("{} is already used by {}", shortcut, other));
+            }
+        }
+        apply(&app, &id, resolved.as_deref())?;
+
+        *overrides = next.clone();
+        next
+    };
+    settings::update(&app, |settings| settings.shortcuts.menu = saved).map(|_| ())
+}
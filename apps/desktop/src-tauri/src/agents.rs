@@ -0,0 +1,268 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::{frontmatter, fs_util, paths, trash};
+
+#[derive(Clone, serde::Serialize)]
+pub struct AgentDefinition {
+    pub name: String,
+    // "user" or "project"
+    pub scope: String,
+    pub path: String,
+    pub description: Option<String>,
+    // Empty means the agent inherits every tool from the main thread
+    pub tools: Vec<String>,
+    pub model: Option<String>,
+    pub color: Option<String>,
+    pub frontmatter: Map<String, Value>,
+    // The agent's system prompt
+    pub body: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct AgentLint {
+    pub valid: bool,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+    // Entries of `tools` that aren't a built-in or MCP tool
+    pub unknown_tools: Vec<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct AgentsChanged {
+    scope: String,
+}
+
+// Tools built into the Claude Code CLI. MCP tools (`mcp__server__tool`)
+// are accepted without being listed here.
+const BUILTIN_TOOLS: &[&str] = &[
+    "Bash",
+    "BashOutput",
+    "Edit",
+    "ExitPlanMode",
+    "Glob",
+    "Grep",
+    "KillShell",
+    "LS",
+    "MultiEdit",
+    "NotebookEdit",
+    "NotebookRead",
+    "Read",
+    "SlashCommand",
+    "Task",
+    "TodoWrite",
+    "WebFetch",
+    "WebSearch",
+    "Write",
+];
+
+const AGENT_MODELS: &[&str] = &["inherit", "sonnet", "opus", "haiku"];
+
+const AGENT_COLORS: &[&str] = &["red", "blue", "green", "yellow", "purple", "orange", "pink", "cyan"];
+
+fn agents_dir(scope: &str, project_path: Option<&str>) -> Result<PathBuf, String> {
+    match scope {
+        "user" => Ok(paths::claude_dir()?.join("agents")),
+        "project" => project_path
+            .filter(|path| !path.is_empty())
+            .map(|path| Path::new(path).join(".claude").join("agents"))
+            .ok_or_else(|| "A project path is required for project agents".to_string()),
+        other => Err(format!("Unknown agent scope: {}", other)),
+    }
+}
+
+// Agent names are lowercase letters, digits and hyphens, like `code-reviewer`
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+fn agent_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    if !is_valid_name(name) {
+        return Err(format!(
+            "Invalid agent name \"{}\": use lowercase letters, numbers and hyphens",
+            name
+        ));
+    }
+    Ok(dir.join(format!("{}.md", name)))
+}
+
+fn string_field(frontmatter: &Map<String, Value>, key: &str) -> Option<String> {
+    frontmatter
+        .get(key)
+        .and_then(Value::as_str)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+fn read_agent_file(path: &Path, scope: &str) -> Result<AgentDefinition, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let document = frontmatter::parse(&text);
+    let file_stem = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+
+    Ok(AgentDefinition {
+        name: string_field(&document.frontmatter, "name").unwrap_or(file_stem),
+        scope: scope.to_string(),
+        path: path.to_string_lossy().to_string(),
+        description: string_field(&document.frontmatter, "description"),
+        tools: frontmatter::list_field(document.frontmatter.get("tools")),
+        model: string_field(&document.frontmatter, "model"),
+        color: string_field(&document.frontmatter, "color"),
+        frontmatter: document.frontmatter,
+        body: document.body,
+    })
+}
+
+fn collect_agents(dir: &Path, scope: &str, agents: &mut Vec<AgentDefinition>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "md") {
+            if let Ok(agent) = read_agent_file(&path, scope) {
+                agents.push(agent);
+            }
+        }
+    }
+}
+
+// Project agents come first; on a name clash they shadow the user's
+#[tauri::command]
+pub fn list_agents(project_path: Option<String>) -> Result<Vec<AgentDefinition>, String> {
+    let mut agents = Vec::new();
+    if project_path.as_deref().is_some_and(|path| !path.is_empty()) {
+        collect_agents(&agents_dir("project", project_path.as_deref())?, "project", &mut agents);
+    }
+    collect_agents(&agents_dir("user", None)?, "user", &mut agents);
+    agents.sort_by(|a, b| (a.scope != "project", &a.name).cmp(&(b.scope != "project", &b.name)));
+    Ok(agents)
+}
+
+#[tauri::command]
+pub fn read_agent(scope: String, project_path: Option<String>, name: String) -> Result<AgentDefinition, String> {
+    let path = agent_path(&agents_dir(&scope, project_path.as_deref())?, &name)?;
+    if !path.exists() {
+        return Err(format!("Agent {} not found", name));
+    }
+    read_agent_file(&path, &scope)
+}
+
+// `Bash(git:*)`-style rules are written for permissions, not agents; only
+// the tool name before the parenthesis is checked
+fn tool_name(tool: &str) -> &str {
+    tool.split('(').next().unwrap_or(tool).trim()
+}
+
+fn lint(frontmatter: &Map<String, Value>, body: &str) -> AgentLint {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    match string_field(frontmatter, "name") {
+        None => errors.push("name is required".to_string()),
+        Some(name) if !is_valid_name(&name) => {
+            errors.push(format!("name \"{}\" must be lowercase letters, numbers and hyphens", name))
+        }
+        Some(_) => {}
+    }
+    if string_field(frontmatter, "description").is_none() {
+        errors.push("description is required; Claude uses it to decide when to delegate".to_string());
+    }
+    if body.trim().is_empty() {
+        errors.push("The agent has no system prompt".to_string());
+    }
+
+    if let Some(model) = string_field(frontmatter, "model") {
+        if !AGENT_MODELS.contains(&model.as_str()) {
+            warnings.push(format!("Unknown model \"{}\"; expected one of {}", model, AGENT_MODELS.join(", ")));
+        }
+    }
+    if let Some(color) = string_field(frontmatter, "color") {
+        if !AGENT_COLORS.contains(&color.as_str()) {
+            warnings.push(format!("Unknown color \"{}\"", color));
+        }
+    }
+
+    let tools = frontmatter::list_field(frontmatter.get("tools"));
+    let unknown_tools: Vec<String> = tools
+        .iter()
+        .filter(|tool| {
+            let name = tool_name(tool);
+            !BUILTIN_TOOLS.contains(&name) && !name.starts_with("mcp__")
+        })
+        .cloned()
+        .collect();
+    for tool in &unknown_tools {
+        let name = tool_name(tool);
+        match BUILTIN_TOOLS.iter().find(|known| known.eq_ignore_ascii_case(name)) {
+            Some(known) => warnings.push(format!("Unknown tool \"{}\"; did you mean {}?", tool, known)),
+            None => warnings.push(format!("Unknown tool \"{}\"", tool)),
+        }
+    }
+    if frontmatter.contains_key("tools") && tools.is_empty() {
+        warnings.push("tools is empty; omit it to inherit every tool".to_string());
+    }
+
+    for key in frontmatter.keys() {
+        if !["name", "description", "tools", "model", "color"].contains(&key.as_str()) {
+            warnings.push(format!("Unknown frontmatter field: {}", key));
+        }
+    }
+
+    AgentLint {
+        valid: errors.is_empty(),
+        errors,
+        warnings,
+        unknown_tools,
+    }
+}
+
+#[tauri::command]
+pub fn lint_agent(frontmatter: Map<String, Value>, body: String) -> AgentLint {
+    lint(&frontmatter, &body)
+}
+
+// Create or overwrite an agent; the file is named after the `name` field.
+// Lint warnings don't block saving, errors do.
+#[tauri::command]
+pub fn save_agent(
+    app: AppHandle,
+    scope: String,
+    project_path: Option<String>,
+    frontmatter: Map<String, Value>,
+    body: String,
+    overwrite: Option<bool>,
+) -> Result<AgentDefinition, String> {
+    let result = lint(&frontmatter, &body);
+    if !result.valid {
+        return Err(result.errors.join("; "));
+    }
+
+    let name = string_field(&frontmatter, "name").unwrap_or_default();
+    let path = agent_path(&agents_dir(&scope, project_path.as_deref())?, &name)?;
+    if path.exists() && !overwrite.unwrap_or(false) {
+        return Err(format!("An agent named {} already exists", name));
+    }
+
+    let text = frontmatter::render(&frontmatter, &body);
+    fs_util::write_atomic(&path, text.as_bytes(), false)?;
+    let _ = app.emit("claude-agents-changed", AgentsChanged { scope: scope.clone() });
+    read_agent_file(&path, &scope)
+}
+
+#[tauri::command]
+pub fn delete_agent(
+    app: AppHandle,
+    scope: String,
+    project_path: Option<String>,
+    name: String,
+) -> Result<(), String> {
+    let path = agent_path(&agents_dir(&scope, project_path.as_deref())?, &name)?;
+    if !path.exists() {
+        return Err(format!("Agent {} not found", name));
+    }
+    trash::move_to_trash(&path)?;
+    let _ = app.emit("claude-agents-changed", AgentsChanged { scope });
+    Ok(())
+}
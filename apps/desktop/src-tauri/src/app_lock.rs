@@ -0,0 +1,225 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::{audit, i18n, settings};
+
+const DEFAULT_IDLE_MINUTES: u32 = 5;
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct LockSettings {
+    pub enabled: bool,
+    // Without activity for this long the app locks; 0 locks it only on
+    // `lock_app`
+    pub idle_minutes: u32,
+}
+
+impl Default for LockSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_minutes: DEFAULT_IDLE_MINUTES,
+        }
+    }
+}
+
+struct LockState {
+    locked: bool,
+    // An OS prompt is showing, so a second one isn't stacked on it
+    unlocking: bool,
+    last_active: Instant,
+}
+
+pub struct AppLock(Mutex<LockState>);
+
+impl Default for AppLock {
+    fn default() -> Self {
+        Self(Mutex::new(LockState {
+            locked: false,
+            unlocking: false,
+            last_active: Instant::now(),
+        }))
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+struct LockStateChanged {
+    locked: bool,
+}
+
+pub fn is_locked(app: &AppHandle) -> bool {
+    app.state::<AppLock>().0.lock().map(|state| state.locked).unwrap_or(true)
+}
+
+pub fn ensure_unlocked(app: &AppHandle) -> Result<(), String> {
+    if is_locked(app) {
+        return Err("The app is locked".to_string());
+    }
+    Ok(())
+}
+
+// Windows hide their content behind the lock screen on `lock-state-changed`
+fn set_locked(app: &AppHandle, locked: bool, origin: &str) -> Result<(), String> {
+    {
+        let lock = app.state::<AppLock>();
+        let mut state = lock.0.lock().map_err(|e| e.to_string())?;
+        if state.locked == locked {
+            return Ok(());
+        }
+        state.locked = locked;
+        state.last_active = Instant::now();
+    }
+    audit::record(app, Some(origin), "lock", if locked { "lock" } else { "unlock" }, "app");
+    let _ = app.emit("lock-state-changed", LockStateChanged { locked });
+    Ok(())
+}
+
+// With the lock on, the app starts locked and locks again whenever it's
+// left idle for the configured time
+pub fn start(app: &AppHandle) {
+    if settings::get(app).lock.enabled {
+        let _ = set_locked(app, true, "startup");
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(IDLE_CHECK_INTERVAL).await;
+            let lock = settings::get(&app).lock;
+            if !lock.enabled || lock.idle_minutes == 0 {
+                continue;
+            }
+            let idle = match app.state::<AppLock>().0.lock() {
+                Ok(state) => state.last_active.elapsed(),
+                Err(_) => continue,
+            };
+            if idle >= Duration::from_secs(u64::from(lock.idle_minutes) * 60) {
+                let _ = set_locked(&app, true, "idle");
+            }
+        }
+    });
+}
+
+// Touch ID or the account password, through LocalAuthentication
+#[cfg(target_os = "macos")]
+fn authenticate(reason: &str, _window: &WebviewWindow) -> Result<bool, String> {
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSError, NSString};
+    use objc2_local_authentication::{LAContext, LAPolicy};
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    // Called once, on a queue of the framework's, with the outcome
+    let reply = RcBlock::new(move |success: Bool, _error: *mut NSError| {
+        let _ = tx.send(success.as_bool());
+    });
+    unsafe {
+        let context = LAContext::new();
+        context.evaluatePolicy_localizedReason_reply(
+            LAPolicy::DeviceOwnerAuthentication,
+            &NSString::from_str(reason),
+            &reply,
+        );
+    }
+    rx.recv().map_err(|e| format!("Authentication failed: {}", e))
+}
+
+// Windows Hello: face, fingerprint or PIN. The prompt is parented to the
+// window so it doesn't open behind it.
+#[cfg(windows)]
+fn authenticate(reason: &str, window: &WebviewWindow) -> Result<bool, String> {
+    use windows::core::{factory, HSTRING};
+    use windows::Security::Credentials::UI::{
+        UserConsentVerificationResult, UserConsentVerifier, UserConsentVerifierAvailability,
+    };
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::WinRT::IUserConsentVerifierInterop;
+
+    let availability = UserConsentVerifier::CheckAvailabilityAsync()
+        .and_then(|operation| operation.join())
+        .map_err(|e| format!("Authentication failed: {}", e))?;
+    if availability != UserConsentVerifierAvailability::Available {
+        return Err("Windows Hello isn't set up on this device".to_string());
+    }
+    let reason = HSTRING::from(reason);
+    let operation = match window.hwnd() {
+        Ok(hwnd) => factory::<UserConsentVerifier, IUserConsentVerifierInterop>()
+            .and_then(|interop| unsafe { interop.RequestVerificationForWindowAsync(HWND(hwnd.0), &reason) }),
+        Err(_) => UserConsentVerifier::RequestVerificationAsync(&reason),
+    };
+    let result = operation
+        .and_then(|operation| operation.join())
+        .map_err(|e| format!("Authentication failed: {}", e))?;
+    Ok(result == UserConsentVerificationResult::Verified)
+}
+
+// polkit, which asks for the password of the user or an administrator
+#[cfg(not(any(target_os = "macos", windows)))]
+fn authenticate(_reason: &str, _window: &WebviewWindow) -> Result<bool, String> {
+    let status = std::process::Command::new("pkcheck")
+        .args(["--action-id", "org.freedesktop.policykit.exec", "--allow-user-interaction", "--process"])
+        .arg(std::process::id().to_string())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| format!("No system authentication is available: {}", e))?;
+    Ok(status.success())
+}
+
+#[tauri::command]
+pub fn get_lock_state(app: AppHandle) -> bool {
+    is_locked(&app)
+}
+
+// The frontend reports input now and then, which puts off the idle lock
+#[tauri::command]
+pub fn report_app_activity(app: AppHandle) -> Result<(), String> {
+    let lock = app.state::<AppLock>();
+    let mut state = lock.0.lock().map_err(|e| e.to_string())?;
+    if !state.locked {
+        state.last_active = Instant::now();
+    }
+    Ok(())
+}
+
+// Locks whether or not the lock is turned on in settings
+#[tauri::command]
+pub fn lock_app(app: AppHandle, window: WebviewWindow) -> Result<(), String> {
+    set_locked(&app, true, window.label())
+}
+
+// Ask the OS to confirm it's the user: Touch ID or the password on macOS,
+// Windows Hello, polkit on Linux. Returns whether the app is now unlocked.
+#[tauri::command]
+pub async fn unlock_app(app: AppHandle, window: WebviewWindow) -> Result<bool, String> {
+    {
+        let lock = app.state::<AppLock>();
+        let mut state = lock.0.lock().map_err(|e| e.to_string())?;
+        if !state.locked {
+            return Ok(true);
+        }
+        if state.unlocking {
+            return Err("Already waiting for authentication".to_string());
+        }
+        state.unlocking = true;
+    }
+    let reason = i18n::t(&app, "dialogs.unlockReason");
+    let prompt_window = window.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || authenticate(&reason, &prompt_window))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+    if let Ok(mut state) = app.state::<AppLock>().0.lock() {
+        state.unlocking = false;
+    }
+
+    let verified = result?;
+    if verified {
+        set_locked(&app, false, window.label())?;
+    } else {
+        audit::record(&app, Some(window.label()), "lock", "unlock-failed", "app");
+    }
+    Ok(verified)
+}
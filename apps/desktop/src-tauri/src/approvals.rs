@@ -0,0 +1,404 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tokio::sync::oneshot;
+
+use crate::{audit, db, i18n, policy};
+
+// Unanswered requests are denied after this
+const APPROVAL_TIMEOUT: Duration = Duration::from_secs(120);
+const DEFAULT_HISTORY_LIMIT: i64 = 100;
+
+// A shell command, or a file write outside the project
+pub const APPROVAL_KINDS: &[&str] = &["shell", "write"];
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct ApprovalRequest {
+    // Set here when the asker doesn't give one
+    #[serde(default)]
+    pub id: String,
+    pub project_path: String,
+    // One of APPROVAL_KINDS
+    pub kind: String,
+    // e.g. "Bash" or "Write"
+    pub tool: String,
+    // The command line, or the path written to
+    pub subject: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ApprovalRequested {
+    #[serde(flatten)]
+    request: ApprovalRequest,
+    timeout_secs: u64,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ApprovalResolved {
+    id: String,
+    allowed: bool,
+}
+
+enum Decision {
+    Allow,
+    Deny,
+    // Allow, and from now on anything `pattern` matches in the project
+    AlwaysAllow { pattern: String },
+}
+
+// Requests waiting on an answer, by id
+#[derive(Default)]
+pub struct PendingApprovals(Mutex<HashMap<String, (ApprovalRequest, oneshot::Sender<Decision>)>>);
+
+#[derive(Clone, serde::Serialize)]
+pub struct ApprovalRule {
+    pub id: String,
+    pub project_path: String,
+    pub kind: String,
+    // A command prefix, or a folder
+    pub pattern: String,
+    pub created_at: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ApprovalDecision {
+    pub id: String,
+    pub project_path: String,
+    pub kind: String,
+    pub tool: String,
+    pub subject: String,
+    pub allowed: bool,
+    // "user", "rule" or "timeout"
+    pub decided_by: String,
+    pub decided_at: String,
+}
+
+type RuleRow = (String, String, String, String, String);
+type DecisionRow = (String, String, String, String, String, bool, String, String);
+
+fn rule_from_row((id, project_path, kind, pattern, created_at): RuleRow) -> ApprovalRule {
+    ApprovalRule {
+        id,
+        project_path,
+        kind,
+        pattern,
+        created_at,
+    }
+}
+
+fn decision_from_row(
+    (id, project_path, kind, tool, subject, allowed, decided_by, decided_at): DecisionRow,
+) -> ApprovalDecision {
+    ApprovalDecision {
+        id,
+        project_path,
+        kind,
+        tool,
+        subject,
+        allowed,
+        decided_by,
+        decided_at,
+    }
+}
+
+// Anything that could chain, substitute or redirect: `npm test && curl ..`
+// starts with `npm test` too. Such commands are always asked about.
+const SHELL_OPERATORS: &[&str] = &[";", "&", "|", "`", "$(", "\n", "\r", ">", "<"];
+
+fn has_shell_operators(command: &str) -> bool {
+    SHELL_OPERATORS.iter().any(|operator| command.contains(operator))
+}
+
+// A shell rule allows the command itself and the command with more
+// arguments; a write rule allows anything under its folder, compared where
+// both really are so a symlink can't lead out of it
+fn matches(kind: &str, pattern: &str, subject: &str) -> bool {
+    match kind {
+        "shell" => {
+            let subject = subject.trim();
+            if has_shell_operators(subject) || has_shell_operators(pattern) {
+                return false;
+            }
+            subject == pattern || subject.strip_prefix(pattern).is_some_and(|rest| rest.starts_with(' '))
+        }
+        _ => match (policy::resolve(Path::new(subject)), policy::resolve(Path::new(pattern))) {
+            (Some(subject), Some(pattern)) => subject.starts_with(pattern),
+            _ => false,
+        },
+    }
+}
+
+// What "always allow" covers when the user doesn't narrow it: the whole
+// command, or the folder the file is in
+fn default_pattern(request: &ApprovalRequest) -> String {
+    match request.kind.as_str() {
+        "shell" => request.subject.trim().to_string(),
+        _ => Path::new(&request.subject)
+            .parent()
+            .map(|parent| parent.to_string_lossy().to_string())
+            .unwrap_or_else(|| request.subject.clone()),
+    }
+}
+
+async fn matching_rule(pool: &SqlitePool, request: &ApprovalRequest) -> Result<bool, String> {
+    let patterns: Vec<(String,)> =
+        sqlx::query_as("SELECT pattern FROM approval_rules WHERE project_path = ? AND kind = ?")
+            .bind(&request.project_path)
+            .bind(&request.kind)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Failed to read approval rules: {}", e))?;
+    Ok(patterns
+        .iter()
+        .any(|(pattern,)| matches(&request.kind, pattern, &request.subject)))
+}
+
+async fn add_rule(pool: &SqlitePool, request: &ApprovalRequest, pattern: &str) -> Result<(), String> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO approval_rules (id, project_path, kind, pattern, created_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(&request.project_path)
+    .bind(&request.kind)
+    .bind(pattern)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to save approval rule: {}", e))?;
+    Ok(())
+}
+
+async fn record(pool: &SqlitePool, request: &ApprovalRequest, allowed: bool, decided_by: &str) -> Result<(), String> {
+    sqlx::query(
+        "INSERT OR REPLACE INTO approval_decisions
+            (id, project_path, kind, tool, subject, allowed, decided_by, decided_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&request.id)
+    .bind(&request.project_path)
+    .bind(&request.kind)
+    .bind(&request.tool)
+    .bind(&request.subject)
+    .bind(allowed)
+    .bind(decided_by)
+    .bind(chrono::Utc::now().to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to record approval: {}", e))?;
+    Ok(())
+}
+
+fn resolve(app: &AppHandle, id: &str, decision: Decision) -> bool {
+    let pending = app
+        .state::<PendingApprovals>()
+        .0
+        .lock()
+        .ok()
+        .and_then(|mut pending| pending.remove(id));
+    match pending {
+        Some((_, sender)) => sender.send(decision).is_ok(),
+        None => false,
+    }
+}
+
+// With every window hidden to the tray no one would see the event, so the
+// question goes in a native dialog too. Whichever answer comes first wins.
+fn ask_natively(app: &AppHandle, request: &ApprovalRequest) {
+    let project = Path::new(&request.project_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| request.project_path.clone());
+    let key = match request.kind.as_str() {
+        "shell" => "dialogs.approvalShell",
+        _ => "dialogs.approvalWrite",
+    };
+    let handle = app.clone();
+    let id = request.id.clone();
+    app.dialog()
+        .message(i18n::t_with(app, key, &[("project", &project), ("subject", &request.subject)]))
+        .title(i18n::t(app, "dialogs.approvalTitle"))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            i18n::t(app, "dialogs.allow"),
+            i18n::t(app, "dialogs.deny"),
+        ))
+        .show(move |allowed| {
+            resolve(&handle, &id, if allowed { Decision::Allow } else { Decision::Deny });
+        });
+}
+
+// What the sidecar goes on to do once allowed: run the command or write
+// the file
+fn audit_outcome(app: &AppHandle, request: &ApprovalRequest, allowed: bool) {
+    let (category, action) = match (request.kind.as_str(), allowed) {
+        ("shell", true) => ("command", "run"),
+        ("shell", false) => ("command", "denied"),
+        (_, true) => ("file", "write"),
+        (_, false) => ("file", "denied"),
+    };
+    audit::record(app, Some("sidecar"), category, action, &request.subject);
+}
+
+// Ask whether a tool may go ahead: allowed outright by a saved rule for the
+// project, or else put to the user as an "approval-requested" event (and a
+// native dialog when no window is showing), denied if no answer comes in
+// time. Every outcome is recorded.
+pub async fn request(app: &AppHandle, mut request: ApprovalRequest) -> Result<bool, String> {
+    if !APPROVAL_KINDS.contains(&request.kind.as_str()) {
+        return Err(format!("Unknown approval kind: {}", request.kind));
+    }
+    if request.id.is_empty() {
+        request.id = uuid::Uuid::new_v4().to_string();
+    }
+    let pool = db::pool(app).await?;
+    // Rules belong to a project; without one every request is asked about
+    let in_project = !request.project_path.is_empty();
+    if in_project && matching_rule(&pool, &request).await? {
+        record(&pool, &request, true, "rule").await?;
+        audit_outcome(app, &request, true);
+        return Ok(true);
+    }
+
+    let (sender, receiver) = oneshot::channel();
+    app.state::<PendingApprovals>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(request.id.clone(), (request.clone(), sender));
+    let _ = app.emit(
+        "approval-requested",
+        ApprovalRequested {
+            request: request.clone(),
+            timeout_secs: APPROVAL_TIMEOUT.as_secs(),
+        },
+    );
+    let visible = app
+        .webview_windows()
+        .values()
+        .any(|window| window.is_visible().unwrap_or(false));
+    if !visible {
+        ask_natively(app, &request);
+    }
+
+    let (allowed, decided_by) = match tokio::time::timeout(APPROVAL_TIMEOUT, receiver).await {
+        Ok(Ok(Decision::Allow)) => (true, "user"),
+        Ok(Ok(Decision::AlwaysAllow { pattern })) => {
+            // A rule that could never match is only allowed this once
+            let usable = request.kind != "shell" || !has_shell_operators(&pattern);
+            if in_project && usable {
+                add_rule(&pool, &request, &pattern).await?;
+            }
+            (true, "user")
+        }
+        Ok(Ok(Decision::Deny)) | Ok(Err(_)) => (false, "user"),
+        Err(_) => {
+            if let Ok(mut pending) = app.state::<PendingApprovals>().0.lock() {
+                pending.remove(&request.id);
+            }
+            (false, "timeout")
+        }
+    };
+    record(&pool, &request, allowed, decided_by).await?;
+    audit_outcome(app, &request, allowed);
+    let _ = app.emit(
+        "approval-resolved",
+        ApprovalResolved {
+            id: request.id.clone(),
+            allowed,
+        },
+    );
+    Ok(allowed)
+}
+
+// The user's answer to an "approval-requested" event: "allow", "deny" or
+// "always". `pattern` narrows or widens what "always" saves.
+#[tauri::command]
+pub fn respond_approval(
+    app: AppHandle,
+    id: String,
+    decision: String,
+    pattern: Option<String>,
+) -> Result<(), String> {
+    let request = app
+        .state::<PendingApprovals>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&id)
+        .map(|(request, _)| request.clone())
+        .ok_or_else(|| format!("Approval request not found: {}", id))?;
+    let decision = match decision.as_str() {
+        "allow" => Decision::Allow,
+        "deny" => Decision::Deny,
+        "always" => Decision::AlwaysAllow {
+            pattern: pattern
+                .map(|pattern| pattern.trim().to_string())
+                .filter(|pattern| !pattern.is_empty())
+                .unwrap_or_else(|| default_pattern(&request)),
+        },
+        _ => return Err(format!("Unknown approval decision: {}", decision)),
+    };
+    resolve(&app, &id, decision);
+    Ok(())
+}
+
+// Requests still waiting, for a window that opened or reloaded after the
+// event went out
+#[tauri::command]
+pub fn list_pending_approvals(app: AppHandle) -> Result<Vec<ApprovalRequest>, String> {
+    let state = app.state::<PendingApprovals>();
+    let pending = state.0.lock().map_err(|e| e.to_string())?;
+    Ok(pending.values().map(|(request, _)| request.clone()).collect())
+}
+
+#[tauri::command]
+pub async fn list_approval_rules(app: AppHandle, project_path: String) -> Result<Vec<ApprovalRule>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<RuleRow> = sqlx::query_as(
+        "SELECT id, project_path, kind, pattern, created_at FROM approval_rules
+         WHERE project_path = ? ORDER BY created_at",
+    )
+    .bind(&project_path)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to list approval rules: {}", e))?;
+    Ok(rows.into_iter().map(rule_from_row).collect())
+}
+
+#[tauri::command]
+pub async fn remove_approval_rule(app: AppHandle, id: String) -> Result<(), String> {
+    let pool = db::pool(&app).await?;
+    sqlx::query("DELETE FROM approval_rules WHERE id = ?")
+        .bind(&id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to remove approval rule: {}", e))?;
+    Ok(())
+}
+
+// What was allowed or denied in a project, newest first
+#[tauri::command]
+pub async fn list_approval_decisions(
+    app: AppHandle,
+    project_path: String,
+    limit: Option<i64>,
+) -> Result<Vec<ApprovalDecision>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<DecisionRow> = sqlx::query_as(
+        "SELECT id, project_path, kind, tool, subject, allowed, decided_by, decided_at FROM approval_decisions
+         WHERE project_path = ? ORDER BY decided_at DESC LIMIT ?",
+    )
+    .bind(&project_path)
+    .bind(limit.unwrap_or(DEFAULT_HISTORY_LIMIT))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to list approval decisions: {}", e))?;
+    Ok(rows.into_iter().map(decision_from_row).collect())
+}
@@ -0,0 +1,211 @@
+use std::path::PathBuf;
+
+use sqlx::{QueryBuilder, Sqlite};
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+use tokio::sync::oneshot;
+
+use crate::{db, fs_util, i18n, redact};
+
+const DEFAULT_LIMIT: i64 = 200;
+const MAX_LIMIT: i64 = 10_000;
+
+#[derive(Clone, serde::Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub occurred_at: String,
+    // "sidecar", "command", "terminal", "file", "scope", "secret" or
+    // "approval"
+    pub category: String,
+    // e.g. "start", "run", "write", "grant", "read"
+    pub action: String,
+    // What it was done to: a command line, a path, a secret's name
+    pub subject: String,
+    // The window label it came from, or what else started it, e.g. "tray"
+    pub origin: Option<String>,
+}
+
+#[derive(Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct AuditFilter {
+    pub category: Option<String>,
+    pub action: Option<String>,
+    pub origin: Option<String>,
+    // RFC 3339 times, inclusive
+    pub since: Option<String>,
+    pub until: Option<String>,
+    // Matched anywhere in the subject
+    pub search: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+type AuditRow = (i64, String, String, String, String, Option<String>);
+
+fn from_row((id, occurred_at, category, action, subject, origin): AuditRow) -> AuditEntry {
+    AuditEntry {
+        id,
+        occurred_at,
+        category,
+        action,
+        subject,
+        origin,
+    }
+}
+
+// Note a privileged operation. Written in the background, so callers don't
+// wait on the database or fail with it, and with any secret in the subject
+// redacted.
+pub fn record(app: &AppHandle, origin: Option<&str>, category: &str, action: &str, subject: &str) {
+    let app = app.clone();
+    let occurred_at = chrono::Utc::now().to_rfc3339();
+    let (origin, category, action, subject) =
+        (origin.map(str::to_string), category.to_string(), action.to_string(), subject.to_string());
+    tauri::async_runtime::spawn(async move {
+        let subject = redact::redact(&subject);
+        let result = async {
+            let pool = db::pool(&app).await?;
+            sqlx::query(
+                "INSERT INTO audit_log (occurred_at, category, action, subject, origin) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(&occurred_at)
+            .bind(&category)
+            .bind(&action)
+            .bind(&subject)
+            .bind(&origin)
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("Failed to write audit log: {}", e))
+        }
+        .await;
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+    });
+}
+
+async fn query(app: &AppHandle, filter: &AuditFilter) -> Result<Vec<AuditEntry>, String> {
+    let pool = db::pool(app).await?;
+    let mut builder: QueryBuilder<Sqlite> =
+        QueryBuilder::new("SELECT id, occurred_at, category, action, subject, origin FROM audit_log WHERE 1 = 1");
+    if let Some(category) = &filter.category {
+        builder.push(" AND category = ").push_bind(category);
+    }
+    if let Some(action) = &filter.action {
+        builder.push(" AND action = ").push_bind(action);
+    }
+    if let Some(origin) = &filter.origin {
+        builder.push(" AND origin = ").push_bind(origin);
+    }
+    if let Some(since) = &filter.since {
+        builder.push(" AND occurred_at >= ").push_bind(since);
+    }
+    if let Some(until) = &filter.until {
+        builder.push(" AND occurred_at <= ").push_bind(until);
+    }
+    if let Some(search) = filter.search.as_ref().filter(|search| !search.is_empty()) {
+        builder.push(" AND instr(subject, ").push_bind(search).push(") > 0");
+    }
+    builder
+        .push(" ORDER BY id DESC LIMIT ")
+        .push_bind(filter.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT))
+        .push(" OFFSET ")
+        .push_bind(filter.offset.unwrap_or(0).max(0));
+    let rows: Vec<AuditRow> = builder
+        .build_query_as()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to read audit log: {}", e))?;
+    Ok(rows.into_iter().map(from_row).collect())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_csv(entries: &[AuditEntry]) -> String {
+    let mut csv = String::from("id,occurred_at,category,action,subject,origin\n");
+    for entry in entries {
+        let fields = [
+            entry.id.to_string(),
+            entry.occurred_at.clone(),
+            entry.category.clone(),
+            entry.action.clone(),
+            entry.subject.clone(),
+            entry.origin.clone().unwrap_or_default(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+async fn pick_destination(app: &AppHandle, extension: &str) -> Option<PathBuf> {
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title(i18n::t(app, "dialogs.exportAuditLog"))
+        .set_file_name(format!("incito-audit-log.{}", extension))
+        .add_filter(extension.to_uppercase(), &[extension])
+        .save_file(move |path| {
+            let _ = tx.send(path.and_then(|path| path.into_path().ok()));
+        });
+    rx.await.ok().flatten()
+}
+
+// Newest first
+#[tauri::command]
+pub async fn query_audit_log(app: AppHandle, filter: Option<AuditFilter>) -> Result<Vec<AuditEntry>, String> {
+    query(&app, &filter.unwrap_or_default()).await
+}
+
+// Write the entries `filter` matches (all of them without a limit) as
+// "json" or "csv" wherever the user picks in a save dialog, never a path
+// the caller chose. Returns the path written, or None if the dialog was
+// cancelled.
+#[tauri::command]
+pub async fn export_audit_log(
+    app: AppHandle,
+    filter: Option<AuditFilter>,
+    format: String,
+) -> Result<Option<String>, String> {
+    if format != "json" && format != "csv" {
+        return Err(format!("Unknown export format: {}", format));
+    }
+    let mut filter = filter.unwrap_or_default();
+    let mut entries = Vec::new();
+    // In pages, so an unbounded export isn't limited to the last page's size
+    if filter.limit.is_none() {
+        filter.offset = Some(0);
+        filter.limit = Some(MAX_LIMIT);
+        loop {
+            let page = query(&app, &filter).await?;
+            let done = (page.len() as i64) < MAX_LIMIT;
+            entries.extend(page);
+            if done {
+                break;
+            }
+            filter.offset = Some(entries.len() as i64);
+        }
+    } else {
+        entries = query(&app, &filter).await?;
+    }
+
+    let Some(dest) = pick_destination(&app, &format).await else {
+        return Ok(None);
+    };
+    // Again, for secrets saved since an entry was written
+    let contents = match format.as_str() {
+        "csv" => render_csv(&entries),
+        _ => serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?,
+    };
+    let contents = redact::redact(&contents);
+    fs_util::write_atomic(&dest, contents.as_bytes(), false)?;
+    record(&app, None, "file", "export", &dest.to_string_lossy());
+    Ok(Some(dest.to_string_lossy().to_string()))
+}
@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::projects::Project;
+use crate::{app_lock, audit, projects, redact, secrets, server, settings, windows};
+
+pub const DEFAULT_PORT: u16 = 3458;
+// The token lives in the keychain with the other secrets, so it's redacted
+// like them
+const TOKEN_SECRET: &str = "automation:token";
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+// Finished tasks past this many are forgotten, oldest first
+const MAX_TASKS: usize = 100;
+const TASK_STATUSES: &[&str] = &["queued", "running", "completed", "failed", "cancelled"];
+
+// The local automation API, for scripts, launcher extensions and editors.
+// Off unless turned on; it only ever listens on loopback.
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct AutomationSettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for AutomationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct AutomationStatus {
+    pub enabled: bool,
+    pub port: u16,
+    // Where it's listening, e.g. "http://127.0.0.1:3458"
+    pub url: Option<String>,
+    // Why it isn't, when it should be
+    pub error: Option<String>,
+}
+
+// A session asked for through the API. The frontend creates it and reports
+// back with `update_automation_task`.
+#[derive(Clone, serde::Serialize)]
+pub struct AutomationTask {
+    pub id: String,
+    // One of TASK_STATUSES
+    pub status: String,
+    pub project_path: String,
+    #[serde(skip)]
+    pub prompt: String,
+    pub session_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+// What the frontend gets as `automation-create-session`, and from
+// `list_queued_automation_tasks`
+#[derive(Clone, serde::Serialize)]
+pub struct SessionRequest {
+    pub task_id: String,
+    pub project: Project,
+    pub prompt: String,
+}
+
+#[derive(Default)]
+struct Listener {
+    server: Option<Arc<Server>>,
+    url: Option<String>,
+    error: Option<String>,
+    token: Option<String>,
+}
+
+#[derive(Default)]
+pub struct AutomationServer(Mutex<Listener>);
+
+#[derive(Default)]
+pub struct AutomationTasks(Mutex<HashMap<String, (AutomationTask, Option<Project>)>>);
+
+#[derive(serde::Deserialize)]
+struct CreateSession {
+    project_path: String,
+    prompt: String,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct StopServer {
+    force: bool,
+}
+
+fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Failed to generate a token: {}", e))?;
+    Ok(hex::encode(bytes))
+}
+
+// The saved token, made the first time it's needed
+fn load_token() -> Result<String, String> {
+    match secrets::get(TOKEN_SECRET)? {
+        Some(token) => Ok(token),
+        None => {
+            let token = generate_token()?;
+            secrets::set(TOKEN_SECRET, &token)?;
+            Ok(token)
+        }
+    }
+}
+
+// Compared in full whatever the input, so timing says nothing about how
+// much of a guess was right
+fn token_matches(expected: &str, given: &str) -> bool {
+    let (expected, given) = (expected.as_bytes(), given.as_bytes());
+    let mut difference = expected.len() ^ given.len();
+    for (index, byte) in expected.iter().enumerate() {
+        difference |= usize::from(byte ^ given.get(index).copied().unwrap_or(0));
+    }
+    difference == 0
+}
+
+fn header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.as_str().as_str().eq_ignore_ascii_case(name))
+        .map(|header| header.value.as_str())
+}
+
+fn json_response(status: u16, body: serde_json::Value) -> Response<std::io::Cursor<Vec<u8>>> {
+    let content_type = Header::from_bytes("Content-Type", "application/json").expect("valid header");
+    Response::from_data(body.to_string().into_bytes())
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+fn error_response(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    json_response(status, serde_json::json!({ "error": message }))
+}
+
+fn read_body<T: serde::de::DeserializeOwned + Default>(request: &mut Request) -> Result<T, String> {
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| format!("Failed to read the request: {}", e))?;
+    if body.len() as u64 > MAX_BODY_BYTES {
+        return Err("The request is too large".to_string());
+    }
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(T::default());
+    }
+    serde_json::from_slice(&body).map_err(|e| format!("Invalid request: {}", e))
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339()
+}
+
+fn add_task(app: &AppHandle, task: AutomationTask, project: Project) -> Result<(), String> {
+    let tasks = app.state::<AutomationTasks>();
+    let mut tasks = tasks.0.lock().map_err(|e| e.to_string())?;
+    tasks.insert(task.id.clone(), (task, Some(project)));
+    while tasks.len() > MAX_TASKS {
+        let oldest = tasks
+            .values()
+            .filter(|(task, _)| task.status != "queued" && task.status != "running")
+            .min_by(|(a, _), (b, _)| a.created_at.cmp(&b.created_at))
+            .map(|(task, _)| task.id.clone());
+        match oldest {
+            Some(id) => tasks.remove(&id),
+            None => break,
+        };
+    }
+    Ok(())
+}
+
+fn create_session(app: &AppHandle, request: CreateSession) -> Result<AutomationTask, (u16, String)> {
+    if request.prompt.trim().is_empty() {
+        return Err((400, "prompt is required".to_string()));
+    }
+    if !Path::new(&request.project_path).is_dir() {
+        return Err((404, format!("Project folder not found: {}", request.project_path)));
+    }
+    // Only projects the user has opened: registering one would let the
+    // webview into a folder, or back into one whose access was revoked
+    let project = tauri::async_runtime::block_on(projects::registered(app, &request.project_path))
+        .map_err(|e| (500, e))?
+        .ok_or_else(|| {
+            (
+                403,
+                format!("{} isn't a project in Incito; open it in the app first", request.project_path),
+            )
+        })?;
+    let created_at = now();
+    let task = AutomationTask {
+        id: uuid::Uuid::new_v4().to_string(),
+        status: "queued".to_string(),
+        project_path: project.path.clone(),
+        prompt: request.prompt,
+        session_id: None,
+        error: None,
+        created_at: created_at.clone(),
+        updated_at: created_at,
+    };
+    add_task(app, task.clone(), project.clone()).map_err(|e| (500, e))?;
+    audit::record(app, Some("automation"), "automation", "create-session", &project.path);
+
+    let _ = windows::show_main_window(app);
+    let _ = app.emit_to(
+        windows::MAIN_WINDOW,
+        "automation-create-session",
+        SessionRequest {
+            task_id: task.id.clone(),
+            project,
+            prompt: task.prompt.clone(),
+        },
+    );
+    Ok(task)
+}
+
+fn task_status(app: &AppHandle, id: &str) -> Option<AutomationTask> {
+    let tasks = app.state::<AutomationTasks>();
+    let tasks = tasks.0.lock().ok()?;
+    tasks.get(id).map(|(task, _)| task.clone())
+}
+
+fn route(app: &AppHandle, request: &mut Request) -> Response<std::io::Cursor<Vec<u8>>> {
+    let path = request.url().split('?').next().unwrap_or_default().to_string();
+    let method = request.method().clone();
+    if path != "/v1/status" && app_lock::is_locked(app) {
+        return error_response(423, "The app is locked");
+    }
+    match (method, path.as_str()) {
+        (Method::Get, "/v1/status") => json_response(
+            200,
+            serde_json::json!({
+                "version": app.package_info().version.to_string(),
+                "locked": app_lock::is_locked(app),
+                "server_running": server::is_running(app),
+            }),
+        ),
+        (Method::Post, "/v1/sessions") => {
+            let body = match read_body::<Option<CreateSession>>(request) {
+                Ok(Some(body)) => body,
+                Ok(None) => return error_response(400, "project_path and prompt are required"),
+                Err(e) => return error_response(400, &e),
+            };
+            match create_session(app, body) {
+                Ok(task) => json_response(202, serde_json::json!(task)),
+                Err((status, e)) => error_response(status, &e),
+            }
+        }
+        (Method::Get, path) if path.starts_with("/v1/tasks/") => match task_status(app, &path["/v1/tasks/".len()..]) {
+            Some(task) => json_response(200, serde_json::json!(task)),
+            None => error_response(404, "No such task"),
+        },
+        (Method::Post, "/v1/server/stop") => {
+            let body = match read_body::<StopServer>(request) {
+                Ok(body) => body,
+                Err(e) => return error_response(400, &e),
+            };
+            match server::stop(app, body.force, "automation") {
+                Ok(()) => json_response(200, serde_json::json!({ "stopped": true })),
+                Err(e) => error_response(500, &e),
+            }
+        }
+        _ => error_response(404, "Not found"),
+    }
+}
+
+// Requests from a browser carry an Origin; a page elsewhere must not reach
+// the API, even on loopback. The Host check stops DNS rebinding.
+fn check_request(request: &Request, port: u16, token: &str) -> Result<(), (u16, &'static str)> {
+    if header(request, "Origin").is_some() {
+        return Err((403, "Browser requests aren't accepted"));
+    }
+    let host = header(request, "Host").unwrap_or_default();
+    if host != format!("127.0.0.1:{}", port) && host != format!("localhost:{}", port) {
+        return Err((403, "Unexpected Host header"));
+    }
+    let given = header(request, "Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    if !token_matches(token, given.trim()) {
+        return Err((401, "Missing or invalid token"));
+    }
+    Ok(())
+}
+
+fn serve(app: AppHandle, server: Arc<Server>, port: u16) {
+    for mut request in server.incoming_requests() {
+        let token = app
+            .state::<AutomationServer>()
+            .0
+            .lock()
+            .ok()
+            .and_then(|listener| listener.token.clone());
+        let response = match token {
+            Some(token) => match check_request(&request, port, &token) {
+                Ok(()) => route(&app, &mut request),
+                Err((status, e)) => error_response(status, e),
+            },
+            None => error_response(503, "Automation is unavailable"),
+        };
+        let _ = request.respond(response);
+    }
+}
+
+fn stop_listening(listener: &mut Listener) {
+    if let Some(server) = listener.server.take() {
+        // Ends `incoming_requests`, and with it the thread
+        server.unblock();
+    }
+    listener.url = None;
+}
+
+// Start or stop listening as the settings say. Called at startup and when
+// they change.
+pub async fn load_saved(app: &AppHandle) -> Result<(), String> {
+    let config = settings::get(app).automation;
+    let token = if config.enabled {
+        Some(
+            tauri::async_runtime::spawn_blocking(load_token)
+                .await
+                .map_err(|e| e.to_string())??,
+        )
+    } else {
+        None
+    };
+    let state = app.state::<AutomationServer>();
+    let mut listener = state.0.lock().map_err(|e| e.to_string())?;
+    stop_listening(&mut listener);
+    listener.error = None;
+    listener.token = token;
+    if !config.enabled {
+        return Ok(());
+    }
+    // Loopback only, never a wildcard address
+    let address = format!("127.0.0.1:{}", config.port);
+    let server = match Server::http(&address) {
+        Ok(server) => Arc::new(server),
+        Err(e) => {
+            let error = format!("Failed to listen on {}: {}", address, e);
+            listener.error = Some(error.clone());
+            return Err(error);
+        }
+    };
+    let (handle, serving, port) = (app.clone(), server.clone(), config.port);
+    std::thread::spawn(move || serve(handle, serving, port));
+    listener.server = Some(server);
+    listener.url = Some(format!("http://{}", address));
+    Ok(())
+}
+
+pub fn validate(config: &AutomationSettings) -> Result<(), String> {
+    if config.port < 1024 {
+        return Err(format!("Port {} is reserved; use 1024 or above", config.port));
+    }
+    Ok(())
+}
+
+fn status(app: &AppHandle) -> AutomationStatus {
+    let config = settings::get(app).automation;
+    let state = app.state::<AutomationServer>();
+    let listener = state.0.lock().ok();
+    AutomationStatus {
+        enabled: config.enabled,
+        port: config.port,
+        url: listener.as_ref().and_then(|listener| listener.url.clone()),
+        error: listener.as_ref().and_then(|listener| listener.error.clone()),
+    }
+}
+
+#[tauri::command]
+pub fn get_automation_status(app: AppHandle) -> AutomationStatus {
+    status(&app)
+}
+
+// Turn the API on or off, or move it to another port
+#[tauri::command]
+pub async fn set_automation_config(
+    app: AppHandle,
+    window: WebviewWindow,
+    config: AutomationSettings,
+) -> Result<AutomationStatus, String> {
+    validate(&config)?;
+    let action = if config.enabled { "enable" } else { "disable" };
+    settings::update(&app, |settings| settings.automation = config.clone())?;
+    audit::record(&app, Some(window.label()), "automation", action, &format!("port {}", config.port));
+    load_saved(&app).await?;
+    Ok(status(&app))
+}
+
+// For the settings UI to show, so it can be pasted into a script
+#[tauri::command]
+pub async fn get_automation_token(app: AppHandle, window: WebviewWindow) -> Result<String, String> {
+    app_lock::ensure_unlocked(&app)?;
+    audit::record(&app, Some(window.label()), "secret", "read", TOKEN_SECRET);
+    tauri::async_runtime::spawn_blocking(load_token)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Replace the token; anything using the old one is refused from now on
+#[tauri::command]
+pub async fn regenerate_automation_token(app: AppHandle, window: WebviewWindow) -> Result<String, String> {
+    app_lock::ensure_unlocked(&app)?;
+    let token = tauri::async_runtime::spawn_blocking(|| {
+        let token = generate_token()?;
+        if let Ok(Some(old)) = secrets::get(TOKEN_SECRET) {
+            redact::forget(&old);
+        }
+        secrets::set(TOKEN_SECRET, &token)?;
+        Ok::<_, String>(token)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    audit::record(&app, Some(window.label()), "secret", "write", TOKEN_SECRET);
+    let state = app.state::<AutomationServer>();
+    let mut listener = state.0.lock().map_err(|e| e.to_string())?;
+    if listener.token.is_some() {
+        listener.token = Some(token.clone());
+    }
+    Ok(token)
+}
+
+// Sessions asked for before the frontend was listening for
+// `automation-create-session`
+#[tauri::command]
+pub fn list_queued_automation_tasks(app: AppHandle) -> Result<Vec<SessionRequest>, String> {
+    let tasks = app.state::<AutomationTasks>();
+    let tasks = tasks.0.lock().map_err(|e| e.to_string())?;
+    let mut queued: Vec<&(AutomationTask, Option<Project>)> =
+        tasks.values().filter(|(task, _)| task.status == "queued").collect();
+    queued.sort_by(|(a, _), (b, _)| a.created_at.cmp(&b.created_at));
+    Ok(queued
+        .into_iter()
+        .filter_map(|(task, project)| {
+            Some(SessionRequest {
+                task_id: task.id.clone(),
+                project: project.clone()?,
+                prompt: task.prompt.clone(),
+            })
+        })
+        .collect())
+}
+
+// The frontend's progress on a task: "running" once it has a session,
+// then "completed", "failed" or "cancelled"
+#[tauri::command]
+pub fn update_automation_task(
+    app: AppHandle,
+    id: String,
+    status: String,
+    session_id: Option<String>,
+    error: Option<String>,
+) -> Result<(), String> {
+    if !TASK_STATUSES.contains(&status.as_str()) {
+        return Err(format!("Unknown task status: {}", status));
+    }
+    let tasks = app.state::<AutomationTasks>();
+    let mut tasks = tasks.0.lock().map_err(|e| e.to_string())?;
+    let (task, project) = tasks.get_mut(&id).ok_or_else(|| format!("No such task: {}", id))?;
+    task.status = status;
+    if session_id.is_some() {
+        task.session_id = session_id;
+    }
+    task.error = error;
+    task.updated_at = now();
+    if task.status != "queued" {
+        // Not needed once it's been picked up
+        *project = None;
+        task.prompt.clear();
+    }
+    Ok(())
+}
@@ -0,0 +1,321 @@
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use libsqlite3_sys as ffi;
+use sqlx::ConnectOptions;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_sql::{DbInstances, DbPool};
+
+use crate::{app_lock, db, encryption, fs_util, migrations};
+
+// Pages copied per backup step; progress is emitted between steps
+const PAGES_PER_STEP: i32 = 1024;
+const BUSY_TIMEOUT_MS: i32 = 5000;
+
+// Only one backup or restore runs at a time
+#[derive(Default)]
+pub struct DatabaseJob(pub tokio::sync::Mutex<()>);
+
+#[derive(Clone, serde::Serialize)]
+struct DatabaseProgress {
+    // "copying", then for a restore "swapping" and "migrating"
+    stage: String,
+    copied_pages: u64,
+    total_pages: u64,
+}
+
+fn emit_progress(app: &AppHandle, event: &str, stage: &str, copied_pages: u64, total_pages: u64) {
+    let _ = app.emit(
+        event,
+        DatabaseProgress {
+            stage: stage.to_string(),
+            copied_pages,
+            total_pages,
+        },
+    );
+}
+
+struct Connection(*mut ffi::sqlite3);
+
+impl Connection {
+    fn open(path: &Path, flags: i32) -> Result<Self, String> {
+        let filename = CString::new(path.to_string_lossy().as_bytes())
+            .map_err(|_| format!("Invalid database path: {}", path.display()))?;
+        let mut handle = std::ptr::null_mut();
+        let rc = unsafe { ffi::sqlite3_open_v2(filename.as_ptr(), &mut handle, flags, std::ptr::null()) };
+        // The handle is allocated even when opening fails, to carry the error
+        let connection = Connection(handle);
+        if rc != ffi::SQLITE_OK {
+            return Err(format!("Failed to open {}: {}", path.display(), connection.error()));
+        }
+        unsafe { ffi::sqlite3_busy_timeout(handle, BUSY_TIMEOUT_MS) };
+        Ok(connection)
+    }
+
+    // SQLCipher's key has to be set before anything else reads the file
+    fn set_key(&self, key: &str) -> Result<(), String> {
+        let sql = CString::new(format!("PRAGMA key = {}", encryption::key_pragma(key))).map_err(|e| e.to_string())?;
+        let rc = unsafe { ffi::sqlite3_exec(self.0, sql.as_ptr(), None, std::ptr::null_mut(), std::ptr::null_mut()) };
+        if rc != ffi::SQLITE_OK {
+            return Err(format!("Failed to unlock the database: {}", self.error()));
+        }
+        Ok(())
+    }
+
+    fn error(&self) -> String {
+        if self.0.is_null() {
+            return "out of memory".to_string();
+        }
+        unsafe { CStr::from_ptr(ffi::sqlite3_errmsg(self.0)) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        unsafe { ffi::sqlite3_close(self.0) };
+    }
+}
+
+// Copy `from` into a fresh file at `to` with SQLite's online backup API, so
+// the copy is consistent even while the app keeps writing to `from`. The
+// backup API can't change encryption, so a copy of an encrypted database
+// is encrypted with the same key.
+fn copy_database(from: &Path, to: &Path, key: Option<&str>, on_progress: impl Fn(u64, u64)) -> Result<(), String> {
+    let source = Connection::open(from, ffi::SQLITE_OPEN_READONLY)?;
+    if let Some(key) = key {
+        source.set_key(key)?;
+    }
+    let partial = fs_util::temp_path_for(to);
+    let result = (|| {
+        let destination = Connection::open(&partial, ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE)?;
+        if let Some(key) = key {
+            destination.set_key(key)?;
+        }
+        let main = c"main";
+        let backup = unsafe { ffi::sqlite3_backup_init(destination.0, main.as_ptr(), source.0, main.as_ptr()) };
+        if backup.is_null() {
+            return Err(format!("Failed to start backup: {}", destination.error()));
+        }
+
+        let rc = loop {
+            let rc = unsafe { ffi::sqlite3_backup_step(backup, PAGES_PER_STEP) };
+            let total = unsafe { ffi::sqlite3_backup_pagecount(backup) }.max(0) as u64;
+            let remaining = unsafe { ffi::sqlite3_backup_remaining(backup) }.max(0) as u64;
+            on_progress(total.saturating_sub(remaining), total);
+            match rc {
+                ffi::SQLITE_OK => {}
+                ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED => std::thread::sleep(Duration::from_millis(100)),
+                rc => break rc,
+            }
+        };
+        let finish = unsafe { ffi::sqlite3_backup_finish(backup) };
+        if rc != ffi::SQLITE_DONE || finish != ffi::SQLITE_OK {
+            return Err(format!("Failed to back up the database: {}", destination.error()));
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => std::fs::rename(&partial, to).map_err(|e| format!("Failed to write {}: {}", to.display(), e)),
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial);
+            Err(e)
+        }
+    }
+}
+
+// The key from the keychain, if `path` is encrypted. Like any other secret,
+// not read while the app is locked.
+fn key_for(app: &AppHandle, path: &Path) -> Result<Option<String>, String> {
+    if !db::is_encrypted(path) {
+        return Ok(None);
+    }
+    app_lock::ensure_unlocked(app)?;
+    encryption::saved_key()?
+        .map(Some)
+        .ok_or_else(|| format!("{} is encrypted, but the key isn't in the system keychain", path.display()))
+}
+
+async fn copy_in_background(app: &AppHandle, from: PathBuf, to: PathBuf, event: &'static str) -> Result<(), String> {
+    let key = key_for(app, &from)?;
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        copy_database(&from, &to, key.as_deref(), |copied, total| {
+            emit_progress(&handle, event, "copying", copied, total)
+        })
+    })
+    .await
+    .map_err(|e| format!("Backup task failed: {}", e))?
+}
+
+// A restore has to be an intact Incito database that this build can migrate
+pub async fn validate(path: &Path) -> Result<(), String> {
+    // Encrypted backups only open with this install's key
+    let mut connection = db::connect_options(path)?
+        .create_if_missing(false)
+        .read_only(true)
+        .connect()
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+
+    let (integrity,): (String,) = sqlx::query_as("PRAGMA integrity_check(1)")
+        .fetch_one(&mut connection)
+        .await
+        .map_err(|e| format!("{} isn't a valid database: {}", path.display(), e))?;
+    if integrity != "ok" {
+        return Err(format!("{} is damaged: {}", path.display(), integrity));
+    }
+
+    let tables: Vec<(String,)> = sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'table'")
+        .fetch_all(&mut connection)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let has_table = |name: &str| tables.iter().any(|(table,)| table == name);
+    if !has_table("settings") {
+        return Err(format!("{} isn't an Incito database", path.display()));
+    }
+
+    if has_table("_sqlx_migrations") {
+        let (version,): (Option<i64>,) = sqlx::query_as("SELECT MAX(version) FROM _sqlx_migrations")
+            .fetch_one(&mut connection)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if version.unwrap_or(0) > migrations::latest_version() {
+            return Err("The backup is from a newer version of Incito".to_string());
+        }
+    }
+    Ok(())
+}
+
+pub fn sidecar_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+// Move `replacement` over the database file, dropping the old WAL so it
+// can't be replayed onto the new file. With `keep_previous`, the old
+// database is kept as `incito.db.bak`.
+fn swap_in(replacement: &Path, path: &Path, keep_previous: bool) -> Result<(), String> {
+    let previous = fs_util::backup_path_for(path);
+    if path.exists() {
+        std::fs::rename(path, &previous).map_err(|e| format!("Failed to move {} aside: {}", path.display(), e))?;
+    }
+    for suffix in ["-wal", "-shm"] {
+        let _ = std::fs::remove_file(sidecar_path(path, suffix));
+    }
+    std::fs::rename(replacement, path).map_err(|e| {
+        let _ = std::fs::rename(&previous, path);
+        format!("Failed to replace {}: {}", path.display(), e)
+    })?;
+    if !keep_previous {
+        let _ = std::fs::remove_file(&previous);
+    }
+    Ok(())
+}
+
+// Close the pool, move `replacement` over the database file and reopen it.
+// Callers hold the `DatabaseJob` lock.
+pub async fn replace_database(app: &AppHandle, replacement: &Path, keep_previous: bool) -> Result<(), String> {
+    let path = db::path(app)?;
+    // Queries started after this fail with "Database is not loaded" until
+    // the pool is back
+    let pool = app
+        .try_state::<DbInstances>()
+        .ok_or_else(|| "Database plugin is not initialized".to_string())?
+        .0
+        .write()
+        .await
+        .remove(db::DB_URL);
+    if let Some(DbPool::Sqlite(pool)) = pool {
+        pool.close().await;
+    }
+
+    let swapped = swap_in(replacement, &path, keep_previous);
+    if swapped.is_err() {
+        let _ = std::fs::remove_file(replacement);
+    }
+    // Reopen whichever file is in place, so a failed swap leaves the app
+    // on the database it had
+    db::open(app).await?;
+    swapped
+}
+
+// Write a consistent copy of the live database to `dest_path`. Emits
+// `db-backup-progress` as pages are copied.
+#[tauri::command]
+pub async fn backup_database(app: AppHandle, dest_path: String) -> Result<(), String> {
+    let job = app.state::<DatabaseJob>();
+    let _guard = job
+        .0
+        .try_lock()
+        .map_err(|_| "A database backup or restore is already running".to_string())?;
+
+    let source = db::path(&app)?;
+    copy_in_background(&app, source, PathBuf::from(dest_path), "db-backup-progress").await
+}
+
+// A copy of `staged` encrypted with the key the database at `path` uses
+async fn encrypt_staged(app: &AppHandle, staged: &Path, path: &Path) -> Result<PathBuf, String> {
+    let key = key_for(app, path)?.ok_or_else(|| format!("{} isn't encrypted", path.display()))?;
+    let encrypted = fs_util::temp_path_for(path);
+    let export = async {
+        let mut connection = db::connect_options(staged)?
+            .create_if_missing(false)
+            .connect()
+            .await
+            .map_err(|e| e.to_string())?;
+        encryption::export_encrypted(&mut connection, &encrypted, &key)
+            .await
+            .map_err(|e| e.to_string())
+    }
+    .await;
+    if let Err(e) = export {
+        let _ = std::fs::remove_file(&encrypted);
+        return Err(format!("Failed to encrypt the restored database: {}", e));
+    }
+    Ok(encrypted)
+}
+
+// Replace the live database with the backup at `src_path`, then bring it up
+// to date with the migrations. Emits `db-restore-progress` as it goes and
+// `db-restored` once the frontend should reload its data.
+#[tauri::command]
+pub async fn restore_database(app: AppHandle, src_path: String) -> Result<(), String> {
+    let job = app.state::<DatabaseJob>();
+    let _guard = job
+        .0
+        .try_lock()
+        .map_err(|_| "A database backup or restore is already running".to_string())?;
+
+    let source = PathBuf::from(src_path);
+    validate(&source).await?;
+
+    // Copy first, so a backup on a slow or removable drive is read while
+    // the current database is still in use
+    let path = db::path(&app)?;
+    let staged = fs_util::temp_path_for(&path);
+    copy_in_background(&app, source, staged.clone(), "db-restore-progress").await?;
+    // A plaintext backup would otherwise turn encryption off
+    let staged = if db::is_encrypted(&path) && !db::is_encrypted(&staged) {
+        let encrypted = encrypt_staged(&app, &staged, &path).await;
+        let _ = std::fs::remove_file(&staged);
+        encrypted?
+    } else {
+        staged
+    };
+
+    emit_progress(&app, "db-restore-progress", "swapping", 0, 0);
+    replace_database(&app, &staged, true).await?;
+
+    emit_progress(&app, "db-restore-progress", "migrating", 0, 0);
+    migrations::run(&app)
+        .await
+        .map_err(|failure| format!("Failed to migrate the restored database: {}", failure.error))?;
+
+    let _ = app.emit("db-restored", ());
+    Ok(())
+}
@@ -0,0 +1,467 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::diff::{self, PathsDiff};
+use crate::policy::{self, Allowlist, PolicyError};
+use crate::{audit, fs_util, project_files, trash};
+
+// Bigger files are left out of checkpoints and listed as skipped
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+// New content one checkpoint may add to the store
+const MAX_CHECKPOINT_BYTES: u64 = 1024 * 1024 * 1024;
+// Older checkpoints of a project are dropped past this
+const MAX_CHECKPOINTS: usize = 50;
+const PROGRESS_EVERY: usize = 200;
+
+// Only one checkpoint is made or reverted at a time
+#[derive(Default)]
+pub struct CheckpointJob(tokio::sync::Mutex<()>);
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct Entry {
+    // SHA-256 of the content, which is stored under it
+    hash: String,
+    size: u64,
+    // Milliseconds since the epoch; with `size`, lets the next checkpoint
+    // skip hashing files that haven't changed
+    modified: i64,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    id: String,
+    project: String,
+    label: Option<String>,
+    created_at: String,
+    // Relative to the project, '/'-separated
+    files: BTreeMap<String, Entry>,
+    skipped: Vec<String>,
+    // Files new or different since the previous checkpoint
+    changed: usize,
+    stored_bytes: u64,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct CheckpointInfo {
+    pub id: String,
+    pub project: String,
+    pub label: Option<String>,
+    pub created_at: String,
+    pub files: usize,
+    pub changed: usize,
+    pub skipped: usize,
+    // New content this checkpoint added to the store
+    pub stored_bytes: u64,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RevertSummary {
+    // The checkpoint taken of the project just before reverting, so the
+    // revert can itself be undone
+    pub backup_checkpoint_id: String,
+    pub restored: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+    pub failed: Vec<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct CheckpointProgress {
+    project: String,
+    // "snapshotting" or "restoring"
+    stage: &'static str,
+    done: usize,
+    total: usize,
+}
+
+impl Manifest {
+    fn info(&self) -> CheckpointInfo {
+        CheckpointInfo {
+            id: self.id.clone(),
+            project: self.project.clone(),
+            label: self.label.clone(),
+            created_at: self.created_at.clone(),
+            files: self.files.len(),
+            changed: self.changed,
+            skipped: self.skipped.len(),
+            stored_bytes: self.stored_bytes,
+        }
+    }
+}
+
+fn store_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("checkpoints"))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn object_path(store: &Path, hash: &str) -> PathBuf {
+    store.join("objects").join(&hash[..2]).join(hash)
+}
+
+// Checkpoints of each project live in a folder named after its path
+fn project_dir(store: &Path, root: &Path) -> PathBuf {
+    let digest = Sha256::digest(root.to_string_lossy().as_bytes());
+    store.join("projects").join(&hex::encode(digest)[..16])
+}
+
+fn canonical_root(project: &str) -> Result<PathBuf, String> {
+    let root = std::fs::canonicalize(project).map_err(|e| format!("Failed to open {}: {}", project, e))?;
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", project));
+    }
+    Ok(root)
+}
+
+fn modified_ms(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as i64)
+}
+
+fn progress(app: &AppHandle, root: &Path, stage: &'static str, done: usize, total: usize) {
+    if done.is_multiple_of(PROGRESS_EVERY) || done == total {
+        let _ = app.emit(
+            "checkpoint-progress",
+            CheckpointProgress {
+                project: root.to_string_lossy().to_string(),
+                stage,
+                done,
+                total,
+            },
+        );
+    }
+}
+
+// Newest first
+fn manifests(dir: &Path) -> Vec<Manifest> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut manifests: Vec<Manifest> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|entry| std::fs::read(entry.path()).ok())
+        .filter_map(|contents| serde_json::from_slice(&contents).ok())
+        .collect();
+    manifests.sort_by(|a: &Manifest, b: &Manifest| b.created_at.cmp(&a.created_at));
+    manifests
+}
+
+fn load(store: &Path, root: &Path, id: &str) -> Result<Manifest, String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("Checkpoint {} not found", id));
+    }
+    let contents = std::fs::read(project_dir(store, root).join(format!("{}.json", id)))
+        .map_err(|_| format!("Checkpoint {} not found", id))?;
+    serde_json::from_slice(&contents).map_err(|e| format!("Failed to read checkpoint {}: {}", id, e))
+}
+
+fn read_object(store: &Path, hash: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(object_path(store, hash)).map_err(|e| format!("Checkpoint content {} is missing: {}", hash, e))
+}
+
+// Drop a project's checkpoints past `MAX_CHECKPOINTS`, then any content no
+// checkpoint of any project still refers to
+fn prune(store: &Path, dir: &Path) {
+    let stale: Vec<Manifest> = manifests(dir).into_iter().skip(MAX_CHECKPOINTS).collect();
+    if stale.is_empty() {
+        return;
+    }
+    for manifest in &stale {
+        let _ = std::fs::remove_file(dir.join(format!("{}.json", manifest.id)));
+    }
+
+    let Ok(projects) = std::fs::read_dir(store.join("projects")) else {
+        return;
+    };
+    let referenced: HashSet<String> = projects
+        .flatten()
+        .flat_map(|project| manifests(&project.path()))
+        .flat_map(|manifest| manifest.files.into_values().map(|entry| entry.hash))
+        .collect();
+    let Ok(buckets) = std::fs::read_dir(store.join("objects")) else {
+        return;
+    };
+    for object in buckets.flatten().filter_map(|bucket| std::fs::read_dir(bucket.path()).ok()).flatten().flatten() {
+        if !referenced.contains(&*object.file_name().to_string_lossy()) {
+            let _ = std::fs::remove_file(object.path());
+        }
+    }
+}
+
+fn create(app: &AppHandle, root: &Path, label: Option<String>) -> Result<Manifest, String> {
+    let store = store_dir(app)?;
+    let dir = project_dir(&store, root);
+    let previous = manifests(&dir).into_iter().next();
+
+    let files: Vec<(String, PathBuf, std::fs::Metadata)> = project_files::walker(root)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .filter_map(|entry| {
+            let relative = project_files::relative_path(root, entry.path())?;
+            let metadata = entry.metadata().ok()?;
+            Some((relative, entry.into_path(), metadata))
+        })
+        .collect();
+
+    let mut manifest = Manifest {
+        id: uuid::Uuid::new_v4().simple().to_string(),
+        project: root.to_string_lossy().to_string(),
+        label,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        files: BTreeMap::new(),
+        skipped: Vec::new(),
+        changed: 0,
+        stored_bytes: 0,
+    };
+    let total = files.len();
+    for (index, (relative, path, metadata)) in files.into_iter().enumerate() {
+        progress(app, root, "snapshotting", index + 1, total);
+        if metadata.len() > MAX_FILE_BYTES {
+            manifest.skipped.push(relative);
+            continue;
+        }
+        let modified = modified_ms(&metadata);
+        let before = previous.as_ref().and_then(|previous| previous.files.get(&relative));
+        if let Some(before) = before.filter(|before| before.size == metadata.len() && before.modified == modified) {
+            manifest.files.insert(relative, before.clone());
+            continue;
+        }
+
+        // Unreadable files, such as ones removed mid-walk, are left out
+        let Ok(bytes) = std::fs::read(&path) else {
+            manifest.skipped.push(relative);
+            continue;
+        };
+        let hash = hex::encode(Sha256::digest(&bytes));
+        if before.is_none_or(|before| before.hash != hash) {
+            manifest.changed += 1;
+        }
+        let object = object_path(&store, &hash);
+        if !object.exists() {
+            manifest.stored_bytes += bytes.len() as u64;
+            if manifest.stored_bytes > MAX_CHECKPOINT_BYTES {
+                return Err(format!(
+                    "The project has more than {} MB of new content to checkpoint",
+                    MAX_CHECKPOINT_BYTES / 1024 / 1024
+                ));
+            }
+            fs_util::write_atomic(&object, &bytes, false)?;
+        }
+        manifest.files.insert(
+            relative,
+            Entry {
+                hash,
+                size: bytes.len() as u64,
+                modified,
+            },
+        );
+    }
+
+    let contents = serde_json::to_vec(&manifest).map_err(|e| e.to_string())?;
+    fs_util::write_atomic(&dir.join(format!("{}.json", manifest.id)), &contents, false)?;
+    Ok(manifest)
+}
+
+fn revert(
+    app: &AppHandle,
+    root: &Path,
+    id: &str,
+    allowlist: &Allowlist,
+) -> Result<RevertSummary, PolicyError> {
+    let store = store_dir(app)?;
+    let target = load(&store, root, id)?;
+    // Snapshotting first both makes the revert undoable and gives the
+    // current hash of every file
+    let current = create(app, root, Some(format!("Before reverting to {}", target.created_at)))?;
+
+    let mut summary = RevertSummary {
+        backup_checkpoint_id: current.id.clone(),
+        restored: 0,
+        deleted: 0,
+        unchanged: 0,
+        failed: Vec::new(),
+    };
+    let removed: Vec<&String> = current
+        .files
+        .keys()
+        .chain(&current.skipped)
+        .filter(|relative| !target.files.contains_key(*relative) && !target.skipped.contains(relative))
+        .collect();
+    // Nothing is touched unless all of it may be
+    let changed = target
+        .files
+        .iter()
+        .filter(|(relative, entry)| current.files.get(*relative).is_none_or(|now| now.hash != entry.hash))
+        .map(|(relative, _)| relative);
+    for relative in changed.chain(removed.iter().copied()) {
+        allowlist.check(&root.join(relative))?;
+    }
+    let total = target.files.len() + removed.len();
+    let mut done = 0;
+
+    for (relative, entry) in &target.files {
+        done += 1;
+        progress(app, root, "restoring", done, total);
+        if current.files.get(relative).is_some_and(|now| now.hash == entry.hash) {
+            summary.unchanged += 1;
+            continue;
+        }
+        let restored = read_object(&store, &entry.hash)
+            .and_then(|bytes| fs_util::write_atomic(&root.join(relative), &bytes, false));
+        match restored {
+            Ok(()) => summary.restored += 1,
+            Err(e) => summary.failed.push(format!("{}: {}", relative, e)),
+        }
+    }
+    // Files made since the checkpoint go to the trash rather than away
+    for relative in removed {
+        done += 1;
+        progress(app, root, "restoring", done, total);
+        match trash::move_to_trash(&root.join(relative)) {
+            Ok(()) => summary.deleted += 1,
+            Err(e) => summary.failed.push(format!("{}: {}", relative, e)),
+        }
+    }
+    // Only now, so the checkpoint being reverted to can't be pruned first
+    prune(&store, &project_dir(&store, root));
+    Ok(summary)
+}
+
+fn diff_with_project(app: &AppHandle, root: &Path, id: &str, context_lines: usize) -> Result<PathsDiff, String> {
+    let store = store_dir(app)?;
+    let checkpoint = load(&store, root, id)?;
+
+    let mut paths: BTreeMap<String, Option<&Entry>> =
+        checkpoint.files.iter().map(|(relative, entry)| (relative.clone(), Some(entry))).collect();
+    for entry in project_files::walker(root).build().filter_map(Result::ok) {
+        if entry.file_type().is_some_and(|kind| kind.is_file()) {
+            if let Some(relative) = project_files::relative_path(root, entry.path()) {
+                paths.entry(relative).or_insert(None);
+            }
+        }
+    }
+
+    let mut result = PathsDiff {
+        files: Vec::new(),
+        truncated: false,
+    };
+    for (relative, entry) in paths {
+        if entry.is_none() && checkpoint.skipped.contains(&relative) {
+            continue;
+        }
+        let path = root.join(&relative);
+        let metadata = std::fs::metadata(&path).ok();
+        if let (Some(entry), Some(metadata)) = (entry, &metadata) {
+            if entry.size == metadata.len() && entry.modified == modified_ms(metadata) {
+                continue;
+            }
+        }
+        let new = match std::fs::read(&path) {
+            // Touched but not changed
+            Ok(bytes) if entry.is_some_and(|entry| hex::encode(Sha256::digest(&bytes)) == entry.hash) => continue,
+            Ok(bytes) => diff::content(bytes),
+            Err(_) => diff::Content::Missing,
+        };
+        let old = match entry {
+            Some(entry) => diff::content(read_object(&store, &entry.hash)?),
+            None => diff::Content::Missing,
+        };
+        if result.files.len() == diff::MAX_FILES {
+            result.truncated = true;
+            break;
+        }
+        let file = diff::file_diff(Some(relative), old, new, context_lines);
+        if file.status != "unchanged" {
+            result.files.push(file);
+        }
+    }
+    Ok(result)
+}
+
+// Snapshot every file in the project that git wouldn't ignore, storing
+// each version once however many checkpoints share it. Emits
+// `checkpoint-progress` while it runs.
+#[tauri::command]
+pub async fn create_checkpoint(
+    app: AppHandle,
+    project: String,
+    label: Option<String>,
+) -> Result<CheckpointInfo, String> {
+    let job = app.state::<CheckpointJob>();
+    let _guard = job
+        .0
+        .try_lock()
+        .map_err(|_| "A checkpoint is already being made or reverted".to_string())?;
+    let root = canonical_root(&project)?;
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let manifest = create(&handle, &root, label)?;
+        let store = store_dir(&handle)?;
+        prune(&store, &project_dir(&store, &root));
+        Ok(manifest.info())
+    })
+    .await
+        .map_err(|e| format!("Checkpoint task failed: {}", e))?
+}
+
+// A project's checkpoints, newest first
+#[tauri::command]
+pub async fn list_checkpoints(app: AppHandle, project: String) -> Result<Vec<CheckpointInfo>, String> {
+    let root = canonical_root(&project)?;
+    let dir = project_dir(&store_dir(&app)?, &root);
+    tauri::async_runtime::spawn_blocking(move || manifests(&dir).iter().map(Manifest::info).collect())
+        .await
+        .map_err(|e| format!("Failed to list checkpoints: {}", e))
+}
+
+// What changed in the project since the checkpoint, as `diff_paths` does
+#[tauri::command]
+pub async fn diff_checkpoint(
+    app: AppHandle,
+    project: String,
+    checkpoint_id: String,
+    context_lines: Option<usize>,
+) -> Result<PathsDiff, String> {
+    let root = canonical_root(&project)?;
+    let context_lines = context_lines.unwrap_or(diff::DEFAULT_CONTEXT_LINES);
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || diff_with_project(&handle, &root, &checkpoint_id, context_lines))
+        .await
+        .map_err(|e| format!("Diff task failed: {}", e))?
+}
+
+// Put the project back as it was at the checkpoint: changed and deleted
+// files are restored and files created since are moved to the trash. The
+// current state is checkpointed first. Emits `checkpoint-progress`.
+#[tauri::command]
+pub async fn revert_to_checkpoint(
+    app: AppHandle,
+    window: WebviewWindow,
+    project: String,
+    checkpoint_id: String,
+) -> Result<RevertSummary, PolicyError> {
+    let job = app.state::<CheckpointJob>();
+    let _guard = job
+        .0
+        .try_lock()
+        .map_err(|_| "A checkpoint is already being made or reverted".to_string())?;
+    let root = canonical_root(&project)?;
+    let allowlist = policy::allowlist(&app, &project).await?;
+    let handle = app.clone();
+    let subject = format!("{} to checkpoint {}", project, checkpoint_id);
+    let summary = tauri::async_runtime::spawn_blocking(move || revert(&handle, &root, &checkpoint_id, &allowlist))
+        .await
+        .map_err(|e| format!("Revert task failed: {}", e))??;
+    audit::record(&app, Some(window.label()), "file", "revert", &subject);
+    Ok(summary)
+}
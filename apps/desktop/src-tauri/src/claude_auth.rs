@@ -0,0 +1,136 @@
+use serde_json::Value;
+
+use crate::paths;
+
+#[derive(Clone, serde::Serialize)]
+pub struct ClaudeAuthStatus {
+    pub logged_in: bool,
+    // "oauth", "api_key", "api_key_helper" or "env"
+    pub method: Option<String>,
+    pub account_email: Option<String>,
+    pub organization: Option<String>,
+    // Subscription type reported by the OAuth credentials, e.g. "pro" or "max"
+    pub plan: Option<String>,
+    // Unix timestamp in milliseconds
+    pub expires_at: Option<i64>,
+    pub expired: bool,
+    pub hint: Option<String>,
+}
+
+const LOGIN_HINT: &str = "Run \"claude login\" in a terminal to authenticate";
+
+// Figure out how the CLI authenticates without starting a session. Checks,
+// in the CLI's own order of precedence: ANTHROPIC_API_KEY, an apiKeyHelper
+// in settings, then OAuth credentials and a stored API key.
+#[tauri::command]
+pub fn get_claude_auth_status() -> Result<ClaudeAuthStatus, String> {
+    let state = read_json(&paths::claude_state_file()?);
+    let account = state.as_ref().and_then(|s| s.get("oauthAccount"));
+    let account_email = account
+        .and_then(|a| a.get("emailAddress"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let organization = account
+        .and_then(|a| a.get("organizationName"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let mut status = ClaudeAuthStatus {
+        logged_in: false,
+        method: None,
+        account_email,
+        organization,
+        plan: None,
+        expires_at: None,
+        expired: false,
+        hint: None,
+    };
+
+    if std::env::var("ANTHROPIC_API_KEY").is_ok_and(|key| !key.is_empty()) {
+        status.logged_in = true;
+        status.method = Some("env".to_string());
+        return Ok(status);
+    }
+
+    let settings = read_json(&paths::claude_dir()?.join("settings.json"));
+    if settings
+        .as_ref()
+        .and_then(|s| s.get("apiKeyHelper"))
+        .and_then(Value::as_str)
+        .is_some_and(|helper| !helper.is_empty())
+    {
+        status.logged_in = true;
+        status.method = Some("api_key_helper".to_string());
+        return Ok(status);
+    }
+
+    if let Some(oauth) = read_oauth_credentials() {
+        status.method = Some("oauth".to_string());
+        status.plan = oauth
+            .get("subscriptionType")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        status.expires_at = oauth.get("expiresAt").and_then(Value::as_i64);
+
+        // An expired access token is fine as long as it can be refreshed
+        let has_refresh_token = oauth
+            .get("refreshToken")
+            .and_then(Value::as_str)
+            .is_some_and(|token| !token.is_empty());
+        status.expired = status.expires_at.is_some_and(|expires_at| expires_at < now_millis());
+        status.logged_in = !status.expired || has_refresh_token;
+        if !status.logged_in {
+            status.hint = Some(format!("Your Claude session has expired. {}", LOGIN_HINT));
+        }
+        return Ok(status);
+    }
+
+    if state
+        .as_ref()
+        .and_then(|s| s.get("primaryApiKey"))
+        .and_then(Value::as_str)
+        .is_some_and(|key| !key.is_empty())
+    {
+        status.logged_in = true;
+        status.method = Some("api_key".to_string());
+        return Ok(status);
+    }
+
+    status.hint = Some(LOGIN_HINT.to_string());
+    Ok(status)
+}
+
+fn read_json(path: &std::path::Path) -> Option<Value> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn now_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+// The `claudeAiOauth` entry of the CLI credentials: a JSON file on Linux and
+// Windows, a Keychain item on macOS
+fn read_oauth_credentials() -> Option<Value> {
+    let from_file = paths::claude_dir()
+        .ok()
+        .and_then(|dir| read_json(&dir.join(".credentials.json")));
+
+    #[cfg(target_os = "macos")]
+    let from_file = from_file.or_else(read_keychain_credentials);
+
+    from_file?.get("claudeAiOauth").cloned()
+}
+
+#[cfg(target_os = "macos")]
+fn read_keychain_credentials() -> Option<Value> {
+    let output = std::process::Command::new("security")
+        .args(["find-generic-password", "-s", "Claude Code-credentials", "-w"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    serde_json::from_slice(&output.stdout).ok()
+}
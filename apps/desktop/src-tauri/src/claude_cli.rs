@@ -0,0 +1,521 @@
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::verify::{self, VerifyError};
+use crate::{fs_util, paths, settings};
+
+#[derive(Clone, serde::Serialize)]
+pub struct ClaudeCodePathResult {
+    pub found: bool,
+    pub path: Option<String>,
+    pub version: Option<String>,
+    pub error: Option<String>,
+    // How the executable is launched: "direct", "node", "cmd" or "powershell"
+    pub launcher: Option<String>,
+    // Whether the version meets MIN_CLAUDE_CODE_VERSION, and why not
+    pub compatible: bool,
+    pub compatibility_reason: Option<String>,
+}
+
+impl ClaudeCodePathResult {
+    fn found(path: String, launcher: &ClaudeLauncher, version: Option<String>) -> Self {
+        let compatibility = version.as_deref().map(check_version_compatibility);
+        ClaudeCodePathResult {
+            found: true,
+            path: Some(path),
+            version,
+            error: None,
+            launcher: Some(launcher.kind().to_string()),
+            compatible: matches!(compatibility, Some(Ok(()))),
+            compatibility_reason: match compatibility {
+                Some(Err(reason)) => Some(reason),
+                Some(Ok(())) => None,
+                None => Some("Could not determine the Claude Code version".to_string()),
+            },
+        }
+    }
+
+    fn not_found(path: Option<String>, error: String) -> Self {
+        ClaudeCodePathResult {
+            found: false,
+            path,
+            version: None,
+            error: Some(error),
+            launcher: None,
+            compatible: false,
+            compatibility_reason: None,
+        }
+    }
+}
+
+// Oldest CLI release providing the flags Incito relies on
+pub const MIN_CLAUDE_CODE_VERSION: &str = "2.0.0";
+
+#[derive(Clone, serde::Serialize)]
+struct IncompatibleVersionWarning {
+    path: String,
+    version: Option<String>,
+    minimum: String,
+    reason: String,
+}
+
+// `claude --version` prints e.g. "2.0.14 (Claude Code)"; take the first
+// token that parses as semver, tolerating a leading "v"
+pub fn parse_claude_version(output: &str) -> Option<semver::Version> {
+    output
+        .split_whitespace()
+        .map(|token| token.trim_start_matches('v'))
+        .find_map(|token| semver::Version::parse(token).ok())
+}
+
+pub fn check_version_compatibility(output: &str) -> Result<(), String> {
+    let minimum = semver::Version::parse(MIN_CLAUDE_CODE_VERSION)
+        .expect("MIN_CLAUDE_CODE_VERSION must be valid semver");
+
+    match parse_claude_version(output) {
+        Some(version) if version >= minimum => Ok(()),
+        Some(version) => Err(format!(
+            "Claude Code {} is older than the minimum supported version {}. Please update the CLI.",
+            version, minimum
+        )),
+        None => Err(format!("Unrecognized Claude Code version: {}", output)),
+    }
+}
+
+// Let the frontend know the configured executable won't work as expected
+pub fn warn_if_incompatible(app: &AppHandle, result: &ClaudeCodePathResult) {
+    if !result.found || result.compatible {
+        return;
+    }
+
+    let _ = app.emit(
+        "claude-version-incompatible",
+        IncompatibleVersionWarning {
+            path: result.path.clone().unwrap_or_default(),
+            version: result.version.clone(),
+            minimum: MIN_CLAUDE_CODE_VERSION.to_string(),
+            reason: result.compatibility_reason.clone().unwrap_or_default(),
+        },
+    );
+}
+
+// How a Claude Code executable has to be started. npm on Windows installs
+// `claude.cmd` / `claude.ps1` shims which can't be exec'd directly, so those
+// are either resolved to the node script they wrap or run through their shell.
+#[derive(Clone, Debug)]
+pub enum ClaudeLauncher {
+    Direct(PathBuf),
+    Node { node: PathBuf, script: PathBuf },
+    Cmd(PathBuf),
+    PowerShell(PathBuf),
+}
+
+impl ClaudeLauncher {
+    pub fn resolve(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("cmd") | Some("bat") => {
+                resolve_node_shim(path).unwrap_or_else(|| ClaudeLauncher::Cmd(path.to_path_buf()))
+            }
+            Some("ps1") => resolve_node_shim(path)
+                .unwrap_or_else(|| ClaudeLauncher::PowerShell(path.to_path_buf())),
+            Some("js") | Some("mjs") | Some("cjs") => match find_node(path) {
+                Some(node) => ClaudeLauncher::Node {
+                    node,
+                    script: path.to_path_buf(),
+                },
+                None => ClaudeLauncher::Direct(path.to_path_buf()),
+            },
+            _ => ClaudeLauncher::Direct(path.to_path_buf()),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ClaudeLauncher::Direct(_) => "direct",
+            ClaudeLauncher::Node { .. } => "node",
+            ClaudeLauncher::Cmd(_) => "cmd",
+            ClaudeLauncher::PowerShell(_) => "powershell",
+        }
+    }
+
+    // Build a command that runs the CLI; callers append the CLI arguments
+    pub fn command(&self) -> Command {
+        match self {
+            ClaudeLauncher::Direct(path) => Command::new(path),
+            ClaudeLauncher::Node { node, script } => {
+                let mut command = Command::new(node);
+                command.arg(script);
+                command
+            }
+            ClaudeLauncher::Cmd(path) => {
+                let mut command = Command::new("cmd");
+                command.arg("/C").arg(path);
+                command
+            }
+            ClaudeLauncher::PowerShell(path) => {
+                let mut command = Command::new("powershell");
+                command
+                    .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-File"])
+                    .arg(path);
+                command
+            }
+        }
+    }
+
+    // The path handed to the sidecar. The Claude Code SDK runs `.js`
+    // entrypoints with node itself, so shims resolve to their script.
+    pub fn sidecar_path(&self) -> PathBuf {
+        match self {
+            ClaudeLauncher::Direct(path)
+            | ClaudeLauncher::Cmd(path)
+            | ClaudeLauncher::PowerShell(path) => path.clone(),
+            ClaudeLauncher::Node { script, .. } => script.clone(),
+        }
+    }
+}
+
+// npm shims reference the package entrypoint relative to the shim directory,
+// e.g. `"%dp0%\node_modules\@anthropic-ai\claude-code\cli.js"` in claude.cmd
+// or `"$basedir/node_modules/@anthropic-ai/claude-code/cli.js"` in claude.ps1
+fn resolve_node_shim(shim: &Path) -> Option<ClaudeLauncher> {
+    let contents = std::fs::read_to_string(shim).ok()?;
+    let base_dir = shim.parent()?;
+
+    let script = contents.split('"').find_map(|segment| {
+        let relative = segment
+            .strip_prefix("%dp0%")
+            .or_else(|| segment.strip_prefix("%~dp0"))
+            .or_else(|| segment.strip_prefix("$basedir"))?;
+        let relative = relative.trim_start_matches(['\\', '/']);
+        if !relative.ends_with(".js") {
+            return None;
+        }
+        let candidate = relative
+            .split(['\\', '/'])
+            .fold(base_dir.to_path_buf(), |acc, part| acc.join(part));
+        candidate.exists().then_some(candidate)
+    })?;
+
+    let node = find_node(shim)?;
+    Some(ClaudeLauncher::Node { node, script })
+}
+
+// npm shims prefer a node binary next to the shim, then fall back to PATH
+fn find_node(near: &Path) -> Option<PathBuf> {
+    let node_name = if cfg!(target_os = "windows") { "node.exe" } else { "node" };
+
+    if let Some(dir) = near.parent() {
+        let local = dir.join(node_name);
+        if local.exists() {
+            return Some(local);
+        }
+    }
+
+    which("node").map(PathBuf::from)
+}
+
+// Look up a program using 'which' on Unix or 'where' on Windows and return
+// the first usable match
+pub fn which(program: &str) -> Option<String> {
+    #[cfg(target_os = "windows")]
+    let output = Command::new("where").arg(program).output();
+
+    #[cfg(not(target_os = "windows"))]
+    let output = Command::new("which").arg(program).output();
+
+    let output = output.ok().filter(|o| o.status.success())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let candidates: Vec<&str> = stdout
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    // On Windows `where claude` lists the extensionless bash shim first,
+    // which can't be executed, so prefer entries with a runnable extension
+    #[cfg(target_os = "windows")]
+    {
+        let runnable = candidates.iter().find(|candidate| {
+            let lower = candidate.to_ascii_lowercase();
+            [".exe", ".cmd", ".bat", ".ps1"]
+                .iter()
+                .any(|ext| lower.ends_with(ext))
+        });
+        if let Some(candidate) = runnable {
+            return Some(candidate.to_string());
+        }
+    }
+
+    candidates.first().map(|candidate| candidate.to_string())
+}
+
+#[tauri::command]
+pub fn find_claude_code_path() -> Result<ClaudeCodePathResult, String> {
+    match which("claude") {
+        Some(path) => {
+            let launcher = ClaudeLauncher::resolve(Path::new(&path));
+            // Try to get version
+            let version = get_claude_version(&launcher);
+            Ok(ClaudeCodePathResult::found(path, &launcher, version))
+        }
+        None => Ok(ClaudeCodePathResult::not_found(
+            None,
+            "Claude Code not found in system PATH".to_string(),
+        )),
+    }
+}
+
+#[tauri::command]
+pub fn check_claude_code_path(app: AppHandle, path: String) -> Result<ClaudeCodePathResult, String> {
+    // Check if file exists
+    let path_obj = Path::new(&path);
+    if !path_obj.exists() {
+        return Ok(ClaudeCodePathResult::not_found(
+            Some(path),
+            "File does not exist".to_string(),
+        ));
+    }
+
+    let launcher = ClaudeLauncher::resolve(path_obj);
+
+    // Try to get version to verify it's actually Claude Code
+    match get_claude_version(&launcher) {
+        Some(version) => {
+            let result = ClaudeCodePathResult::found(path, &launcher, Some(version));
+            warn_if_incompatible(&app, &result);
+            Ok(result)
+        }
+        None => Ok(ClaudeCodePathResult::not_found(
+            Some(path),
+            "File exists but does not appear to be Claude Code (could not get version)".to_string(),
+        )),
+    }
+}
+
+pub fn get_claude_version(launcher: &ClaudeLauncher) -> Option<String> {
+    // Spawn the process
+    let mut child = launcher
+        .command()
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    // Wait with a timeout of 5 seconds
+    let start = std::time::Instant::now();
+    let timeout = Duration::from_secs(5);
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                if status.success() {
+                    let output = child.wait_with_output().ok()?;
+                    let version_str = String::from_utf8_lossy(&output.stdout);
+                    return Some(version_str.trim().to_string());
+                } else {
+                    return None;
+                }
+            }
+            Ok(None) => {
+                if start.elapsed() > timeout {
+                    // Kill the process if it's taking too long
+                    let _ = child.kill();
+                    return None;
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(_) => return None,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+struct InstallProgress {
+    stream: String,
+    line: String,
+}
+
+const CLAUDE_CODE_NPM_PACKAGE: &str = "@anthropic-ai/claude-code";
+// The native installer script, as listed in the signed downloads manifest
+#[cfg(target_os = "windows")]
+const INSTALLER_SCRIPT: &str = "claude-install.ps1";
+#[cfg(not(target_os = "windows"))]
+const INSTALLER_SCRIPT: &str = "claude-install.sh";
+
+// Install or update the Claude Code CLI, streaming installer output as
+// `claude-install-progress` events. `method` is "npm" or "native"; when
+// omitted npm is used if available, falling back to the native installer.
+// The native installer script only runs once it matches its checksum in
+// the signed downloads manifest; npm checks its packages' integrity itself.
+#[tauri::command]
+pub async fn install_claude_code(
+    app: AppHandle,
+    method: Option<String>,
+) -> Result<ClaudeCodePathResult, VerifyError> {
+    let method = match method.as_deref() {
+        Some(method @ ("npm" | "native")) => method.to_string(),
+        Some(other) => return Err(format!("Unknown install method: {}", other).into()),
+        None if which("npm").is_some() => "npm".to_string(),
+        None => "native".to_string(),
+    };
+
+    let script = if method == "native" {
+        let bytes = verify::download_listed(&app, INSTALLER_SCRIPT).await.inspect_err(|error| {
+            verify::report(&app, error);
+        })?;
+        let path = std::env::temp_dir().join(format!("incito-{}-{}", uuid::Uuid::new_v4().simple(), INSTALLER_SCRIPT));
+        fs_util::write_atomic(&path, &bytes, false)?;
+        Some(path)
+    } else {
+        None
+    };
+
+    let install_app = app.clone();
+    let install_method = method.clone();
+    let install_script = script.clone();
+    let installed = tauri::async_runtime::spawn_blocking(move || {
+        run_installer(&install_app, &install_method, install_script.as_deref())
+    })
+    .await
+    .map_err(|e| format!("Installer task failed: {}", e));
+    if let Some(script) = script {
+        let _ = std::fs::remove_file(script);
+    }
+    installed??;
+
+    let path = locate_installed_claude(&method)
+        .ok_or_else(|| "Claude Code was installed but the executable could not be found".to_string())?;
+    // Runs the executable and waits on it, so off the async runtime too
+    let (check_app, check_path) = (app.clone(), path.clone());
+    let result = tauri::async_runtime::spawn_blocking(move || check_claude_code_path(check_app, check_path))
+        .await
+        .map_err(|e| format!("Check task failed: {}", e))??;
+    if !result.found {
+        return Err(result
+            .error
+            .unwrap_or_else(|| "Installed Claude Code could not be verified".to_string())
+            .into());
+    }
+
+    settings::update(&app, |settings| settings.server.claude_code_executable_path = Some(path))?;
+    let _ = app.emit("claude-install-finished", result.clone());
+
+    Ok(result)
+}
+
+// `script` is the verified native installer, for any method but npm
+fn installer_command(method: &str, script: Option<&Path>) -> Result<Command, String> {
+    if method == "npm" {
+        // npm is itself a shim on Windows, so resolve it the same way as claude
+        let npm = which("npm").ok_or_else(|| "npm not found in system PATH".to_string())?;
+        let mut command = ClaudeLauncher::resolve(Path::new(&npm)).command();
+        command.args(["install", "-g", &format!("{}@latest", CLAUDE_CODE_NPM_PACKAGE)]);
+        return Ok(command);
+    }
+
+    let script = script.ok_or_else(|| "The installer script wasn't downloaded".to_string())?;
+
+    #[cfg(target_os = "windows")]
+    {
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-File"]).arg(script);
+        Ok(command)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let mut command = Command::new("bash");
+        command.arg(script);
+        Ok(command)
+    }
+}
+
+fn run_installer(app: &AppHandle, method: &str, script: Option<&Path>) -> Result<(), String> {
+    let mut child = installer_command(method, script)?
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start installer: {}", e))?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let stdout_app = app.clone();
+    let stdout_thread = std::thread::spawn(move || {
+        if let Some(stdout) = stdout {
+            emit_lines(&stdout_app, "stdout", stdout);
+        }
+    });
+    let stderr_app = app.clone();
+    let stderr_thread = std::thread::spawn(move || {
+        if let Some(stderr) = stderr {
+            emit_lines(&stderr_app, "stderr", stderr);
+        }
+    });
+
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to wait for installer: {}", e))?;
+    let _ = stdout_thread.join();
+    let _ = stderr_thread.join();
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Installer exited with {}", status))
+    }
+}
+
+fn emit_lines(app: &AppHandle, stream: &str, reader: impl std::io::Read) {
+    for line in BufReader::new(reader).lines().map_while(Result::ok) {
+        let _ = app.emit(
+            "claude-install-progress",
+            InstallProgress {
+                stream: stream.to_string(),
+                line,
+            },
+        );
+    }
+}
+
+// The GUI process often doesn't inherit the login shell PATH, so also look
+// where each installer puts the executable
+fn locate_installed_claude(method: &str) -> Option<String> {
+    if let Some(path) = which("claude") {
+        return Some(path);
+    }
+
+    let candidates: Vec<PathBuf> = if method == "npm" {
+        let npm = which("npm")?;
+        let output = ClaudeLauncher::resolve(Path::new(&npm))
+            .command()
+            .args(["prefix", "-g"])
+            .output()
+            .ok()?;
+        let prefix = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+        if cfg!(target_os = "windows") {
+            vec![prefix.join("claude.cmd"), prefix.join("claude.ps1")]
+        } else {
+            vec![prefix.join("bin").join("claude")]
+        }
+    } else {
+        let bin = paths::home_dir()?.join(".local").join("bin");
+        vec![bin.join("claude"), bin.join("claude.exe")]
+    };
+
+    candidates
+        .into_iter()
+        .find(|candidate| candidate.exists())
+        .map(|candidate| candidate.to_string_lossy().to_string())
+}
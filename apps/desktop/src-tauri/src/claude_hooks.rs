@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::AppHandle;
+
+use crate::claude_settings;
+
+// `hooks` in settings.json: event name -> matchers, each with the commands
+// to run when a tool name matches
+pub type HooksConfig = BTreeMap<String, Vec<HookMatcher>>;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct HookMatcher {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matcher: Option<String>,
+    #[serde(default)]
+    pub hooks: Vec<HookCommand>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HookCommand {
+    #[serde(rename = "type")]
+    pub hook_type: String,
+    pub command: String,
+    // Seconds, Claude Code defaults to 60
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct HookDryRunResult {
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration_ms: u64,
+    pub timed_out: bool,
+    // Exit code 2 makes Claude Code block the tool call / prompt
+    pub blocking: bool,
+    // stdout parsed as JSON, when the hook returned structured output
+    pub json_output: Option<Value>,
+}
+
+pub const HOOK_EVENTS: &[&str] = &[
+    "PreToolUse",
+    "PostToolUse",
+    "Notification",
+    "UserPromptSubmit",
+    "Stop",
+    "SubagentStop",
+    "PreCompact",
+    "SessionStart",
+    "SessionEnd",
+];
+
+const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 60;
+
+fn validate_hooks(hooks: &HooksConfig) -> Result<(), String> {
+    for (event, matchers) in hooks {
+        if !HOOK_EVENTS.contains(&event.as_str()) {
+            return Err(format!("Unknown hook event: {}", event));
+        }
+        for matcher in matchers {
+            for hook in &matcher.hooks {
+                if hook.hook_type != "command" {
+                    return Err(format!("Unsupported hook type \"{}\" in {}", hook.hook_type, event));
+                }
+                if hook.command.trim().is_empty() {
+                    return Err(format!("A {} hook has an empty command", event));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_claude_hooks(
+    scope: Option<String>,
+    project_path: Option<String>,
+) -> Result<HooksConfig, String> {
+    let scope = scope.unwrap_or_else(|| "user".to_string());
+    let path = claude_settings::settings_path(&scope, project_path.as_deref())?;
+    let settings = claude_settings::load_settings(&path)?;
+
+    match settings.hooks {
+        Some(hooks) => serde_json::from_value(hooks).map_err(|e| format!("Invalid hooks configuration: {}", e)),
+        None => Ok(HooksConfig::new()),
+    }
+}
+
+#[tauri::command]
+pub fn set_claude_hooks(
+    app: AppHandle,
+    hooks: HooksConfig,
+    scope: Option<String>,
+    project_path: Option<String>,
+) -> Result<(), String> {
+    validate_hooks(&hooks)?;
+
+    let scope = scope.unwrap_or_else(|| "user".to_string());
+    let path = claude_settings::settings_path(&scope, project_path.as_deref())?;
+    let mut settings = claude_settings::load_settings(&path)?;
+
+    settings.hooks = if hooks.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_value(&hooks).map_err(|e| e.to_string())?)
+    };
+    claude_settings::save_settings(&app, &scope, &path, &settings)
+}
+
+// Representative stdin payload for an event, matching what Claude Code sends
+pub fn sample_hook_input(event: &str, cwd: &str) -> Value {
+    let mut input = json!({
+        "session_id": "00000000-0000-0000-0000-000000000000",
+        "transcript_path": "",
+        "cwd": cwd,
+        "hook_event_name": event,
+    });
+    let extra = match event {
+        "PreToolUse" => json!({
+            "tool_name": "Bash",
+            "tool_input": { "command": "echo hello", "description": "Print hello" },
+        }),
+        "PostToolUse" => json!({
+            "tool_name": "Bash",
+            "tool_input": { "command": "echo hello", "description": "Print hello" },
+            "tool_response": { "stdout": "hello\n", "stderr": "", "interrupted": false },
+        }),
+        "Notification" => json!({ "message": "Claude needs your permission to use Bash" }),
+        "UserPromptSubmit" => json!({ "prompt": "Write a function that adds two numbers" }),
+        "Stop" | "SubagentStop" => json!({ "stop_hook_active": false }),
+        "PreCompact" => json!({ "trigger": "manual", "custom_instructions": "" }),
+        "SessionStart" => json!({ "source": "startup" }),
+        "SessionEnd" => json!({ "reason": "exit" }),
+        _ => json!({}),
+    };
+    if let (Some(input), Some(extra)) = (input.as_object_mut(), extra.as_object()) {
+        input.extend(extra.clone());
+    }
+    input
+}
+
+fn shell_command(command: &str) -> Command {
+    #[cfg(target_os = "windows")]
+    {
+        let mut shell = Command::new("cmd");
+        shell.args(["/C", command]);
+        shell
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let mut shell = Command::new("sh");
+        shell.args(["-c", command]);
+        shell
+    }
+}
+
+// Run a hook command the way Claude Code would: through the shell, in the
+// project directory, with the event payload as JSON on stdin
+#[tauri::command]
+pub async fn dry_run_hook(
+    command: String,
+    event: String,
+    input: Option<Value>,
+    cwd: Option<String>,
+    timeout: Option<u64>,
+) -> Result<HookDryRunResult, String> {
+    if !HOOK_EVENTS.contains(&event.as_str()) {
+        return Err(format!("Unknown hook event: {}", event));
+    }
+
+    let cwd = match cwd {
+        Some(cwd) => cwd,
+        None => std::env::current_dir()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .map_err(|e| e.to_string())?,
+    };
+    let input = input.unwrap_or_else(|| sample_hook_input(&event, &cwd));
+    let timeout = Duration::from_secs(timeout.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS));
+
+    tauri::async_runtime::spawn_blocking(move || run_hook(&command, &input, &cwd, timeout))
+        .await
+        .map_err(|e| format!("Hook task failed: {}", e))?
+}
+
+fn run_hook(command: &str, input: &Value, cwd: &str, timeout: Duration) -> Result<HookDryRunResult, String> {
+    let started = Instant::now();
+    let mut child = shell_command(command)
+        .current_dir(cwd)
+        .env("CLAUDE_PROJECT_DIR", cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run hook: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that never reads stdin closes the pipe early; that's fine
+        let _ = stdin.write_all(input.to_string().as_bytes());
+    }
+
+    let read_pipe = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut output = String::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_string(&mut output);
+            }
+            output
+        })
+    };
+    let stdout = read_pipe(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr = read_pipe(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    let mut timed_out = false;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) if started.elapsed() > timeout => {
+                timed_out = true;
+                let _ = child.kill();
+                break child.wait().ok();
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(format!("Failed to wait for hook: {}", e)),
+        }
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    let exit_code = if timed_out { None } else { status.and_then(|s| s.code()) };
+
+    Ok(HookDryRunResult {
+        exit_code,
+        json_output: serde_json::from_str(stdout.trim()).ok().filter(Value::is_object),
+        stdout,
+        stderr,
+        duration_ms: started.elapsed().as_millis() as u64,
+        timed_out,
+        blocking: exit_code == Some(2),
+    })
+}
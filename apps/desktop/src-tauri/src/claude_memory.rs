@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Emitter};
+
+use crate::{fs_util, paths};
+
+#[derive(Clone, serde::Serialize)]
+pub struct MemoryFile {
+    pub path: String,
+    // "enterprise", "user", "project", "local" or "parent"
+    pub scope: String,
+    // Files are listed in load order; a higher precedence wins on conflicts
+    pub precedence: usize,
+    pub exists: bool,
+    pub size: u64,
+    pub modified: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct MemoryFileContents {
+    pub path: String,
+    pub content: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct MemoryFileChanged {
+    path: String,
+}
+
+const MEMORY_FILE_NAMES: &[&str] = &["CLAUDE.md", "CLAUDE.local.md"];
+
+fn enterprise_memory_path() -> PathBuf {
+    #[cfg(target_os = "macos")]
+    return PathBuf::from("/Library/Application Support/ClaudeCode/CLAUDE.md");
+
+    #[cfg(target_os = "windows")]
+    return PathBuf::from(r"C:\ProgramData\ClaudeCode\CLAUDE.md");
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    return PathBuf::from("/etc/claude-code/CLAUDE.md");
+}
+
+struct Candidate {
+    path: PathBuf,
+    scope: &'static str,
+    // Offered to the UI even when missing, so it can be created
+    creatable: bool,
+}
+
+// Candidate memory files for a project, in the order Claude Code loads them:
+// enterprise policy, the user's global file, then every directory from the
+// filesystem root down to the project (CLAUDE.md, .claude/CLAUDE.md and
+// CLAUDE.local.md). Files closer to the project take precedence.
+fn candidate_paths(project_path: Option<&str>) -> Result<Vec<Candidate>, String> {
+    let mut candidates = vec![
+        Candidate { path: enterprise_memory_path(), scope: "enterprise", creatable: false },
+        Candidate { path: paths::claude_dir()?.join("CLAUDE.md"), scope: "user", creatable: true },
+    ];
+
+    if let Some(project) = project_path.filter(|p| !p.is_empty()) {
+        let project = Path::new(project)
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", project, e))?;
+        let mut ancestors: Vec<&Path> = project.ancestors().collect();
+        ancestors.reverse();
+
+        for dir in ancestors {
+            let is_project = dir == project;
+            let (scope, local_scope) = if is_project { ("project", "local") } else { ("parent", "parent") };
+            candidates.push(Candidate { path: dir.join("CLAUDE.md"), scope, creatable: is_project });
+            candidates.push(Candidate { path: dir.join(".claude").join("CLAUDE.md"), scope, creatable: false });
+            candidates.push(Candidate { path: dir.join("CLAUDE.local.md"), scope: local_scope, creatable: is_project });
+        }
+    }
+
+    Ok(candidates)
+}
+
+fn describe(path: PathBuf, scope: &str, precedence: usize) -> MemoryFile {
+    let metadata = std::fs::metadata(&path).ok().filter(|m| m.is_file());
+    MemoryFile {
+        path: path.to_string_lossy().to_string(),
+        scope: scope.to_string(),
+        precedence,
+        exists: metadata.is_some(),
+        size: metadata.as_ref().map(|m| m.len()).unwrap_or_default(),
+        modified: metadata
+            .and_then(|m| m.modified().ok())
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+    }
+}
+
+// List memory files relevant to a project in load order. Missing files are
+// only included where the UI offers to create them.
+#[tauri::command]
+pub fn list_memory_files(project_path: Option<String>) -> Result<Vec<MemoryFile>, String> {
+    let files = candidate_paths(project_path.as_deref())?
+        .into_iter()
+        .enumerate()
+        .map(|(precedence, candidate)| (candidate.creatable, describe(candidate.path, candidate.scope, precedence)))
+        .filter(|(creatable, file)| file.exists || *creatable)
+        .map(|(_, file)| file)
+        .collect();
+    Ok(files)
+}
+
+// Only memory files in the project's discovery set can be read or written
+// through these commands; the enterprise policy file is read-only
+fn resolve_memory_path(path: &str, project_path: Option<&str>, writable: bool) -> Result<MemoryFile, String> {
+    let path = PathBuf::from(path);
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    if !MEMORY_FILE_NAMES.contains(&file_name) {
+        return Err(format!("Not a memory file: {}", path.display()));
+    }
+
+    let (precedence, candidate) = candidate_paths(project_path)?
+        .into_iter()
+        .enumerate()
+        .find(|(_, candidate)| candidate.path == path)
+        .ok_or_else(|| format!("{} is outside the memory files for this project", path.display()))?;
+    if writable && candidate.scope == "enterprise" {
+        return Err("The enterprise memory file is managed by your organization".to_string());
+    }
+    Ok(describe(candidate.path, candidate.scope, precedence))
+}
+
+#[tauri::command]
+pub fn read_memory_file(path: String, project_path: Option<String>) -> Result<MemoryFileContents, String> {
+    let file = resolve_memory_path(&path, project_path.as_deref(), false)?;
+    let content = if file.exists {
+        std::fs::read_to_string(&file.path).map_err(|e| format!("Failed to read {}: {}", file.path, e))?
+    } else {
+        String::new()
+    };
+    Ok(MemoryFileContents { path: file.path, content })
+}
+
+#[tauri::command]
+pub fn write_memory_file(
+    app: AppHandle,
+    path: String,
+    content: String,
+    project_path: Option<String>,
+) -> Result<MemoryFile, String> {
+    let file = resolve_memory_path(&path, project_path.as_deref(), true)?;
+    let resolved = PathBuf::from(&file.path);
+    fs_util::write_atomic(&resolved, content.as_bytes(), true)?;
+
+    let _ = app.emit("claude-memory-changed", MemoryFileChanged { path: file.path.clone() });
+    Ok(describe(resolved, &file.scope, file.precedence))
+}
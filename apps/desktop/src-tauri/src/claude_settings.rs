@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::{fs_util, paths};
+
+// Typed view of a Claude Code settings.json. Keys Incito doesn't model are
+// kept in `extra` so round-tripping never drops the user's configuration.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaudeSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<PermissionSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_helper: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cleanup_period_days: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_co_authored_by: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enable_all_project_mcp_servers: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled_mcpjson_servers: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disabled_mcpjson_servers: Option<Vec<String>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionSettings {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ask: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_directories: Vec<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct ClaudeSettingsFile {
+    pub scope: String,
+    pub path: String,
+    pub exists: bool,
+    pub settings: ClaudeSettings,
+}
+
+#[derive(Clone, Serialize)]
+struct SettingsChanged {
+    scope: String,
+    path: String,
+}
+
+const PERMISSION_MODES: &[&str] = &["default", "acceptEdits", "plan", "bypassPermissions"];
+
+// Resolve the settings file for a scope: "user" (~/.claude/settings.json),
+// "project" (.claude/settings.json) or "local" (.claude/settings.local.json)
+pub fn settings_path(scope: &str, project_path: Option<&str>) -> Result<PathBuf, String> {
+    let project_dir = || {
+        project_path
+            .filter(|path| !path.is_empty())
+            .map(|path| Path::new(path).join(".claude"))
+            .ok_or_else(|| format!("A project path is required for {} settings", scope))
+    };
+
+    match scope {
+        "user" => Ok(paths::claude_dir()?.join("settings.json")),
+        "project" => Ok(project_dir()?.join("settings.json")),
+        "local" => Ok(project_dir()?.join("settings.local.json")),
+        other => Err(format!("Unknown settings scope: {}", other)),
+    }
+}
+
+pub fn load_settings(path: &Path) -> Result<ClaudeSettings, String> {
+    if !path.exists() {
+        return Ok(ClaudeSettings::default());
+    }
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if contents.trim().is_empty() {
+        return Ok(ClaudeSettings::default());
+    }
+    serde_json::from_str(&contents).map_err(|e| format!("Invalid settings in {}: {}", path.display(), e))
+}
+
+pub fn validate_settings(settings: &ClaudeSettings) -> Result<(), String> {
+    let mut errors = Vec::new();
+
+    if let Some(permissions) = &settings.permissions {
+        if let Some(mode) = &permissions.default_mode {
+            if !PERMISSION_MODES.contains(&mode.as_str()) {
+                errors.push(format!(
+                    "permissions.defaultMode must be one of {}",
+                    PERMISSION_MODES.join(", ")
+                ));
+            }
+        }
+        for (list, rules) in [
+            ("allow", &permissions.allow),
+            ("deny", &permissions.deny),
+            ("ask", &permissions.ask),
+        ] {
+            for rule in rules {
+                if let Err(reason) = validate_permission_rule(rule) {
+                    errors.push(format!("permissions.{} rule \"{}\": {}", list, rule, reason));
+                }
+            }
+        }
+    }
+
+    if let Some(env) = &settings.env {
+        for key in env.keys() {
+            if key.is_empty() || key.contains('=') {
+                errors.push(format!("env has an invalid variable name \"{}\"", key));
+            }
+        }
+    }
+
+    if settings.hooks.as_ref().is_some_and(|hooks| !hooks.is_object()) {
+        errors.push("hooks must be an object keyed by event name".to_string());
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+// Rules look like `Bash`, `Bash(npm run test:*)` or `Read(./secrets/**)`
+fn validate_permission_rule(rule: &str) -> Result<(), &'static str> {
+    let rule = rule.trim();
+    let tool = match rule.find('(') {
+        Some(open) => {
+            if !rule.ends_with(')') {
+                return Err("missing closing parenthesis");
+            }
+            &rule[..open]
+        }
+        None => rule,
+    };
+
+    if tool.is_empty() {
+        return Err("tool name is empty");
+    }
+    if !tool.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err("tool name contains invalid characters");
+    }
+    Ok(())
+}
+
+pub fn save_settings(app: &AppHandle, scope: &str, path: &Path, settings: &ClaudeSettings) -> Result<(), String> {
+    validate_settings(settings)?;
+
+    let mut contents = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    contents.push('\n');
+    fs_util::write_atomic(path, contents.as_bytes(), true)?;
+
+    let _ = app.emit(
+        "claude-settings-changed",
+        SettingsChanged {
+            scope: scope.to_string(),
+            path: path.to_string_lossy().to_string(),
+        },
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn read_claude_settings(
+    scope: Option<String>,
+    project_path: Option<String>,
+) -> Result<ClaudeSettingsFile, String> {
+    let scope = scope.unwrap_or_else(|| "user".to_string());
+    let path = settings_path(&scope, project_path.as_deref())?;
+
+    Ok(ClaudeSettingsFile {
+        settings: load_settings(&path)?,
+        exists: path.exists(),
+        path: path.to_string_lossy().to_string(),
+        scope,
+    })
+}
+
+#[tauri::command]
+pub fn write_claude_settings(
+    app: AppHandle,
+    settings: ClaudeSettings,
+    scope: Option<String>,
+    project_path: Option<String>,
+) -> Result<ClaudeSettingsFile, String> {
+    let scope = scope.unwrap_or_else(|| "user".to_string());
+    let path = settings_path(&scope, project_path.as_deref())?;
+    save_settings(&app, &scope, &path, &settings)?;
+
+    Ok(ClaudeSettingsFile {
+        settings,
+        exists: true,
+        path: path.to_string_lossy().to_string(),
+        scope,
+    })
+}
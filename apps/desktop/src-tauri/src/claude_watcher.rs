@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::paths;
+
+// Keeps the watcher alive for the lifetime of the app
+pub struct ClaudeWatcher {
+    _debouncer: Mutex<Debouncer<RecommendedWatcher>>,
+}
+
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(300);
+
+// A change made outside Incito, typically by the CLI running in a terminal.
+// The payloads match what Incito emits for its own writes.
+#[derive(PartialEq, Eq, Hash)]
+enum ClaudeChange {
+    Settings { path: String },
+    SessionAppended { session_id: String, path: String },
+    McpConfig { scope: &'static str },
+    Commands,
+    Agents,
+    Memory { path: String },
+}
+
+#[derive(Clone, serde::Serialize)]
+struct SettingsChanged {
+    scope: String,
+    path: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct SessionAppended {
+    session_id: String,
+    path: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ScopeChanged {
+    scope: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct PathChanged {
+    path: String,
+}
+
+fn classify(claude_dir: &Path, path: &Path) -> Option<ClaudeChange> {
+    let relative = path.strip_prefix(claude_dir).ok()?;
+    let components: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    let file_name = components.last()?.as_str();
+    // Atomic writes leave temp files behind for a moment
+    if file_name.starts_with('.') || file_name.ends_with(".tmp") {
+        return None;
+    }
+    let path_string = path.to_string_lossy().to_string();
+
+    match components.first()?.as_str() {
+        "settings.json" | "settings.local.json" if components.len() == 1 => {
+            Some(ClaudeChange::Settings { path: path_string })
+        }
+        "CLAUDE.md" if components.len() == 1 => Some(ClaudeChange::Memory { path: path_string }),
+        "projects" if components.len() == 3 => file_name
+            .strip_suffix(".jsonl")
+            .map(|session_id| ClaudeChange::SessionAppended {
+                session_id: session_id.to_string(),
+                path: path_string,
+            }),
+        "commands" if file_name.ends_with(".md") => Some(ClaudeChange::Commands),
+        "agents" if file_name.ends_with(".md") => Some(ClaudeChange::Agents),
+        _ => None,
+    }
+}
+
+// The CLI rewrites `~/.claude.json` constantly (startup counters, tips), so
+// MCP changes are only reported when the server maps themselves differ
+#[derive(Default)]
+struct McpSnapshot {
+    user: String,
+    local: String,
+}
+
+fn mcp_snapshot(state_file: &Path) -> Option<McpSnapshot> {
+    let contents = std::fs::read_to_string(state_file).ok()?;
+    let state: Value = serde_json::from_str(&contents).ok()?;
+    let local: Vec<(&String, &Value)> = state
+        .get("projects")
+        .and_then(Value::as_object)
+        .map(|projects| {
+            projects
+                .iter()
+                .filter_map(|(project, entry)| entry.get("mcpServers").map(|servers| (project, servers)))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(McpSnapshot {
+        user: state.get("mcpServers").map(Value::to_string).unwrap_or_default(),
+        local: serde_json::to_string(&local).unwrap_or_default(),
+    })
+}
+
+fn emit_change(app: &AppHandle, change: ClaudeChange) {
+    let _ = match change {
+        ClaudeChange::Settings { path } => app.emit(
+            "claude-settings-changed",
+            SettingsChanged {
+                scope: "user".to_string(),
+                path,
+            },
+        ),
+        ClaudeChange::SessionAppended { session_id, path } => {
+            app.emit("claude-session-appended", SessionAppended { session_id, path })
+        }
+        ClaudeChange::McpConfig { scope } => app.emit("claude-mcp-config-changed", scope),
+        ClaudeChange::Commands => app.emit(
+            "claude-commands-changed",
+            ScopeChanged {
+                scope: "user".to_string(),
+            },
+        ),
+        ClaudeChange::Agents => app.emit(
+            "claude-agents-changed",
+            ScopeChanged {
+                scope: "user".to_string(),
+            },
+        ),
+        ClaudeChange::Memory { path } => app.emit("claude-memory-changed", PathChanged { path }),
+    };
+}
+
+// Watch `~/.claude` and `~/.claude.json` and re-emit changes as the typed
+// events the UI already listens to
+pub fn start(app: &AppHandle) -> Result<(), String> {
+    let claude_dir = paths::claude_dir()?;
+    let state_file = paths::claude_state_file()?;
+    let mut mcp = mcp_snapshot(&state_file).unwrap_or_default();
+
+    let handle = app.clone();
+    let watched_dir = claude_dir.clone();
+    let watched_state = state_file.clone();
+    let mut debouncer = new_debouncer(DEBOUNCE_INTERVAL, move |result: DebounceEventResult| {
+        let Ok(events) = result else { return };
+
+        let mut changes = HashSet::new();
+        for event in &events {
+            if event.path == watched_state {
+                if let Some(snapshot) = mcp_snapshot(&watched_state) {
+                    if snapshot.user != mcp.user {
+                        changes.insert(ClaudeChange::McpConfig { scope: "user" });
+                    }
+                    if snapshot.local != mcp.local {
+                        changes.insert(ClaudeChange::McpConfig { scope: "local" });
+                    }
+                    mcp = snapshot;
+                }
+            } else if let Some(change) = classify(&watched_dir, &event.path) {
+                changes.insert(change);
+            }
+        }
+        for change in changes {
+            emit_change(&handle, change);
+        }
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+
+    let watcher = debouncer.watcher();
+    if claude_dir.is_dir() {
+        watcher
+            .watch(&claude_dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {}: {}", claude_dir.display(), e))?;
+    }
+    // The state file is replaced rather than edited in place, so its
+    // directory is watched instead of the file itself
+    let state_dir = state_file.parent().map(PathBuf::from).unwrap_or_default();
+    if state_dir.is_dir() && !state_dir.starts_with(&claude_dir) {
+        watcher
+            .watch(&state_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {}: {}", state_dir.display(), e))?;
+    }
+
+    app.manage(ClaudeWatcher {
+        _debouncer: Mutex::new(debouncer),
+    });
+    Ok(())
+}
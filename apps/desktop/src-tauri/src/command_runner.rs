@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::policy::{self, PolicyError};
+use crate::project_info::{self, ProjectScript};
+use crate::scrollback::{Scrollback, ScrollbackChunk};
+use crate::sandbox::{self, SandboxProfile};
+use crate::{audit, db, process_tree, shell_env};
+
+const DEFAULT_HISTORY_LIMIT: i64 = 50;
+
+struct RunningCommand {
+    pid: u32,
+    cancelled: Arc<AtomicBool>,
+    info: RunningCommandInfo,
+    output: Arc<Mutex<Scrollback>>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RunningCommandInfo {
+    pub run_id: String,
+    pub project_path: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub started_at: String,
+}
+
+// Commands still running, by run id
+#[derive(Default)]
+pub struct CommandRuns(Mutex<HashMap<String, RunningCommand>>);
+
+#[derive(Clone, serde::Serialize)]
+struct CommandOutput {
+    run_id: String,
+    // "stdout" or "stderr"
+    stream: String,
+    line: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct CommandRun {
+    pub run_id: String,
+    pub project_path: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub started_at: String,
+    // None when it couldn't be waited on or was killed by a signal
+    pub exit_code: Option<i32>,
+    pub duration_ms: i64,
+    pub cancelled: bool,
+}
+
+type CommandRunRow = (String, String, String, String, String, Option<i32>, i64, bool);
+
+fn from_row(
+    (run_id, project_path, command, args, started_at, exit_code, duration_ms, cancelled): CommandRunRow,
+) -> CommandRun {
+    CommandRun {
+        run_id,
+        project_path,
+        command,
+        args: serde_json::from_str(&args).unwrap_or_default(),
+        started_at,
+        exit_code,
+        duration_ms,
+        cancelled,
+    }
+}
+
+async fn record(app: &AppHandle, run: &CommandRun) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    let args = serde_json::to_string(&run.args).map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO command_runs (id, project_path, command, args, started_at, exit_code, duration_ms, cancelled)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&run.run_id)
+    .bind(&run.project_path)
+    .bind(&run.command)
+    .bind(&args)
+    .bind(&run.started_at)
+    .bind(run.exit_code)
+    .bind(run.duration_ms)
+    .bind(run.cancelled)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("Failed to record command run: {}", e))?;
+    Ok(())
+}
+
+fn emit_lines(
+    app: &AppHandle,
+    run_id: &str,
+    stream: &str,
+    output: &Mutex<Scrollback>,
+    reader: impl std::io::Read,
+) {
+    for line in BufReader::new(reader).lines().map_while(Result::ok) {
+        if let Ok(mut output) = output.lock() {
+            output.push_line(&line);
+        }
+        let _ = app.emit(
+            "command-output",
+            CommandOutput {
+                run_id: run_id.to_string(),
+                stream: stream.to_string(),
+                line,
+            },
+        );
+    }
+}
+
+fn spawn(
+    project_path: &str,
+    cwd: &Path,
+    command: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+    sandbox: Option<&SandboxProfile>,
+) -> Result<std::process::Child, String> {
+    let argv = match sandbox {
+        Some(profile) => sandbox::wrap(profile, project_path, command, args)?,
+        None => std::iter::once(command.to_string()).chain(args.iter().cloned()).collect(),
+    };
+    let mut process = Command::new(&argv[0]);
+    process
+        .args(&argv[1..])
+        .current_dir(cwd)
+        .env_clear()
+        .envs(shell_env::login_env())
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(not(windows))]
+    {
+        use std::os::unix::process::CommandExt;
+        process.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        process.creation_flags(CREATE_NO_WINDOW);
+    }
+    let mut child = process.spawn().map_err(|e| format!("Failed to run {}: {}", command, e))?;
+    if let Some(profile) = sandbox {
+        if let Err(e) = sandbox::confine(&child, profile) {
+            let _ = child.kill();
+            return Err(e);
+        }
+    }
+    Ok(child)
+}
+
+// Start `command` and stream it, returning its run id. `origin` is the
+// window that asked, for the audit log.
+pub async fn start(
+    app: &AppHandle,
+    origin: &str,
+    project_path: String,
+    cwd: Option<String>,
+    command: String,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+) -> Result<String, PolicyError> {
+    if !Path::new(&project_path).is_dir() {
+        return Err(format!("Project folder not found: {}", project_path).into());
+    }
+    if command.trim().is_empty() {
+        return Err("A command is required".to_string().into());
+    }
+    // Relative to the project, and inside its allowed folders
+    let cwd = match cwd.filter(|cwd| !cwd.trim().is_empty()) {
+        Some(cwd) => Path::new(&project_path).join(cwd),
+        None => PathBuf::from(&project_path),
+    };
+    if !cwd.is_dir() {
+        return Err(format!("Folder not found: {}", cwd.display()).into());
+    }
+    policy::check(app, &project_path, &cwd).await?;
+    let sandbox = sandbox::profile(app, &project_path).await?;
+
+    let spawn_args = (project_path.clone(), command.clone(), args.clone());
+    let mut child = tauri::async_runtime::spawn_blocking(move || {
+        let (project_path, command, args) = spawn_args;
+        spawn(&project_path, &cwd, &command, &args, &env, sandbox.as_ref())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let command_line = std::iter::once(&command).chain(&args).cloned().collect::<Vec<_>>().join(" ");
+    audit::record(app, Some(origin), "command", "run", &command_line);
+
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let started_at = chrono::Utc::now().to_rfc3339();
+    let started = Instant::now();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let output = Arc::new(Mutex::new(Scrollback::default()));
+    app.state::<CommandRuns>().0.lock().map_err(|e| e.to_string())?.insert(
+        run_id.clone(),
+        RunningCommand {
+            pid: child.id(),
+            cancelled: cancelled.clone(),
+            info: RunningCommandInfo {
+                run_id: run_id.clone(),
+                project_path: project_path.clone(),
+                command: command.clone(),
+                args: args.clone(),
+                started_at: started_at.clone(),
+            },
+            output: output.clone(),
+        },
+    );
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let (stdout_app, stdout_id, stdout_output) = (app.clone(), run_id.clone(), output.clone());
+    let stdout_thread = std::thread::spawn(move || {
+        if let Some(stdout) = stdout {
+            emit_lines(&stdout_app, &stdout_id, "stdout", &stdout_output, stdout);
+        }
+    });
+    let (stderr_app, stderr_id) = (app.clone(), run_id.clone());
+    let stderr_thread = std::thread::spawn(move || {
+        if let Some(stderr) = stderr {
+            emit_lines(&stderr_app, &stderr_id, "stderr", &output, stderr);
+        }
+    });
+
+    let handle = app.clone();
+    let id = run_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let status = tauri::async_runtime::spawn_blocking(move || {
+            let status = child.wait().ok();
+            let _ = stdout_thread.join();
+            let _ = stderr_thread.join();
+            status
+        })
+        .await
+        .ok()
+        .flatten();
+        if let Ok(mut runs) = handle.state::<CommandRuns>().0.lock() {
+            runs.remove(&id);
+        }
+        let run = CommandRun {
+            run_id: id,
+            project_path,
+            command,
+            args,
+            started_at,
+            exit_code: status.and_then(|status| status.code()),
+            duration_ms: started.elapsed().as_millis() as i64,
+            cancelled: cancelled.load(Ordering::Relaxed),
+        };
+        if let Err(e) = record(&handle, &run).await {
+            eprintln!("{}", e);
+        }
+        let _ = handle.emit("command-finished", run);
+    });
+    Ok(run_id)
+}
+
+// Run a program in the project folder, or `cwd` in it, with the login
+// shell's environment plus `env`. Output comes as "command-output" lines and the end as
+// "command-finished"; the run id is returned right away and is what
+// `cancel_command` takes.
+#[tauri::command]
+pub async fn run_command(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    command: String,
+    args: Option<Vec<String>>,
+    env: Option<HashMap<String, String>>,
+    cwd: Option<String>,
+) -> Result<String, PolicyError> {
+    start(&app, window.label(), project_path, cwd, command, args.unwrap_or_default(), env.unwrap_or_default()).await
+}
+
+// package.json scripts, Makefile targets, cargo commands and the like, as
+// `detect_project_info` finds them
+#[tauri::command]
+pub async fn list_runnable_scripts(project_path: String) -> Result<Vec<ProjectScript>, String> {
+    let dir = PathBuf::from(&project_path);
+    if !dir.is_dir() {
+        return Err(format!("Project folder not found: {}", project_path));
+    }
+    tauri::async_runtime::spawn_blocking(move || project_info::scripts(&dir))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// "pnpm run dev" as a program and its arguments, keeping the script's name
+// whole even if it has spaces
+fn script_argv(script: &ProjectScript) -> Vec<String> {
+    let mut argv: Vec<String> = match script.run.strip_suffix(&script.name) {
+        Some(prefix) if prefix.ends_with(' ') => {
+            let mut argv: Vec<String> = prefix.split_whitespace().map(str::to_string).collect();
+            argv.push(script.name.clone());
+            argv
+        }
+        _ => script.run.split_whitespace().map(str::to_string).collect(),
+    };
+    // npm, pnpm and yarn are .cmd shims there, which only cmd can start
+    if cfg!(windows) {
+        argv.splice(0..0, ["cmd".to_string(), "/C".to_string()]);
+    }
+    argv
+}
+
+// Run one of `list_runnable_scripts` through the streaming runner. `source`
+// picks between same-named ones, e.g. "package.json" or "cargo" for "build".
+#[tauri::command]
+pub async fn run_script(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    name: String,
+    source: Option<String>,
+) -> Result<String, PolicyError> {
+    let scripts = list_runnable_scripts(project_path.clone()).await?;
+    let script = scripts
+        .iter()
+        .find(|script| script.name == name && source.as_deref().is_none_or(|source| script.source == source))
+        .ok_or_else(|| format!("Script not found: {}", name))?;
+    let mut argv = script_argv(script).into_iter();
+    let command = argv.next().ok_or_else(|| format!("Script not found: {}", name))?;
+    start(&app, window.label(), project_path, None, command, argv.collect(), HashMap::new()).await
+}
+
+// Stop a run and everything it started, asking first unless `force`.
+// "command-finished" follows with `cancelled` set.
+#[tauri::command]
+pub fn cancel_command(app: AppHandle, run_id: String, force: Option<bool>) -> Result<(), String> {
+    let state = app.state::<CommandRuns>();
+    let runs = state.0.lock().map_err(|e| e.to_string())?;
+    let run = runs.get(&run_id).ok_or_else(|| format!("Command not running: {}", run_id))?;
+    run.cancelled.store(true, Ordering::Relaxed);
+    process_tree::kill_tree(run.pid, force.unwrap_or(false))
+}
+
+// Past runs in a project, newest first
+#[tauri::command]
+pub async fn list_command_runs(
+    app: AppHandle,
+    project_path: String,
+    limit: Option<i64>,
+) -> Result<Vec<CommandRun>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<CommandRunRow> = sqlx::query_as(
+        "SELECT id, project_path, command, args, started_at, exit_code, duration_ms, cancelled
+         FROM command_runs WHERE project_path = ? ORDER BY started_at DESC LIMIT ?",
+    )
+    .bind(&project_path)
+    .bind(limit.unwrap_or(DEFAULT_HISTORY_LIMIT))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to list command runs: {}", e))?;
+    Ok(rows.into_iter().map(from_row).collect())
+}
+
+// Output of a run that's still going, stdout and stderr interleaved as
+// they came
+pub fn output_since(app: &AppHandle, run_id: &str, from_line: usize) -> Option<ScrollbackChunk> {
+    let output = app.state::<CommandRuns>().0.lock().ok()?.get(run_id)?.output.clone();
+    let chunk = output.lock().ok()?.since(from_line);
+    Some(chunk)
+}
+
+// Runs still going in a project, so a window opened on it can show them
+// again with `get_terminal_buffer`
+#[tauri::command]
+pub fn list_running_commands(app: AppHandle, project_path: String) -> Result<Vec<RunningCommandInfo>, String> {
+    let state = app.state::<CommandRuns>();
+    let runs = state.0.lock().map_err(|e| e.to_string())?;
+    let mut running: Vec<RunningCommandInfo> = runs
+        .values()
+        .filter(|run| run.info.project_path == project_path)
+        .map(|run| run.info.clone())
+        .collect();
+    running.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    Ok(running)
+}
+
+// On quit, so no dev servers outlive the app
+pub fn kill_all(app: &AppHandle) {
+    let pids: Vec<u32> = match app.state::<CommandRuns>().0.lock() {
+        Ok(runs) => runs.values().map(|run| run.pid).collect(),
+        Err(_) => return,
+    };
+    for pid in pids {
+        let _ = process_tree::kill_tree(pid, true);
+    }
+}
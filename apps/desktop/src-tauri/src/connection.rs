@@ -0,0 +1,369 @@
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager, Url, WebviewWindow};
+
+use crate::{app_lock, audit, secrets, server, settings, sidecar_bridge};
+
+// "local" spawns the bundled sidecar; "remote" connects to a
+// claude-code-server running elsewhere
+pub const MODES: &[&str] = &["local", "remote"];
+pub const LOCAL_URL: &str = "http://127.0.0.1:3457";
+// Sent as a bearer token to a remote server, which has it as
+// CLAUDE_CODE_SERVER_TOKEN
+pub const TOKEN_SECRET: &str = "server:remote-token";
+const DEFAULT_REMOTE_PORT: u16 = 3457;
+const DEFAULT_TUNNEL_PORT: u16 = 3459;
+// How long a new connection (and its tunnel) gets to answer a health check
+const CONNECT_ATTEMPTS: u32 = 20;
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(500);
+const TUNNEL_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ConnectionSettings {
+    // One of MODES
+    pub mode: String,
+    // e.g. "https://buildbox.example.com:3457". Not used with a tunnel.
+    pub remote_url: Option<String>,
+    // Reach the server through `ssh -L` instead of over the network
+    pub tunnel: Option<SshTunnel>,
+}
+
+impl Default for ConnectionSettings {
+    fn default() -> Self {
+        Self {
+            mode: MODES[0].to_string(),
+            remote_url: None,
+            tunnel: None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SshTunnel {
+    // What `ssh` is given: "user@host", or a Host from ~/.ssh/config
+    pub host: String,
+    pub ssh_port: Option<u16>,
+    pub identity_file: Option<String>,
+    // The server's port on the remote machine, and the one forwarded to
+    // it here
+    pub remote_port: u16,
+    pub local_port: u16,
+}
+
+impl Default for SshTunnel {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            ssh_port: None,
+            identity_file: None,
+            remote_port: DEFAULT_REMOTE_PORT,
+            local_port: DEFAULT_TUNNEL_PORT,
+        }
+    }
+}
+
+// Where requests to the server go, and with what token
+#[derive(Clone)]
+pub struct Endpoint {
+    pub base_url: String,
+    pub token: Option<String>,
+    // A remote reached directly rather than through a tunnel
+    pub over_network: bool,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ConnectionStatus {
+    pub mode: String,
+    pub connected: bool,
+    // The URL requests go to: the remote, or the tunnel's end here
+    pub url: String,
+    pub tunnel_pid: Option<u32>,
+    pub has_token: bool,
+}
+
+#[derive(Default)]
+struct Remote {
+    connected: bool,
+    token: Option<String>,
+    tunnel: Option<Child>,
+    // Relays the server's approval requests while connected
+    approvals: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+pub struct RemoteConnection(Mutex<Remote>);
+
+fn is_loopback(url: &Url) -> bool {
+    matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"))
+}
+
+pub fn validate(config: &ConnectionSettings) -> Result<(), String> {
+    if !MODES.contains(&config.mode.as_str()) {
+        return Err(format!("Unknown connection mode: {}", config.mode));
+    }
+    if let Some(tunnel) = &config.tunnel {
+        // Anything starting with "-" would be read by ssh as an option
+        if tunnel.host.trim().is_empty() || tunnel.host.starts_with('-') || tunnel.host.contains(char::is_whitespace) {
+            return Err(format!("Invalid SSH host: {}", tunnel.host));
+        }
+        if tunnel.local_port < 1024 || tunnel.remote_port == 0 {
+            return Err("Tunnel ports must be 1024 or above here, and set on the remote".to_string());
+        }
+    } else if config.mode == "remote" {
+        let remote_url = config.remote_url.as_deref().unwrap_or_default();
+        let url = Url::parse(remote_url).map_err(|e| format!("Invalid server URL {}: {}", remote_url, e))?;
+        // The token would otherwise cross the network in the clear
+        if url.scheme() != "https" && !(url.scheme() == "http" && is_loopback(&url)) {
+            return Err("A remote server must be reached over https, or through an SSH tunnel".to_string());
+        }
+    }
+    Ok(())
+}
+
+pub fn is_remote(app: &AppHandle) -> bool {
+    settings::get(app).server.connection.mode == "remote"
+}
+
+pub fn is_connected(app: &AppHandle) -> bool {
+    app.state::<RemoteConnection>()
+        .0
+        .lock()
+        .map(|remote| remote.connected)
+        .unwrap_or(false)
+}
+
+fn base_url(config: &ConnectionSettings) -> String {
+    match (&config.tunnel, &config.remote_url) {
+        _ if config.mode != "remote" => LOCAL_URL.to_string(),
+        (Some(tunnel), _) => format!("http://127.0.0.1:{}", tunnel.local_port),
+        (None, Some(url)) => url.trim_end_matches('/').to_string(),
+        (None, None) => LOCAL_URL.to_string(),
+    }
+}
+
+pub fn endpoint(app: &AppHandle) -> Endpoint {
+    let config = settings::get(app).server.connection;
+    let remote = config.mode == "remote";
+    let token = if remote {
+        app.state::<RemoteConnection>()
+            .0
+            .lock()
+            .ok()
+            .and_then(|remote| remote.token.clone())
+    } else {
+        None
+    };
+    Endpoint {
+        base_url: base_url(&config),
+        token,
+        over_network: remote && config.tunnel.is_none(),
+    }
+}
+
+fn start_tunnel(tunnel: &SshTunnel) -> Result<Child, String> {
+    let mut command = Command::new("ssh");
+    command
+        .args(["-N", "-o", "ExitOnForwardFailure=yes", "-o", "ServerAliveInterval=15"])
+        .args(["-o", "ServerAliveCountMax=3", "-o", "BatchMode=yes", "-L"])
+        .arg(format!("127.0.0.1:{}:127.0.0.1:{}", tunnel.local_port, tunnel.remote_port));
+    if let Some(port) = tunnel.ssh_port {
+        command.arg("-p").arg(port.to_string());
+    }
+    if let Some(identity) = tunnel.identity_file.as_deref().filter(|identity| !identity.trim().is_empty()) {
+        command.arg("-i").arg(identity);
+    }
+    command.arg("--").arg(&tunnel.host);
+    command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command
+        .spawn()
+        .map_err(|e| format!("Failed to start the SSH tunnel: {}", e))
+}
+
+fn stop_approvals(remote: &mut Remote) {
+    if let Some(task) = remote.approvals.take() {
+        task.abort();
+    }
+}
+
+fn stop_tunnel(remote: &mut Remote) {
+    if let Some(mut tunnel) = remote.tunnel.take() {
+        let _ = tunnel.kill();
+        let _ = tunnel.wait();
+    }
+}
+
+// Mark the connection lost if the tunnel's ssh exits, e.g. when the
+// network drops for longer than the keepalives allow
+fn watch_tunnel(app: &AppHandle, pid: u32) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(TUNNEL_CHECK_INTERVAL).await;
+            let lost = {
+                let state = app.state::<RemoteConnection>();
+                let Ok(mut remote) = state.0.lock() else { return };
+                match remote.tunnel.as_mut() {
+                    Some(tunnel) if tunnel.id() == pid => {
+                        let exited = tunnel.try_wait().map(|status| status.is_some()).unwrap_or(true);
+                        if exited {
+                            remote.tunnel = None;
+                            remote.connected = false;
+                        }
+                        exited
+                    }
+                    // Replaced or stopped
+                    _ => return,
+                }
+            };
+            if lost {
+                audit::record(&app, None, "sidecar", "tunnel-lost", &pid.to_string());
+                server::status_changed(&app);
+                return;
+            }
+        }
+    });
+}
+
+// Open the tunnel, if there is one, and wait for the server to answer.
+// Returns the tunnel's pid, or 0 without one.
+pub async fn connect(app: &AppHandle, origin: &str) -> Result<u32, String> {
+    let config = settings::get(app).server.connection;
+    validate(&config)?;
+    // The token is a secret like any other; the tray and dock can still ask
+    // while the app is locked
+    app_lock::ensure_unlocked(app)?;
+    let token = tauri::async_runtime::spawn_blocking(|| secrets::get(TOKEN_SECRET))
+        .await
+        .map_err(|e| e.to_string())??;
+    let pid = {
+        let state = app.state::<RemoteConnection>();
+        let mut remote = state.0.lock().map_err(|e| e.to_string())?;
+        if remote.connected {
+            return Err("Already connected to the remote server".to_string());
+        }
+        stop_tunnel(&mut remote);
+        stop_approvals(&mut remote);
+        remote.token = token;
+        if let Some(tunnel) = &config.tunnel {
+            let child = start_tunnel(tunnel)?;
+            let pid = child.id();
+            remote.tunnel = Some(child);
+            pid
+        } else {
+            0
+        }
+    };
+
+    let url = base_url(&config);
+    let mut reachable = false;
+    for _ in 0..CONNECT_ATTEMPTS {
+        if sidecar_bridge::healthy(app).await {
+            reachable = true;
+            break;
+        }
+        tokio::time::sleep(CONNECT_RETRY_DELAY).await;
+    }
+    let state = app.state::<RemoteConnection>();
+    let mut remote = state.0.lock().map_err(|e| e.to_string())?;
+    if !reachable {
+        stop_tunnel(&mut remote);
+        return Err(format!("The Claude Code server at {} isn't answering", url));
+    }
+    remote.connected = true;
+    remote.approvals = Some(tauri::async_runtime::spawn(sidecar_bridge::relay_approvals(app.clone())));
+    drop(remote);
+    if pid != 0 {
+        watch_tunnel(app, pid);
+    }
+    audit::record(app, Some(origin), "sidecar", "connect", &url);
+    Ok(pid)
+}
+
+pub fn disconnect(app: &AppHandle, origin: &str) -> Result<(), String> {
+    let state = app.state::<RemoteConnection>();
+    let mut remote = state.0.lock().map_err(|e| e.to_string())?;
+    let was_connected = remote.connected || remote.tunnel.is_some();
+    stop_tunnel(&mut remote);
+    stop_approvals(&mut remote);
+    remote.connected = false;
+    drop(remote);
+    if was_connected {
+        audit::record(app, Some(origin), "sidecar", "disconnect", &base_url(&settings::get(app).server.connection));
+    }
+    Ok(())
+}
+
+fn status(app: &AppHandle) -> ConnectionStatus {
+    let config = settings::get(app).server.connection;
+    let state = app.state::<RemoteConnection>();
+    let remote = state.0.lock().ok();
+    ConnectionStatus {
+        connected: server::is_running(app),
+        url: base_url(&config),
+        tunnel_pid: remote.as_ref().and_then(|remote| remote.tunnel.as_ref().map(Child::id)),
+        has_token: remote.as_ref().is_some_and(|remote| remote.token.is_some()),
+        mode: config.mode,
+    }
+}
+
+#[tauri::command]
+pub fn get_server_connection(app: AppHandle) -> ConnectionStatus {
+    status(&app)
+}
+
+// Switch between the local sidecar and a remote server. Whichever is in
+// use is stopped first; call `start_claude_code_server` to connect.
+#[tauri::command]
+pub async fn set_server_connection(
+    app: AppHandle,
+    window: WebviewWindow,
+    config: ConnectionSettings,
+) -> Result<ConnectionStatus, String> {
+    validate(&config)?;
+    if settings::get(&app).server.connection != config {
+        server::stop(&app, false, window.label())?;
+        settings::update(&app, |settings| settings.server.connection = config.clone())?;
+        audit::record(
+            &app,
+            Some(window.label()),
+            "sidecar",
+            "connection",
+            &format!("{} {}", config.mode, base_url(&config)),
+        );
+    }
+    Ok(status(&app))
+}
+
+// The remote server's token, kept in the keychain; None removes it
+#[tauri::command]
+pub async fn set_remote_server_token(
+    app: AppHandle,
+    window: WebviewWindow,
+    token: Option<String>,
+) -> Result<(), String> {
+    let token = token.map(|token| token.trim().to_string()).filter(|token| !token.is_empty());
+    audit::record(&app, Some(window.label()), "secret", if token.is_some() { "write" } else { "delete" }, TOKEN_SECRET);
+    let saved = token.clone();
+    tauri::async_runtime::spawn_blocking(move || match saved {
+        Some(token) => secrets::set(TOKEN_SECRET, &token),
+        None => secrets::delete(TOKEN_SECRET),
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    // Used from the next request on
+    if let Ok(mut remote) = app.state::<RemoteConnection>().0.lock() {
+        remote.token = token;
+    }
+    Ok(())
+}
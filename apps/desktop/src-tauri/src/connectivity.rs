@@ -0,0 +1,136 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{net, updater};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const ONLINE_INTERVAL: Duration = Duration::from_secs(60);
+// Checked more often while offline so coming back is noticed quickly
+const OFFLINE_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, PartialEq, serde::Serialize)]
+pub struct Connectivity {
+    pub online: bool,
+    pub captive_portal: bool,
+    // The proxy answered 407: it's reachable but wants credentials
+    pub proxy_auth_required: bool,
+}
+
+// Assumed online until a probe says otherwise, so nothing is held back
+// during startup
+impl Default for Connectivity {
+    fn default() -> Self {
+        Self {
+            online: true,
+            captive_portal: false,
+            proxy_auth_required: false,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ConnectivityState(Mutex<Connectivity>);
+
+pub fn current(app: &AppHandle) -> Connectivity {
+    app.try_state::<ConnectivityState>()
+        .and_then(|state| state.0.lock().ok().map(|connectivity| *connectivity))
+        .unwrap_or_default()
+}
+
+// For background work that needs the internet: update checks and the like
+pub fn is_online(app: &AppHandle) -> bool {
+    current(app).online
+}
+
+fn offline(captive_portal: bool, proxy_auth_required: bool) -> Connectivity {
+    Connectivity {
+        online: false,
+        captive_portal,
+        proxy_auth_required,
+    }
+}
+
+// The update manifest for the current channel: what being online is needed
+// for, and reachable wherever updates are. Any answer from it counts, even
+// a 404 for a channel with nothing published, except one that redirects or
+// asks to sign in, which is a login page (usually a hotel's or airport's)
+// answering instead.
+async fn probe_url(app: &AppHandle) -> Option<tauri::Url> {
+    let channel = updater::current_channel(app).await.ok()?;
+    updater::channel_endpoints(app, &channel).ok()?.into_iter().next()
+}
+
+// Through the configured proxy, without following redirects, since a
+// captive portal usually redirects to its login page
+async fn probe(app: &AppHandle) -> Connectivity {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let client = net::client_options(&net::current(app)).and_then(|options| {
+        let builder = reqwest::Client::builder()
+            .user_agent("Incito")
+            .timeout(PROBE_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none());
+        net::apply(builder, &options)
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))
+    });
+    let (Ok(client), Some(url)) = (client, probe_url(app).await) else {
+        return Connectivity::default();
+    };
+    match client.get(url).send().await {
+        Ok(response) => match response.status() {
+            reqwest::StatusCode::PROXY_AUTHENTICATION_REQUIRED => offline(false, true),
+            status if status.is_redirection() || status == reqwest::StatusCode::NETWORK_AUTHENTICATION_REQUIRED => {
+                offline(true, false)
+            }
+            _ => Connectivity::default(),
+        },
+        Err(_) => offline(false, false),
+    }
+}
+
+// Returns the new state when it differs from the last one
+fn set(app: &AppHandle, connectivity: Connectivity) -> Option<Connectivity> {
+    let state = app.state::<ConnectivityState>();
+    let mut current = state.0.lock().ok()?;
+    if *current == connectivity {
+        return None;
+    }
+    *current = connectivity;
+    Some(connectivity)
+}
+
+async fn refresh(app: &AppHandle) -> Connectivity {
+    let connectivity = probe(app).await;
+    if let Some(changed) = set(app, connectivity) {
+        let _ = app.emit("connectivity-changed", changed);
+        // Whatever was put off while offline gets its turn now
+        if changed.online {
+            updater::check_if_due(app).await;
+        }
+    }
+    connectivity
+}
+
+// Probes in the background and emits `connectivity-changed` when the
+// state changes, so the frontend doesn't have to poll
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let connectivity = refresh(&app).await;
+            let interval = if connectivity.online { ONLINE_INTERVAL } else { OFFLINE_INTERVAL };
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+// The last known state, or with `refresh` a fresh probe
+#[tauri::command]
+pub async fn get_connectivity(app: AppHandle, refresh: Option<bool>) -> Connectivity {
+    if refresh.unwrap_or(false) {
+        return self::refresh(&app).await;
+    }
+    current(&app)
+}
@@ -0,0 +1,144 @@
+use std::sync::Mutex;
+
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, LogicalPosition, Manager, Window, Wry};
+use tokio::sync::oneshot;
+
+const ID_PREFIX: &str = "ctx:";
+
+#[derive(Clone, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextMenuItemKind {
+    #[default]
+    Item,
+    Checkbox,
+    Submenu,
+    Separator,
+}
+
+#[derive(Clone, serde::Deserialize)]
+pub struct ContextMenuItem {
+    #[serde(default)]
+    pub kind: ContextMenuItemKind,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub checked: bool,
+    #[serde(default)]
+    pub accelerator: Option<String>,
+    // Children of a submenu
+    #[serde(default)]
+    pub items: Vec<ContextMenuItem>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+// The popup currently waiting for a click. Item ids are namespaced per
+// popup so a late click on an old menu can't resolve a newer one.
+struct PendingMenu {
+    prefix: String,
+    sender: oneshot::Sender<Option<String>>,
+}
+
+#[derive(Default)]
+pub struct ContextMenuState(Mutex<Option<PendingMenu>>);
+
+fn build_item(app: &AppHandle, prefix: &str, item: &ContextMenuItem) -> Result<Box<dyn IsMenuItem<Wry>>, tauri::Error> {
+    let id = format!("{}{}", prefix, item.id);
+    let accelerator = item.accelerator.as_deref();
+    Ok(match item.kind {
+        ContextMenuItemKind::Separator => Box::new(PredefinedMenuItem::separator(app)?),
+        ContextMenuItemKind::Item => Box::new(MenuItem::with_id(app, id, &item.label, item.enabled, accelerator)?),
+        ContextMenuItemKind::Checkbox => Box::new(CheckMenuItem::with_id(
+            app,
+            id,
+            &item.label,
+            item.enabled,
+            item.checked,
+            accelerator,
+        )?),
+        ContextMenuItemKind::Submenu => {
+            let submenu = Submenu::with_id(app, id, &item.label, item.enabled)?;
+            for child in &item.items {
+                submenu.append(build_item(app, prefix, child)?.as_ref())?;
+            }
+            Box::new(submenu)
+        }
+    })
+}
+
+// Called from the app's menu event handler; returns whether the id
+// belonged to a context menu
+pub fn handle_menu_event(app: &AppHandle, id: &str) -> bool {
+    if !id.starts_with(ID_PREFIX) {
+        return false;
+    }
+    let Some(state) = app.try_state::<ContextMenuState>() else { return true };
+    let Ok(mut pending) = state.0.lock() else { return true };
+
+    if let Some(item_id) = pending.as_ref().and_then(|menu| id.strip_prefix(&menu.prefix)) {
+        let item_id = item_id.to_string();
+        if let Some(menu) = pending.take() {
+            let _ = menu.sender.send(Some(item_id));
+        }
+    }
+    true
+}
+
+// Show a native popup at (x, y) in the window's logical coordinates and
+// resolve with the id of the clicked item, or None if it was dismissed
+#[tauri::command]
+pub async fn show_context_menu(
+    app: AppHandle,
+    window: Window,
+    items: Vec<ContextMenuItem>,
+    x: f64,
+    y: f64,
+) -> Result<Option<String>, String> {
+    let prefix = format!("{}{}:", ID_PREFIX, uuid::Uuid::new_v4().simple());
+    let menu = Menu::new(&app).map_err(|e| e.to_string())?;
+    for item in &items {
+        let item = build_item(&app, &prefix, item).map_err(|e| format!("Failed to build menu: {}", e))?;
+        menu.append(item.as_ref()).map_err(|e| e.to_string())?;
+    }
+
+    let (sender, receiver) = oneshot::channel();
+    {
+        let state = app.state::<ContextMenuState>();
+        let mut pending = state.0.lock().map_err(|e| e.to_string())?;
+        // Opening a new menu dismisses any previous one
+        if let Some(previous) = pending.replace(PendingMenu { prefix: prefix.clone(), sender }) {
+            let _ = previous.sender.send(None);
+        }
+    }
+
+    window
+        .popup_menu_at(&menu, LogicalPosition::new(x, y))
+        .map_err(|e| format!("Failed to show menu: {}", e))?;
+
+    // On macOS and Windows the popup is modal, so once it returns the menu is
+    // closed and any click is already queued. GTK returns immediately, so
+    // there the menu stays pending until a click or the next popup.
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(250));
+            if let Ok(mut pending) = app.state::<ContextMenuState>().0.lock() {
+                if pending.as_ref().is_some_and(|menu| menu.prefix == prefix) {
+                    if let Some(menu) = pending.take() {
+                        let _ = menu.sender.send(None);
+                    }
+                }
+            }
+        });
+    }
+
+    Ok(receiver.await.unwrap_or(None))
+}
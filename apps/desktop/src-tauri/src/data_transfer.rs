@@ -0,0 +1,626 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection, SqliteConnection};
+use tauri::{AppHandle, Emitter, Manager};
+use zip::write::SimpleFileOptions;
+use zip::ZipArchive;
+
+use crate::settings::AppSettings;
+use crate::{backup, db, fs_util, settings};
+
+// Bumped when the archive layout changes in a way older builds can't read
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATABASE_ENTRY: &str = "incito.db";
+const SETTINGS_ENTRY: &str = "settings.json";
+const PROMPTS_DIR: &str = "prompts/";
+
+// Frontend setting holding the prompt library folder
+const PROMPT_FOLDER_SETTING: &str = "folder_path";
+
+// Rebuilt from ~/.claude on each machine, so they're left out
+const LOCAL_TABLES: &[&str] = &[
+    "claude_sessions",
+    "claude_sessions_pruned",
+    "claude_messages_fts",
+    "usage_daily",
+    "usage_files",
+    "usage_file_daily",
+];
+
+// Machine-specific settings rows that would point at paths on the other
+// machine
+const LOCAL_SETTINGS: &[&str] = &[PROMPT_FOLDER_SETTING];
+
+// Settings keys and profile environment variables holding credentials
+const SECRET_MARKERS: &[&str] = &["api_key", "apikey", "token", "secret", "password"];
+
+// Columns that say which copy of a row is newer, in order of preference
+const TIMESTAMP_COLUMNS: &[&str] = &["updated_at", "last_opened_at", "last_used_at"];
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Manifest {
+    format_version: u32,
+    app_version: String,
+    exported_at: String,
+    includes_secrets: bool,
+    prompts: Vec<PromptEntry>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PromptEntry {
+    name: String,
+    modified_at: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct TableMerge {
+    pub table: String,
+    pub inserted: u64,
+    pub updated: u64,
+}
+
+#[derive(Clone, Default, serde::Serialize)]
+pub struct ImportReport {
+    pub tables: Vec<TableMerge>,
+    pub settings_applied: bool,
+    pub prompt_folder: Option<String>,
+    pub prompts_added: usize,
+    pub prompts_updated: usize,
+    // Prompts left alone because the local copy is the same or newer
+    pub prompts_kept: usize,
+    // Parts of the archive that couldn't be applied
+    pub warnings: Vec<String>,
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SECRET_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn rfc3339(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()
+}
+
+fn modified(path: &Path) -> Option<String> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok().map(rfc3339)
+}
+
+async fn prompt_folder(app: &AppHandle) -> Result<Option<PathBuf>, String> {
+    Ok(db::get_setting(app, PROMPT_FOLDER_SETTING)
+        .await?
+        .filter(|folder| !folder.trim().is_empty())
+        .map(PathBuf::from))
+}
+
+// Prompt files sit directly in the library folder
+fn list_prompts(folder: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    let mut prompts: Vec<(String, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| (entry.file_name().to_string_lossy().to_string(), entry.path()))
+        .filter(|(name, path)| name.ends_with(".md") && path.is_file())
+        .collect();
+    prompts.sort();
+    prompts
+}
+
+// A plaintext copy of the live database, whether or not it's encrypted, so
+// the archive opens on a machine without this one's keychain
+async fn export_database(app: &AppHandle, dest: &Path) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    let export = async {
+        // ATTACH only applies to one connection, so the export runs on it
+        let mut connection = pool.acquire().await?;
+        sqlx::query("ATTACH DATABASE ? AS export KEY ''")
+            .bind(dest.to_string_lossy().into_owned())
+            .execute(&mut *connection)
+            .await?;
+        let exported = sqlx::query("SELECT sqlcipher_export('export')").execute(&mut *connection).await;
+        sqlx::query("DETACH DATABASE export").execute(&mut *connection).await?;
+        exported.map(|_| ())
+    }
+    .await;
+    export.map_err(|e| format!("Failed to export the database: {}", e))
+}
+
+// Drop what another machine would rebuild, and credentials unless asked
+// to keep them
+async fn scrub_database(path: &Path, include_secrets: bool) -> Result<(), String> {
+    let mut connection = SqliteConnectOptions::new()
+        .filename(path)
+        .connect()
+        .await
+        .map_err(|e| format!("Failed to open the exported database: {}", e))?;
+    let fail = |e: sqlx::Error| format!("Failed to prepare the exported database: {}", e);
+
+    for table in LOCAL_TABLES {
+        sqlx::query(&format!("DROP TABLE IF EXISTS {}", quote(table)))
+            .execute(&mut connection)
+            .await
+            .map_err(fail)?;
+    }
+
+    let keys: Vec<(String,)> = sqlx::query_as("SELECT key FROM settings")
+        .fetch_all(&mut connection)
+        .await
+        .map_err(fail)?;
+    for (key,) in keys {
+        if LOCAL_SETTINGS.contains(&key.as_str()) || (!include_secrets && is_secret(&key)) {
+            sqlx::query("DELETE FROM settings WHERE key = ?")
+                .bind(&key)
+                .execute(&mut connection)
+                .await
+                .map_err(fail)?;
+        }
+    }
+
+    let has_profiles: Option<(String,)> =
+        sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'claude_profiles'")
+            .fetch_optional(&mut connection)
+            .await
+            .map_err(fail)?;
+    if has_profiles.is_some() && !include_secrets {
+        let profiles: Vec<(String, String)> = sqlx::query_as("SELECT id, env FROM claude_profiles")
+            .fetch_all(&mut connection)
+            .await
+            .map_err(fail)?;
+        for (id, env) in profiles {
+            let mut env: HashMap<String, String> = serde_json::from_str(&env).unwrap_or_default();
+            env.retain(|name, _| !is_secret(name));
+            sqlx::query("UPDATE claude_profiles SET env = ? WHERE id = ?")
+                .bind(serde_json::to_string(&env).map_err(|e| e.to_string())?)
+                .bind(&id)
+                .execute(&mut connection)
+                .await
+                .map_err(fail)?;
+        }
+    }
+
+    sqlx::query("VACUUM").execute(&mut connection).await.map_err(fail)?;
+    connection.close().await.map_err(fail)
+}
+
+fn portable_settings(mut settings: AppSettings, include_secrets: bool) -> AppSettings {
+    // Proxy URLs can carry a username and password
+    if !include_secrets && settings.network.proxy.as_deref().is_some_and(|proxy| proxy.contains('@')) {
+        settings.network.proxy = None;
+    }
+    settings
+}
+
+fn write_archive(
+    dest: &Path,
+    manifest: &Manifest,
+    database: &Path,
+    settings: &AppSettings,
+    prompts: &[(String, PathBuf)],
+) -> Result<(), String> {
+    let staged = fs_util::temp_path_for(dest);
+    let write = || -> Result<(), String> {
+        let file = File::create(&staged).map_err(|e| format!("Failed to create {}: {}", staged.display(), e))?;
+        let mut zip = zip::ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        let fail = |e: std::io::Error| format!("Failed to write the archive: {}", e);
+
+        zip.start_file(MANIFEST_ENTRY, options).map_err(|e| e.to_string())?;
+        zip.write_all(&serde_json::to_vec_pretty(manifest).map_err(|e| e.to_string())?)
+            .map_err(fail)?;
+
+        zip.start_file(DATABASE_ENTRY, options).map_err(|e| e.to_string())?;
+        let mut db_file = File::open(database).map_err(fail)?;
+        std::io::copy(&mut db_file, &mut zip).map_err(fail)?;
+
+        zip.start_file(SETTINGS_ENTRY, options).map_err(|e| e.to_string())?;
+        zip.write_all(&serde_json::to_vec_pretty(settings).map_err(|e| e.to_string())?)
+            .map_err(fail)?;
+
+        for (name, path) in prompts {
+            zip.start_file(format!("{}{}", PROMPTS_DIR, name), options)
+                .map_err(|e| e.to_string())?;
+            let mut prompt = File::open(path).map_err(fail)?;
+            std::io::copy(&mut prompt, &mut zip).map_err(fail)?;
+        }
+
+        zip.finish().map_err(|e| e.to_string())?.sync_all().map_err(fail)?;
+        std::fs::rename(&staged, dest).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))
+    };
+    let result = write();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&staged);
+    }
+    result
+}
+
+async fn export(app: &AppHandle, dest: PathBuf, include_secrets: bool) -> Result<(), String> {
+    let staged_db = fs_util::temp_path_for(&db::path(app)?);
+    let result = async {
+        export_database(app, &staged_db).await?;
+        scrub_database(&staged_db, include_secrets).await?;
+
+        let prompts = match prompt_folder(app).await? {
+            Some(folder) => list_prompts(&folder),
+            None => Vec::new(),
+        };
+        let manifest = Manifest {
+            format_version: FORMAT_VERSION,
+            app_version: app.package_info().version.to_string(),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            includes_secrets: include_secrets,
+            prompts: prompts
+                .iter()
+                .map(|(name, path)| PromptEntry {
+                    name: name.clone(),
+                    modified_at: modified(path).unwrap_or_default(),
+                })
+                .collect(),
+        };
+        let settings = portable_settings(settings::get(app), include_secrets);
+        let database = staged_db.clone();
+        tauri::async_runtime::spawn_blocking(move || write_archive(&dest, &manifest, &database, &settings, &prompts))
+            .await
+            .map_err(|e| format!("Export task failed: {}", e))?
+    }
+    .await;
+    let _ = std::fs::remove_file(&staged_db);
+    result
+}
+
+struct Archive {
+    manifest: Manifest,
+    settings: Option<AppSettings>,
+    prompts: Vec<(String, Vec<u8>)>,
+}
+
+fn read_entry(zip: &mut ZipArchive<File>, name: &str) -> Result<Option<Vec<u8>>, String> {
+    let mut entry = match zip.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {} from the archive: {}", name, e)),
+    };
+    let mut contents = Vec::new();
+    entry
+        .read_to_end(&mut contents)
+        .map_err(|e| format!("Failed to read {} from the archive: {}", name, e))?;
+    Ok(Some(contents))
+}
+
+// Unpack the archive, writing its database to `database`
+fn read_archive(src: &Path, database: &Path) -> Result<Archive, String> {
+    let file = File::open(src).map_err(|e| format!("Failed to open {}: {}", src.display(), e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("{} isn't a zip archive: {}", src.display(), e))?;
+
+    let manifest: Manifest = read_entry(&mut zip, MANIFEST_ENTRY)?
+        .and_then(|contents| serde_json::from_slice(&contents).ok())
+        .ok_or_else(|| format!("{} isn't an Incito export", src.display()))?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err("The export is from a newer version of Incito".to_string());
+    }
+
+    let contents = read_entry(&mut zip, DATABASE_ENTRY)?
+        .ok_or_else(|| format!("{} has no database", src.display()))?;
+    let mut db_file = File::create(database).map_err(|e| format!("Failed to unpack the database: {}", e))?;
+    db_file
+        .write_all(&contents)
+        .and_then(|_| db_file.sync_all())
+        .map_err(|e| format!("Failed to unpack the database: {}", e))?;
+
+    let settings = read_entry(&mut zip, SETTINGS_ENTRY)?.and_then(|contents| serde_json::from_slice(&contents).ok());
+
+    // Only plain file names, so an entry can't write outside the folder
+    let mut prompts = Vec::new();
+    for entry in &manifest.prompts {
+        let name = Path::new(&entry.name);
+        if name.file_name() != Some(name.as_os_str()) || !entry.name.ends_with(".md") {
+            continue;
+        }
+        if let Some(contents) = read_entry(&mut zip, &format!("{}{}", PROMPTS_DIR, entry.name))? {
+            prompts.push((entry.name.clone(), contents));
+        }
+    }
+
+    Ok(Archive {
+        manifest,
+        settings,
+        prompts,
+    })
+}
+
+async fn columns(connection: &mut SqliteConnection, schema: &str, table: &str) -> Result<Vec<(String, i64)>, String> {
+    sqlx::query_as("SELECT name, pk FROM pragma_table_info(?, ?)")
+        .bind(table)
+        .bind(schema)
+        .fetch_all(connection)
+        .await
+        .map_err(|e| format!("Failed to read the columns of {}: {}", table, e))
+}
+
+// Rows the archive has and this database doesn't are added. Rows both have
+// take the archive's copy only when it was updated more recently; tables
+// without a timestamp keep the local copy.
+async fn merge_table(connection: &mut SqliteConnection, table: &str) -> Result<TableMerge, String> {
+    let imported: Vec<String> = columns(connection, "imported", table).await?.into_iter().map(|(name, _)| name).collect();
+    let local = columns(connection, "main", table).await?;
+    let shared: Vec<&(String, i64)> = local.iter().filter(|(name, _)| imported.contains(name)).collect();
+    let keys: Vec<&str> = shared.iter().filter(|(_, pk)| *pk > 0).map(|(name, _)| name.as_str()).collect();
+    let values: Vec<&str> = shared.iter().filter(|(_, pk)| *pk == 0).map(|(name, _)| name.as_str()).collect();
+    let mut merge = TableMerge {
+        table: table.to_string(),
+        inserted: 0,
+        updated: 0,
+    };
+    if keys.is_empty() {
+        return Ok(merge);
+    }
+
+    let fail = |e: sqlx::Error| format!("Failed to merge {}: {}", table, e);
+    let column_list = shared.iter().map(|(name, _)| quote(name)).collect::<Vec<_>>().join(", ");
+    merge.inserted = sqlx::query(&format!(
+        "INSERT OR IGNORE INTO main.{table} ({columns}) SELECT {columns} FROM imported.{table}",
+        table = quote(table),
+        columns = column_list,
+    ))
+    .execute(&mut *connection)
+    .await
+    .map_err(fail)?
+    .rows_affected();
+
+    let timestamp = TIMESTAMP_COLUMNS.iter().find(|column| values.contains(column));
+    if let Some(timestamp) = timestamp {
+        let same_row = keys
+            .iter()
+            .map(|key| format!("i.{key} = {table}.{key}", key = quote(key), table = quote(table)))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        merge.updated = sqlx::query(&format!(
+            "UPDATE OR IGNORE main.{table} SET ({targets}) = (SELECT {sources} FROM imported.{table} AS i WHERE {same_row})
+             WHERE EXISTS (SELECT 1 FROM imported.{table} AS i WHERE {same_row} AND i.{timestamp} > {table}.{timestamp})",
+            table = quote(table),
+            targets = values.iter().map(|name| quote(name)).collect::<Vec<_>>().join(", "),
+            sources = values.iter().map(|name| format!("i.{}", quote(name))).collect::<Vec<_>>().join(", "),
+            same_row = same_row,
+            timestamp = quote(timestamp),
+        ))
+        .execute(&mut *connection)
+        .await
+        .map_err(fail)?
+        .rows_affected();
+    }
+    Ok(merge)
+}
+
+async fn orphans(connection: &mut SqliteConnection) -> Result<Vec<(String, i64)>, String> {
+    let rows: Vec<(String, Option<i64>, String, i64)> = sqlx::query_as("PRAGMA main.foreign_key_check")
+        .fetch_all(connection)
+        .await
+        .map_err(|e| format!("Failed to check the imported rows: {}", e))?;
+    Ok(rows.into_iter().filter_map(|(table, rowid, _, _)| Some((table, rowid?))).collect())
+}
+
+async fn has_foreign_keys(connection: &mut SqliteConnection, table: &str) -> Result<bool, String> {
+    let keys: Option<(i64,)> = sqlx::query_as("SELECT id FROM pragma_foreign_key_list(?) LIMIT 1")
+        .bind(table)
+        .fetch_optional(connection)
+        .await
+        .map_err(|e| format!("Failed to import: {}", e))?;
+    Ok(keys.is_some())
+}
+
+async fn merge_database(app: &AppHandle, archive_db: &Path) -> Result<Vec<TableMerge>, String> {
+    // A connection of its own, with foreign keys off until the orphans a
+    // skipped row leaves behind are cleared
+    let mut connection = db::connect_options(&db::path(app)?)?
+        .foreign_keys(false)
+        .connect()
+        .await
+        .map_err(|e| format!("Failed to open the database: {}", e))?;
+    sqlx::query("ATTACH DATABASE ? AS imported KEY ''")
+        .bind(archive_db.to_string_lossy().into_owned())
+        .execute(&mut connection)
+        .await
+        .map_err(|e| format!("Failed to open the exported database: {}", e))?;
+
+    let merged = async {
+        let tables: Vec<(String,)> = sqlx::query_as(
+            "SELECT name FROM imported.sqlite_master
+             WHERE type = 'table' AND rootpage > 0 AND name NOT LIKE 'sqlite_%' AND name != '_sqlx_migrations'",
+        )
+        .fetch_all(&mut connection)
+        .await
+        .map_err(|e| format!("Failed to read the exported database: {}", e))?;
+
+        let mut tx = connection.begin().await.map_err(|e| format!("Failed to import: {}", e))?;
+        // Orphans the local database already had are left alone
+        let orphans_before: HashSet<(String, i64)> = orphans(&mut tx).await?.into_iter().collect();
+        let mut rows_before: HashMap<String, HashSet<i64>> = HashMap::new();
+        let mut merges = Vec::new();
+        for (table,) in tables {
+            if LOCAL_TABLES.iter().any(|local| table.starts_with(local)) {
+                continue;
+            }
+            // Only tables the app's own migrations created. The archive's
+            // schema is never run, and tables this version doesn't know
+            // are skipped.
+            let exists: Option<(String,)> =
+                sqlx::query_as("SELECT name FROM main.sqlite_master WHERE type = 'table' AND name = ?")
+                    .bind(&table)
+                    .fetch_optional(&mut *tx)
+                    .await
+                    .map_err(|e| format!("Failed to import: {}", e))?;
+            if exists.is_none() {
+                continue;
+            }
+            if has_foreign_keys(&mut tx, &table).await? {
+                let rowids: Vec<(i64,)> = sqlx::query_as(&format!("SELECT rowid FROM main.{}", quote(&table)))
+                    .fetch_all(&mut *tx)
+                    .await
+                    .map_err(|e| format!("Failed to import: {}", e))?;
+                rows_before.insert(table.clone(), rowids.into_iter().map(|(rowid,)| rowid).collect());
+            }
+            let merge = merge_table(&mut tx, &table).await?;
+            if merge.inserted > 0 || merge.updated > 0 {
+                merges.push(merge);
+            }
+        }
+
+        // Imported rows whose parent was skipped are dropped. A local row
+        // the import left pointing nowhere means the archive doesn't fit,
+        // so nothing is imported.
+        for (table, rowid) in orphans(&mut tx).await? {
+            if orphans_before.contains(&(table.clone(), rowid)) {
+                continue;
+            }
+            if rows_before.get(&table).is_some_and(|rows| rows.contains(&rowid)) {
+                return Err(format!("The import would leave rows in {} pointing at missing rows", table));
+            }
+            sqlx::query(&format!("DELETE FROM main.{} WHERE rowid = ?", quote(&table)))
+                .bind(rowid)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to import: {}", e))?;
+        }
+        tx.commit().await.map_err(|e| format!("Failed to import: {}", e))?;
+        Ok(merges)
+    }
+    .await;
+
+    let _ = sqlx::query("DETACH DATABASE imported").execute(&mut connection).await;
+    let _ = connection.close().await;
+    merged
+}
+
+// The archive's settings win when it was exported after the local ones were
+// last saved. Where the CLI and the network live stays this machine's.
+async fn merge_settings(app: &AppHandle, archive: &Archive) -> Result<bool, String> {
+    let Some(imported) = archive.settings.clone() else {
+        return Ok(false);
+    };
+    let local_saved = modified(&settings::settings_path(app)?);
+    if local_saved.is_some_and(|saved| saved >= archive.manifest.exported_at) {
+        return Ok(false);
+    }
+    let local = settings::get(app);
+    let next = AppSettings {
+        server: local.server,
+        network: local.network,
+        ..imported
+    };
+    settings::replace(app, next).await?;
+    Ok(true)
+}
+
+// Into the library folder, or a folder in the app's data directory when no
+// library was chosen yet
+async fn merge_prompts(app: &AppHandle, archive: &Archive, report: &mut ImportReport) -> Result<(), String> {
+    if archive.prompts.is_empty() {
+        return Ok(());
+    }
+    let folder = match prompt_folder(app).await? {
+        Some(folder) => folder,
+        None => {
+            let folder = app
+                .path()
+                .app_data_dir()
+                .map_err(|e| format!("Failed to resolve app data dir: {}", e))?
+                .join("prompts");
+            db::set_setting(app, PROMPT_FOLDER_SETTING, &folder.to_string_lossy()).await?;
+            folder
+        }
+    };
+
+    let exported_times: HashMap<&str, &str> = archive
+        .manifest
+        .prompts
+        .iter()
+        .map(|entry| (entry.name.as_str(), entry.modified_at.as_str()))
+        .collect();
+    for (name, contents) in &archive.prompts {
+        let target = folder.join(name);
+        if !target.exists() {
+            fs_util::write_atomic(&target, contents, false)?;
+            report.prompts_added += 1;
+            continue;
+        }
+        let unchanged = std::fs::read(&target).is_ok_and(|local| local == *contents);
+        let newer = match (exported_times.get(name.as_str()), modified(&target)) {
+            (Some(exported), Some(local)) => *exported > local.as_str(),
+            _ => false,
+        };
+        if !unchanged && newer {
+            // The local version is kept next to it as .bak
+            fs_util::write_atomic(&target, contents, true)?;
+            report.prompts_updated += 1;
+        } else {
+            report.prompts_kept += 1;
+        }
+    }
+    report.prompt_folder = Some(folder.to_string_lossy().to_string());
+    Ok(())
+}
+
+async fn import(app: &AppHandle, src: PathBuf) -> Result<ImportReport, String> {
+    let staged_db = fs_util::temp_path_for(&db::path(app)?);
+    let database = staged_db.clone();
+    let result = async {
+        let archive = tauri::async_runtime::spawn_blocking(move || read_archive(&src, &database))
+            .await
+            .map_err(|e| format!("Import task failed: {}", e))??;
+        backup::validate(&staged_db).await?;
+
+        let mut report = ImportReport {
+            tables: merge_database(app, &staged_db).await?,
+            ..Default::default()
+        };
+        match merge_settings(app, &archive).await {
+            Ok(applied) => report.settings_applied = applied,
+            Err(e) => report.warnings.push(format!("Settings weren't imported: {}", e)),
+        }
+        if let Err(e) = merge_prompts(app, &archive, &mut report).await {
+            report.warnings.push(format!("Some prompts weren't imported: {}", e));
+        }
+        Ok(report)
+    }
+    .await;
+    let _ = std::fs::remove_file(&staged_db);
+    result
+}
+
+// Bundle the database, settings and prompt library into a zip at
+// `dest_path` for moving to another machine. API keys, tokens and proxy
+// credentials are left out unless `include_secrets` is set.
+#[tauri::command]
+pub async fn export_app_data(app: AppHandle, dest_path: String, include_secrets: Option<bool>) -> Result<(), String> {
+    let job = app.state::<backup::DatabaseJob>();
+    let _guard = job
+        .0
+        .try_lock()
+        .map_err(|_| "Another database job is running".to_string())?;
+    export(&app, PathBuf::from(dest_path), include_secrets.unwrap_or(false)).await
+}
+
+// Merge an `export_app_data` archive into this install: nothing local is
+// deleted, and where both sides have a row, setting or prompt the more
+// recently changed one is kept. Emits `app-data-imported` with the report
+// once the frontend should reload its data.
+#[tauri::command]
+pub async fn import_app_data(app: AppHandle, src_path: String) -> Result<ImportReport, String> {
+    let job = app.state::<backup::DatabaseJob>();
+    let _guard = job
+        .0
+        .try_lock()
+        .map_err(|_| "Another database job is running".to_string())?;
+    let report = import(&app, PathBuf::from(src_path)).await?;
+    let _ = app.emit("app-data-imported", report.clone());
+    Ok(report)
+}
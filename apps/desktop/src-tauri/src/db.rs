@@ -0,0 +1,98 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_sql::{DbInstances, DbPool};
+
+use crate::encryption;
+
+// Same connection string the frontend uses. Rust opens the pool and shares
+// it through the sql plugin, since only Rust can unlock an encrypted file.
+pub const DB_URL: &str = "sqlite:incito.db";
+
+const PLAIN_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+// Where the sql plugin keeps the file `DB_URL` points at
+pub fn path(app: &AppHandle) -> Result<PathBuf, String> {
+    let file_name = DB_URL.split_once(':').map(|(_, name)| name).unwrap_or(DB_URL);
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(file_name))
+        .map_err(|e| format!("Failed to resolve app config dir: {}", e))
+}
+
+// SQLCipher files have no plain header; an empty or missing file isn't
+// encrypted yet
+pub fn is_encrypted(path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|_| &header != PLAIN_HEADER)
+}
+
+// Options for the database at `path`, keyed when it's encrypted
+pub fn connect_options(path: &Path) -> Result<SqliteConnectOptions, String> {
+    let options = SqliteConnectOptions::new().filename(path).create_if_missing(true);
+    if !is_encrypted(path) {
+        return Ok(options);
+    }
+    let key = encryption::saved_key()?
+        .ok_or_else(|| "The database is encrypted, but its key isn't in the system keychain".to_string())?;
+    Ok(options.pragma("key", encryption::key_pragma(&key)))
+}
+
+// Open the database and hand the pool to the sql plugin, replacing any it
+// had. From setup, before migrations run, and after the file is swapped.
+pub async fn open(app: &AppHandle) -> Result<(), String> {
+    let path = path(app)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let pool = SqlitePool::connect_with(connect_options(&path)?)
+        .await
+        .map_err(|e| format!("Failed to open the database: {}", e))?;
+    let instances = app
+        .try_state::<DbInstances>()
+        .ok_or_else(|| "Database plugin is not initialized".to_string())?;
+    instances.0.write().await.insert(DB_URL.to_string(), DbPool::Sqlite(pool));
+    Ok(())
+}
+
+pub async fn pool(app: &AppHandle) -> Result<SqlitePool, String> {
+    let instances = app
+        .try_state::<DbInstances>()
+        .ok_or_else(|| "Database plugin is not initialized".to_string())?;
+    let instances = instances.0.read().await;
+
+    match instances.get(DB_URL) {
+        Some(DbPool::Sqlite(pool)) => Ok(pool.clone()),
+        None => Err("Database is not loaded".to_string()),
+    }
+}
+
+pub async fn set_setting(app: &AppHandle, key: &str, value: &str) -> Result<(), String> {
+    let pool = pool(app).await?;
+
+    sqlx::query("INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)")
+        .bind(key)
+        .bind(value)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to save setting {}: {}", key, e))?;
+
+    Ok(())
+}
+
+pub async fn get_setting(app: &AppHandle, key: &str) -> Result<Option<String>, String> {
+    let pool = pool(app).await?;
+
+    let value: Option<(Option<String>,)> = sqlx::query_as("SELECT value FROM settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| format!("Failed to read setting {}: {}", key, e))?;
+
+    Ok(value.and_then(|(value,)| value))
+}
@@ -0,0 +1,203 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::projects::{self, Project};
+use crate::{i18n, windows};
+
+pub const SCHEME: &str = "incito";
+
+// Where an incito:// link asks to go, as the frontend gets it in
+// `deep-link-navigate`: `{ action: "open-project", project }` or
+// `{ action: "session", session_id, project_path }`
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum Navigation {
+    OpenProject {
+        project: Project,
+    },
+    Session {
+        session_id: String,
+        project_path: Option<String>,
+    },
+}
+
+#[derive(Clone, serde::Serialize)]
+struct DeepLinkFailed {
+    url: String,
+    error: String,
+}
+
+// Links that arrive before the frontend is listening wait here until it
+// asks for them with `take_deep_links`
+#[derive(Default)]
+struct Pending {
+    ready: bool,
+    navigations: Vec<Navigation>,
+}
+
+#[derive(Default)]
+pub struct PendingNavigation(Mutex<Pending>);
+
+enum Link {
+    Open { project_path: String },
+    Session { session_id: String, project_path: Option<String> },
+}
+
+fn query(url: &Url, key: &str) -> Option<String> {
+    url.query_pairs()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.trim().is_empty())
+}
+
+// incito://open?project=/path and incito://session/<id>, the latter with
+// an optional ?project=
+fn parse(url: &Url) -> Result<Link, String> {
+    if url.scheme() != SCHEME {
+        return Err(format!("Not an {}:// link", SCHEME));
+    }
+    match url.host_str().unwrap_or_default() {
+        "open" => Ok(Link::Open {
+            project_path: query(url, "project").ok_or("The link doesn't name a project")?,
+        }),
+        "session" => {
+            let session_id = url.path().trim_matches('/').to_string();
+            let valid = !session_id.is_empty()
+                && session_id.len() <= 128
+                && session_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(format!("Invalid session id: {}", session_id));
+            }
+            Ok(Link::Session {
+                session_id,
+                project_path: query(url, "project"),
+            })
+        }
+        other => Err(format!("Unknown link: {}://{}", SCHEME, other)),
+    }
+}
+
+// Opening a project lets the webview into its files, so a link can't do
+// that to a folder the user hasn't opened before without asking
+fn confirm_new_project(app: &AppHandle, path: &str) -> bool {
+    let mut dialog = app
+        .dialog()
+        .message(i18n::t_with(app, "dialogs.deepLinkOpenProject", &[("path", path)]))
+        .title(i18n::t(app, "dialogs.deepLinkOpenTitle"))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            i18n::t(app, "dialogs.open"),
+            i18n::t(app, "dialogs.cancel"),
+        ));
+    if let Some(window) = app.get_webview_window(windows::MAIN_WINDOW) {
+        dialog = dialog.parent(&window);
+    }
+    dialog.blocking_show()
+}
+
+// Whether `path` may be opened: a registered project may, any other folder
+// only once the user says so. Also for jump list entries and arguments
+// forwarded by a second instance, which anything can pass.
+pub async fn confirm_if_new(app: &AppHandle, path: &str) -> Result<bool, String> {
+    if projects::registered(app, path).await?.is_some() {
+        return Ok(true);
+    }
+    let (handle, path) = (app.clone(), path.to_string());
+    tauri::async_runtime::spawn_blocking(move || confirm_new_project(&handle, &path))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// None when the user declined
+async fn resolve(app: &AppHandle, link: Link) -> Result<Option<Navigation>, String> {
+    match link {
+        Link::Open { project_path } => {
+            if !Path::new(&project_path).is_dir() {
+                return Err(format!("Project folder not found: {}", project_path));
+            }
+            if !confirm_if_new(app, &project_path).await? {
+                return Ok(None);
+            }
+            let project = projects::record_project_opened(app.clone(), project_path, None).await?;
+            Ok(Some(Navigation::OpenProject { project }))
+        }
+        Link::Session {
+            session_id,
+            project_path,
+        } => Ok(Some(Navigation::Session {
+            session_id,
+            project_path,
+        })),
+    }
+}
+
+fn deliver(app: &AppHandle, navigation: Navigation) {
+    let _ = windows::show_main_window(app);
+    let ready = match app.state::<PendingNavigation>().0.lock() {
+        Ok(mut pending) if !pending.ready => {
+            pending.navigations.push(navigation.clone());
+            false
+        }
+        _ => true,
+    };
+    if ready {
+        let _ = app.emit_to(windows::MAIN_WINDOW, "deep-link-navigate", navigation);
+    }
+}
+
+fn open_links(app: &AppHandle, urls: Vec<Url>) {
+    for url in urls {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let result = match parse(&url) {
+                Ok(link) => resolve(&app, link).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(Some(navigation)) => deliver(&app, navigation),
+                Ok(None) => {}
+                Err(error) => {
+                    let _ = windows::show_main_window(&app);
+                    let _ = app.emit(
+                        "deep-link-failed",
+                        DeepLinkFailed {
+                            url: url.to_string(),
+                            error,
+                        },
+                    );
+                }
+            }
+        });
+    }
+}
+
+// Links opened while the app runs come through `on_open_url`, forwarded by
+// single-instance on Windows and Linux. One the app was launched with is
+// there already.
+pub fn start(app: &AppHandle) {
+    // Installers register the scheme; this covers dev builds and AppImages
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    if let Err(e) = app.deep_link().register_all() {
+        eprintln!("Failed to register {}:// links: {}", SCHEME, e);
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| open_links(&handle, event.urls()));
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        open_links(app, urls);
+    }
+}
+
+// Links that came in before the frontend was listening, oldest first.
+// Later ones are emitted as `deep-link-navigate` straight away.
+#[tauri::command]
+pub fn take_deep_links(app: AppHandle) -> Result<Vec<Navigation>, String> {
+    let pending = app.state::<PendingNavigation>();
+    let mut pending = pending.0.lock().map_err(|e| e.to_string())?;
+    pending.ready = true;
+    Ok(std::mem::take(&mut pending.navigations))
+}
@@ -0,0 +1,306 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use similar::{Algorithm, ChangeTag, DiffOp, TextDiff};
+
+use crate::project_files;
+
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+// Past this a diff is no longer minimal, but it does finish
+const DIFF_TIMEOUT: Duration = Duration::from_secs(2);
+// Bigger files are reported as changed without their hunks
+const MAX_FILE_BYTES: u64 = 8 * 1024 * 1024;
+// A NUL in the first few KB marks a file as binary, as git does
+const BINARY_SNIFF_BYTES: usize = 8192;
+// Past this a directory diff stops and says so
+pub const MAX_FILES: usize = 1000;
+
+#[derive(Clone, serde::Serialize)]
+pub struct DiffLine {
+    // "context", "added" or "removed"
+    pub kind: &'static str,
+    // 1-based; None on the side the line isn't in
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    // Without the line ending
+    pub text: String,
+    // Character offsets of what changed within the line, end exclusive.
+    // Empty for context lines and for lines added or removed outright.
+    pub ranges: Vec<(usize, usize)>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct DiffHunk {
+    // As in a unified diff's "@@ -old_start,old_lines +new_start,new_lines @@"
+    pub old_start: usize,
+    pub old_lines: usize,
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct FileDiff {
+    // Relative to the compared directories for `diff_paths`, otherwise the
+    // path given, if any
+    pub path: Option<String>,
+    // "added", "deleted", "modified" or "unchanged"
+    pub status: &'static str,
+    // Either side isn't text, or is too big to diff; no hunks
+    pub binary: bool,
+    pub added: usize,
+    pub removed: usize,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct PathsDiff {
+    // Only files that differ
+    pub files: Vec<FileDiff>,
+    pub truncated: bool,
+}
+
+pub enum Content {
+    Missing,
+    Binary,
+    Text(String),
+}
+
+pub fn content(bytes: Vec<u8>) -> Content {
+    if bytes.len() as u64 > MAX_FILE_BYTES || bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return Content::Binary;
+    }
+    match String::from_utf8(bytes) {
+        Ok(text) => Content::Text(text),
+        Err(_) => Content::Binary,
+    }
+}
+
+pub fn read(path: &Path) -> Result<Content, String> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Content::Missing),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    if metadata.len() > MAX_FILE_BYTES {
+        return Ok(Content::Binary);
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(content(bytes))
+}
+
+fn source(text: Option<String>, path: Option<&str>) -> Result<Content, String> {
+    match (text, path) {
+        (Some(text), _) => Ok(Content::Text(text)),
+        (None, Some(path)) => read(Path::new(path)),
+        (None, None) => Ok(Content::Missing),
+    }
+}
+
+// Unified diffs number an empty range from the line before it
+fn start(range: &std::ops::Range<usize>) -> usize {
+    if range.is_empty() {
+        range.start
+    } else {
+        range.start + 1
+    }
+}
+
+fn hunk<'a>(
+    diff: &'a TextDiff<'a, 'a, 'a, str>,
+    ops: &[DiffOp],
+    deadline: Instant,
+    counts: &mut (usize, usize),
+) -> DiffHunk {
+    let (Some(first), Some(last)) = (ops.first(), ops.last()) else {
+        return DiffHunk {
+            old_start: 0,
+            old_lines: 0,
+            new_start: 0,
+            new_lines: 0,
+            lines: Vec::new(),
+        };
+    };
+    let old_range = first.old_range().start..last.old_range().end;
+    let new_range = first.new_range().start..last.new_range().end;
+
+    let mut lines = Vec::new();
+    for op in ops {
+        for change in diff.iter_inline_changes_deadline(op, Some(deadline)) {
+            let kind = match change.tag() {
+                ChangeTag::Equal => "context",
+                ChangeTag::Insert => "added",
+                ChangeTag::Delete => "removed",
+            };
+            match change.tag() {
+                ChangeTag::Insert => counts.0 += 1,
+                ChangeTag::Delete => counts.1 += 1,
+                ChangeTag::Equal => {}
+            }
+
+            let mut text = String::new();
+            let mut ranges: Vec<(usize, usize)> = Vec::new();
+            let mut offset = 0;
+            for (emphasized, value) in change.iter_strings_lossy() {
+                let value = value.trim_end_matches(['\n', '\r']);
+                let length = value.chars().count();
+                if emphasized && length > 0 {
+                    // Adjacent changed words read as one range
+                    match ranges.last_mut() {
+                        Some((_, end)) if *end == offset => *end += length,
+                        _ => ranges.push((offset, offset + length)),
+                    }
+                }
+                text.push_str(value);
+                offset += length;
+            }
+            // A line that changed completely gains nothing from highlighting
+            if ranges.len() == 1 && ranges[0] == (0, offset) {
+                ranges.clear();
+            }
+
+            lines.push(DiffLine {
+                kind,
+                old_line: change.old_index().map(|index| index + 1),
+                new_line: change.new_index().map(|index| index + 1),
+                text,
+                ranges,
+            });
+        }
+    }
+
+    DiffHunk {
+        old_start: start(&old_range),
+        old_lines: old_range.len(),
+        new_start: start(&new_range),
+        new_lines: new_range.len(),
+        lines,
+    }
+}
+
+pub fn file_diff(path: Option<String>, old: Content, new: Content, context_lines: usize) -> FileDiff {
+    let status = match (&old, &new) {
+        (Content::Missing, _) => "added",
+        (_, Content::Missing) => "deleted",
+        _ => "modified",
+    };
+    let text = |content: Content| match content {
+        Content::Missing => Some(String::new()),
+        Content::Binary => None,
+        Content::Text(text) => Some(text),
+    };
+    let (Some(old), Some(new)) = (text(old), text(new)) else {
+        return FileDiff {
+            path,
+            status,
+            binary: true,
+            added: 0,
+            removed: 0,
+            hunks: Vec::new(),
+        };
+    };
+
+    let deadline = Instant::now() + DIFF_TIMEOUT;
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Patience)
+        .deadline(deadline)
+        .diff_lines(&old, &new);
+    let mut counts = (0, 0);
+    let hunks: Vec<DiffHunk> = diff
+        .grouped_ops(context_lines)
+        .iter()
+        .map(|ops| hunk(&diff, ops, deadline, &mut counts))
+        .filter(|hunk| !hunk.lines.is_empty())
+        .collect();
+    FileDiff {
+        path,
+        status: if hunks.is_empty() && status == "modified" { "unchanged" } else { status },
+        binary: false,
+        added: counts.0,
+        removed: counts.1,
+        hunks,
+    }
+}
+
+fn files(root: &Path) -> BTreeSet<String> {
+    if !root.is_dir() {
+        return BTreeSet::new();
+    }
+    project_files::walker(root)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .filter_map(|entry| project_files::relative_path(root, entry.path()))
+        .collect()
+}
+
+fn diff_directories(old_root: &Path, new_root: &Path, context_lines: usize) -> Result<PathsDiff, String> {
+    let mut paths = files(old_root);
+    paths.extend(files(new_root));
+
+    let mut result = PathsDiff {
+        files: Vec::new(),
+        truncated: false,
+    };
+    for relative in paths {
+        let (old_path, new_path) = (old_root.join(&relative), new_root.join(&relative));
+        // Most files match, and comparing bytes is much cheaper than diffing
+        let old_bytes = std::fs::read(&old_path).ok();
+        if old_bytes.is_some() && old_bytes == std::fs::read(&new_path).ok() {
+            continue;
+        }
+        if result.files.len() == MAX_FILES {
+            result.truncated = true;
+            break;
+        }
+        result
+            .files
+            .push(file_diff(Some(relative), read(&old_path)?, read(&new_path)?, context_lines));
+    }
+    Ok(result)
+}
+
+// Diff two versions of a file, each given as text or a path, into hunks
+// ready to render. A side with neither, or a path that doesn't exist, is an
+// empty file, so a proposed new file diffs as all added.
+#[tauri::command]
+pub async fn compute_diff(
+    old_text: Option<String>,
+    old_path: Option<String>,
+    new_text: Option<String>,
+    new_path: Option<String>,
+    context_lines: Option<usize>,
+) -> Result<FileDiff, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let old = source(old_text, old_path.as_deref())?;
+        let new = source(new_text, new_path.as_deref())?;
+        let path = new_path.or(old_path);
+        Ok(file_diff(path, old, new, context_lines.unwrap_or(DEFAULT_CONTEXT_LINES)))
+    })
+    .await
+    .map_err(|e| format!("Diff task failed: {}", e))?
+}
+
+// Diff two directories file by file, skipping what git would ignore, or two
+// files as a directory of one. Only files that differ are listed.
+#[tauri::command]
+pub async fn diff_paths(old_path: String, new_path: String, context_lines: Option<usize>) -> Result<PathsDiff, String> {
+    let context_lines = context_lines.unwrap_or(DEFAULT_CONTEXT_LINES);
+    let (old_root, new_root) = (PathBuf::from(&old_path), PathBuf::from(&new_path));
+    if !old_root.exists() && !new_root.exists() {
+        return Err(format!("Not found: {} or {}", old_path, new_path));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        if old_root.is_dir() || new_root.is_dir() {
+            return diff_directories(&old_root, &new_root, context_lines);
+        }
+        let file = file_diff(Some(new_path), read(&old_root)?, read(&new_root)?, context_lines);
+        Ok(PathsDiff {
+            files: if file.status == "unchanged" { Vec::new() } else { vec![file] },
+            truncated: false,
+        })
+    })
+    .await
+    .map_err(|e| format!("Diff task failed: {}", e))?
+}
@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use ignore::{WalkBuilder, WalkState};
+use tauri::{AppHandle, Emitter};
+
+// Progress goes out every this many files
+const PROGRESS_EVERY: u64 = 2000;
+
+#[derive(Clone, Copy, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct DirSizeOptions {
+    // Count only what git would track, leaving out .git too
+    pub respect_gitignore: bool,
+    pub skip_node_modules: bool,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct DirSize {
+    pub path: String,
+    pub bytes: u64,
+    pub files: u64,
+    pub dirs: u64,
+    // Entries that couldn't be read, such as folders without permission
+    pub unreadable: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct DirSizeProgress {
+    path: String,
+    bytes: u64,
+    files: u64,
+}
+
+fn measure(app: &AppHandle, root: &Path, options: DirSizeOptions) -> DirSize {
+    let started = Instant::now();
+    let bytes = AtomicU64::new(0);
+    let files = AtomicU64::new(0);
+    let dirs = AtomicU64::new(0);
+    let unreadable = AtomicU64::new(0);
+    let path = root.to_string_lossy().to_string();
+
+    let mut walker = WalkBuilder::new(root);
+    walker
+        .standard_filters(false)
+        .git_ignore(options.respect_gitignore)
+        .git_exclude(options.respect_gitignore)
+        .git_global(options.respect_gitignore)
+        .require_git(false)
+        .filter_entry(move |entry| match entry.file_name().to_str() {
+            Some(".git") => !options.respect_gitignore,
+            Some("node_modules") => !options.skip_node_modules,
+            _ => true,
+        });
+
+    walker.build_parallel().run(|| {
+        Box::new(|entry| {
+            let Ok(entry) = entry else {
+                unreadable.fetch_add(1, Ordering::Relaxed);
+                return WalkState::Continue;
+            };
+            if entry.depth() == 0 {
+                return WalkState::Continue;
+            }
+            match entry.file_type() {
+                Some(kind) if kind.is_dir() => {
+                    dirs.fetch_add(1, Ordering::Relaxed);
+                }
+                // Symlinks count as themselves, not what they point to
+                Some(_) => {
+                    let Ok(metadata) = entry.metadata() else {
+                        unreadable.fetch_add(1, Ordering::Relaxed);
+                        return WalkState::Continue;
+                    };
+                    let total = bytes.fetch_add(metadata.len(), Ordering::Relaxed) + metadata.len();
+                    let count = files.fetch_add(1, Ordering::Relaxed) + 1;
+                    if count.is_multiple_of(PROGRESS_EVERY) {
+                        let _ = app.emit(
+                            "dir-size-progress",
+                            DirSizeProgress {
+                                path: path.clone(),
+                                bytes: total,
+                                files: count,
+                            },
+                        );
+                    }
+                }
+                None => {}
+            }
+            WalkState::Continue
+        })
+    });
+
+    DirSize {
+        path,
+        bytes: bytes.into_inner(),
+        files: files.into_inner(),
+        dirs: dirs.into_inner(),
+        unreadable: unreadable.into_inner(),
+        duration_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+// Total size and counts of everything under `path`, walked on all cores.
+// Emits `dir-size-progress` as it goes, for big folders.
+#[tauri::command]
+pub async fn calculate_dir_size(
+    app: AppHandle,
+    path: String,
+    options: Option<DirSizeOptions>,
+) -> Result<DirSize, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || measure(&app, &root, options))
+        .await
+        .map_err(|e| format!("Failed to measure {}: {}", path, e))
+}
@@ -0,0 +1,138 @@
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use tauri::AppHandle;
+
+use crate::{connection, server, settings};
+
+const DEFAULT_FROM_PORT: u16 = 3457;
+const DEFAULT_TO_PORT: u16 = 3466;
+const MAX_PORTS: u16 = 256;
+// Everything is on this machine, so anything slower isn't a server worth
+// attaching to
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+
+// The ports `discover_local_servers` probes on 127.0.0.1, both included
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DiscoverySettings {
+    pub from_port: u16,
+    pub to_port: u16,
+}
+
+impl Default for DiscoverySettings {
+    fn default() -> Self {
+        Self {
+            from_port: DEFAULT_FROM_PORT,
+            to_port: DEFAULT_TO_PORT,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct LocalServer {
+    pub port: u16,
+    pub url: String,
+    // From /health; None for servers that predate it or need a token
+    pub version: Option<String>,
+    pub executable_path: Option<String>,
+    // Started with CLAUDE_CODE_SERVER_TOKEN, so /health wasn't answered
+    pub requires_token: bool,
+    // The sidecar Incito itself started
+    pub managed: bool,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Health {
+    status: String,
+    provider: Option<String>,
+    version: Option<String>,
+    executable_path: Option<String>,
+}
+
+pub fn validate(config: &DiscoverySettings) -> Result<(), String> {
+    if config.from_port < 1024 || config.from_port > config.to_port {
+        return Err(format!("Invalid port range {}-{}", config.from_port, config.to_port));
+    }
+    if config.to_port - config.from_port >= MAX_PORTS {
+        return Err(format!("At most {} ports can be probed", MAX_PORTS));
+    }
+    Ok(())
+}
+
+fn client() -> Result<reqwest::Client, String> {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let client = reqwest::Client::builder()
+        .user_agent("Incito")
+        .no_proxy()
+        .timeout(HEALTH_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    Ok(CLIENT.get_or_init(|| client).clone())
+}
+
+// Closed ports are turned away by a plain connect first, which is much
+// cheaper than an HTTP request that has to time out
+async fn probe(client: reqwest::Client, port: u16) -> Option<LocalServer> {
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let connect = tokio::time::timeout(CONNECT_TIMEOUT, tokio::net::TcpStream::connect(address)).await;
+    if !matches!(connect, Ok(Ok(_))) {
+        return None;
+    }
+
+    let url = format!("http://127.0.0.1:{}", port);
+    let response = client.get(format!("{}/health", url)).send().await.ok()?;
+    let found = |version, executable_path, requires_token| LocalServer {
+        port,
+        url: url.clone(),
+        version,
+        executable_path,
+        requires_token,
+        managed: false,
+    };
+    // The server's own answer to a missing token, not just any 401
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        let body: serde_json::Value = response.json().await.ok()?;
+        return (body.get("code").and_then(|code| code.as_str()) == Some("UNAUTHORIZED"))
+            .then(|| found(None, None, true));
+    }
+    if !response.status().is_success() {
+        return None;
+    }
+    let health: Health = response.json().await.ok()?;
+    (health.status == "ok" && health.provider.as_deref() == Some("claude-code"))
+        .then(|| found(health.version, health.executable_path, false))
+}
+
+// Claude Code servers listening on 127.0.0.1 in the configured port range,
+// e.g. one started from a terminal. Attaching to one is connecting to it
+// as a remote server at its url.
+#[tauri::command]
+pub async fn discover_local_servers(app: AppHandle) -> Result<Vec<LocalServer>, String> {
+    let ports = settings::get(&app).server.discovery;
+    validate(&ports)?;
+    let client = client()?;
+
+    let probes: Vec<_> = (ports.from_port..=ports.to_port)
+        .map(|port| tauri::async_runtime::spawn(probe(client.clone(), port)))
+        .collect();
+    let mut servers = Vec::new();
+    for probe in probes {
+        if let Ok(Some(server)) = probe.await {
+            servers.push(server);
+        }
+    }
+
+    let managed_url = (!connection::is_remote(&app) && server::is_running(&app)).then_some(connection::LOCAL_URL);
+    for server in &mut servers {
+        server.managed = managed_url == Some(server.url.as_str());
+    }
+    Ok(servers)
+}
@@ -0,0 +1,436 @@
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter, Listener, Manager};
+
+use crate::{deep_link, i18n, menu, projects, server, windows};
+
+const RECENT_PROJECTS_LIMIT: i64 = 3;
+
+// What a dock menu / jump list entry does. Jump list entries relaunch the
+// executable, so these also round-trip through command line arguments.
+#[derive(Clone)]
+enum Action {
+    NewSession,
+    StartServer,
+    StopServer,
+    OpenProject(String),
+}
+
+impl Action {
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn to_args(&self) -> String {
+        match self {
+            Action::NewSession => "--jump new-session".to_string(),
+            Action::StartServer => "--jump start-server".to_string(),
+            Action::StopServer => "--jump stop-server".to_string(),
+            Action::OpenProject(path) => format!("--open-project {}", quote(path)),
+        }
+    }
+
+    fn from_args(args: &[String]) -> Option<Action> {
+        let value = |flag: &str| {
+            args.iter()
+                .position(|arg| arg == flag)
+                .and_then(|index| args.get(index + 1))
+        };
+        if let Some(path) = value("--open-project") {
+            return Some(Action::OpenProject(path.clone()));
+        }
+        match value("--jump")?.as_str() {
+            "new-session" => Some(Action::NewSession),
+            "start-server" => Some(Action::StartServer),
+            "stop-server" => Some(Action::StopServer),
+            _ => None,
+        }
+    }
+}
+
+// Quoted the way Windows splits a command line: backslashes before the
+// closing quote are doubled, or `"D:\"` would escape the quote
+#[cfg_attr(not(windows), allow(dead_code))]
+fn quote(arg: &str) -> String {
+    let trailing = arg.len() - arg.trim_end_matches('\\').len();
+    format!("\"{}{}\"", arg, "\\".repeat(trailing))
+}
+
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+struct Entry {
+    label: String,
+    action: Action,
+}
+
+// Everything the dock menu / jump list shows, rebuilt from the current state
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+struct DockMenu {
+    tasks: Vec<Entry>,
+    recent_title: String,
+    recent: Vec<Entry>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct LaunchAction {
+    // "new-session" or "open-project"
+    pub action: String,
+    pub path: Option<String>,
+}
+
+// A jump list entry that started the app, held until the frontend is
+// listening
+#[derive(Default)]
+pub struct PendingLaunch(Mutex<Option<LaunchAction>>);
+
+async fn entries(app: &AppHandle) -> Result<DockMenu, String> {
+    let server = if server::is_running(app) {
+        Entry {
+            label: i18n::t(app, "dock.stopServer"),
+            action: Action::StopServer,
+        }
+    } else {
+        Entry {
+            label: i18n::t(app, "dock.startServer"),
+            action: Action::StartServer,
+        }
+    };
+    let tasks = vec![
+        Entry {
+            label: i18n::t(app, "dock.newSession"),
+            action: Action::NewSession,
+        },
+        server,
+    ];
+    let recent = projects::recent_projects(app, RECENT_PROJECTS_LIMIT)
+        .await?
+        .into_iter()
+        .map(|project| Entry {
+            label: project.name,
+            action: Action::OpenProject(project.path),
+        })
+        .collect();
+    Ok(DockMenu {
+        tasks,
+        recent_title: i18n::t(app, "dock.recentProjects"),
+        recent,
+    })
+}
+
+fn perform(app: &AppHandle, action: Action) {
+    match action {
+        Action::NewSession => {
+            let _ = windows::show_main_window(app);
+            let _ = app.emit_to(windows::MAIN_WINDOW, "menu-new-session", ());
+        }
+        Action::StartServer => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = server::start(&app, None, "dock").await {
+                    let _ = app.emit("claude-server-error", e);
+                }
+            });
+        }
+        Action::StopServer => {
+            if let Err(e) = server::stop(app, false, "dock") {
+                let _ = app.emit("claude-server-error", e);
+            }
+        }
+        Action::OpenProject(path) => {
+            let _ = windows::show_main_window(app);
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                match deep_link::confirm_if_new(&app, &path).await {
+                    Ok(true) => menu::open_project(&app, path),
+                    Ok(false) => {}
+                    Err(e) => {
+                        let _ = app.emit("menu-action-failed", e);
+                    }
+                }
+            });
+        }
+    }
+}
+
+// Arguments from a jump list entry, either at launch or forwarded by a
+// second instance. At launch the frontend isn't up yet, so anything that
+// needs it waits for `take_launch_action`. Returns whether there was one.
+pub fn handle_args(app: &AppHandle, args: &[String], at_launch: bool) -> bool {
+    let Some(action) = Action::from_args(args) else { return false };
+    let pending = match &action {
+        Action::NewSession if at_launch => Some(LaunchAction {
+            action: "new-session".to_string(),
+            path: None,
+        }),
+        Action::OpenProject(path) if at_launch => Some(LaunchAction {
+            action: "open-project".to_string(),
+            path: Some(path.clone()),
+        }),
+        _ => None,
+    };
+    match pending {
+        Some(pending) => {
+            if let Ok(mut launch) = app.state::<PendingLaunch>().0.lock() {
+                *launch = Some(pending);
+            }
+        }
+        None => perform(app, action),
+    }
+    true
+}
+
+pub async fn refresh(app: &AppHandle) -> Result<(), String> {
+    let dock_menu = entries(app).await?;
+    platform::set(app, dock_menu)
+}
+
+// Keep the entries in step with the recent projects and the server
+pub fn watch(app: &AppHandle) {
+    for event in projects::CHANGE_EVENTS.iter().chain(&["claude-server-status-changed"]) {
+        let handle = app.clone();
+        app.listen_any(*event, move |_| {
+            let handle = handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = refresh(&handle).await;
+            });
+        });
+    }
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = refresh(&handle).await {
+            eprintln!("{}", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn take_launch_action(app: AppHandle) -> Option<LaunchAction> {
+    app.state::<PendingLaunch>().0.lock().ok().and_then(|mut launch| launch.take())
+}
+
+// The dock menu is asked for by AppKit each time it opens, so the entries
+// are kept here and the menu is built on demand by `applicationDockMenu:`,
+// added to tao's app delegate
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::cell::OnceCell;
+    use std::sync::{Mutex, Once, OnceLock};
+
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, Imp, Sel};
+    use objc2::{define_class, msg_send, sel, MainThreadMarker, MainThreadOnly};
+    use objc2_app_kit::{NSApplication, NSMenu, NSMenuItem};
+    use objc2_foundation::{NSObject, NSString};
+    use tauri::AppHandle;
+
+    use super::{Action, DockMenu};
+
+    #[derive(Clone)]
+    enum Item {
+        Action(String, Action),
+        Header(String),
+        Separator,
+    }
+
+    // Clickable items are tagged with their index here
+    static ITEMS: Mutex<Vec<Item>> = Mutex::new(Vec::new());
+    static APP: OnceLock<AppHandle> = OnceLock::new();
+    static INSTALL: Once = Once::new();
+
+    thread_local! {
+        static TARGET: OnceCell<Retained<DockMenuTarget>> = const { OnceCell::new() };
+    }
+
+    define_class!(
+        #[unsafe(super(NSObject))]
+        #[thread_kind = MainThreadOnly]
+        #[name = "IncitoDockMenuTarget"]
+        struct DockMenuTarget;
+
+        impl DockMenuTarget {
+            #[unsafe(method(incitoDockItem:))]
+            fn item_clicked(&self, sender: &NSMenuItem) {
+                let action = ITEMS.lock().ok().and_then(|items| {
+                    let index = usize::try_from(sender.tag()).ok()?;
+                    match items.get(index)? {
+                        Item::Action(_, action) => Some(action.clone()),
+                        _ => None,
+                    }
+                });
+                if let (Some(app), Some(action)) = (APP.get(), action) {
+                    super::perform(app, action);
+                }
+            }
+        }
+    );
+
+    unsafe extern "C-unwind" fn dock_menu(_this: *mut AnyObject, _cmd: Sel, _sender: *mut AnyObject) -> *mut NSMenu {
+        let Some(mtm) = MainThreadMarker::new() else { return std::ptr::null_mut() };
+        let target = TARGET.with(|target| {
+            target
+                .get_or_init(|| unsafe { msg_send![DockMenuTarget::alloc(mtm), init] })
+                .clone()
+        });
+
+        let menu = NSMenu::new(mtm);
+        let items = ITEMS.lock().map(|items| items.clone()).unwrap_or_default();
+        for (index, item) in items.iter().enumerate() {
+            let menu_item = match item {
+                Item::Separator => NSMenuItem::separatorItem(mtm),
+                // No action, so AppKit shows it disabled
+                Item::Header(title) => unsafe {
+                    NSMenuItem::initWithTitle_action_keyEquivalent(
+                        mtm.alloc(),
+                        &NSString::from_str(title),
+                        None,
+                        &NSString::from_str(""),
+                    )
+                },
+                Item::Action(label, _) => unsafe {
+                    let menu_item = NSMenuItem::initWithTitle_action_keyEquivalent(
+                        mtm.alloc(),
+                        &NSString::from_str(label),
+                        Some(sel!(incitoDockItem:)),
+                        &NSString::from_str(""),
+                    );
+                    menu_item.setTarget(Some(&target));
+                    menu_item.setTag(index as isize);
+                    menu_item
+                },
+            };
+            menu.addItem(&menu_item);
+        }
+        Retained::autorelease_return(menu)
+    }
+
+    fn install(app: &AppHandle) -> Result<(), String> {
+        let _ = APP.set(app.clone());
+        let mtm = MainThreadMarker::new().ok_or_else(|| "Dock menu must be set up on the main thread".to_string())?;
+        let delegate = NSApplication::sharedApplication(mtm)
+            .delegate()
+            .ok_or_else(|| "App delegate is not set".to_string())?;
+        let delegate: &AnyObject = delegate.as_ref();
+        unsafe {
+            let imp: Imp = std::mem::transmute::<
+                unsafe extern "C-unwind" fn(*mut AnyObject, Sel, *mut AnyObject) -> *mut NSMenu,
+                Imp,
+            >(dock_menu);
+            objc2::ffi::class_addMethod(
+                delegate.class() as *const _ as *mut _,
+                sel!(applicationDockMenu:),
+                imp,
+                c"@@:@".as_ptr(),
+            );
+        }
+        Ok(())
+    }
+
+    pub fn set(app: &AppHandle, dock_menu: DockMenu) -> Result<(), String> {
+        let mut items: Vec<Item> = dock_menu
+            .tasks
+            .into_iter()
+            .map(|entry| Item::Action(entry.label, entry.action))
+            .collect();
+        if !dock_menu.recent.is_empty() {
+            items.push(Item::Separator);
+            items.push(Item::Header(dock_menu.recent_title));
+            items.extend(dock_menu.recent.into_iter().map(|entry| Item::Action(entry.label, entry.action)));
+        }
+        *ITEMS.lock().map_err(|e| e.to_string())? = items;
+
+        let handle = app.clone();
+        app.run_on_main_thread(move || {
+            INSTALL.call_once(|| {
+                if let Err(e) = install(&handle) {
+                    eprintln!("{}", e);
+                }
+            });
+        })
+        .map_err(|e| e.to_string())
+    }
+}
+
+// Tasks and a "Recent Projects" category in the taskbar jump list. Entries
+// relaunch the executable with arguments; the single instance plugin hands
+// them to the running app.
+#[cfg(windows)]
+mod platform {
+    use tauri::AppHandle;
+    use windows::core::{Interface, HSTRING};
+    use windows::Win32::Foundation::PROPERTYKEY;
+    use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+    use windows::Win32::System::Com::StructuredStorage::{PropVariantChangeType, PROPVARIANT, PROPVAR_CHANGE_FLAGS};
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED};
+    use windows::Win32::System::Variant::VT_LPWSTR;
+    use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+    use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+    use windows::Win32::UI::Shell::{
+        DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW, ShellLink,
+    };
+
+    use super::{DockMenu, Entry};
+
+    fn set_title(link: &IShellLinkW, key: &PROPERTYKEY, title: &str) -> windows::core::Result<()> {
+        let store: IPropertyStore = link.cast()?;
+        let mut value = PROPVARIANT::default();
+        unsafe {
+            PropVariantChangeType(&mut value, &PROPVARIANT::from(title), PROPVAR_CHANGE_FLAGS(0), VT_LPWSTR)?;
+            store.SetValue(key, &value)?;
+            store.Commit()
+        }
+    }
+
+    fn collection(exe: &HSTRING, entries: &[Entry]) -> windows::core::Result<IObjectCollection> {
+        let collection: IObjectCollection =
+            unsafe { CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)? };
+        for entry in entries {
+            let link: IShellLinkW = unsafe { CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)? };
+            unsafe {
+                link.SetPath(exe)?;
+                link.SetArguments(&HSTRING::from(entry.action.to_args()))?;
+                link.SetIconLocation(exe, 0)?;
+            }
+            set_title(&link, &PKEY_Title, &entry.label)?;
+            unsafe { collection.AddObject(&link)? };
+        }
+        Ok(collection)
+    }
+
+    fn commit(dock_menu: &DockMenu) -> windows::core::Result<()> {
+        let exe = std::env::current_exe().map_err(|e| windows::core::Error::new(windows::core::HRESULT(-1), e.to_string()))?;
+        let exe = HSTRING::from(exe.as_os_str());
+        unsafe {
+            // Already initialised by the webview on this thread
+            let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+            let list: ICustomDestinationList = CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+            let mut slots = 0u32;
+            let _removed: IObjectArray = list.BeginList(&mut slots)?;
+            list.AddUserTasks(&collection(&exe, &dock_menu.tasks)?.cast::<IObjectArray>()?)?;
+            if !dock_menu.recent.is_empty() {
+                list.AppendCategory(
+                    &HSTRING::from(dock_menu.recent_title.as_str()),
+                    &collection(&exe, &dock_menu.recent)?.cast::<IObjectArray>()?,
+                )?;
+            }
+            list.CommitList()
+        }
+    }
+
+    pub fn set(app: &AppHandle, dock_menu: DockMenu) -> Result<(), String> {
+        app.run_on_main_thread(move || {
+            if let Err(e) = commit(&dock_menu) {
+                eprintln!("Failed to update jump list: {}", e);
+            }
+        })
+        .map_err(|e| e.to_string())
+    }
+}
+
+// Linux desktops have no equivalent that can be set at runtime
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use tauri::AppHandle;
+
+    use super::DockMenu;
+
+    pub fn set(_app: &AppHandle, _dock_menu: DockMenu) -> Result<(), String> {
+        Ok(())
+    }
+}
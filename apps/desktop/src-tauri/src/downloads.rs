@@ -0,0 +1,477 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::header::{CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::StatusCode;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{watch, Semaphore};
+
+use crate::net;
+use crate::verify::{self, VerifyError};
+
+// More than this wait their turn as `queued`
+const MAX_CONCURRENT: usize = 2;
+// In a row without any bytes arriving; each retry resumes where the last
+// one stopped
+const MAX_ATTEMPTS: u32 = 4;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+// A stalled connection rather than a slow one
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+// `label` names the file in events and errors, e.g. "claude-code-server".
+// With `sha256` the file is only handed over once it matches.
+pub struct Request {
+    pub url: String,
+    pub label: String,
+    pub sha256: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Queued,
+    Downloading,
+    Paused,
+    Verifying,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+// As emitted in `download-progress`, on every status change and a few
+// times a second while bytes arrive
+#[derive(Clone, serde::Serialize)]
+pub struct DownloadInfo {
+    pub id: String,
+    pub label: String,
+    pub url: String,
+    pub status: Status,
+    pub received: u64,
+    pub total: Option<u64>,
+    pub bytes_per_second: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Control {
+    Run,
+    Pause,
+    Cancel,
+}
+
+struct Job {
+    info: DownloadInfo,
+    // The partial file's name, so the same URL isn't downloaded twice at once
+    key: String,
+    control: watch::Sender<Control>,
+}
+
+pub struct Downloads {
+    jobs: Mutex<HashMap<String, Job>>,
+    slots: Arc<Semaphore>,
+}
+
+impl Default for Downloads {
+    fn default() -> Self {
+        Self {
+            jobs: Mutex::default(),
+            slots: Arc::new(Semaphore::new(MAX_CONCURRENT)),
+        }
+    }
+}
+
+// Kept next to a partial download so it's only resumed from the same URL,
+// and only while the server still has the same file
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Partial {
+    url: String,
+    validator: Option<String>,
+}
+
+enum Stop {
+    Cancelled,
+    Failed(VerifyError),
+}
+
+impl From<String> for Stop {
+    fn from(message: String) -> Self {
+        Stop::Failed(message.into())
+    }
+}
+
+enum Interrupted {
+    Paused,
+    Cancelled,
+    Failed { error: String, retry: bool, progressed: bool },
+}
+
+fn failed(error: impl ToString, retry: bool, progressed: bool) -> Interrupted {
+    Interrupted::Failed {
+        error: error.to_string(),
+        retry,
+        progressed,
+    }
+}
+
+fn downloads_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join("downloads"))
+        .map_err(|e| format!("Failed to resolve app cache dir: {}", e))
+}
+
+// No overall timeout, which would cut off a large file on a slow link
+fn client(app: &AppHandle) -> Result<reqwest::Client, String> {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let builder = reqwest::Client::builder()
+        .user_agent("Incito")
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT);
+    net::apply(builder, &net::client_options(&net::current(app))?)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+fn update(app: &AppHandle, id: &str, change: impl FnOnce(&mut DownloadInfo)) {
+    let downloads = app.state::<Downloads>();
+    let info = match downloads.jobs.lock() {
+        Ok(mut jobs) => jobs.get_mut(id).map(|job| {
+            change(&mut job.info);
+            job.info.clone()
+        }),
+        Err(_) => None,
+    };
+    if let Some(info) = info {
+        let _ = app.emit("download-progress", info);
+    }
+}
+
+fn set_status(app: &AppHandle, id: &str, status: Status) {
+    update(app, id, |info| {
+        info.status = status;
+        info.bytes_per_second = 0;
+    });
+}
+
+// Returns once the download may go on
+async fn wait_while_paused(app: &AppHandle, id: &str, control: &mut watch::Receiver<Control>) -> Result<(), Stop> {
+    loop {
+        let requested = *control.borrow_and_update();
+        match requested {
+            Control::Run => return Ok(()),
+            Control::Cancel => return Err(Stop::Cancelled),
+            Control::Pause => {}
+        }
+        set_status(app, id, Status::Paused);
+        if control.changed().await.is_err() {
+            return Err(Stop::Cancelled);
+        }
+    }
+}
+
+fn content_range(response: &reqwest::Response) -> Option<(u64, Option<u64>)> {
+    // bytes 100-199/200, or bytes 100-199/* when the size isn't known
+    let value = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let start = range.split_once('-')?.0.parse().ok()?;
+    Some((start, total.parse().ok()))
+}
+
+// If-Range only works with a strong ETag, or else the modification time
+fn validator(response: &reqwest::Response) -> Option<String> {
+    let header = |name| response.headers().get(name).and_then(|value| value.to_str().ok());
+    header(ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| header(LAST_MODIFIED))
+        .map(|value| value.to_string())
+}
+
+async fn remove_partial(part: &Path, meta: &Path) {
+    let _ = tokio::fs::remove_file(part).await;
+    let _ = tokio::fs::remove_file(meta).await;
+}
+
+// One request, from wherever the partial file left off
+async fn transfer(
+    app: &AppHandle,
+    id: &str,
+    client: &reqwest::Client,
+    url: &str,
+    (part, meta): (&Path, &Path),
+    control: &mut watch::Receiver<Control>,
+) -> Result<(), Interrupted> {
+    let saved = tokio::fs::read(meta)
+        .await
+        .ok()
+        .and_then(|contents| serde_json::from_slice::<Partial>(&contents).ok())
+        .filter(|saved| saved.url == url && saved.validator.is_some());
+    let offset = match saved {
+        Some(_) => tokio::fs::metadata(part).await.map(|metadata| metadata.len()).unwrap_or(0),
+        None => 0,
+    };
+
+    let mut request = client.get(url);
+    if let Some(validator) = saved.and_then(|saved| saved.validator).filter(|_| offset > 0) {
+        request = request
+            .header(RANGE, format!("bytes={}-", offset))
+            .header(IF_RANGE, validator);
+    }
+    let mut response = request.send().await.map_err(|e| failed(e, true, false))?;
+    let status = response.status();
+    if status == StatusCode::RANGE_NOT_SATISFIABLE {
+        remove_partial(part, meta).await;
+        return Err(failed("the partial download no longer fits the file", true, false));
+    }
+    if !status.is_success() {
+        let retry = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+        return Err(failed(format!("the server answered {}", status), retry, false));
+    }
+
+    // A 200 to a range request means the file changed, so it starts over
+    let range = content_range(&response).filter(|_| status == StatusCode::PARTIAL_CONTENT);
+    let resumed = offset > 0 && range.is_some_and(|(start, _)| start == offset);
+    if status == StatusCode::PARTIAL_CONTENT && !resumed {
+        remove_partial(part, meta).await;
+        return Err(failed("the server sent a different part of the file", true, false));
+    }
+    let (mut received, total) = match range {
+        Some((start, total)) if resumed => (start, total),
+        _ => (0, response.content_length()),
+    };
+
+    let partial = Partial {
+        url: url.to_string(),
+        validator: validator(&response),
+    };
+    let contents = serde_json::to_vec(&partial).map_err(|e| failed(e, false, false))?;
+    tokio::fs::write(meta, contents)
+        .await
+        .map_err(|e| failed(format!("Failed to save download state: {}", e), false, false))?;
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(part)
+        .await
+        .map_err(|e| failed(format!("Failed to open {}: {}", part.display(), e), false, false))?;
+
+    update(app, id, |info| {
+        info.status = Status::Downloading;
+        info.received = received;
+        info.total = total;
+        info.bytes_per_second = 0;
+    });
+    let (mut last_emit, mut last_received, mut progressed) = (Instant::now(), received, false);
+    loop {
+        let chunk = tokio::select! {
+            chunk = response.chunk() => chunk.map_err(|e| failed(e, true, progressed))?,
+            _ = control.changed() => {
+                let requested = *control.borrow_and_update();
+                match requested {
+                    Control::Run => continue,
+                    Control::Pause => {
+                        let _ = file.flush().await;
+                        return Err(Interrupted::Paused);
+                    }
+                    Control::Cancel => return Err(Interrupted::Cancelled),
+                }
+            }
+        };
+        let Some(chunk) = chunk else { break };
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| failed(format!("Failed to write {}: {}", part.display(), e), false, progressed))?;
+        received += chunk.len() as u64;
+        progressed = true;
+
+        let elapsed = last_emit.elapsed();
+        if elapsed >= PROGRESS_INTERVAL {
+            let bytes_per_second = ((received - last_received) as f64 / elapsed.as_secs_f64()) as u64;
+            update(app, id, |info| {
+                info.received = received;
+                info.bytes_per_second = bytes_per_second;
+            });
+            (last_emit, last_received) = (Instant::now(), received);
+        }
+    }
+    file.flush()
+        .await
+        .map_err(|e| failed(format!("Failed to write {}: {}", part.display(), e), false, progressed))?;
+    if total.is_some_and(|total| received < total) {
+        return Err(failed("the connection closed early", true, progressed));
+    }
+    update(app, id, |info| info.received = received);
+    Ok(())
+}
+
+async fn run(
+    app: &AppHandle,
+    id: &str,
+    request: &Request,
+    path: &Path,
+    control: &mut watch::Receiver<Control>,
+) -> Result<(), Stop> {
+    let client = client(app)?;
+    let slots = app.state::<Downloads>().slots.clone();
+    let (part, meta) = (path.with_extension("part"), path.with_extension("part.json"));
+    let mut attempts = 0;
+    loop {
+        wait_while_paused(app, id, control).await?;
+        // Pausing or cancelling while queued takes effect straight away
+        let permit = tokio::select! {
+            permit = slots.clone().acquire_owned() => permit.map_err(|e| e.to_string())?,
+            _ = control.changed() => continue,
+        };
+        let result = transfer(app, id, &client, &request.url, (&part, &meta), control).await;
+        drop(permit);
+        match result {
+            Ok(()) => break,
+            Err(Interrupted::Paused) => continue,
+            Err(Interrupted::Cancelled) => return Err(Stop::Cancelled),
+            Err(Interrupted::Failed {
+                error,
+                retry,
+                progressed,
+            }) => {
+                attempts = if progressed { 1 } else { attempts + 1 };
+                // The partial file stays, so a later download of the same
+                // URL picks up from there
+                if !retry || attempts >= MAX_ATTEMPTS {
+                    return Err(format!("Failed to download {}: {}", request.label, error).into());
+                }
+                set_status(app, id, Status::Queued);
+                tokio::select! {
+                    _ = tokio::time::sleep(RETRY_DELAY * 2u32.pow(attempts - 1)) => {}
+                    _ = control.changed() => {}
+                }
+            }
+        }
+    }
+
+    set_status(app, id, Status::Verifying);
+    if let Some(expected) = request.sha256.clone() {
+        let (label, checked_part) = (request.label.clone(), part.clone());
+        let checked = tauri::async_runtime::spawn_blocking(move || {
+            verify::check_sha256_file(&label, &checked_part, Some(&expected))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+        // A corrupt partial file would only fail again
+        if let Err(error) = checked {
+            remove_partial(&part, &meta).await;
+            return Err(Stop::Failed(error));
+        }
+    }
+    let _ = tokio::fs::remove_file(path).await;
+    tokio::fs::rename(&part, path)
+        .await
+        .map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+    let _ = tokio::fs::remove_file(&meta).await;
+    Ok(())
+}
+
+// Download `request.url` into the app's cache, resuming a partial file left
+// by an earlier attempt, and return the finished file. It can be paused and
+// cancelled from the frontend, and only runs alongside a few others.
+pub async fn download(app: &AppHandle, request: Request) -> Result<PathBuf, VerifyError> {
+    let dir = downloads_dir(app)?;
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let key = verify::sha256_hex(request.url.as_bytes())[..16].to_string();
+    let path = dir.join(&key);
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let (control, mut control_rx) = watch::channel(Control::Run);
+    {
+        let downloads = app.state::<Downloads>();
+        let mut jobs = downloads.jobs.lock().map_err(|e| e.to_string())?;
+        if jobs.values().any(|job| job.key == key) {
+            return Err(format!("{} is already being downloaded", request.label).into());
+        }
+        let info = DownloadInfo {
+            id: id.clone(),
+            label: request.label.clone(),
+            url: request.url.clone(),
+            status: Status::Queued,
+            received: 0,
+            total: None,
+            bytes_per_second: 0,
+            error: None,
+        };
+        jobs.insert(id.clone(), Job { info, key, control });
+    }
+    set_status(app, &id, Status::Queued);
+
+    let result = run(app, &id, &request, &path, &mut control_rx).await;
+    let result = match result {
+        Ok(()) => {
+            set_status(app, &id, Status::Completed);
+            Ok(path)
+        }
+        Err(Stop::Cancelled) => {
+            remove_partial(&path.with_extension("part"), &path.with_extension("part.json")).await;
+            set_status(app, &id, Status::Cancelled);
+            Err(format!("Download of {} cancelled", request.label).into())
+        }
+        Err(Stop::Failed(error)) => {
+            update(app, &id, |info| {
+                info.status = Status::Failed;
+                info.error = Some(error.to_string());
+            });
+            Err(error)
+        }
+    };
+    if let Ok(mut jobs) = app.state::<Downloads>().jobs.lock() {
+        jobs.remove(&id);
+    }
+    result
+}
+
+// For files small enough to handle in memory, which don't stay in the cache
+pub async fn download_bytes(app: &AppHandle, request: Request) -> Result<Vec<u8>, VerifyError> {
+    let path = download(app, request).await?;
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+    let _ = tokio::fs::remove_file(&path).await;
+    Ok(bytes?)
+}
+
+fn send(app: &AppHandle, id: &str, control: Control) -> Result<(), String> {
+    let downloads = app.state::<Downloads>();
+    let jobs = downloads.jobs.lock().map_err(|e| e.to_string())?;
+    let job = jobs.get(id).ok_or_else(|| format!("No download with id {}", id))?;
+    job.control.send_replace(control);
+    Ok(())
+}
+
+// Downloads in progress, queued or paused
+#[tauri::command]
+pub fn list_downloads(app: AppHandle) -> Result<Vec<DownloadInfo>, String> {
+    let downloads = app.state::<Downloads>();
+    let jobs = downloads.jobs.lock().map_err(|e| e.to_string())?;
+    Ok(jobs.values().map(|job| job.info.clone()).collect())
+}
+
+#[tauri::command]
+pub fn pause_download(app: AppHandle, id: String) -> Result<(), String> {
+    send(&app, &id, Control::Pause)
+}
+
+#[tauri::command]
+pub fn resume_download(app: AppHandle, id: String) -> Result<(), String> {
+    send(&app, &id, Control::Run)
+}
+
+// The partial file is removed, so nothing is left to resume
+#[tauri::command]
+pub fn cancel_download(app: AppHandle, id: String) -> Result<(), String> {
+    send(&app, &id, Control::Cancel)
+}
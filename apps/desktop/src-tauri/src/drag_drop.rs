@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use tauri::{AppHandle, Emitter, Manager, Window};
+
+use crate::projects;
+
+// The same limits the chat input applies to picked files
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+const MAX_TOTAL_BYTES: u64 = 20 * 1024 * 1024;
+const MAX_FILES: usize = 5;
+
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("pdf", "application/pdf"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("json", "application/json"),
+    ("csv", "text/csv"),
+];
+
+#[derive(Clone, serde::Serialize)]
+struct ProjectDropped {
+    path: String,
+    project_id: String,
+}
+
+// The fields of the frontend's ChatAttachment, less the preview URL
+#[derive(Clone, serde::Serialize)]
+struct DroppedAttachment {
+    id: String,
+    file_name: String,
+    mime_type: String,
+    base64_data: String,
+    // "image", "pdf" or "text"
+    #[serde(rename = "type")]
+    kind: &'static str,
+    size: u64,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct RejectedFile {
+    path: String,
+    // "unsupported", "too_large", "too_many", "total_too_large" or
+    // "unreadable"
+    reason: &'static str,
+}
+
+#[derive(Clone, Default, serde::Serialize)]
+struct FilesDropped {
+    attachments: Vec<DroppedAttachment>,
+    rejected: Vec<RejectedFile>,
+}
+
+fn mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    MIME_TYPES
+        .iter()
+        .find(|(known, _)| *known == extension)
+        .map(|(_, mime_type)| *mime_type)
+}
+
+fn kind(mime_type: &str) -> &'static str {
+    if mime_type.starts_with("image/") {
+        "image"
+    } else if mime_type == "application/pdf" {
+        "pdf"
+    } else {
+        "text"
+    }
+}
+
+fn attachment(path: &Path, total: u64) -> Result<DroppedAttachment, &'static str> {
+    let mime_type = mime_type(path).ok_or("unsupported")?;
+    let metadata = std::fs::metadata(path).map_err(|_| "unreadable")?;
+    if metadata.len() > MAX_FILE_BYTES {
+        return Err("too_large");
+    }
+    if total + metadata.len() > MAX_TOTAL_BYTES {
+        return Err("total_too_large");
+    }
+    let bytes = std::fs::read(path).map_err(|_| "unreadable")?;
+    Ok(DroppedAttachment {
+        id: uuid::Uuid::new_v4().to_string(),
+        file_name: path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+        mime_type: mime_type.to_string(),
+        base64_data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+        kind: kind(mime_type),
+        size: bytes.len() as u64,
+    })
+}
+
+// Read dropped files into attachments, since the webview can't read paths
+// outside its fs scope
+fn stage(files: Vec<PathBuf>) -> FilesDropped {
+    let mut dropped = FilesDropped::default();
+    let mut total = 0;
+    for path in files {
+        let result = if dropped.attachments.len() == MAX_FILES {
+            Err("too_many")
+        } else {
+            attachment(&path, total)
+        };
+        match result {
+            Ok(attachment) => {
+                total += attachment.size;
+                dropped.attachments.push(attachment);
+            }
+            Err(reason) => dropped.rejected.push(RejectedFile {
+                path: path.to_string_lossy().to_string(),
+                reason,
+            }),
+        }
+    }
+    dropped
+}
+
+async fn handle_drop(app: &AppHandle, label: &str, paths: Vec<PathBuf>) {
+    let (folders, files): (Vec<PathBuf>, Vec<PathBuf>) = paths.into_iter().partition(|path| path.is_dir());
+
+    // A dropped folder becomes a project, which also lets the webview in
+    for folder in folders {
+        let path = folder.to_string_lossy().to_string();
+        match projects::register_project(app.clone(), path.clone(), None).await {
+            Ok(project) => {
+                let _ = app.emit_to(
+                    label,
+                    "project-dropped",
+                    ProjectDropped {
+                        path,
+                        project_id: project.id,
+                    },
+                );
+            }
+            Err(e) => {
+                let _ = app.emit_to(label, "menu-action-failed", e);
+            }
+        }
+    }
+
+    if files.is_empty() {
+        return;
+    }
+    match tauri::async_runtime::spawn_blocking(move || stage(files)).await {
+        Ok(dropped) => {
+            let _ = app.emit_to(label, "files-dropped", dropped);
+        }
+        Err(e) => eprintln!("Failed to read dropped files: {}", e),
+    }
+}
+
+// From the window event handler: emits `project-dropped` for each folder
+// and `files-dropped` with the files as attachments, to that window only
+pub fn on_drop(window: &Window, paths: &[PathBuf]) {
+    let app = window.app_handle().clone();
+    let label = window.label().to_string();
+    let paths = paths.to_vec();
+    tauri::async_runtime::spawn(async move {
+        handle_drop(&app, &label, paths).await;
+    });
+}
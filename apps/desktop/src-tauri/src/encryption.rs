@@ -0,0 +1,107 @@
+use std::path::Path;
+
+use sqlx::SqliteConnection;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{app_lock, backup, db, fs_util};
+
+const KEYCHAIN_SERVICE: &str = "Incito";
+const KEYCHAIN_ACCOUNT: &str = "database-key";
+
+#[derive(Clone, serde::Serialize)]
+pub struct DatabaseEncryption {
+    pub encrypted: bool,
+}
+
+fn keychain_entry() -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_ACCOUNT).map_err(|e| format!("Failed to open the system keychain: {}", e))
+}
+
+// The hex-encoded 256-bit key the database is encrypted with, if one was
+// ever created
+pub fn saved_key() -> Result<Option<String>, String> {
+    match keychain_entry()?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read the database key from the system keychain: {}", e)),
+    }
+}
+
+// A raw key, so SQLCipher uses it as is instead of deriving one from it
+pub fn key_pragma(key: &str) -> String {
+    format!("\"x'{}'\"", key)
+}
+
+fn new_key() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Failed to generate a database key: {}", e))?;
+    Ok(hex::encode(bytes))
+}
+
+// Export everything in the database `connection` is on into a new SQLCipher
+// file at `dest`, keyed with `key`. ATTACH only applies to one connection,
+// so the export runs on it.
+pub async fn export_encrypted(connection: &mut SqliteConnection, dest: &Path, key: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("ATTACH DATABASE ? AS encrypted KEY ?")
+        .bind(dest.to_string_lossy().into_owned())
+        .bind(format!("x'{}'", key))
+        .execute(&mut *connection)
+        .await?;
+    let exported = sqlx::query("SELECT sqlcipher_export('encrypted')").execute(&mut *connection).await;
+    sqlx::query("DETACH DATABASE encrypted").execute(&mut *connection).await?;
+    exported.map(|_| ())
+}
+
+#[tauri::command]
+pub fn get_database_encryption(app: AppHandle) -> Result<DatabaseEncryption, String> {
+    Ok(DatabaseEncryption {
+        encrypted: db::is_encrypted(&db::path(&app)?),
+    })
+}
+
+// One-way switch to an encrypted database: export everything into a new
+// SQLCipher file keyed from the keychain and swap it in. The plaintext file
+// is deleted. Emits `db-encrypted` once the frontend's queries work again.
+#[tauri::command]
+pub async fn encrypt_existing_database(app: AppHandle) -> Result<DatabaseEncryption, String> {
+    let job = app.state::<backup::DatabaseJob>();
+    let _guard = job
+        .0
+        .try_lock()
+        .map_err(|_| "A database backup or restore is already running".to_string())?;
+
+    let path = db::path(&app)?;
+    if db::is_encrypted(&path) {
+        return Err("The database is already encrypted".to_string());
+    }
+
+    // Reuse a key a failed earlier attempt saved, and save a new one before
+    // any data depends on it. Not while the app is locked.
+    app_lock::ensure_unlocked(&app)?;
+    let key = match saved_key()? {
+        Some(key) => key,
+        None => {
+            let key = new_key()?;
+            keychain_entry()?
+                .set_password(&key)
+                .map_err(|e| format!("Failed to save the database key to the system keychain: {}", e))?;
+            key
+        }
+    };
+
+    let staged = fs_util::temp_path_for(&path);
+    let pool = db::pool(&app).await?;
+    let export = async {
+        let mut connection = pool.acquire().await?;
+        export_encrypted(&mut connection, &staged, &key).await
+    }
+    .await;
+    if let Err(e) = export {
+        let _ = std::fs::remove_file(&staged);
+        return Err(format!("Failed to encrypt the database: {}", e));
+    }
+
+    backup::replace_database(&app, &staged, false).await?;
+    let _ = app.emit("db-encrypted", ());
+    Ok(DatabaseEncryption { encrypted: true })
+}
@@ -0,0 +1,206 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+// Enough of the start of a file to tell text from binary
+const SNIFF_BYTES: usize = 64 * 1024;
+// The most one read hands to the webview at once
+const MAX_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Clone, Copy)]
+enum Encoding {
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    fn bom_len(self) -> u64 {
+        match self {
+            Encoding::Utf8 => 0,
+            Encoding::Utf8Bom => 3,
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf8Bom => "utf-8-bom",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct FileStat {
+    pub path: String,
+    pub size: u64,
+    pub modified_at: Option<String>,
+    pub binary: bool,
+    // "utf-8", "utf-8-bom", "utf-16le", "utf-16be", or "unknown" for text
+    // that isn't valid UTF-8; none for binaries
+    pub encoding: Option<&'static str>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct FileChunk {
+    // Where the text starts, which may be after the offset asked for so
+    // as not to split a character
+    pub offset: u64,
+    pub next_offset: u64,
+    pub text: String,
+    pub size: u64,
+    pub eof: bool,
+}
+
+fn bom(head: &[u8]) -> Option<Encoding> {
+    if head.starts_with(&[0xEF, 0xBB, 0xBF]) {
+        Some(Encoding::Utf8Bom)
+    } else if head.starts_with(&[0xFF, 0xFE]) {
+        Some(Encoding::Utf16Le)
+    } else if head.starts_with(&[0xFE, 0xFF]) {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+fn read_at(file: &mut File, offset: u64, length: u64) -> std::io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::with_capacity(length as usize);
+    file.take(length).read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn open(path: &Path) -> Result<(File, u64), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let metadata = file
+        .metadata()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if metadata.is_dir() {
+        return Err(format!("Not a file: {}", path.display()));
+    }
+    Ok((file, metadata.len()))
+}
+
+fn stat(path: &Path) -> Result<FileStat, String> {
+    let (mut file, size) = open(path)?;
+    let head =
+        read_at(&mut file, 0, SNIFF_BYTES as u64).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let modified_at = file
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(|modified| chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339());
+
+    let encoding = match bom(&head) {
+        Some(encoding) => Some(encoding.name()),
+        // A NUL outside UTF-16 marks a binary, as git does
+        None if head.contains(&0) => None,
+        None => match std::str::from_utf8(&head) {
+            Ok(_) => Some("utf-8"),
+            // The sniff may have cut the last character short
+            Err(e) if e.error_len().is_none() => Some("utf-8"),
+            Err(_) => Some("unknown"),
+        },
+    };
+    Ok(FileStat {
+        path: path.to_string_lossy().to_string(),
+        size,
+        modified_at,
+        binary: encoding.is_none(),
+        encoding,
+    })
+}
+
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+fn read_chunk(path: &Path, offset: u64, length: u64) -> Result<FileChunk, String> {
+    let (mut file, size) = open(path)?;
+    let read_error = |e: std::io::Error| format!("Failed to read {}: {}", path.display(), e);
+    let head = read_at(&mut file, 0, 4).map_err(read_error)?;
+    let encoding = bom(&head).unwrap_or(Encoding::Utf8);
+
+    let mut start = offset.max(encoding.bom_len()).min(size);
+    let mut end = start.saturating_add(length.min(MAX_CHUNK_BYTES)).min(size);
+    let utf16 = matches!(encoding, Encoding::Utf16Le | Encoding::Utf16Be);
+    if utf16 {
+        // Code units start on even offsets after the BOM
+        start = (start + start % 2).min(size);
+        end -= end % 2;
+        end = end.max(start);
+    }
+    let mut bytes = read_at(&mut file, start, end - start).map_err(read_error)?;
+
+    let text = if utf16 {
+        // A surrogate pair split across the end waits for the next chunk
+        let mut units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| match encoding {
+                Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                _ => u16::from_be_bytes([pair[0], pair[1]]),
+            })
+            .collect();
+        if end < size && units.last().is_some_and(|unit| (0xD800..0xDC00).contains(unit)) {
+            units.pop();
+            end -= 2;
+        }
+        char::decode_utf16(units)
+            .map(|unit| unit.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    } else {
+        // Skip the tail of a character the offset landed in, and leave one
+        // cut off at the end for the next chunk
+        let lead = bytes.iter().take(3).take_while(|byte| is_continuation(**byte)).count();
+        bytes.drain(..lead);
+        start += lead as u64;
+        if end < size {
+            if let Some(last_lead) = bytes.iter().rposition(|byte| !is_continuation(*byte)) {
+                let width = match bytes[last_lead] {
+                    byte if byte >= 0xF0 => 4,
+                    byte if byte >= 0xE0 => 3,
+                    byte if byte >= 0xC0 => 2,
+                    _ => 1,
+                };
+                if last_lead + width > bytes.len() && last_lead > 0 {
+                    end -= (bytes.len() - last_lead) as u64;
+                    bytes.truncate(last_lead);
+                }
+            }
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+
+    Ok(FileChunk {
+        offset: start,
+        next_offset: end,
+        text,
+        size,
+        eof: end >= size,
+    })
+}
+
+// Size, encoding and whether the file is binary, from its first 64 KB, so
+// the viewer can decide how to open it before reading it
+#[tauri::command]
+pub async fn stat_file(path: String) -> Result<FileStat, String> {
+    let path = PathBuf::from(path);
+    tauri::async_runtime::spawn_blocking(move || stat(&path))
+        .await
+        .map_err(|e| format!("Read task failed: {}", e))?
+}
+
+// Up to `length` bytes of text from `offset`, at most 4 MB at a time.
+// Chunks break on character boundaries; read on from `next_offset`.
+#[tauri::command]
+pub async fn read_file_chunked(path: String, offset: u64, length: u64) -> Result<FileChunk, String> {
+    let path = PathBuf::from(path);
+    tauri::async_runtime::spawn_blocking(move || read_chunk(&path, offset, length))
+        .await
+        .map_err(|e| format!("Read task failed: {}", e))?
+}
@@ -0,0 +1,186 @@
+use serde_json::{Map, Value};
+
+// A markdown file with an optional `---` delimited header, as used by
+// Claude Code for slash commands and agent definitions
+pub struct Document {
+    pub frontmatter: Map<String, Value>,
+    pub body: String,
+}
+
+// Only the flat subset of YAML those headers use is understood: `key: value`
+// pairs, inline `[a, b]` lists and indented `- item` lists. Anything else is
+// kept as a plain string.
+pub fn parse(text: &str) -> Document {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return Document {
+            frontmatter: Map::new(),
+            body: text.to_string(),
+        };
+    };
+
+    let mut header = Vec::new();
+    let mut body_start = None;
+    let mut position = 0;
+    for line in rest.split_inclusive('\n') {
+        position += line.len();
+        if line.trim_end() == "---" {
+            body_start = Some(position);
+            break;
+        }
+        header.push(line.trim_end());
+    }
+    // An unterminated header is just part of the body
+    let Some(body_start) = body_start else {
+        return Document {
+            frontmatter: Map::new(),
+            body: text.to_string(),
+        };
+    };
+
+    let mut frontmatter = Map::new();
+    let mut list_key: Option<String> = None;
+    for line in header {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+        if let (Some(key), Some(item)) = (&list_key, line.trim_start().strip_prefix("- ")) {
+            if let Some(Value::Array(items)) = frontmatter.get_mut(key) {
+                items.push(Value::String(unquote(item.trim())));
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let key = key.trim().to_string();
+        let value = value.trim();
+        if value.is_empty() {
+            frontmatter.insert(key.clone(), Value::Array(Vec::new()));
+            list_key = Some(key);
+            continue;
+        }
+        list_key = None;
+        frontmatter.insert(key, parse_value(value));
+    }
+
+    // A key followed by nothing at all was an empty scalar, not a list
+    for value in frontmatter.values_mut() {
+        if value.as_array().is_some_and(Vec::is_empty) {
+            *value = Value::String(String::new());
+        }
+    }
+
+    Document {
+        frontmatter,
+        body: rest[body_start..].trim_start_matches(['\r', '\n']).to_string(),
+    }
+}
+
+fn parse_value(value: &str) -> Value {
+    if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+        return Value::Array(
+            split_list(inner)
+                .into_iter()
+                .map(|item| Value::String(unquote(&item)))
+                .collect(),
+        );
+    }
+    match value {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::String(unquote(value)),
+    }
+}
+
+// Split on commas outside quotes and parentheses, so `Bash(git add:*), Read`
+// stays two items
+fn split_list(inner: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut quote = None;
+    for c in inner.chars() {
+        match c {
+            '"' | '\'' if quote.is_none() => quote = Some(c),
+            c if Some(c) == quote => quote = None,
+            '(' if quote.is_none() => depth += 1,
+            ')' if quote.is_none() => depth -= 1,
+            ',' if quote.is_none() && depth == 0 => {
+                items.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        items.push(current.trim().to_string());
+    }
+    items
+}
+
+// Tool lists are written either as a comma separated string or a list
+pub fn list_field(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Array(items)) => items.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+        Some(Value::String(tools)) => split_list(tools)
+            .into_iter()
+            .filter(|item| !item.is_empty())
+            .map(|item| unquote(&item))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn unquote(value: &str) -> String {
+    for quote in ['"', '\''] {
+        if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+            return value[1..value.len() - 1].replace(&format!("\\{}", quote), &quote.to_string());
+        }
+    }
+    value.to_string()
+}
+
+fn render_scalar(value: &str) -> String {
+    let needs_quotes = value.is_empty()
+        || value.starts_with([' ', '[', '{', '"', '\'', '#', '-', '!', '&', '*', '@', '`'])
+        || value.ends_with(' ')
+        || value.contains(": ")
+        || value.contains(" #")
+        || matches!(value, "true" | "false" | "null");
+    if needs_quotes {
+        format!("\"{}\"", value.replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn render(frontmatter: &Map<String, Value>, body: &str) -> String {
+    if frontmatter.is_empty() {
+        return body.to_string();
+    }
+
+    let mut text = String::from("---\n");
+    for (key, value) in frontmatter {
+        let rendered = match value {
+            Value::String(s) => render_scalar(s),
+            Value::Array(items) => format!(
+                "[{}]",
+                items
+                    .iter()
+                    .map(|item| match item {
+                        Value::String(s) => render_scalar(s),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Value::Null => continue,
+            other => other.to_string(),
+        };
+        text.push_str(&format!("{}: {}\n", key, rendered));
+    }
+    text.push_str("---\n\n");
+    text.push_str(body.trim_start_matches(['\r', '\n']));
+    text
+}
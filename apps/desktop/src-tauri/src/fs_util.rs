@@ -0,0 +1,94 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, WebviewWindow};
+
+use crate::audit;
+use crate::policy::{self, PolicyError};
+
+// Write `contents` to `path` without ever leaving a truncated file behind:
+// the data goes to a temp file in the same directory which is fsynced and
+// renamed over the target. With `backup`, the previous version is kept as
+// `<name>.bak` next to it. The file keeps its permissions, and a symlink
+// is written through rather than replaced.
+pub fn write_atomic(path: &Path, contents: &[u8], backup: bool) -> Result<(), String> {
+    let resolved = fs::canonicalize(path).ok().filter(|resolved| resolved.is_file());
+    let path = resolved.as_deref().unwrap_or(path);
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let tmp_path = temp_path_for(path);
+    let result = (|| {
+        let mut file = File::create(&tmp_path)
+            .map_err(|e| format!("Failed to create temp file: {}", e))?;
+        file.write_all(contents)
+            .map_err(|e| format!("Failed to write temp file: {}", e))?;
+        if let Ok(metadata) = fs::metadata(path) {
+            file.set_permissions(metadata.permissions())
+                .map_err(|e| format!("Failed to set permissions on temp file: {}", e))?;
+        }
+        file.sync_all()
+            .map_err(|e| format!("Failed to sync temp file: {}", e))?;
+
+        if backup && path.exists() {
+            fs::copy(path, backup_path_for(path))
+                .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+        }
+
+        fs::rename(&tmp_path, path)
+            .map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+        // The rename itself only survives a crash once the directory is synced
+        #[cfg(unix)]
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+// Save a file the user cares about, such as a source file Claude edited:
+// written atomically with the previous version kept as `<name>.bak`, so it
+// is never left truncated if the app dies mid-write. The path has to be in
+// one of the project's allowed folders.
+#[tauri::command]
+pub async fn write_file_safe(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: String,
+    path: String,
+    contents: String,
+) -> Result<(), PolicyError> {
+    let path = PathBuf::from(path);
+    if path.is_dir() {
+        return Err(format!("Not a file: {}", path.display()).into());
+    }
+    policy::check(&app, &project_path, &path).await?;
+    let subject = path.to_string_lossy().to_string();
+    tauri::async_runtime::spawn_blocking(move || write_atomic(&path, contents.as_bytes(), true))
+        .await
+        .map_err(|e| format!("Write task failed: {}", e))??;
+    audit::record(&app, Some(window.label()), "file", "write", &subject);
+    Ok(())
+}
+
+pub fn backup_path_for(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+pub fn temp_path_for(path: &Path) -> PathBuf {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(".{}.tmp", uuid::Uuid::new_v4().simple()));
+    path.with_file_name(name)
+}
@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+// Errors the UI tells apart, e.g. to offer stashing on a dirty worktree.
+// Serialized with a `kind`, like { kind: "branch_exists", name: "fix" }.
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GitError {
+    GitNotInstalled,
+    NotARepo,
+    InvalidName { name: String },
+    BranchExists { name: String },
+    BranchNotFound { name: String },
+    // Tracked files with uncommitted changes
+    DirtyWorktree { files: Vec<String> },
+    EmptyMessage,
+    NothingToCommit,
+    // A commit hook such as husky's pre-commit refused the commit; its
+    // output is what it printed
+    HookFailed { hooks: Vec<String>, output: String },
+    StashNotFound { index: usize },
+    // The remote turned the credentials down, or needed some and had none
+    AuthFailed { host: Option<String> },
+    // e.g. the remote has commits the branch doesn't; pull first
+    PushRejected { message: String },
+    NoUpstream { branch: String },
+    // The GitHub CLI, for pull requests
+    GhNotInstalled,
+    GhNotAuthenticated { host: String },
+    PullRequestExists { url: String },
+    Failed { message: String },
+}
+
+pub fn command(dir: &Path, args: &[&str]) -> Command {
+    let mut command = Command::new("git");
+    // Polling status shouldn't take the index lock from a running git
+    command.arg("--no-optional-locks").args(args).current_dir(dir);
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command
+}
+
+pub fn git(dir: &Path, args: &[&str]) -> std::io::Result<Output> {
+    command(dir, args).output()
+}
+
+pub fn run(dir: &Path, args: &[&str]) -> Result<Output, GitError> {
+    git(dir, args).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => GitError::GitNotInstalled,
+        _ => GitError::Failed {
+            message: format!("Failed to run git: {}", e),
+        },
+    })
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+pub fn failed(output: &Output) -> GitError {
+    GitError::Failed {
+        message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+    }
+}
+
+// Git lists the files it won't overwrite indented under its message
+pub fn indented_files(stderr: &str) -> Vec<String> {
+    stderr
+        .lines()
+        .filter(|line| line.starts_with('\t'))
+        .map(|line| line.trim().to_string())
+        .collect()
+}
+
+// Run a command that only has to succeed
+pub fn check(dir: &Path, args: &[&str]) -> Result<Output, GitError> {
+    let output = run(dir, args)?;
+    if !output.status.success() {
+        return Err(failed(&output));
+    }
+    Ok(output)
+}
+
+pub fn repo(project_path: &str) -> Result<PathBuf, GitError> {
+    let dir = PathBuf::from(project_path);
+    if !dir.is_dir() || !run(&dir, &["rev-parse", "--git-dir"])?.status.success() {
+        return Err(GitError::NotARepo);
+    }
+    Ok(dir)
+}
+
+// A file's repository, from the folder it's in, and its name there
+pub fn file_repo(path: &str) -> Result<(PathBuf, String), GitError> {
+    let file = Path::new(path);
+    let (Some(dir), Some(name)) = (file.parent(), file.file_name()) else {
+        return Err(GitError::Failed {
+            message: format!("Not a file: {}", path),
+        });
+    };
+    Ok((repo(&dir.to_string_lossy())?, name.to_string_lossy().to_string()))
+}
+
+pub fn exists(dir: &Path, reference: &str) -> Result<bool, GitError> {
+    Ok(run(dir, &["rev-parse", "--verify", "--quiet", reference])?.status.success())
+}
+
+pub async fn blocking<T: Send + 'static>(
+    task: impl FnOnce() -> Result<T, GitError> + Send + 'static,
+) -> Result<T, GitError> {
+    tauri::async_runtime::spawn_blocking(task).await.map_err(|e| GitError::Failed {
+        message: format!("Git task failed: {}", e),
+    })?
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::git::{blocking, check, file_repo, GitError};
+
+// 1-based and inclusive, like `git blame -L`
+#[derive(Clone, Copy, serde::Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct BlameLine {
+    pub line: usize,
+    pub commit: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub authored_at: String,
+    pub age_seconds: i64,
+    pub summary: String,
+    // Changed on disk and not committed yet
+    pub uncommitted: bool,
+    pub text: String,
+}
+
+#[derive(Clone, Default)]
+struct Commit {
+    author_name: String,
+    author_email: String,
+    author_time: i64,
+    summary: String,
+}
+
+// `git blame --porcelain`: each line is "<commit> <old> <new> [<count>]",
+// then the commit's details the first time it comes up, then the line's
+// text after a tab
+fn parse(output: &str) -> Vec<BlameLine> {
+    let now = chrono::Utc::now().timestamp();
+    let mut commits: HashMap<String, Commit> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<(String, usize)> = None;
+
+    for row in output.lines() {
+        if let Some(text) = row.strip_prefix('\t') {
+            let Some((commit, line)) = current.take() else { continue };
+            let details = commits.get(&commit).cloned().unwrap_or_default();
+            lines.push(BlameLine {
+                line,
+                uncommitted: commit.bytes().all(|byte| byte == b'0'),
+                commit,
+                author_name: details.author_name,
+                author_email: details.author_email,
+                authored_at: chrono::DateTime::from_timestamp(details.author_time, 0)
+                    .map(|time| time.to_rfc3339())
+                    .unwrap_or_default(),
+                age_seconds: (now - details.author_time).max(0),
+                summary: details.summary,
+                text: text.to_string(),
+            });
+            continue;
+        }
+
+        let (key, value) = row.split_once(' ').unwrap_or((row, ""));
+        if current.is_none() && key.len() >= 40 && key.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            let line = value.split(' ').nth(1).and_then(|line| line.parse().ok()).unwrap_or(0);
+            commits.entry(key.to_string()).or_default();
+            current = Some((key.to_string(), line));
+            continue;
+        }
+        let Some(commit) = current.as_ref().and_then(|(commit, _)| commits.get_mut(commit)) else {
+            continue;
+        };
+        match key {
+            "author" => commit.author_name = value.to_string(),
+            "author-mail" => commit.author_email = value.trim_matches(['<', '>']).to_string(),
+            "author-time" => commit.author_time = value.parse().unwrap_or(0),
+            "summary" => commit.summary = value.to_string(),
+            _ => {}
+        }
+    }
+    lines
+}
+
+fn blame(dir: &Path, file: &str, range: Option<LineRange>) -> Result<Vec<BlameLine>, GitError> {
+    let range = range.map(|range| format!("-L{},{}", range.start.max(1), range.end.max(range.start.max(1))));
+    let mut args = vec!["blame", "--porcelain"];
+    args.extend(range.as_deref());
+    args.extend(["--", file]);
+    let output = check(dir, &args)?;
+    Ok(parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// Who last changed each line of a file, and when. Lines edited on disk
+// come back as `uncommitted`.
+#[tauri::command]
+pub async fn git_blame(path: String, line_range: Option<LineRange>) -> Result<Vec<BlameLine>, GitError> {
+    blocking(move || {
+        let (dir, name) = file_repo(&path)?;
+        blame(&dir, &name, line_range)
+    })
+    .await
+}
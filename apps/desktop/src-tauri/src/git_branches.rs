@@ -0,0 +1,163 @@
+use std::path::Path;
+
+use crate::git::{blocking, check, exists, failed, repo, run, stdout, GitError};
+
+#[derive(Clone, serde::Serialize)]
+pub struct GitBranch {
+    // e.g. "main", or "origin/main" for a remote branch
+    pub name: String,
+    pub remote: bool,
+    pub current: bool,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    // The upstream was deleted on the remote
+    pub upstream_gone: bool,
+    pub last_commit_at: Option<String>,
+    pub subject: Option<String>,
+}
+
+// "ahead 2, behind 1", "gone" or nothing, from %(upstream:track,nobracket)
+fn track(value: &str) -> (u32, u32, bool) {
+    let (mut ahead, mut behind) = (0, 0);
+    for part in value.split(", ") {
+        if let Some(count) = part.strip_prefix("ahead ") {
+            ahead = count.parse().unwrap_or(0);
+        } else if let Some(count) = part.strip_prefix("behind ") {
+            behind = count.parse().unwrap_or(0);
+        }
+    }
+    (ahead, behind, value == "gone")
+}
+
+fn list(dir: &Path) -> Result<Vec<GitBranch>, GitError> {
+    let format = [
+        "%(refname)",
+        "%(refname:short)",
+        "%(HEAD)",
+        "%(upstream:short)",
+        "%(upstream:track,nobracket)",
+        "%(committerdate:iso-strict)",
+        "%(contents:subject)",
+    ]
+    .join("%00");
+    let output = check(
+        dir,
+        &["for-each-ref", &format!("--format={}", format), "refs/heads", "refs/remotes"],
+    )?;
+
+    let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_string());
+    Ok(stdout(&output)
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\0').collect();
+            let [refname, name, head, upstream, track_value, date, subject] = fields[..] else {
+                return None;
+            };
+            // A remote's HEAD only points at one of its branches
+            if refname.starts_with("refs/remotes/") && refname.ends_with("/HEAD") {
+                return None;
+            }
+            let (ahead, behind, upstream_gone) = track(track_value);
+            Some(GitBranch {
+                name: name.to_string(),
+                remote: refname.starts_with("refs/remotes/"),
+                current: head == "*",
+                upstream: non_empty(upstream),
+                ahead,
+                behind,
+                upstream_gone,
+                last_commit_at: non_empty(date),
+                subject: non_empty(subject),
+            })
+        })
+        .collect())
+}
+
+pub fn check_name(dir: &Path, name: &str) -> Result<(), GitError> {
+    let valid = run(dir, &["check-ref-format", "--branch", name])?.status.success();
+    if !valid || name.starts_with('-') {
+        return Err(GitError::InvalidName { name: name.to_string() });
+    }
+    Ok(())
+}
+
+fn create(dir: &Path, name: &str, from: Option<&str>) -> Result<(), GitError> {
+    check_name(dir, name)?;
+    if exists(dir, &format!("refs/heads/{}", name))? {
+        return Err(GitError::BranchExists { name: name.to_string() });
+    }
+    let from = from.filter(|from| !from.trim().is_empty()).unwrap_or("HEAD");
+    if from.starts_with('-') || !exists(dir, &format!("{}^{{commit}}", from))? {
+        return Err(GitError::BranchNotFound { name: from.to_string() });
+    }
+    // Branching off a remote branch doesn't make it the upstream; the
+    // first push sets that
+    check(dir, &["branch", "--no-track", name, from])?;
+    Ok(())
+}
+
+fn dirty_files(dir: &Path) -> Result<Vec<String>, GitError> {
+    let output = check(dir, &["status", "--porcelain", "-z", "--untracked-files=no"])?;
+    // Each record is "XY path"; a rename's original path follows as its own
+    let text = stdout(&output);
+    let mut records = text.split('\0').filter(|record| !record.is_empty());
+    let mut files = Vec::new();
+    while let Some(record) = records.next() {
+        let Some(path) = record.get(3..) else { continue };
+        if record.starts_with('R') || record.starts_with('C') {
+            records.next();
+        }
+        files.push(path.to_string());
+    }
+    Ok(files)
+}
+
+fn switch(dir: &Path, name: &str, allow_dirty: bool) -> Result<(), GitError> {
+    if !allow_dirty {
+        let files = dirty_files(dir)?;
+        if !files.is_empty() {
+            return Err(GitError::DirtyWorktree { files });
+        }
+    }
+    let output = run(dir, &["switch", "--no-guess", name])?;
+    if output.status.success() {
+        return Ok(());
+    }
+    if exists(dir, &format!("refs/heads/{}", name))? {
+        return Err(failed(&output));
+    }
+    // A name only found on a remote makes a local branch tracking it
+    let remote = list(dir)?
+        .into_iter()
+        .find(|branch| branch.remote && branch.name.split_once('/').map(|(_, rest)| rest) == Some(name));
+    let Some(remote) = remote else {
+        return Err(GitError::BranchNotFound { name: name.to_string() });
+    };
+    check(dir, &["switch", "--track", "-c", name, &remote.name])?;
+    Ok(())
+}
+
+// Local branches, then remote ones, as git sorts them
+#[tauri::command]
+pub async fn git_list_branches(project_path: String) -> Result<Vec<GitBranch>, GitError> {
+    blocking(move || list(&repo(&project_path)?)).await
+}
+
+// Make a branch from `from`, HEAD by default, without switching to it
+#[tauri::command]
+pub async fn git_create_branch(project_path: String, name: String, from: Option<String>) -> Result<(), GitError> {
+    blocking(move || create(&repo(&project_path)?, &name, from.as_deref())).await
+}
+
+// Switch to a local branch, or a remote one as a new local branch tracking
+// it. Refuses with uncommitted changes to tracked files unless
+// `allow_dirty`, in which case git carries them over when it can.
+#[tauri::command]
+pub async fn git_switch_branch(
+    project_path: String,
+    name: String,
+    allow_dirty: Option<bool>,
+) -> Result<(), GitError> {
+    blocking(move || switch(&repo(&project_path)?, &name, allow_dirty.unwrap_or(false))).await
+}
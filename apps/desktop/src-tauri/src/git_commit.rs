@@ -0,0 +1,138 @@
+use std::path::Path;
+
+use crate::git::{blocking, check, exists, failed, repo, run, stdout, GitError};
+
+// Hooks that can stop a commit; post-commit runs once it's made
+const COMMIT_HOOKS: &[&str] = &["pre-commit", "prepare-commit-msg", "commit-msg"];
+
+#[derive(Clone, serde::Serialize)]
+pub struct GitCommit {
+    pub hash: String,
+    pub short_hash: String,
+}
+
+// With paths as the UI lists them: relative to the project, and not globs,
+// which a Next.js route like `[id].tsx` would otherwise be read as
+fn with_paths<'a>(args: &[&'a str], paths: &'a [String]) -> Vec<&'a str> {
+    let mut all = vec!["--literal-pathspecs"];
+    all.extend_from_slice(args);
+    all.push("--");
+    all.extend(paths.iter().map(String::as_str));
+    all
+}
+
+fn stage(dir: &Path, paths: &[String]) -> Result<(), GitError> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    // -A also stages deletions of the paths
+    check(dir, &with_paths(&["add", "-A"], paths))?;
+    Ok(())
+}
+
+fn unstage(dir: &Path, paths: &[String]) -> Result<(), GitError> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    // Before the first commit there's no HEAD to restore the index from
+    if exists(dir, "HEAD")? {
+        check(dir, &with_paths(&["restore", "--staged"], paths))?;
+    } else {
+        check(dir, &with_paths(&["rm", "--cached", "-r", "--quiet"], paths))?;
+    }
+    Ok(())
+}
+
+fn active_hooks(dir: &Path) -> Vec<String> {
+    let Ok(output) = check(dir, &["rev-parse", "--git-path", "hooks"]) else {
+        return Vec::new();
+    };
+    // Relative to the project unless core.hooksPath is absolute
+    let hooks_dir = dir.join(stdout(&output).trim_end());
+    COMMIT_HOOKS
+        .iter()
+        .filter(|hook| is_executable(&hooks_dir.join(hook)))
+        .map(|hook| hook.to_string())
+        .collect()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+fn commit(dir: &Path, message: &str, paths: &[String], all: bool) -> Result<GitCommit, GitError> {
+    if message.trim().is_empty() {
+        return Err(GitError::EmptyMessage);
+    }
+    // Everything under the project, which may be part of a bigger repo
+    let paths = if all { vec![".".to_string()] } else { paths.to_vec() };
+    stage(dir, &paths)?;
+
+    let staged = run(dir, &with_paths(&["diff", "--cached", "--quiet"], &paths))?;
+    match staged.status.code() {
+        Some(0) => return Err(GitError::NothingToCommit),
+        Some(1) => {}
+        _ => return Err(failed(&staged)),
+    }
+
+    // Only the given paths go in, whatever else is staged
+    let output = if paths.is_empty() {
+        run(dir, &["commit", "-m", message])?
+    } else {
+        run(dir, &with_paths(&["commit", "-m", message], &paths))?
+    };
+    if !output.status.success() {
+        // Git's own refusals start with "fatal:"; anything else with a hook
+        // installed came from the hook
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let hooks = active_hooks(dir);
+        if hooks.is_empty() || stderr.lines().any(|line| line.starts_with("fatal:")) {
+            return Err(failed(&output));
+        }
+        let printed = format!("{}\n{}", stdout(&output).trim(), stderr.trim());
+        return Err(GitError::HookFailed {
+            hooks,
+            output: printed.trim().to_string(),
+        });
+    }
+
+    let hash = check(dir, &["rev-parse", "HEAD"])?;
+    let short_hash = check(dir, &["rev-parse", "--short", "HEAD"])?;
+    Ok(GitCommit {
+        hash: stdout(&hash).trim().to_string(),
+        short_hash: stdout(&short_hash).trim().to_string(),
+    })
+}
+
+#[tauri::command]
+pub async fn git_stage(project_path: String, paths: Vec<String>) -> Result<(), GitError> {
+    blocking(move || stage(&repo(&project_path)?, &paths)).await
+}
+
+#[tauri::command]
+pub async fn git_unstage(project_path: String, paths: Vec<String>) -> Result<(), GitError> {
+    blocking(move || unstage(&repo(&project_path)?, &paths)).await
+}
+
+// Commit the given paths, staging them first, or with `all` everything
+// changed under the project. With neither, what's already staged.
+#[tauri::command]
+pub async fn git_commit(
+    project_path: String,
+    message: String,
+    paths: Option<Vec<String>>,
+    all: Option<bool>,
+) -> Result<GitCommit, GitError> {
+    blocking(move || {
+        let dir = repo(&project_path)?;
+        commit(&dir, &message, &paths.unwrap_or_default(), all.unwrap_or(false))
+    })
+    .await
+}
@@ -0,0 +1,291 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::diff::{self, Content};
+use crate::fs_util;
+use crate::git::{blocking, check, repo, stdout, GitError};
+
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SPLIT_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+#[derive(Clone, serde::Serialize)]
+pub struct ConflictHunk {
+    // 1-based lines of the <<<<<<< and >>>>>>> markers
+    pub start_line: usize,
+    pub end_line: usize,
+    // e.g. "HEAD" and the branch being merged in
+    pub ours_label: String,
+    pub theirs_label: String,
+    pub ours: String,
+    // Only with merge.conflictStyle diff3 or zdiff3
+    pub base: Option<String>,
+    pub theirs: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ConflictFile {
+    // Relative to the project
+    pub path: String,
+    // A side that deleted the file has no version to keep, and no markers
+    pub deleted_by_us: bool,
+    pub deleted_by_them: bool,
+    pub binary: bool,
+    pub hunks: Vec<ConflictHunk>,
+}
+
+// Which version to keep, e.g. { kind: "content", content: "..." } for one
+// the user edited
+#[derive(Clone, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Resolution {
+    Ours,
+    Theirs,
+    Base,
+    // Ours, then theirs
+    Both,
+    Content { content: String },
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ConflictResolved {
+    // No conflicts left, so the file was staged as resolved
+    pub resolved: bool,
+    pub remaining: usize,
+}
+
+enum Section {
+    Outside,
+    Ours,
+    Base,
+    Theirs,
+}
+
+fn marker<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.trim_end_matches(['\n', '\r']).strip_prefix(marker)?;
+    (rest.is_empty() || rest.starts_with(' ')).then(|| rest.trim())
+}
+
+// Hunks in a file's text, as (the hunk, the byte range its markers span).
+// Lines keep their endings so a resolution writes them back as they were.
+fn parse(text: &str) -> Vec<(ConflictHunk, std::ops::Range<usize>)> {
+    let mut hunks = Vec::new();
+    let mut section = Section::Outside;
+    let mut current: Option<(ConflictHunk, usize)> = None;
+    let mut offset = 0;
+
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let start = offset;
+        offset += line.len();
+        match section {
+            Section::Outside => {
+                if let Some(label) = marker(line, OURS_MARKER) {
+                    let hunk = ConflictHunk {
+                        start_line: index + 1,
+                        end_line: index + 1,
+                        ours_label: label.to_string(),
+                        theirs_label: String::new(),
+                        ours: String::new(),
+                        base: None,
+                        theirs: String::new(),
+                    };
+                    current = Some((hunk, start));
+                    section = Section::Ours;
+                }
+            }
+            Section::Ours | Section::Base => {
+                let Some((hunk, _)) = current.as_mut() else { continue };
+                if marker(line, BASE_MARKER).is_some() {
+                    hunk.base = Some(String::new());
+                    section = Section::Base;
+                } else if marker(line, SPLIT_MARKER).is_some() {
+                    section = Section::Theirs;
+                } else if let (Section::Base, Some(base)) = (&section, hunk.base.as_mut()) {
+                    base.push_str(line);
+                } else {
+                    hunk.ours.push_str(line);
+                }
+            }
+            Section::Theirs => {
+                let Some((hunk, _)) = current.as_mut() else { continue };
+                if let Some(label) = marker(line, THEIRS_MARKER) {
+                    hunk.theirs_label = label.to_string();
+                    hunk.end_line = index + 1;
+                    if let Some((hunk, hunk_start)) = current.take() {
+                        hunks.push((hunk, hunk_start..offset));
+                    }
+                    section = Section::Outside;
+                } else {
+                    hunk.theirs.push_str(line);
+                }
+            }
+        }
+    }
+    hunks
+}
+
+// Conflicted files under the project, from the index's unmerged entries:
+// "mode hash stage<TAB>path", stage 1 the base, 2 ours and 3 theirs
+fn conflicted(dir: &Path) -> Result<BTreeMap<String, [bool; 3]>, GitError> {
+    let output = stdout(&check(dir, &["ls-files", "--unmerged", "-z"])?);
+    let mut files: BTreeMap<String, [bool; 3]> = BTreeMap::new();
+    for record in output.split('\0').filter(|record| !record.is_empty()) {
+        let Some((info, path)) = record.split_once('\t') else { continue };
+        let stage: usize = info.rsplit(' ').next().and_then(|stage| stage.parse().ok()).unwrap_or(0);
+        if let Some(present) = files.entry(path.to_string()).or_default().get_mut(stage.wrapping_sub(1)) {
+            *present = true;
+        }
+    }
+    Ok(files)
+}
+
+fn conflicts(dir: &Path) -> Result<Vec<ConflictFile>, GitError> {
+    let mut files = Vec::new();
+    for (path, stages) in conflicted(dir)? {
+        let content = diff::read(&dir.join(&path)).map_err(|message| GitError::Failed { message })?;
+        let (binary, hunks) = match content {
+            Content::Text(text) => (false, parse(&text).into_iter().map(|(hunk, _)| hunk).collect()),
+            Content::Binary => (true, Vec::new()),
+            Content::Missing => (false, Vec::new()),
+        };
+        files.push(ConflictFile {
+            path,
+            deleted_by_us: !stages[1],
+            deleted_by_them: !stages[2],
+            binary,
+            hunks,
+        });
+    }
+    Ok(files)
+}
+
+fn chosen(hunk: &ConflictHunk, resolution: &Resolution) -> Result<String, GitError> {
+    Ok(match resolution {
+        Resolution::Ours => hunk.ours.clone(),
+        Resolution::Theirs => hunk.theirs.clone(),
+        // Keeping nothing would look like keeping the base
+        Resolution::Base => hunk.base.clone().ok_or_else(|| GitError::Failed {
+            message: format!(
+                "The conflict at line {} has no base version; git only writes one with merge.conflictStyle diff3",
+                hunk.start_line
+            ),
+        })?,
+        Resolution::Both => format!("{}{}", hunk.ours, hunk.theirs),
+        Resolution::Content { content } => content.clone(),
+    })
+}
+
+// Where a conflicted file is in the worktree. Only paths git reports as
+// conflicted are accepted, and only inside the repository once symlinks
+// and ".." are resolved.
+fn worktree_file(dir: &Path, path: &str) -> Result<PathBuf, GitError> {
+    let failed = |message: String| GitError::Failed { message };
+    if !conflicted(dir)?.contains_key(path) {
+        return Err(failed(format!("{} isn't conflicted", path)));
+    }
+    let root = dunce::canonicalize(dir).map_err(|e| failed(format!("Failed to open {}: {}", dir.display(), e)))?;
+    let file = root.join(path);
+    let resolved = match dunce::canonicalize(&file) {
+        Ok(resolved) => resolved,
+        // Deleted on our side; its folder still has to be inside
+        Err(_) => {
+            let (Some(parent), Some(name)) = (file.parent(), file.file_name()) else {
+                return Err(failed(format!("Invalid path: {}", path)));
+            };
+            dunce::canonicalize(parent).map(|parent| parent.join(name)).unwrap_or(file.clone())
+        }
+    };
+    if !resolved.starts_with(&root) {
+        return Err(failed(format!("{} is outside the repository", path)));
+    }
+    Ok(file)
+}
+
+// A whole side for a file without markers, e.g. one side deleted it or
+// it's binary
+fn resolve_whole(dir: &Path, file: &Path, path: &str, resolution: &Resolution) -> Result<(), GitError> {
+    let stages = conflicted(dir)?.remove(path).unwrap_or_default();
+    let (side, flag) = match resolution {
+        Resolution::Ours => (stages[1], "--ours"),
+        Resolution::Theirs => (stages[2], "--theirs"),
+        Resolution::Content { content } => {
+            fs_util::write_atomic(file, content.as_bytes(), false)
+                .map_err(|message| GitError::Failed { message })?;
+            check(dir, &["--literal-pathspecs", "add", "--", path])?;
+            return Ok(());
+        }
+        Resolution::Base | Resolution::Both => {
+            return Err(GitError::Failed {
+                message: format!("{} has no conflict markers; keep one side or give its content", path),
+            });
+        }
+    };
+    if side {
+        check(dir, &["--literal-pathspecs", "checkout", flag, "--", path])?;
+        check(dir, &["--literal-pathspecs", "add", "--", path])?;
+    } else {
+        // Keeping the side that deleted it
+        check(dir, &["--literal-pathspecs", "rm", "--quiet", "--", path])?;
+    }
+    Ok(())
+}
+
+fn resolve(dir: &Path, path: &str, resolution: &Resolution, hunk: Option<usize>) -> Result<ConflictResolved, GitError> {
+    let file = worktree_file(dir, path)?;
+    let text = match diff::read(&file).map_err(|message| GitError::Failed { message })? {
+        Content::Text(text) => text,
+        _ => String::new(),
+    };
+    let hunks = parse(&text);
+    if hunks.is_empty() {
+        resolve_whole(dir, &file, path, resolution)?;
+        return Ok(ConflictResolved {
+            resolved: true,
+            remaining: 0,
+        });
+    }
+    if let Some(index) = hunk.filter(|index| *index >= hunks.len()) {
+        return Err(GitError::Failed {
+            message: format!("{} has no conflict {}", path, index),
+        });
+    }
+
+    // Back to front, so the earlier ranges still hold
+    let mut resolved = text.clone();
+    let mut remaining = hunks.len();
+    for (index, (conflict, range)) in hunks.iter().enumerate().rev() {
+        if hunk.is_some_and(|hunk| hunk != index) {
+            continue;
+        }
+        resolved.replace_range(range.clone(), &chosen(conflict, resolution)?);
+        remaining -= 1;
+    }
+    fs_util::write_atomic(&file, resolved.as_bytes(), false).map_err(|message| GitError::Failed { message })?;
+    if remaining == 0 {
+        check(dir, &["--literal-pathspecs", "add", "--", path])?;
+    }
+    Ok(ConflictResolved {
+        resolved: remaining == 0,
+        remaining,
+    })
+}
+
+// Files left conflicted by a merge, rebase, pull or stash, with their
+// conflict markers parsed into each side
+#[tauri::command]
+pub async fn git_conflicts(project_path: String) -> Result<Vec<ConflictFile>, GitError> {
+    blocking(move || conflicts(&repo(&project_path)?)).await
+}
+
+// Keep one side of a file's conflicts, or of just the `hunk`th one. Once
+// none are left the file is staged as resolved.
+#[tauri::command]
+pub async fn resolve_conflict(
+    project_path: String,
+    path: String,
+    resolution: Resolution,
+    hunk: Option<usize>,
+) -> Result<ConflictResolved, GitError> {
+    blocking(move || resolve(&repo(&project_path)?, &path, &resolution, hunk)).await
+}
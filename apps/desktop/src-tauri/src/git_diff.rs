@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use crate::diff::{self, Content, PathsDiff};
+use crate::git::{blocking, check, exists, repo, run, stdout, GitError};
+
+// What to compare, e.g. { kind: "commit_range", from: "main" }
+#[derive(Clone, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DiffTarget {
+    // The index against HEAD
+    Staged,
+    // The files on disk against HEAD, untracked ones included
+    Worktree,
+    // `to` defaults to HEAD
+    CommitRange { from: String, to: Option<String> },
+}
+
+// A side of the comparison
+enum Side<'a> {
+    Rev(&'a str),
+    Index,
+    Disk,
+}
+
+fn read(dir: &Path, side: &Side, path: &str) -> Result<Content, GitError> {
+    let spec = match side {
+        Side::Disk => {
+            return diff::read(&dir.join(path)).map_err(|message| GitError::Failed { message });
+        }
+        // `./` makes the path relative to the project rather than the repo
+        Side::Index => format!(":./{}", path),
+        Side::Rev(rev) => format!("{}:./{}", rev, path),
+    };
+    let output = run(dir, &["show", &spec])?;
+    if !output.status.success() {
+        return Ok(Content::Missing);
+    }
+    Ok(diff::content(output.stdout))
+}
+
+fn pathspec(path: &Option<String>) -> Vec<&str> {
+    let mut args = vec!["--"];
+    args.extend(path.as_deref());
+    args
+}
+
+// (status letter, path) pairs from `--name-status -z`
+fn name_status(dir: &Path, args: &[&str], path: &Option<String>) -> Result<Vec<(char, String)>, GitError> {
+    let mut all = vec![
+        "--literal-pathspecs",
+        "diff",
+        "--name-status",
+        "-z",
+        "--no-renames",
+        "--relative",
+        "--ignore-submodules",
+    ];
+    all.extend_from_slice(args);
+    all.extend(pathspec(path));
+    let output = stdout(&check(dir, &all)?);
+    let mut records = output.split('\0').filter(|record| !record.is_empty());
+    let mut changes = Vec::new();
+    while let (Some(status), Some(file)) = (records.next(), records.next()) {
+        changes.push((status.chars().next().unwrap_or('M'), file.to_string()));
+    }
+    Ok(changes)
+}
+
+fn listed(dir: &Path, args: &[&str], path: &Option<String>) -> Result<Vec<String>, GitError> {
+    let mut all = vec!["--literal-pathspecs", "ls-files", "-z"];
+    all.extend_from_slice(args);
+    all.extend(pathspec(path));
+    let output = stdout(&check(dir, &all)?);
+    Ok(output.split('\0').filter(|file| !file.is_empty()).map(str::to_string).collect())
+}
+
+fn git_diff_files(
+    dir: &Path,
+    target: &DiffTarget,
+    path: &Option<String>,
+    context_lines: usize,
+) -> Result<PathsDiff, GitError> {
+    let born = exists(dir, "HEAD")?;
+    let (old, new, mut changes) = match target {
+        DiffTarget::Staged => {
+            let changes = name_status(dir, &["--cached"], path)?;
+            (Side::Rev("HEAD"), Side::Index, changes)
+        }
+        DiffTarget::Worktree if born => {
+            let mut changes = name_status(dir, &["HEAD"], path)?;
+            for file in listed(dir, &["--others", "--exclude-standard"], path)? {
+                changes.push(('A', file));
+            }
+            (Side::Rev("HEAD"), Side::Disk, changes)
+        }
+        // Before the first commit everything is new
+        DiffTarget::Worktree => {
+            let changes = listed(dir, &["--cached", "--others", "--exclude-standard"], path)?
+                .into_iter()
+                .map(|file| ('A', file))
+                .collect();
+            (Side::Rev("HEAD"), Side::Disk, changes)
+        }
+        DiffTarget::CommitRange { from, to } => {
+            let to = to.as_deref().unwrap_or("HEAD");
+            for rev in [from.as_str(), to] {
+                if rev.starts_with('-') || !exists(dir, &format!("{}^{{commit}}", rev))? {
+                    return Err(GitError::BranchNotFound { name: rev.to_string() });
+                }
+            }
+            let changes = name_status(dir, &[from, to], path)?;
+            (Side::Rev(from), Side::Rev(to), changes)
+        }
+    };
+    changes.sort_by(|a, b| a.1.cmp(&b.1));
+    changes.dedup_by(|a, b| a.1 == b.1);
+
+    let truncated = changes.len() > diff::MAX_FILES;
+    changes.truncate(diff::MAX_FILES);
+    let mut files = Vec::new();
+    for (status, file) in changes {
+        let old_content = if status == 'A' { Content::Missing } else { read(dir, &old, &file)? };
+        let new_content = if status == 'D' { Content::Missing } else { read(dir, &new, &file)? };
+        let file_diff = diff::file_diff(Some(file), old_content, new_content, context_lines);
+        if file_diff.status != "unchanged" {
+            files.push(file_diff);
+        }
+    }
+    Ok(PathsDiff { files, truncated })
+}
+
+// What changed in the project as hunks in the same shape as `compute_diff`,
+// with paths relative to the project. `path` narrows it to one file or
+// folder.
+#[tauri::command]
+pub async fn git_diff(
+    project_path: String,
+    target: DiffTarget,
+    path: Option<String>,
+    context_lines: Option<usize>,
+) -> Result<PathsDiff, GitError> {
+    let context_lines = context_lines.unwrap_or(diff::DEFAULT_CONTEXT_LINES);
+    blocking(move || git_diff_files(&repo(&project_path)?, &target, &path, context_lines)).await
+}
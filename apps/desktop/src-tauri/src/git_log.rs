@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use crate::git::{blocking, check, exists, file_repo, repo, GitError};
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+// Each commit starts with a record separator and its fields end in NULs;
+// --numstat's lines follow the last one
+const FORMAT: &str = "--format=%x1e%H%x00%h%x00%P%x00%an%x00%ae%x00%aI%x00%cI%x00%s%x00%b%x00";
+
+#[derive(Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct LogOptions {
+    pub limit: Option<usize>,
+    // Commits to skip, for paging
+    pub skip: Option<usize>,
+    // Only commits touching this file or folder, relative to the project
+    pub path: Option<String>,
+    // Matched against the name and email, as `git log --author` does
+    pub author: Option<String>,
+    // e.g. an RFC 3339 time, to line commits up with a session
+    pub since: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct GitLogEntry {
+    pub hash: String,
+    pub short_hash: String,
+    pub parents: Vec<String>,
+    pub author_name: String,
+    pub author_email: String,
+    pub authored_at: String,
+    pub committed_at: String,
+    pub subject: String,
+    pub body: String,
+    // Only the files in the path asked for, if any
+    pub files_changed: usize,
+    // Binary files count as changed without lines
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+fn parse(output: &str) -> Vec<GitLogEntry> {
+    output
+        .split('\x1e')
+        .filter_map(|record| {
+            let fields: Vec<&str> = record.splitn(10, '\0').collect();
+            let [hash, short_hash, parents, author_name, author_email, authored_at, committed_at, subject, body, stats] =
+                fields[..]
+            else {
+                return None;
+            };
+            let mut entry = GitLogEntry {
+                hash: hash.to_string(),
+                short_hash: short_hash.to_string(),
+                parents: parents.split_whitespace().map(str::to_string).collect(),
+                author_name: author_name.to_string(),
+                author_email: author_email.to_string(),
+                authored_at: authored_at.to_string(),
+                committed_at: committed_at.to_string(),
+                subject: subject.to_string(),
+                body: body.trim_end().to_string(),
+                files_changed: 0,
+                insertions: 0,
+                deletions: 0,
+            };
+            // "added<TAB>deleted<TAB>path", with "-" for binaries
+            for line in stats.lines() {
+                let mut columns = line.split('\t');
+                let (Some(added), Some(deleted), Some(_)) = (columns.next(), columns.next(), columns.next()) else {
+                    continue;
+                };
+                entry.files_changed += 1;
+                entry.insertions += added.parse::<usize>().unwrap_or(0);
+                entry.deletions += deleted.parse::<usize>().unwrap_or(0);
+            }
+            Some(entry)
+        })
+        .collect()
+}
+
+fn log(dir: &Path, options: &LogOptions, follow: Option<&str>) -> Result<Vec<GitLogEntry>, GitError> {
+    // Before the first commit there's nothing to show
+    if !exists(dir, "HEAD")? {
+        return Ok(Vec::new());
+    }
+    let limit = format!("--max-count={}", options.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT));
+    let skip = format!("--skip={}", options.skip.unwrap_or(0));
+    let author = options.author.as_ref().map(|author| format!("--author={}", author));
+    let since = options.since.as_ref().map(|since| format!("--since={}", since));
+
+    let mut args = vec!["--literal-pathspecs", "log", FORMAT, "--numstat", "--no-renames", &limit, &skip];
+    args.extend(author.as_deref());
+    args.extend(since.as_deref());
+    match follow {
+        // Through renames, which needs exactly one file
+        Some(file) => args.extend(["--follow", "--", file]),
+        None => {
+            args.push("--");
+            args.extend(options.path.as_deref());
+        }
+    }
+    let output = check(dir, &args)?;
+    Ok(parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// Commits on the current branch, newest first, with their line counts
+#[tauri::command]
+pub async fn git_log(project_path: String, options: Option<LogOptions>) -> Result<Vec<GitLogEntry>, GitError> {
+    let options = options.unwrap_or_default();
+    blocking(move || log(&repo(&project_path)?, &options, None)).await
+}
+
+// The commits that changed one file, following it back through renames
+#[tauri::command]
+pub async fn git_file_history(path: String, limit: Option<usize>) -> Result<Vec<GitLogEntry>, GitError> {
+    let options = LogOptions {
+        limit,
+        ..LogOptions::default()
+    };
+    blocking(move || {
+        let (dir, name) = file_repo(&path)?;
+        log(&dir, &options, Some(&name))
+    })
+    .await
+}
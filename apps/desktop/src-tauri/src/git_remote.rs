@@ -0,0 +1,521 @@
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use tauri::{AppHandle, Emitter, WebviewWindow};
+
+use crate::{app_lock, audit, redact, secrets};
+use crate::git::{self, blocking, check, indented_files, repo, run, stdout, GitError};
+
+// Tokens are kept through `secrets` as "git:<host>"; older versions kept
+// them in the keychain themselves under this service, with that account
+const LEGACY_KEYCHAIN_SERVICE: &str = "Incito";
+
+// Answers git's credential requests from a file only the user can read,
+// and only for the host the token is for. Git asks about every host it
+// connects to, submodules' and redirects' included. `{file}` is replaced
+// with the file's path.
+const CREDENTIAL_HELPER: &str = "credential.helper=!f() { test \"$1\" = get || return 0; want=; \
+     while IFS= read -r line && test -n \"$line\"; do case \"$line\" in \
+     host=*) want=$(printf %s \"${line#host=}\" | tr 'A-Z' 'a-z');; esac; done; \
+     test -n \"$want\" && test \"host=$want\" = \"$(sed -n 1p '{file}')\" && sed -n '2,3p' '{file}'; }; f";
+
+// What git prints when a remote won't let it in, over HTTPS and SSH
+const AUTH_FAILURES: &[&str] = &[
+    "Authentication failed",
+    "could not read Username",
+    "could not read Password",
+    "terminal prompts disabled",
+    "Permission denied (publickey",
+    "Host key verification failed",
+    "HTTP Basic: Access denied",
+    "The requested URL returned error: 403",
+];
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredToken {
+    username: String,
+    token: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct GitProgress {
+    project_path: String,
+    // "fetch", "pull" or "push"
+    operation: &'static str,
+    // e.g. "Receiving objects"
+    stage: String,
+    percent: u8,
+    message: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct PullResult {
+    pub updated: bool,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    // Files left with conflict markers; the merge or rebase is still in
+    // progress until they're resolved
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct PushResult {
+    pub remote: String,
+    pub branch: String,
+    // The branch had no upstream and now tracks the pushed one
+    pub set_upstream: bool,
+}
+
+struct Remote {
+    name: String,
+    // For HTTPS remotes; SSH ones go through ssh-agent
+    host: Option<String>,
+}
+
+struct Finished {
+    success: bool,
+    stdout: String,
+    stderr: String,
+}
+
+// Removed with its directory once git is done, so the token is only on
+// disk for as long as the fetch, pull or push runs. Kept out of the
+// environment, which hooks like pre-push inherit.
+struct CredentialFile {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl CredentialFile {
+    fn create(host: &str, token: &StoredToken) -> Result<Self, String> {
+        let fields = [host, &token.username, &token.token];
+        if fields.iter().any(|field| field.contains(['\n', '\r', '\''])) {
+            return Err("The stored git token can't be passed to git".to_string());
+        }
+        let dir = std::env::temp_dir().join(format!("incito-git-{}", uuid::Uuid::new_v4().simple()));
+        let mut builder = std::fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+        builder
+            .create(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let file = CredentialFile {
+            path: dir.join("credentials"),
+            dir,
+        };
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let contents = format!("host={}\nusername={}\npassword={}\n", host, token.username, token.token);
+        options
+            .open(&file.path)
+            .and_then(|mut handle| handle.write_all(contents.as_bytes()))
+            .map_err(|e| format!("Failed to write {}: {}", file.path.display(), e))?;
+        Ok(file)
+    }
+
+    // Git for Windows' sh takes forward slashes
+    fn helper(&self) -> String {
+        CREDENTIAL_HELPER.replace("{file}", &self.path.to_string_lossy().replace('\\', "/"))
+    }
+}
+
+impl Drop for CredentialFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn secret_name(host: &str) -> String {
+    format!("git:{}", host)
+}
+
+// Where tokens were kept before they went through `secrets`
+fn legacy_entry(host: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(LEGACY_KEYCHAIN_SERVICE, &secret_name(host))
+        .map_err(|e| format!("Failed to open the system keychain: {}", e))
+}
+
+// A token saved by an older version is moved into `secrets` the first
+// time it's looked up
+fn migrate_legacy(host: &str) -> Result<Option<String>, String> {
+    let entry = legacy_entry(host)?;
+    let secret = match entry.get_password() {
+        Ok(secret) => secret,
+        Err(keyring::Error::NoEntry) => return Ok(None),
+        Err(e) => return Err(format!("Failed to read the token from the system keychain: {}", e)),
+    };
+    secrets::set(&secret_name(host), &secret)?;
+    let _ = entry.delete_credential();
+    Ok(Some(secret))
+}
+
+fn stored_token(host: &str) -> Option<StoredToken> {
+    let secret = match secrets::get(&secret_name(host)) {
+        Ok(Some(secret)) => secret,
+        Ok(None) => migrate_legacy(host).ok()??,
+        Err(_) => return None,
+    };
+    let stored: StoredToken = serde_json::from_str(&secret).ok()?;
+    redact::remember(&stored.token);
+    Some(stored)
+}
+
+// "https://user@host:443/owner/repo.git" has host "host:443"; scp-style
+// and ssh:// URLs have none, since they don't take tokens
+fn https_host(url: &str) -> Option<String> {
+    let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"))?;
+    let authority = rest.split('/').next()?;
+    let host = authority.rsplit('@').next()?;
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+pub fn current_branch(dir: &Path) -> Result<String, GitError> {
+    let output = run(dir, &["symbolic-ref", "--short", "-q", "HEAD"])?;
+    if !output.status.success() {
+        return Err(GitError::Failed {
+            message: "HEAD is detached; switch to a branch first".to_string(),
+        });
+    }
+    Ok(stdout(&output).trim().to_string())
+}
+
+pub fn has_upstream(dir: &Path) -> Result<bool, GitError> {
+    Ok(run(dir, &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{upstream}"])?.status.success())
+}
+
+// The remote asked for, else the branch's upstream's, else origin
+fn remote(dir: &Path, name: Option<String>) -> Result<Remote, GitError> {
+    let name = match name.filter(|name| !name.trim().is_empty()) {
+        Some(name) => name,
+        None => {
+            let branch = current_branch(dir).ok();
+            let configured = branch
+                .map(|branch| run(dir, &["config", "--get", &format!("branch.{}.remote", branch)]))
+                .transpose()?
+                .filter(|output| output.status.success())
+                .map(|output| stdout(&output).trim().to_string());
+            configured.unwrap_or_else(|| "origin".to_string())
+        }
+    };
+    let url = run(dir, &["remote", "get-url", &name])?;
+    if !url.status.success() {
+        return Err(GitError::Failed {
+            message: format!("No remote named {}", name),
+        });
+    }
+    Ok(Remote {
+        host: https_host(stdout(&url).trim()),
+        name,
+    })
+}
+
+// "Receiving objects:  45% (9/20), 1.20 MiB | 2.00 MiB/s"
+fn progress(project_path: &str, operation: &'static str, line: &str) -> Option<GitProgress> {
+    let message = line.strip_prefix("remote: ").unwrap_or(line).trim();
+    let (stage, rest) = message.split_once(':')?;
+    // Stages without a percentage are one-off messages
+    let percent = rest.trim_start().split('%').next().and_then(|percent| percent.trim().parse().ok())?;
+    Some(GitProgress {
+        project_path: project_path.to_string(),
+        operation,
+        stage: stage.trim().to_string(),
+        percent,
+        message: message.to_string(),
+    })
+}
+
+// Run a fetch, pull or push with the stored token for the remote, if any,
+// and never a prompt; emits `git-progress` from what git prints
+fn run_remote(
+    app: &AppHandle,
+    dir: &Path,
+    project_path: &str,
+    operation: &'static str,
+    remote: &Remote,
+    args: &[&str],
+) -> Result<Finished, GitError> {
+    let credentials = match remote.host.as_deref() {
+        Some(host) => {
+            // Like any other secret, not even looked up while the app is locked
+            app_lock::ensure_unlocked(app).map_err(|message| GitError::Failed { message })?;
+            match stored_token(host) {
+                Some(token) => {
+                    audit::record(app, None, "secret", "read", &format!("git token for {}", host));
+                    Some(CredentialFile::create(host, &token).map_err(|message| GitError::Failed { message })?)
+                }
+                None => None,
+            }
+        }
+        None => None,
+    };
+    let helper = credentials.as_ref().map(CredentialFile::helper);
+    let mut all: Vec<&str> = Vec::new();
+    if let Some(helper) = &helper {
+        // Only the stored token, not whatever helper would answer otherwise
+        all.extend(["-c", "credential.helper=", "-c", helper]);
+    }
+    all.extend_from_slice(args);
+
+    let mut command = git::command(dir, &all);
+    command
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // An SSH key with a passphrase not in the agent fails rather than waits
+    let ssh_configured = std::env::var_os("GIT_SSH_COMMAND").is_some()
+        || run(dir, &["config", "--get", "core.sshCommand"])?.status.success();
+    if !ssh_configured {
+        command.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    }
+    let mut child = command.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => GitError::GitNotInstalled,
+        _ => GitError::Failed {
+            message: format!("Failed to run git: {}", e),
+        },
+    })?;
+
+    let mut out = child.stdout.take();
+    let reader = std::thread::spawn(move || {
+        let mut text = String::new();
+        if let Some(out) = out.as_mut() {
+            let _ = out.read_to_string(&mut text);
+        }
+        text
+    });
+
+    // Progress lines end in \r as they update, and \n once done
+    let mut stderr = String::new();
+    let mut line = Vec::new();
+    let mut buffer = [0u8; 4096];
+    if let Some(mut err) = child.stderr.take() {
+        while let Ok(read) = err.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+            for &byte in &buffer[..read] {
+                if byte != b'\r' && byte != b'\n' {
+                    line.push(byte);
+                    continue;
+                }
+                let text = String::from_utf8_lossy(&line).to_string();
+                line.clear();
+                if let Some(progress) = progress(project_path, operation, &text) {
+                    let _ = app.emit("git-progress", progress);
+                }
+                // Only finished lines are kept for errors
+                if byte == b'\n' {
+                    stderr.push_str(&text);
+                    stderr.push('\n');
+                }
+            }
+        }
+    }
+    stderr.push_str(&String::from_utf8_lossy(&line));
+
+    let status = child.wait().map_err(|e| GitError::Failed {
+        message: format!("Failed to run git: {}", e),
+    })?;
+    Ok(Finished {
+        success: status.success(),
+        stdout: reader.join().unwrap_or_default(),
+        stderr,
+    })
+}
+
+fn failure(remote: &Remote, finished: &Finished) -> GitError {
+    if AUTH_FAILURES.iter().any(|pattern| finished.stderr.contains(pattern)) {
+        return GitError::AuthFailed {
+            host: remote.host.clone(),
+        };
+    }
+    GitError::Failed {
+        message: finished.stderr.trim().to_string(),
+    }
+}
+
+fn head(dir: &Path) -> Option<String> {
+    check(dir, &["rev-parse", "HEAD"])
+        .ok()
+        .map(|output| stdout(&output).trim().to_string())
+}
+
+fn fetch(app: &AppHandle, project_path: &str, remote_name: Option<String>) -> Result<(), GitError> {
+    let dir = repo(project_path)?;
+    let remote = remote(&dir, remote_name)?;
+    let args = ["fetch", "--progress", "--prune", remote.name.as_str()];
+    let finished = run_remote(app, &dir, project_path, "fetch", &remote, &args)?;
+    if !finished.success {
+        return Err(failure(&remote, &finished));
+    }
+    Ok(())
+}
+
+fn pull(app: &AppHandle, project_path: &str, rebase: bool) -> Result<PullResult, GitError> {
+    let dir = repo(project_path)?;
+    let branch = current_branch(&dir)?;
+    if !has_upstream(&dir)? {
+        return Err(GitError::NoUpstream { branch });
+    }
+    let remote = remote(&dir, None)?;
+    let from = head(&dir);
+    let mode = if rebase { "--rebase" } else { "--no-rebase" };
+    let finished = run_remote(app, &dir, project_path, "pull", &remote, &["pull", "--progress", mode])?;
+
+    if !finished.success {
+        let conflicts: Vec<String> = stdout(&check(&dir, &["diff", "--name-only", "--diff-filter=U", "-z"])?)
+            .split('\0')
+            .filter(|file| !file.is_empty())
+            .map(str::to_string)
+            .collect();
+        if conflicts.is_empty() {
+            // Nothing was merged because it would overwrite local changes
+            let files = indented_files(&finished.stderr);
+            if !files.is_empty() {
+                return Err(GitError::DirtyWorktree { files });
+            }
+            return Err(failure(&remote, &finished));
+        }
+        return Ok(PullResult {
+            updated: true,
+            from,
+            to: head(&dir),
+            conflicts,
+        });
+    }
+    let to = head(&dir);
+    Ok(PullResult {
+        updated: from != to,
+        from,
+        to,
+        conflicts: Vec::new(),
+    })
+}
+
+fn push(
+    app: &AppHandle,
+    project_path: &str,
+    remote_name: Option<String>,
+    set_upstream: bool,
+    force_with_lease: bool,
+) -> Result<PushResult, GitError> {
+    let dir = repo(project_path)?;
+    let branch = current_branch(&dir)?;
+    let tracked = has_upstream(&dir)?;
+    if !tracked && !set_upstream {
+        return Err(GitError::NoUpstream { branch });
+    }
+    let remote = remote(&dir, remote_name)?;
+
+    // --porcelain puts each ref's outcome on stdout, "!" for rejected
+    let mut args = vec!["push", "--progress", "--porcelain"];
+    if force_with_lease {
+        args.push("--force-with-lease");
+    }
+    if !tracked {
+        args.push("--set-upstream");
+    }
+    args.extend([remote.name.as_str(), "HEAD"]);
+    let finished = run_remote(app, &dir, project_path, "push", &remote, &args)?;
+
+    let rejected = finished.stdout.lines().find(|line| line.starts_with("!\t"));
+    if let Some(line) = rejected {
+        return Err(GitError::PushRejected {
+            message: line.rsplit('\t').next().unwrap_or(line).trim().to_string(),
+        });
+    }
+    if !finished.success {
+        return Err(failure(&remote, &finished));
+    }
+    Ok(PushResult {
+        remote: remote.name,
+        branch,
+        set_upstream: !tracked,
+    })
+}
+
+// Fetch from a remote, the current branch's by default, pruning deleted
+// branches. Progress comes as `git-progress` events.
+#[tauri::command]
+pub async fn git_fetch(app: AppHandle, project_path: String, remote: Option<String>) -> Result<(), GitError> {
+    blocking(move || fetch(&app, &project_path, remote)).await
+}
+
+// Pull into the current branch from its upstream, merging unless `rebase`.
+// Conflicts come back in the result for the user to resolve.
+#[tauri::command]
+pub async fn git_pull(app: AppHandle, project_path: String, rebase: Option<bool>) -> Result<PullResult, GitError> {
+    blocking(move || pull(&app, &project_path, rebase.unwrap_or(false))).await
+}
+
+// Push the current branch. One without an upstream is pushed under its own
+// name and tracks it, unless `set_upstream` is false.
+#[tauri::command]
+pub async fn git_push(
+    app: AppHandle,
+    project_path: String,
+    remote: Option<String>,
+    set_upstream: Option<bool>,
+    force_with_lease: Option<bool>,
+) -> Result<PushResult, GitError> {
+    blocking(move || {
+        push(
+            &app,
+            &project_path,
+            remote,
+            set_upstream.unwrap_or(true),
+            force_with_lease.unwrap_or(false),
+        )
+    })
+    .await
+}
+
+// Keep an HTTPS token for a host, e.g. "github.com", in the system
+// keychain. It's used for that host's remotes instead of git's own
+// credential helpers.
+#[tauri::command]
+pub fn set_git_token(
+    app: AppHandle,
+    window: WebviewWindow,
+    host: String,
+    username: String,
+    token: String,
+) -> Result<(), String> {
+    let host = host.trim().to_lowercase();
+    if host.is_empty() || token.trim().is_empty() {
+        return Err("A host and token are required".to_string());
+    }
+    audit::record(&app, Some(window.label()), "secret", "write", &format!("git token for {}", host));
+    redact::remember(&token);
+    let secret = serde_json::to_string(&StoredToken { username, token }).map_err(|e| e.to_string())?;
+    secrets::set(&secret_name(&host), &secret)?;
+    // Otherwise it would be moved over the new one on the next lookup
+    let _ = legacy_entry(&host).and_then(|entry| entry.delete_credential().map_err(|e| e.to_string()));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_git_token(app: AppHandle, window: WebviewWindow, host: String) -> Result<(), String> {
+    let host = host.trim().to_lowercase();
+    audit::record(&app, Some(window.label()), "secret", "delete", &format!("git token for {}", host));
+    match legacy_entry(&host)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(format!("Failed to remove the token from the system keychain: {}", e)),
+    }
+    secrets::delete(&secret_name(&host))
+}
+
+#[tauri::command]
+pub fn has_git_token(host: String) -> bool {
+    stored_token(&host.trim().to_lowercase()).is_some()
+}
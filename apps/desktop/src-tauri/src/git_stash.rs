@@ -0,0 +1,151 @@
+use std::path::Path;
+
+use crate::git::{blocking, check, exists, failed, indented_files, repo, run, stdout, GitError};
+
+#[derive(Clone, serde::Serialize)]
+pub struct GitStash {
+    // As in stash@{index}; 0 is the newest
+    pub index: usize,
+    pub hash: String,
+    pub message: String,
+    // The branch it was made on
+    pub branch: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct StashApplied {
+    // Files left with conflict markers, to be resolved by hand
+    pub conflicts: Vec<String>,
+    // Popping keeps the stash when there are conflicts, as git does
+    pub dropped: bool,
+}
+
+// "On main: message", or "WIP on main: abc123 subject" without a message
+fn subject(subject: &str) -> (Option<String>, String) {
+    let rest = subject
+        .strip_prefix("WIP on ")
+        .or_else(|| subject.strip_prefix("On "))
+        .unwrap_or(subject);
+    match rest.split_once(": ") {
+        Some((branch, message)) if branch != "(no branch)" => (Some(branch.to_string()), message.to_string()),
+        Some((_, message)) => (None, message.to_string()),
+        None => (None, subject.to_string()),
+    }
+}
+
+fn list(dir: &Path) -> Result<Vec<GitStash>, GitError> {
+    let output = stdout(&check(dir, &["stash", "list", "--format=%H%x00%gs%x00%cI"])?);
+    Ok(output
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let fields: Vec<&str> = line.split('\0').collect();
+            let [hash, reflog_subject, created_at] = fields[..] else {
+                return None;
+            };
+            let (branch, message) = subject(reflog_subject);
+            Some(GitStash {
+                index,
+                hash: hash.to_string(),
+                message,
+                branch,
+                created_at: created_at.to_string(),
+            })
+        })
+        .collect())
+}
+
+fn top(dir: &Path) -> Result<Option<GitStash>, GitError> {
+    Ok(list(dir)?.into_iter().next())
+}
+
+fn push(dir: &Path, message: Option<&str>, include_untracked: bool) -> Result<Option<GitStash>, GitError> {
+    let before = top(dir)?.map(|stash| stash.hash);
+    let mut args = vec!["stash", "push"];
+    if include_untracked {
+        args.push("--include-untracked");
+    }
+    if let Some(message) = message.filter(|message| !message.trim().is_empty()) {
+        args.extend(["--message", message]);
+    }
+    check(dir, &args)?;
+    // Git succeeds without making one when there's nothing to stash
+    Ok(top(dir)?.filter(|stash| Some(&stash.hash) != before.as_ref()))
+}
+
+fn reference(dir: &Path, index: usize) -> Result<String, GitError> {
+    let reference = format!("stash@{{{}}}", index);
+    if !exists(dir, &reference)? {
+        return Err(GitError::StashNotFound { index });
+    }
+    Ok(reference)
+}
+
+fn apply(dir: &Path, index: usize, pop: bool) -> Result<StashApplied, GitError> {
+    let reference = reference(dir, index)?;
+    let output = run(dir, &["stash", if pop { "pop" } else { "apply" }, &reference])?;
+    if output.status.success() {
+        return Ok(StashApplied {
+            conflicts: Vec::new(),
+            dropped: pop,
+        });
+    }
+
+    let conflicts: Vec<String> = stdout(&check(dir, &["diff", "--name-only", "--diff-filter=U", "-z"])?)
+        .split('\0')
+        .filter(|file| !file.is_empty())
+        .map(str::to_string)
+        .collect();
+    if !conflicts.is_empty() {
+        return Ok(StashApplied {
+            conflicts,
+            dropped: false,
+        });
+    }
+    // Nothing was applied because it would overwrite local changes
+    let files = indented_files(&String::from_utf8_lossy(&output.stderr));
+    if !files.is_empty() {
+        return Err(GitError::DirtyWorktree { files });
+    }
+    Err(failed(&output))
+}
+
+fn drop_stash(dir: &Path, index: usize) -> Result<(), GitError> {
+    let reference = reference(dir, index)?;
+    check(dir, &["stash", "drop", &reference])?;
+    Ok(())
+}
+
+// Stash uncommitted changes, untracked files too unless told otherwise.
+// None if there was nothing to stash.
+#[tauri::command]
+pub async fn git_stash_push(
+    project_path: String,
+    message: Option<String>,
+    include_untracked: Option<bool>,
+) -> Result<Option<GitStash>, GitError> {
+    blocking(move || push(&repo(&project_path)?, message.as_deref(), include_untracked.unwrap_or(true))).await
+}
+
+#[tauri::command]
+pub async fn git_stash_list(project_path: String) -> Result<Vec<GitStash>, GitError> {
+    blocking(move || list(&repo(&project_path)?)).await
+}
+
+// Apply a stash and drop it. Conflicts come back in the result rather than
+// as an error, since the rest of the stash was applied.
+#[tauri::command]
+pub async fn git_stash_pop(project_path: String, index: Option<usize>) -> Result<StashApplied, GitError> {
+    blocking(move || apply(&repo(&project_path)?, index.unwrap_or(0), true)).await
+}
+
+#[tauri::command]
+pub async fn git_stash_apply(project_path: String, index: Option<usize>) -> Result<StashApplied, GitError> {
+    blocking(move || apply(&repo(&project_path)?, index.unwrap_or(0), false)).await
+}
+
+#[tauri::command]
+pub async fn git_stash_drop(project_path: String, index: usize) -> Result<(), GitError> {
+    blocking(move || drop_stash(&repo(&project_path)?, index)).await
+}
@@ -0,0 +1,187 @@
+use std::path::Path;
+use std::process::Output;
+
+use crate::git;
+
+#[derive(Clone, Default, serde::Serialize)]
+pub struct GitStatus {
+    pub is_repo: bool,
+    // None when HEAD is detached
+    pub branch: Option<String>,
+    pub head: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+    pub files: Vec<GitFileStatus>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct GitFileStatus {
+    // Relative to the project, with forward slashes
+    pub path: String,
+    // Where a renamed or copied file came from
+    pub orig_path: Option<String>,
+    // "modified", "added", "deleted", "renamed", "copied" or "type_changed"
+    // in the index and in the working tree, none if unchanged there
+    pub staged: Option<&'static str>,
+    pub unstaged: Option<&'static str>,
+    pub untracked: bool,
+    pub conflicted: bool,
+}
+
+fn run(dir: &Path, args: &[&str]) -> Result<Output, String> {
+    git::git(dir, args).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => "Git isn't installed".to_string(),
+        _ => format!("Failed to run git: {}", e),
+    })
+}
+
+fn state(code: u8) -> Option<&'static str> {
+    match code {
+        b'M' => Some("modified"),
+        b'A' => Some("added"),
+        b'D' => Some("deleted"),
+        b'R' => Some("renamed"),
+        b'C' => Some("copied"),
+        b'T' => Some("type_changed"),
+        _ => None,
+    }
+}
+
+// Paths come relative to the repository, the project may be inside it
+fn project_path(prefix: &str, path: &str) -> String {
+    path.strip_prefix(prefix).unwrap_or(path).to_string()
+}
+
+// `git status --porcelain=v2 --branch -z`: NUL-separated records, with a
+// rename's original path as a record of its own
+fn parse(output: &[u8], prefix: &str) -> GitStatus {
+    let text = String::from_utf8_lossy(output);
+    let mut records = text.split('\0').filter(|record| !record.is_empty());
+    let mut status = GitStatus {
+        is_repo: true,
+        ..GitStatus::default()
+    };
+
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            match key {
+                "branch.oid" if value != "(initial)" => status.head = Some(value.to_string()),
+                "branch.head" if value != "(detached)" => status.branch = Some(value.to_string()),
+                "branch.upstream" => status.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    for count in value.split(' ') {
+                        if let Some(ahead) = count.strip_prefix('+') {
+                            status.ahead = ahead.parse().unwrap_or(0);
+                        } else if let Some(behind) = count.strip_prefix('-') {
+                            status.behind = behind.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        // Fields before the path: ordinary changes have 8, renames and
+        // copies 9, unmerged entries 10
+        let (kind, rest) = record.split_at(1);
+        let fields = match kind {
+            "1" => 8,
+            "2" => 9,
+            "u" => 10,
+            "?" => {
+                status.files.push(GitFileStatus {
+                    path: project_path(prefix, rest.strip_prefix(' ').unwrap_or(rest)),
+                    orig_path: None,
+                    staged: None,
+                    unstaged: None,
+                    untracked: true,
+                    conflicted: false,
+                });
+                continue;
+            }
+            _ => continue,
+        };
+        let parts: Vec<&str> = record.splitn(fields + 1, ' ').collect();
+        let (Some(codes), Some(path)) = (parts.get(1), parts.get(fields)) else {
+            continue;
+        };
+        let codes = codes.as_bytes();
+        let orig_path = (kind == "2").then(|| records.next()).flatten();
+        status.files.push(GitFileStatus {
+            path: project_path(prefix, path),
+            orig_path: orig_path.map(|orig| project_path(prefix, orig)),
+            staged: codes.first().copied().and_then(state).filter(|_| kind != "u"),
+            unstaged: codes.get(1).copied().and_then(state).filter(|_| kind != "u"),
+            untracked: false,
+            conflicted: kind == "u",
+        });
+    }
+    status
+}
+
+fn status(dir: &Path) -> Result<GitStatus, String> {
+    // Fails outside a repository, and says where the project sits in one
+    let prefix = run(dir, &["rev-parse", "--show-prefix"])?;
+    if !prefix.status.success() {
+        return Ok(GitStatus::default());
+    }
+    let prefix = String::from_utf8_lossy(&prefix.stdout).trim_end().to_string();
+
+    let output = run(
+        dir,
+        &["status", "--porcelain=v2", "--branch", "-z", "--untracked-files=all", "--", "."],
+    )?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to read git status: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(parse(&output.stdout, &prefix))
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct WorktreeCheck {
+    pub is_repo: bool,
+    // Outside a repository there's nothing to tangle with, so it's clean
+    pub clean: bool,
+    // Changed and untracked files, relative to the project
+    pub dirty_files: Vec<String>,
+}
+
+pub async fn project_status(project_path: &str) -> Result<GitStatus, String> {
+    let dir = Path::new(project_path).to_path_buf();
+    if !dir.is_dir() {
+        return Err(format!("Not a directory: {}", project_path));
+    }
+    tauri::async_runtime::spawn_blocking(move || status(&dir))
+        .await
+        .map_err(|e| format!("Git status task failed: {}", e))?
+}
+
+pub async fn check_worktree(project_path: &str) -> Result<WorktreeCheck, String> {
+    let status = project_status(project_path).await?;
+    let dirty_files: Vec<String> = status.files.into_iter().map(|file| file.path).collect();
+    Ok(WorktreeCheck {
+        is_repo: status.is_repo,
+        clean: dirty_files.is_empty(),
+        dirty_files,
+    })
+}
+
+// Branch, ahead/behind its upstream and the changed files under the
+// project. Not being in a repository isn't an error; `is_repo` is false.
+#[tauri::command]
+pub async fn git_status(project_path: String) -> Result<GitStatus, String> {
+    project_status(&project_path).await
+}
+
+// Whether the project has uncommitted changes a task's edits would get
+// mixed up with
+#[tauri::command]
+pub async fn check_worktree_clean(project_path: String) -> Result<WorktreeCheck, String> {
+    check_worktree(&project_path).await
+}
@@ -0,0 +1,200 @@
+use std::path::Path;
+
+use tauri::AppHandle;
+
+use crate::git::{blocking, check, exists, repo, stdout, GitError};
+use crate::{git_branches, projects, windows};
+
+#[derive(Clone, serde::Serialize)]
+pub struct GitWorktree {
+    pub path: String,
+    // None when detached
+    pub branch: Option<String>,
+    pub head: Option<String>,
+    // The repository's own checkout, which can't be removed
+    pub main: bool,
+    pub locked: bool,
+    // Its folder is gone; `git worktree prune` would drop it
+    pub prunable: bool,
+    // The registered project at this path, if any
+    pub project_id: Option<String>,
+}
+
+fn failure(message: String) -> GitError {
+    GitError::Failed { message }
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    let canonical = |path: &Path| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    canonical(a) == canonical(b)
+}
+
+// `git worktree list --porcelain`: a block of "key value" lines each, the
+// main worktree first
+fn list(dir: &Path) -> Result<Vec<GitWorktree>, GitError> {
+    let output = stdout(&check(dir, &["worktree", "list", "--porcelain"])?);
+    let mut worktrees = Vec::new();
+    for block in output.split("\n\n") {
+        let mut worktree: Option<GitWorktree> = None;
+        for line in block.lines() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            if key == "worktree" {
+                worktree = Some(GitWorktree {
+                    path: value.to_string(),
+                    branch: None,
+                    head: None,
+                    main: worktrees.is_empty(),
+                    locked: false,
+                    prunable: false,
+                    project_id: None,
+                });
+                continue;
+            }
+            let Some(worktree) = worktree.as_mut() else { continue };
+            match key {
+                "HEAD" => worktree.head = Some(value.to_string()),
+                "branch" => worktree.branch = Some(value.strip_prefix("refs/heads/").unwrap_or(value).to_string()),
+                "locked" => worktree.locked = true,
+                "prunable" => worktree.prunable = true,
+                _ => {}
+            }
+        }
+        worktrees.extend(worktree);
+    }
+    Ok(worktrees)
+}
+
+async fn with_projects(app: &AppHandle, mut worktrees: Vec<GitWorktree>) -> Result<Vec<GitWorktree>, GitError> {
+    let projects = projects::all(app).await.map_err(failure)?;
+    for worktree in &mut worktrees {
+        worktree.project_id = projects
+            .iter()
+            .find(|project| same_path(Path::new(&project.path), Path::new(&worktree.path)))
+            .map(|project| project.id.clone());
+    }
+    Ok(worktrees)
+}
+
+fn add(dir: &Path, branch: &str, path: &Path) -> Result<(), GitError> {
+    git_branches::check_name(dir, branch)?;
+    let target = path.to_string_lossy();
+    // An existing branch is checked out there; otherwise it's made from HEAD
+    if exists(dir, &format!("refs/heads/{}", branch))? {
+        check(dir, &["worktree", "add", "--", &target, branch])?;
+    } else {
+        check(dir, &["worktree", "add", "-b", branch, "--", &target])?;
+    }
+    Ok(())
+}
+
+fn remove(dir: &Path, path: &Path, force: bool, delete_branch: bool) -> Result<(), GitError> {
+    let worktree = list(dir)?
+        .into_iter()
+        .find(|worktree| same_path(Path::new(&worktree.path), path))
+        .ok_or_else(|| failure(format!("{} isn't a worktree of this repository", path.display())))?;
+    if worktree.main {
+        return Err(failure("The main worktree can't be removed".to_string()));
+    }
+
+    // Changes, untracked files included, would be lost with the folder
+    if !force && !worktree.prunable {
+        let status = stdout(&check(path, &["status", "--porcelain", "-z"])?);
+        let files: Vec<String> = status
+            .split('\0')
+            .filter_map(|record| record.get(3..))
+            .map(str::to_string)
+            .collect();
+        if !files.is_empty() {
+            return Err(GitError::DirtyWorktree { files });
+        }
+    }
+    let mut args = vec!["worktree", "remove"];
+    if force {
+        args.push("--force");
+    }
+    let target = worktree.path.clone();
+    args.extend(["--", &target]);
+    check(dir, &args)?;
+
+    // Discarding a task's work also drops its branch; without `force` an
+    // unmerged branch is kept and git says why
+    if let (true, Some(branch)) = (delete_branch, worktree.branch) {
+        check(dir, &["branch", if force { "-D" } else { "-d" }, "--", &branch])?;
+    }
+    Ok(())
+}
+
+// Every worktree of the project's repository, with the project registered
+// for each
+#[tauri::command]
+pub async fn git_worktree_list(app: AppHandle, project_path: String) -> Result<Vec<GitWorktree>, GitError> {
+    let worktrees = blocking(move || list(&repo(&project_path)?)).await?;
+    with_projects(&app, worktrees).await
+}
+
+// Check out `branch` into a new worktree at `path`, making the branch from
+// HEAD if it doesn't exist, and register it as a project. With
+// `open_window` it also opens in a window of its own, for a parallel task.
+#[tauri::command]
+pub async fn git_worktree_add(
+    app: AppHandle,
+    project_path: String,
+    branch: String,
+    path: String,
+    open_window: Option<bool>,
+) -> Result<GitWorktree, GitError> {
+    let dir = repo(&project_path)?;
+    // Relative to the project's folder
+    let target = dir.join(&path);
+    let (repo_dir, add_target, add_branch) = (dir.clone(), target.clone(), branch.clone());
+    blocking(move || add(&repo_dir, &add_branch, &add_target)).await?;
+
+    let repo_name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| project_path.clone());
+    let project = projects::register_project(
+        app.clone(),
+        target.to_string_lossy().to_string(),
+        Some(format!("{} ({})", repo_name, branch)),
+    )
+    .await
+    .map_err(failure)?;
+    if open_window.unwrap_or(false) {
+        windows::open_project_window(app.clone(), project.id.clone()).map_err(failure)?;
+    }
+
+    let worktrees = blocking(move || list(&dir)).await?;
+    with_projects(&app, worktrees)
+        .await?
+        .into_iter()
+        .find(|worktree| same_path(Path::new(&worktree.path), &target))
+        .ok_or_else(|| failure(format!("Failed to add worktree {}", target.display())))
+}
+
+// Remove a worktree and the project registered for it. Refuses with
+// uncommitted changes unless `force`; `delete_branch` also deletes its
+// branch, for discarding a task rather than merging it.
+#[tauri::command]
+pub async fn git_worktree_remove(
+    app: AppHandle,
+    project_path: String,
+    path: String,
+    force: Option<bool>,
+    delete_branch: Option<bool>,
+) -> Result<(), GitError> {
+    let dir = repo(&project_path)?;
+    let target = dir.join(&path);
+    let project = projects::all(&app)
+        .await
+        .map_err(failure)?
+        .into_iter()
+        .find(|project| same_path(Path::new(&project.path), &target));
+
+    let removed = target.clone();
+    blocking(move || remove(&dir, &removed, force.unwrap_or(false), delete_branch.unwrap_or(false))).await?;
+    if let Some(project) = project {
+        projects::remove_project(app, project.id).await.map_err(failure)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use crate::git::{blocking, repo, run, stdout, GitError};
+use crate::{claude_cli, git_remote};
+
+// Where installers put gh when the app's PATH, from the Dock or Start
+// menu, doesn't have it
+#[cfg(not(windows))]
+const GH_LOCATIONS: &[&str] = &["/opt/homebrew/bin/gh", "/usr/local/bin/gh", "/usr/bin/gh"];
+#[cfg(windows)]
+const GH_LOCATIONS: &[&str] = &[r"C:\Program Files\GitHub CLI\gh.exe"];
+
+const DEFAULT_HOST: &str = "github.com";
+
+#[derive(Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct PullRequestOptions {
+    pub title: String,
+    pub body: String,
+    // The repository's default branch when not given
+    pub base: Option<String>,
+    pub draft: bool,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct PullRequest {
+    pub url: String,
+    pub number: Option<u64>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct GhStatus {
+    pub installed: bool,
+    pub version: Option<String>,
+    pub host: String,
+    pub authenticated: bool,
+}
+
+fn find_gh() -> Option<PathBuf> {
+    claude_cli::which("gh")
+        .map(PathBuf::from)
+        .or_else(|| GH_LOCATIONS.iter().map(PathBuf::from).find(|path| path.is_file()))
+}
+
+fn gh(gh: &Path, dir: &Path, args: &[&str]) -> Result<Output, GitError> {
+    let mut command = Command::new(gh);
+    command
+        .args(args)
+        .current_dir(dir)
+        // Fail instead of waiting on a prompt no one can answer
+        .env("GH_PROMPT_DISABLED", "1")
+        .env("GH_NO_UPDATE_NOTIFIER", "1");
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command.output().map_err(|e| GitError::Failed {
+        message: format!("Failed to run gh: {}", e),
+    })
+}
+
+// The host of a remote, over HTTPS or SSH: "github.com" for
+// "git@github.com:owner/repo.git" and "https://github.com/owner/repo"
+fn remote_host(url: &str) -> Option<String> {
+    let rest = url
+        .split_once("://")
+        .map(|(_, rest)| rest.split('/').next().unwrap_or(rest))
+        .unwrap_or_else(|| url.split(':').next().unwrap_or(url));
+    let host = rest.rsplit('@').next()?.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+fn host(dir: &Path) -> String {
+    run(dir, &["remote", "get-url", "origin"])
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| remote_host(stdout(&output).trim()))
+        .unwrap_or_else(|| DEFAULT_HOST.to_string())
+}
+
+fn authenticated(gh_path: &Path, dir: &Path, host: &str) -> Result<bool, GitError> {
+    Ok(gh(gh_path, dir, &["auth", "status", "--hostname", host])?.status.success())
+}
+
+fn status(dir: &Path) -> Result<GhStatus, GitError> {
+    let host = host(dir);
+    let Some(gh_path) = find_gh() else {
+        return Ok(GhStatus {
+            installed: false,
+            version: None,
+            host,
+            authenticated: false,
+        });
+    };
+    // "gh version 2.40.1 (2023-12-13)"
+    let version = gh(&gh_path, dir, &["--version"])?;
+    let version = String::from_utf8_lossy(&version.stdout)
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(2))
+        .map(str::to_string);
+    Ok(GhStatus {
+        installed: true,
+        version,
+        authenticated: authenticated(&gh_path, dir, &host)?,
+        host,
+    })
+}
+
+fn create(dir: &Path, options: &PullRequestOptions) -> Result<PullRequest, GitError> {
+    if options.title.trim().is_empty() {
+        return Err(GitError::EmptyMessage);
+    }
+    let gh_path = find_gh().ok_or(GitError::GhNotInstalled)?;
+    let host = host(dir);
+    if !authenticated(&gh_path, dir, &host)? {
+        return Err(GitError::GhNotAuthenticated { host });
+    }
+    // gh would offer to push it, but can't ask here
+    let branch = git_remote::current_branch(dir)?;
+    if !git_remote::has_upstream(dir)? {
+        return Err(GitError::NoUpstream { branch });
+    }
+
+    let mut args = vec!["pr", "create", "--title", &options.title, "--body", &options.body];
+    if let Some(base) = options.base.as_deref().filter(|base| !base.trim().is_empty()) {
+        args.extend(["--base", base]);
+    }
+    if options.draft {
+        args.push("--draft");
+    }
+    let output = gh(&gh_path, dir, &args)?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // The URL is the last thing gh prints, on stdout when it made the pull
+    // request and on stderr when one already exists
+    let url = |text: &str| {
+        text.lines()
+            .rev()
+            .map(str::trim)
+            .find(|line| line.starts_with("https://"))
+            .map(str::to_string)
+    };
+    if !output.status.success() {
+        if let (true, Some(url)) = (stderr.contains("already exists"), url(&stderr)) {
+            return Err(GitError::PullRequestExists { url });
+        }
+        return Err(GitError::Failed {
+            message: stderr.trim().to_string(),
+        });
+    }
+    let url = url(&stdout(&output)).ok_or_else(|| GitError::Failed {
+        message: "gh didn't say where the pull request is".to_string(),
+    })?;
+    Ok(PullRequest {
+        number: url.rsplit('/').next().and_then(|number| number.parse().ok()),
+        url,
+    })
+}
+
+// Whether the GitHub CLI is installed and signed in to the project's host,
+// so the UI can say what to set up before offering pull requests
+#[tauri::command]
+pub async fn gh_status(project_path: String) -> Result<GhStatus, GitError> {
+    blocking(move || status(&repo(&project_path)?)).await
+}
+
+// Open a pull request for the current branch, which has to be pushed
+// first, and return its URL
+#[tauri::command]
+pub async fn create_pull_request(project_path: String, options: PullRequestOptions) -> Result<PullRequest, GitError> {
+    blocking(move || create(&repo(&project_path)?, &options)).await
+}
@@ -0,0 +1,79 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::{claude_cli, server, settings};
+
+const ISSUES_URL: &str = "https://github.com/ohmatey/incito/issues/new";
+
+fn open_folder(app: &AppHandle, dir: PathBuf) -> Result<(), String> {
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| format!("Failed to open {}: {}", dir.display(), e))
+}
+
+pub fn open_logs_folder(app: &AppHandle) -> Result<(), String> {
+    let dir = app.path().app_log_dir().map_err(|e| e.to_string())?;
+    open_folder(app, dir)
+}
+
+pub fn show_config_folder(app: &AppHandle) -> Result<(), String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    open_folder(app, dir)
+}
+
+// The configured CLI if there is one, otherwise whatever is on PATH
+pub async fn claude_version(app: &AppHandle) -> Option<String> {
+    let path = match settings::get(app).server.claude_code_executable_path {
+        Some(path) if !path.is_empty() => Some(path),
+        _ => claude_cli::which("claude"),
+    }?;
+    tauri::async_runtime::spawn_blocking(move || {
+        claude_cli::get_claude_version(&claude_cli::ClaudeLauncher::resolve(Path::new(&path)))
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+async fn issue_url(app: &AppHandle) -> Result<String, String> {
+    let claude = claude_version(app).await.unwrap_or_else(|| "not found".to_string());
+    let body = format!(
+        "**Describe the issue**\n\n\n\n**Steps to reproduce**\n\n1. \n\n---\n- Incito: {}\n- OS: {} {} ({})\n- Claude Code: {}\n",
+        app.package_info().version,
+        tauri_plugin_os::type_(),
+        tauri_plugin_os::version(),
+        tauri_plugin_os::arch(),
+        claude,
+    );
+    reqwest::Url::parse_with_params(ISSUES_URL, &[("body", body)])
+        .map(|url| url.to_string())
+        .map_err(|e| e.to_string())
+}
+
+pub async fn report_issue(app: &AppHandle) -> Result<(), String> {
+    let url = issue_url(app).await?;
+    app.opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| format!("Failed to open browser: {}", e))
+}
+
+// Menu actions run off the event loop; failures are surfaced to the UI
+pub fn handle_menu_event(app: &AppHandle, id: &str) {
+    let app = app.clone();
+    let id = id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let result = match id.as_str() {
+            "open-logs" => open_logs_folder(&app),
+            "report-issue" => report_issue(&app).await,
+            "show-config" => show_config_folder(&app),
+            "restart-server" => server::restart(&app, "menu").await.map(|_| ()),
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            let _ = app.emit("menu-action-failed", e);
+        }
+    });
+}
@@ -0,0 +1,210 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::transcripts::{self, TranscriptEntry};
+use crate::{backup, db, sessions, usage};
+
+// Files written per transaction, and a cap on how much is parsed into
+// memory before writing, whichever comes first
+const BATCH_FILES: usize = 25;
+const BATCH_MAX_BYTES: i64 = 32 * 1024 * 1024;
+
+#[derive(Clone, serde::Serialize)]
+struct ImportProgress {
+    project: String,
+    projects_done: usize,
+    projects_total: usize,
+    files_done: usize,
+    files_total: usize,
+    sessions_imported: usize,
+    messages_imported: usize,
+}
+
+#[derive(Clone, Default, serde::Serialize)]
+pub struct ImportSummary {
+    pub projects: usize,
+    pub files: usize,
+    pub sessions_imported: usize,
+    pub messages_imported: usize,
+    // Transcripts already fully imported
+    pub unchanged: usize,
+    // Transcripts that couldn't be read, with why
+    pub failed: Vec<String>,
+    pub duration_ms: u64,
+}
+
+// What one transcript still owes the session index and the usage rollups
+struct Work {
+    dir_name: String,
+    path: PathBuf,
+    session: Option<sessions::PendingFile>,
+    usage: Option<usage::PendingFile>,
+}
+
+impl Work {
+    fn size(&self) -> i64 {
+        let session = self.session.as_ref().map(|file| file.file_size - file.from_offset as i64);
+        let usage = self.usage.as_ref().map(|file| file.file_size - file.from_offset as i64);
+        session.max(usage).unwrap_or_default()
+    }
+}
+
+struct Parsed {
+    session: Option<(Vec<TranscriptEntry>, u64)>,
+    usage: Option<(Vec<TranscriptEntry>, u64)>,
+}
+
+type ParseResult = Result<(Vec<TranscriptEntry>, u64), String>;
+
+// The index and the rollups are usually at the same offset, so the file is
+// read once; when they aren't, each gets its own read
+fn parse(work: &Work) -> Result<Parsed, String> {
+    let read = |offset: u64| -> ParseResult { transcripts::parse_from(&work.path, offset, None) };
+    let session_offset = work.session.as_ref().map(|file| file.from_offset);
+    let usage_offset = work.usage.as_ref().map(|file| file.from_offset);
+    match (session_offset, usage_offset) {
+        (Some(a), Some(b)) if a == b => {
+            let parsed = read(a)?;
+            Ok(Parsed {
+                session: Some(parsed.clone()),
+                usage: Some(parsed),
+            })
+        }
+        (session, usage) => Ok(Parsed {
+            session: session.map(read).transpose()?,
+            usage: usage.map(read).transpose()?,
+        }),
+    }
+}
+
+async fn write_batch(
+    app: &AppHandle,
+    batch: Vec<Work>,
+    summary: &mut ImportSummary,
+) -> Result<(), String> {
+    let parsed = tauri::async_runtime::spawn_blocking(move || {
+        batch
+            .into_iter()
+            .map(|work| {
+                let parsed = parse(&work);
+                (work, parsed)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| format!("Import task failed: {}", e))?;
+
+    let pool = usage::usage_pool(app).await?;
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to import history: {}", e))?;
+    for (work, parsed) in parsed {
+        let parsed = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                summary.failed.push(format!("{}: {}", work.path.display(), e));
+                continue;
+            }
+        };
+        if let (Some(file), Some((entries, next_offset))) = (&work.session, &parsed.session) {
+            summary.messages_imported += sessions::save_file(&mut tx, file, entries, *next_offset).await?;
+            summary.sessions_imported += 1;
+        }
+        if let (Some(file), Some((entries, next_offset))) = (&work.usage, &parsed.usage) {
+            usage::save_file(&mut tx, file, &work.dir_name, entries, *next_offset).await?;
+        }
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to import history: {}", e))
+}
+
+async fn import(app: &AppHandle) -> Result<ImportSummary, String> {
+    let started = Instant::now();
+    let sessions_pool = db::pool(app).await?;
+    let usage_pool = usage::usage_pool(app).await?;
+
+    let mut files = transcripts::list_transcript_files()?;
+    files.sort();
+    let mut projects: Vec<String> = files.iter().map(|(dir_name, _)| dir_name.clone()).collect();
+    projects.dedup();
+
+    let mut summary = ImportSummary {
+        projects: projects.len(),
+        files: files.len(),
+        ..Default::default()
+    };
+    let mut progress = ImportProgress {
+        project: String::new(),
+        projects_done: 0,
+        projects_total: projects.len(),
+        files_done: 0,
+        files_total: files.len(),
+        sessions_imported: 0,
+        messages_imported: 0,
+    };
+
+    let mut written = 0;
+    for project in &projects {
+        progress.project = project.clone();
+        let project_files: Vec<PathBuf> = files
+            .iter()
+            .filter(|(dir_name, _)| dir_name == project)
+            .map(|(_, path)| path.clone())
+            .collect();
+
+        let mut batch = Vec::new();
+        let mut batch_bytes = 0;
+        for (index, path) in project_files.iter().enumerate() {
+            let work = Work {
+                dir_name: project.clone(),
+                session: sessions::pending_file(&sessions_pool, project, path).await?,
+                usage: usage::pending_file(&usage_pool, path).await?,
+                path: path.clone(),
+            };
+            if work.session.is_none() && work.usage.is_none() {
+                summary.unchanged += 1;
+            } else {
+                batch_bytes += work.size();
+                batch.push(work);
+            }
+
+            let last = index + 1 == project_files.len();
+            if !batch.is_empty() && (last || batch.len() >= BATCH_FILES || batch_bytes >= BATCH_MAX_BYTES) {
+                written += batch.len();
+                write_batch(app, std::mem::take(&mut batch), &mut summary).await?;
+                batch_bytes = 0;
+
+                progress.files_done = written + summary.unchanged;
+                progress.sessions_imported = summary.sessions_imported;
+                progress.messages_imported = summary.messages_imported;
+                let _ = app.emit("history-import-progress", progress.clone());
+            }
+        }
+        progress.files_done = written + summary.unchanged;
+        progress.projects_done += 1;
+        let _ = app.emit("history-import-progress", progress.clone());
+    }
+
+    let _ = app.emit("usage-updated", ());
+    summary.duration_ms = started.elapsed().as_millis() as u64;
+    Ok(summary)
+}
+
+// Import every transcript under ~/.claude/projects into the session index,
+// the message search index and the usage rollups in one pass, so a first
+// run shows past work straight away. Safe to run again: transcripts already
+// imported are skipped and grown ones are read from where they left off.
+// Emits `history-import-progress` after each batch and each project.
+#[tauri::command]
+pub async fn import_claude_history(app: AppHandle) -> Result<ImportSummary, String> {
+    let job = app.state::<backup::DatabaseJob>();
+    let _guard = job
+        .0
+        .try_lock()
+        .map_err(|_| "Another database job is running".to_string())?;
+    import(&app).await
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{dock, menu, menu_state, settings, tray};
+
+const DEFAULT_LANGUAGE: &str = "en";
+
+// Same language codes as the frontend's i18n setup
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.json")),
+    ("th", include_str!("../locales/th.json")),
+];
+
+static BUNDLES: LazyLock<HashMap<&'static str, Value>> = LazyLock::new(|| {
+    LOCALES
+        .iter()
+        .filter_map(|(code, json)| serde_json::from_str(json).ok().map(|bundle| (*code, bundle)))
+        .collect()
+});
+
+// The language native menus, the tray and dialogs are built in
+pub struct Language(Mutex<String>);
+
+impl Default for Language {
+    fn default() -> Self {
+        Self(Mutex::new(DEFAULT_LANGUAGE.to_string()))
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct LanguageInfo {
+    pub language: String,
+    // Set when the user picked a language instead of following the OS
+    pub override_language: Option<String>,
+    pub available: Vec<String>,
+}
+
+fn lookup<'a>(language: &str, key: &str) -> Option<&'a str> {
+    let mut value = BUNDLES.get(language)?;
+    for part in key.split('.') {
+        value = value.get(part)?;
+    }
+    value.as_str()
+}
+
+fn current_language(app: &AppHandle) -> String {
+    app.try_state::<Language>()
+        .and_then(|language| language.0.lock().ok().map(|language| language.clone()))
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string())
+}
+
+// Translate a dotted key like "menu.about", falling back to English and then
+// to the key itself. `{{name}}` placeholders are filled from `args`.
+pub fn t_with(app: &AppHandle, key: &str, args: &[(&str, &str)]) -> String {
+    let language = current_language(app);
+    let mut text = lookup(&language, key)
+        .or_else(|| lookup(DEFAULT_LANGUAGE, key))
+        .unwrap_or(key)
+        .to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{{{}}}}}", name), value);
+    }
+    text
+}
+
+pub fn t(app: &AppHandle, key: &str) -> String {
+    t_with(app, key, &[])
+}
+
+// "th-TH" and "th_TH" both match "th"
+fn supported(language: &str) -> Option<&'static str> {
+    let base = language.split(['-', '_']).next()?.to_lowercase();
+    LOCALES.iter().map(|(code, _)| *code).find(|code| *code == base)
+}
+
+pub fn is_supported(language: &str) -> bool {
+    supported(language).is_some()
+}
+
+fn resolve(override_language: Option<&str>) -> String {
+    override_language
+        .and_then(supported)
+        .or_else(|| tauri_plugin_os::locale().as_deref().and_then(supported))
+        .unwrap_or(DEFAULT_LANGUAGE)
+        .to_string()
+}
+
+fn saved_override(app: &AppHandle) -> Option<String> {
+    settings::get(app).appearance.language.filter(|language| !language.is_empty())
+}
+
+// Recreate the app menu and tray menu in the current language
+pub async fn rebuild_menus(app: &AppHandle) -> Result<(), String> {
+    let app_menu = menu::create(app).map_err(|e| format!("Failed to build menu: {}", e))?;
+    app.set_menu(app_menu).map_err(|e| e.to_string())?;
+    menu::rebuild_recent_projects(app).await?;
+    menu_state::sync(app);
+    dock::refresh(app).await?;
+    tray::rebuild_menu(app).await.map_err(|e| format!("Failed to build tray menu: {}", e))
+}
+
+// Pick the language from the saved override or the OS locale. The menus are
+// first built before the database is reachable, so they're rebuilt if that
+// guess was wrong.
+pub async fn load_saved(app: &AppHandle) -> Result<(), String> {
+    let language = resolve(saved_override(app).as_deref());
+    if language == current_language(app) {
+        return Ok(());
+    }
+    *app.state::<Language>().0.lock().map_err(|e| e.to_string())? = language;
+    rebuild_menus(app).await
+}
+
+#[tauri::command]
+pub async fn get_app_language(app: AppHandle) -> Result<LanguageInfo, String> {
+    Ok(LanguageInfo {
+        language: current_language(&app),
+        override_language: saved_override(&app),
+        available: LOCALES.iter().map(|(code, _)| code.to_string()).collect(),
+    })
+}
+
+// Switch native UI to `language`, or back to the OS language with `None`
+#[tauri::command]
+pub async fn set_app_language(app: AppHandle, language: Option<String>) -> Result<String, String> {
+    let override_language = language.map(|language| language.trim().to_string()).filter(|language| !language.is_empty());
+    if let Some(ref language) = override_language {
+        if supported(language).is_none() {
+            return Err(format!("Unsupported language: {}", language));
+        }
+    }
+    settings::update(&app, |settings| settings.appearance.language = override_language.clone())?;
+
+    let language = resolve(override_language.as_deref());
+    *app.state::<Language>().0.lock().map_err(|e| e.to_string())? = language.clone();
+    rebuild_menus(&app).await?;
+    let _ = app.emit("app-language-changed", language.clone());
+    Ok(language)
+}
@@ -1,340 +1,157 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::sync::Mutex;
-use tauri::{AppHandle, Emitter, Manager};
-use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
-use tauri_plugin_shell::ShellExt;
-use tauri_plugin_shell::process::CommandChild;
-use tauri_plugin_updater::UpdaterExt;
-
-// State to track the Claude Code server process
-struct ClaudeCodeState {
-    process: Mutex<Option<CommandChild>>,
-}
-
-#[tauri::command]
-async fn start_claude_code_server(
-    app: AppHandle,
-    state: tauri::State<'_, ClaudeCodeState>,
-    executable_path: Option<String>,
-) -> Result<u32, String> {
-    let mut process_guard = state.process.lock().map_err(|e| e.to_string())?;
-
-    if process_guard.is_some() {
-        return Err("Claude Code server is already running".to_string());
-    }
-
-    let mut command = app
-        .shell()
-        .sidecar("claude-code-server")
-        .map_err(|e| format!("Failed to create sidecar command: {}", e))?;
-
-    // Pass custom executable path as command line argument
-    if let Some(ref path) = executable_path {
-        if !path.is_empty() {
-            command = command
-                .env("CLAUDE_CODE_EXECUTABLE_PATH", path)
-                .args(["--claude-path", path]);
-        }
-    }
-
-    let (_, child) = command
-        .spawn()
-        .map_err(|e| format!("Failed to spawn sidecar: {}", e))?;
-
-    let pid = child.pid();
-    *process_guard = Some(child);
-
-    Ok(pid)
-}
-
-#[tauri::command]
-async fn stop_claude_code_server(
-    state: tauri::State<'_, ClaudeCodeState>,
-) -> Result<(), String> {
-    let mut process_guard = state.process.lock().map_err(|e| e.to_string())?;
-
-    if let Some(child) = process_guard.take() {
-        child.kill().map_err(|e| format!("Failed to kill process: {}", e))?;
-    }
-
-    Ok(())
-}
-
-#[tauri::command]
-async fn get_claude_code_server_status(
-    state: tauri::State<'_, ClaudeCodeState>,
-) -> Result<bool, String> {
-    let process_guard = state.process.lock().map_err(|e| e.to_string())?;
-    Ok(process_guard.is_some())
-}
-
-#[derive(Clone, serde::Serialize)]
-struct UpdateInfo {
-    version: String,
-    body: Option<String>,
-    date: Option<String>,
-}
-
-#[derive(Clone, serde::Serialize)]
-struct ClaudeCodePathResult {
-    found: bool,
-    path: Option<String>,
-    version: Option<String>,
-    error: Option<String>,
-}
-
-#[tauri::command]
-fn find_claude_code_path() -> Result<ClaudeCodePathResult, String> {
-    // Try to find claude using 'which' on Unix or 'where' on Windows
-    #[cfg(target_os = "windows")]
-    let output = std::process::Command::new("where")
-        .arg("claude")
-        .output();
-
-    #[cfg(not(target_os = "windows"))]
-    let output = std::process::Command::new("which")
-        .arg("claude")
-        .output();
-
-    match output {
-        Ok(result) if result.status.success() => {
-            let path = String::from_utf8_lossy(&result.stdout)
-                .lines()
-                .next()
-                .unwrap_or("")
-                .trim()
-                .to_string();
-
-            if path.is_empty() {
-                Ok(ClaudeCodePathResult {
-                    found: false,
-                    path: None,
-                    version: None,
-                    error: Some("Claude Code not found in system PATH".to_string()),
-                })
-            } else {
-                // Try to get version
-                let version = get_claude_version(&path);
-                Ok(ClaudeCodePathResult {
-                    found: true,
-                    path: Some(path),
-                    version,
-                    error: None,
-                })
-            }
-        }
-        Ok(_) => Ok(ClaudeCodePathResult {
-            found: false,
-            path: None,
-            version: None,
-            error: Some("Claude Code not found in system PATH".to_string()),
-        }),
-        Err(e) => Ok(ClaudeCodePathResult {
-            found: false,
-            path: None,
-            version: None,
-            error: Some(format!("Failed to search for Claude Code: {}", e)),
-        }),
-    }
-}
-
-#[tauri::command]
-fn check_claude_code_path(path: String) -> Result<ClaudeCodePathResult, String> {
-    // Check if file exists
-    let path_obj = std::path::Path::new(&path);
-    if !path_obj.exists() {
-        return Ok(ClaudeCodePathResult {
-            found: false,
-            path: Some(path),
-            version: None,
-            error: Some("File does not exist".to_string()),
-        });
-    }
-
-    // Try to get version to verify it's actually Claude Code
-    match get_claude_version(&path) {
-        Some(version) => Ok(ClaudeCodePathResult {
-            found: true,
-            path: Some(path),
-            version: Some(version),
-            error: None,
-        }),
-        None => Ok(ClaudeCodePathResult {
-            found: false,
-            path: Some(path),
-            version: None,
-            error: Some("File exists but does not appear to be Claude Code (could not get version)".to_string()),
-        }),
-    }
-}
-
-fn get_claude_version(path: &str) -> Option<String> {
-    use std::process::Stdio;
-    use std::time::Duration;
-
-    // Spawn the process
-    let mut child = std::process::Command::new(path)
-        .arg("--version")
-        .stdin(Stdio::null())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::null())
-        .spawn()
-        .ok()?;
-
-    // Wait with a timeout of 5 seconds
-    let start = std::time::Instant::now();
-    let timeout = Duration::from_secs(5);
-
-    loop {
-        match child.try_wait() {
-            Ok(Some(status)) => {
-                if status.success() {
-                    let output = child.wait_with_output().ok()?;
-                    let version_str = String::from_utf8_lossy(&output.stdout);
-                    return Some(version_str.trim().to_string());
-                } else {
-                    return None;
-                }
-            }
-            Ok(None) => {
-                if start.elapsed() > timeout {
-                    // Kill the process if it's taking too long
-                    let _ = child.kill();
-                    return None;
-                }
-                std::thread::sleep(Duration::from_millis(100));
-            }
-            Err(_) => return None,
-        }
-    }
-}
-
-#[tauri::command]
-async fn check_for_updates(app: AppHandle) -> Result<Option<UpdateInfo>, String> {
-    let updater = app.updater().map_err(|e| e.to_string())?;
-
-    match updater.check().await {
-        Ok(Some(update)) => {
-            Ok(Some(UpdateInfo {
-                version: update.version.clone(),
-                body: update.body.clone(),
-                date: update.date.map(|d| d.to_string()),
-            }))
-        }
-        Ok(None) => Ok(None),
-        Err(e) => Err(e.to_string()),
-    }
-}
-
-#[tauri::command]
-async fn install_update(app: AppHandle) -> Result<(), String> {
-    let updater = app.updater().map_err(|e| e.to_string())?;
-
-    match updater.check().await {
-        Ok(Some(update)) => {
-            // Download and install the update
-            update.download_and_install(|_, _| {}, || {}).await
-                .map_err(|e| e.to_string())?;
-            Ok(())
-        }
-        Ok(None) => Err("No update available".to_string()),
-        Err(e) => Err(e.to_string()),
-    }
-}
-
-fn create_menu(app: &AppHandle) -> Result<Menu<tauri::Wry>, tauri::Error> {
-    let app_name = "Incito";
-
-    // App menu (macOS only, but harmless on other platforms)
-    let about = MenuItem::with_id(app, "about", format!("About {}", app_name), true, None::<&str>)?;
-    let check_updates = MenuItem::with_id(app, "check-updates", "Check for Updates...", true, None::<&str>)?;
-    let separator1 = PredefinedMenuItem::separator(app)?;
-    let separator2 = PredefinedMenuItem::separator(app)?;
-    let separator3 = PredefinedMenuItem::separator(app)?;
-    let separator4 = PredefinedMenuItem::separator(app)?;
-    let services = Submenu::with_id_and_items(app, "services", "Services", true, &[])?;
-    let hide = PredefinedMenuItem::hide(app, Some(app_name))?;
-    let hide_others = PredefinedMenuItem::hide_others(app, Some("Hide Others"))?;
-    let show_all = PredefinedMenuItem::show_all(app, Some("Show All"))?;
-    let quit = PredefinedMenuItem::quit(app, Some(&format!("Quit {}", app_name)))?;
-
-    let app_menu = Submenu::with_id_and_items(
-        app,
-        "app",
-        app_name,
-        true,
-        &[
-            &about,
-            &separator1,
-            &check_updates,
-            &separator2,
-            &services,
-            &separator3,
-            &hide,
-            &hide_others,
-            &show_all,
-            &separator4,
-            &quit,
-        ],
-    )?;
-
-    // Edit menu
-    let undo = PredefinedMenuItem::undo(app, Some("Undo"))?;
-    let redo = PredefinedMenuItem::redo(app, Some("Redo"))?;
-    let edit_separator = PredefinedMenuItem::separator(app)?;
-    let cut = PredefinedMenuItem::cut(app, Some("Cut"))?;
-    let copy = PredefinedMenuItem::copy(app, Some("Copy"))?;
-    let paste = PredefinedMenuItem::paste(app, Some("Paste"))?;
-    let select_all = PredefinedMenuItem::select_all(app, Some("Select All"))?;
-
-    let edit_menu = Submenu::with_id_and_items(
-        app,
-        "edit",
-        "Edit",
-        true,
-        &[
-            &undo,
-            &redo,
-            &edit_separator,
-            &cut,
-            &copy,
-            &paste,
-            &select_all,
-        ],
-    )?;
-
-    // Window menu
-    let minimize = PredefinedMenuItem::minimize(app, Some("Minimize"))?;
-    let maximize = MenuItem::with_id(app, "maximize", "Maximize", true, None::<&str>)?;
-    let window_separator = PredefinedMenuItem::separator(app)?;
-    let close = PredefinedMenuItem::close_window(app, Some("Close Window"))?;
-
-    let window_menu = Submenu::with_id_and_items(
-        app,
-        "window",
-        "Window",
-        true,
-        &[
-            &minimize,
-            &maximize,
-            &window_separator,
-            &close,
-        ],
-    )?;
-
-    // Build the complete menu
-    Menu::with_items(app, &[&app_menu, &edit_menu, &window_menu])
-}
+mod about;
+mod accelerators;
+mod agents;
+mod app_lock;
+mod approvals;
+mod audit;
+mod automation;
+mod backup;
+mod checkpoints;
+mod claude_auth;
+mod claude_cli;
+mod claude_hooks;
+mod claude_memory;
+mod claude_settings;
+mod claude_watcher;
+mod command_runner;
+mod connection;
+mod connectivity;
+mod context_menu;
+mod data_transfer;
+mod db;
+mod deep_link;
+mod diff;
+mod discovery;
+mod dir_size;
+mod dock;
+mod downloads;
+mod drag_drop;
+mod encryption;
+mod file_reader;
+mod frontmatter;
+mod git;
+mod git_blame;
+mod git_branches;
+mod git_commit;
+mod git_conflicts;
+mod git_diff;
+mod git_log;
+mod git_remote;
+mod git_stash;
+mod git_status;
+mod git_worktree;
+mod github;
+mod fs_util;
+mod help;
+mod history_import;
+mod i18n;
+mod maintenance;
+mod mcp;
+mod menu;
+mod menu_state;
+mod migrations;
+mod mini_mode;
+mod net;
+mod notifications;
+mod paths;
+mod policy;
+mod process_tree;
+mod profiles;
+mod project_files;
+mod project_info;
+mod project_search;
+mod project_tree;
+mod project_watcher;
+mod projects;
+mod quick_open;
+mod recovery;
+mod redact;
+mod retention;
+mod rollback;
+mod sandbox;
+mod scrollback;
+mod secrets;
+mod scopes;
+mod server;
+mod session_export;
+mod settings;
+mod sessions;
+mod shell_env;
+mod shortcuts;
+mod sidecar;
+mod sidecar_bridge;
+mod slash_commands;
+mod taskbar;
+mod tasks;
+mod terminal;
+mod theme;
+mod title_bar;
+mod transcripts;
+mod trash;
+mod tray;
+mod updater;
+mod usage;
+mod verify;
+mod view;
+mod windows;
 
 fn main() {
     tauri::Builder::default()
-        .manage(ClaudeCodeState {
-            process: Mutex::new(None),
-        })
+        .manage(server::ClaudeCodeState::default())
+        .manage(transcripts::TranscriptWatchers::default())
+        .manage(windows::WindowBehavior::default())
+        .manage(windows::WindowRegistry::default())
+        .manage(view::ZoomState::default())
+        .manage(tasks::ActiveTasks::default())
+        .manage(context_menu::ContextMenuState::default())
+        .manage(shortcuts::GlobalShortcutState::default())
+        .manage(mini_mode::MiniModeState::default())
+        .manage(accelerators::AcceleratorState::default())
+        .manage(i18n::Language::default())
+        .manage(menu::RecentMenu::default())
+        .manage(menu_state::MenuHandles::default())
+        .manage(mini_mode::WindowMenuHandles::default())
+        .manage(tray::TrayState::default())
+        .manage(title_bar::TitleBarState::default())
+        .manage(theme::ThemeState::default())
+        .manage(dock::PendingLaunch::default())
+        .manage(updater::UpdateDownload::default())
+        .manage(updater::StagedUpdate::default())
+        .manage(updater::StagedSnapshot::default())
+        .manage(net::NetworkState::default())
+        .manage(downloads::Downloads::default())
+        .manage(connectivity::ConnectivityState::default())
+        .manage(migrations::MigrationStatus::default())
+        .manage(backup::DatabaseJob::default())
+        .manage(checkpoints::CheckpointJob::default())
+        .manage(recovery::RecoveryStatus::default())
+        .manage(project_watcher::ProjectWatchers::default())
+        .manage(project_search::ProjectSearches::default())
+        .manage(settings::SettingsStore::default())
+        .manage(terminal::Terminals::default())
+        .manage(command_runner::CommandRuns::default())
+        .manage(approvals::PendingApprovals::default())
+        .manage(app_lock::AppLock::default())
+        .manage(sidecar_bridge::SidecarBridge::default())
+        .manage(sidecar_bridge::SidecarStreams::default())
+        .manage(automation::AutomationServer::default())
+        .manage(automation::AutomationTasks::default())
+        .manage(deep_link::PendingNavigation::default())
+        .manage(connection::RemoteConnection::default())
+        // Must come first so a second launch exits before anything else starts
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            if !dock::handle_args(app, &argv, false) {
+                let _ = windows::show_main_window(app);
+            }
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         // IMPORTANT: fs must be registered BEFORE persisted-scope
         .plugin(tauri_plugin_fs::init())
+        // Scrubs revoked paths from persisted-scope's file before it loads
+        .plugin(scopes::plugin())
         .plugin(tauri_plugin_persisted_scope::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_clipboard_manager::init())
@@ -343,42 +160,321 @@ fn main() {
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_updater::Builder::default().build())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(shortcuts::plugin())
+        .plugin(windows::state_plugin())
         .setup(|app| {
+            // Windows from the config are created after setup, so the schema
+            // is current before the frontend touches it. Opening unlocks an
+            // encrypted database with the key from the system keychain.
+            let handle = app.handle();
+            tauri::async_runtime::block_on(async {
+                // A damaged file is salvaged into a new one before anything opens it
+                let recovered = recovery::check_and_recover(handle).await;
+                match db::open(handle).await {
+                    Ok(()) => {
+                        let _ = migrations::run(handle).await;
+                    }
+                    Err(error) => migrations::report(handle, &migrations::MigrationFailure { version: None, error }),
+                }
+                // Settings saved by older versions are still in the database
+                if let Err(e) = settings::load(handle).await {
+                    eprintln!("{}", e);
+                }
+                // Before any window can show what's behind the lock
+                app_lock::start(handle);
+                if let Some(report) = recovered {
+                    recovery::announce(handle, report);
+                }
+                rollback::report_failed(handle);
+                if let Err(e) = projects::restore_scope(handle).await {
+                    eprintln!("{}", e);
+                }
+            });
+
             // Create and set the menu
-            let menu = create_menu(app.handle())?;
+            let menu = menu::create(app.handle())?;
             app.set_menu(menu)?;
-
-            Ok(())
-        })
-        .on_menu_event(|app, event| {
-            match event.id().as_ref() {
-                "check-updates" => {
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.emit("menu-check-updates", ());
-                    }
+            menu::watch_recent_projects(app.handle());
+
+            tray::create(app.handle())?;
+            dock::watch(app.handle());
+            let args: Vec<String> = std::env::args().collect();
+            dock::handle_args(app.handle(), &args, true);
+            deep_link::start(app.handle());
+
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = windows::load_behavior(&handle).await;
+                if let Err(e) = net::load_saved(&handle).await {
+                    eprintln!("{}", e);
                 }
-                "about" => {
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.emit("menu-about", ());
-                    }
+                connectivity::start(&handle);
+                updater::start_background_checks(&handle);
+                retention::start_background_pruning(&handle);
+                maintenance::start_idle_maintenance(&handle);
+                sidecar_bridge::start(&handle);
+                if let Err(e) = automation::load_saved(&handle).await {
+                    eprintln!("{}", e);
                 }
-                "maximize" => {
-                    if let Some(window) = app.get_webview_window("main") {
-                        let _ = window.maximize();
-                    }
+                let _ = view::load_zoom(&handle).await;
+                let _ = theme::load_saved(&handle).await;
+                if let Err(e) = accelerators::load_saved(&handle).await {
+                    eprintln!("{}", e);
                 }
-                _ => {}
+                if let Err(e) = i18n::load_saved(&handle).await {
+                    eprintln!("{}", e);
+                }
+                if let Err(e) = shortcuts::register_saved(&handle).await {
+                    eprintln!("{}", e);
+                }
+            });
+
+            // The UI still works without live updates, so a watcher failure isn't fatal
+            if let Err(e) = claude_watcher::start(app.handle()) {
+                eprintln!("{}", e);
             }
+
+            Ok(())
         })
+        .on_window_event(windows::on_window_event)
+        .on_menu_event(menu::on_menu_event)
         .invoke_handler(tauri::generate_handler![
-            start_claude_code_server,
-            stop_claude_code_server,
-            get_claude_code_server_status,
-            check_for_updates,
-            install_update,
-            find_claude_code_path,
-            check_claude_code_path,
+            server::start_claude_code_server,
+            server::stop_claude_code_server,
+            server::get_claude_code_server_status,
+            server::get_server_logs,
+            connection::get_server_connection,
+            discovery::discover_local_servers,
+            connection::set_server_connection,
+            connection::set_remote_server_token,
+            updater::check_for_updates,
+            updater::install_update,
+            updater::cancel_update_download,
+            updater::download_update_in_background,
+            updater::get_staged_update,
+            updater::get_update_channel,
+            updater::set_update_channel,
+            updater::get_update_check_settings,
+            updater::set_update_check_settings,
+            updater::skip_update_version,
+            updater::list_skipped_versions,
+            updater::clear_skipped_versions,
+            net::get_network_config,
+            net::set_network_config,
+            net::test_network_config,
+            connectivity::get_connectivity,
+            sidecar::check_sidecar_update,
+            sidecar::install_sidecar_update,
+            downloads::list_downloads,
+            downloads::pause_download,
+            downloads::resume_download,
+            downloads::cancel_download,
+            sidecar_bridge::sidecar_request,
+            sidecar_bridge::sidecar_stream,
+            sidecar_bridge::ack_sidecar_stream,
+            sidecar_bridge::cancel_sidecar_stream,
+            sidecar_bridge::get_sidecar_bridge_status,
+            rollback::get_rollback_version,
+            rollback::rollback_update,
+            migrations::get_db_migration_failure,
+            recovery::get_db_recovery,
+            backup::backup_database,
+            backup::restore_database,
+            data_transfer::export_app_data,
+            data_transfer::import_app_data,
+            encryption::get_database_encryption,
+            encryption::encrypt_existing_database,
+            claude_cli::find_claude_code_path,
+            claude_cli::check_claude_code_path,
+            claude_cli::install_claude_code,
+            claude_auth::get_claude_auth_status,
+            profiles::list_claude_profiles,
+            profiles::add_claude_profile,
+            profiles::remove_claude_profile,
+            profiles::activate_claude_profile,
+            claude_settings::read_claude_settings,
+            claude_settings::write_claude_settings,
+            claude_hooks::get_claude_hooks,
+            claude_hooks::set_claude_hooks,
+            claude_hooks::dry_run_hook,
+            transcripts::read_session_transcript,
+            transcripts::watch_session_transcript,
+            transcripts::unwatch_session_transcript,
+            sessions::list_claude_sessions,
+            sessions::search_sessions,
+            sessions::search_messages,
+            history_import::import_claude_history,
+            session_export::export_session,
+            retention::get_retention_rules,
+            retention::set_retention_rules,
+            retention::preview_prune,
+            retention::run_prune,
+            maintenance::run_db_maintenance,
+            settings::get_settings,
+            settings::update_settings,
+            usage::get_usage_summary,
+            mcp::list_mcp_servers,
+            mcp::add_mcp_server,
+            mcp::remove_mcp_server,
+            mcp::test_mcp_server,
+            windows::get_close_to_tray,
+            windows::set_close_to_tray,
+            windows::open_project_window,
+            project_watcher::watch_project,
+            project_watcher::unwatch_project,
+            project_tree::list_project_tree,
+            project_search::search_in_project,
+            project_search::cancel_project_search,
+            quick_open::fuzzy_find_files,
+            project_info::detect_project_info,
+            dir_size::calculate_dir_size,
+            file_reader::stat_file,
+            file_reader::read_file_chunked,
+            git_status::git_status,
+            git_status::check_worktree_clean,
+            git_branches::git_list_branches,
+            git_branches::git_create_branch,
+            git_branches::git_switch_branch,
+            git_commit::git_stage,
+            git_commit::git_unstage,
+            git_commit::git_commit,
+            git_diff::git_diff,
+            git_log::git_log,
+            git_log::git_file_history,
+            git_stash::git_stash_push,
+            git_stash::git_stash_list,
+            git_stash::git_stash_pop,
+            git_stash::git_stash_apply,
+            git_stash::git_stash_drop,
+            git_blame::git_blame,
+            git_remote::git_fetch,
+            git_remote::git_pull,
+            git_remote::git_push,
+            git_remote::set_git_token,
+            git_remote::clear_git_token,
+            git_remote::has_git_token,
+            github::gh_status,
+            github::create_pull_request,
+            git_conflicts::git_conflicts,
+            git_conflicts::resolve_conflict,
+            terminal::create_terminal,
+            terminal::write_terminal,
+            terminal::resize_terminal,
+            terminal::kill_terminal,
+            terminal::get_terminal_buffer,
+            terminal::attach_terminal,
+            terminal::list_terminals,
+            command_runner::run_command,
+            command_runner::cancel_command,
+            command_runner::list_command_runs,
+            command_runner::list_runnable_scripts,
+            command_runner::run_script,
+            command_runner::list_running_commands,
+            secrets::set_secret,
+            secrets::get_secret,
+            secrets::delete_secret,
+            secrets::list_secret_names,
+            approvals::respond_approval,
+            approvals::list_pending_approvals,
+            approvals::list_approval_rules,
+            approvals::remove_approval_rule,
+            approvals::list_approval_decisions,
+            app_lock::get_lock_state,
+            app_lock::report_app_activity,
+            app_lock::lock_app,
+            app_lock::unlock_app,
+            sandbox::get_project_sandbox,
+            sandbox::set_project_sandbox,
+            sandbox::get_sandbox_support,
+            audit::query_audit_log,
+            audit::export_audit_log,
+            automation::get_automation_status,
+            automation::set_automation_config,
+            automation::get_automation_token,
+            automation::regenerate_automation_token,
+            automation::list_queued_automation_tasks,
+            automation::update_automation_task,
+            policy::get_project_allowlist,
+            policy::set_project_allowlist,
+            git_worktree::git_worktree_list,
+            git_worktree::git_worktree_add,
+            git_worktree::git_worktree_remove,
+            diff::compute_diff,
+            diff::diff_paths,
+            fs_util::write_file_safe,
+            trash::trash_paths,
+            checkpoints::create_checkpoint,
+            checkpoints::list_checkpoints,
+            checkpoints::diff_checkpoint,
+            checkpoints::revert_to_checkpoint,
+            projects::register_project,
+            projects::record_project_opened,
+            projects::list_projects,
+            projects::set_project_pinned,
+            projects::remove_project,
+            scopes::list_allowed_scopes,
+            scopes::revoke_scope,
+            projects::list_recent_projects,
+            projects::clear_recent_projects,
+            tasks::set_task_running,
+            tasks::start_task,
+            context_menu::show_context_menu,
+            taskbar::set_task_progress,
+            notifications::send_task_notification,
+            about::get_about_info,
+            mini_mode::set_always_on_top,
+            mini_mode::enter_mini_mode,
+            mini_mode::exit_mini_mode,
+            mini_mode::is_mini_mode,
+            accelerators::get_menu_accelerators,
+            accelerators::set_menu_accelerator,
+            i18n::get_app_language,
+            i18n::set_app_language,
+            title_bar::get_title_bar_info,
+            title_bar::start_dragging,
+            title_bar::toggle_maximize,
+            title_bar::title_bar_double_click,
+            theme::get_system_theme,
+            theme::set_window_theme,
+            dock::take_launch_action,
+            deep_link::take_deep_links,
+            windows::get_window_context,
+            shortcuts::get_global_shortcut,
+            shortcuts::set_global_shortcut,
+            claude_memory::list_memory_files,
+            claude_memory::read_memory_file,
+            claude_memory::write_memory_file,
+            slash_commands::list_slash_commands,
+            slash_commands::validate_slash_command,
+            slash_commands::save_slash_command,
+            slash_commands::rename_slash_command,
+            slash_commands::delete_slash_command,
+            agents::list_agents,
+            agents::read_agent,
+            agents::lint_agent,
+            agents::save_agent,
+            agents::delete_agent,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Quitting mid-task asks first
+            tauri::RunEvent::ExitRequested { code, api, .. } if tasks::intercept_exit(app, code) => {
+                api.prevent_exit();
+            }
+            tauri::RunEvent::ExitRequested { .. } => updater::install_staged(app),
+            // Full shutdown: don't leave the sidecar running after we exit
+            tauri::RunEvent::Exit => {
+                let _ = server::stop(app, true, "exit");
+                terminal::kill_all(app);
+                command_runner::kill_all(app);
+            }
+            // Clicking the dock icon brings a hidden main window back
+            #[cfg(target_os = "macos")]
+            tauri::RunEvent::Reopen { has_visible_windows: false, .. } => {
+                let _ = windows::show_main_window(app);
+            }
+            _ => {}
+        });
 }
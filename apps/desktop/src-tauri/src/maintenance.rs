@@ -0,0 +1,153 @@
+use std::time::{Duration, Instant};
+
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{backup, db, tasks};
+
+const LAST_RUN_SETTING: &str = "db_maintenance_last_run";
+// Share of free pages above which a VACUUM is worth the rewrite
+const VACUUM_THRESHOLD: f64 = 0.2;
+const IDLE_TICK: Duration = Duration::from_secs(10 * 60);
+const RUN_EVERY_HOURS: i64 = 24;
+
+#[derive(Clone, serde::Serialize)]
+pub struct MaintenanceReport {
+    // The database file plus its WAL, in bytes
+    pub size_before: u64,
+    pub size_after: u64,
+    // What integrity_check found; empty when the database is intact
+    pub issues: Vec<String>,
+    pub checkpointed_pages: i64,
+    // Free pages as a share of all pages, before any VACUUM
+    pub fragmentation: f64,
+    pub vacuumed: bool,
+    pub duration_ms: u64,
+}
+
+fn file_size(app: &AppHandle) -> u64 {
+    let Ok(path) = db::path(app) else {
+        return 0;
+    };
+    let mut wal = path.as_os_str().to_os_string();
+    wal.push("-wal");
+    [path.into_os_string(), wal]
+        .iter()
+        .filter_map(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+async fn integrity_issues(pool: &SqlitePool) -> Result<Vec<String>, String> {
+    let rows: Vec<(String,)> = sqlx::query_as("PRAGMA integrity_check")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to check database integrity: {}", e))?;
+    Ok(rows.into_iter().map(|(row,)| row).filter(|row| row != "ok").collect())
+}
+
+async fn checkpoint(pool: &SqlitePool) -> Result<i64, String> {
+    let (_busy, _log, checkpointed): (i64, i64, i64) = sqlx::query_as("PRAGMA wal_checkpoint(TRUNCATE)")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to checkpoint the WAL: {}", e))?;
+    Ok(checkpointed.max(0))
+}
+
+async fn fragmentation(pool: &SqlitePool) -> Result<f64, String> {
+    let (pages, free): (i64, i64) = sqlx::query_as("SELECT page_count, freelist_count FROM pragma_page_count(), pragma_freelist_count()")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("Failed to read database size: {}", e))?;
+    Ok(if pages > 0 { free as f64 / pages as f64 } else { 0.0 })
+}
+
+async fn run(app: &AppHandle) -> Result<MaintenanceReport, String> {
+    let job = app.state::<backup::DatabaseJob>();
+    let _guard = job
+        .0
+        .try_lock()
+        .map_err(|_| "A database backup or restore is running".to_string())?;
+
+    let started = Instant::now();
+    let pool = db::pool(app).await?;
+    let size_before = file_size(app);
+
+    let issues = integrity_issues(&pool).await?;
+    let checkpointed_pages = checkpoint(&pool).await?;
+    let fragmentation = fragmentation(&pool).await?;
+
+    // Rewriting a damaged database could make things worse, so it's left
+    // for a restore
+    let vacuumed = issues.is_empty() && fragmentation > VACUUM_THRESHOLD;
+    if vacuumed {
+        sqlx::query("VACUUM")
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("Failed to vacuum the database: {}", e))?;
+        checkpoint(&pool).await?;
+    }
+    sqlx::query("PRAGMA optimize")
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to optimize the database: {}", e))?;
+
+    let _ = db::set_setting(app, LAST_RUN_SETTING, &chrono::Utc::now().to_rfc3339()).await;
+    Ok(MaintenanceReport {
+        size_before,
+        size_after: file_size(app),
+        issues,
+        checkpointed_pages,
+        fragmentation,
+        vacuumed,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+async fn due(app: &AppHandle) -> bool {
+    let last_run = db::get_setting(app, LAST_RUN_SETTING)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|value| chrono::DateTime::parse_from_rfc3339(&value).ok());
+    match last_run {
+        Some(last_run) => chrono::Utc::now() - last_run.with_timezone(&chrono::Utc) >= chrono::Duration::hours(RUN_EVERY_HOURS),
+        None => true,
+    }
+}
+
+// Nobody is looking at the app and nothing is running in it
+fn is_idle(app: &AppHandle) -> bool {
+    !tasks::any_running(app)
+        && !app
+            .webview_windows()
+            .values()
+            .any(|window| window.is_focused().unwrap_or(false))
+}
+
+// Runs about once a day, once the app has been idle for a whole tick.
+// Emits `db-maintenance-finished` with the report.
+pub fn start_idle_maintenance(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut was_idle = false;
+        loop {
+            tokio::time::sleep(IDLE_TICK).await;
+            let idle = is_idle(&app);
+            if idle && was_idle && due(&app).await {
+                match run(&app).await {
+                    Ok(report) => {
+                        let _ = app.emit("db-maintenance-finished", report);
+                    }
+                    Err(e) => eprintln!("Database maintenance failed: {}", e),
+                }
+            }
+            was_idle = idle;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn run_db_maintenance(app: AppHandle) -> Result<MaintenanceReport, String> {
+    run(&app).await
+}
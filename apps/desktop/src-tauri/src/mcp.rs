@@ -0,0 +1,458 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::{fs_util, net, paths};
+
+// One entry of an `mcpServers` map. stdio servers use command/args/env,
+// remote ones ("sse" / "http") use url/headers.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub server_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl McpServerConfig {
+    fn transport(&self) -> &str {
+        match self.server_type.as_deref() {
+            Some(transport) => transport,
+            None if self.url.is_some() && self.command.is_none() => "http",
+            None => "stdio",
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct McpServerEntry {
+    pub name: String,
+    // "project" (.mcp.json), "local" (this project in ~/.claude.json) or "user"
+    pub scope: String,
+    pub source: String,
+    pub config: McpServerConfig,
+}
+
+#[derive(Clone, Serialize)]
+pub struct McpTestResult {
+    pub ok: bool,
+    pub server_name: Option<String>,
+    pub server_version: Option<String>,
+    pub protocol_version: Option<String>,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+const MCP_PROTOCOL_VERSION: &str = "2025-03-26";
+const MCP_TEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+fn read_json_object(path: &Path) -> Result<Map<String, Value>, String> {
+    if !path.exists() {
+        return Ok(Map::new());
+    }
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if contents.trim().is_empty() {
+        return Ok(Map::new());
+    }
+    match serde_json::from_str(&contents) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(_) => Err(format!("{} is not a JSON object", path.display())),
+        Err(e) => Err(format!("Invalid JSON in {}: {}", path.display(), e)),
+    }
+}
+
+fn write_json_object(path: &Path, object: Map<String, Value>) -> Result<(), String> {
+    let mut contents = serde_json::to_string_pretty(&Value::Object(object)).map_err(|e| e.to_string())?;
+    contents.push('\n');
+    fs_util::write_atomic(path, contents.as_bytes(), true)
+}
+
+fn project_mcp_file(project_path: Option<&str>) -> Result<PathBuf, String> {
+    project_path
+        .filter(|path| !path.is_empty())
+        .map(|path| Path::new(path).join(".mcp.json"))
+        .ok_or_else(|| "A project path is required for project and local MCP servers".to_string())
+}
+
+// The `mcpServers` object for a scope, as a mutable slot inside the parsed file
+fn servers_slot<'a>(
+    root: &'a mut Map<String, Value>,
+    scope: &str,
+    project_path: Option<&str>,
+) -> Result<&'a mut Map<String, Value>, String> {
+    let container = if scope == "local" {
+        let project = project_path.ok_or_else(|| "A project path is required".to_string())?;
+        let projects = root
+            .entry("projects")
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| "\"projects\" is not an object".to_string())?;
+        projects
+            .entry(project)
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or_else(|| format!("Project entry for {} is not an object", project))?
+    } else {
+        root
+    };
+
+    container
+        .entry("mcpServers")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| "\"mcpServers\" is not an object".to_string())
+}
+
+fn scope_file(scope: &str, project_path: Option<&str>) -> Result<PathBuf, String> {
+    match scope {
+        "project" => project_mcp_file(project_path),
+        "local" | "user" => paths::claude_state_file(),
+        other => Err(format!("Unknown MCP scope: {}", other)),
+    }
+}
+
+fn collect_servers(
+    servers: Option<&Value>,
+    scope: &str,
+    source: &Path,
+    entries: &mut Vec<McpServerEntry>,
+) {
+    let Some(servers) = servers.and_then(Value::as_object) else {
+        return;
+    };
+    for (name, config) in servers {
+        if let Ok(config) = serde_json::from_value::<McpServerConfig>(config.clone()) {
+            entries.push(McpServerEntry {
+                name: name.clone(),
+                scope: scope.to_string(),
+                source: source.to_string_lossy().to_string(),
+                config,
+            });
+        }
+    }
+}
+
+// List servers in Claude Code's precedence order: local, project, user
+#[tauri::command]
+pub fn list_mcp_servers(project_path: Option<String>) -> Result<Vec<McpServerEntry>, String> {
+    let mut entries = Vec::new();
+    let state_file = paths::claude_state_file()?;
+    let state = read_json_object(&state_file)?;
+
+    if let Some(project) = project_path.as_deref().filter(|p| !p.is_empty()) {
+        let local = state
+            .get("projects")
+            .and_then(|projects| projects.get(project))
+            .and_then(|entry| entry.get("mcpServers"));
+        collect_servers(local, "local", &state_file, &mut entries);
+
+        let project_file = project_mcp_file(Some(project))?;
+        let project_config = read_json_object(&project_file)?;
+        collect_servers(project_config.get("mcpServers"), "project", &project_file, &mut entries);
+    }
+
+    collect_servers(state.get("mcpServers"), "user", &state_file, &mut entries);
+    Ok(entries)
+}
+
+fn validate_server(name: &str, config: &McpServerConfig) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Server name is required".to_string());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.') {
+        return Err("Server names may only contain letters, numbers, '.', '_' and '-'".to_string());
+    }
+    match config.transport() {
+        "stdio" if config.command.as_deref().is_none_or(str::is_empty) => {
+            Err("stdio servers need a command".to_string())
+        }
+        "sse" | "http" if config.url.as_deref().is_none_or(str::is_empty) => {
+            Err("Remote servers need a url".to_string())
+        }
+        "stdio" | "sse" | "http" => Ok(()),
+        other => Err(format!("Unknown MCP server type: {}", other)),
+    }
+}
+
+#[tauri::command]
+pub fn add_mcp_server(
+    app: AppHandle,
+    name: String,
+    config: McpServerConfig,
+    scope: String,
+    project_path: Option<String>,
+) -> Result<McpServerEntry, String> {
+    validate_server(&name, &config)?;
+
+    let path = scope_file(&scope, project_path.as_deref())?;
+    let mut root = read_json_object(&path)?;
+    let servers = servers_slot(&mut root, &scope, project_path.as_deref())?;
+    servers.insert(
+        name.clone(),
+        serde_json::to_value(&config).map_err(|e| e.to_string())?,
+    );
+    write_json_object(&path, root)?;
+
+    let _ = app.emit("claude-mcp-config-changed", &scope);
+    Ok(McpServerEntry {
+        name,
+        scope,
+        source: path.to_string_lossy().to_string(),
+        config,
+    })
+}
+
+#[tauri::command]
+pub fn remove_mcp_server(
+    app: AppHandle,
+    name: String,
+    scope: String,
+    project_path: Option<String>,
+) -> Result<(), String> {
+    let path = scope_file(&scope, project_path.as_deref())?;
+    let mut root = read_json_object(&path)?;
+    let servers = servers_slot(&mut root, &scope, project_path.as_deref())?;
+    if servers.remove(&name).is_none() {
+        return Err(format!("MCP server \"{}\" not found in {} scope", name, scope));
+    }
+    write_json_object(&path, root)?;
+
+    let _ = app.emit("claude-mcp-config-changed", &scope);
+    Ok(())
+}
+
+fn initialize_request() -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": MCP_PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "incito", "version": env!("CARGO_PKG_VERSION") },
+        },
+    })
+}
+
+fn result_from_response(response: &Value, started: Instant) -> McpTestResult {
+    let duration_ms = started.elapsed().as_millis() as u64;
+    if let Some(error) = response.get("error") {
+        return McpTestResult {
+            ok: false,
+            server_name: None,
+            server_version: None,
+            protocol_version: None,
+            duration_ms,
+            error: Some(format!("Server returned an error: {}", error)),
+        };
+    }
+
+    let result = response.get("result");
+    let server_info = result.and_then(|r| r.get("serverInfo"));
+    let field = |value: Option<&Value>, key: &str| {
+        value
+            .and_then(|v| v.get(key))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    McpTestResult {
+        ok: result.is_some(),
+        server_name: field(server_info, "name"),
+        server_version: field(server_info, "version"),
+        protocol_version: field(result, "protocolVersion"),
+        duration_ms,
+        error: result.is_none().then(|| "Response did not contain a result".to_string()),
+    }
+}
+
+fn failed(started: Instant, error: String) -> McpTestResult {
+    McpTestResult {
+        ok: false,
+        server_name: None,
+        server_version: None,
+        protocol_version: None,
+        duration_ms: started.elapsed().as_millis() as u64,
+        error: Some(error),
+    }
+}
+
+// Spawn a stdio server, send `initialize` and wait for the matching response
+fn test_stdio_server(config: &McpServerConfig, cwd: Option<&Path>) -> McpTestResult {
+    let started = Instant::now();
+    let command = config.command.clone().unwrap_or_default();
+
+    let mut process = Command::new(&command);
+    process
+        .args(&config.args)
+        .envs(&config.env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = cwd {
+        process.current_dir(cwd);
+    }
+
+    let mut child = match process.spawn() {
+        Ok(child) => child,
+        Err(e) => return failed(started, format!("Failed to start {}: {}", command, e)),
+    };
+
+    let mut stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    if let Some(stdin) = stdin.as_mut() {
+        let message = format!("{}\n", initialize_request());
+        if let Err(e) = stdin.write_all(message.as_bytes()).and_then(|_| stdin.flush()) {
+            let _ = child.kill();
+            return failed(started, format!("Failed to write to server: {}", e));
+        }
+    }
+
+    // Drain stderr continuously so a chatty server can't block on a full pipe
+    let stderr_tail = Arc::new(Mutex::new(Vec::<String>::new()));
+    if let Some(stderr) = stderr {
+        let stderr_tail = stderr_tail.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if let Ok(mut tail) = stderr_tail.lock() {
+                    if tail.len() == 20 {
+                        tail.remove(0);
+                    }
+                    tail.push(line);
+                }
+            }
+        });
+    }
+
+    let (tx, rx) = mpsc::channel();
+    if let Some(stdout) = stdout {
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                    continue;
+                };
+                if message.get("id") == Some(&json!(1)) {
+                    let _ = tx.send(message);
+                    break;
+                }
+            }
+        });
+    }
+
+    let result = match rx.recv_timeout(MCP_TEST_TIMEOUT) {
+        Ok(response) => result_from_response(&response, started),
+        Err(_) => {
+            let mut error = "Server did not respond to initialize".to_string();
+            let tail = stderr_tail.lock().map(|tail| tail.join("\n")).unwrap_or_default();
+            if !tail.is_empty() {
+                error.push_str(": ");
+                error.push_str(&tail);
+            }
+            failed(started, error)
+        }
+    };
+
+    drop(stdin);
+    let _ = child.kill();
+    let _ = child.wait();
+    result
+}
+
+// Remote servers answer `initialize` either as JSON or as a one-event SSE stream
+async fn test_http_server(app: &AppHandle, config: &McpServerConfig) -> McpTestResult {
+    let started = Instant::now();
+    let url = config.url.clone().unwrap_or_default();
+
+    let client = match net::http_client(app, MCP_TEST_TIMEOUT) {
+        Ok(client) => client,
+        Err(e) => return failed(started, e),
+    };
+
+    let mut request = if config.transport() == "sse" {
+        client.get(&url).header("Accept", "text/event-stream")
+    } else {
+        client
+            .post(&url)
+            .header("Accept", "application/json, text/event-stream")
+            .json(&initialize_request())
+    };
+    for (key, value) in &config.headers {
+        request = request.header(key, value);
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return failed(started, format!("Failed to connect to {}: {}", url, e)),
+    };
+    if !response.status().is_success() {
+        return failed(started, format!("Server responded with HTTP {}", response.status()));
+    }
+
+    // Legacy SSE servers only need to accept the event stream connection
+    if config.transport() == "sse" {
+        return McpTestResult {
+            ok: true,
+            server_name: None,
+            server_version: None,
+            protocol_version: None,
+            duration_ms: started.elapsed().as_millis() as u64,
+            error: None,
+        };
+    }
+
+    let body = match response.text().await {
+        Ok(body) => body,
+        Err(e) => return failed(started, format!("Failed to read response: {}", e)),
+    };
+    let message = serde_json::from_str::<Value>(&body).ok().or_else(|| {
+        body.lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .find_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+    });
+
+    match message {
+        Some(message) => result_from_response(&message, started),
+        None => failed(started, "Server response was not a JSON-RPC message".to_string()),
+    }
+}
+
+#[tauri::command]
+pub async fn test_mcp_server(
+    app: AppHandle,
+    config: McpServerConfig,
+    project_path: Option<String>,
+) -> Result<McpTestResult, String> {
+    validate_server("test", &config)?;
+
+    if config.transport() == "stdio" {
+        tauri::async_runtime::spawn_blocking(move || {
+            test_stdio_server(&config, project_path.as_deref().map(Path::new))
+        })
+        .await
+        .map_err(|e| format!("MCP test task failed: {}", e))
+    } else {
+        Ok(test_http_server(&app, &config).await)
+    }
+}
@@ -0,0 +1,355 @@
+use std::sync::Mutex;
+
+use tauri::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, Emitter, Listener, Manager, Wry};
+use tauri_plugin_dialog::DialogExt;
+
+use crate::projects::{self, Project};
+use crate::{about, accelerators, context_menu, help, i18n, menu_state, mini_mode, rollback, view, windows};
+
+const RECENT_PROJECTS_LIMIT: i64 = 10;
+
+// The Open Recent submenu and the projects behind its items, in order. The
+// submenu is replaced whenever the app menu is rebuilt.
+#[derive(Default)]
+pub struct RecentMenu {
+    submenu: Mutex<Option<Submenu<Wry>>>,
+    projects: Mutex<Vec<Project>>,
+}
+
+pub fn create(app: &AppHandle) -> Result<Menu<tauri::Wry>, tauri::Error> {
+    let app_name = "Incito";
+
+    // App menu (macOS only, but harmless on other platforms)
+    let about = MenuItem::with_id(app, "about", i18n::t_with(app, "menu.about", &[("app", app_name)]), true, accelerators::current(app, "about"))?;
+    let check_updates = MenuItem::with_id(app, "check-updates", i18n::t(app, "menu.checkUpdates"), true, accelerators::current(app, "check-updates"))?;
+    let rollback_update = MenuItem::with_id(app, "rollback-update", i18n::t(app, "menu.rollbackUpdate"), true, accelerators::current(app, "rollback-update"))?;
+    let separator1 = PredefinedMenuItem::separator(app)?;
+    let separator2 = PredefinedMenuItem::separator(app)?;
+    let separator3 = PredefinedMenuItem::separator(app)?;
+    let separator4 = PredefinedMenuItem::separator(app)?;
+    let services = Submenu::with_id_and_items(app, "services", i18n::t(app, "menu.services"), true, &[])?;
+    let hide = PredefinedMenuItem::hide(app, Some(&i18n::t_with(app, "menu.hide", &[("app", app_name)])))?;
+    let hide_others = PredefinedMenuItem::hide_others(app, Some(&i18n::t(app, "menu.hideOthers")))?;
+    let show_all = PredefinedMenuItem::show_all(app, Some(&i18n::t(app, "menu.showAll")))?;
+    let quit = PredefinedMenuItem::quit(app, Some(&i18n::t_with(app, "menu.quit", &[("app", app_name)])))?;
+
+    let app_menu = Submenu::with_id_and_items(
+        app,
+        "app",
+        app_name,
+        true,
+        &[
+            &about,
+            &separator1,
+            &check_updates,
+            &rollback_update,
+            &separator2,
+            &services,
+            &separator3,
+            &hide,
+            &hide_others,
+            &show_all,
+            &separator4,
+            &quit,
+        ],
+    )?;
+
+    // File menu
+    let new_session = MenuItem::with_id(app, "new-session", i18n::t(app, "menu.newSession"), true, accelerators::current(app, "new-session"))?;
+    let open_project = MenuItem::with_id(app, "open-project", i18n::t(app, "menu.openProject"), true, accelerators::current(app, "open-project"))?;
+    let open_recent = Submenu::with_id(app, "open-recent", i18n::t(app, "menu.openRecent"), true)?;
+    let file_separator = PredefinedMenuItem::separator(app)?;
+    let stop_task = MenuItem::with_id(app, "stop-task", i18n::t(app, "menu.stopTask"), false, accelerators::current(app, "stop-task"))?;
+    let file_menu = Submenu::with_id_and_items(app, "file", i18n::t(app, "menu.file"), true, &[&new_session, &open_project, &open_recent, &file_separator, &stop_task])?;
+    if let Ok(mut submenu) = app.state::<RecentMenu>().submenu.lock() {
+        *submenu = Some(open_recent);
+    }
+
+    // Edit menu
+    let undo = PredefinedMenuItem::undo(app, Some(&i18n::t(app, "menu.undo")))?;
+    let redo = PredefinedMenuItem::redo(app, Some(&i18n::t(app, "menu.redo")))?;
+    let edit_separator = PredefinedMenuItem::separator(app)?;
+    let cut = PredefinedMenuItem::cut(app, Some(&i18n::t(app, "menu.cut")))?;
+    let copy = PredefinedMenuItem::copy(app, Some(&i18n::t(app, "menu.copy")))?;
+    let paste = PredefinedMenuItem::paste(app, Some(&i18n::t(app, "menu.paste")))?;
+    let select_all = PredefinedMenuItem::select_all(app, Some(&i18n::t(app, "menu.selectAll")))?;
+    let edit_separator2 = PredefinedMenuItem::separator(app)?;
+    let find = MenuItem::with_id(app, "find", i18n::t(app, "menu.find"), true, accelerators::current(app, "find"))?;
+
+    let edit_menu = Submenu::with_id_and_items(
+        app,
+        "edit",
+        i18n::t(app, "menu.edit"),
+        true,
+        &[
+            &undo,
+            &redo,
+            &edit_separator,
+            &cut,
+            &copy,
+            &paste,
+            &select_all,
+            &edit_separator2,
+            &find,
+        ],
+    )?;
+
+    // View menu
+    let zoom_in = MenuItem::with_id(app, "zoom-in", i18n::t(app, "menu.zoomIn"), true, accelerators::current(app, "zoom-in"))?;
+    let zoom_out = MenuItem::with_id(app, "zoom-out", i18n::t(app, "menu.zoomOut"), true, accelerators::current(app, "zoom-out"))?;
+    let zoom_reset = MenuItem::with_id(app, "zoom-reset", i18n::t(app, "menu.actualSize"), true, accelerators::current(app, "zoom-reset"))?;
+    let view_separator1 = PredefinedMenuItem::separator(app)?;
+    let reload = MenuItem::with_id(app, "reload", i18n::t(app, "menu.reload"), true, accelerators::current(app, "reload"))?;
+    let view_separator2 = PredefinedMenuItem::separator(app)?;
+    let fullscreen = MenuItem::with_id(
+        app,
+        "toggle-fullscreen",
+        i18n::t(app, "menu.toggleFullScreen"),
+        true,
+        accelerators::current(app, "toggle-fullscreen"),
+    )?;
+
+    let view_menu = Submenu::with_id_and_items(
+        app,
+        "view",
+        i18n::t(app, "menu.view"),
+        true,
+        &[
+            &zoom_in,
+            &zoom_out,
+            &zoom_reset,
+            &view_separator1,
+            &reload,
+            &view_separator2,
+            &fullscreen,
+        ],
+    )?;
+
+    #[cfg(debug_assertions)]
+    {
+        let devtools = MenuItem::with_id(app, "toggle-devtools", i18n::t(app, "menu.toggleDevtools"), true, accelerators::current(app, "toggle-devtools"))?;
+        view_menu.append_items(&[&PredefinedMenuItem::separator(app)?, &devtools])?;
+    }
+
+    // Window menu
+    let minimize = PredefinedMenuItem::minimize(app, Some(&i18n::t(app, "menu.minimize")))?;
+    let maximize = MenuItem::with_id(app, "maximize", i18n::t(app, "menu.maximize"), true, accelerators::current(app, "maximize"))?;
+    let window_separator = PredefinedMenuItem::separator(app)?;
+    let always_on_top = CheckMenuItem::with_id(app, "always-on-top", i18n::t(app, "menu.floatOnTop"), true, false, accelerators::current(app, "always-on-top"))?;
+    let mini_mode = CheckMenuItem::with_id(app, "mini-mode", i18n::t(app, "menu.miniMode"), true, false, accelerators::current(app, "mini-mode"))?;
+    let window_separator2 = PredefinedMenuItem::separator(app)?;
+    let close = PredefinedMenuItem::close_window(app, Some(&i18n::t(app, "menu.closeWindow")))?;
+
+    let window_menu = Submenu::with_id_and_items(
+        app,
+        "window",
+        i18n::t(app, "menu.window"),
+        true,
+        &[
+            &minimize,
+            &maximize,
+            &window_separator,
+            &always_on_top,
+            &mini_mode,
+            &window_separator2,
+            &close,
+        ],
+    )?;
+    mini_mode::register(app, always_on_top.clone(), mini_mode.clone());
+
+    // Help menu
+    let open_logs = MenuItem::with_id(app, "open-logs", i18n::t(app, "menu.openLogs"), true, accelerators::current(app, "open-logs"))?;
+    let show_config = MenuItem::with_id(app, "show-config", i18n::t(app, "menu.showConfig"), true, accelerators::current(app, "show-config"))?;
+    let help_separator1 = PredefinedMenuItem::separator(app)?;
+    let restart_server = MenuItem::with_id(app, "restart-server", i18n::t(app, "menu.restartServer"), false, accelerators::current(app, "restart-server"))?;
+    let help_separator2 = PredefinedMenuItem::separator(app)?;
+    let report_issue = MenuItem::with_id(app, "report-issue", i18n::t(app, "menu.reportIssue"), true, accelerators::current(app, "report-issue"))?;
+
+    let help_menu = Submenu::with_id_and_items(
+        app,
+        "help",
+        i18n::t(app, "menu.help"),
+        true,
+        &[
+            &open_logs,
+            &show_config,
+            &help_separator1,
+            &restart_server,
+            &help_separator2,
+            &report_issue,
+        ],
+    )?;
+
+    menu_state::register(app, restart_server.clone(), stop_task.clone());
+
+    // Build the complete menu
+    Menu::with_items(app, &[&app_menu, &file_menu, &edit_menu, &view_menu, &window_menu, &help_menu])
+}
+
+// Fill Open Recent from the database now and whenever the list changes
+pub fn watch_recent_projects(app: &AppHandle) {
+    for event in projects::CHANGE_EVENTS {
+        let handle = app.clone();
+        app.listen_any(*event, move |_| {
+            let handle = handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = rebuild_recent_projects(&handle).await;
+            });
+        });
+    }
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _ = rebuild_recent_projects(&handle).await;
+    });
+}
+
+pub async fn rebuild_recent_projects(app: &AppHandle) -> Result<(), String> {
+    let Some(state) = app.try_state::<RecentMenu>() else { return Ok(()) };
+    let recent = projects::recent_projects(app, RECENT_PROJECTS_LIMIT).await?;
+
+    let Some(submenu) = state.submenu.lock().map_err(|e| e.to_string())?.clone() else { return Ok(()) };
+    for item in submenu.items().map_err(|e| e.to_string())? {
+        submenu.remove(&item).map_err(|e| e.to_string())?;
+    }
+    if recent.is_empty() {
+        let empty = MenuItem::with_id(app, "recent-empty", i18n::t(app, "menu.noRecentProjects"), false, None::<&str>)
+            .map_err(|e| e.to_string())?;
+        submenu.append(&empty).map_err(|e| e.to_string())?;
+    }
+    for (index, project) in recent.iter().enumerate() {
+        let item = MenuItem::with_id(app, format!("recent-project-{}", index), &project.name, true, None::<&str>)
+            .map_err(|e| e.to_string())?;
+        submenu.append(&item).map_err(|e| e.to_string())?;
+    }
+    let separator = PredefinedMenuItem::separator(app).map_err(|e| e.to_string())?;
+    let clear = MenuItem::with_id(
+        app,
+        "clear-recent",
+        i18n::t(app, "menu.clearRecent"),
+        !recent.is_empty(),
+        accelerators::current(app, "clear-recent"),
+    )
+    .map_err(|e| e.to_string())?;
+    submenu.append_items(&[&separator, &clear]).map_err(|e| e.to_string())?;
+
+    *state.projects.lock().map_err(|e| e.to_string())? = recent;
+    Ok(())
+}
+
+fn open_recent_project(app: &AppHandle, index: usize) {
+    let Some(state) = app.try_state::<RecentMenu>() else { return };
+    let project = state.projects.lock().ok().and_then(|projects| projects.get(index).cloned());
+    let Some(project) = project else { return };
+
+    if let Some(window) = windows::focused_window(app) {
+        let _ = window.emit_to(window.label(), "menu-open-project", project);
+    }
+}
+
+// Record a folder as a project and open it like a recent one
+pub fn open_project(app: &AppHandle, path: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match projects::record_project_opened(app.clone(), path, None).await {
+            Ok(project) => {
+                if let Some(window) = windows::focused_window(&app) {
+                    let _ = window.emit_to(window.label(), "menu-open-project", project);
+                }
+            }
+            Err(e) => {
+                let _ = app.emit("menu-action-failed", e);
+            }
+        }
+    });
+}
+
+fn pick_project(app: &AppHandle) {
+    let handle = app.clone();
+    app.dialog().file().set_title(i18n::t(app, "dialogs.openProject")).pick_folder(move |folder| {
+        if let Some(path) = folder.and_then(|folder| folder.into_path().ok()) {
+            open_project(&handle, path.to_string_lossy().to_string());
+        }
+    });
+}
+
+pub fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    if context_menu::handle_menu_event(app, event.id().as_ref()) {
+        return;
+    }
+    if let Some(index) = event
+        .id()
+        .as_ref()
+        .strip_prefix("recent-project-")
+        .and_then(|index| index.parse::<usize>().ok())
+    {
+        open_recent_project(app, index);
+        return;
+    }
+
+    match event.id().as_ref() {
+        "new-session" => {
+            if let Some(window) = windows::focused_window(app) {
+                let _ = window.emit_to(window.label(), "menu-new-session", ());
+            }
+        }
+        "open-project" => pick_project(app),
+        "find" => {
+            if let Some(window) = windows::focused_window(app) {
+                let _ = window.emit_to(window.label(), "menu-find", ());
+            }
+        }
+        "stop-task" => {
+            if let Some(window) = windows::focused_window(app) {
+                let _ = window.emit_to(window.label(), "menu-stop-task", ());
+            }
+        }
+        "clear-recent" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = projects::clear_recent(&app).await;
+            });
+        }
+        // Routed to the focused window; emit alone would reach every window
+        "check-updates" => {
+            if let Some(window) = windows::focused_window(app) {
+                let _ = window.emit_to(window.label(), "menu-check-updates", ());
+            }
+        }
+        "rollback-update" => rollback::confirm_rollback(app),
+        "about" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move { about::show_about(&app).await });
+        }
+        "zoom-in" => view::zoom_in(app),
+        "zoom-out" => view::zoom_out(app),
+        "zoom-reset" => view::reset_zoom(app),
+        "reload" => {
+            if let Some(window) = windows::focused_window(app) {
+                let _ = window.reload();
+            }
+        }
+        "toggle-fullscreen" => {
+            if let Some(window) = windows::focused_window(app) {
+                view::toggle_fullscreen(&window);
+            }
+        }
+        #[cfg(debug_assertions)]
+        "toggle-devtools" => {
+            if let Some(window) = windows::focused_window(app) {
+                view::toggle_devtools(&window);
+            }
+        }
+        "open-logs" | "show-config" | "restart-server" | "report-issue" => {
+            help::handle_menu_event(app, event.id().as_ref());
+        }
+        "always-on-top" | "mini-mode" => mini_mode::handle_menu_event(app, event.id().as_ref()),
+        "maximize" => {
+            if let Some(window) = windows::focused_window(app) {
+                let _ = window.maximize();
+            }
+        }
+        _ => {}
+    }
+}
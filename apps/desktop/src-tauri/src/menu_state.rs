@@ -0,0 +1,37 @@
+use std::sync::Mutex;
+
+use tauri::menu::MenuItem;
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::{server, tasks};
+
+// App menu items that only make sense while something is running
+struct Handles {
+    restart_server: MenuItem<Wry>,
+    stop_task: MenuItem<Wry>,
+}
+
+// Replaced whenever the app menu is rebuilt
+#[derive(Default)]
+pub struct MenuHandles(Mutex<Option<Handles>>);
+
+pub fn register(app: &AppHandle, restart_server: MenuItem<Wry>, stop_task: MenuItem<Wry>) {
+    if let Ok(mut handles) = app.state::<MenuHandles>().0.lock() {
+        *handles = Some(Handles {
+            restart_server,
+            stop_task,
+        });
+    }
+    sync(app);
+}
+
+// Bring enabled state in line with the sidecar and the active task
+pub fn sync(app: &AppHandle) {
+    let Some(state) = app.try_state::<MenuHandles>() else { return };
+    let Ok(handles) = state.0.lock() else { return };
+    let Some(handles) = handles.as_ref() else { return };
+    let task_running = tasks::any_running(app);
+
+    let _ = handles.restart_server.set_enabled(server::is_running(app));
+    let _ = handles.stop_task.set_enabled(task_running);
+}
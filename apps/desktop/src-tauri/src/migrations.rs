@@ -0,0 +1,202 @@
+use std::sync::Mutex;
+
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::db;
+
+// Versioned files in src-tauri/migrations, embedded at build time. They run
+// from setup rather than through the sql plugin's preload, where a failure
+// would abort startup before anything could tell the user why.
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+// Columns the frontend used to add with ALTER TABLE on every load. Older
+// databases may lack any of them.
+const LEGACY_COLUMNS: &[(&str, &str, &str)] = &[
+    ("prompt_runs", "input_tokens", "INTEGER"),
+    ("prompt_runs", "output_tokens", "INTEGER"),
+    ("prompt_runs", "total_tokens", "INTEGER"),
+    ("prompt_runs", "model_id", "TEXT"),
+    ("prompt_runs", "provider", "TEXT"),
+    ("prompt_runs", "estimated_cost_usd", "REAL"),
+    ("prompt_runs", "sync_id", "TEXT"),
+    ("run_analytics_daily", "total_input_tokens", "INTEGER NOT NULL DEFAULT 0"),
+    ("run_analytics_daily", "total_output_tokens", "INTEGER NOT NULL DEFAULT 0"),
+    ("run_analytics_daily", "total_estimated_cost_usd", "REAL NOT NULL DEFAULT 0"),
+    ("prompt_versions", "run_config", "TEXT"),
+    ("tags", "created_at", "TEXT"),
+    ("tags", "updated_at", "TEXT"),
+    ("tags", "sync_id", "TEXT"),
+    ("graders", "sync_id", "TEXT"),
+    ("playbooks", "sync_id", "TEXT"),
+    ("playbook_rules", "sync_id", "TEXT"),
+    ("chat_sessions", "sync_id", "TEXT"),
+];
+
+// Columns Rust added with ALTER TABLE to tables it created on first use,
+// before migrations covered them, and the migration that now creates them
+const FIRST_USE_COLUMNS: &[(i64, &str, &str, &str)] = &[
+    (2, "projects", "kind", "TEXT"),
+    (2, "projects", "pinned", "INTEGER NOT NULL DEFAULT 0"),
+    (3, "usage_files", "per_file", "INTEGER NOT NULL DEFAULT 0"),
+    (4, "project_sandbox", "accept_limits_only", "INTEGER NOT NULL DEFAULT 0"),
+];
+
+const SYNC_ID_TABLES: &[&str] = &["tags", "graders", "playbooks", "playbook_rules", "chat_sessions", "prompt_runs"];
+
+#[derive(Clone, serde::Serialize)]
+pub struct MigrationFailure {
+    // The migration that failed, when it's known
+    pub version: Option<i64>,
+    pub error: String,
+}
+
+// Kept for a frontend that wasn't listening yet when it was emitted
+#[derive(Default)]
+pub struct MigrationStatus(Mutex<Option<MigrationFailure>>);
+
+async fn table_exists(pool: &SqlitePool, table: &str) -> Result<bool, sqlx::Error> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?")
+        .bind(table)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.is_some())
+}
+
+async fn has_column(pool: &SqlitePool, table: &str, column: &str) -> Result<bool, sqlx::Error> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT name FROM pragma_table_info(?) WHERE name = ?")
+        .bind(table)
+        .bind(column)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.is_some())
+}
+
+// Bring a database the frontend created before migrations existed up to the
+// baseline's shape, so the baseline's CREATE ... IF NOT EXISTS statements
+// and indexes apply cleanly
+async fn upgrade_legacy(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    if table_exists(pool, "_sqlx_migrations").await? {
+        return Ok(());
+    }
+
+    for (table, column, definition) in LEGACY_COLUMNS {
+        if table_exists(pool, table).await? && !has_column(pool, table, column).await? {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(pool)
+                .await?;
+        }
+    }
+
+    if table_exists(pool, "tags").await? {
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query("UPDATE tags SET created_at = ?, updated_at = ? WHERE created_at IS NULL")
+            .bind(&now)
+            .bind(&now)
+            .execute(pool)
+            .await?;
+    }
+
+    for table in SYNC_ID_TABLES {
+        if !table_exists(pool, table).await? {
+            continue;
+        }
+        let ids: Vec<(String,)> = sqlx::query_as(&format!("SELECT id FROM {} WHERE sync_id IS NULL", table))
+            .fetch_all(pool)
+            .await?;
+        for (id,) in ids {
+            sqlx::query(&format!("UPDATE {} SET sync_id = ? WHERE id = ?", table))
+                .bind(uuid::Uuid::new_v4().to_string())
+                .bind(id)
+                .execute(pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn is_applied(pool: &SqlitePool, version: i64) -> Result<bool, sqlx::Error> {
+    if !table_exists(pool, "_sqlx_migrations").await? {
+        return Ok(false);
+    }
+    let row: Option<(i64,)> = sqlx::query_as("SELECT version FROM _sqlx_migrations WHERE version = ?")
+        .bind(version)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.is_some())
+}
+
+// Tables Rust used to create on first use may be older than the migration
+// that now creates them, and lack columns it has
+async fn upgrade_first_use(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    for (version, table, column, definition) in FIRST_USE_COLUMNS {
+        if is_applied(pool, *version).await? || !table_exists(pool, table).await? {
+            continue;
+        }
+        if !has_column(pool, table, column).await? {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+fn failure(error: MigrateError) -> MigrationFailure {
+    let version = match &error {
+        MigrateError::ExecuteMigration(_, version)
+        | MigrateError::VersionMissing(version)
+        | MigrateError::VersionMismatch(version)
+        | MigrateError::VersionNotPresent(version)
+        | MigrateError::Dirty(version) => Some(*version),
+        _ => None,
+    };
+    MigrationFailure {
+        version,
+        error: error.to_string(),
+    }
+}
+
+// From setup, before the first window is created. A failure is emitted as
+// `db-migration-failed` and kept for `get_db_migration_failure`; the app
+// keeps running so the user can see what happened.
+pub async fn run(app: &AppHandle) -> Result<(), MigrationFailure> {
+    let result = async {
+        let pool = db::pool(app).await.map_err(|error| MigrationFailure { version: None, error })?;
+        upgrade_legacy(&pool)
+            .await
+            .map_err(|e| failure(MigrateError::Execute(e)))?;
+        upgrade_first_use(&pool)
+            .await
+            .map_err(|e| failure(MigrateError::Execute(e)))?;
+        MIGRATOR.run(&pool).await.map_err(failure)
+    }
+    .await;
+
+    if let Err(failure) = &result {
+        report(app, failure);
+    }
+    result
+}
+
+// Also used when the database can't be opened at all, which the user sees
+// the same way
+pub fn report(app: &AppHandle, failure: &MigrationFailure) {
+    eprintln!("Database migration failed: {}", failure.error);
+    if let Ok(mut status) = app.state::<MigrationStatus>().0.lock() {
+        *status = Some(failure.clone());
+    }
+    let _ = app.emit("db-migration-failed", failure.clone());
+}
+
+// The newest migration this build knows about. A database migrated past it
+// came from a newer version of the app.
+pub fn latest_version() -> i64 {
+    MIGRATOR.iter().map(|migration| migration.version).max().unwrap_or(0)
+}
+
+#[tauri::command]
+pub fn get_db_migration_failure(app: AppHandle) -> Option<MigrationFailure> {
+    app.state::<MigrationStatus>().0.lock().ok().and_then(|status| status.clone())
+}
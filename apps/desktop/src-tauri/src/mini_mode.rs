@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use tauri::menu::CheckMenuItem;
+use tauri::{AppHandle, Emitter, LogicalSize, Manager, PhysicalPosition, PhysicalSize, WebviewWindow, Wry};
+
+use crate::windows::{self, MAIN_WINDOW};
+
+const MINI_WIDTH: f64 = 360.0;
+const MINI_HEIGHT: f64 = 240.0;
+const MINI_MARGIN: f64 = 24.0;
+
+// How a window looked before it went into mini mode
+struct SavedFrame {
+    size: PhysicalSize<u32>,
+    position: PhysicalPosition<i32>,
+    maximized: bool,
+    decorated: bool,
+    always_on_top: bool,
+}
+
+// Windows currently in mini mode, keyed by label
+#[derive(Default)]
+pub struct MiniModeState(Mutex<HashMap<String, SavedFrame>>);
+
+// The Window menu toggles, kept in step with the focused window
+struct Handles {
+    always_on_top: CheckMenuItem<Wry>,
+    mini_mode: CheckMenuItem<Wry>,
+}
+
+// Replaced whenever the app menu is rebuilt
+#[derive(Default)]
+pub struct WindowMenuHandles(Mutex<Option<Handles>>);
+
+pub fn register(app: &AppHandle, always_on_top: CheckMenuItem<Wry>, mini_mode: CheckMenuItem<Wry>) {
+    if let Ok(mut handles) = app.state::<WindowMenuHandles>().0.lock() {
+        *handles = Some(Handles {
+            always_on_top,
+            mini_mode,
+        });
+    }
+}
+
+pub fn is_mini(window: &WebviewWindow) -> bool {
+    window
+        .app_handle()
+        .state::<MiniModeState>()
+        .0
+        .lock()
+        .is_ok_and(|windows| windows.contains_key(window.label()))
+}
+
+pub fn sync_menu(window: &WebviewWindow) {
+    let Some(state) = window.app_handle().try_state::<WindowMenuHandles>() else { return };
+    let Ok(handles) = state.0.lock() else { return };
+    let Some(handles) = handles.as_ref() else { return };
+    let _ = handles
+        .always_on_top
+        .set_checked(window.is_always_on_top().unwrap_or(false));
+    let _ = handles.mini_mode.set_checked(is_mini(window));
+}
+
+fn changed(window: &WebviewWindow) {
+    sync_menu(window);
+    let _ = window.emit_to(window.label(), "mini-mode-changed", is_mini(window));
+}
+
+// The minimum size windows are created with; mini mode has to lift it
+fn configured_min_size(app: &AppHandle) -> Option<LogicalSize<f64>> {
+    let config = app.config().app.windows.iter().find(|window| window.label == MAIN_WINDOW)?;
+    Some(LogicalSize::new(config.min_width?, config.min_height?))
+}
+
+pub fn enter(window: &WebviewWindow) -> Result<(), String> {
+    if is_mini(window) {
+        return Ok(());
+    }
+    let saved = SavedFrame {
+        size: window.outer_size().map_err(|e| e.to_string())?,
+        position: window.outer_position().map_err(|e| e.to_string())?,
+        maximized: window.is_maximized().unwrap_or(false),
+        decorated: window.is_decorated().unwrap_or(true),
+        always_on_top: window.is_always_on_top().unwrap_or(false),
+    };
+
+    let _ = window.set_fullscreen(false);
+    let _ = window.unmaximize();
+    window.set_min_size(None::<LogicalSize<f64>>).map_err(|e| e.to_string())?;
+    window.set_decorations(false).map_err(|e| e.to_string())?;
+    window
+        .set_size(LogicalSize::new(MINI_WIDTH, MINI_HEIGHT))
+        .map_err(|e| format!("Failed to resize window: {}", e))?;
+    window.set_always_on_top(true).map_err(|e| e.to_string())?;
+
+    // Tuck it into the top-right corner of the screen it's on
+    if let Ok(Some(monitor)) = window.current_monitor() {
+        let scale = monitor.scale_factor();
+        let x = monitor.position().x + monitor.size().width as i32 - ((MINI_WIDTH + MINI_MARGIN) * scale) as i32;
+        let y = monitor.position().y + (MINI_MARGIN * scale) as i32;
+        let _ = window.set_position(PhysicalPosition::new(x, y));
+    }
+
+    window
+        .app_handle()
+        .state::<MiniModeState>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(window.label().to_string(), saved);
+    changed(window);
+    Ok(())
+}
+
+pub fn exit(window: &WebviewWindow) -> Result<(), String> {
+    let saved = window
+        .app_handle()
+        .state::<MiniModeState>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(window.label());
+    let Some(saved) = saved else { return Ok(()) };
+
+    window.set_decorations(saved.decorated).map_err(|e| e.to_string())?;
+    window.set_always_on_top(saved.always_on_top).map_err(|e| e.to_string())?;
+    window.set_min_size(configured_min_size(window.app_handle())).map_err(|e| e.to_string())?;
+    window.set_size(saved.size).map_err(|e| e.to_string())?;
+    window.set_position(saved.position).map_err(|e| e.to_string())?;
+    if saved.maximized {
+        let _ = window.maximize();
+    }
+    changed(window);
+    Ok(())
+}
+
+pub fn toggle(window: &WebviewWindow) -> Result<(), String> {
+    if is_mini(window) {
+        exit(window)
+    } else {
+        enter(window)
+    }
+}
+
+pub fn toggle_always_on_top(window: &WebviewWindow) -> Result<(), String> {
+    let enabled = !window.is_always_on_top().unwrap_or(false);
+    set_always_on_top(window.clone(), enabled)
+}
+
+// Called from the Window menu; acts on the focused window
+pub fn handle_menu_event(app: &AppHandle, id: &str) {
+    let Some(window) = windows::focused_window(app) else { return };
+    let result = match id {
+        "always-on-top" => toggle_always_on_top(&window),
+        "mini-mode" => toggle(&window),
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        let _ = app.emit("menu-action-failed", e);
+    }
+    // Check items flip themselves on click; put them back if nothing changed
+    sync_menu(&window);
+}
+
+#[tauri::command]
+pub fn set_always_on_top(window: WebviewWindow, enabled: bool) -> Result<(), String> {
+    window
+        .set_always_on_top(enabled)
+        .map_err(|e| format!("Failed to set always on top: {}", e))?;
+    sync_menu(&window);
+    Ok(())
+}
+
+// Shrink the window to a small frameless companion that stays above other
+// apps, for keeping an eye on a running task
+#[tauri::command]
+pub fn enter_mini_mode(window: WebviewWindow) -> Result<(), String> {
+    enter(&window)
+}
+
+#[tauri::command]
+pub fn exit_mini_mode(window: WebviewWindow) -> Result<(), String> {
+    exit(&window)
+}
+
+#[tauri::command]
+pub fn is_mini_mode(window: WebviewWindow) -> bool {
+    is_mini(&window)
+}
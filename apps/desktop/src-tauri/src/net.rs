@@ -0,0 +1,195 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Manager};
+
+use crate::{settings, updater};
+
+const TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Proxy and extra CA certificates for every Rust-side HTTP client, the
+// updater's included. Without a proxy, reqwest picks up HTTPS_PROXY and the
+// OS proxy settings on its own.
+#[derive(Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct NetworkConfig {
+    pub proxy: Option<String>,
+    // Path to a PEM file, added on top of the built-in roots
+    pub ca_bundle: Option<String>,
+}
+
+#[derive(Default)]
+pub struct NetworkState(Mutex<NetworkConfig>);
+
+#[derive(Clone, serde::Serialize)]
+pub struct NetworkTestResult {
+    pub ok: bool,
+    pub url: String,
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
+
+// What a client builder needs, resolved once so it can be applied from the
+// updater's infallible client hook
+#[derive(Clone)]
+pub struct ClientOptions {
+    proxy: Option<reqwest::Proxy>,
+    certificates: Vec<reqwest::Certificate>,
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+// INCITO_PROXY / INCITO_CA_BUNDLE, for managed machines that set things up
+// through the environment
+fn from_env() -> NetworkConfig {
+    NetworkConfig {
+        proxy: non_empty(std::env::var("INCITO_PROXY").ok()),
+        ca_bundle: non_empty(std::env::var("INCITO_CA_BUNDLE").ok()),
+    }
+}
+
+// Saved settings win over the environment
+pub async fn load_saved(app: &AppHandle) -> Result<(), String> {
+    let env = from_env();
+    let saved = settings::get(app).network;
+    let config = NetworkConfig {
+        proxy: non_empty(saved.proxy).or(env.proxy),
+        ca_bundle: non_empty(saved.ca_bundle).or(env.ca_bundle),
+    };
+    *app.state::<NetworkState>().0.lock().map_err(|e| e.to_string())? = config;
+    Ok(())
+}
+
+pub fn current(app: &AppHandle) -> NetworkConfig {
+    app.try_state::<NetworkState>()
+        .and_then(|state| state.0.lock().ok().map(|config| config.clone()))
+        .unwrap_or_else(from_env)
+}
+
+pub fn client_options(config: &NetworkConfig) -> Result<ClientOptions, String> {
+    let proxy = match &config.proxy {
+        Some(proxy) => Some(reqwest::Proxy::all(proxy).map_err(|e| format!("Invalid proxy {}: {}", proxy, e))?),
+        None => None,
+    };
+    let certificates = match &config.ca_bundle {
+        Some(path) => {
+            let pem = std::fs::read(path).map_err(|e| format!("Failed to read CA bundle {}: {}", path, e))?;
+            reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| format!("Invalid CA bundle {}: {}", path, e))?
+        }
+        None => Vec::new(),
+    };
+    Ok(ClientOptions { proxy, certificates })
+}
+
+pub fn apply(builder: reqwest::ClientBuilder, options: &ClientOptions) -> reqwest::ClientBuilder {
+    let mut builder = builder.tls_certs_merge(options.certificates.clone());
+    if let Some(proxy) = &options.proxy {
+        builder = builder.proxy(proxy.clone());
+    }
+    builder
+}
+
+// reqwest is built without a bundled crypto provider (like the updater's),
+// so install ring before the first client is created
+fn build_client(config: &NetworkConfig, timeout: Duration) -> Result<reqwest::Client, String> {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    let builder = reqwest::Client::builder().user_agent("Incito").timeout(timeout);
+    apply(builder, &client_options(config)?)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+// Shared HTTP client setup for Rust-side network calls
+pub fn http_client(app: &AppHandle, timeout: Duration) -> Result<reqwest::Client, String> {
+    build_client(&current(app), timeout)
+}
+
+#[tauri::command]
+pub fn get_network_config(app: AppHandle) -> NetworkConfig {
+    current(&app)
+}
+
+#[tauri::command]
+pub async fn set_network_config(app: AppHandle, config: NetworkConfig) -> Result<(), String> {
+    let config = NetworkConfig {
+        proxy: non_empty(config.proxy),
+        ca_bundle: non_empty(config.ca_bundle),
+    };
+    // Fail before saving anything that can't be used
+    client_options(&config)?;
+    settings::update(&app, |settings| settings.network = config)?;
+    load_saved(&app).await
+}
+
+// Try a config (the current one if none is given) against the update
+// manifest, the request that matters most behind a corporate proxy
+#[tauri::command]
+pub async fn test_network_config(app: AppHandle, config: Option<NetworkConfig>) -> Result<NetworkTestResult, String> {
+    let config = config.unwrap_or_else(|| current(&app));
+    let url = updater::channel_endpoints(&app, "stable")?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No updater endpoints configured".to_string())?
+        .to_string();
+    let client = build_client(&config, TEST_TIMEOUT)?;
+
+    let started = Instant::now();
+    let response = client.get(&url).send().await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+    Ok(match response {
+        Ok(response) => NetworkTestResult {
+            ok: response.status().is_success(),
+            url,
+            status: Some(response.status().as_u16()),
+            duration_ms,
+            error: None,
+        },
+        Err(e) => NetworkTestResult {
+            ok: false,
+            url,
+            status: None,
+            duration_ms,
+            error: Some(e.to_string()),
+        },
+    })
+}
+
+// Whether the current connection is metered (mobile data, a hotspot marked
+// as metered), so background downloads can hold off. Unknown counts as not
+// metered.
+#[cfg(windows)]
+pub fn is_metered() -> bool {
+    use windows::Networking::Connectivity::{NetworkCostType, NetworkInformation};
+
+    let cost = NetworkInformation::GetInternetConnectionProfile().and_then(|profile| profile.GetConnectionCost());
+    let Ok(cost) = cost else { return false };
+    matches!(cost.NetworkCostType(), Ok(NetworkCostType::Fixed) | Ok(NetworkCostType::Variable))
+        || cost.Roaming().unwrap_or(false)
+        || cost.OverDataLimit().unwrap_or(false)
+}
+
+// NetworkManager's guess for the primary connection: 1 yes, 3 guess-yes
+#[cfg(target_os = "linux")]
+pub fn is_metered() -> bool {
+    std::process::Command::new("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .is_some_and(|output| matches!(String::from_utf8_lossy(&output.stdout).trim(), "u 1" | "u 3"))
+}
+
+// macOS only reports this through Network.framework path monitors
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn is_metered() -> bool {
+    false
+}
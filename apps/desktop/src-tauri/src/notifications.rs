@@ -0,0 +1,112 @@
+use notify_rust::NotificationResponse;
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::{i18n, windows};
+
+#[derive(Clone, serde::Serialize)]
+pub struct NotificationTarget {
+    pub window: String,
+    pub session_id: Option<String>,
+}
+
+fn app_focused(app: &AppHandle) -> bool {
+    app.webview_windows()
+        .values()
+        .any(|window| window.is_focused().unwrap_or(false))
+}
+
+// Set up the way the notification plugin does it, through the same backend,
+// which also reports clicks. The plugin's builder doesn't pass them on.
+fn build(app: &AppHandle, title: &str, body: &str) -> notify_rust::Notification {
+    let mut notification = notify_rust::Notification::new();
+    notification.summary(title).body(body).auto_icon();
+    // Freedesktop servers only report a click on the body when there's a
+    // "default" action; elsewhere an action would be shown as a button
+    #[cfg(all(unix, not(target_os = "macos")))]
+    notification.action("default", &i18n::t(app, "notifications.open"));
+    // The installer registers the app id; a build run from target/ has none
+    #[cfg(windows)]
+    {
+        let installed = tauri::utils::platform::current_exe().ok().is_some_and(|exe| {
+            !exe.parent().is_some_and(|dir| dir.ends_with("target/debug") || dir.ends_with("target/release"))
+        });
+        if installed {
+            notification.app_id(&app.config().identifier);
+        }
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let _ = notify_rust::set_application(if tauri::is_dev() {
+            "com.apple.Terminal"
+        } else {
+            &app.config().identifier
+        });
+    }
+    notification
+}
+
+// Show a notification unless the user is already looking at the app.
+// Returns whether it was shown. Clicking it brings up the target window.
+pub fn notify(app: &AppHandle, title: &str, body: &str, target: NotificationTarget) -> Result<bool, String> {
+    if app_focused(app) {
+        return Ok(false);
+    }
+
+    let notification = build(app, title, body);
+    let app = app.clone();
+    // Waiting for a response blocks until the notification is clicked or
+    // dismissed
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = notification.show().and_then(|handle| {
+            handle.wait_for_response(|response: &NotificationResponse| {
+                if let NotificationResponse::Default = response {
+                    open(&app, target);
+                }
+            })
+        });
+        if let Err(e) = result {
+            eprintln!("Failed to show notification: {}", e);
+        }
+    });
+    Ok(true)
+}
+
+pub fn notify_task_finished(
+    app: &AppHandle,
+    window: &str,
+    project_name: &str,
+    session_id: Option<String>,
+    success: bool,
+) -> Result<bool, String> {
+    let title = i18n::t(app, if success { "notifications.taskFinished" } else { "notifications.taskFailed" });
+    let body = i18n::t_with(app, "notifications.inProject", &[("status", &title), ("project", project_name)]);
+    let target = NotificationTarget {
+        window: window.to_string(),
+        session_id,
+    };
+    notify(app, &title, &body, target)
+}
+
+fn open(app: &AppHandle, target: NotificationTarget) {
+    let window = app.get_webview_window(&target.window).or_else(|| {
+        windows::show_main_window(app).ok()?;
+        app.get_webview_window(windows::MAIN_WINDOW)
+    });
+    if let Some(window) = window {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+        let _ = app.emit_to(window.label(), "notification-clicked", target);
+    }
+}
+
+#[tauri::command]
+pub fn send_task_notification(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_name: String,
+    session_id: Option<String>,
+    success: Option<bool>,
+) -> Result<bool, String> {
+    notify_task_finished(&app, window.label(), &project_name, session_id, success.unwrap_or(true))
+}
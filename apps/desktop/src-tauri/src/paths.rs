@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+// Claude Code's config directory, `~/.claude` unless CLAUDE_CONFIG_DIR is set
+pub fn claude_dir() -> Result<PathBuf, String> {
+    if let Some(dir) = std::env::var_os("CLAUDE_CONFIG_DIR").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+
+    home_dir()
+        .map(|home| home.join(".claude"))
+        .ok_or_else(|| "Could not determine home directory".to_string())
+}
+
+// Global CLI state (account info, MCP servers, per-project settings) lives
+// in `~/.claude.json`, next to rather than inside the config directory
+pub fn claude_state_file() -> Result<PathBuf, String> {
+    if let Some(dir) = std::env::var_os("CLAUDE_CONFIG_DIR").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir).join(".claude.json"));
+    }
+
+    home_dir()
+        .map(|home| home.join(".claude.json"))
+        .ok_or_else(|| "Could not determine home directory".to_string())
+}
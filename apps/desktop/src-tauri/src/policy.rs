@@ -0,0 +1,140 @@
+use std::path::{Component, Path, PathBuf};
+
+use sqlx::SqlitePool;
+use tauri::AppHandle;
+
+use crate::db;
+
+// What a file operation started on Claude's behalf may not do, as the
+// frontend sees it: `{ kind: "permission_denied", path, project_path }`
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PolicyError {
+    PermissionDenied { path: String, project_path: String },
+    Failed { message: String },
+}
+
+impl From<String> for PolicyError {
+    fn from(message: String) -> Self {
+        PolicyError::Failed { message }
+    }
+}
+
+// The folders operations in a project may touch. With none saved, that's
+// the project folder and everything in it.
+pub struct Allowlist {
+    project_path: String,
+    dirs: Vec<PathBuf>,
+}
+
+impl Allowlist {
+    pub fn allows(&self, path: &Path) -> bool {
+        resolve(path).is_some_and(|path| self.dirs.iter().any(|dir| path.starts_with(dir)))
+    }
+
+    pub fn check(&self, path: &Path) -> Result<(), PolicyError> {
+        if self.allows(path) {
+            return Ok(());
+        }
+        Err(PolicyError::PermissionDenied {
+            path: path.to_string_lossy().to_string(),
+            project_path: self.project_path.clone(),
+        })
+    }
+}
+
+// Where `path` really is, through symlinks and "..", even when it doesn't
+// exist yet: the deepest part that does is resolved and the rest added on
+pub fn resolve(path: &Path) -> Option<PathBuf> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(resolved) = std::fs::canonicalize(existing) {
+            let mut resolved = resolved;
+            for component in rest.iter().rev() {
+                match component {
+                    Component::Normal(name) => resolved.push(name),
+                    // ".." past a folder that doesn't exist can't be followed
+                    _ => return None,
+                }
+            }
+            return Some(resolved);
+        }
+        rest.push(existing.components().next_back()?);
+        existing = existing.parent()?;
+    }
+}
+
+async fn saved_dirs(pool: &SqlitePool, project_path: &str) -> Result<Vec<String>, String> {
+    let rows: Vec<(String,)> = sqlx::query_as("SELECT dir FROM project_allowed_dirs WHERE project_path = ? ORDER BY dir")
+        .bind(project_path)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to read allowed folders: {}", e))?;
+    Ok(rows.into_iter().map(|(dir,)| dir).collect())
+}
+
+pub async fn allowlist(app: &AppHandle, project_path: &str) -> Result<Allowlist, String> {
+    let root = std::fs::canonicalize(project_path).map_err(|e| format!("Failed to open {}: {}", project_path, e))?;
+    let pool = db::pool(app).await?;
+    let saved = saved_dirs(&pool, project_path).await?;
+    let dirs = if saved.is_empty() {
+        vec![root]
+    } else {
+        // Relative ones are inside the project
+        saved.iter().filter_map(|dir| resolve(&root.join(dir))).collect()
+    };
+    Ok(Allowlist {
+        project_path: project_path.to_string(),
+        dirs,
+    })
+}
+
+// Check one path against a project's allowlist
+pub async fn check(app: &AppHandle, project_path: &str, path: &Path) -> Result<(), PolicyError> {
+    allowlist(app, project_path).await?.check(path)
+}
+
+// The folders saved for a project, as given: relative to it or absolute.
+// Empty means the whole project.
+#[tauri::command]
+pub async fn get_project_allowlist(app: AppHandle, project_path: String) -> Result<Vec<String>, String> {
+    let pool = db::pool(&app).await?;
+    saved_dirs(&pool, &project_path).await
+}
+
+#[tauri::command]
+pub async fn set_project_allowlist(app: AppHandle, project_path: String, dirs: Vec<String>) -> Result<(), String> {
+    let root = Path::new(&project_path);
+    if !root.is_dir() {
+        return Err(format!("Project folder not found: {}", project_path));
+    }
+    let mut dirs: Vec<String> = dirs
+        .iter()
+        .map(|dir| dir.trim().trim_end_matches(['/', '\\']).to_string())
+        .filter(|dir| !dir.is_empty())
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    if let Some(dir) = dirs.iter().find(|dir| resolve(&root.join(dir)).is_none()) {
+        return Err(format!("Invalid folder: {}", dir));
+    }
+
+    let pool = db::pool(&app).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM project_allowed_dirs WHERE project_path = ?")
+        .bind(&project_path)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to save allowed folders: {}", e))?;
+    for dir in &dirs {
+        sqlx::query("INSERT INTO project_allowed_dirs (project_path, dir) VALUES (?, ?)")
+            .bind(&project_path)
+            .bind(dir)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to save allowed folders: {}", e))?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::process::{Command, Stdio};
+
+// Every process started under `pid`, found through `ps` since a shell's
+// jobs and a dev server's workers can be in other process groups
+#[cfg(not(windows))]
+fn descendants(pid: u32) -> Vec<u32> {
+    let Ok(output) = Command::new("ps").args(["-A", "-o", "pid=,ppid="]).stderr(Stdio::null()).output() else {
+        return Vec::new();
+    };
+    let processes: Vec<(u32, u32)> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            Some((columns.next()?.parse().ok()?, columns.next()?.parse().ok()?))
+        })
+        .collect();
+    let mut found = Vec::new();
+    let mut parents = vec![pid];
+    while let Some(parent) = parents.pop() {
+        for &(child, ppid) in &processes {
+            if ppid == parent && !found.contains(&child) {
+                found.push(child);
+                parents.push(child);
+            }
+        }
+    }
+    found
+}
+
+// Stop `pid` and everything it started. Without `force` they're asked to
+// exit (SIGTERM, or a close request on Windows) and may clean up first;
+// with it they're killed outright.
+#[cfg(not(windows))]
+pub fn kill_tree(pid: u32, force: bool) -> Result<(), String> {
+    let signal = if force { "-KILL" } else { "-TERM" };
+    let mut targets: Vec<String> = descendants(pid).iter().map(u32::to_string).collect();
+    targets.push(pid.to_string());
+    // Its process group too, when it leads one, for anything that was
+    // reparented away from it
+    targets.push(format!("-{}", pid));
+
+    // Some of them may be gone already, or it may not lead a group, so
+    // kill's status says nothing useful
+    Command::new("kill")
+        .arg(signal)
+        .arg("--")
+        .args(&targets)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| format!("Failed to stop process {}: {}", pid, e))?;
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn kill_tree(pid: u32, force: bool) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let pid_arg = pid.to_string();
+    let mut args = vec!["/T", "/PID", &pid_arg];
+    if force {
+        args.push("/F");
+    }
+    let output = Command::new("taskkill")
+        .args(&args)
+        .stdin(Stdio::null())
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Failed to stop process {}: {}", pid, e))?;
+    // 128: the process has already exited
+    if !output.status.success() && output.status.code() != Some(128) {
+        return Err(format!(
+            "Failed to stop process {}: {}",
+            pid,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use tauri::AppHandle;
+
+use crate::db;
+
+// A named Claude Code executable, e.g. a stable install and a local dev build
+#[derive(Clone, serde::Serialize)]
+pub struct ClaudeProfile {
+    pub id: String,
+    pub name: String,
+    pub executable_path: String,
+    pub env: HashMap<String, String>,
+    pub active: bool,
+    pub created_at: String,
+}
+
+type ProfileRow = (String, String, String, String, bool, String);
+
+fn from_row((id, name, executable_path, env, active, created_at): ProfileRow) -> ClaudeProfile {
+    ClaudeProfile {
+        id,
+        name,
+        executable_path,
+        env: serde_json::from_str(&env).unwrap_or_default(),
+        active,
+        created_at,
+    }
+}
+
+const SELECT_PROFILES: &str =
+    "SELECT id, name, executable_path, env, active, created_at FROM claude_profiles";
+
+#[tauri::command]
+pub async fn list_claude_profiles(app: AppHandle) -> Result<Vec<ClaudeProfile>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<ProfileRow> = sqlx::query_as(&format!("{} ORDER BY name", SELECT_PROFILES))
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to list profiles: {}", e))?;
+    Ok(rows.into_iter().map(from_row).collect())
+}
+
+#[tauri::command]
+pub async fn add_claude_profile(
+    app: AppHandle,
+    name: String,
+    executable_path: String,
+    env: Option<HashMap<String, String>>,
+) -> Result<ClaudeProfile, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Profile name is required".to_string());
+    }
+    if !std::path::Path::new(&executable_path).exists() {
+        return Err(format!("Executable does not exist: {}", executable_path));
+    }
+
+    let profile = ClaudeProfile {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        executable_path,
+        env: env.unwrap_or_default(),
+        active: false,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+    let env_json = serde_json::to_string(&profile.env).map_err(|e| e.to_string())?;
+
+    let pool = db::pool(&app).await?;
+    sqlx::query(
+        "INSERT INTO claude_profiles (id, name, executable_path, env, active, created_at)
+         VALUES (?, ?, ?, ?, 0, ?)",
+    )
+    .bind(&profile.id)
+    .bind(&profile.name)
+    .bind(&profile.executable_path)
+    .bind(env_json)
+    .bind(&profile.created_at)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("Failed to add profile: {}", e))?;
+
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn remove_claude_profile(app: AppHandle, id: String) -> Result<(), String> {
+    let pool = db::pool(&app).await?;
+    sqlx::query("DELETE FROM claude_profiles WHERE id = ?")
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to remove profile: {}", e))?;
+    Ok(())
+}
+
+// Make a profile the one used for sidecar launches. Passing no id
+// deactivates all profiles so the configured executable path applies again.
+#[tauri::command]
+pub async fn activate_claude_profile(app: AppHandle, id: Option<String>) -> Result<(), String> {
+    let pool = db::pool(&app).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    sqlx::query("UPDATE claude_profiles SET active = 0")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("Failed to update profiles: {}", e))?;
+
+    if let Some(id) = id {
+        let updated = sqlx::query("UPDATE claude_profiles SET active = 1 WHERE id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to activate profile: {}", e))?;
+        if updated.rows_affected() == 0 {
+            return Err(format!("Profile not found: {}", id));
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub async fn active_profile(app: &AppHandle) -> Result<Option<ClaudeProfile>, String> {
+    let pool = db::pool(app).await?;
+    let row: Option<ProfileRow> =
+        sqlx::query_as(&format!("{} WHERE active = 1 LIMIT 1", SELECT_PROFILES))
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| format!("Failed to read active profile: {}", e))?;
+    Ok(row.map(from_row))
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{Match, WalkBuilder};
+
+// Never worth showing, whatever the ignore files say
+pub const ALWAYS_EXCLUDED: &[&str] = &[".git", "node_modules"];
+
+fn is_excluded_name(name: &str) -> bool {
+    ALWAYS_EXCLUDED.contains(&name)
+}
+
+// A walk over a project that skips what git would and `ALWAYS_EXCLUDED`.
+// Dotfiles are kept, since `.env.example` and `.github` matter.
+pub fn walker(root: &Path) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(false)
+        .git_ignore(true)
+        .git_exclude(true)
+        .git_global(true)
+        // Projects that aren't repos yet still have a .gitignore
+        .require_git(false)
+        .filter_entry(|entry| !is_excluded_name(&entry.file_name().to_string_lossy()));
+    builder
+}
+
+// Paths relative to the project root with '/' separators, on every OS
+pub fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+// The same rules as `walker`, for checking one path at a time as changes
+// come in. Each directory's .gitignore is read the first time it's needed.
+pub struct IgnoreRules {
+    root: PathBuf,
+    // Directory to its .gitignore, or None when it has none
+    nested: HashMap<PathBuf, Option<Gitignore>>,
+    exclude: Gitignore,
+    global: Gitignore,
+}
+
+impl IgnoreRules {
+    pub fn new(root: &Path) -> Self {
+        let mut exclude = GitignoreBuilder::new(root);
+        let _ = exclude.add(root.join(".git").join("info").join("exclude"));
+        Self {
+            root: root.to_path_buf(),
+            nested: HashMap::new(),
+            exclude: exclude.build().unwrap_or_else(|_| Gitignore::empty()),
+            global: GitignoreBuilder::new(root).build_global().0,
+        }
+    }
+
+    // Forget a directory's rules after its .gitignore changed
+    pub fn reload(&mut self, gitignore: &Path) {
+        if let Some(dir) = gitignore.parent() {
+            self.nested.remove(dir);
+        }
+    }
+
+    fn nested(&mut self, dir: &Path) -> Option<&Gitignore> {
+        self.nested
+            .entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                let path = dir.join(".gitignore");
+                path.is_file().then(|| Gitignore::new(&path).0)
+            })
+            .as_ref()
+    }
+
+    pub fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return true;
+        };
+        if relative
+            .components()
+            .any(|component| is_excluded_name(&component.as_os_str().to_string_lossy()))
+        {
+            return true;
+        }
+
+        // The closest .gitignore with an opinion wins, as in git
+        let dirs: Vec<PathBuf> = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.root))
+            .map(Path::to_path_buf)
+            .collect();
+        for dir in dirs {
+            match self.nested(&dir).map(|rules| rules.matched_path_or_any_parents(path, is_dir)) {
+                Some(Match::Ignore(_)) => return true,
+                Some(Match::Whitelist(_)) => return false,
+                _ => {}
+            }
+        }
+        for rules in [&self.exclude, &self.global] {
+            match rules.matched_path_or_any_parents(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,483 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+// A file at the top of a project that says what it's written in, in order
+// of how much it says about the project as a whole
+const LANGUAGE_MARKERS: &[(&str, &str)] = &[
+    ("Cargo.toml", "rust"),
+    ("go.mod", "go"),
+    ("pyproject.toml", "python"),
+    ("requirements.txt", "python"),
+    ("setup.py", "python"),
+    ("Package.swift", "swift"),
+    ("pubspec.yaml", "dart"),
+    ("pom.xml", "java"),
+    ("build.gradle", "java"),
+    ("build.gradle.kts", "kotlin"),
+    ("Gemfile", "ruby"),
+    ("composer.json", "php"),
+    ("mix.exs", "elixir"),
+    ("deno.json", "deno"),
+    ("package.json", "node"),
+];
+
+// Lockfiles, checked before the package.json `packageManager` field
+const NODE_LOCKFILES: &[(&str, &str)] = &[
+    ("pnpm-lock.yaml", "pnpm"),
+    ("bun.lock", "bun"),
+    ("bun.lockb", "bun"),
+    ("yarn.lock", "yarn"),
+    ("package-lock.json", "npm"),
+];
+
+const PYTHON_MANAGERS: &[(&str, &str)] = &[
+    ("uv.lock", "uv"),
+    ("poetry.lock", "poetry"),
+    ("Pipfile", "pipenv"),
+    ("pdm.lock", "pdm"),
+    ("requirements.txt", "pip"),
+];
+
+// A dependency that names the framework, checked in order so the more
+// specific comes first (Next.js before React)
+const NODE_FRAMEWORKS: &[(&str, &str)] = &[
+    ("next", "next"),
+    ("nuxt", "nuxt"),
+    ("@remix-run/react", "remix"),
+    ("@sveltejs/kit", "sveltekit"),
+    ("astro", "astro"),
+    ("@angular/core", "angular"),
+    ("@nestjs/core", "nest"),
+    ("@tauri-apps/api", "tauri"),
+    ("electron", "electron"),
+    ("react", "react"),
+    ("vue", "vue"),
+    ("svelte", "svelte"),
+    ("solid-js", "solid"),
+    ("express", "express"),
+    ("fastify", "fastify"),
+    ("vite", "vite"),
+];
+
+const RUST_FRAMEWORKS: &[(&str, &str)] = &[
+    ("tauri", "tauri"),
+    ("axum", "axum"),
+    ("actix-web", "actix"),
+    ("rocket", "rocket"),
+    ("leptos", "leptos"),
+    ("bevy", "bevy"),
+];
+
+const PYTHON_FRAMEWORKS: &[(&str, &str)] = &[
+    ("django", "django"),
+    ("fastapi", "fastapi"),
+    ("flask", "flask"),
+    ("streamlit", "streamlit"),
+    ("pytest", "pytest"),
+];
+
+const GO_FRAMEWORKS: &[(&str, &str)] = &[
+    ("github.com/gin-gonic/gin", "gin"),
+    ("github.com/labstack/echo", "echo"),
+    ("github.com/gofiber/fiber", "fiber"),
+    ("github.com/spf13/cobra", "cobra"),
+];
+
+// Makefiles with more targets than this are summarised by their first ones
+const MAX_MAKE_TARGETS: usize = 30;
+
+#[derive(Clone, serde::Serialize)]
+pub struct ProjectScript {
+    pub name: String,
+    // What it runs, when that's known, e.g. "vite build"
+    pub command: Option<String>,
+    // How to run it from the project root, e.g. "pnpm run build"
+    pub run: String,
+    // "package.json", "Makefile", "cargo", "go" or "python"
+    pub source: &'static str,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct Workspace {
+    // "pnpm", "npm", "yarn", "cargo" or "go"
+    pub tool: String,
+    // Member folders relative to the project, '/'-separated
+    pub members: Vec<String>,
+}
+
+#[derive(Clone, Default, serde::Serialize)]
+pub struct ProjectInfo {
+    // Most telling first, e.g. ["rust", "node"] for a Tauri app
+    pub languages: Vec<String>,
+    pub frameworks: Vec<String>,
+    // One per ecosystem that has one, e.g. ["cargo", "pnpm"]
+    pub package_managers: Vec<String>,
+    pub scripts: Vec<ProjectScript>,
+    pub workspaces: Vec<Workspace>,
+    pub is_git_repo: bool,
+}
+
+pub fn languages(dir: &Path) -> Vec<String> {
+    let mut languages: Vec<String> = Vec::new();
+    for (marker, language) in LANGUAGE_MARKERS {
+        if dir.join(marker).is_file() && !languages.iter().any(|known| known == language) {
+            languages.push(language.to_string());
+        }
+    }
+    languages
+}
+
+fn push_unique(list: &mut Vec<String>, value: &str) {
+    if !list.iter().any(|known| known == value) {
+        list.push(value.to_string());
+    }
+}
+
+fn frameworks_in<'a>(known: &[(&str, &'a str)], has: impl Fn(&str) -> bool) -> Vec<&'a str> {
+    known.iter().filter(|(dependency, _)| has(dependency)).map(|(_, framework)| *framework).collect()
+}
+
+// Member patterns like "packages/*" as the folders they match. Only a
+// trailing `*` or `**` is expanded, which is what workspaces use.
+fn expand_members(dir: &Path, patterns: &[String]) -> Vec<String> {
+    let mut members = Vec::new();
+    for pattern in patterns {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        if pattern.starts_with('!') {
+            continue;
+        }
+        let base = pattern.trim_end_matches("/**").trim_end_matches("/*");
+        if base == pattern {
+            if dir.join(pattern).is_dir() {
+                members.push(pattern.to_string());
+            }
+            continue;
+        }
+        let Ok(entries) = std::fs::read_dir(dir.join(base)) else {
+            continue;
+        };
+        let mut found: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| format!("{}/{}", base, entry.file_name().to_string_lossy()))
+            .collect();
+        found.sort();
+        members.extend(found);
+    }
+    members.dedup();
+    members
+}
+
+fn string_list(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .map(|items| items.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn node(dir: &Path, info: &mut ProjectInfo) {
+    let Some(manifest) = std::fs::read(dir.join("package.json"))
+        .ok()
+        .and_then(|contents| serde_json::from_slice::<Value>(&contents).ok())
+    else {
+        return;
+    };
+
+    let manager = NODE_LOCKFILES
+        .iter()
+        .find(|(lockfile, _)| dir.join(lockfile).is_file())
+        .map(|(_, manager)| manager.to_string())
+        .or_else(|| {
+            // "pnpm@9.1.0"
+            manifest
+                .get("packageManager")
+                .and_then(Value::as_str)
+                .and_then(|field| field.split('@').next())
+                .filter(|name| !name.is_empty())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "npm".to_string());
+    push_unique(&mut info.package_managers, &manager);
+
+    let has = |dependency: &str| {
+        ["dependencies", "devDependencies", "peerDependencies"]
+            .iter()
+            .any(|section| manifest.get(section).and_then(|deps| deps.get(dependency)).is_some())
+    };
+    for framework in frameworks_in(NODE_FRAMEWORKS, has) {
+        push_unique(&mut info.frameworks, framework);
+    }
+
+    if let Some(scripts) = manifest.get("scripts").and_then(Value::as_object) {
+        for (name, command) in scripts {
+            info.scripts.push(ProjectScript {
+                name: name.clone(),
+                command: command.as_str().map(str::to_string),
+                run: format!("{} run {}", manager, name),
+                source: "package.json",
+            });
+        }
+    }
+
+    // npm and yarn list members in package.json, as an array or under
+    // `packages`; pnpm has its own file
+    let workspaces = manifest.get("workspaces");
+    let mut patterns = string_list(workspaces);
+    if patterns.is_empty() {
+        patterns = string_list(workspaces.and_then(|workspaces| workspaces.get("packages")));
+    }
+    let tool = if patterns.is_empty() {
+        patterns = pnpm_workspace(dir);
+        "pnpm".to_string()
+    } else {
+        manager
+    };
+    if !patterns.is_empty() {
+        info.workspaces.push(Workspace {
+            tool,
+            members: expand_members(dir, &patterns),
+        });
+    }
+}
+
+// The `packages:` list of pnpm-workspace.yaml, read by line since that's
+// all that's needed from it
+fn pnpm_workspace(dir: &Path) -> Vec<String> {
+    let Ok(contents) = std::fs::read_to_string(dir.join("pnpm-workspace.yaml")) else {
+        return Vec::new();
+    };
+    let mut patterns = Vec::new();
+    let mut in_packages = false;
+    for line in contents.lines() {
+        if !line.starts_with([' ', '\t', '-']) && !line.trim().is_empty() {
+            in_packages = line.trim_end() == "packages:";
+            continue;
+        }
+        if let Some(item) = line.trim().strip_prefix("- ").filter(|_| in_packages) {
+            patterns.push(item.trim().trim_matches(['"', '\'']).to_string());
+        }
+    }
+    patterns
+}
+
+fn read_toml(path: &Path) -> Option<toml::Table> {
+    std::fs::read_to_string(path).ok()?.parse().ok()
+}
+
+fn rust(dir: &Path, info: &mut ProjectInfo) {
+    let Some(manifest) = read_toml(&dir.join("Cargo.toml")) else {
+        return;
+    };
+    push_unique(&mut info.package_managers, "cargo");
+
+    let workspace = manifest.get("workspace").and_then(toml::Value::as_table);
+    let has = |dependency: &str| {
+        let in_table = |table: Option<&toml::Value>| table.and_then(|table| table.get(dependency)).is_some();
+        in_table(manifest.get("dependencies"))
+            || in_table(workspace.and_then(|workspace| workspace.get("dependencies")))
+    };
+    for framework in frameworks_in(RUST_FRAMEWORKS, has) {
+        push_unique(&mut info.frameworks, framework);
+    }
+
+    if let Some(workspace) = workspace {
+        let patterns: Vec<String> = workspace
+            .get("members")
+            .and_then(toml::Value::as_array)
+            .map(|members| members.iter().filter_map(toml::Value::as_str).map(str::to_string).collect())
+            .unwrap_or_default();
+        info.workspaces.push(Workspace {
+            tool: "cargo".to_string(),
+            members: expand_members(dir, &patterns),
+        });
+    }
+
+    let mut commands = vec!["build", "test", "clippy"];
+    if manifest.contains_key("package") && dir.join("src").join("main.rs").is_file() {
+        commands.insert(0, "run");
+    }
+    for command in commands {
+        info.scripts.push(ProjectScript {
+            name: command.to_string(),
+            command: None,
+            run: format!("cargo {}", command),
+            source: "cargo",
+        });
+    }
+}
+
+fn go(dir: &Path, info: &mut ProjectInfo) {
+    let Ok(module) = std::fs::read_to_string(dir.join("go.mod")) else {
+        return;
+    };
+    push_unique(&mut info.package_managers, "go");
+    for framework in frameworks_in(GO_FRAMEWORKS, |dependency| module.contains(dependency)) {
+        push_unique(&mut info.frameworks, framework);
+    }
+    for command in ["build", "test", "vet"] {
+        info.scripts.push(ProjectScript {
+            name: command.to_string(),
+            command: None,
+            run: format!("go {} ./...", command),
+            source: "go",
+        });
+    }
+
+    // go.work lists modules in a `use` line or block
+    if let Ok(work) = std::fs::read_to_string(dir.join("go.work")) {
+        let mut patterns = Vec::new();
+        let mut in_block = false;
+        for line in work.lines().map(str::trim) {
+            if let Some(rest) = line.strip_prefix("use") {
+                let rest = rest.trim();
+                if rest == "(" {
+                    in_block = true;
+                } else if !rest.is_empty() {
+                    patterns.push(rest.to_string());
+                }
+            } else if in_block && line == ")" {
+                in_block = false;
+            } else if in_block && !line.is_empty() && !line.starts_with("//") {
+                patterns.push(line.to_string());
+            }
+        }
+        info.workspaces.push(Workspace {
+            tool: "go".to_string(),
+            members: expand_members(dir, &patterns),
+        });
+    }
+}
+
+fn python(dir: &Path, info: &mut ProjectInfo) {
+    let pyproject = read_toml(&dir.join("pyproject.toml"));
+    let tool = pyproject.as_ref().and_then(|pyproject| pyproject.get("tool"));
+    let manager = PYTHON_MANAGERS
+        .iter()
+        .find(|(marker, _)| dir.join(marker).is_file())
+        .map(|(_, manager)| *manager)
+        .or_else(|| {
+            ["poetry", "uv", "pdm", "hatch"]
+                .into_iter()
+                .find(|name| tool.and_then(|tool| tool.get(name)).is_some())
+        })
+        .unwrap_or("pip");
+    push_unique(&mut info.package_managers, manager);
+
+    // Dependencies as written, e.g. "fastapi>=0.110", from PEP 621 and
+    // Poetry tables and requirements.txt
+    let mut dependencies: Vec<String> = Vec::new();
+    if let Some(project) = pyproject.as_ref().and_then(|pyproject| pyproject.get("project")) {
+        let listed = project.get("dependencies").and_then(toml::Value::as_array);
+        dependencies.extend(listed.into_iter().flatten().filter_map(toml::Value::as_str).map(str::to_string));
+    }
+    if let Some(poetry) = tool.and_then(|tool| tool.get("poetry")) {
+        let listed = poetry.get("dependencies").and_then(toml::Value::as_table);
+        dependencies.extend(listed.into_iter().flat_map(|table| table.keys().cloned()));
+    }
+    if let Ok(requirements) = std::fs::read_to_string(dir.join("requirements.txt")) {
+        dependencies.extend(requirements.lines().map(str::to_string));
+    }
+    let names: Vec<String> = dependencies
+        .iter()
+        .map(|dependency| {
+            dependency
+                .trim()
+                .split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.'))
+                .next()
+                .unwrap_or_default()
+                .to_lowercase()
+        })
+        .collect();
+    let has = |dependency: &str| names.iter().any(|name| name == dependency);
+    for framework in frameworks_in(PYTHON_FRAMEWORKS, has) {
+        push_unique(&mut info.frameworks, framework);
+    }
+
+    let runner = match manager {
+        "uv" | "poetry" | "pdm" | "hatch" | "pipenv" => format!("{} run ", manager),
+        _ => String::new(),
+    };
+    if has("pytest") || dir.join("tests").is_dir() {
+        info.scripts.push(ProjectScript {
+            name: "test".to_string(),
+            command: None,
+            run: format!("{}pytest", runner),
+            source: "python",
+        });
+    }
+    if has("django") && dir.join("manage.py").is_file() {
+        info.scripts.push(ProjectScript {
+            name: "runserver".to_string(),
+            command: None,
+            run: format!("{}python manage.py runserver", runner),
+            source: "python",
+        });
+    }
+}
+
+fn makefile(dir: &Path, info: &mut ProjectInfo) {
+    let Some(contents) = ["Makefile", "makefile", "GNUmakefile"]
+        .iter()
+        .find_map(|name| std::fs::read_to_string(dir.join(name)).ok())
+    else {
+        return;
+    };
+    let targets = contents.lines().filter_map(|line| {
+        let (target, rest) = line.split_once(':')?;
+        let valid = !target.is_empty()
+            && !target.starts_with('.')
+            && target.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+        // `VAR := value` is an assignment, not a rule
+        (valid && !rest.starts_with('=')).then(|| target.to_string())
+    });
+    let mut seen: Vec<String> = Vec::new();
+    for target in targets {
+        if seen.len() == MAX_MAKE_TARGETS {
+            break;
+        }
+        if seen.contains(&target) {
+            continue;
+        }
+        seen.push(target.clone());
+        info.scripts.push(ProjectScript {
+            run: format!("make {}", target),
+            name: target,
+            command: None,
+            source: "Makefile",
+        });
+    }
+}
+
+fn detect(dir: &Path) -> ProjectInfo {
+    let mut info = ProjectInfo {
+        languages: languages(dir),
+        is_git_repo: dir.join(".git").exists(),
+        ..Default::default()
+    };
+    rust(dir, &mut info);
+    go(dir, &mut info);
+    if info.languages.iter().any(|language| language == "python") {
+        python(dir, &mut info);
+    }
+    node(dir, &mut info);
+    makefile(dir, &mut info);
+    info
+}
+
+pub fn scripts(dir: &Path) -> Vec<ProjectScript> {
+    detect(dir).scripts
+}
+
+// What the project at `path` is built with, and the commands it can run,
+// for suggesting context and commands for a new session
+#[tauri::command]
+pub async fn detect_project_info(path: String) -> Result<ProjectInfo, String> {
+    let dir = PathBuf::from(&path);
+    if !dir.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    tauri::async_runtime::spawn_blocking(move || detect(&dir))
+        .await
+        .map_err(|e| format!("Failed to inspect {}: {}", path, e))
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkState;
+use regex::{Regex, RegexBuilder};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::project_files;
+
+const DEFAULT_MAX_RESULTS: usize = 2000;
+// Bigger files are almost always generated or data, not code to search
+const MAX_FILE_BYTES: u64 = 8 * 1024 * 1024;
+// A NUL in the first few KB marks a file as binary, as git does
+const BINARY_SNIFF_BYTES: usize = 8192;
+// Minified files can have one enormous line
+const MAX_LINE_CHARS: usize = 1000;
+
+// Searches in progress, so they can be cancelled by id
+#[derive(Default)]
+pub struct ProjectSearches(Mutex<HashMap<String, Arc<AtomicBool>>>);
+
+#[derive(Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct SearchOptions {
+    // Chosen by the caller, and passed to `cancel_project_search`
+    pub search_id: String,
+    // Treat the query as a regular expression rather than literal text
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    // Lines of context around each match
+    pub context_lines: usize,
+    pub max_results: Option<usize>,
+    // Globs relative to the project, e.g. "*.rs" or "src/**"
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct SearchMatch {
+    // Relative to the project, '/'-separated
+    pub path: String,
+    // 1-based
+    pub line_number: usize,
+    pub line: String,
+    // Character offsets of each match within `line`, end exclusive
+    pub ranges: Vec<(usize, usize)>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct SearchResults {
+    search_id: String,
+    matches: Vec<SearchMatch>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct SearchSummary {
+    pub search_id: String,
+    pub matches: usize,
+    pub files_searched: usize,
+    pub files_matched: usize,
+    // Stopped at `max_results`
+    pub truncated: bool,
+    pub cancelled: bool,
+    pub duration_ms: u64,
+}
+
+fn build_regex(query: &str, options: &SearchOptions) -> Result<Regex, String> {
+    let pattern = if options.regex { query.to_string() } else { regex::escape(query) };
+    let pattern = if options.whole_word { format!(r"\b(?:{})\b", pattern) } else { pattern };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
+
+fn truncate_line(line: &str) -> String {
+    match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((index, _)) => line[..index].to_string(),
+        None => line.to_string(),
+    }
+}
+
+fn read_text(path: &Path) -> Option<String> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if metadata.len() > MAX_FILE_BYTES {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn search_file(regex: &Regex, relative: &str, text: &str, context_lines: usize) -> Vec<SearchMatch> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut matches = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let ranges: Vec<(usize, usize)> = regex
+            .find_iter(line)
+            .map(|found| {
+                let start = line[..found.start()].chars().count();
+                (start, start + found.as_str().chars().count())
+            })
+            .filter(|(start, _)| *start < MAX_LINE_CHARS)
+            .map(|(start, end)| (start, end.min(MAX_LINE_CHARS)))
+            .collect();
+        if ranges.is_empty() {
+            continue;
+        }
+        let context = |lines: &[&str]| lines.iter().map(|line| truncate_line(line)).collect();
+        matches.push(SearchMatch {
+            path: relative.to_string(),
+            line_number: index + 1,
+            line: truncate_line(line),
+            ranges,
+            before: context(&lines[index.saturating_sub(context_lines)..index]),
+            after: context(&lines[(index + 1).min(lines.len())..(index + 1 + context_lines).min(lines.len())]),
+        });
+    }
+    matches
+}
+
+fn search(
+    app: &AppHandle,
+    root: &Path,
+    regex: &Regex,
+    options: &SearchOptions,
+    cancelled: &AtomicBool,
+) -> Result<SearchSummary, String> {
+    let started = Instant::now();
+    let max_results = options.max_results.unwrap_or(DEFAULT_MAX_RESULTS);
+
+    let mut overrides = OverrideBuilder::new(root);
+    for glob in &options.include {
+        overrides.add(glob).map_err(|e| format!("Invalid include pattern {}: {}", glob, e))?;
+    }
+    for glob in &options.exclude {
+        overrides
+            .add(&format!("!{}", glob))
+            .map_err(|e| format!("Invalid exclude pattern {}: {}", glob, e))?;
+    }
+    let overrides = overrides.build().map_err(|e| format!("Invalid file patterns: {}", e))?;
+
+    let found = AtomicUsize::new(0);
+    let files_searched = AtomicUsize::new(0);
+    let files_matched = AtomicUsize::new(0);
+    let truncated = AtomicBool::new(false);
+
+    project_files::walker(root).overrides(overrides).build_parallel().run(|| {
+        Box::new(|entry| {
+            if cancelled.load(Ordering::Relaxed) || truncated.load(Ordering::Relaxed) {
+                return WalkState::Quit;
+            }
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
+            if !entry.file_type().is_some_and(|kind| kind.is_file()) {
+                return WalkState::Continue;
+            }
+            let Some(text) = read_text(entry.path()) else {
+                return WalkState::Continue;
+            };
+            files_searched.fetch_add(1, Ordering::Relaxed);
+
+            let relative = project_files::relative_path(root, entry.path()).unwrap_or_default();
+            let mut matches = search_file(regex, &relative, &text, options.context_lines);
+            if matches.is_empty() {
+                return WalkState::Continue;
+            }
+            // Claim room under the limit before emitting, so concurrent
+            // files can't overshoot it
+            let before = found.fetch_add(matches.len(), Ordering::Relaxed);
+            if before >= max_results {
+                truncated.store(true, Ordering::Relaxed);
+                return WalkState::Quit;
+            }
+            if before + matches.len() > max_results {
+                matches.truncate(max_results - before);
+                truncated.store(true, Ordering::Relaxed);
+            }
+            files_matched.fetch_add(1, Ordering::Relaxed);
+            let _ = app.emit(
+                "project-search-results",
+                SearchResults {
+                    search_id: options.search_id.clone(),
+                    matches,
+                },
+            );
+            WalkState::Continue
+        })
+    });
+
+    Ok(SearchSummary {
+        search_id: options.search_id.clone(),
+        matches: found.load(Ordering::Relaxed).min(max_results),
+        files_searched: files_searched.load(Ordering::Relaxed),
+        files_matched: files_matched.load(Ordering::Relaxed),
+        truncated: truncated.load(Ordering::Relaxed),
+        cancelled: cancelled.load(Ordering::Relaxed),
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+// Search every file in the project that git wouldn't ignore, across all
+// cores. Matches are emitted file by file as `project-search-results` while
+// the search runs; the summary comes back once it's finished, cancelled or
+// reached `max_results`.
+#[tauri::command]
+pub async fn search_in_project(
+    app: AppHandle,
+    path: String,
+    query: String,
+    options: Option<SearchOptions>,
+) -> Result<SearchSummary, String> {
+    let options = options.unwrap_or_default();
+    if query.is_empty() {
+        return Err("Search query is empty".to_string());
+    }
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    let regex = build_regex(&query, &options)?;
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let searches = app.state::<ProjectSearches>();
+    searches
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(options.search_id.clone(), cancelled.clone());
+
+    let handle = app.clone();
+    let search_id = options.search_id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || search(&handle, &root, &regex, &options, &cancelled))
+        .await
+        .map_err(|e| format!("Search task failed: {}", e));
+
+    if let Ok(mut searches) = searches.0.lock() {
+        searches.remove(&search_id);
+    }
+    result?
+}
+
+#[tauri::command]
+pub fn cancel_project_search(app: AppHandle, search_id: String) -> Result<(), String> {
+    let searches = app.state::<ProjectSearches>();
+    if let Some(cancelled) = searches.0.lock().map_err(|e| e.to_string())?.get(&search_id) {
+        cancelled.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::project_files;
+
+// Past this the listing stops and says so, to keep the payload sane
+const MAX_ENTRIES: usize = 100_000;
+
+#[derive(Clone, serde::Serialize)]
+pub struct TreeNode {
+    pub name: String,
+    // Relative to the listed root, '/'-separated; empty for the root
+    pub path: String,
+    pub is_dir: bool,
+    // Bytes, for files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    // Milliseconds since the epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<i64>,
+    // Directories below `max_depth` have none, so the UI can list them on
+    // expand
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<TreeNode>>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ProjectTree {
+    pub root: TreeNode,
+    pub entries: usize,
+    pub truncated: bool,
+}
+
+fn modified_ms(metadata: &std::fs::Metadata) -> Option<i64> {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+}
+
+fn node(root: &Path, path: &Path, metadata: Option<&std::fs::Metadata>, expanded: bool) -> TreeNode {
+    let is_dir = metadata.is_some_and(|metadata| metadata.is_dir());
+    TreeNode {
+        name: path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+        path: project_files::relative_path(root, path).unwrap_or_default(),
+        is_dir,
+        size: metadata.filter(|_| !is_dir).map(|metadata| metadata.len()),
+        modified: metadata.and_then(modified_ms),
+        children: (is_dir && expanded).then(Vec::new),
+    }
+}
+
+// Directories first, then by name
+fn sort_children(children: &mut [TreeNode]) {
+    children.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+}
+
+type Children = HashMap<PathBuf, Vec<(PathBuf, TreeNode)>>;
+
+fn assemble(path: &Path, node: &mut TreeNode, by_parent: &mut Children) {
+    let Some(children) = node.children.as_mut() else {
+        return;
+    };
+    for (child_path, mut child) in by_parent.remove(path).unwrap_or_default() {
+        assemble(&child_path, &mut child, by_parent);
+        children.push(child);
+    }
+    sort_children(children);
+}
+
+fn build(root: &Path, max_depth: Option<usize>) -> Result<ProjectTree, String> {
+    let mut walker = project_files::walker(root);
+    walker.max_depth(max_depth);
+
+    // Nodes are grouped by parent directory, then attached from the root down
+    let mut by_parent: Children = HashMap::new();
+    let mut entries = 0;
+    let mut truncated = false;
+    for entry in walker.build().filter_map(Result::ok) {
+        if entry.depth() == 0 {
+            continue;
+        }
+        if entries == MAX_ENTRIES {
+            truncated = true;
+            break;
+        }
+        entries += 1;
+        let path = entry.path().to_path_buf();
+        let expanded = max_depth.is_none_or(|max_depth| entry.depth() < max_depth);
+        let node = node(root, &path, entry.metadata().ok().as_ref(), expanded);
+        let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        by_parent.entry(parent).or_default().push((path, node));
+    }
+
+    let metadata = std::fs::metadata(root).map_err(|e| format!("Failed to read {}: {}", root.display(), e))?;
+    let mut tree = node(root, root, Some(&metadata), true);
+    assemble(root, &mut tree, &mut by_parent);
+    Ok(ProjectTree {
+        root: tree,
+        entries,
+        truncated,
+    })
+}
+
+// The project's files and folders as a tree, skipping .git, node_modules
+// and anything gitignored. `max_depth` 1 lists only the top level; without
+// it the whole project is listed.
+#[tauri::command]
+pub async fn list_project_tree(path: String, max_depth: Option<usize>) -> Result<ProjectTree, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    tauri::async_runtime::spawn_blocking(move || build(&root, max_depth))
+        .await
+        .map_err(|e| format!("Failed to list {}: {}", path, e))?
+}
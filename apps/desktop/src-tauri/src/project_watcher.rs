@@ -0,0 +1,221 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::project_files::{self, IgnoreRules};
+
+// Long enough that an install or a branch switch arrives as a few batches
+// rather than thousands of events
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(500);
+
+// Every file in a watched project that isn't ignored, kept current by the
+// watcher. Paths are relative to the root, '/'-separated.
+pub struct ProjectIndex {
+    pub root: PathBuf,
+    pub files: HashSet<String>,
+}
+
+struct WatchedProject {
+    _debouncer: Debouncer<RecommendedWatcher>,
+    index: Arc<Mutex<ProjectIndex>>,
+    // How many times each window asked for it; it's dropped once none do
+    holders: HashMap<String, usize>,
+}
+
+#[derive(Default)]
+pub struct ProjectWatchers(Mutex<HashMap<PathBuf, WatchedProject>>);
+
+#[derive(Clone, Default, serde::Serialize)]
+struct ProjectFilesChanged {
+    project: String,
+    created: Vec<String>,
+    modified: Vec<String>,
+    deleted: Vec<String>,
+}
+
+// The index of a project being watched, for lookups that shouldn't walk
+// the disk
+pub fn index(app: &AppHandle, root: &Path) -> Option<Arc<Mutex<ProjectIndex>>> {
+    let root = std::fs::canonicalize(root).ok()?;
+    let watchers = app.state::<ProjectWatchers>();
+    let watchers = watchers.0.lock().ok()?;
+    watchers.get(&root).map(|project| project.index.clone())
+}
+
+fn scan(root: &Path, from: &Path) -> Vec<String> {
+    project_files::walker(from)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|kind| kind.is_file()))
+        .filter_map(|entry| project_files::relative_path(root, entry.path()))
+        .collect()
+}
+
+// Sort a batch of raw paths into what was created, changed and removed,
+// updating the index to match. A path that's neither on disk nor in the
+// index was a temp file that came and went.
+fn classify(index: &mut ProjectIndex, rules: &mut IgnoreRules, paths: BTreeSet<PathBuf>) -> ProjectFilesChanged {
+    let root = index.root.clone();
+    let mut changes = ProjectFilesChanged {
+        project: root.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let absolute = |relative: &str| root.join(relative).to_string_lossy().to_string();
+
+    for path in paths.iter().filter(|path| path.file_name().is_some_and(|name| name == ".gitignore")) {
+        rules.reload(path);
+    }
+
+    for path in paths {
+        let Some(relative) = project_files::relative_path(&root, &path) else {
+            continue;
+        };
+        let metadata = std::fs::symlink_metadata(&path).ok();
+        let is_dir = metadata.as_ref().is_some_and(|metadata| metadata.is_dir());
+        // Checked for deletions too, so removing an ignored tree is cheap
+        if rules.is_ignored(&path, is_dir) {
+            continue;
+        }
+
+        match metadata {
+            // A new or moved-in directory arrives as one event, so what's
+            // inside it is found by walking it
+            Some(_) if is_dir => {
+                for file in scan(&root, &path) {
+                    if index.files.insert(file.clone()) {
+                        changes.created.push(absolute(&file));
+                    }
+                }
+            }
+            Some(_) => {
+                if index.files.insert(relative.clone()) {
+                    changes.created.push(path.to_string_lossy().to_string());
+                } else {
+                    changes.modified.push(path.to_string_lossy().to_string());
+                }
+            }
+            None => {
+                if index.files.remove(&relative) {
+                    changes.deleted.push(path.to_string_lossy().to_string());
+                    continue;
+                }
+                // A removed directory takes everything under it
+                let prefix = format!("{}/", relative);
+                let removed: Vec<String> = index.files.iter().filter(|file| file.starts_with(&prefix)).cloned().collect();
+                for file in removed {
+                    index.files.remove(&file);
+                    changes.deleted.push(absolute(&file));
+                }
+            }
+        }
+    }
+    changes
+}
+
+// Count another hold on a watched project for `window`
+fn hold(app: &AppHandle, root: &Path, window: &str) -> Option<Arc<Mutex<ProjectIndex>>> {
+    let watchers = app.state::<ProjectWatchers>();
+    let mut watchers = watchers.0.lock().ok()?;
+    let project = watchers.get_mut(root)?;
+    *project.holders.entry(window.to_string()).or_default() += 1;
+    Some(project.index.clone())
+}
+
+// Start watching a project for `window` unless it already is, and return
+// its index. Each call is matched by an `unwatch`, or goes when the window
+// does.
+pub async fn watch(app: &AppHandle, path: &str, window: &str) -> Result<Arc<Mutex<ProjectIndex>>, String> {
+    let root = std::fs::canonicalize(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    if let Some(index) = hold(app, &root, window) {
+        return Ok(index);
+    }
+
+    let scan_root = root.clone();
+    let files = tauri::async_runtime::spawn_blocking(move || scan(&scan_root, &scan_root))
+        .await
+        .map_err(|e| format!("Failed to list {}: {}", path, e))?;
+    let index = Arc::new(Mutex::new(ProjectIndex {
+        root: root.clone(),
+        files: files.into_iter().collect(),
+    }));
+
+    let handle = app.clone();
+    let watched_index = index.clone();
+    let mut rules = IgnoreRules::new(&root);
+    let mut debouncer = new_debouncer(DEBOUNCE_INTERVAL, move |result: DebounceEventResult| {
+        let Ok(events) = result else { return };
+        let paths: BTreeSet<PathBuf> = events.into_iter().map(|event| event.path).collect();
+        let Ok(mut index) = watched_index.lock() else { return };
+        let changes = classify(&mut index, &mut rules, paths);
+        if !changes.created.is_empty() || !changes.modified.is_empty() || !changes.deleted.is_empty() {
+            let _ = handle.emit("project-files-changed", changes);
+        }
+    })
+    .map_err(|e| format!("Failed to create watcher: {}", e))?;
+    debouncer
+        .watcher()
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", root.display(), e))?;
+
+    let watchers = app.state::<ProjectWatchers>();
+    let mut watchers = watchers.0.lock().map_err(|e| e.to_string())?;
+    // Another call may have won the race to watch it
+    let project = watchers.entry(root).or_insert(WatchedProject {
+        _debouncer: debouncer,
+        index,
+        holders: HashMap::new(),
+    });
+    *project.holders.entry(window.to_string()).or_default() += 1;
+    Ok(project.index.clone())
+}
+
+fn unwatch(app: &AppHandle, root: &Path, window: &str) -> Result<(), String> {
+    let watchers = app.state::<ProjectWatchers>();
+    let mut watchers = watchers.0.lock().map_err(|e| e.to_string())?;
+    let Some(project) = watchers.get_mut(root) else {
+        return Ok(());
+    };
+    if let Some(count) = project.holders.get_mut(window) {
+        *count -= 1;
+        if *count == 0 {
+            project.holders.remove(window);
+        }
+    }
+    if project.holders.is_empty() {
+        watchers.remove(root);
+    }
+    Ok(())
+}
+
+// A closed window lets go of everything it watched
+pub fn on_window_destroyed(app: &AppHandle, window: &str) {
+    let watchers = app.state::<ProjectWatchers>();
+    let Ok(mut watchers) = watchers.0.lock() else { return };
+    watchers.retain(|_, project| {
+        project.holders.remove(window);
+        !project.holders.is_empty()
+    });
+}
+
+// Watch a project recursively and emit `project-files-changed` with the
+// created, modified and deleted paths each time a burst of changes settles.
+// Ignored files are left out. A project watched more than once, from any
+// windows, has one watcher, which stops once every watch is undone.
+#[tauri::command]
+pub async fn watch_project(app: AppHandle, window: WebviewWindow, path: String) -> Result<(), String> {
+    watch(&app, &path, window.label()).await.map(|_| ())
+}
+
+#[tauri::command]
+pub fn unwatch_project(app: AppHandle, window: WebviewWindow, path: String) -> Result<(), String> {
+    let root = std::fs::canonicalize(&path).unwrap_or_else(|_| PathBuf::from(&path));
+    unwatch(&app, &root, window.label())
+}
@@ -0,0 +1,291 @@
+use std::path::{Path, PathBuf};
+
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_fs::FsExt;
+
+use crate::{audit, db, project_info, scopes};
+
+// Anything that changes the list; the menu, tray and dock rebuild on these
+pub const CHANGE_EVENTS: &[&str] = &["project-opened", "projects-changed", "recent-projects-cleared"];
+
+#[derive(Clone, serde::Serialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    // Its main language when it was registered, e.g. "rust"
+    pub kind: Option<String>,
+    pub pinned: bool,
+    pub created_at: String,
+    pub last_opened_at: Option<String>,
+}
+
+type ProjectRow = (String, String, String, Option<String>, bool, String, Option<String>);
+
+fn from_row((id, name, path, kind, pinned, created_at, last_opened_at): ProjectRow) -> Project {
+    Project {
+        id,
+        name,
+        path,
+        kind,
+        pinned,
+        created_at,
+        last_opened_at,
+    }
+}
+
+const SELECT_PROJECTS: &str = "SELECT id, name, path, kind, pinned, created_at, last_opened_at FROM projects";
+
+// Folders whose whole tree is the system's, never a project
+#[cfg(not(windows))]
+const SYSTEM_TREES: &[&str] = &[
+    "/bin", "/boot", "/dev", "/etc", "/lib", "/lib64", "/proc", "/sbin", "/sys", "/usr", "/System", "/Library",
+    "/private/etc", "/private/var",
+];
+#[cfg(windows)]
+const SYSTEM_TREES: &[&str] = &[
+    r"C:\Windows",
+    r"C:\Program Files",
+    r"C:\Program Files (x86)",
+    r"C:\ProgramData",
+];
+// Holding projects, but not one themselves
+#[cfg(not(windows))]
+const SYSTEM_FOLDERS: &[&str] = &["/opt", "/var", "/tmp", "/Applications", "/Volumes", "/mnt", "/media"];
+#[cfg(windows)]
+const SYSTEM_FOLDERS: &[&str] = &[];
+
+// The table comes from migrations/0002_projects.sql
+pub async fn projects_pool(app: &AppHandle) -> Result<SqlitePool, String> {
+    db::pool(app).await
+}
+
+// The path a project is registered under: absolute, with symlinks and
+// `..` resolved, so the same folder is only ever one project. Folders that
+// would open far more than a project to the webview are refused: the
+// filesystem root, the home directory and anything above it, and the
+// system's own folders.
+pub fn project_root(app: &AppHandle, path: &str) -> Result<PathBuf, String> {
+    if path.trim().is_empty() {
+        return Err("A project path is required".to_string());
+    }
+    let root = dunce::canonicalize(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    let refused = || Err(format!("{} can't be opened as a project", root.display()));
+    if root.parent().is_none() {
+        return refused();
+    }
+    if let Some(home) = app.path().home_dir().ok().and_then(|home| dunce::canonicalize(home).ok()) {
+        if home.starts_with(&root) {
+            return refused();
+        }
+    }
+    let system = |folder: &&str| dunce::canonicalize(folder).unwrap_or_else(|_| PathBuf::from(folder));
+    if SYSTEM_TREES.iter().map(system).any(|tree| root.starts_with(tree))
+        || SYSTEM_FOLDERS.iter().map(system).any(|folder| root == folder)
+    {
+        return refused();
+    }
+    Ok(root)
+}
+
+async fn find(pool: &SqlitePool, path: &str) -> Result<Option<Project>, String> {
+    let row: Option<ProjectRow> = sqlx::query_as(&format!("{} WHERE path = ?", SELECT_PROJECTS))
+        .bind(path)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to read project: {}", e))?;
+    Ok(row.map(from_row))
+}
+
+// Pinned projects, then those opened since the list was last cleared,
+// newest first
+pub async fn recent_projects(app: &AppHandle, limit: i64) -> Result<Vec<Project>, String> {
+    let pool = projects_pool(app).await?;
+    let rows: Vec<ProjectRow> = sqlx::query_as(&format!(
+        "{} WHERE pinned = 1 OR last_opened_at IS NOT NULL ORDER BY pinned DESC, last_opened_at DESC LIMIT ?",
+        SELECT_PROJECTS
+    ))
+    .bind(limit)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to list recent projects: {}", e))?;
+    Ok(rows.into_iter().map(from_row).collect())
+}
+
+fn default_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+// Let the webview read and write inside the project. The persisted-scope
+// plugin remembers it across launches.
+fn allow_in_scope(app: &AppHandle, path: &str) {
+    if let Err(e) = app.fs_scope().allow_directory(path, true) {
+        eprintln!("Failed to allow {}: {}", path, e);
+    }
+    scopes::unrevoke(app, path);
+}
+
+// Projects registered before they were added to the fs scope here, or
+// whose scope was lost, are allowed again at startup. Ones registered
+// before folders were checked are skipped if they can't be projects now.
+pub async fn restore_scope(app: &AppHandle) -> Result<(), String> {
+    let pool = projects_pool(app).await?;
+    let paths: Vec<(String,)> = sqlx::query_as("SELECT path FROM projects")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to list projects: {}", e))?;
+    for (path,) in paths {
+        match project_root(app, &path) {
+            Ok(_) => allow_in_scope(app, &path),
+            Err(e) => eprintln!("Not restoring access to {}: {}", path, e),
+        }
+    }
+    Ok(())
+}
+
+// The project already registered for `path`, if any, under any spelling of it
+pub async fn registered(app: &AppHandle, path: &str) -> Result<Option<Project>, String> {
+    let Ok(root) = project_root(app, path) else {
+        return Ok(None);
+    };
+    let pool = projects_pool(app).await?;
+    find(&pool, &root.to_string_lossy()).await
+}
+
+async fn register(app: &AppHandle, path: &str, name: Option<String>) -> Result<Project, String> {
+    let root = project_root(app, path)?;
+    let (dir, path) = (root.as_path(), root.to_string_lossy().to_string());
+    let path = path.as_str();
+    let pool = projects_pool(app).await?;
+    let name = name.filter(|name| !name.trim().is_empty());
+
+    // A project registered again keeps its name unless given a new one
+    sqlx::query(
+        "INSERT INTO projects (id, name, path, kind, created_at) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT (path) DO UPDATE SET kind = excluded.kind, name = COALESCE(?, name)",
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(name.clone().unwrap_or_else(|| default_name(path)))
+    .bind(path)
+    .bind(project_info::languages(dir).into_iter().next())
+    .bind(chrono::Utc::now().to_rfc3339())
+    .bind(name)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("Failed to save project: {}", e))?;
+
+    allow_in_scope(app, path);
+    audit::record(app, None, "scope", "grant", path);
+    find(&pool, path)
+        .await?
+        .ok_or_else(|| format!("Failed to save project {}", path))
+}
+
+// Add a folder as a project, or refresh one already added. Fires
+// `projects-changed`.
+#[tauri::command]
+pub async fn register_project(app: AppHandle, path: String, name: Option<String>) -> Result<Project, String> {
+    let project = register(&app, &path, name).await?;
+    let _ = app.emit("projects-changed", ());
+    Ok(project)
+}
+
+// Record that a project was opened, registering it on first use. Fires
+// `project-opened`, which also rebuilds the Open Recent menu.
+#[tauri::command]
+pub async fn record_project_opened(app: AppHandle, path: String, name: Option<String>) -> Result<Project, String> {
+    let registered = register(&app, &path, name).await?;
+    let pool = projects_pool(&app).await?;
+    sqlx::query("UPDATE projects SET last_opened_at = ? WHERE id = ?")
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(&registered.id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to save project: {}", e))?;
+    let project = find(&pool, &registered.path)
+        .await?
+        .ok_or_else(|| format!("Failed to save project {}", registered.path))?;
+
+    let _ = app.emit("project-opened", project.clone());
+    Ok(project)
+}
+
+// Every registered project: pinned first, then by when it was last opened
+pub async fn all(app: &AppHandle) -> Result<Vec<Project>, String> {
+    let pool = projects_pool(app).await?;
+    let rows: Vec<ProjectRow> = sqlx::query_as(&format!(
+        "{} ORDER BY pinned DESC, last_opened_at IS NULL, last_opened_at DESC, name COLLATE NOCASE",
+        SELECT_PROJECTS
+    ))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to list projects: {}", e))?;
+    Ok(rows.into_iter().map(from_row).collect())
+}
+
+#[tauri::command]
+pub async fn list_projects(app: AppHandle) -> Result<Vec<Project>, String> {
+    all(&app).await
+}
+
+#[tauri::command]
+pub async fn list_recent_projects(app: AppHandle, limit: Option<i64>) -> Result<Vec<Project>, String> {
+    recent_projects(&app, limit.unwrap_or(10)).await
+}
+
+#[tauri::command]
+pub async fn set_project_pinned(app: AppHandle, id: String, pinned: bool) -> Result<(), String> {
+    let pool = projects_pool(&app).await?;
+    let result = sqlx::query("UPDATE projects SET pinned = ? WHERE id = ?")
+        .bind(pinned)
+        .bind(&id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to update project: {}", e))?;
+    if result.rows_affected() == 0 {
+        return Err(format!("Project {} not found", id));
+    }
+    let _ = app.emit("projects-changed", ());
+    Ok(())
+}
+
+// Forget a project and revoke the webview's access to its folder. Its
+// files are left alone.
+#[tauri::command]
+pub async fn remove_project(app: AppHandle, id: String) -> Result<(), String> {
+    let pool = projects_pool(&app).await?;
+    let path: Option<(String,)> = sqlx::query_as("DELETE FROM projects WHERE id = ? RETURNING path")
+        .bind(&id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| format!("Failed to remove project: {}", e))?;
+    if let Some((path,)) = path {
+        // Not in the scope is fine; it may have been revoked already
+        let _ = scopes::revoke(&app, &path).await;
+    }
+    let _ = app.emit("projects-changed", ());
+    Ok(())
+}
+
+// Forget when projects were opened; the projects themselves are kept
+pub async fn clear_recent(app: &AppHandle) -> Result<(), String> {
+    let pool = projects_pool(app).await?;
+    sqlx::query("UPDATE projects SET last_opened_at = NULL")
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("Failed to clear recent projects: {}", e))?;
+    let _ = app.emit("recent-projects-cleared", ());
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn clear_recent_projects(app: AppHandle) -> Result<(), String> {
+    clear_recent(&app).await
+}
@@ -0,0 +1,184 @@
+use std::path::Path;
+
+use tauri::{AppHandle, WebviewWindow};
+
+use crate::project_watcher;
+
+const DEFAULT_LIMIT: usize = 50;
+// Paths longer than this are skipped rather than scored
+const MAX_PATH_CHARS: usize = 1024;
+
+// Scoring in the style of fzy: matches right after a separator or at a
+// camelCase hump count most, runs of consecutive matches next, and gaps
+// cost a little
+const SCORE_MIN: f64 = f64::NEG_INFINITY;
+const SCORE_GAP_LEADING: f64 = -0.005;
+const SCORE_GAP_TRAILING: f64 = -0.005;
+const SCORE_GAP_INNER: f64 = -0.01;
+const SCORE_MATCH_CONSECUTIVE: f64 = 1.0;
+const SCORE_MATCH_SLASH: f64 = 0.9;
+const SCORE_MATCH_WORD: f64 = 0.8;
+const SCORE_MATCH_CAPITAL: f64 = 0.7;
+const SCORE_MATCH_DOT: f64 = 0.6;
+// What people type is usually the file's name, so a match that lies
+// entirely within it ranks above one spread along the directories
+const SCORE_FILE_NAME: f64 = 1.0;
+
+#[derive(Clone, serde::Serialize)]
+pub struct FileMatch {
+    // Relative to the project, '/'-separated
+    pub path: String,
+    pub score: f64,
+    // Character offsets of the matched characters in `path`
+    pub positions: Vec<usize>,
+}
+
+fn bonus(previous: char, current: char) -> f64 {
+    match previous {
+        '/' | '\\' => SCORE_MATCH_SLASH,
+        '-' | '_' | ' ' => SCORE_MATCH_WORD,
+        '.' => SCORE_MATCH_DOT,
+        previous if previous.is_lowercase() && current.is_uppercase() => SCORE_MATCH_CAPITAL,
+        _ => 0.0,
+    }
+}
+
+fn is_subsequence(needle: &[char], haystack: &str) -> bool {
+    let mut needle = needle.iter().peekable();
+    for c in haystack.chars().flat_map(char::to_lowercase) {
+        if needle.peek() == Some(&&c) {
+            needle.next();
+        }
+    }
+    needle.peek().is_none()
+}
+
+// Best score for `needle` (lowercased) in `haystack`, and which characters
+// produced it
+fn score(needle: &[char], haystack: &str) -> Option<(f64, Vec<usize>)> {
+    let chars: Vec<char> = haystack.chars().collect();
+    let (n, m) = (needle.len(), chars.len());
+    if n == 0 || n > m || m > MAX_PATH_CHARS {
+        return None;
+    }
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let bonuses: Vec<f64> = (0..m)
+        .map(|j| if j == 0 { SCORE_MATCH_SLASH } else { bonus(chars[j - 1], chars[j]) })
+        .collect();
+
+    // best[i][j]: best score with needle[..=i] matched and needle[i] at or
+    // before j; ending[i][j]: the same with needle[i] exactly at j
+    let mut best = vec![vec![SCORE_MIN; m]; n];
+    let mut ending = vec![vec![SCORE_MIN; m]; n];
+    for i in 0..n {
+        let gap = if i == n - 1 { SCORE_GAP_TRAILING } else { SCORE_GAP_INNER };
+        let mut previous = SCORE_MIN;
+        for j in 0..m {
+            if lower[j] == needle[i] {
+                let score = if i == 0 {
+                    j as f64 * SCORE_GAP_LEADING + bonuses[j]
+                } else if j > 0 {
+                    (best[i - 1][j - 1] + bonuses[j]).max(ending[i - 1][j - 1] + SCORE_MATCH_CONSECUTIVE)
+                } else {
+                    SCORE_MIN
+                };
+                ending[i][j] = score;
+                previous = score.max(previous + gap);
+            } else {
+                previous += gap;
+            }
+            best[i][j] = previous;
+        }
+    }
+    let total = best[n - 1][m - 1];
+    if total == SCORE_MIN {
+        return None;
+    }
+
+    // Walk back through the table to find the positions behind the score
+    let mut positions = vec![0; n];
+    let mut must_match = false;
+    let mut j = m;
+    for i in (0..n).rev() {
+        while j > 0 {
+            j -= 1;
+            if ending[i][j] != SCORE_MIN && (must_match || ending[i][j] == best[i][j]) {
+                must_match = i > 0 && j > 0 && ending[i][j] == ending[i - 1][j - 1] + SCORE_MATCH_CONSECUTIVE;
+                positions[i] = j;
+                break;
+            }
+        }
+    }
+
+    let file_name_start = chars.iter().rposition(|c| *c == '/').map_or(0, |slash| slash + 1);
+    let total = if positions[0] >= file_name_start { total + SCORE_FILE_NAME } else { total };
+    Some((total, positions))
+}
+
+fn rank(files: &[String], query: &str, limit: usize) -> Vec<FileMatch> {
+    let needle: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if needle.is_empty() {
+        let mut paths: Vec<&String> = files.iter().collect();
+        paths.sort();
+        return paths
+            .into_iter()
+            .take(limit)
+            .map(|path| FileMatch {
+                path: path.clone(),
+                score: 0.0,
+                positions: Vec::new(),
+            })
+            .collect();
+    }
+
+    let mut matches: Vec<FileMatch> = files
+        .iter()
+        .filter(|path| is_subsequence(&needle, path))
+        .filter_map(|path| {
+            score(&needle, path).map(|(score, positions)| FileMatch {
+                path: path.clone(),
+                score,
+                positions,
+            })
+        })
+        .collect();
+    // Shorter paths first among equals
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.path.len().cmp(&b.path.len()))
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    matches.truncate(limit);
+    matches
+}
+
+// Quick-open: files in the project whose path fuzzily matches `query`, best
+// first. Uses the project watcher's index, starting the watcher if the
+// project isn't watched yet, so only the first call walks the disk. One
+// started here is held for the window until it closes.
+#[tauri::command]
+pub async fn fuzzy_find_files(
+    app: AppHandle,
+    window: WebviewWindow,
+    path: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<FileMatch>, String> {
+    let index = match project_watcher::index(&app, Path::new(&path)) {
+        Some(index) => index,
+        None => project_watcher::watch(&app, &path, window.label()).await?,
+    };
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    tauri::async_runtime::spawn_blocking(move || {
+        // Copied out so the watcher isn't held up while scoring runs
+        let files: Vec<String> = index.lock().map_err(|e| e.to_string())?.files.iter().cloned().collect();
+        Ok(rank(&files, &query, limit))
+    })
+    .await
+    .map_err(|e| format!("Search task failed: {}", e))?
+}
@@ -0,0 +1,333 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection, SqliteConnection};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
+
+use crate::{backup, db, encryption, fs_util, i18n};
+
+// Another process holding the file is waited out, not treated as damage
+const LOCK_RETRIES: u32 = 5;
+const LOCK_RETRY_DELAY: Duration = Duration::from_secs(1);
+// When a table can't be copied in one go, rows are copied this many
+// rowids at a time, then one by one within a chunk that fails
+const SALVAGE_CHUNK: i64 = 256;
+// Times a table's rowids may fail to list before the rest of it is given
+// up on. Each failure skips twice as far as the last.
+const MAX_FAILED_WALKS: u32 = 32;
+
+// SQLite primary result codes
+const SQLITE_BUSY: i64 = 5;
+const SQLITE_LOCKED: i64 = 6;
+const SQLITE_CORRUPT: i64 = 11;
+const SQLITE_NOTADB: i64 = 26;
+
+#[derive(Clone, serde::Serialize)]
+pub struct TableSalvage {
+    pub table: String,
+    pub recovered_rows: u64,
+    // False when some rows couldn't be read
+    pub complete: bool,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct RecoveryReport {
+    // What the health check found
+    pub issue: String,
+    // The damaged file, kept for a manual look
+    pub damaged_path: String,
+    // False when not even the list of tables could be read, so the
+    // database was started over
+    pub schema_recovered: bool,
+    pub tables: Vec<TableSalvage>,
+}
+
+// Kept for a frontend that wasn't listening yet when it was emitted
+#[derive(Default)]
+pub struct RecoveryStatus(Mutex<Option<RecoveryReport>>);
+
+enum Health {
+    Healthy,
+    Damaged(String),
+    // Locked, or encrypted with a key we don't have. Left alone so the
+    // usual open reports it.
+    Unavailable,
+}
+
+fn result_code(error: &sqlx::Error) -> Option<i64> {
+    match error {
+        sqlx::Error::Database(error) => error.code().and_then(|code| code.parse::<i64>().ok()).map(|code| code & 0xff),
+        _ => None,
+    }
+}
+
+async fn probe(options: &SqliteConnectOptions) -> Result<String, sqlx::Error> {
+    let mut connection = options.clone().create_if_missing(false).read_only(true).connect().await?;
+    let (result,): (String,) = sqlx::query_as("PRAGMA quick_check(1)").fetch_one(&mut connection).await?;
+    let _ = connection.close().await;
+    Ok(result)
+}
+
+async fn check(path: &Path) -> Health {
+    if !path.exists() {
+        return Health::Healthy;
+    }
+    let encrypted = db::is_encrypted(path);
+    let Ok(options) = db::connect_options(path) else {
+        return Health::Unavailable;
+    };
+    for _ in 0..LOCK_RETRIES {
+        match probe(&options).await {
+            Ok(result) if result == "ok" => return Health::Healthy,
+            Ok(result) => return Health::Damaged(result),
+            Err(e) => match result_code(&e) {
+                Some(SQLITE_BUSY | SQLITE_LOCKED) => tokio::time::sleep(LOCK_RETRY_DELAY).await,
+                // An encrypted file that won't open is more likely the wrong
+                // key than damage
+                Some(SQLITE_NOTADB) if encrypted => return Health::Unavailable,
+                Some(SQLITE_CORRUPT | SQLITE_NOTADB) => return Health::Damaged(e.to_string()),
+                _ => return Health::Unavailable,
+            },
+        }
+    }
+    Health::Unavailable
+}
+
+// Move the file and its WAL aside together, so the WAL still matches
+fn move_aside(path: &Path) -> Result<PathBuf, String> {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let damaged = path.with_file_name(format!("{}.corrupt-{}.db", stem, stamp));
+    std::fs::rename(path, &damaged).map_err(|e| format!("Failed to move {} aside: {}", path.display(), e))?;
+    for suffix in ["-wal", "-shm"] {
+        let sidecar = backup::sidecar_path(path, suffix);
+        if sidecar.exists() {
+            let _ = std::fs::rename(&sidecar, backup::sidecar_path(&damaged, suffix));
+        }
+    }
+    Ok(damaged)
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+// Copy what can still be read from one table: all at once if possible,
+// otherwise chunk by chunk and row by row, skipping what fails
+async fn salvage_table(connection: &mut SqliteConnection, table: &str) -> TableSalvage {
+    let copy = format!("INSERT OR IGNORE INTO main.{table} SELECT * FROM damaged.{table}", table = quote(table));
+    let mut salvage = TableSalvage {
+        table: table.to_string(),
+        recovered_rows: 0,
+        complete: true,
+    };
+    if let Ok(result) = sqlx::query(&copy).execute(&mut *connection).await {
+        salvage.recovered_rows = result.rows_affected();
+        return salvage;
+    }
+
+    salvage.complete = false;
+    // Only rowids that exist are tried, so a sparse table with a huge
+    // rowid doesn't take forever. Where even listing them fails, the walk
+    // skips past the damage.
+    let rowids = format!("SELECT rowid FROM damaged.{} WHERE rowid > ? ORDER BY rowid LIMIT ?", quote(table));
+    let ranged = format!("{} WHERE rowid BETWEEN ? AND ?", copy);
+    let mut after: i64 = -1;
+    let mut skip = SALVAGE_CHUNK;
+    let mut failed_walks = 0;
+    while failed_walks < MAX_FAILED_WALKS {
+        let chunk: Result<Vec<(i64,)>, _> = sqlx::query_as(&rowids)
+            .bind(after)
+            .bind(SALVAGE_CHUNK)
+            .fetch_all(&mut *connection)
+            .await;
+        let chunk = match chunk {
+            Ok(chunk) if chunk.is_empty() => break,
+            Ok(chunk) => chunk,
+            Err(_) => {
+                failed_walks += 1;
+                after = after.saturating_add(skip);
+                skip = skip.saturating_mul(2);
+                continue;
+            }
+        };
+        let (first, last) = (chunk[0].0, chunk[chunk.len() - 1].0);
+        match sqlx::query(&ranged).bind(first).bind(last).execute(&mut *connection).await {
+            Ok(result) => salvage.recovered_rows += result.rows_affected(),
+            Err(_) => {
+                for (rowid,) in &chunk {
+                    if let Ok(result) = sqlx::query(&ranged).bind(rowid).bind(rowid).execute(&mut *connection).await {
+                        salvage.recovered_rows += result.rows_affected();
+                    }
+                }
+            }
+        }
+        after = last;
+        skip = SALVAGE_CHUNK;
+    }
+    salvage
+}
+
+// Rebuild the damaged database's schema in `fresh` and copy across every
+// row that can still be read. Search indexes are left for the app to
+// rebuild from the transcripts.
+async fn salvage(damaged: &Path, fresh: &Path, key: Option<&str>) -> Result<(bool, Vec<TableSalvage>), String> {
+    let mut options = SqliteConnectOptions::new()
+        .filename(fresh)
+        .create_if_missing(true)
+        .foreign_keys(false);
+    if let Some(key) = key {
+        options = options.pragma("key", encryption::key_pragma(key));
+    }
+    let mut connection = options
+        .connect()
+        .await
+        .map_err(|e| format!("Failed to create the recovered database: {}", e))?;
+    sqlx::query("ATTACH DATABASE ? AS damaged KEY ?")
+        .bind(damaged.to_string_lossy().into_owned())
+        .bind(key.map(|key| format!("x'{}'", key)).unwrap_or_default())
+        .execute(&mut connection)
+        .await
+        .map_err(|e| format!("Failed to open the damaged database: {}", e))?;
+
+    let schema: Result<Vec<(String, String, String)>, _> =
+        sqlx::query_as("SELECT type, name, sql FROM damaged.sqlite_master WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%'")
+            .fetch_all(&mut connection)
+            .await;
+    let Ok(schema) = schema else {
+        let _ = connection.close().await;
+        return Ok((false, Vec::new()));
+    };
+
+    // Virtual tables and the shadow tables behind them
+    let virtual_tables: Vec<&str> = schema
+        .iter()
+        .filter(|(kind, _, sql)| kind == "table" && sql.to_uppercase().starts_with("CREATE VIRTUAL TABLE"))
+        .map(|(_, name, _)| name.as_str())
+        .collect();
+    let is_virtual = |name: &str| {
+        virtual_tables
+            .iter()
+            .any(|table| name == *table || name.starts_with(&format!("{}_", table)))
+    };
+
+    let mut tables = Vec::new();
+    for (kind, name, sql) in &schema {
+        if kind != "table" || is_virtual(name) {
+            continue;
+        }
+        if sqlx::query(sql).execute(&mut connection).await.is_err() {
+            tables.push(TableSalvage {
+                table: name.clone(),
+                recovered_rows: 0,
+                complete: false,
+            });
+            continue;
+        }
+        tables.push(salvage_table(&mut connection, name).await);
+    }
+    // Indexes once the rows are in, then views and triggers. One that
+    // fails only costs speed or a convenience, so it's skipped.
+    for (kind, name, sql) in &schema {
+        if kind != "table" && !is_virtual(name) {
+            let _ = sqlx::query(sql).execute(&mut connection).await;
+        }
+    }
+
+    let _ = sqlx::query("DETACH DATABASE damaged").execute(&mut connection).await;
+    connection
+        .close()
+        .await
+        .map_err(|e| format!("Failed to save the recovered database: {}", e))?;
+    Ok((true, tables))
+}
+
+async fn recover(path: &Path, issue: String) -> Result<RecoveryReport, String> {
+    // The salvaged copy is encrypted with the same key as the damaged one
+    let key = if db::is_encrypted(path) {
+        encryption::saved_key()?
+    } else {
+        None
+    };
+    let damaged = move_aside(path)?;
+    let fresh = fs_util::temp_path_for(path);
+
+    let (schema_recovered, tables) = match salvage(&damaged, &fresh, key.as_deref()).await {
+        Ok(salvaged) => salvaged,
+        Err(e) => {
+            eprintln!("{}", e);
+            (false, Vec::new())
+        }
+    };
+    // With nothing salvaged the app starts on an empty database
+    if schema_recovered {
+        std::fs::rename(&fresh, path).map_err(|e| format!("Failed to save the recovered database: {}", e))?;
+    } else {
+        let _ = std::fs::remove_file(&fresh);
+    }
+
+    Ok(RecoveryReport {
+        issue,
+        damaged_path: damaged.to_string_lossy().to_string(),
+        schema_recovered,
+        tables,
+    })
+}
+
+// From setup, before the database is opened: a damaged file is moved aside
+// and whatever can be read from it copied into a new one, which the
+// migrations then bring up to date
+pub async fn check_and_recover(app: &AppHandle) -> Option<RecoveryReport> {
+    let path = db::path(app).ok()?;
+    let Health::Damaged(issue) = check(&path).await else {
+        return None;
+    };
+    eprintln!("Database is damaged ({}), recovering", issue);
+    match recover(&path, issue).await {
+        Ok(report) => Some(report),
+        Err(e) => {
+            eprintln!("Database recovery failed: {}", e);
+            None
+        }
+    }
+}
+
+// Tell the user what happened, once settings (and so the language) are
+// loaded. Emits `db-recovered` with the report.
+pub fn announce(app: &AppHandle, report: RecoveryReport) {
+    let incomplete: Vec<&str> = report
+        .tables
+        .iter()
+        .filter(|table| !table.complete)
+        .map(|table| table.table.as_str())
+        .collect();
+    let message = if !report.schema_recovered {
+        i18n::t_with(app, "dialogs.dbRecoveredEmpty", &[("path", &report.damaged_path)])
+    } else if incomplete.is_empty() {
+        i18n::t_with(app, "dialogs.dbRecoveredComplete", &[("path", &report.damaged_path)])
+    } else {
+        i18n::t_with(
+            app,
+            "dialogs.dbRecoveredPartial",
+            &[("tables", &incomplete.join(", ")), ("path", &report.damaged_path)],
+        )
+    };
+    app.dialog()
+        .message(message)
+        .title(i18n::t(app, "dialogs.dbRecoveredTitle"))
+        .kind(MessageDialogKind::Warning)
+        .show(|_| {});
+
+    if let Ok(mut status) = app.state::<RecoveryStatus>().0.lock() {
+        *status = Some(report.clone());
+    }
+    let _ = app.emit("db-recovered", report);
+}
+
+#[tauri::command]
+pub fn get_db_recovery(app: AppHandle) -> Option<RecoveryReport> {
+    app.state::<RecoveryStatus>().0.lock().ok().and_then(|status| status.clone())
+}
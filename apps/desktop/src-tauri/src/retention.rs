@@ -0,0 +1,227 @@
+use std::time::Duration;
+
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter};
+
+use crate::{db, sessions, settings};
+
+const PRUNE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+// Rough per-row cost on top of the text a row holds, for size estimates
+const ROW_OVERHEAD_BYTES: i64 = 256;
+
+// Every rule is off until it's set
+#[derive(Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RetentionRules {
+    // Indexed Claude sessions and agent chats not touched in this many days
+    pub session_max_age_days: Option<u32>,
+    // Prompt runs older than this many days, with their variables, grades
+    // and feedback. Daily analytics are kept.
+    pub run_max_age_days: Option<u32>,
+    // Oldest data of any kind goes first until the live data fits. The file
+    // itself shrinks on the next VACUUM.
+    pub max_database_mb: Option<u64>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct PruneItem {
+    // "claude_session", "chat_session" or "prompt_run"
+    pub kind: String,
+    pub id: String,
+    pub title: Option<String>,
+    pub updated_at: Option<String>,
+    // "age" or "size"
+    pub reason: String,
+    pub estimated_bytes: i64,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct PrunePreview {
+    pub items: Vec<PruneItem>,
+    // Pages in use, not counting free pages a VACUUM would release
+    pub database_bytes: i64,
+    pub estimated_freed_bytes: i64,
+}
+
+pub fn rules(app: &AppHandle) -> RetentionRules {
+    settings::get(app).retention
+}
+
+fn cutoff(days: u32) -> String {
+    (chrono::Utc::now() - chrono::Duration::days(days.into())).to_rfc3339()
+}
+
+type CandidateRow = (String, Option<String>, Option<String>, i64);
+
+// Everything of one kind, oldest first, with an estimate of its size
+async fn candidates(pool: &SqlitePool, kind: &str) -> Result<Vec<PruneItem>, String> {
+    let sql = match kind {
+        "claude_session" => {
+            "SELECT s.id, s.first_prompt, s.updated_at,
+                    COALESCE((SELECT SUM(LENGTH(content)) FROM claude_messages_fts WHERE session_id = s.id), 0)
+                        + COALESCE(LENGTH(s.first_prompt), 0)
+             FROM claude_sessions s ORDER BY s.updated_at"
+        }
+        "chat_session" => {
+            "SELECT c.id, c.title, c.updated_at,
+                    COALESCE((SELECT SUM(LENGTH(content) + COALESCE(LENGTH(tool_calls), 0)) FROM chat_messages WHERE session_id = c.id), 0)
+             FROM chat_sessions c ORDER BY c.updated_at"
+        }
+        _ => {
+            "SELECT r.id, r.prompt_name, r.created_at,
+                    COALESCE((SELECT SUM(LENGTH(variable_value)) FROM run_variables WHERE run_id = r.id), 0)
+                        + COALESCE(LENGTH(r.error_message), 0)
+             FROM prompt_runs r ORDER BY r.created_at"
+        }
+    };
+    let rows: Vec<CandidateRow> = sqlx::query_as(sql)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to read {} rows: {}", kind, e))?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, title, updated_at, bytes)| PruneItem {
+            kind: kind.to_string(),
+            id,
+            title,
+            updated_at,
+            reason: "age".to_string(),
+            estimated_bytes: bytes + ROW_OVERHEAD_BYTES,
+        })
+        .collect())
+}
+
+async fn database_bytes(pool: &SqlitePool) -> Result<i64, String> {
+    let (bytes,): (i64,) = sqlx::query_as(
+        "SELECT (page_count - freelist_count) * page_size FROM pragma_page_count(), pragma_freelist_count(), pragma_page_size()",
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to read database size: {}", e))?;
+    Ok(bytes)
+}
+
+async fn plan(app: &AppHandle, rules: &RetentionRules) -> Result<PrunePreview, String> {
+    let pool = db::pool(app).await?;
+    let mut all = Vec::new();
+    for kind in ["claude_session", "chat_session", "prompt_run"] {
+        all.extend(candidates(&pool, kind).await?);
+    }
+
+    let session_cutoff = rules.session_max_age_days.map(cutoff);
+    let run_cutoff = rules.run_max_age_days.map(cutoff);
+    let too_old = |item: &PruneItem| {
+        let cutoff = match item.kind.as_str() {
+            "prompt_run" => &run_cutoff,
+            _ => &session_cutoff,
+        };
+        match (cutoff, &item.updated_at) {
+            (Some(cutoff), Some(updated_at)) => updated_at < cutoff,
+            _ => false,
+        }
+    };
+    let (mut items, mut rest): (Vec<_>, Vec<_>) = all.into_iter().partition(|item| too_old(item));
+
+    let database_bytes = database_bytes(&pool).await?;
+    if let Some(max_mb) = rules.max_database_mb {
+        let limit = (max_mb as i64) * 1024 * 1024;
+        let mut size = database_bytes - items.iter().map(|item| item.estimated_bytes).sum::<i64>();
+        rest.sort_by(|a, b| a.updated_at.cmp(&b.updated_at));
+        for mut item in rest {
+            if size <= limit {
+                break;
+            }
+            size -= item.estimated_bytes;
+            item.reason = "size".to_string();
+            items.push(item);
+        }
+    }
+
+    Ok(PrunePreview {
+        estimated_freed_bytes: items.iter().map(|item| item.estimated_bytes).sum(),
+        items,
+        database_bytes,
+    })
+}
+
+async fn delete(pool: &SqlitePool, items: &[PruneItem]) -> Result<(), String> {
+    let claude_sessions: Vec<String> = items
+        .iter()
+        .filter(|item| item.kind == "claude_session")
+        .map(|item| item.id.clone())
+        .collect();
+    sessions::prune(pool, &claude_sessions).await?;
+
+    let mut tx = pool.begin().await.map_err(|e| format!("Failed to prune: {}", e))?;
+    for item in items {
+        let statements: &[&str] = match item.kind.as_str() {
+            "chat_session" => &["DELETE FROM chat_messages WHERE session_id = ?", "DELETE FROM chat_sessions WHERE id = ?"],
+            "prompt_run" => &[
+                "DELETE FROM run_variables WHERE run_id = ?",
+                "DELETE FROM grader_results WHERE run_id = ?",
+                "DELETE FROM run_feedback WHERE run_id = ?",
+                "DELETE FROM prompt_runs WHERE id = ?",
+            ],
+            _ => &[],
+        };
+        for statement in statements {
+            sqlx::query(statement)
+                .bind(&item.id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("Failed to prune {} {}: {}", item.kind, item.id, e))?;
+        }
+    }
+    tx.commit().await.map_err(|e| format!("Failed to prune: {}", e))
+}
+
+// Apply the saved rules. Emits `retention-pruned` with what was removed.
+pub async fn prune(app: &AppHandle) -> Result<PrunePreview, String> {
+    let rules = rules(app);
+    let preview = plan(app, &rules).await?;
+    if !preview.items.is_empty() {
+        delete(&db::pool(app).await?, &preview.items).await?;
+        let _ = app.emit("retention-pruned", preview.clone());
+    }
+    Ok(preview)
+}
+
+// Prunes on startup and every few hours after, while any rule is set
+pub fn start_background_pruning(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let rules = rules(&app);
+            let enabled =
+                rules.session_max_age_days.is_some() || rules.run_max_age_days.is_some() || rules.max_database_mb.is_some();
+            if enabled {
+                if let Err(e) = prune(&app).await {
+                    eprintln!("{}", e);
+                }
+            }
+            tokio::time::sleep(PRUNE_INTERVAL).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_retention_rules(app: AppHandle) -> RetentionRules {
+    rules(&app)
+}
+
+#[tauri::command]
+pub fn set_retention_rules(app: AppHandle, rules: RetentionRules) -> Result<(), String> {
+    settings::update(&app, |settings| settings.retention = rules).map(|_| ())
+}
+
+// What `run_prune` would delete under the saved rules, or under `rules` when
+// the settings page wants to show the effect of edits before saving them
+#[tauri::command]
+pub async fn preview_prune(app: AppHandle, rules: Option<RetentionRules>) -> Result<PrunePreview, String> {
+    let rules = rules.unwrap_or_else(|| self::rules(&app));
+    plan(&app, &rules).await
+}
+
+#[tauri::command]
+pub async fn run_prune(app: AppHandle) -> Result<PrunePreview, String> {
+    prune(&app).await
+}
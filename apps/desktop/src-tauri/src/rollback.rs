@@ -0,0 +1,352 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{fs_util, i18n, server, updater};
+
+// The install an update replaced, copied aside just before it was installed
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct Snapshot {
+    version: String,
+}
+
+// Held while a snapshot is being taken, so two never copy at once
+static SNAPSHOT_LOCK: Mutex<()> = Mutex::new(());
+
+// Written by the Windows rollback script when robocopy fails, and reported
+// on the next launch
+const FAILED_FILE: &str = "failed";
+
+#[derive(Clone, serde::Serialize)]
+struct RollbackProgress {
+    // "stopping", "restoring" or "restarting"
+    stage: String,
+    version: String,
+}
+
+fn rollback_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("rollback"))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+// What an update replaces: the .app bundle on macOS, the install directory
+// on Windows and the AppImage on Linux
+fn install_path() -> Result<PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate the app: {}", e))?;
+
+    #[cfg(target_os = "macos")]
+    {
+        // Incito.app/Contents/MacOS/incito
+        exe.ancestors()
+            .nth(3)
+            .filter(|bundle| bundle.extension().is_some_and(|extension| extension == "app"))
+            .map(Path::to_path_buf)
+            .ok_or_else(|| "The app isn't running from an app bundle".to_string())
+    }
+
+    #[cfg(windows)]
+    {
+        exe.parent()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| "Failed to locate the install directory".to_string())
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        let _ = exe;
+        std::env::var_os("APPIMAGE")
+            .map(PathBuf::from)
+            .ok_or_else(|| "Rolling back is only supported for AppImage installs".to_string())
+    }
+}
+
+fn remove_path(path: &Path) -> Result<(), String> {
+    let result = if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else if path.exists() {
+        std::fs::remove_file(path)
+    } else {
+        Ok(())
+    };
+    result.map_err(|e| format!("Failed to remove {}: {}", path.display(), e))
+}
+
+#[cfg(windows)]
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+    let entries = std::fs::read_dir(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+    for entry in entries.flatten() {
+        let target = to.join(entry.file_name());
+        if entry.path().is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)
+                .map_err(|e| format!("Failed to copy {}: {}", entry.path().display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_install(from: &Path, to: &Path) -> Result<(), String> {
+    // ditto keeps the bundle's symlinks, permissions and code signature
+    #[cfg(target_os = "macos")]
+    {
+        let status = std::process::Command::new("ditto")
+            .arg(from)
+            .arg(to)
+            .status()
+            .map_err(|e| format!("Failed to run ditto: {}", e))?;
+        if !status.success() {
+            return Err(format!("Failed to copy {}", from.display()));
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    {
+        copy_dir(from, to)
+    }
+
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        std::fs::copy(from, to)
+            .map(|_| ())
+            .map_err(|e| format!("Failed to copy {}: {}", from.display(), e))
+    }
+}
+
+// Only the last replaced version is kept. snapshot.json is removed first and
+// written last, so a copy cut short isn't offered as a rollback.
+fn snapshot(app: &AppHandle) -> Result<(), String> {
+    let _lock = SNAPSHOT_LOCK.lock().map_err(|e| e.to_string())?;
+    let install_path = install_path()?;
+    let dir = rollback_dir(app)?;
+    let content = dir.join("previous");
+    remove_path(&dir.join("snapshot.json"))?;
+    remove_path(&content)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    copy_install(&install_path, &content)?;
+
+    let snapshot = Snapshot {
+        version: app.package_info().version.to_string(),
+    };
+    let contents = serde_json::to_vec_pretty(&snapshot).map_err(|e| e.to_string())?;
+    fs_util::write_atomic(&dir.join("snapshot.json"), &contents, false)
+}
+
+// Before an update is installed, or as soon as one is staged so quitting
+// doesn't wait on the copy. Copying the install can take a while, so it's
+// kept off the calling thread.
+pub async fn take_snapshot(app: &AppHandle) {
+    let app = app.clone();
+    match tauri::async_runtime::spawn_blocking(move || snapshot(&app)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => eprintln!("Failed to keep the current version for rollback: {}", e),
+        Err(e) => eprintln!("Failed to keep the current version for rollback: {}", e),
+    }
+}
+
+// The snapshot, unless it's of the version that's running now
+fn saved_snapshot(app: &AppHandle) -> Option<Snapshot> {
+    let dir = rollback_dir(app).ok()?;
+    let contents = std::fs::read_to_string(dir.join("snapshot.json")).ok()?;
+    let snapshot: Snapshot = serde_json::from_str(&contents).ok()?;
+    let current = app.package_info().version.to_string();
+    (snapshot.version != current && dir.join("previous").exists()).then_some(snapshot)
+}
+
+fn progress(app: &AppHandle, stage: &str, version: &str) {
+    let _ = app.emit(
+        "rollback-progress",
+        RollbackProgress {
+            stage: stage.to_string(),
+            version: version.to_string(),
+        },
+    );
+}
+
+// The running install can be moved aside and replaced in place
+#[cfg(not(windows))]
+fn restore(_app: &AppHandle, content: &Path, install_path: &Path) -> Result<(), String> {
+    let mut aside = install_path.as_os_str().to_os_string();
+    aside.push(".rollback-old");
+    let aside = PathBuf::from(aside);
+    remove_path(&aside)?;
+    std::fs::rename(install_path, &aside).map_err(|e| format!("Failed to move {} aside: {}", install_path.display(), e))?;
+
+    if let Err(e) = copy_install(content, install_path) {
+        let _ = remove_path(install_path);
+        let _ = std::fs::rename(&aside, install_path);
+        return Err(e);
+    }
+    let _ = remove_path(&aside);
+    let _ = remove_path(content);
+    Ok(())
+}
+
+// A per-user install can be written to as is; one in Program Files needs
+// an administrator
+#[cfg(windows)]
+fn writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".incito-rollback-{}", std::process::id()));
+    let writable = std::fs::write(&probe, b"").is_ok();
+    let _ = std::fs::remove_file(&probe);
+    writable
+}
+
+// Files in the install directory are locked while the app runs, so a script
+// waits for this process to exit, copies the snapshot back and relaunches.
+// Only robocopy runs elevated, through a UAC prompt, so the app comes back
+// as the user. A declined prompt counts as robocopy's "serious error", 16;
+// any exit code from 8 up is left in FAILED_FILE for the next launch.
+#[cfg(windows)]
+fn restore(app: &AppHandle, content: &Path, install_path: &Path) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    const ROBOCOPY_FLAGS: &str = "/MIR /NFL /NDL /NJH /NJS /NP";
+
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to locate the app: {}", e))?;
+    let failed = rollback_dir(app)?.join(FAILED_FILE);
+    remove_path(&failed)?;
+    let pid = std::process::id();
+    let script = std::env::temp_dir().join(format!("incito-rollback-{}.cmd", pid));
+    let elevated = std::env::temp_dir().join(format!("incito-rollback-{}.ps1", pid));
+    let copy = if writable(install_path) {
+        format!(
+            "robocopy \"{}\" \"{}\" {} >nul",
+            content.display(),
+            install_path.display(),
+            ROBOCOPY_FLAGS
+        )
+    } else {
+        let quoted = |path: &Path| format!("'\"{}\"'", path.display().to_string().replace('\'', "''"));
+        let arguments: Vec<String> = [quoted(content), quoted(install_path)]
+            .into_iter()
+            .chain(ROBOCOPY_FLAGS.split(' ').map(|flag| format!("'{}'", flag)))
+            .collect();
+        let contents = format!(
+            "try {{\r\n\
+             $arguments = @({})\r\n\
+             $copy = Start-Process robocopy $arguments -Verb RunAs -WindowStyle Hidden -Wait -PassThru\r\n\
+             exit $copy.ExitCode\r\n\
+             }} catch {{ exit 16 }}\r\n",
+            arguments.join(", ")
+        );
+        std::fs::write(&elevated, contents).map_err(|e| format!("Failed to write rollback script: {}", e))?;
+        format!(
+            "powershell -NoProfile -NonInteractive -ExecutionPolicy Bypass -File \"{}\"",
+            elevated.display()
+        )
+    };
+    // The exit code is redirected first; "echo 16>file" would redirect stream 16
+    let contents = format!(
+        "@echo off\r\n\
+         :wait\r\n\
+         tasklist /FI \"PID eq {pid}\" 2>nul | find \"{pid}\" >nul && (timeout /t 1 /nobreak >nul & goto wait)\r\n\
+         {copy}\r\n\
+         if errorlevel 8 (>\"{failed}\" echo %ERRORLEVEL%)\r\n\
+         start \"\" \"{exe}\"\r\n\
+         del \"{elevated}\" 2>nul\r\n\
+         del \"%~f0\"\r\n",
+        pid = pid,
+        copy = copy,
+        failed = failed.display(),
+        exe = exe.display(),
+        elevated = elevated.display(),
+    );
+    std::fs::write(&script, contents).map_err(|e| format!("Failed to write rollback script: {}", e))?;
+    std::process::Command::new("cmd")
+        .arg("/C")
+        .arg(&script)
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn()
+        .map_err(|e| format!("Failed to start rollback: {}", e))?;
+    Ok(())
+}
+
+// Put the snapshot back and restart into it. Emits `rollback-progress` as
+// it goes.
+pub fn rollback(app: &AppHandle) -> Result<(), String> {
+    let snapshot = saved_snapshot(app).ok_or_else(|| "There's no previous version to roll back to".to_string())?;
+    let content = rollback_dir(app)?.join("previous");
+    let install_path = install_path()?;
+
+    // A staged update would otherwise be installed on the way out
+    updater::discard_staged(app);
+
+    progress(app, "stopping", &snapshot.version);
+    let _ = server::stop(app, true, "rollback");
+
+    progress(app, "restoring", &snapshot.version);
+    restore(app, &content, &install_path)?;
+
+    progress(app, "restarting", &snapshot.version);
+    #[cfg(windows)]
+    {
+        app.exit(0);
+        Ok(())
+    }
+    #[cfg(not(windows))]
+    app.restart()
+}
+
+// From setup: a Windows rollback finishes after the app has exited, so
+// whether robocopy failed is only known on the next launch
+pub fn report_failed(app: &AppHandle) {
+    let Ok(failed) = rollback_dir(app).map(|dir| dir.join(FAILED_FILE)) else {
+        return;
+    };
+    let Ok(code) = std::fs::read_to_string(&failed) else {
+        return;
+    };
+    let _ = std::fs::remove_file(&failed);
+    app.dialog()
+        .message(i18n::t_with(app, "dialogs.rollbackFailed", &[("code", code.trim())]))
+        .title(i18n::t(app, "dialogs.rollbackTitle"))
+        .kind(MessageDialogKind::Error)
+        .show(|_| {});
+}
+
+// From the app menu: confirm, then roll back
+pub fn confirm_rollback(app: &AppHandle) {
+    let Some(snapshot) = saved_snapshot(app) else {
+        app.dialog()
+            .message(i18n::t(app, "dialogs.noRollback"))
+            .title(i18n::t(app, "dialogs.rollbackTitle"))
+            .kind(MessageDialogKind::Info)
+            .show(|_| {});
+        return;
+    };
+
+    let handle = app.clone();
+    app.dialog()
+        .message(i18n::t_with(app, "dialogs.rollbackConfirm", &[("version", &snapshot.version)]))
+        .title(i18n::t(app, "dialogs.rollbackTitle"))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            i18n::t(app, "dialogs.rollBack"),
+            i18n::t(app, "dialogs.cancel"),
+        ))
+        .show(move |confirmed| {
+            if confirmed {
+                if let Err(e) = rollback(&handle) {
+                    let _ = handle.emit("menu-action-failed", e);
+                }
+            }
+        });
+}
+
+// The version a rollback would restore, if there's one
+#[tauri::command]
+pub fn get_rollback_version(app: AppHandle) -> Option<String> {
+    saved_snapshot(&app).map(|snapshot| snapshot.version)
+}
+
+#[tauri::command]
+pub fn rollback_update(app: AppHandle) -> Result<(), String> {
+    rollback(&app)
+}
@@ -0,0 +1,315 @@
+use std::path::Path;
+
+use tauri::AppHandle;
+
+use crate::db;
+
+// How commands in a project are held back when sandboxed: no network and
+// writes only to temp folders unless allowed, and optional limits
+#[derive(Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SandboxProfile {
+    pub enabled: bool,
+    pub allow_network: bool,
+    // Temp folders are always writable
+    pub allow_project_writes: bool,
+    // CPU time each process may use, and memory it may take. None is no
+    // limit.
+    pub cpu_seconds: Option<u64>,
+    pub memory_mb: Option<u64>,
+    // Run commands with whatever this machine can enforce, e.g. only the
+    // limits on Windows, instead of refusing them
+    pub accept_limits_only: bool,
+}
+
+// Which restrictions this machine can enforce, and with what
+#[derive(Clone, serde::Serialize)]
+pub struct SandboxSupport {
+    // "sandbox-exec", "bwrap" or "job-object"; None when nothing but the
+    // limits is available
+    pub mechanism: Option<&'static str>,
+    pub network: bool,
+    pub writes: bool,
+    pub cpu: bool,
+    pub memory: bool,
+}
+
+type SandboxRow = (bool, bool, bool, Option<i64>, Option<i64>, bool);
+
+fn from_row(
+    (enabled, allow_network, allow_project_writes, cpu_seconds, memory_mb, accept_limits_only): SandboxRow,
+) -> SandboxProfile {
+    SandboxProfile {
+        enabled,
+        allow_network,
+        allow_project_writes,
+        cpu_seconds: cpu_seconds.map(|seconds| seconds.max(0) as u64),
+        memory_mb: memory_mb.map(|mb| mb.max(0) as u64),
+        accept_limits_only,
+    }
+}
+
+async fn saved(app: &AppHandle, project_path: &str) -> Result<SandboxProfile, String> {
+    let pool = db::pool(app).await?;
+    let row: Option<SandboxRow> = sqlx::query_as(
+        "SELECT enabled, allow_network, allow_project_writes, cpu_seconds, memory_mb, accept_limits_only
+         FROM project_sandbox
+         WHERE project_path = ?",
+    )
+    .bind(project_path)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("Failed to read sandbox settings: {}", e))?;
+    Ok(row.map(from_row).unwrap_or_default())
+}
+
+// The project's profile, if commands there are sandboxed
+pub async fn profile(app: &AppHandle, project_path: &str) -> Result<Option<SandboxProfile>, String> {
+    Ok(Some(saved(app, project_path).await?).filter(|profile| profile.enabled))
+}
+
+fn has_bwrap() -> bool {
+    std::process::Command::new("bwrap")
+        .arg("--version")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+pub fn support() -> SandboxSupport {
+    if cfg!(target_os = "macos") {
+        // RLIMIT_AS can't be set there
+        SandboxSupport {
+            mechanism: Some("sandbox-exec"),
+            network: true,
+            writes: true,
+            cpu: true,
+            memory: false,
+        }
+    } else if cfg!(windows) {
+        SandboxSupport {
+            mechanism: Some("job-object"),
+            network: false,
+            writes: false,
+            cpu: true,
+            memory: true,
+        }
+    } else {
+        let bwrap = has_bwrap();
+        SandboxSupport {
+            mechanism: bwrap.then_some("bwrap"),
+            network: bwrap,
+            writes: bwrap,
+            cpu: true,
+            memory: true,
+        }
+    }
+}
+
+// The profile's restrictions `support` can't enforce
+fn unenforced(profile: &SandboxProfile, support: &SandboxSupport) -> Vec<&'static str> {
+    let mut missing = Vec::new();
+    if !profile.allow_network && !support.network {
+        missing.push("no network");
+    }
+    if !support.writes {
+        missing.push("no writes outside the temp folders");
+    }
+    if profile.cpu_seconds.is_some() && !support.cpu {
+        missing.push("the CPU limit");
+    }
+    if profile.memory_mb.is_some() && !support.memory {
+        missing.push("the memory limit");
+    }
+    missing
+}
+
+fn sbpl_string(path: &Path) -> String {
+    format!("\"{}\"", path.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// A Seatbelt profile: everything allowed but writes outside the temp
+// folders and, unless allowed, network connections
+fn seatbelt_profile(profile: &SandboxProfile, root: &Path) -> String {
+    let mut rules = vec![
+        "(version 1)".to_string(),
+        "(allow default)".to_string(),
+        "(deny file-write*)".to_string(),
+        concat!(
+            "(allow file-write* (subpath \"/private/tmp\") (subpath \"/private/var/folders\")",
+            " (literal \"/dev/null\") (literal \"/dev/zero\") (regex #\"^/dev/tty\") (regex #\"^/dev/fd/\"))"
+        )
+        .to_string(),
+    ];
+    if profile.allow_project_writes {
+        rules.push(format!("(allow file-write* (subpath {}))", sbpl_string(root)));
+    }
+    if !profile.allow_network {
+        rules.push("(deny network-outbound (remote ip))".to_string());
+        rules.push("(deny network-bind (local ip))".to_string());
+    }
+    rules.join("\n")
+}
+
+// `command` run through sh, which sets the limits and then becomes it
+fn with_limits(profile: &SandboxProfile, support: &SandboxSupport, command: &str, args: &[String]) -> Vec<String> {
+    let mut limits = Vec::new();
+    if let Some(seconds) = profile.cpu_seconds.filter(|_| support.cpu) {
+        limits.push(format!("ulimit -t {}", seconds));
+    }
+    if let Some(mb) = profile.memory_mb.filter(|_| support.memory) {
+        limits.push(format!("ulimit -v {}", mb * 1024));
+    }
+    if limits.is_empty() {
+        return std::iter::once(command.to_string()).chain(args.iter().cloned()).collect();
+    }
+    limits.push("exec \"$@\"".to_string());
+    ["/bin/sh", "-c", &limits.join(" && "), "sh", command]
+        .into_iter()
+        .map(str::to_string)
+        .chain(args.iter().cloned())
+        .collect()
+}
+
+// The program and arguments that run `command` inside the sandbox. On
+// Windows it's unchanged; the process is put in a job by `confine`. Fails
+// when a restriction can't be enforced here, unless the profile accepts
+// running with what can be.
+pub fn wrap(
+    profile: &SandboxProfile,
+    project_path: &str,
+    command: &str,
+    args: &[String],
+) -> Result<Vec<String>, String> {
+    let root = std::fs::canonicalize(project_path).unwrap_or_else(|_| project_path.into());
+    let support = support();
+    let missing = unenforced(profile, &support);
+    if !missing.is_empty() && !profile.accept_limits_only {
+        let mut message = format!("This machine can't enforce the project's sandbox: {}.", missing.join(", "));
+        if cfg!(target_os = "linux") && support.mechanism.is_none() {
+            message.push_str(" Installing bubblewrap (bwrap) would let it.");
+        }
+        message.push_str(" To run commands with only what it can enforce, accept that in the sandbox settings.");
+        return Err(message);
+    }
+    let inner = if cfg!(windows) {
+        std::iter::once(command.to_string()).chain(args.iter().cloned()).collect()
+    } else {
+        with_limits(profile, &support, command, args)
+    };
+
+    let mut argv: Vec<String> = Vec::new();
+    match support.mechanism {
+        Some("sandbox-exec") => {
+            argv.extend(["/usr/bin/sandbox-exec".to_string(), "-p".to_string(), seatbelt_profile(profile, &root)]);
+        }
+        Some("bwrap") => {
+            let root = root.to_string_lossy().to_string();
+            argv.extend(
+                ["bwrap", "--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"]
+                    .map(str::to_string),
+            );
+            if profile.allow_project_writes {
+                argv.extend(["--bind".to_string(), root.clone(), root]);
+            }
+            if !profile.allow_network {
+                argv.push("--unshare-net".to_string());
+            }
+            argv.extend(["--die-with-parent", "--setenv", "TMPDIR", "/tmp", "--"].map(str::to_string));
+        }
+        _ => {}
+    }
+    argv.extend(inner);
+    Ok(argv)
+}
+
+// Put a started process, and so everything it starts, in a job object with
+// the profile's limits. It has already been running for a moment by then.
+#[cfg(windows)]
+pub fn confine(child: &std::process::Child, profile: &SandboxProfile) -> Result<(), String> {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation, SetInformationJobObject,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_PROCESS_MEMORY, JOB_OBJECT_LIMIT_PROCESS_TIME,
+    };
+
+    if profile.cpu_seconds.is_none() && profile.memory_mb.is_none() {
+        return Ok(());
+    }
+    let mut limits = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+    if let Some(seconds) = profile.cpu_seconds {
+        // In 100ns units
+        limits.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_TIME;
+        limits.BasicLimitInformation.PerProcessUserTimeLimit = seconds as i64 * 10_000_000;
+    }
+    if let Some(mb) = profile.memory_mb {
+        limits.BasicLimitInformation.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+        limits.ProcessMemoryLimit = (mb * 1024 * 1024) as usize;
+    }
+    // The limits stay with the processes once the handle is closed
+    unsafe {
+        let job = CreateJobObjectW(None, None).map_err(|e| format!("Failed to limit the command: {}", e))?;
+        let result = SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &limits as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        )
+        .and_then(|()| AssignProcessToJobObject(job, HANDLE(child.as_raw_handle())));
+        let _ = CloseHandle(job);
+        result.map_err(|e| format!("Failed to limit the command: {}", e))
+    }
+}
+
+#[cfg(not(windows))]
+pub fn confine(_child: &std::process::Child, _profile: &SandboxProfile) -> Result<(), String> {
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_project_sandbox(app: AppHandle, project_path: String) -> Result<SandboxProfile, String> {
+    saved(&app, &project_path).await
+}
+
+// Sandbox the commands run in a project, or stop sandboxing them
+#[tauri::command]
+pub async fn set_project_sandbox(app: AppHandle, project_path: String, profile: SandboxProfile) -> Result<(), String> {
+    if !Path::new(&project_path).is_dir() {
+        return Err(format!("Project folder not found: {}", project_path));
+    }
+    if profile.cpu_seconds == Some(0) || profile.memory_mb == Some(0) {
+        return Err("Limits must be above zero".to_string());
+    }
+    let pool = db::pool(&app).await?;
+    sqlx::query(
+        "INSERT INTO project_sandbox
+            (project_path, enabled, allow_network, allow_project_writes, cpu_seconds, memory_mb, accept_limits_only)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT (project_path) DO UPDATE SET enabled = excluded.enabled,
+            allow_network = excluded.allow_network, allow_project_writes = excluded.allow_project_writes,
+            cpu_seconds = excluded.cpu_seconds, memory_mb = excluded.memory_mb,
+            accept_limits_only = excluded.accept_limits_only",
+    )
+    .bind(&project_path)
+    .bind(profile.enabled)
+    .bind(profile.allow_network)
+    .bind(profile.allow_project_writes)
+    .bind(profile.cpu_seconds.map(|seconds| seconds as i64))
+    .bind(profile.memory_mb.map(|mb| mb as i64))
+    .bind(profile.accept_limits_only)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("Failed to save sandbox settings: {}", e))?;
+    Ok(())
+}
+
+// What a sandbox can hold back here, for the settings UI to warn about
+// restrictions that won't apply
+#[tauri::command]
+pub async fn get_sandbox_support() -> Result<SandboxSupport, String> {
+    tauri::async_runtime::spawn_blocking(support)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,253 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use tauri::plugin::{Builder, TauriPlugin};
+use tauri::{AppHandle, Manager, Runtime, Wry};
+use tauri_plugin_fs::FsExt;
+
+use crate::{audit, fs_util, projects};
+
+// Where the persisted-scope plugin keeps the paths it has granted
+const SCOPE_STATE_FILENAME: &str = ".persisted-scope";
+const REVOCATIONS_FILENAME: &str = "scope-revocations.json";
+
+// The plugin's file, in the layout it writes with bincode
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct PersistedScope {
+    allowed_paths: Vec<String>,
+    forbidden_patterns: Vec<String>,
+}
+
+// The runtime scope can't drop a path once it's allowed, only forbid it on
+// top, and the plugin saves both lists. Revoked paths are recorded here and
+// scrubbed from its file on the next launch, before it loads: `revoked`
+// out of the allowed paths, and `forbidden` out of the forbidden ones, so
+// a path revoked this session can be granted again later.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct Revocations {
+    revoked: Vec<String>,
+    forbidden: Vec<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ScopeGrant {
+    pub path: String,
+    // "directory" with everything under it, "folder" for its direct
+    // children only, or "file"
+    pub kind: &'static str,
+    // The registered project at this path, if any
+    pub project: Option<String>,
+}
+
+fn app_data_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+// Scope patterns are globs with the special characters escaped
+fn unescape(pattern: &str) -> String {
+    pattern
+        .replace("[[]", "[")
+        .replace("[]]", "]")
+        .replace("[?]", "?")
+        .replace("[*]", "*")
+}
+
+// The path a pattern grants, with how much of it. The Windows verbatim
+// prefix is dropped, since each path is granted with and without it.
+fn grant(pattern: &str) -> (String, &'static str) {
+    let path = unescape(pattern);
+    let path = path.strip_prefix(r"\\?\").map(str::to_string).unwrap_or(path);
+    for (suffix, kind) in [("**", "directory"), ("*", "folder")] {
+        if let Some(dir) = path.strip_suffix(suffix) {
+            let dir = dir.trim_end_matches(['/', '\\']);
+            return (dir.to_string(), kind);
+        }
+    }
+    (path, "file")
+}
+
+// Grants with the same path compare equal however they were spelled
+fn same_path(a: &str, b: &str) -> bool {
+    let normalize = |path: &str| path.trim_end_matches(['/', '\\']).replace('\\', "/");
+    normalize(a) == normalize(b)
+}
+
+// A file that can't be decoded, e.g. after the plugin changed its format,
+// is an error rather than an empty scope, so it's never written over
+fn read_persisted(path: &Path) -> Result<PersistedScope, String> {
+    let contents = match std::fs::read(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(PersistedScope::default()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    bincode::deserialize(&contents).map_err(|e| format!("Failed to decode {}: {}", path.display(), e))
+}
+
+fn write_persisted(path: &Path, scope: &PersistedScope) -> Result<(), String> {
+    let contents = bincode::serialize(scope).map_err(|e| format!("Failed to encode fs scope: {}", e))?;
+    fs_util::write_atomic(path, &contents, false)
+}
+
+fn read_revocations(path: &Path) -> Revocations {
+    std::fs::read(path)
+        .ok()
+        .and_then(|contents| serde_json::from_slice(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn write_revocations(path: &Path, revocations: &Revocations) -> Result<(), String> {
+    let contents = serde_json::to_vec_pretty(revocations).map_err(|e| e.to_string())?;
+    fs_util::write_atomic(path, &contents, false)
+}
+
+fn scrub(scope: &mut PersistedScope, revocations: &Revocations) {
+    let listed = |paths: &[String], pattern: &str| paths.iter().any(|path| same_path(path, &grant(pattern).0));
+    scope.allowed_paths.retain(|pattern| !listed(&revocations.revoked, pattern));
+    scope.forbidden_patterns.retain(|pattern| !listed(&revocations.forbidden, pattern));
+}
+
+// Registered before the persisted-scope plugin, so revoked paths are gone
+// from its file by the time it loads it
+pub fn plugin() -> TauriPlugin<Wry> {
+    Builder::new("scope-revocations")
+        .setup(|app, _api| {
+            let Ok(dir) = app_data_dir(app) else { return Ok(()) };
+            let revocations_path = dir.join(REVOCATIONS_FILENAME);
+            if !revocations_path.exists() {
+                return Ok(());
+            }
+            let state_path = dir.join(SCOPE_STATE_FILENAME);
+            // The revocations stay for a later launch that can read it
+            let mut scope = match read_persisted(&state_path) {
+                Ok(scope) => scope,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return Ok(());
+                }
+            };
+            scrub(&mut scope, &read_revocations(&revocations_path));
+            match write_persisted(&state_path, &scope) {
+                Ok(()) => {
+                    let _ = std::fs::remove_file(&revocations_path);
+                }
+                Err(e) => eprintln!("{}", e),
+            }
+            Ok(())
+        })
+        .build()
+}
+
+// A path granted again is no longer to be scrubbed at the next launch
+pub fn unrevoke(app: &AppHandle, path: &str) {
+    let Ok(dir) = app_data_dir(app) else { return };
+    let revocations_path = dir.join(REVOCATIONS_FILENAME);
+    let mut revocations = read_revocations(&revocations_path);
+    let before = revocations.revoked.len();
+    revocations.revoked.retain(|revoked| !same_path(revoked, path));
+    if revocations.revoked.len() != before {
+        let _ = write_revocations(&revocations_path, &revocations);
+    }
+}
+
+fn grants(app: &AppHandle) -> BTreeMap<String, &'static str> {
+    let scope = app.fs_scope();
+    let forbidden: Vec<String> = scope
+        .forbidden_patterns()
+        .iter()
+        .map(|pattern| grant(pattern.as_str()).0)
+        .collect();
+    let mut grants: BTreeMap<String, &'static str> = BTreeMap::new();
+    for pattern in scope.allowed_patterns() {
+        let (path, kind) = grant(pattern.as_str());
+        // Revoked this session
+        if forbidden.iter().any(|forbidden| same_path(forbidden, &path)) {
+            continue;
+        }
+        // A directory is granted as itself and its contents; the contents
+        // say how much
+        let entry = grants.entry(path).or_insert(kind);
+        if *entry == "file" {
+            *entry = kind;
+        }
+    }
+    grants
+}
+
+// Stop the webview reaching a path now, and in later launches
+pub async fn revoke(app: &AppHandle, path: &str) -> Result<(), String> {
+    let Some((granted, kind)) = grants(app).into_iter().find(|(granted, _)| same_path(granted, path)) else {
+        return Err(format!("{} isn't in the fs scope", path));
+    };
+
+    // Forbidding a directory would shut out projects inside it too until
+    // the next launch, so those keep access until then
+    let projects = projects::all(app).await?;
+    let nested = projects
+        .iter()
+        .any(|project| !same_path(&project.path, &granted) && Path::new(&project.path).starts_with(&granted));
+    let dir = app_data_dir(app)?;
+    let revocations_path = dir.join(REVOCATIONS_FILENAME);
+    let mut revocations = read_revocations(&revocations_path);
+    if !nested {
+        let scope = app.fs_scope();
+        let forbidden = match kind {
+            "file" => scope.forbid_file(&granted),
+            kind => scope.forbid_directory(&granted, kind == "directory"),
+        };
+        forbidden.map_err(|e| format!("Failed to revoke {}: {}", granted, e))?;
+        revocations.forbidden.push(granted.clone());
+    }
+    revocations.revoked.push(granted.clone());
+    write_revocations(&revocations_path, &revocations)?;
+
+    // Also scrubbed now, in case the plugin doesn't save again this session
+    // The revocation above still applies at the next launch if the file
+    // can't be read now
+    let state_path = dir.join(SCOPE_STATE_FILENAME);
+    match read_persisted(&state_path) {
+        Ok(mut scope) => {
+            scrub(
+                &mut scope,
+                &Revocations {
+                    revoked: vec![granted.clone()],
+                    forbidden: Vec::new(),
+                },
+            );
+            write_persisted(&state_path, &scope)?;
+        }
+        Err(e) => eprintln!("{}", e),
+    }
+    audit::record(app, None, "scope", "revoke", &granted);
+    Ok(())
+}
+
+// Every path the webview has been given access to at runtime, for the
+// privacy settings
+#[tauri::command]
+pub async fn list_allowed_scopes(app: AppHandle) -> Result<Vec<ScopeGrant>, String> {
+    let projects = projects::all(&app).await?;
+    Ok(grants(&app)
+        .into_iter()
+        .map(|(path, kind)| ScopeGrant {
+            project: projects
+                .iter()
+                .find(|project| same_path(&project.path, &path))
+                .map(|project| project.name.clone()),
+            path,
+            kind,
+        })
+        .collect())
+}
+
+// Take away access to a path. A registered project can't be used without
+// access, so revoking its folder removes the project too. Granting the
+// path again takes effect from the next launch.
+#[tauri::command]
+pub async fn revoke_scope(app: AppHandle, path: String) -> Result<(), String> {
+    if let Some(project) = projects::all(&app).await?.into_iter().find(|project| same_path(&project.path, &path)) {
+        return projects::remove_project(app, project.id).await;
+    }
+    revoke(&app, &path).await
+}
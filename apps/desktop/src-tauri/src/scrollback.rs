@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+
+const MAX_LINES: usize = 10_000;
+const MAX_BYTES: usize = 4 * 1024 * 1024;
+
+// Recent output of a terminal or command, by line. Line numbers keep
+// counting as old lines are dropped, so callers can ask for what's new.
+#[derive(Default)]
+pub struct Scrollback {
+    // Each with its "\n"
+    lines: VecDeque<Vec<u8>>,
+    // The number of the first line still kept
+    first_line: usize,
+    // Output after the last "\n", e.g. a shell prompt
+    partial: Vec<u8>,
+    bytes: usize,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct ScrollbackChunk {
+    // Later than asked for when the lines before it were dropped
+    pub from_line: usize,
+    // What to ask from next time
+    pub next_line: usize,
+    // Output as it was written. An unfinished last line comes back each
+    // time until it ends.
+    pub data: String,
+    pub truncated: bool,
+}
+
+impl Scrollback {
+    pub fn push(&mut self, mut data: &[u8]) {
+        while let Some(end) = data.iter().position(|&byte| byte == b'\n') {
+            let mut line = std::mem::take(&mut self.partial);
+            line.extend_from_slice(&data[..=end]);
+            self.bytes += end + 1;
+            self.lines.push_back(line);
+            data = &data[end + 1..];
+        }
+        self.partial.extend_from_slice(data);
+        self.bytes += data.len();
+
+        while self.lines.len() > MAX_LINES || (self.bytes > MAX_BYTES && !self.lines.is_empty()) {
+            if let Some(line) = self.lines.pop_front() {
+                self.bytes -= line.len();
+                self.first_line += 1;
+            }
+        }
+        // A progress bar redrawn with "\r" never ends its line
+        if self.partial.len() > MAX_BYTES {
+            let excess = self.partial.len() - MAX_BYTES;
+            self.partial.drain(..excess);
+            self.bytes -= excess;
+        }
+    }
+
+    pub fn push_line(&mut self, line: &str) {
+        self.push(line.as_bytes());
+        self.push(b"\n");
+    }
+
+    pub fn since(&self, from_line: usize) -> ScrollbackChunk {
+        let start = from_line.max(self.first_line);
+        let next_line = self.first_line + self.lines.len();
+        let mut data = Vec::new();
+        for line in self.lines.iter().skip(start - self.first_line) {
+            data.extend_from_slice(line);
+        }
+        data.extend_from_slice(&self.partial);
+        ScrollbackChunk {
+            from_line: start.min(next_line),
+            next_line,
+            data: String::from_utf8_lossy(&data).to_string(),
+            truncated: from_line < self.first_line,
+        }
+    }
+}
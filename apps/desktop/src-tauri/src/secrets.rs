@@ -0,0 +1,128 @@
+use std::sync::Mutex;
+
+use tauri::{AppHandle, WebviewWindow};
+
+use crate::{app_lock, audit, redact};
+
+const KEYCHAIN_SERVICE: &str = "Incito";
+// Keychains can't be listed portably, so the names are kept in an entry of
+// their own
+const NAMES_ACCOUNT: &str = "secret-names";
+const MAX_NAME_LENGTH: usize = 128;
+
+// Serialises updates to the list of names
+static NAMES_LOCK: Mutex<()> = Mutex::new(());
+
+fn entry(account: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, account).map_err(|e| format!("Failed to open the system keychain: {}", e))
+}
+
+// e.g. "anthropic-api-key" or "webhook:slack"
+fn check_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LENGTH
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+    if !valid {
+        return Err(format!("Invalid secret name: {}", name));
+    }
+    Ok(())
+}
+
+fn account(name: &str) -> String {
+    format!("secret:{}", name)
+}
+
+fn names() -> Result<Vec<String>, String> {
+    match entry(NAMES_ACCOUNT)?.get_password() {
+        Ok(names) => Ok(serde_json::from_str(&names).unwrap_or_default()),
+        Err(keyring::Error::NoEntry) => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read secrets from the system keychain: {}", e)),
+    }
+}
+
+fn update_names(update: impl FnOnce(&mut Vec<String>)) -> Result<(), String> {
+    let _guard = NAMES_LOCK.lock().map_err(|e| e.to_string())?;
+    let mut list = names()?;
+    update(&mut list);
+    list.sort();
+    list.dedup();
+    let contents = serde_json::to_string(&list).map_err(|e| e.to_string())?;
+    entry(NAMES_ACCOUNT)?
+        .set_password(&contents)
+        .map_err(|e| format!("Failed to save to the system keychain: {}", e))
+}
+
+// Every value saved, for redaction. Ones that can't be read are left out.
+pub fn values() -> Vec<String> {
+    names()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|name| get(name).ok().flatten())
+        .collect()
+}
+
+pub fn get(name: &str) -> Result<Option<String>, String> {
+    check_name(name)?;
+    match entry(&account(name))?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read {} from the system keychain: {}", name, e)),
+    }
+}
+
+pub fn set(name: &str, value: &str) -> Result<(), String> {
+    check_name(name)?;
+    entry(&account(name))?
+        .set_password(value)
+        .map_err(|e| format!("Failed to save {} to the system keychain: {}", name, e))?;
+    redact::remember(value);
+    update_names(|names| names.push(name.to_string()))
+}
+
+pub fn delete(name: &str) -> Result<(), String> {
+    check_name(name)?;
+    if let Ok(Some(value)) = get(name) {
+        redact::forget(&value);
+    }
+    match entry(&account(name))?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(format!("Failed to remove {} from the system keychain: {}", name, e)),
+    }
+    update_names(|names| names.retain(|known| known != name))
+}
+
+// API keys, tokens and webhook URLs go in the OS keychain (Keychain on
+// macOS, the Credential Manager on Windows, the Secret Service on Linux),
+// never in the database or localStorage
+#[tauri::command]
+pub async fn set_secret(app: AppHandle, window: WebviewWindow, name: String, value: String) -> Result<(), String> {
+    audit::record(&app, Some(window.label()), "secret", "write", &name);
+    tauri::async_runtime::spawn_blocking(move || set(&name, &value))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn get_secret(app: AppHandle, window: WebviewWindow, name: String) -> Result<Option<String>, String> {
+    app_lock::ensure_unlocked(&app)?;
+    audit::record(&app, Some(window.label()), "secret", "read", &name);
+    tauri::async_runtime::spawn_blocking(move || get(&name))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn delete_secret(app: AppHandle, window: WebviewWindow, name: String) -> Result<(), String> {
+    audit::record(&app, Some(window.label()), "secret", "delete", &name);
+    tauri::async_runtime::spawn_blocking(move || delete(&name))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// The names of the secrets saved, not their values
+#[tauri::command]
+pub async fn list_secret_names() -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(names)
+        .await
+        .map_err(|e| e.to_string())?
+}
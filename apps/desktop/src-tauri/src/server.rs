@@ -0,0 +1,267 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
+use tauri_plugin_shell::ShellExt;
+
+use crate::{
+    approvals, audit, claude_cli, connection, menu_state, process_tree, profiles, redact, settings, sidecar,
+    sidecar_bridge, tray,
+};
+
+// Marks an approval request among the sidecar's output
+const APPROVAL_PREFIX: &str = "@incito:approval ";
+
+// In the logs folder. Past the limit it's kept as sidecar.log.old and a new
+// one started.
+const LOG_FILENAME: &str = "sidecar.log";
+const OLD_LOG_FILENAME: &str = "sidecar.log.old";
+const LOG_MAX_BYTES: u64 = 5 * 1024 * 1024;
+const DEFAULT_LOG_LINES: usize = 200;
+const MAX_LOG_LINES: usize = 5000;
+
+// State to track the Claude Code server process
+#[derive(Default)]
+pub struct ClaudeCodeState {
+    process: Mutex<Option<CommandChild>>,
+}
+
+// In remote mode, whether the remote server is connected
+pub fn is_running(app: &AppHandle) -> bool {
+    if connection::is_remote(app) {
+        return connection::is_connected(app);
+    }
+    app.state::<ClaudeCodeState>()
+        .process
+        .lock()
+        .map(|process| process.is_some())
+        .unwrap_or(false)
+}
+
+pub fn status_changed(app: &AppHandle) {
+    let running = is_running(app);
+    tray::set_server_status(app, running);
+    menu_state::sync(app);
+    let _ = app.emit("claude-server-status-changed", running);
+}
+
+// Spawn the sidecar, or in remote mode connect to the remote server and
+// return the SSH tunnel's pid (0 without one). Without an explicit path,
+// the active profile or the saved executable setting is used. `origin` is
+// what asked for it, for the audit log: a window label, "tray", "dock" and
+// so on.
+pub async fn start(app: &AppHandle, executable_path: Option<String>, origin: &str) -> Result<u32, String> {
+    if connection::is_remote(app) {
+        let pid = connection::connect(app, origin).await?;
+        status_changed(app);
+        return Ok(pid);
+    }
+    // An active executable profile takes precedence over the path passed in
+    let profile = profiles::active_profile(app).await?;
+    let executable_path = match profile.as_ref().map(|profile| profile.executable_path.clone()) {
+        Some(path) => Some(path),
+        None if executable_path.is_some() => executable_path,
+        None => settings::get(app).server.claude_code_executable_path,
+    };
+
+    let state = app.state::<ClaudeCodeState>();
+    let mut process_guard = state.process.lock().map_err(|e| e.to_string())?;
+
+    if process_guard.is_some() {
+        return Err("Claude Code server is already running".to_string());
+    }
+
+    // A separately updated build wins over the bundled one
+    let mut command = match sidecar::active_binary(app) {
+        Some(path) => app.shell().command(path),
+        None => app
+            .shell()
+            .sidecar("claude-code-server")
+            .map_err(|e| format!("Failed to create sidecar command: {}", e))?,
+    };
+
+    // Pass custom executable path as command line argument. Windows npm
+    // shims are resolved to the script they wrap so the SDK can run them.
+    if let Some(ref path) = executable_path {
+        if !path.is_empty() {
+            let launcher = claude_cli::ClaudeLauncher::resolve(std::path::Path::new(path));
+            let path = launcher.sidecar_path().to_string_lossy().to_string();
+            command = command
+                .env("CLAUDE_CODE_EXECUTABLE_PATH", &path)
+                .args(["--claude-path", &path]);
+        }
+    }
+
+    if let Some(profile) = profile {
+        command = command.envs(profile.env);
+    }
+
+    let (mut events, child) = command
+        .spawn()
+        .map_err(|e| format!("Failed to spawn sidecar: {}", e))?;
+
+    let pid = child.pid();
+    *process_guard = Some(child);
+    drop(process_guard);
+
+    // Clear the state when the sidecar exits on its own, so the UI and tray
+    // don't keep showing a dead server as running
+    let handle = app.clone();
+    let mut log = open_log(app);
+    tauri::async_runtime::spawn(async move {
+        while let Some(event) = events.recv().await {
+            match &event {
+                CommandEvent::Stdout(line) if !on_stdout(&handle, line) => write_log(&mut log, "stdout", line),
+                CommandEvent::Stderr(line) => write_log(&mut log, "stderr", line),
+                _ => {}
+            }
+            if let CommandEvent::Terminated(_) = event {
+                let state = handle.state::<ClaudeCodeState>();
+                if let Ok(mut process) = state.process.lock() {
+                    if process.as_ref().is_some_and(|child| child.pid() == pid) {
+                        *process = None;
+                    }
+                }
+                status_changed(&handle);
+                break;
+            }
+        }
+    });
+
+    audit::record(app, Some(origin), "sidecar", "start", &pid.to_string());
+    status_changed(app);
+    Ok(pid)
+}
+
+// The sidecar asks before a tool runs a shell command or writes outside
+// the project by printing a prefixed JSON line, and reads the answer from
+// stdin as {"id", "allowed"}. Returns whether `line` was one.
+fn on_stdout(app: &AppHandle, line: &[u8]) -> bool {
+    let line = String::from_utf8_lossy(line);
+    let Some(request) = line.trim().strip_prefix(APPROVAL_PREFIX) else {
+        return false;
+    };
+    let Ok(request) = serde_json::from_str::<approvals::ApprovalRequest>(request) else {
+        return false;
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let id = request.id.clone();
+        let allowed = approvals::request(&app, request).await.unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            false
+        });
+        let reply = serde_json::json!({ "id": id, "allowed": allowed });
+        let state = app.state::<ClaudeCodeState>();
+        if let Ok(mut process) = state.process.lock() {
+            if let Some(child) = process.as_mut() {
+                let _ = child.write(format!("{}\n", reply).as_bytes());
+            }
+        };
+    });
+    true
+}
+
+fn open_log(app: &AppHandle) -> Option<File> {
+    let dir = app.path().app_log_dir().ok()?;
+    std::fs::create_dir_all(&dir).ok()?;
+    let path = dir.join(LOG_FILENAME);
+    if std::fs::metadata(&path).is_ok_and(|metadata| metadata.len() > LOG_MAX_BYTES) {
+        let _ = std::fs::rename(&path, dir.join(OLD_LOG_FILENAME));
+    }
+    OpenOptions::new().create(true).append(true).open(path).ok()
+}
+
+// The sidecar prints whatever the SDK and Claude Code do, keys and tokens
+// included, so lines are redacted before they're written
+fn write_log(log: &mut Option<File>, stream: &str, line: &[u8]) {
+    if let Some(file) = log {
+        let line = redact::redact(String::from_utf8_lossy(line).trim_end());
+        let _ = writeln!(file, "{} [{}] {}", chrono::Utc::now().to_rfc3339(), stream, line);
+    }
+}
+
+// The sidecar and the Claude Code processes it started. Without `force`
+// they get the chance to exit cleanly. A remote server is only
+// disconnected from.
+pub fn stop(app: &AppHandle, force: bool, origin: &str) -> Result<(), String> {
+    connection::disconnect(app, origin)?;
+    let state = app.state::<ClaudeCodeState>();
+    let child = state.process.lock().map_err(|e| e.to_string())?.take();
+
+    if let Some(child) = child {
+        let pid = child.pid();
+        if process_tree::kill_tree(pid, force).is_err() {
+            child.kill().map_err(|e| format!("Failed to kill process: {}", e))?;
+        }
+        audit::record(app, Some(origin), "sidecar", "stop", &pid.to_string());
+    }
+
+    status_changed(app);
+    Ok(())
+}
+
+pub async fn restart(app: &AppHandle, origin: &str) -> Result<u32, String> {
+    stop(app, false, origin)?;
+    start(app, None, origin).await
+}
+
+#[tauri::command]
+pub async fn start_claude_code_server(
+    app: AppHandle,
+    window: WebviewWindow,
+    executable_path: Option<String>,
+) -> Result<u32, String> {
+    start(&app, executable_path, window.label()).await
+}
+
+#[tauri::command]
+pub async fn stop_claude_code_server(app: AppHandle, window: WebviewWindow, force: Option<bool>) -> Result<(), String> {
+    stop(&app, force.unwrap_or(false), window.label())
+}
+
+#[tauri::command]
+pub async fn get_claude_code_server_status(app: AppHandle) -> Result<bool, String> {
+    Ok(is_running(&app))
+}
+
+fn local_log_lines(app: &AppHandle, lines: usize) -> Result<Vec<String>, String> {
+    let path = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| e.to_string())?
+        .join(LOG_FILENAME);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    let all: Vec<&str> = contents.lines().collect();
+    Ok(all[all.len().saturating_sub(lines)..].iter().map(|line| line.to_string()).collect())
+}
+
+// The last `lines` lines the server logged, oldest first: the sidecar's log
+// file, or from a remote server its /logs endpoint
+#[tauri::command]
+pub async fn get_server_logs(app: AppHandle, lines: Option<usize>) -> Result<Vec<String>, String> {
+    let lines = lines.unwrap_or(DEFAULT_LOG_LINES).clamp(1, MAX_LOG_LINES);
+    if !connection::is_remote(&app) {
+        return tauri::async_runtime::spawn_blocking(move || local_log_lines(&app, lines))
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let response =
+        sidecar_bridge::sidecar_request(app, "GET".to_string(), format!("/logs?lines={}", lines), None, None).await?;
+    if response.status != 200 {
+        return Err(format!("The remote server answered {} for its logs", response.status));
+    }
+    let remote_lines: Vec<String> = response
+        .body
+        .get("lines")
+        .and_then(|lines| serde_json::from_value(lines.clone()).ok())
+        .ok_or("The remote server sent logs in an unexpected form")?;
+    // Redacted here too, with the secrets this machine knows about
+    Ok(remote_lines.iter().map(|line| redact::redact(line)).collect())
+}
@@ -0,0 +1,325 @@
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use pulldown_cmark::{html, Event, Parser};
+use serde_json::Value;
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+use tokio::sync::oneshot;
+
+use crate::transcripts::{self, ContentBlock, TranscriptEntry};
+use crate::{audit, fs_util, i18n, redact, sessions};
+
+// Tool output past this is cut, so one huge file read doesn't swamp the export
+const TOOL_RESULT_MAX_CHARS: usize = 4000;
+
+const HTML_STYLE: &str = "body{font:15px/1.55 -apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;max-width:820px;margin:40px auto;padding:0 20px;color:#1f2328;background:#fff}\
+header{border-bottom:1px solid #d0d7de;margin-bottom:24px}\
+header p{color:#59636e;margin:4px 0}\
+.message{margin:20px 0;padding:12px 16px;border-radius:8px}\
+.user{background:#f0f6ff}\
+.assistant{background:#f6f8fa}\
+.role{font-weight:600;font-size:13px;color:#59636e;margin-bottom:6px}\
+pre{background:#fff;border:1px solid #d0d7de;border-radius:6px;padding:10px;overflow-x:auto;font:13px/1.45 ui-monospace,SFMono-Regular,Menlo,monospace}\
+code{font:13px ui-monospace,SFMono-Regular,Menlo,monospace}\
+details{margin:8px 0}\
+summary{cursor:pointer;color:#59636e;font-size:13px}\
+@media(prefers-color-scheme:dark){body{color:#e6edf3;background:#0d1117}.user{background:#132339}.assistant{background:#161b22}pre{background:#0d1117;border-color:#30363d}header{border-color:#30363d}}";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl Format {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "html" => Ok(Self::Html),
+            "json" => Ok(Self::Json),
+            other => Err(format!("Unknown export format: {}", other)),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
+}
+
+// One rendered turn: who spoke and what they said, in transcript order
+struct Turn<'a> {
+    role: &'a str,
+    timestamp: Option<&'a str>,
+    blocks: Vec<&'a ContentBlock>,
+}
+
+struct Session<'a> {
+    id: &'a str,
+    title: String,
+    project_path: Option<&'a str>,
+    started_at: Option<&'a str>,
+    turns: Vec<Turn<'a>>,
+}
+
+// Text the CLI wraps in tags (slash commands, local command output) is
+// bookkeeping rather than conversation
+fn is_bookkeeping(text: &str) -> bool {
+    text.trim_start().starts_with('<')
+}
+
+fn shown(block: &ContentBlock) -> bool {
+    match block {
+        ContentBlock::Text { text } => !text.trim().is_empty() && !is_bookkeeping(text),
+        ContentBlock::ToolUse { .. } | ContentBlock::ToolResult { .. } | ContentBlock::Image { .. } => true,
+        ContentBlock::Thinking { .. } | ContentBlock::Unknown => false,
+    }
+}
+
+fn collect<'a>(session_id: &'a str, entries: &'a [TranscriptEntry]) -> Session<'a> {
+    let summary = entries.iter().find_map(|entry| entry.summary.clone());
+    let first_prompt = entries.iter().find_map(sessions::prompt_text);
+    let turns = entries
+        .iter()
+        .filter(|entry| (entry.entry_type == "user" || entry.entry_type == "assistant") && !entry.is_meta && !entry.is_sidechain)
+        .filter_map(|entry| {
+            let blocks: Vec<&ContentBlock> = entry.message.as_ref()?.content.iter().filter(|block| shown(block)).collect();
+            (!blocks.is_empty()).then_some(Turn {
+                role: &entry.entry_type,
+                timestamp: entry.timestamp.as_deref(),
+                blocks,
+            })
+        })
+        .collect();
+
+    Session {
+        id: session_id,
+        title: summary.or(first_prompt).unwrap_or_else(|| session_id.to_string()),
+        project_path: entries.iter().find_map(|entry| entry.cwd.as_deref()),
+        started_at: entries.iter().find_map(|entry| entry.timestamp.as_deref()),
+        turns,
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}\n… (truncated)", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+// Tool results are a string or a list of text blocks
+fn tool_result_text(content: &Value) -> String {
+    let text = match content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter_map(|block| block.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    truncate(&text, TOOL_RESULT_MAX_CHARS)
+}
+
+fn tool_input_text(input: &Value) -> String {
+    serde_json::to_string_pretty(input).unwrap_or_default()
+}
+
+// A fence longer than any backtick run inside, so code containing ``` stays
+// in one block
+fn fence(text: &str, language: &str) -> String {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}\n", fence, language, text.trim_end_matches('\n'), fence)
+}
+
+fn role_label(role: &str) -> &'static str {
+    if role == "user" {
+        "User"
+    } else {
+        "Assistant"
+    }
+}
+
+fn render_markdown(session: &Session) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {}\n", session.title.lines().next().unwrap_or_default());
+    let _ = writeln!(out, "- Session: `{}`", session.id);
+    if let Some(project_path) = session.project_path {
+        let _ = writeln!(out, "- Project: `{}`", project_path);
+    }
+    if let Some(started_at) = session.started_at {
+        let _ = writeln!(out, "- Started: {}", started_at);
+    }
+
+    for turn in &session.turns {
+        let _ = write!(out, "\n## {}", role_label(turn.role));
+        if let Some(timestamp) = turn.timestamp {
+            let _ = write!(out, " · {}", timestamp);
+        }
+        out.push_str("\n\n");
+        for block in &turn.blocks {
+            match block {
+                ContentBlock::Text { text } => {
+                    let _ = writeln!(out, "{}\n", text.trim());
+                }
+                ContentBlock::ToolUse { name, input, .. } => {
+                    let _ = writeln!(out, "**Tool call: {}**\n\n{}", name, fence(&tool_input_text(input), "json"));
+                }
+                ContentBlock::ToolResult { content, is_error, .. } => {
+                    let label = if *is_error { "Tool error" } else { "Tool result" };
+                    let _ = writeln!(out, "**{}**\n\n{}", label, fence(&tool_result_text(content), ""));
+                }
+                ContentBlock::Image { .. } => out.push_str("*[image]*\n\n"),
+                ContentBlock::Thinking { .. } | ContentBlock::Unknown => {}
+            }
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// Markdown from the model rendered to HTML, with any raw HTML in it shown as
+// text rather than injected into the page
+fn markdown_to_html(text: &str) -> String {
+    let events = Parser::new(text).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        event => event,
+    });
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+fn render_html(session: &Session) -> String {
+    let title = escape_html(session.title.lines().next().unwrap_or_default());
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<header>\n<h1>{}</h1>\n<p>Session <code>{}</code></p>\n",
+        title,
+        HTML_STYLE,
+        title,
+        escape_html(session.id)
+    );
+    if let Some(project_path) = session.project_path {
+        let _ = writeln!(out, "<p>Project <code>{}</code></p>", escape_html(project_path));
+    }
+    if let Some(started_at) = session.started_at {
+        let _ = writeln!(out, "<p>Started {}</p>", escape_html(started_at));
+    }
+    out.push_str("</header>\n");
+
+    for turn in &session.turns {
+        let _ = write!(
+            out,
+            "<section class=\"message {}\">\n<div class=\"role\">{}",
+            turn.role,
+            role_label(turn.role)
+        );
+        if let Some(timestamp) = turn.timestamp {
+            let _ = write!(out, " · {}", escape_html(timestamp));
+        }
+        out.push_str("</div>\n");
+        for block in &turn.blocks {
+            match block {
+                ContentBlock::Text { text } => out.push_str(&markdown_to_html(text)),
+                ContentBlock::ToolUse { name, input, .. } => {
+                    let _ = writeln!(
+                        out,
+                        "<details><summary>Tool call: {}</summary><pre>{}</pre></details>",
+                        escape_html(name),
+                        escape_html(&tool_input_text(input))
+                    );
+                }
+                ContentBlock::ToolResult { content, is_error, .. } => {
+                    let label = if *is_error { "Tool error" } else { "Tool result" };
+                    let _ = writeln!(
+                        out,
+                        "<details><summary>{}</summary><pre>{}</pre></details>",
+                        label,
+                        escape_html(&tool_result_text(content))
+                    );
+                }
+                ContentBlock::Image { .. } => out.push_str("<p><em>[image]</em></p>\n"),
+                ContentBlock::Thinking { .. } | ContentBlock::Unknown => {}
+            }
+        }
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+// Every parsed entry, bookkeeping included, for tools that want the lot
+fn render_json(session: &Session, entries: &[TranscriptEntry]) -> Result<String, String> {
+    let export = serde_json::json!({
+        "session_id": session.id,
+        "title": session.title,
+        "project_path": session.project_path,
+        "started_at": session.started_at,
+        "entries": entries,
+    });
+    serde_json::to_string_pretty(&export).map_err(|e| e.to_string())
+}
+
+async fn pick_destination(app: &AppHandle, session_id: &str, format: Format) -> Option<PathBuf> {
+    let (tx, rx) = oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title(i18n::t(app, "dialogs.exportSession"))
+        .set_file_name(format!("{}.{}", session_id, format.extension()))
+        .add_filter(format.extension().to_uppercase(), &[format.extension()])
+        .save_file(move |path| {
+            let _ = tx.send(path.and_then(|path| path.into_path().ok()));
+        });
+    rx.await.ok().flatten()
+}
+
+// Render a session as "markdown", "html" or "json" and write it wherever
+// the user picks in a save dialog, never a path the caller chose. Returns
+// the path written, or None if the dialog was cancelled.
+#[tauri::command]
+pub async fn export_session(app: AppHandle, session_id: String, format: String) -> Result<Option<String>, String> {
+    let format = Format::parse(&format)?;
+    let Some(dest) = pick_destination(&app, &session_id, format).await else {
+        return Ok(None);
+    };
+
+    let path = transcripts::find_session_file(&session_id)?;
+    let contents = tauri::async_runtime::spawn_blocking(move || {
+        let (entries, _) = transcripts::parse_from(&path, 0, None)?;
+        let session = collect(&session_id, &entries);
+        let contents = match format {
+            Format::Markdown => render_markdown(&session),
+            Format::Html => render_html(&session),
+            Format::Json => render_json(&session, &entries)?,
+        };
+        // Keys and tokens read from files or printed by commands end up in
+        // tool output
+        Ok::<_, String>(redact::redact(&contents))
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))??;
+
+    fs_util::write_atomic(&dest, contents.as_bytes(), false)?;
+    audit::record(&app, None, "file", "export", &dest.to_string_lossy());
+    Ok(Some(dest.to_string_lossy().to_string()))
+}
@@ -0,0 +1,564 @@
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use sqlx::{SqliteConnection, SqlitePool};
+use tauri::{AppHandle, Emitter};
+
+use crate::db;
+use crate::transcripts::{self, ContentBlock, TranscriptEntry};
+
+#[derive(Clone, serde::Serialize)]
+pub struct SessionSummary {
+    pub id: String,
+    pub project_path: String,
+    pub file_path: String,
+    pub first_prompt: Option<String>,
+    pub started_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub message_count: i64,
+    pub file_size: i64,
+}
+
+type SessionRow = (String, String, String, Option<String>, Option<String>, Option<String>, i64, i64);
+
+fn from_row(
+    (id, project_path, file_path, first_prompt, started_at, updated_at, message_count, file_size): SessionRow,
+) -> SessionSummary {
+    SessionSummary {
+        id,
+        project_path,
+        file_path,
+        first_prompt,
+        started_at,
+        updated_at,
+        message_count,
+        file_size,
+    }
+}
+
+const SELECT_SESSIONS: &str = "SELECT id, project_path, file_path, first_prompt, started_at, updated_at, message_count, file_size FROM claude_sessions";
+
+const FIRST_PROMPT_MAX_CHARS: usize = 500;
+
+// Wrapped around matched terms by snippet(), then turned into `highlights`
+const HIGHLIGHT_START: char = '\u{2}';
+const HIGHLIGHT_END: char = '\u{3}';
+const SNIPPET_TOKENS: i64 = 24;
+
+// What the index needs to know about a slice of transcript entries
+#[derive(Default)]
+struct SessionStats {
+    project_path: Option<String>,
+    first_prompt: Option<String>,
+    started_at: Option<String>,
+    updated_at: Option<String>,
+    message_count: i64,
+}
+
+fn is_conversation_message(entry: &TranscriptEntry) -> bool {
+    (entry.entry_type == "user" || entry.entry_type == "assistant") && !entry.is_meta
+}
+
+// The first thing the user typed, skipping tool results and slash-command
+// bookkeeping the CLI records as user messages
+pub fn prompt_text(entry: &TranscriptEntry) -> Option<String> {
+    if entry.entry_type != "user" || entry.is_meta || entry.is_sidechain {
+        return None;
+    }
+    let message = entry.message.as_ref()?;
+    let text = message.content.iter().find_map(|block| match block {
+        ContentBlock::Text { text } => Some(text.trim()),
+        _ => None,
+    })?;
+    if text.is_empty() || text.starts_with('<') {
+        return None;
+    }
+    Some(text.chars().take(FIRST_PROMPT_MAX_CHARS).collect())
+}
+
+// The searchable text of a user or assistant message: its text blocks,
+// without tool calls, thinking or the CLI's bookkeeping
+fn message_text(entry: &TranscriptEntry) -> Option<String> {
+    if !is_conversation_message(entry) {
+        return None;
+    }
+    let text = entry
+        .message
+        .as_ref()?
+        .content
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::Text { text } if !text.trim_start().starts_with('<') => Some(text.trim()),
+            _ => None,
+        })
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    (!text.is_empty()).then_some(text)
+}
+
+async fn index_messages(
+    conn: &mut SqliteConnection,
+    session_id: &str,
+    entries: &[TranscriptEntry],
+    replace: bool,
+) -> Result<usize, String> {
+    if replace {
+        sqlx::query("DELETE FROM claude_messages_fts WHERE session_id = ?")
+            .bind(session_id)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("Failed to update message search index: {}", e))?;
+    }
+    let mut indexed = 0;
+    for entry in entries {
+        let Some(text) = message_text(entry) else {
+            continue;
+        };
+        sqlx::query(
+            "INSERT INTO claude_messages_fts (content, session_id, message_uuid, role, timestamp) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(text)
+        .bind(session_id)
+        .bind(&entry.uuid)
+        .bind(&entry.entry_type)
+        .bind(&entry.timestamp)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update message search index: {}", e))?;
+        indexed += 1;
+    }
+    Ok(indexed)
+}
+
+fn collect_stats(entries: &[TranscriptEntry]) -> SessionStats {
+    let mut stats = SessionStats::default();
+    for entry in entries {
+        if stats.project_path.is_none() {
+            stats.project_path = entry.cwd.clone();
+        }
+        if stats.first_prompt.is_none() {
+            stats.first_prompt = prompt_text(entry);
+        }
+        if let Some(timestamp) = &entry.timestamp {
+            if stats.started_at.is_none() {
+                stats.started_at = Some(timestamp.clone());
+            }
+            stats.updated_at = Some(timestamp.clone());
+        }
+        if is_conversation_message(entry) {
+            stats.message_count += 1;
+        }
+    }
+    stats
+}
+
+// Project directories are the project path with separators replaced by '-',
+// which can't be reversed reliably; it's only used when no entry has a cwd
+fn decode_project_dir(dir_name: &str) -> String {
+    dir_name.replace('-', "/")
+}
+
+fn file_mtime(metadata: &std::fs::Metadata) -> i64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+#[derive(Clone, serde::Serialize)]
+struct IndexProgress {
+    scanned: usize,
+    total: usize,
+}
+
+// Bring the index up to date with the transcripts on disk. Unchanged files
+// are skipped and grown files are only parsed from where indexing stopped.
+pub async fn refresh_index(app: &AppHandle) -> Result<usize, String> {
+    let pool = db::pool(app).await?;
+    let files = transcripts::list_transcript_files()?;
+    let total = files.len();
+    let mut updated = 0;
+    for (index, (dir_name, path)) in files.into_iter().enumerate() {
+        if index_file(&pool, &dir_name, &path).await? {
+            updated += 1;
+        }
+        if index % 50 == 0 {
+            let _ = app.emit("session-index-progress", IndexProgress { scanned: index + 1, total });
+        }
+    }
+
+    // Drop sessions whose transcripts were deleted
+    let rows: Vec<(String, String)> = sqlx::query_as("SELECT id, file_path FROM claude_sessions")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("Failed to read session index: {}", e))?;
+    for (id, file_path) in rows {
+        if !Path::new(&file_path).exists() {
+            sqlx::query("DELETE FROM claude_sessions WHERE id = ?")
+                .bind(&id)
+                .execute(&pool)
+                .await
+                .map_err(|e| format!("Failed to prune session index: {}", e))?;
+            sqlx::query("DELETE FROM claude_messages_fts WHERE session_id = ?")
+                .bind(&id)
+                .execute(&pool)
+                .await
+                .map_err(|e| format!("Failed to prune message search index: {}", e))?;
+        }
+    }
+
+    let _ = app.emit("session-index-progress", IndexProgress { scanned: total, total });
+    Ok(updated)
+}
+
+// A transcript the index is behind on, and where to read it from
+pub struct PendingFile {
+    pub session_id: String,
+    pub dir_name: String,
+    pub path: PathBuf,
+    pub file_size: i64,
+    pub mtime: i64,
+    pub from_offset: u64,
+}
+
+// None when the index already has everything in the file, or a retention
+// rule removed it and it hasn't changed since
+pub async fn pending_file(pool: &SqlitePool, dir_name: &str, path: &Path) -> Result<Option<PendingFile>, String> {
+    let Some(session_id) = path.file_stem().map(|stem| stem.to_string_lossy().to_string()) else {
+        return Ok(None);
+    };
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return Ok(None);
+    };
+    let file_size = metadata.len() as i64;
+    let mtime = file_mtime(&metadata);
+
+    let pruned: Option<(i64,)> = sqlx::query_as("SELECT file_mtime FROM claude_sessions_pruned WHERE id = ?")
+        .bind(&session_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("Failed to read session index: {}", e))?;
+    match pruned {
+        Some((pruned_mtime,)) if pruned_mtime == mtime => return Ok(None),
+        // Written to again since it was pruned, so it's indexed again
+        Some(_) => {
+            sqlx::query("DELETE FROM claude_sessions_pruned WHERE id = ?")
+                .bind(&session_id)
+                .execute(pool)
+                .await
+                .map_err(|e| format!("Failed to update session index: {}", e))?;
+        }
+        None => {}
+    }
+
+    let existing: Option<(i64, i64, i64)> =
+        sqlx::query_as("SELECT file_size, file_mtime, indexed_offset FROM claude_sessions WHERE id = ?")
+            .bind(&session_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("Failed to read session index: {}", e))?;
+
+    let from_offset = match existing {
+        Some((size, indexed_mtime, _)) if size == file_size && indexed_mtime == mtime => return Ok(None),
+        // Appended to since the last scan
+        Some((size, _, offset)) if file_size > size => offset as u64,
+        // New, or rewritten in place
+        _ => 0,
+    };
+
+    Ok(Some(PendingFile {
+        session_id,
+        dir_name: dir_name.to_string(),
+        path: path.to_path_buf(),
+        file_size,
+        mtime,
+        from_offset,
+    }))
+}
+
+// Write what was parsed from a pending file, inside the caller's
+// transaction. Returns how many messages were made searchable.
+pub async fn save_file(
+    conn: &mut SqliteConnection,
+    file: &PendingFile,
+    entries: &[TranscriptEntry],
+    next_offset: u64,
+) -> Result<usize, String> {
+    let stats = collect_stats(entries);
+    let indexed = index_messages(conn, &file.session_id, entries, file.from_offset == 0).await?;
+
+    if file.from_offset > 0 {
+        sqlx::query(
+            "UPDATE claude_sessions SET
+                first_prompt = COALESCE(first_prompt, ?),
+                started_at = COALESCE(started_at, ?),
+                updated_at = COALESCE(?, updated_at),
+                message_count = message_count + ?,
+                file_size = ?, file_mtime = ?, indexed_offset = ?
+             WHERE id = ?",
+        )
+        .bind(stats.first_prompt)
+        .bind(stats.started_at)
+        .bind(stats.updated_at)
+        .bind(stats.message_count)
+        .bind(file.file_size)
+        .bind(file.mtime)
+        .bind(next_offset as i64)
+        .bind(&file.session_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update session index: {}", e))?;
+    } else {
+        sqlx::query(
+            "INSERT OR REPLACE INTO claude_sessions
+                (id, project_path, file_path, first_prompt, started_at, updated_at, message_count, file_size, file_mtime, indexed_offset)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&file.session_id)
+        .bind(stats.project_path.unwrap_or_else(|| decode_project_dir(&file.dir_name)))
+        .bind(file.path.to_string_lossy().to_string())
+        .bind(stats.first_prompt)
+        .bind(stats.started_at)
+        .bind(stats.updated_at)
+        .bind(stats.message_count)
+        .bind(file.file_size)
+        .bind(file.mtime)
+        .bind(next_offset as i64)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to update session index: {}", e))?;
+    }
+
+    Ok(indexed)
+}
+
+async fn index_file(pool: &SqlitePool, dir_name: &str, path: &Path) -> Result<bool, String> {
+    let Some(file) = pending_file(pool, dir_name, path).await? else {
+        return Ok(false);
+    };
+
+    let parse_path = file.path.clone();
+    let from_offset = file.from_offset;
+    let (entries, next_offset) =
+        tauri::async_runtime::spawn_blocking(move || transcripts::parse_from(&parse_path, from_offset, None))
+            .await
+            .map_err(|e| format!("Index task failed: {}", e))??;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to update session index: {}", e))?;
+    save_file(&mut tx, &file, &entries, next_offset).await?;
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to update session index: {}", e))?;
+    Ok(true)
+}
+
+// List indexed sessions, newest first. The disk scan is incremental, so
+// `refresh` is cheap, but callers can skip it to render from the index alone.
+#[tauri::command]
+pub async fn list_claude_sessions(
+    app: AppHandle,
+    project_path: Option<String>,
+    refresh: Option<bool>,
+    limit: Option<i64>,
+) -> Result<Vec<SessionSummary>, String> {
+    if refresh.unwrap_or(true) {
+        refresh_index(&app).await?;
+    }
+
+    let pool = db::pool(&app).await?;
+    let rows: Vec<SessionRow> = match project_path {
+        Some(project_path) => sqlx::query_as(&format!(
+            "{} WHERE project_path = ? ORDER BY updated_at DESC LIMIT ?",
+            SELECT_SESSIONS
+        ))
+        .bind(project_path)
+        .bind(limit.unwrap_or(-1))
+        .fetch_all(&pool)
+        .await,
+        None => sqlx::query_as(&format!("{} ORDER BY updated_at DESC LIMIT ?", SELECT_SESSIONS))
+            .bind(limit.unwrap_or(-1))
+            .fetch_all(&pool)
+            .await,
+    }
+    .map_err(|e| format!("Failed to list sessions: {}", e))?;
+
+    Ok(rows.into_iter().map(from_row).collect())
+}
+
+#[tauri::command]
+pub async fn search_sessions(
+    app: AppHandle,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<SessionSummary>, String> {
+    let pool = db::pool(&app).await?;
+    let pattern = format!(
+        "%{}%",
+        query.trim().replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+    );
+
+    let rows: Vec<SessionRow> = sqlx::query_as(&format!(
+        "{} WHERE first_prompt LIKE ?1 ESCAPE '\\' OR project_path LIKE ?1 ESCAPE '\\' OR id LIKE ?1 ESCAPE '\\'
+         ORDER BY updated_at DESC LIMIT ?2",
+        SELECT_SESSIONS
+    ))
+    .bind(pattern)
+    .bind(limit.unwrap_or(100))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to search sessions: {}", e))?;
+
+    Ok(rows.into_iter().map(from_row).collect())
+}
+
+#[derive(Default, serde::Deserialize)]
+pub struct MessageSearchFilters {
+    pub project_path: Option<String>,
+    pub session_id: Option<String>,
+    // "user" or "assistant"
+    pub role: Option<String>,
+    // RFC 3339 bounds on the message timestamp
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct MessageMatch {
+    pub session_id: String,
+    pub project_path: String,
+    pub message_uuid: Option<String>,
+    pub role: String,
+    pub timestamp: Option<String>,
+    pub snippet: String,
+    // Character ranges of `snippet` that matched, as [start, end)
+    pub highlights: Vec<(usize, usize)>,
+}
+
+// Each word is matched as a literal term and the last one as a prefix, so
+// results update while typing and FTS5 syntax in the input can't fail the
+// query
+fn match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", terms.join(" ")))
+}
+
+fn split_highlights(marked: &str) -> (String, Vec<(usize, usize)>) {
+    let mut snippet = String::with_capacity(marked.len());
+    let mut highlights = Vec::new();
+    let mut start = None;
+    let mut position = 0;
+    for c in marked.chars() {
+        match c {
+            HIGHLIGHT_START => start = Some(position),
+            HIGHLIGHT_END => {
+                if let Some(start) = start.take() {
+                    highlights.push((start, position));
+                }
+            }
+            c => {
+                snippet.push(c);
+                position += 1;
+            }
+        }
+    }
+    (snippet, highlights)
+}
+
+type MessageRow = (String, String, Option<String>, String, Option<String>, String);
+
+// Ranked full-text search over every indexed transcript message
+#[tauri::command]
+pub async fn search_messages(
+    app: AppHandle,
+    query: String,
+    filters: Option<MessageSearchFilters>,
+) -> Result<Vec<MessageMatch>, String> {
+    let Some(expression) = match_expression(&query) else {
+        return Ok(Vec::new());
+    };
+    let filters = filters.unwrap_or_default();
+    let pool = db::pool(&app).await?;
+
+    let rows: Vec<MessageRow> = sqlx::query_as(&format!(
+        "SELECT f.session_id, s.project_path, f.message_uuid, f.role, f.timestamp,
+                snippet(claude_messages_fts, 0, '{}', '{}', '…', {})
+         FROM claude_messages_fts f JOIN claude_sessions s ON s.id = f.session_id
+         WHERE claude_messages_fts MATCH ?
+           AND (?2 IS NULL OR s.project_path = ?2)
+           AND (?3 IS NULL OR f.session_id = ?3)
+           AND (?4 IS NULL OR f.role = ?4)
+           AND (?5 IS NULL OR f.timestamp >= ?5)
+           AND (?6 IS NULL OR f.timestamp <= ?6)
+         ORDER BY f.rank LIMIT ?7",
+        HIGHLIGHT_START, HIGHLIGHT_END, SNIPPET_TOKENS
+    ))
+    .bind(expression)
+    .bind(filters.project_path)
+    .bind(filters.session_id)
+    .bind(filters.role)
+    .bind(filters.since)
+    .bind(filters.until)
+    .bind(filters.limit.unwrap_or(100))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("Failed to search messages: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(session_id, project_path, message_uuid, role, timestamp, marked)| {
+            let (snippet, highlights) = split_highlights(&marked);
+            MessageMatch {
+                session_id,
+                project_path,
+                message_uuid,
+                role,
+                timestamp,
+                snippet,
+                highlights,
+            }
+        })
+        .collect())
+}
+
+// Remove sessions from the index and keep them out of it until their
+// transcripts change. The transcripts themselves belong to the CLI and are
+// left alone.
+pub async fn prune(pool: &SqlitePool, ids: &[String]) -> Result<(), String> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("Failed to prune session index: {}", e))?;
+    for id in ids {
+        sqlx::query("INSERT OR REPLACE INTO claude_sessions_pruned (id, file_mtime) SELECT id, file_mtime FROM claude_sessions WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to prune session index: {}", e))?;
+        sqlx::query("DELETE FROM claude_messages_fts WHERE session_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to prune message search index: {}", e))?;
+        sqlx::query("DELETE FROM claude_sessions WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("Failed to prune session index: {}", e))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("Failed to prune session index: {}", e))
+}
@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::app_lock::LockSettings;
+use crate::automation::{self, AutomationSettings};
+use crate::connection::{self, ConnectionSettings};
+use crate::discovery::{self, DiscoverySettings};
+use crate::net::NetworkConfig;
+use crate::retention::RetentionRules;
+use crate::{accelerators, db, fs_util, i18n, net, shortcuts, tasks, theme, updater, view, windows};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+// Keys these settings lived under in the database's settings table. They're
+// read once, the first time there's no settings file.
+const LEGACY_KEYS: &[&str] = &[
+    "claude_code_executable_path",
+    "update_channel",
+    "update_check_interval_hours",
+    "update_check_on_metered",
+    "update_skipped_versions",
+    "window_theme",
+    "zoom_level",
+    "app_language",
+    "global_shortcut",
+    "menu_accelerators",
+    "close_to_tray",
+    "network_proxy",
+    "network_ca_bundle",
+    "retention_rules",
+];
+
+// Everything the app remembers about how it's set up, in one file. Fields
+// missing from the file take their defaults, so older files keep loading.
+#[derive(Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub server: ServerSettings,
+    pub updates: UpdateSettings,
+    pub appearance: AppearanceSettings,
+    pub shortcuts: ShortcutSettings,
+    pub window: WindowSettings,
+    pub network: NetworkConfig,
+    pub retention: RetentionRules,
+    pub tasks: TaskSettings,
+    pub lock: LockSettings,
+    pub automation: AutomationSettings,
+    // None until the user has been asked
+    pub telemetry_consent: Option<bool>,
+}
+
+#[derive(Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ServerSettings {
+    // The CLI the server runs when no executable profile is active
+    pub claude_code_executable_path: Option<String>,
+    // The bundled sidecar, or a remote server
+    pub connection: ConnectionSettings,
+    // Where to look for servers started outside Incito
+    pub discovery: DiscoverySettings,
+}
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct UpdateSettings {
+    pub channel: String,
+    // 0 turns background checks off
+    pub check_interval_hours: u32,
+    pub check_on_metered: bool,
+    pub skipped_versions: Vec<String>,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            channel: updater::CHANNELS[0].to_string(),
+            check_interval_hours: updater::DEFAULT_CHECK_INTERVAL_HOURS,
+            check_on_metered: false,
+            skipped_versions: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct AppearanceSettings {
+    // "light", "dark" or "auto"
+    pub theme: String,
+    pub zoom_level: f64,
+    // None follows the OS language
+    pub language: Option<String>,
+}
+
+impl Default for AppearanceSettings {
+    fn default() -> Self {
+        Self {
+            theme: "auto".to_string(),
+            zoom_level: 1.0,
+            language: None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ShortcutSettings {
+    // The summon shortcut; None turns it off
+    pub global: Option<String>,
+    // Menu item id to accelerator, only for items the user rebound. An
+    // empty accelerator removes the item's shortcut.
+    pub menu: HashMap<String, String>,
+}
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        Self {
+            global: Some(shortcuts::DEFAULT_GLOBAL_SHORTCUT.to_string()),
+            menu: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub close_to_tray: bool,
+}
+
+#[derive(Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TaskSettings {
+    // What to do when a task starts with uncommitted changes in the
+    // project: one of tasks::DIRTY_WORKTREE_POLICIES
+    pub dirty_worktree: String,
+}
+
+impl Default for TaskSettings {
+    fn default() -> Self {
+        Self {
+            dirty_worktree: tasks::DIRTY_WORKTREE_POLICIES[0].to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SettingsStore(Mutex<AppSettings>);
+
+pub fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(SETTINGS_FILE))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.trim().is_empty())
+}
+
+async fn from_legacy(app: &AppHandle) -> Result<AppSettings, String> {
+    let mut saved = HashMap::new();
+    for key in LEGACY_KEYS {
+        if let Some(value) = db::get_setting(app, key).await? {
+            saved.insert(*key, value);
+        }
+    }
+    let mut settings = AppSettings::default();
+    let mut take = |key: &str| saved.remove(key);
+
+    settings.server.claude_code_executable_path = non_empty(take("claude_code_executable_path"));
+    if let Some(channel) = take("update_channel").filter(|channel| updater::CHANNELS.contains(&channel.as_str())) {
+        settings.updates.channel = channel;
+    }
+    if let Some(hours) = take("update_check_interval_hours").and_then(|value| value.parse().ok()) {
+        settings.updates.check_interval_hours = hours;
+    }
+    settings.updates.check_on_metered = take("update_check_on_metered").as_deref() == Some("true");
+    if let Some(versions) = take("update_skipped_versions").and_then(|value| serde_json::from_str(&value).ok()) {
+        settings.updates.skipped_versions = versions;
+    }
+    if let Some(theme) = non_empty(take("window_theme")) {
+        settings.appearance.theme = theme;
+    }
+    if let Some(zoom) = take("zoom_level").and_then(|value| value.parse().ok()) {
+        settings.appearance.zoom_level = zoom;
+    }
+    settings.appearance.language = non_empty(take("app_language"));
+    // An empty saved shortcut meant it was turned off
+    if let Some(shortcut) = take("global_shortcut") {
+        settings.shortcuts.global = non_empty(Some(shortcut));
+    }
+    if let Some(menu) = take("menu_accelerators").and_then(|value| serde_json::from_str(&value).ok()) {
+        settings.shortcuts.menu = menu;
+    }
+    settings.window.close_to_tray = take("close_to_tray").as_deref() == Some("true");
+    settings.network.proxy = non_empty(take("network_proxy"));
+    settings.network.ca_bundle = non_empty(take("network_ca_bundle"));
+    if let Some(rules) = take("retention_rules").and_then(|value| serde_json::from_str(&value).ok()) {
+        settings.retention = rules;
+    }
+    Ok(settings)
+}
+
+fn save(app: &AppHandle, settings: &AppSettings) -> Result<(), String> {
+    let contents = serde_json::to_vec_pretty(settings).map_err(|e| e.to_string())?;
+    fs_util::write_atomic(&settings_path(app)?, &contents, true)
+}
+
+// From setup, once the database is open. Falls back to the backup the last
+// save kept if the file is unreadable, and to the old database keys when
+// there's no file yet.
+pub async fn load(app: &AppHandle) -> Result<(), String> {
+    let path = settings_path(app)?;
+    let read = |path: &std::path::Path| -> Option<AppSettings> {
+        let contents = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&contents).ok()
+    };
+
+    let settings = match read(&path).or_else(|| read(&fs_util::backup_path_for(&path))) {
+        Some(settings) => settings,
+        None if path.exists() => {
+            eprintln!("Settings file {} is unreadable, using defaults", path.display());
+            AppSettings::default()
+        }
+        None => {
+            let settings = from_legacy(app).await.unwrap_or_default();
+            save(app, &settings)?;
+            settings
+        }
+    };
+    *app.state::<SettingsStore>().0.lock().map_err(|e| e.to_string())? = settings;
+    Ok(())
+}
+
+pub fn get(app: &AppHandle) -> AppSettings {
+    app.try_state::<SettingsStore>()
+        .and_then(|store| store.0.lock().ok().map(|settings| settings.clone()))
+        .unwrap_or_default()
+}
+
+// Change and save the settings, then emit `settings-changed` with the
+// result. Callers apply the change themselves.
+pub fn update(app: &AppHandle, change: impl FnOnce(&mut AppSettings)) -> Result<AppSettings, String> {
+    let store = app.state::<SettingsStore>();
+    let mut current = store.0.lock().map_err(|e| e.to_string())?;
+    let mut next = current.clone();
+    change(&mut next);
+    if next != *current {
+        save(app, &next)?;
+        *current = next.clone();
+        let _ = app.emit("settings-changed", next.clone());
+    }
+    Ok(next)
+}
+
+// Objects merge key by key; anything else, null included, replaces
+fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+// The same checks the individual setters make, so a patch can't save what
+// they would refuse
+fn validate(settings: &AppSettings) -> Result<(), String> {
+    if !updater::CHANNELS.contains(&settings.updates.channel.as_str()) {
+        return Err(format!("Unknown update channel: {}", settings.updates.channel));
+    }
+    theme::parse_preference(&settings.appearance.theme)?;
+    if let Some(language) = &settings.appearance.language {
+        if !i18n::is_supported(language) {
+            return Err(format!("Unsupported language: {}", language));
+        }
+    }
+    if let Some(id) = settings.shortcuts.menu.keys().find(|id| !accelerators::is_known(id)) {
+        return Err(format!("Unknown menu item: {}", id));
+    }
+    net::client_options(&settings.network)?;
+    if !tasks::DIRTY_WORKTREE_POLICIES.contains(&settings.tasks.dirty_worktree.as_str()) {
+        return Err(format!("Unknown dirty worktree policy: {}", settings.tasks.dirty_worktree));
+    }
+    automation::validate(&settings.automation)?;
+    connection::validate(&settings.server.connection)?;
+    discovery::validate(&settings.server.discovery)?;
+    Ok(())
+}
+
+// Bring the running app in line with whatever a patch changed
+async fn apply_changes(app: &AppHandle, previous: &AppSettings, next: &AppSettings) -> Result<(), String> {
+    if previous.appearance.theme != next.appearance.theme {
+        theme::load_saved(app).await?;
+    }
+    if previous.appearance.zoom_level != next.appearance.zoom_level {
+        view::load_zoom(app).await?;
+    }
+    if previous.appearance.language != next.appearance.language {
+        i18n::load_saved(app).await?;
+    }
+    if previous.shortcuts.global != next.shortcuts.global {
+        shortcuts::register_saved(app).await?;
+    }
+    if previous.shortcuts.menu != next.shortcuts.menu {
+        accelerators::load_saved(app).await?;
+    }
+    if previous.window != next.window {
+        windows::load_behavior(app).await?;
+    }
+    if previous.network != next.network {
+        net::load_saved(app).await?;
+    }
+    if previous.automation != next.automation {
+        automation::load_saved(app).await?;
+    }
+    Ok(())
+}
+
+// Check, save and apply a whole new set of settings
+pub async fn replace(app: &AppHandle, next: AppSettings) -> Result<AppSettings, String> {
+    validate(&next)?;
+    let previous = get(app);
+    let saved = update(app, |settings| *settings = next)?;
+    apply_changes(app, &previous, &saved).await?;
+    Ok(saved)
+}
+
+#[tauri::command]
+pub fn get_settings(app: AppHandle) -> AppSettings {
+    get(&app)
+}
+
+// Merge `patch` (any subset of the settings, nested the same way) into the
+// saved settings, save them and apply what changed
+#[tauri::command]
+pub async fn update_settings(app: AppHandle, patch: Value) -> Result<AppSettings, String> {
+    let previous = get(&app);
+    let mut merged = serde_json::to_value(&previous).map_err(|e| e.to_string())?;
+    merge(&mut merged, patch);
+    let next: AppSettings = serde_json::from_value(merged).map_err(|e| format!("Invalid settings: {}", e))?;
+    replace(&app, next).await
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// Printed around the environment so anything rc files echo is skipped
+#[cfg(not(windows))]
+const MARKER: &str = "__INCITO_ENV__";
+// rc files that wait on something shouldn't hold a command up for long
+#[cfg(not(windows))]
+const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+static LOGIN_ENV: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+// Opened from the Dock or a desktop launcher, the app gets a bare
+// environment; PATH and friends come from the user's login shell
+#[cfg(not(windows))]
+fn from_login_shell() -> Option<HashMap<String, String>> {
+    use std::io::Read;
+    use std::process::{Command, Stdio};
+
+    let shell = std::env::var("SHELL").ok().filter(|shell| !shell.is_empty())?;
+    let script = format!("printf '{}'; env -0; printf '{}'", MARKER, MARKER);
+    let mut child = Command::new(&shell)
+        .args(["-l", "-i", "-c", &script])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let mut stdout = child.stdout.take()?;
+    let reader = std::thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = stdout.read_to_end(&mut output);
+        output
+    });
+
+    let started = std::time::Instant::now();
+    while child.try_wait().ok()?.is_none() {
+        if started.elapsed() > TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    }
+    let output = String::from_utf8_lossy(&reader.join().ok()?).to_string();
+    let mut sections = output.split(MARKER);
+    let env = sections.nth(1)?;
+    Some(
+        env.split('\0')
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+    )
+}
+
+#[cfg(windows)]
+fn from_login_shell() -> Option<HashMap<String, String>> {
+    None
+}
+
+// The app's environment with the login shell's on top, resolved once
+pub fn login_env() -> &'static HashMap<String, String> {
+    LOGIN_ENV.get_or_init(|| {
+        let mut env: HashMap<String, String> = std::env::vars().collect();
+        env.extend(from_login_shell().unwrap_or_default());
+        env
+    })
+}
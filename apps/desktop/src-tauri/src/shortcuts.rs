@@ -0,0 +1,71 @@
+use std::sync::Mutex;
+
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, Wry};
+use tauri_plugin_global_shortcut::{Builder, GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::{settings, windows};
+
+pub const DEFAULT_GLOBAL_SHORTCUT: &str = "CommandOrControl+Shift+I";
+
+// The currently registered shortcut to summon Incito, if any
+#[derive(Default)]
+pub struct GlobalShortcutState(Mutex<Option<String>>);
+
+pub fn plugin() -> TauriPlugin<Wry> {
+    Builder::new()
+        .with_handler(|app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                let _ = windows::show_main_window(app);
+            }
+        })
+        .build()
+}
+
+fn parse_shortcut(shortcut: &str) -> Result<Shortcut, String> {
+    shortcut
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut \"{}\": {}", shortcut, e))
+}
+
+fn register(app: &AppHandle, shortcut: Option<String>) -> Result<(), String> {
+    let state = app.state::<GlobalShortcutState>();
+    let mut current = state.0.lock().map_err(|e| e.to_string())?;
+
+    let next = shortcut.as_deref().map(parse_shortcut).transpose()?;
+    let previous = current.as_deref().and_then(|previous| parse_shortcut(previous).ok());
+    if let Some(previous) = previous {
+        let _ = app.global_shortcut().unregister(previous);
+    }
+    if let Some(next) = next {
+        // Usually another app already owns the combination; keep the old binding
+        if let Err(e) = app.global_shortcut().register(next) {
+            if let Some(previous) = previous {
+                let _ = app.global_shortcut().register(previous);
+            }
+            return Err(format!("Failed to register shortcut: {}", e));
+        }
+    }
+
+    *current = shortcut;
+    Ok(())
+}
+
+// Register the saved shortcut, which is the default until the user changes
+// or turns it off
+pub async fn register_saved(app: &AppHandle) -> Result<(), String> {
+    register(app, settings::get(app).shortcuts.global)
+}
+
+#[tauri::command]
+pub fn get_global_shortcut(state: tauri::State<'_, GlobalShortcutState>) -> Result<Option<String>, String> {
+    Ok(state.0.lock().map_err(|e| e.to_string())?.clone())
+}
+
+// Rebind the summon shortcut; `None` or an empty string turns it off
+#[tauri::command]
+pub async fn set_global_shortcut(app: AppHandle, shortcut: Option<String>) -> Result<(), String> {
+    let shortcut = shortcut.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    register(&app, shortcut.clone())?;
+    settings::update(&app, |settings| settings.shortcuts.global = shortcut).map(|_| ())
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::verify::{self, VerifyError};
+use crate::{connection, downloads, fs_util, server, updater};
+
+const SERVER_BINARY: &str = "claude-code-server";
+
+// `sidecar.json` (or `sidecar-beta.json`, ...) next to the app manifest,
+// signed as `sidecar.json.sig`
+#[derive(serde::Deserialize)]
+struct SidecarManifest {
+    version: String,
+    notes: Option<String>,
+    platforms: HashMap<String, SidecarPlatform>,
+}
+
+#[derive(serde::Deserialize)]
+struct SidecarPlatform {
+    url: String,
+    signature: String,
+    sha256: Option<String>,
+}
+
+// Which downloaded build the server runs. The bundled sidecar is used while
+// there's none.
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+struct ActiveSidecar {
+    version: Option<String>,
+    // Of the binary as verified when it was installed
+    sha256: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct SidecarUpdateInfo {
+    pub current_version: String,
+    pub version: String,
+    pub notes: Option<String>,
+}
+
+fn sidecar_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("sidecar"))
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+fn binary_name() -> String {
+    format!("{}{}", SERVER_BINARY, std::env::consts::EXE_SUFFIX)
+}
+
+fn active(app: &AppHandle) -> ActiveSidecar {
+    sidecar_dir(app)
+        .ok()
+        .and_then(|dir| std::fs::read_to_string(dir.join("active.json")).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+// Switching versions is a single atomic write of this file, so the server
+// never sees a half-written binary
+fn set_active(app: &AppHandle, active: &ActiveSidecar) -> Result<(), String> {
+    let contents = serde_json::to_vec_pretty(active).map_err(|e| e.to_string())?;
+    fs_util::write_atomic(&sidecar_dir(app)?.join("active.json"), &contents, false)
+}
+
+// A downloaded build the app has since caught up with, e.g. after an app
+// update that bundles the same or a newer sidecar
+fn is_outdated(app: &AppHandle, version: &str) -> bool {
+    !is_newer(version, &app.package_info().version.to_string())
+}
+
+fn installed_binary(app: &AppHandle) -> Option<PathBuf> {
+    let version = active(app).version.filter(|version| !is_outdated(app, version))?;
+    let path = sidecar_dir(app).ok()?.join(version).join(binary_name());
+    path.is_file().then_some(path)
+}
+
+// Go back to the bundled sidecar and delete the downloaded build
+fn drop_outdated(app: &AppHandle) {
+    let Some(version) = active(app).version.filter(|version| is_outdated(app, version)) else {
+        return;
+    };
+    if let Err(e) = set_active(app, &ActiveSidecar::default()) {
+        eprintln!("Failed to go back to the bundled sidecar: {}", e);
+        return;
+    }
+    // Only a real version names a directory of ours
+    if let (Ok(dir), Ok(_)) = (sidecar_dir(app), semver::Version::parse(&version)) {
+        let _ = std::fs::remove_dir_all(dir.join(version));
+    }
+}
+
+// The downloaded build to run instead of the bundled sidecar, if any. One
+// that isn't newer than the bundled sidecar is dropped, and one that no
+// longer matches the hash it was verified with is refused and reported, so
+// the bundled sidecar runs instead.
+pub fn active_binary(app: &AppHandle) -> Option<PathBuf> {
+    drop_outdated(app);
+    let path = installed_binary(app)?;
+    match verify::check_installed(SERVER_BINARY, &path, active(app).sha256.as_deref()) {
+        Ok(()) => Some(path),
+        Err(error) => {
+            verify::report(app, &error);
+            None
+        }
+    }
+}
+
+// The bundled sidecar ships with the app, so it has the app's version
+fn current_version(app: &AppHandle) -> String {
+    active(app)
+        .version
+        .filter(|_| installed_binary(app).is_some())
+        .unwrap_or_else(|| app.package_info().version.to_string())
+}
+
+async fn fetch_manifest(app: &AppHandle) -> Result<SidecarManifest, VerifyError> {
+    let channel = updater::current_channel(app).await?;
+    let file_name = if channel == "stable" {
+        "sidecar.json".to_string()
+    } else {
+        format!("sidecar-{}.json", channel)
+    };
+    verify::fetch_manifest(app, &file_name).await
+}
+
+fn is_newer(version: &str, current: &str) -> bool {
+    match (semver::Version::parse(version), semver::Version::parse(current)) {
+        (Ok(version), Ok(current)) => version > current,
+        _ => false,
+    }
+}
+
+async fn available_update(app: &AppHandle) -> Result<Option<(SidecarManifest, SidecarUpdateInfo)>, VerifyError> {
+    let manifest = fetch_manifest(app).await?;
+    let current_version = current_version(app);
+    if !is_newer(&manifest.version, &current_version) || !manifest.platforms.contains_key(env!("INCITO_TARGET")) {
+        return Ok(None);
+    }
+    let info = SidecarUpdateInfo {
+        current_version,
+        version: manifest.version.clone(),
+        notes: manifest.notes.clone(),
+    };
+    Ok(Some((manifest, info)))
+}
+
+#[tauri::command]
+pub async fn check_sidecar_update(app: AppHandle) -> Result<Option<SidecarUpdateInfo>, VerifyError> {
+    Ok(available_update(&app).await?.map(|(_, info)| info))
+}
+
+// Download the new build and check it against the signed manifest, both
+// its signature and its SHA-256, then save it into its own directory, point
+// active.json at it and restart the server if it was running. Nothing is
+// written if either check fails.
+#[tauri::command]
+pub async fn install_sidecar_update(app: AppHandle) -> Result<SidecarUpdateInfo, VerifyError> {
+    let (manifest, info) = available_update(&app)
+        .await?
+        .ok_or_else(|| "No sidecar update available".to_string())?;
+    let platform = &manifest.platforms[env!("INCITO_TARGET")];
+
+    let bytes = downloads::download_bytes(
+        &app,
+        downloads::Request {
+            url: platform.url.clone(),
+            label: SERVER_BINARY.to_string(),
+            sha256: platform.sha256.clone(),
+        },
+    )
+    .await?;
+    let checked = verify::check_signature(&app, SERVER_BINARY, &bytes, &platform.signature)
+        .and_then(|()| verify::check_sha256(SERVER_BINARY, &bytes, platform.sha256.as_deref()));
+    if let Err(error) = checked {
+        verify::report(&app, &error);
+        return Err(error);
+    }
+
+    let path = sidecar_dir(&app)?.join(&manifest.version).join(binary_name());
+    fs_util::write_atomic(&path, &bytes, false)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make sidecar executable: {}", e))?;
+    }
+
+    set_active(
+        &app,
+        &ActiveSidecar {
+            version: Some(manifest.version.clone()),
+            sha256: Some(verify::sha256_hex(&bytes)),
+        },
+    )?;
+    // A remote server isn't this build
+    if server::is_running(&app) && !connection::is_remote(&app) {
+        server::restart(&app, "sidecar-update").await?;
+    }
+    let _ = app.emit("sidecar-updated", info.clone());
+    Ok(info)
+}
@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use tauri::async_runtime::JoinHandle;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Semaphore;
+
+use crate::connection::{self, Endpoint};
+use crate::{approvals, net, server};
+
+// The webview talks to the server only through here, never to localhost or
+// a remote itself
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+const HEALTH_INTERVAL: Duration = Duration::from_secs(5);
+// A stream that can't connect is retried this many times, waiting twice as
+// long each time up to the max
+const RECONNECT_ATTEMPTS: u32 = 5;
+const RECONNECT_DELAY: Duration = Duration::from_millis(250);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(4);
+// Events sent to the webview that it hasn't acknowledged yet. Past this the
+// bridge stops reading, and the sidecar in turn stops writing.
+const STREAM_WINDOW: usize = 64;
+
+// Whether the sidecar answered its last health check
+#[derive(Default)]
+pub struct SidecarBridge(Mutex<bool>);
+
+struct SidecarStream {
+    task: JoinHandle<()>,
+    credit: Arc<Semaphore>,
+}
+
+// Open streams by id
+#[derive(Default)]
+pub struct SidecarStreams(Mutex<HashMap<String, SidecarStream>>);
+
+#[derive(Clone, serde::Serialize)]
+struct BridgeStatus {
+    connected: bool,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct SidecarResponse {
+    pub status: u16,
+    // Parsed as JSON when it is, otherwise the text as a string
+    pub body: serde_json::Value,
+}
+
+// What a stream's channel receives, `{ type: "message", event, data }` and
+// so on. Every stream ends with exactly one "closed" or "error".
+#[derive(Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Open { status: u16 },
+    // One SSE event; `data` is its data lines joined, as EventSource does
+    Message { event: Option<String>, data: String },
+    Reconnecting { attempt: u32, delay_ms: u64 },
+    Closed,
+    Error { message: String, status: Option<u16> },
+}
+
+// For the sidecar or a tunnel's end here: no proxy, and no overall
+// timeout, which would cut off a long stream
+fn local_client() -> Result<reqwest::Client, String> {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client.clone());
+    }
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let client = reqwest::Client::builder()
+        .user_agent("Incito")
+        .no_proxy()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    Ok(CLIENT.get_or_init(|| client).clone())
+}
+
+// For a remote over the network: the proxy and CA bundle from the network
+// settings, where a self-signed certificate can be added
+fn remote_client(app: &AppHandle) -> Result<reqwest::Client, String> {
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let builder = reqwest::Client::builder().user_agent("Incito").connect_timeout(CONNECT_TIMEOUT);
+    net::apply(builder, &net::client_options(&net::current(app))?)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+// Only paths on the server itself; `path` can't point anywhere else
+fn url(endpoint: &Endpoint, path: &str) -> Result<String, String> {
+    if !path.starts_with('/') || path.starts_with("//") || path.contains(['\\', '#']) {
+        return Err(format!("Invalid sidecar path: {}", path));
+    }
+    Ok(format!("{}{}", endpoint.base_url, path))
+}
+
+fn request(
+    app: &AppHandle,
+    method: &str,
+    path: &str,
+    body: Option<&serde_json::Value>,
+) -> Result<reqwest::RequestBuilder, String> {
+    let endpoint = connection::endpoint(app);
+    let url = url(&endpoint, path)?;
+    let client = if endpoint.over_network {
+        remote_client(app)?
+    } else {
+        local_client()?
+    };
+    let request = match method.to_uppercase().as_str() {
+        "GET" => client.get(url),
+        "POST" => client.post(url),
+        _ => return Err(format!("Unsupported method: {}", method)),
+    };
+    let request = match &endpoint.token {
+        Some(token) => request.bearer_auth(token),
+        None => request,
+    };
+    Ok(match body {
+        Some(body) => request.json(body),
+        None => request,
+    })
+}
+
+fn set_connected(app: &AppHandle, connected: bool) {
+    let changed = match app.state::<SidecarBridge>().0.lock() {
+        Ok(mut state) => std::mem::replace(&mut *state, connected) != connected,
+        Err(_) => false,
+    };
+    if changed {
+        let _ = app.emit("sidecar-bridge-status", BridgeStatus { connected });
+    }
+}
+
+pub async fn healthy(app: &AppHandle) -> bool {
+    match request(app, "GET", "/health", None) {
+        Ok(request) => request
+            .timeout(HEALTH_TIMEOUT)
+            .send()
+            .await
+            .is_ok_and(|response| response.status().is_success()),
+        Err(_) => false,
+    }
+}
+
+// Keeps watch on the sidecar while it's running and emits
+// `sidecar-bridge-status` when it comes up or goes away
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let connected = server::is_running(&app) && healthy(&app).await;
+            set_connected(&app, connected);
+            tokio::time::sleep(HEALTH_INTERVAL).await;
+        }
+    });
+}
+
+// Only a stream that hasn't delivered anything can be started again
+// without the webview seeing part of it twice: the sidecar has no way to
+// resume one
+fn retryable(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout()
+}
+
+fn retry_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+}
+
+async fn connect(
+    app: &AppHandle,
+    path: &str,
+    body: Option<&serde_json::Value>,
+    channel: &Channel<StreamEvent>,
+) -> Result<reqwest::Response, StreamEvent> {
+    let mut delay = RECONNECT_DELAY;
+    let mut attempt = 0;
+    loop {
+        let sent = request(app, "POST", path, body)
+            .map_err(|message| StreamEvent::Error { message, status: None })?
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await;
+        match sent {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                if !retry_status(response.status()) || attempt == RECONNECT_ATTEMPTS {
+                    let status = response.status().as_u16();
+                    let text = response.text().await.unwrap_or_default();
+                    return Err(StreamEvent::Error {
+                        message: error_message(&text).unwrap_or_else(|| format!("The sidecar answered {}", status)),
+                        status: Some(status),
+                    });
+                }
+            }
+            Err(e) => {
+                set_connected(app, false);
+                if !retryable(&e) || attempt == RECONNECT_ATTEMPTS {
+                    return Err(StreamEvent::Error {
+                        message: format!("Failed to reach the sidecar: {}", e),
+                        status: None,
+                    });
+                }
+            }
+        }
+        attempt += 1;
+        let _ = channel.send(StreamEvent::Reconnecting {
+            attempt,
+            delay_ms: delay.as_millis() as u64,
+        });
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+// The `error` of a JSON error body, as the sidecar sends them
+fn error_message(text: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(text)
+        .ok()?
+        .get("error")?
+        .as_str()
+        .map(str::to_string)
+}
+
+// Splits SSE text into events as it arrives. Comments and ids are dropped;
+// the sidecar doesn't resume streams.
+#[derive(Default)]
+struct SseParser {
+    buffer: String,
+    event: Option<String>,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    fn push(&mut self, text: &str) -> Vec<StreamEvent> {
+        self.buffer.push_str(text);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.find('\n') {
+            let line: String = self.buffer.drain(..=end).collect();
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push(StreamEvent::Message {
+                        event: self.event.take(),
+                        data: std::mem::take(&mut self.data).join("\n"),
+                    });
+                }
+                self.event = None;
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+// The text that has fully arrived. A character split across chunks waits
+// for the rest.
+fn take_text(pending: &mut Vec<u8>) -> String {
+    let valid = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    let text = String::from_utf8_lossy(&pending[..valid]).into_owned();
+    pending.drain(..valid);
+    text
+}
+
+async fn forward(response: reqwest::Response, channel: &Channel<StreamEvent>, credit: &Semaphore) -> StreamEvent {
+    let mut response = response;
+    let mut parser = SseParser::default();
+    let mut pending: Vec<u8> = Vec::new();
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => return StreamEvent::Closed,
+            Err(e) => {
+                return StreamEvent::Error {
+                    message: format!("Lost the connection to the sidecar: {}", e),
+                    status: None,
+                }
+            }
+        };
+        pending.extend_from_slice(&chunk);
+        for event in parser.push(&take_text(&mut pending)) {
+            match credit.acquire().await {
+                Ok(permit) => permit.forget(),
+                Err(_) => return StreamEvent::Closed,
+            }
+            if channel.send(event).is_err() {
+                // The webview is gone
+                return StreamEvent::Closed;
+            }
+        }
+    }
+}
+
+// Ask the user about one of the remote's requests and send the answer back
+fn answer_approval(app: &AppHandle, data: &str) {
+    let Ok(asked) = serde_json::from_str::<approvals::ApprovalRequest>(data) else {
+        return;
+    };
+    // It goes into the answer's path
+    if asked.id.is_empty() || !asked.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let path = format!("/approvals/{}", asked.id);
+        let allowed = approvals::request(&app, asked).await.unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            false
+        });
+        let body = serde_json::json!({ "allowed": allowed });
+        let sent = match request(&app, "POST", &path, Some(&body)) {
+            Ok(answer) => answer.timeout(HEALTH_TIMEOUT).send().await.map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = sent {
+            eprintln!("Failed to answer the server's approval request: {}", e);
+        }
+    });
+}
+
+// Read the remote's approval requests until the stream ends. Returns
+// whether to open it again; a server that predates it answers 404.
+async fn relay_approvals_once(app: &AppHandle) -> Result<bool, String> {
+    let mut response = request(app, "GET", "/approvals", None)?
+        .header(reqwest::header::ACCEPT, "text/event-stream")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach the server for approvals: {}", e))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(false);
+    }
+    if !response.status().is_success() {
+        return Err(format!("The server answered {} for approvals", response.status().as_u16()));
+    }
+    let mut parser = SseParser::default();
+    let mut pending: Vec<u8> = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Lost the server's approvals: {}", e))?
+    {
+        pending.extend_from_slice(&chunk);
+        for event in parser.push(&take_text(&mut pending)) {
+            if let StreamEvent::Message { event: Some(event), data } = event {
+                if event == "approval" {
+                    answer_approval(app, &data);
+                }
+            }
+        }
+    }
+    Ok(true)
+}
+
+// A remote server started with a token can't ask on stdin, so it sends
+// approval requests over the authenticated connection instead. Relays them
+// for as long as the app stays connected.
+pub async fn relay_approvals(app: AppHandle) {
+    while connection::is_remote(&app) && connection::is_connected(&app) {
+        match relay_approvals_once(&app).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => eprintln!("{}", e),
+        }
+        tokio::time::sleep(MAX_RECONNECT_DELAY).await;
+    }
+}
+
+fn remove(app: &AppHandle, id: &str) -> Option<SidecarStream> {
+    app.state::<SidecarStreams>().0.lock().ok()?.remove(id)
+}
+
+// Make a request to the sidecar and return its answer, whatever its
+// status; the frontend decides what an error status means
+#[tauri::command]
+pub async fn sidecar_request(
+    app: AppHandle,
+    method: String,
+    path: String,
+    body: Option<serde_json::Value>,
+    timeout_ms: Option<u64>,
+) -> Result<SidecarResponse, String> {
+    let response = request(&app, &method, &path, body.as_ref())?
+        .timeout(timeout_ms.map(Duration::from_millis).unwrap_or(REQUEST_TIMEOUT))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach the sidecar: {}", e))?;
+    let status = response.status().as_u16();
+    let text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read the sidecar's answer: {}", e))?;
+    let body = serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text));
+    Ok(SidecarResponse { status, body })
+}
+
+// POST `body` to an SSE endpoint of the sidecar and send its events to
+// `on_event`. The webview acknowledges what it has handled with
+// `ack_sidecar_stream`; the bridge keeps no more than 64 events unhandled.
+// Returns the stream's id, for acks and `cancel_sidecar_stream`.
+#[tauri::command]
+pub async fn sidecar_stream(
+    app: AppHandle,
+    path: String,
+    body: Option<serde_json::Value>,
+    on_event: Channel<StreamEvent>,
+) -> Result<String, String> {
+    url(&connection::endpoint(&app), &path)?;
+    let id = uuid::Uuid::new_v4().to_string();
+    let credit = Arc::new(Semaphore::new(STREAM_WINDOW));
+    let streams = app.state::<SidecarStreams>();
+    // Held until the task is stored, so a stream that ends at once still
+    // finds itself to remove
+    let mut open = streams.0.lock().map_err(|e| e.to_string())?;
+    let task = {
+        let (app, id, credit) = (app.clone(), id.clone(), credit.clone());
+        tauri::async_runtime::spawn(async move {
+            let end = match connect(&app, &path, body.as_ref(), &on_event).await {
+                Ok(response) => {
+                    set_connected(&app, true);
+                    let _ = on_event.send(StreamEvent::Open {
+                        status: response.status().as_u16(),
+                    });
+                    forward(response, &on_event, &credit).await
+                }
+                Err(error) => error,
+            };
+            let _ = on_event.send(end);
+            remove(&app, &id);
+        })
+    };
+    open.insert(id.clone(), SidecarStream { task, credit });
+    Ok(id)
+}
+
+// The webview has handled `count` more of the stream's events
+#[tauri::command]
+pub fn ack_sidecar_stream(app: AppHandle, id: String, count: usize) -> Result<(), String> {
+    let streams = app.state::<SidecarStreams>();
+    let open = streams.0.lock().map_err(|e| e.to_string())?;
+    if let Some(stream) = open.get(&id) {
+        let unacknowledged = STREAM_WINDOW.saturating_sub(stream.credit.available_permits());
+        stream.credit.add_permits(count.min(unacknowledged));
+    }
+    Ok(())
+}
+
+// Stop a stream; the sidecar sees its connection close
+#[tauri::command]
+pub fn cancel_sidecar_stream(app: AppHandle, id: String) -> Result<(), String> {
+    if let Some(stream) = remove(&app, &id) {
+        stream.credit.close();
+        stream.task.abort();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_sidecar_bridge_status(app: AppHandle) -> bool {
+    app.state::<SidecarBridge>().0.lock().map(|connected| *connected).unwrap_or(false)
+}
@@ -0,0 +1,299 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter};
+
+use crate::{frontmatter, fs_util, paths, trash};
+
+#[derive(Clone, serde::Serialize)]
+pub struct SlashCommand {
+    // Invoked as `/<name>`; commands in subdirectories are `dir:name`
+    pub name: String,
+    // "user" or "project"
+    pub scope: String,
+    pub path: String,
+    pub description: Option<String>,
+    pub argument_hint: Option<String>,
+    pub allowed_tools: Vec<String>,
+    pub model: Option<String>,
+    pub frontmatter: Map<String, Value>,
+    pub body: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct SlashCommandValidation {
+    pub valid: bool,
+    // Placeholders the template uses, e.g. "$ARGUMENTS" or "$1"
+    pub placeholders: Vec<String>,
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct CommandsChanged {
+    scope: String,
+}
+
+fn commands_dir(scope: &str, project_path: Option<&str>) -> Result<PathBuf, String> {
+    match scope {
+        "user" => Ok(paths::claude_dir()?.join("commands")),
+        "project" => project_path
+            .filter(|path| !path.is_empty())
+            .map(|path| Path::new(path).join(".claude").join("commands"))
+            .ok_or_else(|| "A project path is required for project commands".to_string()),
+        other => Err(format!("Unknown command scope: {}", other)),
+    }
+}
+
+// `frontend:lint` -> `<dir>/frontend/lint.md`. Segments are restricted so a
+// name can never point outside the commands directory.
+fn command_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let segments: Vec<&str> = name.split(':').collect();
+    let valid = segments.iter().all(|segment| {
+        !segment.is_empty()
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            && !segment.starts_with('.')
+    });
+    if !valid {
+        return Err(format!(
+            "Invalid command name \"{}\": use letters, numbers, '-', '_' and ':' for folders",
+            name
+        ));
+    }
+
+    let mut path = dir.to_path_buf();
+    for segment in &segments[..segments.len() - 1] {
+        path.push(segment);
+    }
+    path.push(format!("{}.md", segments[segments.len() - 1]));
+    Ok(path)
+}
+
+fn string_field(frontmatter: &Map<String, Value>, key: &str) -> Option<String> {
+    frontmatter
+        .get(key)
+        .and_then(Value::as_str)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+fn read_command(path: &Path, name: String, scope: &str) -> Result<SlashCommand, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let document = frontmatter::parse(&text);
+
+    Ok(SlashCommand {
+        name,
+        scope: scope.to_string(),
+        path: path.to_string_lossy().to_string(),
+        description: string_field(&document.frontmatter, "description"),
+        argument_hint: string_field(&document.frontmatter, "argument-hint"),
+        allowed_tools: frontmatter::list_field(document.frontmatter.get("allowed-tools")),
+        model: string_field(&document.frontmatter, "model"),
+        frontmatter: document.frontmatter,
+        body: document.body,
+    })
+}
+
+fn collect_commands(dir: &Path, prefix: &str, scope: &str, commands: &mut Vec<SlashCommand>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if file_name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            collect_commands(&path, &format!("{}{}:", prefix, file_name), scope, commands);
+        } else if let Some(stem) = file_name.strip_suffix(".md") {
+            if let Ok(command) = read_command(&path, format!("{}{}", prefix, stem), scope) {
+                commands.push(command);
+            }
+        }
+    }
+}
+
+// Project commands come first; on a name clash they shadow the user's
+#[tauri::command]
+pub fn list_slash_commands(project_path: Option<String>) -> Result<Vec<SlashCommand>, String> {
+    let mut commands = Vec::new();
+    if project_path.as_deref().is_some_and(|path| !path.is_empty()) {
+        let dir = commands_dir("project", project_path.as_deref())?;
+        collect_commands(&dir, "", "project", &mut commands);
+    }
+    collect_commands(&commands_dir("user", None)?, "", "user", &mut commands);
+    commands.sort_by(|a, b| (a.scope != "project", &a.name).cmp(&(b.scope != "project", &b.name)));
+    Ok(commands)
+}
+
+// Claude Code substitutes `$ARGUMENTS` with everything after the command and
+// `$1`..`$9` with individual arguments. Other `$NAME` tokens are passed
+// through untouched, which is usually a typo.
+fn validate_template(body: &str, frontmatter: &Map<String, Value>) -> SlashCommandValidation {
+    let mut placeholders: Vec<String> = Vec::new();
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let mut positional = Vec::new();
+
+    let chars: Vec<char> = body.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '$' || (i > 0 && chars[i - 1] == '\\') {
+            i += 1;
+            continue;
+        }
+        let start = i + 1;
+        let mut end = start;
+        while end < chars.len() && (chars[end].is_ascii_alphanumeric() || chars[end] == '_') {
+            end += 1;
+        }
+        let token: String = chars[start..end].iter().collect();
+        i = end.max(i + 1);
+
+        if token == "ARGUMENTS" {
+            if !placeholders.contains(&"$ARGUMENTS".to_string()) {
+                placeholders.push("$ARGUMENTS".to_string());
+            }
+        } else if let Ok(n) = token.parse::<usize>() {
+            if n == 0 {
+                errors.push("$0 is not a valid argument placeholder; arguments start at $1".to_string());
+            } else {
+                let placeholder = format!("${}", n);
+                if !placeholders.contains(&placeholder) {
+                    placeholders.push(placeholder);
+                    positional.push(n);
+                }
+            }
+        } else if !token.is_empty()
+            && token.chars().all(|c| c.is_ascii_uppercase() || c == '_')
+            && token.starts_with("ARG")
+        {
+            errors.push(format!("Unknown placeholder ${}; did you mean $ARGUMENTS?", token));
+        }
+    }
+
+    positional.sort_unstable();
+    if let Some(&max) = positional.last() {
+        let missing: Vec<String> = (1..max)
+            .filter(|n| !positional.contains(n))
+            .map(|n| format!("${}", n))
+            .collect();
+        if !missing.is_empty() {
+            warnings.push(format!("Argument placeholders skip {}", missing.join(", ")));
+        }
+        if placeholders.contains(&"$ARGUMENTS".to_string()) {
+            warnings.push("$ARGUMENTS and positional placeholders are both used; $ARGUMENTS includes every argument".to_string());
+        }
+    }
+    if !placeholders.is_empty() && string_field(frontmatter, "argument-hint").is_none() {
+        warnings.push("The command takes arguments but has no argument-hint".to_string());
+    }
+    if body.trim().is_empty() {
+        errors.push("The command has no prompt".to_string());
+    }
+    for key in frontmatter.keys() {
+        if !["description", "argument-hint", "allowed-tools", "model", "disable-model-invocation"].contains(&key.as_str()) {
+            warnings.push(format!("Unknown frontmatter field: {}", key));
+        }
+    }
+
+    SlashCommandValidation {
+        valid: errors.is_empty(),
+        placeholders,
+        errors,
+        warnings,
+    }
+}
+
+#[tauri::command]
+pub fn validate_slash_command(body: String, frontmatter: Option<Map<String, Value>>) -> SlashCommandValidation {
+    validate_template(&body, &frontmatter.unwrap_or_default())
+}
+
+// Create or overwrite a command. Unless `overwrite` is set, an existing
+// command with the same name is an error.
+#[tauri::command]
+pub fn save_slash_command(
+    app: AppHandle,
+    scope: String,
+    project_path: Option<String>,
+    name: String,
+    frontmatter: Map<String, Value>,
+    body: String,
+    overwrite: Option<bool>,
+) -> Result<SlashCommand, String> {
+    let validation = validate_template(&body, &frontmatter);
+    if !validation.valid {
+        return Err(validation.errors.join("; "));
+    }
+
+    let dir = commands_dir(&scope, project_path.as_deref())?;
+    let path = command_path(&dir, &name)?;
+    if path.exists() && !overwrite.unwrap_or(false) {
+        return Err(format!("A command named /{} already exists", name));
+    }
+
+    let text = frontmatter::render(&frontmatter, &body);
+    fs_util::write_atomic(&path, text.as_bytes(), false)?;
+    let _ = app.emit("claude-commands-changed", CommandsChanged { scope: scope.clone() });
+    read_command(&path, name, &scope)
+}
+
+#[tauri::command]
+pub fn rename_slash_command(
+    app: AppHandle,
+    scope: String,
+    project_path: Option<String>,
+    name: String,
+    new_name: String,
+) -> Result<SlashCommand, String> {
+    let dir = commands_dir(&scope, project_path.as_deref())?;
+    let from = command_path(&dir, &name)?;
+    let to = command_path(&dir, &new_name)?;
+    if !from.exists() {
+        return Err(format!("Command /{} not found", name));
+    }
+    if to.exists() {
+        return Err(format!("A command named /{} already exists", new_name));
+    }
+
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::rename(&from, &to).map_err(|e| format!("Failed to rename command: {}", e))?;
+    remove_empty_dirs(&dir, from.parent());
+
+    let _ = app.emit("claude-commands-changed", CommandsChanged { scope: scope.clone() });
+    read_command(&to, new_name, &scope)
+}
+
+#[tauri::command]
+pub fn delete_slash_command(
+    app: AppHandle,
+    scope: String,
+    project_path: Option<String>,
+    name: String,
+) -> Result<(), String> {
+    let dir = commands_dir(&scope, project_path.as_deref())?;
+    let path = command_path(&dir, &name)?;
+    if !path.exists() {
+        return Err(format!("Command /{} not found", name));
+    }
+
+    trash::move_to_trash(&path)?;
+    remove_empty_dirs(&dir, path.parent());
+
+    let _ = app.emit("claude-commands-changed", CommandsChanged { scope });
+    Ok(())
+}
+
+// Drop namespace folders left empty by a delete or rename
+fn remove_empty_dirs(root: &Path, mut dir: Option<&Path>) {
+    while let Some(current) = dir {
+        if current == root || !current.starts_with(root) || std::fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
@@ -0,0 +1,64 @@
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::{AppHandle, UserAttentionType, WebviewWindow};
+
+use crate::tasks;
+
+fn set_badge(window: &WebviewWindow, label: Option<String>) {
+    #[cfg(target_os = "macos")]
+    let _ = window.set_badge_label(label);
+
+    #[cfg(not(target_os = "macos"))]
+    let _ = (window, label);
+}
+
+fn clear(window: &WebviewWindow) {
+    let _ = window.set_progress_bar(ProgressBarState {
+        status: Some(ProgressBarStatus::None),
+        progress: None,
+    });
+    set_badge(window, None);
+}
+
+// Show task progress in the dock / taskbar. `state` is one of "normal",
+// "indeterminate", "paused" or "error" while the task runs, and "done",
+// "failed" or "none" to clear it. Finishing in the background bounces the
+// dock icon / flashes the taskbar button.
+#[tauri::command]
+pub fn set_task_progress(
+    app: AppHandle,
+    window: WebviewWindow,
+    percent: Option<f64>,
+    state: String,
+) -> Result<(), String> {
+    let status = match state.as_str() {
+        "normal" => ProgressBarStatus::Normal,
+        "indeterminate" => ProgressBarStatus::Indeterminate,
+        "paused" => ProgressBarStatus::Paused,
+        "error" => ProgressBarStatus::Error,
+        "done" | "failed" | "none" => {
+            clear(&window);
+            tasks::mark(&app, window.label(), false);
+            if state != "none" && !window.is_focused().unwrap_or(true) {
+                let attention = if state == "failed" {
+                    UserAttentionType::Critical
+                } else {
+                    UserAttentionType::Informational
+                };
+                let _ = window.request_user_attention(Some(attention));
+            }
+            return Ok(());
+        }
+        other => return Err(format!("Unknown progress state: {}", other)),
+    };
+
+    let progress = percent.map(|percent| percent.clamp(0.0, 100.0).round() as u64);
+    window
+        .set_progress_bar(ProgressBarState {
+            status: Some(status),
+            progress,
+        })
+        .map_err(|e| format!("Failed to set progress: {}", e))?;
+    set_badge(&window, progress.map(|progress| format!("{}%", progress)));
+    tasks::mark(&app, window.label(), true);
+    Ok(())
+}
@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow, Window};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::{git_status, i18n, menu_state, settings};
+
+// How long the frontend gets to stop a task before the window goes anyway
+const STOP_TIMEOUT: Duration = Duration::from_secs(3);
+// Files named in the dirty worktree prompt before "and N more"
+const PROMPT_FILES: usize = 10;
+
+// Starting a task with uncommitted changes in the project: go ahead, ask
+// first, or don't
+pub const DIRTY_WORKTREE_POLICIES: &[&str] = &["allow", "prompt", "refuse"];
+
+// Labels of windows with a Claude task in flight
+#[derive(Clone, serde::Serialize)]
+pub struct TaskStart {
+    pub started: bool,
+    // The uncommitted changes, when they were checked and found
+    pub dirty_files: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct ActiveTasks {
+    windows: Mutex<HashSet<String>>,
+    // Set once the user agreed to stop running tasks and quit
+    quit_confirmed: AtomicBool,
+}
+
+pub fn is_running(app: &AppHandle, label: &str) -> bool {
+    app.try_state::<ActiveTasks>()
+        .is_some_and(|tasks| tasks.windows.lock().is_ok_and(|windows| windows.contains(label)))
+}
+
+pub fn any_running(app: &AppHandle) -> bool {
+    app.try_state::<ActiveTasks>()
+        .is_some_and(|tasks| tasks.windows.lock().is_ok_and(|windows| !windows.is_empty()))
+}
+
+pub fn mark(app: &AppHandle, label: &str, running: bool) {
+    if let Ok(mut windows) = app.state::<ActiveTasks>().windows.lock() {
+        if running {
+            windows.insert(label.to_string());
+        } else {
+            windows.remove(label);
+        }
+    }
+    menu_state::sync(app);
+}
+
+// Ask the windows to stop their tasks and wait (briefly) until they report
+// back, then run `then`
+fn stop_then(app: &AppHandle, labels: Vec<String>, then: impl FnOnce(&AppHandle) + Send + 'static) {
+    for label in &labels {
+        let _ = app.emit_to(label.as_str(), "menu-stop-task", ());
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let started = Instant::now();
+        while labels.iter().any(|label| is_running(&app, label)) && started.elapsed() < STOP_TIMEOUT {
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        for label in &labels {
+            mark(&app, label, false);
+        }
+        then(&app);
+    });
+}
+
+fn confirm(app: &AppHandle, parent: Option<&WebviewWindow>, message: String, confirm_label: String, on_confirm: impl FnOnce() + Send + 'static) {
+    let mut dialog = app
+        .dialog()
+        .message(message)
+        .title(i18n::t(app, "dialogs.taskRunningTitle"))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(confirm_label, i18n::t(app, "dialogs.cancel")));
+    if let Some(parent) = parent {
+        dialog = dialog.parent(parent);
+    }
+    dialog.show(move |confirmed| {
+        if confirmed {
+            on_confirm();
+        }
+    });
+}
+
+// From the window's CloseRequested handler when closing would destroy it.
+// Returns whether the close has to wait for the user.
+pub fn intercept_close(window: &Window) -> bool {
+    let app = window.app_handle().clone();
+    if !is_running(&app, window.label()) {
+        return false;
+    }
+    let label = window.label().to_string();
+    let parent = app.get_webview_window(&label);
+    let message = i18n::t(&app, "dialogs.taskRunningClose");
+    let confirm_label = i18n::t(&app, "dialogs.stopAndClose");
+    let handle = app.clone();
+    confirm(&app, parent.as_ref(), message, confirm_label, move || {
+        stop_then(&handle, vec![label.clone()], move |app| {
+            if let Some(window) = app.get_webview_window(&label) {
+                let _ = window.close();
+            }
+        });
+    });
+    true
+}
+
+// From RunEvent::ExitRequested. Returns whether the exit has to wait.
+pub fn intercept_exit(app: &AppHandle, code: Option<i32>) -> bool {
+    let Some(tasks) = app.try_state::<ActiveTasks>() else { return false };
+    if tasks.quit_confirmed.load(Ordering::Relaxed) || !any_running(app) {
+        return false;
+    }
+    let labels: Vec<String> = tasks
+        .windows
+        .lock()
+        .map(|windows| windows.iter().cloned().collect())
+        .unwrap_or_default();
+    let parent = labels.first().and_then(|label| app.get_webview_window(label));
+
+    let message = i18n::t(app, "dialogs.taskRunningQuit");
+    let confirm_label = i18n::t(app, "dialogs.stopAndQuit");
+    let handle = app.clone();
+    confirm(app, parent.as_ref(), message, confirm_label, move || {
+        handle.state::<ActiveTasks>().quit_confirmed.store(true, Ordering::Relaxed);
+        stop_then(&handle, labels, move |app| app.exit(code.unwrap_or(0)));
+    });
+    true
+}
+
+pub fn on_window_destroyed(app: &AppHandle, label: &str) {
+    mark(app, label, false);
+}
+
+// The frontend reports when a task starts and stops, so closing a window
+// or quitting mid-task can ask first
+#[tauri::command]
+pub fn set_task_running(app: AppHandle, window: WebviewWindow, running: bool) {
+    mark(&app, window.label(), running);
+}
+
+fn prompt_dirty(app: &AppHandle, window: &WebviewWindow, dirty_files: &[String]) -> bool {
+    let mut files: Vec<String> = dirty_files.iter().take(PROMPT_FILES).cloned().collect();
+    if dirty_files.len() > PROMPT_FILES {
+        let more = (dirty_files.len() - PROMPT_FILES).to_string();
+        files.push(i18n::t_with(app, "dialogs.dirtyWorktreeMore", &[("count", &more)]));
+    }
+    app.dialog()
+        .message(i18n::t_with(app, "dialogs.dirtyWorktreeStart", &[("files", &files.join("\n"))]))
+        .title(i18n::t(app, "dialogs.dirtyWorktreeTitle"))
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            i18n::t(app, "dialogs.startAnyway"),
+            i18n::t(app, "dialogs.cancel"),
+        ))
+        .parent(window)
+        .blocking_show()
+}
+
+// Used instead of `set_task_running(true)` for a task in a project: applies
+// the dirty worktree policy, asking in a native dialog under "prompt", and
+// marks the task running only if it may start
+#[tauri::command]
+pub async fn start_task(
+    app: AppHandle,
+    window: WebviewWindow,
+    project_path: Option<String>,
+) -> Result<TaskStart, String> {
+    let policy = settings::get(&app).tasks.dirty_worktree;
+    let dirty_files = match project_path {
+        Some(path) if policy != "allow" => git_status::check_worktree(&path).await?.dirty_files,
+        _ => Vec::new(),
+    };
+
+    let started = if dirty_files.is_empty() {
+        true
+    } else if policy == "prompt" {
+        let (handle, parent, files) = (app.clone(), window.clone(), dirty_files.clone());
+        tauri::async_runtime::spawn_blocking(move || prompt_dirty(&handle, &parent, &files))
+            .await
+            .map_err(|e| format!("Failed to ask about uncommitted changes: {}", e))?
+    } else {
+        false
+    };
+    if started {
+        mark(&app, window.label(), true);
+    }
+    Ok(TaskStart { started, dirty_files })
+}
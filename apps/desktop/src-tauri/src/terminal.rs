@@ -0,0 +1,314 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use portable_pty::{native_pty_system, ChildKiller, CommandBuilder, MasterPty, PtySize};
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+
+use crate::scrollback::{Scrollback, ScrollbackChunk};
+use crate::{audit, command_runner, process_tree, profiles};
+
+const READ_BUFFER_BYTES: usize = 16 * 1024;
+
+// What the shell has written, and where it goes next. The channel is
+// swapped when a reloaded window attaches again.
+struct TerminalOutput {
+    scrollback: Scrollback,
+    channel: Channel<InvokeResponseBody>,
+}
+
+struct Terminal {
+    // The window it belongs to, so closing that window ends it
+    window: String,
+    info: TerminalInfo,
+    output: Arc<Mutex<TerminalOutput>>,
+    master: Box<dyn MasterPty + Send>,
+    // Its own lock, so a write blocked on a program that isn't reading
+    // doesn't hold up every other terminal. Tokio's, which is taken in the
+    // order writes arrive, so keystrokes aren't reordered.
+    writer: Arc<tokio::sync::Mutex<Box<dyn Write + Send>>>,
+    pid: Option<u32>,
+    killer: Box<dyn ChildKiller + Send + Sync>,
+}
+
+impl Terminal {
+    // Dropping it hangs up the terminal too, which ends shells that ignore
+    // SIGTERM
+    fn kill(mut self, force: bool) -> Result<(), String> {
+        match self.pid {
+            Some(pid) => process_tree::kill_tree(pid, force),
+            None => self.killer.kill().map_err(|e| format!("Failed to kill terminal: {}", e)),
+        }
+    }
+}
+
+// Open terminals by id
+#[derive(Default)]
+pub struct Terminals(Mutex<HashMap<String, Terminal>>);
+
+#[derive(Clone, serde::Serialize)]
+pub struct TerminalInfo {
+    pub id: String,
+    pub pid: Option<u32>,
+    pub shell: String,
+    pub cwd: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct TerminalExited {
+    id: String,
+    exit_code: u32,
+}
+
+fn size(cols: u16, rows: u16) -> PtySize {
+    PtySize {
+        rows: rows.max(1),
+        cols: cols.max(1),
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+// Without a shell given, the user's login shell, as a new terminal window
+// on their system would start
+fn shell_command(shell: Option<&str>) -> CommandBuilder {
+    match shell.map(str::trim).filter(|shell| !shell.is_empty()) {
+        Some(shell) => CommandBuilder::new(shell),
+        None => CommandBuilder::new_default_prog(),
+    }
+}
+
+fn remove(app: &AppHandle, id: &str) -> Option<Terminal> {
+    app.state::<Terminals>().0.lock().ok()?.remove(id)
+}
+
+// Start a shell in `cwd` on a new pseudo-terminal. Its output goes to
+// `on_output` as raw bytes, for xterm.js to decode, and "terminal-exited"
+// is emitted to the window once it ends.
+#[tauri::command]
+pub async fn create_terminal(
+    app: AppHandle,
+    window: WebviewWindow,
+    cwd: String,
+    shell: Option<String>,
+    cols: u16,
+    rows: u16,
+    on_output: Channel<InvokeResponseBody>,
+) -> Result<TerminalInfo, String> {
+    if !Path::new(&cwd).is_dir() {
+        return Err(format!("Not a folder: {}", cwd));
+    }
+    // The same environment Claude Code gets from the active profile
+    let profile = profiles::active_profile(&app).await.ok().flatten();
+
+    let pair = native_pty_system()
+        .openpty(size(cols, rows))
+        .map_err(|e| format!("Failed to open terminal: {}", e))?;
+    let mut command = shell_command(shell.as_deref());
+    command.cwd(&cwd);
+    command.env("TERM", "xterm-256color");
+    command.env("COLORTERM", "truecolor");
+    command.env("TERM_PROGRAM", "Incito");
+    command.env("TERM_PROGRAM_VERSION", app.package_info().version.to_string());
+    for (key, value) in profile.map(|profile| profile.env).unwrap_or_default() {
+        command.env(key, value);
+    }
+    let shell = if command.is_default_prog() {
+        command.get_shell()
+    } else {
+        command.get_argv().first().map(|program| program.to_string_lossy().to_string()).unwrap_or_default()
+    };
+
+    let mut child = pair
+        .slave
+        .spawn_command(command)
+        .map_err(|e| format!("Failed to start {}: {}", shell, e))?;
+    // Only the shell should hold the other end, so reads end when it exits
+    drop(pair.slave);
+    audit::record(&app, Some(window.label()), "terminal", "open", &format!("{} in {}", shell, cwd));
+    let mut reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| format!("Failed to read terminal: {}", e))?;
+    let writer = pair
+        .master
+        .take_writer()
+        .map_err(|e| format!("Failed to write to terminal: {}", e))?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let info = TerminalInfo {
+        id: id.clone(),
+        pid: child.process_id(),
+        shell,
+        cwd,
+    };
+    let output = Arc::new(Mutex::new(TerminalOutput {
+        scrollback: Scrollback::default(),
+        channel: on_output,
+    }));
+    app.state::<Terminals>().0.lock().map_err(|e| e.to_string())?.insert(
+        id.clone(),
+        Terminal {
+            window: window.label().to_string(),
+            info: info.clone(),
+            output: output.clone(),
+            master: pair.master,
+            writer: Arc::new(tokio::sync::Mutex::new(writer)),
+            pid: child.process_id(),
+            killer: child.clone_killer(),
+        },
+    );
+
+    // Reading goes on with no one attached, so the shell never blocks on a
+    // full terminal
+    std::thread::spawn(move || {
+        let mut buffer = vec![0; READ_BUFFER_BYTES];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    let Ok(mut output) = output.lock() else { break };
+                    output.scrollback.push(&buffer[..read]);
+                    let _ = output.channel.send(InvokeResponseBody::Raw(buffer[..read].to_vec()));
+                }
+            }
+        }
+    });
+    // Windows' pseudo-consoles don't end reads on exit, so closing the
+    // terminal is left to the wait
+    let label = window.label().to_string();
+    std::thread::spawn(move || {
+        let exit_code = child.wait().map(|status| status.exit_code()).unwrap_or(1);
+        drop(remove(&app, &id));
+        let _ = app.emit_to(label.as_str(), "terminal-exited", TerminalExited { id, exit_code });
+    });
+    Ok(info)
+}
+
+// Keystrokes and pastes, as xterm.js hands them over. A large paste blocks
+// until the program reads it, so it's written off the main thread.
+#[tauri::command]
+pub async fn write_terminal(app: AppHandle, id: String, data: String) -> Result<(), String> {
+    let writer = app
+        .state::<Terminals>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&id)
+        .map(|terminal| terminal.writer.clone())
+        .ok_or_else(|| format!("Terminal not found: {}", id))?;
+    let mut writer = writer.lock_owned().await;
+    tauri::async_runtime::spawn_blocking(move || {
+        writer
+            .write_all(data.as_bytes())
+            .and_then(|_| writer.flush())
+            .map_err(|e| format!("Failed to write to terminal: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Terminal write failed: {}", e))?
+}
+
+#[tauri::command]
+pub fn resize_terminal(app: AppHandle, id: String, cols: u16, rows: u16) -> Result<(), String> {
+    let state = app.state::<Terminals>();
+    let terminals = state.0.lock().map_err(|e| e.to_string())?;
+    let terminal = terminals.get(&id).ok_or_else(|| format!("Terminal not found: {}", id))?;
+    terminal
+        .master
+        .resize(size(cols, rows))
+        .map_err(|e| format!("Failed to resize terminal: {}", e))
+}
+
+// Output kept from `from_line` on, of a terminal or of a command from
+// `run_command`, to fill a pane again after its window reloads or reopens
+#[tauri::command]
+pub fn get_terminal_buffer(
+    app: AppHandle,
+    id: String,
+    from_line: Option<usize>,
+) -> Result<ScrollbackChunk, String> {
+    let output = app
+        .state::<Terminals>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&id)
+        .map(|terminal| terminal.output.clone());
+    match output {
+        Some(output) => Ok(output.lock().map_err(|e| e.to_string())?.scrollback.since(from_line.unwrap_or(0))),
+        None => command_runner::output_since(&app, &id, from_line.unwrap_or(0))
+            .ok_or_else(|| format!("Terminal not found: {}", id)),
+    }
+}
+
+// Send a terminal's output to `on_output` from now on, after a reload
+// dropped the old channel. Everything kept so far comes back with it, with
+// nothing lost or repeated in between.
+#[tauri::command]
+pub fn attach_terminal(
+    app: AppHandle,
+    id: String,
+    on_output: Channel<InvokeResponseBody>,
+) -> Result<ScrollbackChunk, String> {
+    let output = app
+        .state::<Terminals>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(&id)
+        .map(|terminal| terminal.output.clone())
+        .ok_or_else(|| format!("Terminal not found: {}", id))?;
+    let mut output = output.lock().map_err(|e| e.to_string())?;
+    output.channel = on_output;
+    Ok(output.scrollback.since(0))
+}
+
+// Terminals still open in the calling window, e.g. after a reload
+#[tauri::command]
+pub fn list_terminals(app: AppHandle, window: WebviewWindow) -> Result<Vec<TerminalInfo>, String> {
+    let state = app.state::<Terminals>();
+    let terminals = state.0.lock().map_err(|e| e.to_string())?;
+    Ok(terminals
+        .values()
+        .filter(|terminal| terminal.window == window.label())
+        .map(|terminal| terminal.info.clone())
+        .collect())
+}
+
+// The shell and anything running in it. "terminal-exited" still follows,
+// once the shell is gone.
+#[tauri::command]
+pub fn kill_terminal(app: AppHandle, id: String, force: Option<bool>) -> Result<(), String> {
+    let terminal = remove(&app, &id).ok_or_else(|| format!("Terminal not found: {}", id))?;
+    terminal.kill(force.unwrap_or(false))
+}
+
+pub fn on_window_destroyed(app: &AppHandle, label: &str) {
+    let closed: Vec<Terminal> = match app.state::<Terminals>().0.lock() {
+        Ok(mut terminals) => {
+            let ids: Vec<String> = terminals
+                .iter()
+                .filter(|(_, terminal)| terminal.window == label)
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.iter().filter_map(|id| terminals.remove(id)).collect()
+        }
+        Err(_) => return,
+    };
+    for terminal in closed {
+        let _ = terminal.kill(false);
+    }
+}
+
+// On quit, so no shells outlive the app
+pub fn kill_all(app: &AppHandle) {
+    let closed: Vec<Terminal> = match app.state::<Terminals>().0.lock() {
+        Ok(mut terminals) => terminals.drain().map(|(_, terminal)| terminal).collect(),
+        Err(_) => return,
+    };
+    for terminal in closed {
+        let _ = terminal.kill(true);
+    }
+}
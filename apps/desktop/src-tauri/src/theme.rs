@@ -0,0 +1,94 @@
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter, Manager, Theme};
+
+use crate::{server, settings, tray, windows};
+
+// The OS appearance and the user's choice for native chrome. The OS value is
+// only observable while windows follow it, so it's cached for when a theme
+// is forced.
+#[derive(Default)]
+pub struct ThemeState {
+    system: Mutex<Option<Theme>>,
+    preference: Mutex<Option<Theme>>,
+}
+
+fn theme_name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Dark => "dark",
+        _ => "light",
+    }
+}
+
+pub fn parse_preference(value: &str) -> Result<Option<Theme>, String> {
+    match value {
+        "light" => Ok(Some(Theme::Light)),
+        "dark" => Ok(Some(Theme::Dark)),
+        "auto" | "" => Ok(None),
+        other => Err(format!("Unknown theme: {}", other)),
+    }
+}
+
+pub fn system_theme(app: &AppHandle) -> Theme {
+    let state = app.state::<ThemeState>();
+    let cached = state.system.lock().ok().and_then(|system| *system);
+    let following = state.preference.lock().is_ok_and(|preference| preference.is_none());
+    match cached {
+        Some(theme) if !following => theme,
+        _ => windows::focused_window(app)
+            .and_then(|window| window.theme().ok())
+            .or(cached)
+            .unwrap_or(Theme::Light),
+    }
+}
+
+pub fn is_dark(app: &AppHandle) -> bool {
+    system_theme(app) == Theme::Dark
+}
+
+// Window chrome, menus and webviews in the chosen theme, or the OS one
+fn apply(app: &AppHandle, preference: Option<Theme>) -> Result<(), String> {
+    // Remember what the OS is using before a forced theme hides it
+    let current = system_theme(app);
+    let state = app.state::<ThemeState>();
+    *state.system.lock().map_err(|e| e.to_string())? = Some(current);
+    *state.preference.lock().map_err(|e| e.to_string())? = preference;
+    app.set_theme(preference);
+    Ok(())
+}
+
+pub async fn load_saved(app: &AppHandle) -> Result<(), String> {
+    let saved = settings::get(app).appearance.theme;
+    apply(app, parse_preference(&saved).unwrap_or(None))?;
+    tray::set_server_status(app, server::is_running(app));
+    Ok(())
+}
+
+// Called from window events. Fires `system-theme-changed` and redraws the
+// tray icon for the new menu bar / taskbar colour.
+pub fn on_theme_changed(app: &AppHandle, theme: Theme) {
+    let state = app.state::<ThemeState>();
+    let following = state.preference.lock().is_ok_and(|preference| preference.is_none());
+    if !following {
+        return;
+    }
+    let previous = state.system.lock().ok().and_then(|mut system| system.replace(theme));
+    if previous != Some(theme) {
+        tray::set_server_status(app, server::is_running(app));
+        let _ = app.emit("system-theme-changed", theme_name(theme));
+    }
+}
+
+#[tauri::command]
+pub fn get_system_theme(app: AppHandle) -> String {
+    theme_name(system_theme(&app)).to_string()
+}
+
+// "light", "dark", or "auto" to follow the OS
+#[tauri::command]
+pub async fn set_window_theme(app: AppHandle, theme: String) -> Result<(), String> {
+    let preference = parse_preference(&theme)?;
+    apply(&app, preference)?;
+    let theme = if preference.is_some() { theme } else { "auto".to_string() };
+    settings::update(&app, |settings| settings.appearance.theme = theme).map(|_| ())
+}
@@ -0,0 +1,153 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow, WebviewWindowBuilder, Wry};
+
+use crate::windows::MAIN_WINDOW;
+
+// Height of the toolbar the frontend draws in place of the OS title bar
+const TITLE_BAR_HEIGHT: f64 = 40.0;
+// Room kept free on the left for the macOS traffic lights
+#[cfg(target_os = "macos")]
+const TRAFFIC_LIGHTS_INSET: f64 = 78.0;
+
+#[derive(Clone, serde::Serialize)]
+pub struct TitleBarInfo {
+    // "overlay" (macOS: native traffic lights over the webview), "custom"
+    // (no OS frame; the frontend draws the window controls) or "native"
+    pub style: String,
+    pub height: f64,
+    // Logical pixels to leave empty at the left edge of the title bar
+    pub inset_left: f64,
+    // Whether the frontend should render minimize/maximize/close itself
+    pub draw_controls: bool,
+}
+
+// Windows that were full screen at the last resize, so title bar changes are
+// only announced when that flips
+#[derive(Default)]
+pub struct TitleBarState(Mutex<HashSet<String>>);
+
+// Give windows created in code the same frame as the main window's config
+pub fn configure<'a>(app: &AppHandle, builder: WebviewWindowBuilder<'a, Wry, AppHandle>) -> WebviewWindowBuilder<'a, Wry, AppHandle> {
+    let Some(config) = app.config().app.windows.iter().find(|window| window.label == MAIN_WINDOW) else {
+        return builder;
+    };
+    let builder = builder.decorations(config.decorations);
+    #[cfg(target_os = "macos")]
+    let builder = {
+        let mut builder = builder
+            .title_bar_style(config.title_bar_style)
+            .hidden_title(config.hidden_title);
+        if let Some(ref position) = config.traffic_light_position {
+            builder = builder.traffic_light_position(tauri::LogicalPosition::new(position.x, position.y));
+        }
+        builder
+    };
+    builder
+}
+
+pub fn info(window: &WebviewWindow) -> TitleBarInfo {
+    let fullscreen = window.is_fullscreen().unwrap_or(false);
+
+    #[cfg(target_os = "macos")]
+    {
+        // Overlay comes from tauri.macos.conf.json; the traffic lights are
+        // hidden in full screen
+        TitleBarInfo {
+            style: "overlay".to_string(),
+            height: TITLE_BAR_HEIGHT,
+            inset_left: if fullscreen { 0.0 } else { TRAFFIC_LIGHTS_INSET },
+            draw_controls: false,
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let custom = !window.is_decorated().unwrap_or(true);
+        TitleBarInfo {
+            style: if custom { "custom" } else { "native" }.to_string(),
+            height: if custom { TITLE_BAR_HEIGHT } else { 0.0 },
+            inset_left: 0.0,
+            draw_controls: custom && !fullscreen,
+        }
+    }
+}
+
+// Called on resize; full screen changes what the title bar has to make room for
+pub fn on_resized(window: &WebviewWindow) {
+    let fullscreen = window.is_fullscreen().unwrap_or(false);
+    let changed = window
+        .app_handle()
+        .state::<TitleBarState>()
+        .0
+        .lock()
+        .is_ok_and(|mut fullscreen_windows| {
+            if fullscreen {
+                fullscreen_windows.insert(window.label().to_string())
+            } else {
+                fullscreen_windows.remove(window.label())
+            }
+        });
+    if changed {
+        let _ = window.emit_to(window.label(), "title-bar-changed", info(window));
+    }
+}
+
+fn toggle_maximized(window: &WebviewWindow) -> Result<(), String> {
+    let result = if window.is_maximized().unwrap_or(false) {
+        window.unmaximize()
+    } else {
+        window.maximize()
+    };
+    result.map_err(|e| format!("Failed to resize window: {}", e))
+}
+
+// What the user picked under "Double-click a window's title bar to" in
+// System Settings: "Maximize" (zoom), "Minimize" or "None"
+#[cfg(target_os = "macos")]
+fn double_click_action() -> String {
+    std::process::Command::new("defaults")
+        .args(["read", "-g", "AppleActionOnDoubleClick"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "Maximize".to_string())
+}
+
+#[tauri::command]
+pub fn get_title_bar_info(window: WebviewWindow) -> TitleBarInfo {
+    info(&window)
+}
+
+// For mousedown on the toolbar's empty space
+#[tauri::command]
+pub fn start_dragging(window: WebviewWindow) -> Result<(), String> {
+    window
+        .start_dragging()
+        .map_err(|e| format!("Failed to drag window: {}", e))
+}
+
+#[tauri::command]
+pub fn toggle_maximize(window: WebviewWindow) -> Result<(), String> {
+    toggle_maximized(&window)
+}
+
+// Double-click on the toolbar, behaving like a native title bar would
+#[tauri::command]
+pub fn title_bar_double_click(window: WebviewWindow) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        match double_click_action().as_str() {
+            "Minimize" => window.minimize().map_err(|e| e.to_string()),
+            "None" => Ok(()),
+            _ => toggle_maximized(&window),
+        }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        toggle_maximized(&window)
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use crate::paths;
+
+// One line of a `~/.claude/projects/<project>/<session>.jsonl` transcript
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptEntry {
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    #[serde(default)]
+    pub uuid: Option<String>,
+    #[serde(default)]
+    pub parent_uuid: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub git_branch: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub is_sidechain: bool,
+    #[serde(default)]
+    pub is_meta: bool,
+    #[serde(default)]
+    pub message: Option<TranscriptMessage>,
+    // Set on "summary" entries
+    #[serde(default)]
+    pub summary: Option<String>,
+    // Byte offset of the line in the transcript, filled in by the parser
+    #[serde(default)]
+    pub offset: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TranscriptMessage {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_content")]
+    pub content: Vec<ContentBlock>,
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentBlock {
+    Text {
+        text: String,
+    },
+    Thinking {
+        #[serde(default)]
+        thinking: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        #[serde(default)]
+        input: Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        #[serde(default)]
+        content: Value,
+        #[serde(default)]
+        is_error: bool,
+    },
+    Image {
+        #[serde(default)]
+        source: Value,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TokenUsage {
+    #[serde(default)]
+    pub input_tokens: u64,
+    #[serde(default)]
+    pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u64,
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
+}
+
+// User messages store plain prompts as a string rather than a block list
+fn deserialize_content<'de, D>(deserializer: D) -> Result<Vec<ContentBlock>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::String(text) => Ok(vec![ContentBlock::Text { text }]),
+        Value::Array(blocks) => Ok(blocks
+            .into_iter()
+            .map(|block| serde_json::from_value(block).unwrap_or(ContentBlock::Unknown))
+            .collect()),
+        _ => Ok(Vec::new()),
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct TranscriptChunk {
+    pub session_id: String,
+    pub path: String,
+    pub entries: Vec<TranscriptEntry>,
+    // Pass back as `from_offset` to continue where this chunk ended
+    pub next_offset: u64,
+    pub file_size: u64,
+}
+
+// Parse complete lines starting at `offset`. A trailing line without a
+// newline is still being written by the CLI, so it is left for the next read.
+pub fn parse_from(path: &Path, offset: u64, limit: Option<usize>) -> Result<(Vec<TranscriptEntry>, u64), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+    reader
+        .seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Failed to seek transcript: {}", e))?;
+
+    let mut entries = Vec::new();
+    let mut position = offset;
+    let mut line = Vec::new();
+
+    loop {
+        if limit.is_some_and(|limit| entries.len() >= limit) {
+            break;
+        }
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|e| format!("Failed to read transcript: {}", e))?;
+        if read == 0 || line.last() != Some(&b'\n') {
+            break;
+        }
+
+        let line_offset = position;
+        position += read as u64;
+
+        // Malformed or unknown lines are skipped rather than failing the read
+        if let Ok(mut entry) = serde_json::from_slice::<TranscriptEntry>(&line) {
+            entry.offset = line_offset;
+            entries.push(entry);
+        }
+    }
+
+    Ok((entries, position))
+}
+
+// Every transcript on disk as (project directory name, path)
+pub fn list_transcript_files() -> Result<Vec<(String, PathBuf)>, String> {
+    let projects_dir = paths::claude_dir()?.join("projects");
+    if !projects_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let projects = std::fs::read_dir(&projects_dir)
+        .map_err(|e| format!("Failed to read {}: {}", projects_dir.display(), e))?;
+    let mut files = Vec::new();
+    for project in projects.filter_map(Result::ok) {
+        if !project.path().is_dir() {
+            continue;
+        }
+        let dir_name = project.file_name().to_string_lossy().to_string();
+        let Ok(entries) = std::fs::read_dir(project.path()) else {
+            continue;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "jsonl") {
+                files.push((dir_name.clone(), path));
+            }
+        }
+    }
+    Ok(files)
+}
+
+// Transcripts are grouped per project directory; the session id is the file stem
+pub fn find_session_file(session_id: &str) -> Result<PathBuf, String> {
+    if session_id.is_empty() || session_id.contains(['/', '\\']) || session_id.contains("..") {
+        return Err(format!("Invalid session id: {}", session_id));
+    }
+
+    let projects_dir = paths::claude_dir()?.join("projects");
+    let file_name = format!("{}.jsonl", session_id);
+    let projects = std::fs::read_dir(&projects_dir)
+        .map_err(|e| format!("Failed to read {}: {}", projects_dir.display(), e))?;
+
+    projects
+        .filter_map(Result::ok)
+        .map(|project| project.path().join(&file_name))
+        .find(|candidate| candidate.is_file())
+        .ok_or_else(|| format!("Session not found: {}", session_id))
+}
+
+fn read_chunk(session_id: &str, from_offset: u64, limit: Option<usize>) -> Result<TranscriptChunk, String> {
+    let path = find_session_file(session_id)?;
+    let file_size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or_default();
+    // A transcript that shrank was rewritten, so start over
+    let from_offset = if from_offset > file_size { 0 } else { from_offset };
+    let (entries, next_offset) = parse_from(&path, from_offset, limit)?;
+
+    Ok(TranscriptChunk {
+        session_id: session_id.to_string(),
+        path: path.to_string_lossy().to_string(),
+        entries,
+        next_offset,
+        file_size,
+    })
+}
+
+#[tauri::command]
+pub async fn read_session_transcript(
+    session_id: String,
+    from_offset: Option<u64>,
+    limit: Option<usize>,
+) -> Result<TranscriptChunk, String> {
+    tauri::async_runtime::spawn_blocking(move || read_chunk(&session_id, from_offset.unwrap_or(0), limit))
+        .await
+        .map_err(|e| format!("Transcript task failed: {}", e))?
+}
+
+// Tails currently being followed, keyed by session id
+#[derive(Default)]
+pub struct TranscriptWatchers(Mutex<HashMap<String, Arc<AtomicBool>>>);
+
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Follow a transcript as the CLI appends to it, emitting each batch of new
+// entries as a `session-transcript-appended` event (a TranscriptChunk)
+#[tauri::command]
+pub fn watch_session_transcript(
+    app: AppHandle,
+    state: tauri::State<'_, TranscriptWatchers>,
+    session_id: String,
+    from_offset: Option<u64>,
+) -> Result<(), String> {
+    let path = find_session_file(&session_id)?;
+    let mut watchers = state.0.lock().map_err(|e| e.to_string())?;
+    if watchers.contains_key(&session_id) {
+        return Ok(());
+    }
+
+    let running = Arc::new(AtomicBool::new(true));
+    watchers.insert(session_id.clone(), running.clone());
+
+    let mut offset = match from_offset {
+        Some(offset) => offset,
+        None => std::fs::metadata(&path).map(|m| m.len()).unwrap_or_default(),
+    };
+
+    std::thread::spawn(move || {
+        while running.load(Ordering::Relaxed) {
+            let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or_default();
+            if size < offset {
+                offset = 0;
+            }
+            if size > offset {
+                if let Ok((entries, next_offset)) = parse_from(&path, offset, None) {
+                    offset = next_offset;
+                    if !entries.is_empty() {
+                        let _ = app.emit(
+                            "session-transcript-appended",
+                            TranscriptChunk {
+                                session_id: session_id.clone(),
+                                path: path.to_string_lossy().to_string(),
+                                entries,
+                                next_offset,
+                                file_size: size,
+                            },
+                        );
+                    }
+                }
+            }
+            std::thread::sleep(TAIL_POLL_INTERVAL);
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unwatch_session_transcript(
+    state: tauri::State<'_, TranscriptWatchers>,
+    session_id: String,
+) -> Result<(), String> {
+    let mut watchers = state.0.lock().map_err(|e| e.to_string())?;
+    if let Some(running) = watchers.remove(&session_id) {
+        running.store(false, Ordering::Relaxed);
+    }
+    Ok(())
+}
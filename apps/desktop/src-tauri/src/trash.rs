@@ -0,0 +1,42 @@
+use std::path::Path;
+
+#[derive(Clone, serde::Serialize)]
+pub struct TrashResult {
+    pub path: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Move a file or folder to the Recycle Bin or Trash, so a delete made from
+// the app can be undone from the OS
+pub fn move_to_trash(path: &Path) -> Result<(), String> {
+    if std::fs::symlink_metadata(path).is_err() {
+        return Err(format!("Not found: {}", path.display()));
+    }
+    trash::delete(path).map_err(|e| format!("Failed to move {} to the trash: {}", path.display(), e))
+}
+
+// Trash each path on its own, so one that fails doesn't stop the rest
+#[tauri::command]
+pub async fn trash_paths(paths: Vec<String>) -> Result<Vec<TrashResult>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        paths
+            .into_iter()
+            .map(|path| match move_to_trash(Path::new(&path)) {
+                Ok(()) => TrashResult {
+                    path,
+                    ok: true,
+                    error: None,
+                },
+                Err(e) => TrashResult {
+                    path,
+                    ok: false,
+                    error: Some(e),
+                },
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Trash task failed: {}", e))
+}
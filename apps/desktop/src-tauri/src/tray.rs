@@ -0,0 +1,226 @@
+use std::sync::Mutex;
+
+use tauri::image::Image;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Listener, Manager, Wry};
+
+use crate::{i18n, projects, server, theme, windows};
+
+const TRAY_ID: &str = "main";
+const RECENT_PROJECTS_LIMIT: i64 = 8;
+
+const RUNNING_COLOR: [u8; 3] = [0x22, 0xc5, 0x5e];
+const STOPPED_COLOR: [u8; 3] = [0x9c, 0xa3, 0xaf];
+// Lighter grey so the dot stays visible on a dark menu bar / taskbar
+const STOPPED_COLOR_DARK: [u8; 3] = [0xd1, 0xd5, 0xdb];
+
+// Menu items whose text or enabled state follows the server
+struct TrayItems {
+    status: MenuItem<Wry>,
+    start: MenuItem<Wry>,
+    stop: MenuItem<Wry>,
+    restart: MenuItem<Wry>,
+    recent: Submenu<Wry>,
+}
+
+// The items are replaced whenever the tray menu is rebuilt
+#[derive(Default)]
+pub struct TrayState {
+    items: Mutex<Option<TrayItems>>,
+    // Project paths in the order of the recent projects submenu
+    recent_paths: Mutex<Vec<String>>,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct OpenProject {
+    path: String,
+}
+
+// The app icon with a status dot in the bottom-right corner
+fn status_icon(app: &AppHandle, color: [u8; 3]) -> Option<Image<'static>> {
+    let icon = app.default_window_icon()?;
+    let (width, height) = (icon.width(), icon.height());
+    let mut rgba = icon.rgba().to_vec();
+
+    let radius = width.min(height) as f32 * 0.22;
+    let (cx, cy) = (width as f32 - radius - 1.0, height as f32 - radius - 1.0);
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+            if dx * dx + dy * dy <= radius * radius {
+                let i = ((y * width + x) * 4) as usize;
+                rgba[i..i + 4].copy_from_slice(&[color[0], color[1], color[2], 0xff]);
+            }
+        }
+    }
+    Some(Image::new_owned(rgba, width, height))
+}
+
+fn build_menu(app: &AppHandle) -> Result<Menu<Wry>, tauri::Error> {
+    let app_name = [("app", "Incito")];
+    let status = MenuItem::with_id(app, "tray-status", i18n::t(app, "tray.serverStopped"), false, None::<&str>)?;
+    let start = MenuItem::with_id(app, "tray-start", i18n::t(app, "tray.startServer"), true, None::<&str>)?;
+    let stop = MenuItem::with_id(app, "tray-stop", i18n::t(app, "tray.stopServer"), false, None::<&str>)?;
+    let restart = MenuItem::with_id(app, "tray-restart", i18n::t(app, "tray.restartServer"), false, None::<&str>)?;
+    let open = MenuItem::with_id(app, "tray-open", i18n::t_with(app, "tray.open", &app_name), true, None::<&str>)?;
+    let recent = Submenu::with_id(app, "tray-recent", i18n::t(app, "tray.recentProjects"), false)?;
+    let quit = MenuItem::with_id(app, "tray-quit", i18n::t_with(app, "tray.quit", &app_name), true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app)?,
+            &start,
+            &stop,
+            &restart,
+            &PredefinedMenuItem::separator(app)?,
+            &open,
+            &recent,
+            &PredefinedMenuItem::separator(app)?,
+            &quit,
+        ],
+    )?;
+
+    if let Ok(mut items) = app.state::<TrayState>().items.lock() {
+        *items = Some(TrayItems {
+            status,
+            start,
+            stop,
+            restart,
+            recent,
+        });
+    }
+    Ok(menu)
+}
+
+pub fn create(app: &AppHandle) -> Result<(), tauri::Error> {
+    let menu = build_menu(app)?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .tooltip("Incito")
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = status_icon(app, STOPPED_COLOR) {
+        builder = builder.icon(icon);
+    }
+    builder.build(app)?;
+
+    for event in projects::CHANGE_EVENTS {
+        let handle = app.clone();
+        app.listen_any(*event, move |_| {
+            let handle = handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let _ = update_recent_projects(&handle).await;
+            });
+        });
+    }
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _ = update_recent_projects(&handle).await;
+    });
+    Ok(())
+}
+
+// Swap in a freshly built menu, e.g. after the language changed
+pub async fn rebuild_menu(app: &AppHandle) -> Result<(), String> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else { return Ok(()) };
+    let menu = build_menu(app).map_err(|e| e.to_string())?;
+    tray.set_menu(Some(menu)).map_err(|e| e.to_string())?;
+    set_server_status(app, server::is_running(app));
+    update_recent_projects(app).await
+}
+
+pub fn set_server_status(app: &AppHandle, running: bool) {
+    let Some(state) = app.try_state::<TrayState>() else { return };
+    if let Ok(items) = state.items.lock() {
+        if let Some(items) = items.as_ref() {
+            let status = if running { "tray.serverRunning" } else { "tray.serverStopped" };
+            let _ = items.status.set_text(i18n::t(app, status));
+            let _ = items.start.set_enabled(!running);
+            let _ = items.stop.set_enabled(running);
+            let _ = items.restart.set_enabled(running);
+        }
+    }
+
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let color = match (running, theme::is_dark(app)) {
+            (true, _) => RUNNING_COLOR,
+            (false, true) => STOPPED_COLOR_DARK,
+            (false, false) => STOPPED_COLOR,
+        };
+        if let Some(icon) = status_icon(app, color) {
+            let _ = tray.set_icon(Some(icon));
+        }
+        let tooltip = if running { "tray.tooltipRunning" } else { "tray.tooltipStopped" };
+        let _ = tray.set_tooltip(Some(i18n::t_with(app, tooltip, &[("app", "Incito")])));
+    }
+}
+
+// Rebuild the recent projects submenu from the project registry, as the
+// app menu and dock are
+pub async fn update_recent_projects(app: &AppHandle) -> Result<(), String> {
+    let Some(state) = app.try_state::<TrayState>() else { return Ok(()) };
+    let projects = projects::recent_projects(app, RECENT_PROJECTS_LIMIT).await?;
+
+    let recent = state
+        .items
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_ref()
+        .map(|items| items.recent.clone());
+    let Some(recent) = recent else { return Ok(()) };
+    for item in recent.items().map_err(|e| e.to_string())? {
+        recent.remove(&item).map_err(|e| e.to_string())?;
+    }
+    for (index, project) in projects.iter().enumerate() {
+        let item = MenuItem::with_id(app, format!("tray-recent-{}", index), &project.name, true, None::<&str>)
+            .map_err(|e| e.to_string())?;
+        recent.append(&item).map_err(|e| e.to_string())?;
+    }
+    let _ = recent.set_enabled(!projects.is_empty());
+
+    *state.recent_paths.lock().map_err(|e| e.to_string())? = projects.into_iter().map(|project| project.path).collect();
+    Ok(())
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    match event.id().as_ref() {
+        "tray-start" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = server::start(&app, None, "tray").await {
+                    let _ = app.emit("claude-server-error", e);
+                }
+            });
+        }
+        "tray-stop" => {
+            let _ = server::stop(app, false, "tray");
+        }
+        "tray-restart" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = server::restart(&app, "tray").await {
+                    let _ = app.emit("claude-server-error", e);
+                }
+            });
+        }
+        "tray-open" => {
+            let _ = windows::show_main_window(app);
+        }
+        // Goes through ExitRequested, which asks first if a task is running,
+        // and Exit, which stops the sidecar
+        "tray-quit" => app.exit(0),
+        id => {
+            let Some(index) = id.strip_prefix("tray-recent-").and_then(|i| i.parse::<usize>().ok()) else {
+                return;
+            };
+            let Some(state) = app.try_state::<TrayState>() else { return };
+            let path = state.recent_paths.lock().ok().and_then(|paths| paths.get(index).cloned());
+            if let Some(path) = path {
+                let _ = windows::show_main_window(app);
+                let _ = app.emit("tray-open-project", OpenProject { path });
+            }
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_updater::{Update, Updater, UpdaterExt};
+use tokio::sync::oneshot;
+
+use crate::{connectivity, db, net, rollback, server, settings};
+
+const LAST_CHECKED_SETTING: &str = "update_last_checked";
+pub const DEFAULT_CHECK_INTERVAL_HOURS: u32 = 24;
+// How often the background task wakes to see whether a check is due
+const CHECK_TICK: Duration = Duration::from_secs(15 * 60);
+pub const CHANNELS: &[&str] = &["stable", "beta", "nightly"];
+
+#[derive(Clone, serde::Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub body: Option<String>,
+    pub date: Option<String>,
+    pub channel: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct DownloadProgress {
+    received: u64,
+    total: Option<u64>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct UpdateCheckSettings {
+    // 0 turns background checks off
+    pub interval_hours: u32,
+    pub check_on_metered: bool,
+}
+
+// The download in flight, so it can be cancelled
+#[derive(Default)]
+pub struct UpdateDownload(Mutex<Option<JoinHandle<()>>>);
+
+// A downloaded, verified package waiting for the app to quit
+#[derive(Default)]
+pub struct StagedUpdate(Mutex<Option<(Update, Vec<u8>)>>);
+
+// The copy of this version taken for rolling back once an update is staged
+#[derive(Default)]
+pub struct StagedSnapshot(Mutex<Option<JoinHandle<()>>>);
+
+pub async fn current_channel(app: &AppHandle) -> Result<String, String> {
+    let channel = settings::get(app).updates.channel;
+    Ok(if CHANNELS.contains(&channel.as_str()) {
+        channel
+    } else {
+        CHANNELS[0].to_string()
+    })
+}
+
+fn plugin_config(app: &AppHandle, key: &str) -> Option<serde_json::Value> {
+    app.config().plugins.0.get("updater")?.get(key).cloned()
+}
+
+// The signing key from tauri.conf.json, which also signs sidecar builds
+pub fn public_key(app: &AppHandle) -> Result<String, String> {
+    plugin_config(app, "pubkey")
+        .and_then(|pubkey| pubkey.as_str().map(str::to_string))
+        .ok_or_else(|| "No updater public key configured".to_string())
+}
+
+// Manifests are published next to the one from tauri.conf.json; this swaps
+// its file name for `file_name`
+pub fn manifest_endpoints(app: &AppHandle, file_name: Option<&str>) -> Result<Vec<Url>, String> {
+    let configured = plugin_config(app, "endpoints")
+        .and_then(|endpoints| endpoints.as_array().cloned())
+        .ok_or_else(|| "No updater endpoints configured".to_string())?;
+    configured
+        .iter()
+        .filter_map(|endpoint| endpoint.as_str())
+        .map(|endpoint| {
+            let mut url = Url::parse(endpoint).map_err(|e| format!("Invalid updater endpoint {}: {}", endpoint, e))?;
+            if let Some(file_name) = file_name {
+                if let Ok(mut segments) = url.path_segments_mut() {
+                    segments.pop().push(file_name);
+                }
+            }
+            Ok(url)
+        })
+        .collect()
+}
+
+// Each channel has its own manifest: latest.json, beta.json, nightly.json
+pub fn channel_endpoints(app: &AppHandle, channel: &str) -> Result<Vec<Url>, String> {
+    if channel == CHANNELS[0] {
+        manifest_endpoints(app, None)
+    } else {
+        manifest_endpoints(app, Some(&format!("{}.json", channel)))
+    }
+}
+
+// Newer releases win as usual. Someone on a beta or nightly build who moves
+// back to stable is offered the latest stable release even though it sorts
+// lower than their prerelease.
+pub async fn updater(app: &AppHandle) -> Result<(Updater, String), String> {
+    let channel = current_channel(app).await?;
+    let stable = channel == CHANNELS[0];
+    let options = net::client_options(&net::current(app))?;
+    let updater = app
+        .updater_builder()
+        .endpoints(channel_endpoints(app, &channel)?)
+        .map_err(|e| e.to_string())?
+        .configure_client(move |builder| net::apply(builder, &options))
+        .version_comparator(move |current, release| {
+            if stable && !current.pre.is_empty() {
+                release.version != current
+            } else {
+                release.version > current
+            }
+        })
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok((updater, channel))
+}
+
+fn skipped_versions(app: &AppHandle) -> Vec<String> {
+    settings::get(app).updates.skipped_versions
+}
+
+fn save_skipped_versions(app: &AppHandle, versions: Vec<String>) -> Result<(), String> {
+    settings::update(app, |settings| settings.updates.skipped_versions = versions).map(|_| ())
+}
+
+// Releases the user chose to skip are reported as no update. Installing
+// doesn't look at the list, so a skipped release can still be installed
+// on purpose.
+pub async fn check(app: &AppHandle) -> Result<Option<UpdateInfo>, String> {
+    let (updater, channel) = updater(app).await?;
+    let skipped = skipped_versions(app);
+
+    match updater.check().await {
+        Ok(Some(update)) if skipped.contains(&update.version) => Ok(None),
+        Ok(Some(update)) => {
+            Ok(Some(UpdateInfo {
+                version: update.version.clone(),
+                body: update.body.clone(),
+                date: update.date.map(|d| d.to_string()),
+                channel,
+            }))
+        }
+        Ok(None) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn check_settings(app: &AppHandle) -> UpdateCheckSettings {
+    let updates = settings::get(app).updates;
+    UpdateCheckSettings {
+        interval_hours: updates.check_interval_hours,
+        check_on_metered: updates.check_on_metered,
+    }
+}
+
+async fn check_due(app: &AppHandle) -> Result<bool, String> {
+    let settings = check_settings(app);
+    // Offline it would only fail; it runs once the connection is back
+    let offline = !connectivity::is_online(app);
+    if offline || settings.interval_hours == 0 || (!settings.check_on_metered && net::is_metered()) {
+        return Ok(false);
+    }
+    let last_checked = db::get_setting(app, LAST_CHECKED_SETTING)
+        .await?
+        .and_then(|value| chrono::DateTime::parse_from_rfc3339(&value).ok());
+    Ok(last_checked.is_none_or(|last_checked| {
+        chrono::Utc::now().signed_duration_since(last_checked) >= chrono::Duration::hours(settings.interval_hours.into())
+    }))
+}
+
+// Checks in the background once the interval has passed since the last
+// successful check, across restarts. A failed check (usually offline) isn't
+// recorded, so it's retried on the next tick.
+pub fn start_background_checks(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            check_if_due(&app).await;
+            tokio::time::sleep(CHECK_TICK).await;
+        }
+    });
+}
+
+pub async fn check_if_due(app: &AppHandle) {
+    if check_due(app).await.unwrap_or(false) {
+        if let Ok(available) = check(app).await {
+            let _ = db::set_setting(app, LAST_CHECKED_SETTING, &chrono::Utc::now().to_rfc3339()).await;
+            if let Some(info) = available {
+                let _ = app.emit("update-available", info);
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> Result<Option<UpdateInfo>, String> {
+    check(&app).await
+}
+
+async fn available_update(app: &AppHandle) -> Result<Update, String> {
+    let (updater, _) = updater(app).await?;
+    match updater.check().await {
+        Ok(Some(update)) => Ok(update),
+        Ok(None) => Err("No update available".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// Emits `update-download-progress` per chunk and `update-ready` once the
+// package is downloaded and its signature checked. The plugin's finish
+// callback comes before that check, so it isn't used for this.
+async fn download(app: &AppHandle, update: Update) -> Result<(Update, Vec<u8>), String> {
+    // Runs as its own task so cancelling can abort it; the result comes back
+    // over the channel, which closes without one if it was aborted
+    let (result_tx, result_rx) = oneshot::channel();
+    let handle = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        let mut received = 0u64;
+        let progress = handle.clone();
+        let result = update
+            .download(
+                move |chunk_length, total| {
+                    received += chunk_length as u64;
+                    let _ = progress.emit("update-download-progress", DownloadProgress { received, total });
+                },
+                || {},
+            )
+            .await
+            .map_err(|e| e.to_string());
+        if result.is_ok() {
+            let _ = handle.emit("update-ready", ());
+        }
+        let _ = result_tx.send(result.map(|bytes| (update, bytes)));
+    });
+
+    let state = app.state::<UpdateDownload>();
+    let previous = state.0.lock().map_err(|e| e.to_string())?.replace(task);
+    if let Some(previous) = previous {
+        previous.abort();
+    }
+
+    let result = result_rx.await.unwrap_or_else(|_| Err("Update download cancelled".to_string()));
+    if let Ok(mut download) = state.0.lock() {
+        download.take();
+    }
+    result
+}
+
+fn install(app: &AppHandle, update: &Update, bytes: &[u8]) -> Result<(), String> {
+    // The app restarts after installing, and on Windows the installer exits
+    // the process right away, skipping RunEvent::Exit, so the sidecar is
+    // stopped first
+    let _ = server::stop(app, true, "updater");
+    update.install(bytes).map_err(|e| format!("Failed to install update: {}", e))
+}
+
+// Whether the rollback copy started when the update was staged is done.
+// One that failed counts, as it does for `install_update`.
+fn snapshot_finished(app: &AppHandle) -> bool {
+    app.try_state::<StagedSnapshot>()
+        .and_then(|snapshot| snapshot.0.lock().ok().and_then(|mut task| task.take()))
+        .is_some_and(|task| task.inner().is_finished())
+}
+
+// From RunEvent::ExitRequested: apply an update staged by
+// `download_update_in_background` on the way out. Quitting doesn't wait on
+// a rollback copy still being taken; the update is left for the next time
+// instead of being installed with no way back.
+pub fn install_staged(app: &AppHandle) {
+    let staged = app
+        .try_state::<StagedUpdate>()
+        .and_then(|staged| staged.0.lock().ok().and_then(|mut staged| staged.take()));
+    if let Some((update, bytes)) = staged {
+        if !snapshot_finished(app) {
+            eprintln!("Not installing {} yet: the copy to roll back to isn't finished", update.version);
+            return;
+        }
+        if let Err(e) = install(app, &update, &bytes) {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+pub fn discard_staged(app: &AppHandle) {
+    if let Some(staged) = app.try_state::<StagedUpdate>() {
+        if let Ok(mut staged) = staged.0.lock() {
+            staged.take();
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn install_update(app: AppHandle) -> Result<(), String> {
+    let update = available_update(&app).await?;
+    let (update, bytes) = download(&app, update).await?;
+    // Keep what's being replaced so `rollback_update` can bring it back
+    rollback::take_snapshot(&app).await;
+    install(&app, &update, &bytes)
+}
+
+// Fetch and verify the update now, install it when the app quits
+#[tauri::command]
+pub async fn download_update_in_background(app: AppHandle) -> Result<UpdateInfo, String> {
+    let channel = current_channel(&app).await?;
+    let update = available_update(&app).await?;
+    let (update, bytes) = download(&app, update).await?;
+    let info = UpdateInfo {
+        version: update.version.clone(),
+        body: update.body.clone(),
+        date: update.date.map(|d| d.to_string()),
+        channel,
+    };
+    *app.state::<StagedUpdate>().0.lock().map_err(|e| e.to_string())? = Some((update, bytes));
+    let _ = app.emit("update-staged", info.clone());
+    // Now rather than on the way out, where the copy would hold up quitting
+    let handle = app.clone();
+    let snapshot = tauri::async_runtime::spawn(async move { rollback::take_snapshot(&handle).await });
+    // One already running for an earlier update is waited on by this one
+    *app.state::<StagedSnapshot>().0.lock().map_err(|e| e.to_string())? = Some(snapshot);
+    Ok(info)
+}
+
+// The version waiting to be installed on quit, if any
+#[tauri::command]
+pub fn get_staged_update(app: AppHandle) -> Option<String> {
+    app.state::<StagedUpdate>()
+        .0
+        .lock()
+        .ok()
+        .and_then(|staged| staged.as_ref().map(|(update, _)| update.version.clone()))
+}
+
+#[tauri::command]
+pub fn cancel_update_download(app: AppHandle) -> Result<bool, String> {
+    let task = app.state::<UpdateDownload>().0.lock().map_err(|e| e.to_string())?.take();
+    match task {
+        Some(task) => {
+            task.abort();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[tauri::command]
+pub async fn get_update_channel(app: AppHandle) -> Result<String, String> {
+    current_channel(&app).await
+}
+
+#[tauri::command]
+pub async fn set_update_channel(app: AppHandle, channel: String) -> Result<(), String> {
+    if !CHANNELS.contains(&channel.as_str()) {
+        return Err(format!("Unknown update channel: {}", channel));
+    }
+    settings::update(&app, |settings| settings.updates.channel = channel).map(|_| ())
+}
+
+#[tauri::command]
+pub async fn get_update_check_settings(app: AppHandle) -> Result<UpdateCheckSettings, String> {
+    Ok(check_settings(&app))
+}
+
+#[tauri::command]
+pub async fn set_update_check_settings(app: AppHandle, settings: UpdateCheckSettings) -> Result<(), String> {
+    let check = settings;
+    settings::update(&app, |settings| {
+        settings.updates.check_interval_hours = check.interval_hours;
+        settings.updates.check_on_metered = check.check_on_metered;
+    })
+    .map(|_| ())
+}
+
+#[tauri::command]
+pub async fn skip_update_version(app: AppHandle, version: String) -> Result<(), String> {
+    let mut skipped = skipped_versions(&app);
+    if !skipped.contains(&version) {
+        skipped.push(version);
+    }
+    save_skipped_versions(&app, skipped)
+}
+
+#[tauri::command]
+pub async fn list_skipped_versions(app: AppHandle) -> Result<Vec<String>, String> {
+    Ok(skipped_versions(&app))
+}
+
+#[tauri::command]
+pub async fn clear_skipped_versions(app: AppHandle) -> Result<(), String> {
+    save_skipped_versions(&app, Vec::new())
+}
@@ -0,0 +1,583 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use sqlx::{SqliteConnection, SqlitePool};
+use tauri::{AppHandle, Emitter};
+
+use crate::db;
+use crate::transcripts::{self, TranscriptEntry};
+
+// USD per million tokens: input, output, cache write, cache read
+struct ModelPricing {
+    input: f64,
+    output: f64,
+    cache_write: f64,
+    cache_read: f64,
+}
+
+// Matched by substring against the model id in the transcript, most
+// specific first
+const MODEL_PRICING: &[(&str, ModelPricing)] = &[
+    ("opus-4-5", ModelPricing { input: 5.0, output: 25.0, cache_write: 6.25, cache_read: 0.5 }),
+    ("opus", ModelPricing { input: 15.0, output: 75.0, cache_write: 18.75, cache_read: 1.5 }),
+    ("sonnet", ModelPricing { input: 3.0, output: 15.0, cache_write: 3.75, cache_read: 0.3 }),
+    ("haiku-4-5", ModelPricing { input: 1.0, output: 5.0, cache_write: 1.25, cache_read: 0.1 }),
+    ("haiku", ModelPricing { input: 0.8, output: 4.0, cache_write: 1.0, cache_read: 0.08 }),
+];
+
+fn estimate_cost(model: &str, input: u64, output: u64, cache_write: u64, cache_read: u64) -> f64 {
+    let Some((_, pricing)) = MODEL_PRICING.iter().find(|(pattern, _)| model.contains(pattern)) else {
+        return 0.0;
+    };
+    (input as f64 * pricing.input
+        + output as f64 * pricing.output
+        + cache_write as f64 * pricing.cache_write
+        + cache_read as f64 * pricing.cache_read)
+        / 1_000_000.0
+}
+
+// The tables come from migrations/0003_usage.sql
+pub async fn usage_pool(app: &AppHandle) -> Result<SqlitePool, String> {
+    db::pool(app).await
+}
+
+#[derive(Default)]
+struct Rollup {
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_creation_tokens: u64,
+    cache_read_tokens: u64,
+    cost_usd: f64,
+    message_count: u64,
+}
+
+// Local calendar day of an RFC 3339 timestamp
+fn local_date(timestamp: &str) -> Option<String> {
+    chrono::DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string())
+}
+
+// The CLI writes one line per content block of a streamed response, each
+// repeating the message's usage, so only the first line per message counts
+fn rollup_entries(
+    entries: &[TranscriptEntry],
+    project_path: &str,
+    last_message_id: &mut Option<String>,
+    rollups: &mut HashMap<(String, String, String), Rollup>,
+) {
+    for entry in entries {
+        if entry.entry_type != "assistant" {
+            continue;
+        }
+        let Some(message) = &entry.message else { continue };
+        let Some(usage) = &message.usage else { continue };
+        if message.id.is_some() && message.id == *last_message_id {
+            continue;
+        }
+        last_message_id.clone_from(&message.id);
+
+        let Some(date) = entry.timestamp.as_deref().and_then(local_date) else { continue };
+        let model = message.model.clone().unwrap_or_else(|| "unknown".to_string());
+        // Synthetic messages (errors, interrupts) carry no real usage
+        if model == "<synthetic>" {
+            continue;
+        }
+        let project = entry.cwd.clone().unwrap_or_else(|| project_path.to_string());
+
+        let rollup = rollups.entry((date, model.clone(), project)).or_default();
+        rollup.input_tokens += usage.input_tokens;
+        rollup.output_tokens += usage.output_tokens;
+        rollup.cache_creation_tokens += usage.cache_creation_input_tokens;
+        rollup.cache_read_tokens += usage.cache_read_input_tokens;
+        rollup.cost_usd += estimate_cost(
+            &model,
+            usage.input_tokens,
+            usage.output_tokens,
+            usage.cache_creation_input_tokens,
+            usage.cache_read_input_tokens,
+        );
+        rollup.message_count += 1;
+    }
+}
+
+// Fold new transcript lines into the daily rollups. Returns whether anything changed.
+pub async fn refresh_usage(app: &AppHandle) -> Result<bool, String> {
+    let pool = usage_pool(app).await?;
+    let files = transcripts::list_transcript_files()?;
+    let mut changed = false;
+
+    for (dir_name, path) in files {
+        if aggregate_file(&pool, &dir_name, &path).await? {
+            changed = true;
+        }
+    }
+
+    if changed {
+        let _ = app.emit("usage-updated", ());
+    }
+    Ok(changed)
+}
+
+// A transcript the rollups are behind on, and where to read it from
+pub struct PendingFile {
+    pub file_path: String,
+    pub file_size: i64,
+    pub from_offset: u64,
+    last_message_id: Option<String>,
+    // Counted before, so its share is taken out of the rollups first
+    recount: bool,
+}
+
+// What was saved about a transcript the last time it was read
+struct Progress {
+    file_size: i64,
+    indexed_offset: i64,
+    last_message_id: Option<String>,
+    // Its share of the rollups is in usage_file_daily
+    per_file: bool,
+}
+
+// Where to pick a transcript up again: (offset, last message id, recount),
+// or None when nothing was added to it
+fn resume_point(progress: Option<Progress>, file_size: i64) -> Option<(u64, Option<String>, bool)> {
+    match progress {
+        None => Some((0, None, false)),
+        Some(progress) if progress.file_size == file_size => None,
+        Some(progress) if file_size > progress.file_size => {
+            Some((progress.indexed_offset as u64, progress.last_message_id, false))
+        }
+        // Transcripts are append-only; a shrunk file was rewritten and is
+        // counted again from scratch
+        Some(progress) if progress.per_file => Some((0, None, true)),
+        // Counted before its share was kept, so it can't be taken back out.
+        // Only what's added from here on is counted rather than counting
+        // the rest twice.
+        Some(_) => Some((file_size as u64, None, false)),
+    }
+}
+
+pub async fn pending_file(pool: &SqlitePool, path: &Path) -> Result<Option<PendingFile>, String> {
+    let file_path = path.to_string_lossy().to_string();
+    let file_size = std::fs::metadata(path).map(|m| m.len() as i64).unwrap_or_default();
+
+    let progress: Option<(i64, i64, Option<String>, bool)> = sqlx::query_as(
+        "SELECT file_size, indexed_offset, last_message_id, per_file FROM usage_files WHERE file_path = ?",
+    )
+    .bind(&file_path)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("Failed to read usage progress: {}", e))?;
+    let progress = progress.map(|(file_size, indexed_offset, last_message_id, per_file)| Progress {
+        file_size,
+        indexed_offset,
+        last_message_id,
+        per_file,
+    });
+
+    let Some((from_offset, last_message_id, recount)) = resume_point(progress, file_size) else {
+        return Ok(None);
+    };
+    Ok(Some(PendingFile {
+        file_path,
+        file_size,
+        from_offset,
+        last_message_id,
+        recount,
+    }))
+}
+
+// Take what a transcript added to the rollups back out of them
+async fn uncount_file(conn: &mut SqliteConnection, file_path: &str) -> Result<(), String> {
+    sqlx::query(
+        "UPDATE usage_daily SET
+            input_tokens = usage_daily.input_tokens - f.input_tokens,
+            output_tokens = usage_daily.output_tokens - f.output_tokens,
+            cache_creation_tokens = usage_daily.cache_creation_tokens - f.cache_creation_tokens,
+            cache_read_tokens = usage_daily.cache_read_tokens - f.cache_read_tokens,
+            cost_usd = usage_daily.cost_usd - f.cost_usd,
+            message_count = usage_daily.message_count - f.message_count
+         FROM usage_file_daily f
+         WHERE f.file_path = ? AND f.date = usage_daily.date AND f.model = usage_daily.model
+            AND f.project_path = usage_daily.project_path",
+    )
+    .bind(file_path)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to save usage: {}", e))?;
+    sqlx::query("DELETE FROM usage_daily WHERE message_count <= 0")
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to save usage: {}", e))?;
+    sqlx::query("DELETE FROM usage_file_daily WHERE file_path = ?")
+        .bind(file_path)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to save usage: {}", e))?;
+    Ok(())
+}
+
+// Add a rollup to one of the tables keyed like usage_daily, optionally
+// with the transcript's path in front
+async fn add_rollup(
+    conn: &mut SqliteConnection,
+    table: &str,
+    file_path: Option<&str>,
+    (date, model, project_path): &(String, String, String),
+    rollup: &Rollup,
+) -> Result<(), String> {
+    let (file_column, file_value, file_key) = match file_path {
+        Some(_) => ("file_path, ", "?, ", "file_path, "),
+        None => ("", "", ""),
+    };
+    let sql = format!(
+        "INSERT INTO {0}
+            ({1}date, model, project_path, input_tokens, output_tokens, cache_creation_tokens, cache_read_tokens,
+             cost_usd, message_count)
+         VALUES ({2}?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT ({3}date, model, project_path) DO UPDATE SET
+            input_tokens = input_tokens + excluded.input_tokens,
+            output_tokens = output_tokens + excluded.output_tokens,
+            cache_creation_tokens = cache_creation_tokens + excluded.cache_creation_tokens,
+            cache_read_tokens = cache_read_tokens + excluded.cache_read_tokens,
+            cost_usd = cost_usd + excluded.cost_usd,
+            message_count = message_count + excluded.message_count",
+        table, file_column, file_value, file_key
+    );
+    let mut query = sqlx::query(&sql);
+    if let Some(file_path) = file_path {
+        query = query.bind(file_path);
+    }
+    query
+        .bind(date)
+        .bind(model)
+        .bind(project_path)
+        .bind(rollup.input_tokens as i64)
+        .bind(rollup.output_tokens as i64)
+        .bind(rollup.cache_creation_tokens as i64)
+        .bind(rollup.cache_read_tokens as i64)
+        .bind(rollup.cost_usd)
+        .bind(rollup.message_count as i64)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("Failed to save usage: {}", e))?;
+    Ok(())
+}
+
+// Fold what was parsed from a pending file into the rollups, inside the
+// caller's transaction. The file's own share is kept alongside, so a
+// rewritten transcript can replace it instead of being counted twice.
+pub async fn save_file(
+    conn: &mut SqliteConnection,
+    file: &PendingFile,
+    dir_name: &str,
+    entries: &[TranscriptEntry],
+    next_offset: u64,
+) -> Result<(), String> {
+    if file.recount {
+        uncount_file(conn, &file.file_path).await?;
+    }
+
+    let mut last_message_id = file.last_message_id.clone();
+    let mut rollups = HashMap::new();
+    rollup_entries(entries, dir_name, &mut last_message_id, &mut rollups);
+
+    for (key, rollup) in &rollups {
+        add_rollup(conn, "usage_daily", None, key, rollup).await?;
+        add_rollup(conn, "usage_file_daily", Some(&file.file_path), key, rollup).await?;
+    }
+    // A file counted before shares were kept stays that way, since the
+    // share would only cover what was added since
+    sqlx::query(
+        "INSERT INTO usage_files (file_path, file_size, indexed_offset, last_message_id, per_file)
+         VALUES (?1, ?2, ?3, ?4, 1)
+         ON CONFLICT (file_path) DO UPDATE SET
+            file_size = ?2, indexed_offset = ?3, last_message_id = ?4,
+            per_file = usage_files.per_file OR ?5",
+    )
+    .bind(&file.file_path)
+    .bind(file.file_size)
+    .bind(next_offset as i64)
+    .bind(last_message_id)
+    .bind(file.from_offset == 0)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| format!("Failed to save usage progress: {}", e))?;
+    Ok(())
+}
+
+async fn aggregate_file(pool: &SqlitePool, dir_name: &str, path: &Path) -> Result<bool, String> {
+    let Some(file) = pending_file(pool, path).await? else {
+        return Ok(false);
+    };
+
+    let parse_path = path.to_path_buf();
+    let from_offset = file.from_offset;
+    let (entries, next_offset) =
+        tauri::async_runtime::spawn_blocking(move || transcripts::parse_from(&parse_path, from_offset, None))
+            .await
+            .map_err(|e| format!("Usage task failed: {}", e))??;
+
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    save_file(&mut tx, &file, dir_name, &entries, next_offset).await?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(true)
+}
+
+// Inclusive YYYY-MM-DD bounds; either side may be open
+#[derive(Clone, Default, serde::Deserialize)]
+pub struct UsageRange {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub project_path: Option<String>,
+}
+
+#[derive(Clone, Default, serde::Serialize)]
+pub struct UsageTotals {
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cache_creation_tokens: i64,
+    pub cache_read_tokens: i64,
+    pub cost_usd: f64,
+    pub message_count: i64,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct UsageBreakdown {
+    // The model id or the YYYY-MM-DD date, depending on the breakdown
+    pub key: String,
+    pub totals: UsageTotals,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub struct UsageSummary {
+    pub totals: UsageTotals,
+    pub by_model: Vec<UsageBreakdown>,
+    pub by_day: Vec<UsageBreakdown>,
+}
+
+type TotalsRow = (String, i64, i64, i64, i64, f64, i64);
+
+fn breakdown((key, input, output, cache_creation, cache_read, cost, messages): TotalsRow) -> UsageBreakdown {
+    UsageBreakdown {
+        key,
+        totals: UsageTotals {
+            input_tokens: input,
+            output_tokens: output,
+            cache_creation_tokens: cache_creation,
+            cache_read_tokens: cache_read,
+            cost_usd: cost,
+            message_count: messages,
+        },
+    }
+}
+
+async fn grouped(pool: &SqlitePool, group_by: &str, range: &UsageRange) -> Result<Vec<UsageBreakdown>, String> {
+    let sql = format!(
+        "SELECT {0}, SUM(input_tokens), SUM(output_tokens), SUM(cache_creation_tokens),
+                SUM(cache_read_tokens), SUM(cost_usd), SUM(message_count)
+         FROM usage_daily
+         WHERE (?1 IS NULL OR date >= ?1) AND (?2 IS NULL OR date <= ?2) AND (?3 IS NULL OR project_path = ?3)
+         GROUP BY {0} ORDER BY {0}",
+        group_by
+    );
+    let rows: Vec<TotalsRow> = sqlx::query_as(&sql)
+        .bind(&range.from)
+        .bind(&range.to)
+        .bind(&range.project_path)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("Failed to read usage: {}", e))?;
+    Ok(rows.into_iter().map(breakdown).collect())
+}
+
+#[tauri::command]
+pub async fn get_usage_summary(
+    app: AppHandle,
+    range: Option<UsageRange>,
+    refresh: Option<bool>,
+) -> Result<UsageSummary, String> {
+    if refresh.unwrap_or(true) {
+        refresh_usage(&app).await?;
+    }
+
+    let pool = usage_pool(&app).await?;
+    let range = range.unwrap_or_default();
+    let by_model = grouped(&pool, "model", &range).await?;
+    let by_day = grouped(&pool, "date", &range).await?;
+
+    let mut totals = UsageTotals::default();
+    for model in &by_model {
+        totals.input_tokens += model.totals.input_tokens;
+        totals.output_tokens += model.totals.output_tokens;
+        totals.cache_creation_tokens += model.totals.cache_creation_tokens;
+        totals.cache_read_tokens += model.totals.cache_read_tokens;
+        totals.cost_usd += model.totals.cost_usd;
+        totals.message_count += model.totals.message_count;
+    }
+
+    Ok(UsageSummary { totals, by_model, by_day })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assistant(id: &str, model: &str, timestamp: &str, cwd: Option<&str>, input: u64, output: u64) -> TranscriptEntry {
+        serde_json::from_value(serde_json::json!({
+            "type": "assistant",
+            "timestamp": timestamp,
+            "cwd": cwd,
+            "message": {
+                "id": id,
+                "role": "assistant",
+                "model": model,
+                "content": [],
+                "usage": { "input_tokens": input, "output_tokens": output }
+            }
+        }))
+        .unwrap()
+    }
+
+    fn rollup(entries: &[TranscriptEntry]) -> HashMap<(String, String, String), Rollup> {
+        let mut rollups = HashMap::new();
+        rollup_entries(entries, "project", &mut None, &mut rollups);
+        rollups
+    }
+
+    fn day(timestamp: &str) -> String {
+        local_date(timestamp).unwrap()
+    }
+
+    const NOON: &str = "2025-06-02T12:00:00Z";
+
+    #[test]
+    fn counts_each_message_once() {
+        let entries = [
+            assistant("a", "claude-sonnet-4", NOON, None, 100, 10),
+            assistant("a", "claude-sonnet-4", NOON, None, 100, 10),
+            assistant("b", "claude-sonnet-4", NOON, None, 50, 5),
+        ];
+        let rollups = rollup(&entries);
+        let total = &rollups[&(day(NOON), "claude-sonnet-4".to_string(), "project".to_string())];
+        assert_eq!(total.input_tokens, 150);
+        assert_eq!(total.output_tokens, 15);
+        assert_eq!(total.message_count, 2);
+        assert!((total.cost_usd - estimate_cost("claude-sonnet-4", 150, 15, 0, 0)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn continues_from_the_last_message_of_the_previous_read() {
+        let mut rollups = HashMap::new();
+        let mut last_message_id = Some("a".to_string());
+        rollup_entries(
+            &[assistant("a", "claude-sonnet-4", NOON, None, 100, 10)],
+            "project",
+            &mut last_message_id,
+            &mut rollups,
+        );
+        assert!(rollups.is_empty());
+        assert_eq!(last_message_id.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn skips_synthetic_and_non_assistant_entries() {
+        let mut user = assistant("u", "claude-sonnet-4", NOON, None, 100, 10);
+        user.entry_type = "user".to_string();
+        let entries = [user, assistant("s", "<synthetic>", NOON, None, 0, 0)];
+        assert!(rollup(&entries).is_empty());
+    }
+
+    #[test]
+    fn groups_by_day_model_and_working_directory() {
+        let evening = "2025-06-03T12:00:00Z";
+        let entries = [
+            assistant("a", "claude-sonnet-4", NOON, Some("/work/one"), 1, 1),
+            assistant("b", "claude-opus-4", NOON, Some("/work/one"), 1, 1),
+            assistant("c", "claude-sonnet-4", evening, None, 1, 1),
+        ];
+        let rollups = rollup(&entries);
+        assert_eq!(rollups.len(), 3);
+        assert!(rollups.contains_key(&(day(NOON), "claude-opus-4".to_string(), "/work/one".to_string())));
+        assert!(rollups.contains_key(&(day(evening), "claude-sonnet-4".to_string(), "project".to_string())));
+    }
+
+    fn progress(file_size: i64, per_file: bool) -> Option<Progress> {
+        Some(Progress {
+            file_size,
+            indexed_offset: file_size - 10,
+            last_message_id: Some("a".to_string()),
+            per_file,
+        })
+    }
+
+    #[test]
+    fn resumes_where_reading_stopped() {
+        assert_eq!(resume_point(None, 100), Some((0, None, false)));
+        assert_eq!(resume_point(progress(100, true), 100), None);
+        assert_eq!(resume_point(progress(100, true), 150), Some((90, Some("a".to_string()), false)));
+    }
+
+    #[test]
+    fn recounts_a_shrunk_file_only_when_its_share_is_known() {
+        assert_eq!(resume_point(progress(100, true), 40), Some((0, None, true)));
+        assert_eq!(resume_point(progress(100, false), 40), Some((40, None, false)));
+    }
+
+    async fn memory_pool() -> SqlitePool {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::raw_sql(include_str!("../migrations/0003_usage.sql"))
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool
+    }
+
+    async fn save(pool: &SqlitePool, from_offset: u64, recount: bool, entries: &[TranscriptEntry]) {
+        let file = PendingFile {
+            file_path: "/transcripts/session.jsonl".to_string(),
+            file_size: 100,
+            from_offset,
+            last_message_id: None,
+            recount,
+        };
+        let mut conn = pool.acquire().await.unwrap();
+        save_file(&mut conn, &file, "project", entries, 100).await.unwrap();
+    }
+
+    async fn totals(pool: &SqlitePool) -> (i64, i64) {
+        sqlx::query_as("SELECT COALESCE(SUM(input_tokens), 0), COALESCE(SUM(message_count), 0) FROM usage_daily")
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn a_recounted_file_replaces_its_share() {
+        tauri::async_runtime::block_on(async {
+            let pool = memory_pool().await;
+            save(
+                &pool,
+                0,
+                false,
+                &[
+                    assistant("a", "claude-sonnet-4", NOON, None, 100, 10),
+                    assistant("b", "claude-sonnet-4", NOON, None, 50, 5),
+                ],
+            )
+            .await;
+            assert_eq!(totals(&pool).await, (150, 2));
+
+            save(&pool, 0, true, &[assistant("c", "claude-sonnet-4", NOON, None, 30, 3)]).await;
+            assert_eq!(totals(&pool).await, (30, 1));
+
+            save(&pool, 0, true, &[]).await;
+            assert_eq!(totals(&pool).await, (0, 0));
+        });
+    }
+}
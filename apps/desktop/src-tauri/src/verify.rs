@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+use base64::Engine;
+use minisign_verify::{PublicKey, Signature};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter};
+
+use crate::{audit, downloads, net, updater};
+
+const MANIFEST_TIMEOUT: Duration = Duration::from_secs(30);
+// Files other than app and sidecar builds, such as installer scripts
+const DOWNLOADS_MANIFEST: &str = "downloads.json";
+
+#[derive(serde::Deserialize)]
+struct DownloadsManifest {
+    files: HashMap<String, Download>,
+}
+
+#[derive(serde::Deserialize)]
+struct Download {
+    url: String,
+    sha256: Option<String>,
+}
+
+// Why a downloaded file wasn't trusted, as the frontend sees it:
+// `{ kind: "checksum_mismatch", artifact, expected, actual }` and so on
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VerifyError {
+    // The manifest or file isn't signed with the app's key
+    BadSignature { artifact: String, message: String },
+    ChecksumMismatch { artifact: String, expected: String, actual: String },
+    // The signed manifest has no checksum for it
+    NotListed { artifact: String },
+    // An installed file about to run no longer matches what was verified
+    Unverified { artifact: String, path: String },
+    Failed { message: String },
+}
+
+impl From<String> for VerifyError {
+    fn from(message: String) -> Self {
+        VerifyError::Failed { message }
+    }
+}
+
+impl std::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VerifyError::BadSignature { artifact, message } => {
+                write!(f, "{} signature check failed: {}", artifact, message)
+            }
+            VerifyError::ChecksumMismatch { artifact, .. } => {
+                write!(f, "{} doesn't match its published checksum", artifact)
+            }
+            VerifyError::NotListed { artifact } => write!(f, "{} has no published checksum", artifact),
+            VerifyError::Unverified { artifact, path } => {
+                write!(f, "{} at {} has changed since it was verified", artifact, path)
+            }
+            VerifyError::Failed { message } => f.write_str(message),
+        }
+    }
+}
+
+// Emitted as `artifact-verification-failed`, for failures the UI didn't
+// ask about directly, such as a sidecar build refused at server start
+pub fn report(app: &AppHandle, error: &VerifyError) {
+    let artifact = match error {
+        VerifyError::BadSignature { artifact, .. }
+        | VerifyError::ChecksumMismatch { artifact, .. }
+        | VerifyError::NotListed { artifact }
+        | VerifyError::Unverified { artifact, .. } => artifact.as_str(),
+        VerifyError::Failed { .. } => "download",
+    };
+    audit::record(app, None, "verification", "failed", &format!("{}: {}", artifact, error));
+    let _ = app.emit("artifact-verification-failed", error.clone());
+}
+
+fn decode_base64(value: &str) -> Result<String, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(value.trim())
+        .map_err(|e| format!("Invalid base64: {}", e))?;
+    String::from_utf8(bytes).map_err(|e| format!("Invalid UTF-8: {}", e))
+}
+
+// A minisign signature, base64-encoded as `tauri signer` writes it, made
+// with the app updater's key
+pub fn check_signature(app: &AppHandle, artifact: &str, data: &[u8], signature: &str) -> Result<(), VerifyError> {
+    let bad_signature = |message: String| VerifyError::BadSignature {
+        artifact: artifact.to_string(),
+        message,
+    };
+    let public_key = PublicKey::decode(&decode_base64(&updater::public_key(app)?)?)
+        .map_err(|e| format!("Invalid public key: {}", e))?;
+    let signature = decode_base64(signature).map_err(bad_signature)?;
+    let signature = Signature::decode(&signature).map_err(|e| bad_signature(format!("Invalid signature: {}", e)))?;
+    public_key
+        .verify(data, &signature, true)
+        .map_err(|e| bad_signature(e.to_string()))
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+// `expected` comes from a signed manifest; None means it listed none
+pub fn check_sha256(artifact: &str, data: &[u8], expected: Option<&str>) -> Result<(), VerifyError> {
+    let expected = expected
+        .map(|expected| expected.trim().to_lowercase())
+        .filter(|expected| !expected.is_empty())
+        .ok_or_else(|| VerifyError::NotListed {
+            artifact: artifact.to_string(),
+        })?;
+    let actual = sha256_hex(data);
+    if actual != expected {
+        return Err(VerifyError::ChecksumMismatch {
+            artifact: artifact.to_string(),
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+// Like check_sha256, for a file too big to read into memory
+pub fn check_sha256_file(artifact: &str, path: &Path, expected: Option<&str>) -> Result<(), VerifyError> {
+    let expected = expected
+        .map(|expected| expected.trim().to_lowercase())
+        .filter(|expected| !expected.is_empty())
+        .ok_or_else(|| VerifyError::NotListed {
+            artifact: artifact.to_string(),
+        })?;
+    let actual = sha256_file(path)?;
+    if actual != expected {
+        return Err(VerifyError::ChecksumMismatch {
+            artifact: artifact.to_string(),
+            expected,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+// Whether a file installed earlier still has the hash recorded when it was
+// verified. Run before executing it.
+pub fn check_installed(artifact: &str, path: &Path, expected: Option<&str>) -> Result<(), VerifyError> {
+    let unverified = || VerifyError::Unverified {
+        artifact: artifact.to_string(),
+        path: path.to_string_lossy().to_string(),
+    };
+    let expected = expected.ok_or_else(unverified)?;
+    if sha256_file(path)? != expected {
+        return Err(unverified());
+    }
+    Ok(())
+}
+
+// A manifest published next to the app's update manifest, e.g.
+// "sidecar.json", with its signature beside it as "sidecar.json.sig". It's
+// only parsed once the signature checks out.
+pub async fn fetch_manifest<T: DeserializeOwned>(app: &AppHandle, file_name: &str) -> Result<T, VerifyError> {
+    let client = net::http_client(app, MANIFEST_TIMEOUT)?;
+    let mut last_error = VerifyError::from(format!("No endpoints configured for {}", file_name));
+    let endpoints = updater::manifest_endpoints(app, Some(file_name))?;
+    let signature_endpoints = updater::manifest_endpoints(app, Some(&format!("{}.sig", file_name)))?;
+    for (url, signature_url) in endpoints.into_iter().zip(signature_endpoints) {
+        let fetch = |url: String| {
+            let client = client.clone();
+            async move {
+                client
+                    .get(url)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())?
+                    .bytes()
+                    .await
+            }
+        };
+        let manifest = match fetch(url.to_string()).await {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                last_error = format!("Failed to fetch {}: {}", file_name, e).into();
+                continue;
+            }
+        };
+        let signature = fetch(signature_url.to_string())
+            .await
+            .map_err(|e| VerifyError::BadSignature {
+                artifact: file_name.to_string(),
+                message: format!("No signature: {}", e),
+            })?;
+        check_signature(app, file_name, &manifest, &String::from_utf8_lossy(&signature))?;
+        return serde_json::from_slice(&manifest)
+            .map_err(|e| format!("Invalid {} at {}: {}", file_name, url, e).into());
+    }
+    Err(last_error)
+}
+
+// Download `artifact` from where the signed downloads manifest says, and
+// only return it if it has the SHA-256 listed there
+pub async fn download_listed(app: &AppHandle, artifact: &str) -> Result<Vec<u8>, VerifyError> {
+    let manifest: DownloadsManifest = fetch_manifest(app, DOWNLOADS_MANIFEST).await?;
+    let download = manifest.files.get(artifact).ok_or_else(|| VerifyError::NotListed {
+        artifact: artifact.to_string(),
+    })?;
+    let bytes = downloads::download_bytes(
+        app,
+        downloads::Request {
+            url: download.url.clone(),
+            label: artifact.to_string(),
+            sha256: download.sha256.clone(),
+        },
+    )
+    .await?;
+    check_sha256(artifact, &bytes, download.sha256.as_deref())?;
+    Ok(bytes)
+}
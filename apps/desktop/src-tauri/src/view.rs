@@ -0,0 +1,76 @@
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager, WebviewWindow};
+
+use crate::settings;
+
+const ZOOM_STEP: f64 = 0.1;
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+
+// One zoom level for every window, like a browser's default zoom
+pub struct ZoomState(Mutex<f64>);
+
+impl Default for ZoomState {
+    fn default() -> Self {
+        Self(Mutex::new(1.0))
+    }
+}
+
+fn current_zoom(app: &AppHandle) -> f64 {
+    app.state::<ZoomState>().0.lock().map(|zoom| *zoom).unwrap_or(1.0)
+}
+
+pub fn apply_zoom(window: &WebviewWindow) {
+    let zoom = current_zoom(window.app_handle());
+    if zoom != 1.0 {
+        let _ = window.set_zoom(zoom);
+    }
+}
+
+pub async fn load_zoom(app: &AppHandle) -> Result<(), String> {
+    let zoom = settings::get(app).appearance.zoom_level;
+    *app.state::<ZoomState>().0.lock().map_err(|e| e.to_string())? = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+    for window in app.webview_windows().values() {
+        apply_zoom(window);
+    }
+    Ok(())
+}
+
+fn set_zoom(app: &AppHandle, zoom: f64) {
+    // Rounded so repeated steps don't drift (0.1 isn't exact in binary)
+    let zoom = (zoom.clamp(MIN_ZOOM, MAX_ZOOM) * 10.0).round() / 10.0;
+    if let Ok(mut current) = app.state::<ZoomState>().0.lock() {
+        *current = zoom;
+    }
+    for window in app.webview_windows().values() {
+        let _ = window.set_zoom(zoom);
+    }
+    let _ = settings::update(app, |settings| settings.appearance.zoom_level = zoom);
+}
+
+pub fn zoom_in(app: &AppHandle) {
+    set_zoom(app, current_zoom(app) + ZOOM_STEP);
+}
+
+pub fn zoom_out(app: &AppHandle) {
+    set_zoom(app, current_zoom(app) - ZOOM_STEP);
+}
+
+pub fn reset_zoom(app: &AppHandle) {
+    set_zoom(app, 1.0);
+}
+
+pub fn toggle_fullscreen(window: &WebviewWindow) {
+    let fullscreen = window.is_fullscreen().unwrap_or(false);
+    let _ = window.set_fullscreen(!fullscreen);
+}
+
+#[cfg(debug_assertions)]
+pub fn toggle_devtools(window: &WebviewWindow) {
+    if window.is_devtools_open() {
+        window.close_devtools();
+    } else {
+        window.open_devtools();
+    }
+}
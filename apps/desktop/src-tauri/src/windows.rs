@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, DragDropEvent, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder, Window, WindowEvent, Wry};
+use tauri_plugin_window_state::StateFlags;
+
+use crate::{drag_drop, mini_mode, project_watcher, settings, tasks, terminal, theme, title_bar, view};
+
+pub const MAIN_WINDOW: &str = "main";
+
+
+// Whether closing the main window hides it to the tray instead of quitting.
+// Cached here because window events can't wait on the database.
+pub struct WindowBehavior {
+    close_to_tray: AtomicBool,
+}
+
+impl Default for WindowBehavior {
+    fn default() -> Self {
+        Self {
+            close_to_tray: AtomicBool::new(true),
+        }
+    }
+}
+
+// What a window was opened for, handed to its webview on load
+#[derive(Clone, serde::Serialize)]
+pub struct WindowContext {
+    pub label: String,
+    pub project_id: Option<String>,
+}
+
+// Open windows other than main, keyed by label
+#[derive(Default)]
+pub struct WindowRegistry(Mutex<HashMap<String, WindowContext>>);
+
+// Size, position (and with it the monitor), maximized and fullscreen state
+// are saved on close and exit and restored when a window is created. A saved
+// position on a display that's no longer connected is ignored. Visibility is
+// left out so a window hidden to the tray doesn't come back hidden.
+pub fn state_plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_window_state::Builder::default()
+        .with_state_flags(StateFlags::SIZE | StateFlags::POSITION | StateFlags::MAXIMIZED | StateFlags::FULLSCREEN)
+        .build()
+}
+
+// Bring the main window to the front, recreating it from the config in
+// tauri.conf.json if it was closed
+pub fn show_main_window(app: &AppHandle) -> Result<(), String> {
+    let window = match app.get_webview_window(MAIN_WINDOW) {
+        Some(window) => window,
+        None => {
+            let config = app
+                .config()
+                .app
+                .windows
+                .iter()
+                .find(|window| window.label == MAIN_WINDOW)
+                .ok_or_else(|| "Main window is not configured".to_string())?;
+            let window = WebviewWindowBuilder::from_config(app, config)
+                .and_then(|builder| builder.build())
+                .map_err(|e| format!("Failed to open window: {}", e))?;
+            view::apply_zoom(&window);
+            window
+        }
+    };
+
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+    Ok(())
+}
+
+pub async fn load_behavior(app: &AppHandle) -> Result<(), String> {
+    app.state::<WindowBehavior>()
+        .close_to_tray
+        .store(settings::get(app).window.close_to_tray, Ordering::Relaxed);
+    Ok(())
+}
+
+// Closing the main window keeps the app, the sidecar and any running tasks
+// alive in the tray; quitting goes through the tray or app menu
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    match event {
+        WindowEvent::CloseRequested { api, .. } => {
+            let close_to_tray = window
+                .app_handle()
+                .state::<WindowBehavior>()
+                .close_to_tray
+                .load(Ordering::Relaxed);
+            let hide = window.label() == MAIN_WINDOW && close_to_tray;
+            // Hiding keeps tasks running; actually closing asks first
+            if !hide && tasks::intercept_close(window) {
+                api.prevent_close();
+                return;
+            }
+            // Leave mini mode first so the full-size frame is what gets saved
+            if let Some(window) = window.app_handle().get_webview_window(window.label()) {
+                let _ = mini_mode::exit(&window);
+            }
+            if hide {
+                api.prevent_close();
+                let _ = window.hide();
+            }
+        }
+        // What a window owns goes with it: its running tasks, its terminals
+        // and its holds on project watchers. Sidecar streams end on their own once the webview is
+        // gone, and command runs carry on for the next window on the project.
+        WindowEvent::Destroyed => {
+            let app = window.app_handle();
+            tasks::on_window_destroyed(app, window.label());
+            terminal::on_window_destroyed(app, window.label());
+            project_watcher::on_window_destroyed(app, window.label());
+            if let Ok(mut windows) = app.state::<WindowRegistry>().0.lock() {
+                windows.remove(window.label());
+            }
+        }
+        WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) => drag_drop::on_drop(window, paths),
+        WindowEvent::ThemeChanged(theme) => theme::on_theme_changed(window.app_handle(), *theme),
+        WindowEvent::Resized(_) => {
+            if let Some(window) = window.app_handle().get_webview_window(window.label()) {
+                title_bar::on_resized(&window);
+            }
+        }
+        WindowEvent::Focused(true) => {
+            if let Some(window) = window.app_handle().get_webview_window(window.label()) {
+                mini_mode::sync_menu(&window);
+            }
+        }
+        _ => {}
+    }
+}
+
+// The window menu events should act on: the focused one, falling back to main
+pub fn focused_window(app: &AppHandle) -> Option<WebviewWindow> {
+    let windows = app.webview_windows();
+    windows
+        .values()
+        .find(|window| window.is_focused().unwrap_or(false))
+        .or_else(|| windows.get(MAIN_WINDOW))
+        .cloned()
+}
+
+// Labels may only contain alphanumerics and `-/:_`
+fn project_window_label(project_id: &str) -> String {
+    let id: String = project_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("project-{}", id)
+}
+
+// Open a project in its own window, or focus the window it's already open in
+#[tauri::command]
+pub fn open_project_window(app: AppHandle, project_id: String) -> Result<WindowContext, String> {
+    if project_id.trim().is_empty() {
+        return Err("A project id is required".to_string());
+    }
+    let label = project_window_label(&project_id);
+    let context = WindowContext {
+        label: label.clone(),
+        project_id: Some(project_id),
+    };
+
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(context);
+    }
+
+    let main_config = app.config().app.windows.iter().find(|window| window.label == MAIN_WINDOW);
+    let (width, height, min_width, min_height) = main_config
+        .map(|config| (config.width, config.height, config.min_width, config.min_height))
+        .unwrap_or((1200.0, 800.0, None, None));
+    let init = serde_json::to_string(&context).map_err(|e| e.to_string())?;
+
+    let builder = title_bar::configure(&app, WebviewWindowBuilder::new(&app, &label, WebviewUrl::default()));
+    let mut builder = builder
+        .title("Incito")
+        .inner_size(width, height)
+        .initialization_script(format!("window.__INCITO_WINDOW__ = {};", init));
+    if let (Some(min_width), Some(min_height)) = (min_width, min_height) {
+        builder = builder.min_inner_size(min_width, min_height);
+    }
+    let window = builder
+        .build()
+        .map_err(|e| format!("Failed to open window: {}", e))?;
+    view::apply_zoom(&window);
+
+    app.state::<WindowRegistry>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(label, context.clone());
+    Ok(context)
+}
+
+#[tauri::command]
+pub fn get_window_context(
+    window: Window,
+    registry: tauri::State<'_, WindowRegistry>,
+) -> Result<WindowContext, String> {
+    let windows = registry.0.lock().map_err(|e| e.to_string())?;
+    Ok(windows.get(window.label()).cloned().unwrap_or_else(|| WindowContext {
+        label: window.label().to_string(),
+        project_id: None,
+    }))
+}
+
+#[tauri::command]
+pub fn get_close_to_tray(state: tauri::State<'_, WindowBehavior>) -> bool {
+    state.close_to_tray.load(Ordering::Relaxed)
+}
+
+#[tauri::command]
+pub async fn set_close_to_tray(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::update(&app, |settings| settings.window.close_to_tray = enabled)?;
+    app.state::<WindowBehavior>()
+        .close_to_tray
+        .store(enabled, Ordering::Relaxed);
+    Ok(())
+}
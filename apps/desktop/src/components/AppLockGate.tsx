@@ -0,0 +1,106 @@
+import { useCallback, useEffect, useRef, useState, type ReactNode } from 'react'
+import { useTranslation } from 'react-i18next'
+import { Loader2, Lock } from 'lucide-react'
+import { Button } from '@/components/ui/button'
+import { getLockState, onLockStateChanged, reportAppActivity, unlockApp } from '@/lib/app-lock'
+
+// Input is reported at most this often; the idle lock counts in minutes
+const ACTIVITY_REPORT_INTERVAL_MS = 30_000
+const ACTIVITY_EVENTS = ['keydown', 'pointerdown', 'pointermove', 'wheel'] as const
+
+interface AppLockGateProps {
+  children: ReactNode
+}
+
+// Hides the window's content while the app is locked, and reports input so
+// the idle lock only fires when the app is really left alone. The content
+// stays mounted underneath, so unsaved work survives a lock; the lock screen
+// also covers dialogs and popovers portaled outside it.
+export function AppLockGate({ children }: AppLockGateProps) {
+  const isTauri = typeof window !== 'undefined' && !!window.__TAURI_INTERNALS__
+  // Unknown until the first answer, so nothing shows before it
+  const [locked, setLocked] = useState<boolean | null>(isTauri ? null : false)
+  const lastReport = useRef(0)
+
+  useEffect(() => {
+    if (!isTauri) return
+    let cancelled = false
+    const unlisten = onLockStateChanged((value) => {
+      if (!cancelled) setLocked(value)
+    })
+    getLockState()
+      .then((value) => {
+        if (!cancelled) setLocked(value)
+      })
+      .catch(() => {
+        if (!cancelled) setLocked(true)
+      })
+    return () => {
+      cancelled = true
+      unlisten.then((fn) => fn())
+    }
+  }, [isTauri])
+
+  useEffect(() => {
+    if (!isTauri || locked !== false) return
+    const report = () => {
+      const now = Date.now()
+      if (now - lastReport.current < ACTIVITY_REPORT_INTERVAL_MS) return
+      lastReport.current = now
+      reportAppActivity().catch(() => {})
+    }
+    for (const event of ACTIVITY_EVENTS) {
+      window.addEventListener(event, report, { passive: true })
+    }
+    return () => {
+      for (const event of ACTIVITY_EVENTS) {
+        window.removeEventListener(event, report)
+      }
+    }
+  }, [isTauri, locked])
+
+  return (
+    <>
+      <div className={locked === false ? 'contents' : 'hidden'} aria-hidden={locked !== false}>
+        {children}
+      </div>
+      {locked && <LockScreen />}
+    </>
+  )
+}
+
+function LockScreen() {
+  const { t } = useTranslation('common')
+  const [unlocking, setUnlocking] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+
+  const unlock = useCallback(async () => {
+    setUnlocking(true)
+    setError(null)
+    try {
+      // On success `lock-state-changed` swaps the content back in
+      if (!(await unlockApp())) {
+        setError(t('appLock.failed'))
+      }
+    } catch (err) {
+      setError(String(err))
+    } finally {
+      setUnlocking(false)
+    }
+  }, [t])
+
+  return (
+    <div className="fixed inset-0 z-[1000] flex flex-col items-center justify-center gap-4 bg-background text-foreground">
+      <Lock className="h-10 w-10 text-muted-foreground" />
+      <div className="text-center">
+        <h1 className="text-lg font-semibold">{t('appLock.title')}</h1>
+        <p className="text-sm text-muted-foreground">{t('appLock.description')}</p>
+      </div>
+      <Button onClick={unlock} disabled={unlocking} autoFocus>
+        {unlocking && <Loader2 className="animate-spin" />}
+        {unlocking ? t('appLock.unlocking') : t('appLock.unlock')}
+      </Button>
+      {error && <p className="text-sm text-destructive">{error}</p>}
+    </div>
+  )
+}
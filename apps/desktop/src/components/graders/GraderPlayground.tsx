@@ -9,6 +9,7 @@ import { Play, Loader2, CheckCircle2, XCircle } from 'lucide-react'
 import type { Grader } from '@/types/grader'
 import { isAssertionGrader } from '@/types/grader'
 import { executeAssertionGrader } from '@/lib/grader-executor'
+import { isOk, sidecarRequest } from '@/lib/claude-code-client'
 
 interface GraderPlaygroundProps {
   grader: Grader
@@ -46,18 +47,15 @@ export function GraderPlayground({ grader }: GraderPlaygroundProps) {
         setResult(testResult)
       } else {
         // For LLM judges, we need to call the backend
-        const response = await fetch('http://localhost:3457/graders/test', {
-          method: 'POST',
-          headers: { 'Content-Type': 'application/json' },
-          body: JSON.stringify({ grader, input, output }),
+        const response = await sidecarRequest<TestResult>('POST', '/graders/test', {
+          body: { grader, input, output },
         })
 
-        if (!response.ok) {
+        if (!isOk(response)) {
           throw new Error('Failed to test grader')
         }
 
-        const data = await response.json()
-        setResult(data)
+        setResult(response.body)
       }
     } catch (error) {
       toast.error(t('errors.testFailed'))
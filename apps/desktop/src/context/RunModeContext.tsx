@@ -26,6 +26,7 @@ import {
 } from '@/lib/run-mode/system-prompt'
 import { streamChat, type StreamChatMessage } from '@/lib/mastra-client'
 import { executeAssertionGrader } from '@/lib/grader-executor'
+import { isOk, sidecarRequest } from '@/lib/claude-code-client'
 import { getPromptGraders, getGrader, saveGraderResults, getLatestPromptRunConfig, getActiveRulesForPrompt } from '@/lib/store'
 import type { PlaybookRule } from '@/types/playbook'
 import { toast } from 'sonner'
@@ -372,18 +373,18 @@ export function RunModeProvider({ children }: RunModeProviderProps) {
         const llmJudgeResults: GraderResultWithGrader[] = []
         for (const grader of llmJudgeGraders) {
           try {
-            const response = await fetch('http://localhost:3457/graders/run-llm-judge', {
-              method: 'POST',
-              headers: { 'Content-Type': 'application/json' },
-              body: JSON.stringify({ grader, input, output }),
-            })
-
-            const result = response.ok
-              ? await response.json()
+            const response = await sidecarRequest<Parameters<typeof buildResult>[1] & { error?: string }>(
+              'POST',
+              '/graders/run-llm-judge',
+              { body: { grader, input, output } }
+            )
+
+            const result = isOk(response)
+              ? response.body
               : {
                   score: 0,
                   passed: false,
-                  reason: (await response.json()).error || 'LLM judge execution failed',
+                  reason: response.body?.error || 'LLM judge execution failed',
                   executionTimeMs: 0,
                 }
 
@@ -574,20 +575,22 @@ export function RunModeProvider({ children }: RunModeProviderProps) {
           const providerId = selectedProviderIds[0]
 
           // Make API call to generate content
-          const response = await fetch('http://localhost:3457/chat', {
-            method: 'POST',
-            headers: { 'Content-Type': 'application/json' },
-            body: JSON.stringify({
+          const response = await sidecarRequest<{
+            content?: string
+            text?: string
+            usage?: Record<string, number>
+          }>('POST', '/chat', {
+            body: {
               providerId,
               messages: [{ role: 'user', content: input }],
-            }),
+            },
           })
 
-          if (!response.ok) {
+          if (!isOk(response)) {
             throw new Error(`API error: ${response.status}`)
           }
 
-          const data = await response.json()
+          const data = response.body
           const content = data.content || data.text || ''
           const tokens = data.usage || {}
 
@@ -1,4 +1,5 @@
 import { createContext, useContext, useEffect, useState } from 'react'
+import { getSettings, onSettingsChanged, updateSettings, type AppSettings } from '@/lib/settings'
 
 type Theme = 'light' | 'dark' | 'system'
 
@@ -10,8 +11,14 @@ interface ThemeContextValue {
 
 const ThemeContext = createContext<ThemeContextValue | undefined>(undefined)
 
+// The settings file is the source of truth; this copy only avoids a flash
+// of the wrong theme before it has been read
 const THEME_STORAGE_KEY = 'incito-theme'
 
+function fromSettings(settings: AppSettings): Theme {
+  return settings.appearance.theme === 'auto' ? 'system' : settings.appearance.theme
+}
+
 function getSystemTheme(): 'light' | 'dark' {
   if (typeof window !== 'undefined' && window.matchMedia('(prefers-color-scheme: dark)').matches) {
     return 'dark'
@@ -49,6 +56,20 @@ export function ThemeProvider({ children }: { children: React.ReactNode }) {
     root.classList.add(effectiveTheme)
   }, [theme])
 
+  useEffect(() => {
+    function sync(settings: AppSettings) {
+      const saved = fromSettings(settings)
+      localStorage.setItem(THEME_STORAGE_KEY, saved)
+      setThemeState(saved)
+    }
+
+    getSettings().then(sync).catch(() => {})
+    const unlisten = onSettingsChanged(sync)
+    return () => {
+      unlisten.then((fn) => fn())
+    }
+  }, [])
+
   // Listen for system theme changes
   useEffect(() => {
     if (theme !== 'system') return
@@ -69,6 +90,7 @@ export function ThemeProvider({ children }: { children: React.ReactNode }) {
   function setTheme(newTheme: Theme) {
     localStorage.setItem(THEME_STORAGE_KEY, newTheme)
     setThemeState(newTheme)
+    updateSettings({ appearance: { theme: newTheme === 'system' ? 'auto' : newTheme } }).catch(() => {})
   }
 
   return (
@@ -1,6 +1,7 @@
-import { readDir, readTextFile, writeTextFile, remove } from '@tauri-apps/plugin-fs'
+import { readDir, readTextFile, writeTextFile } from '@tauri-apps/plugin-fs'
 import { join, resolve } from '@tauri-apps/api/path'
 import { parseAgentFile, serializeAgent } from './agent-parser'
+import { moveToTrash } from './trash'
 import type { AgentFile, AgentIcon, AgentSettings, AgentTool } from '../types/agent'
 
 export interface InitialAgentContent {
@@ -134,7 +135,7 @@ export async function duplicateAgent(
 }
 
 export async function deleteAgent(agent: AgentFile): Promise<void> {
-  await remove(agent.path)
+  await moveToTrash(agent.path)
 }
 
 function generateUniqueFileName(baseName: string, existingNames: string[]): string {
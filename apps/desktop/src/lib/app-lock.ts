@@ -0,0 +1,27 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+// The app lock in src-tauri/src/app_lock.rs. While it's locked every window
+// shows the lock screen instead of its content.
+export function getLockState(): Promise<boolean> {
+  return invoke<boolean>('get_lock_state')
+}
+
+// Asks the OS to confirm it's the user; resolves to whether the app is now
+// unlocked
+export function unlockApp(): Promise<boolean> {
+  return invoke<boolean>('unlock_app')
+}
+
+export function lockApp(): Promise<void> {
+  return invoke('lock_app')
+}
+
+// Puts off the idle lock
+export function reportAppActivity(): Promise<void> {
+  return invoke('report_app_activity')
+}
+
+export function onLockStateChanged(handler: (locked: boolean) => void): Promise<UnlistenFn> {
+  return listen<{ locked: boolean }>('lock-state-changed', (event) => handler(event.payload.locked))
+}
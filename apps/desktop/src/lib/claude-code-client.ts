@@ -6,9 +6,10 @@
  * which require Node.js-compatible runtime and CLI authentication.
  */
 
-import { invoke } from '@tauri-apps/api/core'
+import { Channel, invoke } from '@tauri-apps/api/core'
 
-const CLAUDE_CODE_URL = 'http://localhost:3457'
+// Events the webview has handled are acknowledged to the bridge in batches
+const STREAM_ACK_BATCH = 16
 
 // Track the executable path the server was started with
 // undefined = unknown (server might be running from previous session)
@@ -24,6 +25,9 @@ export interface ClaudeCodePathResult {
   path?: string
   version?: string
   error?: string
+  launcher?: 'direct' | 'node' | 'cmd' | 'powershell'
+  compatible: boolean
+  compatibility_reason?: string
 }
 
 /**
@@ -66,6 +70,145 @@ interface ErrorResponse {
   code?: string
 }
 
+/**
+ * A sidecar answer relayed by the Rust bridge
+ */
+export interface SidecarResponse<T = unknown> {
+  status: number
+  body: T
+}
+
+/**
+ * What the Rust bridge sends over a stream's channel
+ */
+type SidecarStreamEvent =
+  | { type: 'open'; status: number }
+  | { type: 'message'; event?: string | null; data: string }
+  | { type: 'reconnecting'; attempt: number; delay_ms: number }
+  | { type: 'closed' }
+  | { type: 'error'; message: string; status?: number | null }
+
+/**
+ * Make a request to the sidecar through the Rust bridge.
+ * The webview never talks to the sidecar's localhost port itself.
+ */
+export async function sidecarRequest<T = unknown>(
+  method: 'GET' | 'POST',
+  path: string,
+  options: { body?: unknown; timeoutMs?: number } = {}
+): Promise<SidecarResponse<T>> {
+  return invoke<SidecarResponse<T>>('sidecar_request', {
+    method,
+    path,
+    body: options.body ?? null,
+    timeoutMs: options.timeoutMs ?? null,
+  })
+}
+
+/**
+ * Whether a relayed answer has a 2xx status
+ */
+export function isOk(response: SidecarResponse): boolean {
+  return response.status >= 200 && response.status < 300
+}
+
+/**
+ * Check whether the bridge can currently reach the sidecar
+ */
+export async function isSidecarBridgeConnected(): Promise<boolean> {
+  return invoke<boolean>('get_sidecar_bridge_status')
+}
+
+/**
+ * POST to an SSE endpoint of the sidecar through the Rust bridge.
+ * Resolves once the stream has closed, failed or been aborted.
+ */
+export async function sidecarStream(
+  path: string,
+  body: unknown,
+  callbacks: {
+    onMessage: (data: string, event?: string | null) => boolean | void
+    onClose: () => void
+    onError: (error: Error) => void
+  },
+  signal?: AbortSignal
+): Promise<void> {
+  const channel = new Channel<SidecarStreamEvent>()
+  let streamId: string | null = null
+  let unacknowledged = 0
+  let finished = false
+
+  const cancel = () => {
+    if (streamId) void invoke('cancel_sidecar_stream', { id: streamId })
+  }
+
+  const acknowledge = () => {
+    if (!streamId || unacknowledged === 0) return
+    const count = unacknowledged
+    unacknowledged = 0
+    void invoke('ack_sidecar_stream', { id: streamId, count })
+  }
+
+  return new Promise<void>((resolve) => {
+    const finish = (callback?: () => void) => {
+      if (finished) return
+      finished = true
+      signal?.removeEventListener('abort', onAbort)
+      callback?.()
+      resolve()
+    }
+    const onAbort = () => {
+      cancel()
+      finish()
+    }
+
+    channel.onmessage = (message) => {
+      if (finished) return
+      switch (message.type) {
+        case 'message': {
+          // Returning true from onMessage ends the stream early
+          let done: boolean | void
+          try {
+            done = callbacks.onMessage(message.data, message.event)
+          } catch (error) {
+            cancel()
+            finish(() => callbacks.onError(error instanceof Error ? error : new Error(String(error))))
+            return
+          }
+          if (done) {
+            cancel()
+            finish(callbacks.onClose)
+            return
+          }
+          unacknowledged += 1
+          if (unacknowledged >= STREAM_ACK_BATCH) acknowledge()
+          break
+        }
+        case 'closed':
+          finish(callbacks.onClose)
+          break
+        case 'error':
+          finish(() => callbacks.onError(new Error(message.message)))
+          break
+      }
+    }
+
+    if (signal?.aborted) {
+      finish()
+      return
+    }
+    signal?.addEventListener('abort', onAbort)
+
+    invoke<string>('sidecar_stream', { path, body, onEvent: channel })
+      .then((id) => {
+        streamId = id
+        if (finished) cancel()
+        else acknowledge()
+      })
+      .catch((error) => finish(() => callbacks.onError(error instanceof Error ? error : new Error(String(error)))))
+  })
+}
+
 /**
  * Start the Claude Code sidecar server via Tauri
  * @param executablePath - Optional custom path to the Claude Code executable
@@ -115,13 +258,9 @@ export async function isClaudeCodeServerRunning(): Promise<boolean> {
  */
 export async function checkClaudeCodeHealth(): Promise<boolean> {
   try {
-    const response = await fetch(`${CLAUDE_CODE_URL}/health`, {
-      method: 'GET',
-      signal: AbortSignal.timeout(2000),
-    })
-    if (!response.ok) return false
-    const data = (await response.json()) as HealthResponse
-    return data.status === 'ok'
+    const response = await sidecarRequest<HealthResponse>('GET', '/health', { timeoutMs: 2000 })
+    if (!isOk(response)) return false
+    return response.body.status === 'ok'
   } catch {
     return false
   }
@@ -132,11 +271,8 @@ export async function checkClaudeCodeHealth(): Promise<boolean> {
  */
 export async function checkClaudeCodeAuth(): Promise<AuthStatusResponse> {
   try {
-    const response = await fetch(`${CLAUDE_CODE_URL}/auth-status`, {
-      method: 'GET',
-      signal: AbortSignal.timeout(5000),
-    })
-    return (await response.json()) as AuthStatusResponse
+    const response = await sidecarRequest<AuthStatusResponse>('GET', '/auth-status', { timeoutMs: 5000 })
+    return response.body
   } catch (error) {
     return {
       authenticated: false,
@@ -207,24 +343,25 @@ export async function generateWithClaudeCode(
   options: {
     model?: 'opus' | 'sonnet' | 'haiku'
     system?: string
+    // The session's project, which Claude Code runs in and approvals are for
+    projectPath?: string
   } = {}
 ): Promise<GenerateResponse> {
-  const response = await fetch(`${CLAUDE_CODE_URL}/generate`, {
-    method: 'POST',
-    headers: { 'Content-Type': 'application/json' },
-    body: JSON.stringify({
+  const response = await sidecarRequest<GenerateResponse | ErrorResponse>('POST', '/generate', {
+    body: {
       prompt,
       model: options.model || 'sonnet',
       system: options.system,
-    }),
+      projectPath: options.projectPath,
+    },
   })
 
-  if (!response.ok) {
-    const error = (await response.json()) as ErrorResponse
-    throw new Error(error.error || 'Generation failed')
+  if (!isOk(response)) {
+    const error = response.body as ErrorResponse
+    throw new Error(error?.error || 'Generation failed')
   }
 
-  return (await response.json()) as GenerateResponse
+  return response.body as GenerateResponse
 }
 
 /**
@@ -235,6 +372,7 @@ export async function streamWithClaudeCode(
   options: {
     model?: 'opus' | 'sonnet' | 'haiku'
     system?: string
+    projectPath?: string
   },
   callbacks: {
     onChunk: (text: string) => void
@@ -245,72 +383,35 @@ export async function streamWithClaudeCode(
 ): Promise<void> {
   const { onChunk, onComplete, onError } = callbacks
 
-  try {
-    const response = await fetch(`${CLAUDE_CODE_URL}/stream`, {
-      method: 'POST',
-      headers: { 'Content-Type': 'application/json' },
-      body: JSON.stringify({
-        messages,
-        model: options.model || 'sonnet',
-        system: options.system,
-      }),
-      signal,
-    })
-
-    if (!response.ok) {
-      const error = (await response.json()) as ErrorResponse
-      throw new Error(error.error || 'Stream request failed')
-    }
-
-    const reader = response.body?.getReader()
-    if (!reader) {
-      throw new Error('No response body')
-    }
-
-    const decoder = new TextDecoder()
-    let buffer = ''
-
-    while (true) {
-      const { done, value } = await reader.read()
-      if (done) break
-
-      buffer += decoder.decode(value, { stream: true })
-
-      // Process complete SSE messages
-      const lines = buffer.split('\n')
-      buffer = lines.pop() || '' // Keep incomplete line in buffer
-
-      for (const line of lines) {
-        if (line.startsWith('data: ')) {
-          const data = line.slice(6)
-          try {
-            const parsed = JSON.parse(data) as {
-              type: 'text' | 'done' | 'error'
-              content?: string
-              error?: string
-            }
-
-            if (parsed.type === 'text' && parsed.content) {
-              onChunk(parsed.content)
-            } else if (parsed.type === 'done') {
-              onComplete()
-              return
-            } else if (parsed.type === 'error') {
-              throw new Error(parsed.error || 'Stream error')
-            }
-          } catch (parseError) {
-            // Ignore JSON parse errors for incomplete data
-            if (parseError instanceof SyntaxError) continue
-            throw parseError
-          }
+  await sidecarStream(
+    '/stream',
+    {
+      messages,
+      model: options.model || 'sonnet',
+      system: options.system,
+      projectPath: options.projectPath,
+    },
+    {
+      onMessage: (data) => {
+        let parsed: { type: 'text' | 'done' | 'error'; content?: string; error?: string }
+        try {
+          parsed = JSON.parse(data)
+        } catch {
+          // Ignore events that aren't JSON
+          return
         }
-      }
-    }
 
-    onComplete()
-  } catch (error) {
-    if ((error as Error).name !== 'AbortError') {
-      onError(error instanceof Error ? error : new Error(String(error)))
-    }
-  }
+        if (parsed.type === 'text' && parsed.content) {
+          onChunk(parsed.content)
+        } else if (parsed.type === 'done') {
+          return true
+        } else if (parsed.type === 'error') {
+          throw new Error(parsed.error || 'Stream error')
+        }
+      },
+      onClose: onComplete,
+      onError,
+    },
+    signal
+  )
 }
@@ -0,0 +1,36 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+// Mirrors DownloadInfo in src-tauri/src/downloads.rs. Downloads are started
+// on the Rust side (sidecar updates, the CLI installer); the UI can show,
+// pause and cancel them.
+export interface DownloadInfo {
+  id: string
+  label: string
+  url: string
+  status: 'queued' | 'downloading' | 'paused' | 'verifying' | 'completed' | 'failed' | 'cancelled'
+  received: number
+  total: number | null
+  bytes_per_second: number
+  error: string | null
+}
+
+export function listDownloads(): Promise<DownloadInfo[]> {
+  return invoke<DownloadInfo[]>('list_downloads')
+}
+
+export function pauseDownload(id: string): Promise<void> {
+  return invoke('pause_download', { id })
+}
+
+export function resumeDownload(id: string): Promise<void> {
+  return invoke('resume_download', { id })
+}
+
+export function cancelDownload(id: string): Promise<void> {
+  return invoke('cancel_download', { id })
+}
+
+export function onDownloadProgress(handler: (download: DownloadInfo) => void): Promise<UnlistenFn> {
+  return listen<DownloadInfo>('download-progress', (event) => handler(event.payload))
+}
@@ -1,8 +1,9 @@
-import { readDir, readTextFile, writeTextFile, remove } from '@tauri-apps/plugin-fs'
+import { readDir, readTextFile, writeTextFile } from '@tauri-apps/plugin-fs'
 import { join, resolve } from '@tauri-apps/api/path'
 import { parsePromptFile, serializePrompt } from './parser'
 import { syncPromptTags, createPromptVersion, deletePromptVersions, hasAIConfigured, setBaseFolderPath } from './store'
 import { summarizePromptChanges } from './mastra-client'
+import { moveToTrash } from './trash'
 import type { PromptFile, Variable } from '../types/prompt'
 
 export interface InitialPromptContent {
@@ -147,7 +148,7 @@ export async function deletePrompt(prompt: PromptFile, folderPath: string): Prom
     throw new Error('Path traversal detected: cannot delete prompt outside of prompts folder')
   }
 
-  await remove(prompt.path)
+  await moveToTrash(prompt.path)
   // Clean up versions when prompt is deleted
   await deletePromptVersions(prompt.path)
 }
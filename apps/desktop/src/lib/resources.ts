@@ -1,16 +1,17 @@
 import Database from '@tauri-apps/plugin-sql'
-import { readFile, writeFile, remove, mkdir, exists } from '@tauri-apps/plugin-fs'
+import { readFile, writeFile, mkdir, exists } from '@tauri-apps/plugin-fs'
 import { join } from '@tauri-apps/api/path'
 import type { Resource, ResourceType, ResourceChunk } from '@/types/resource'
 import { getResourceTypeFromExtension, isSupportedExtension } from '@/types/resource'
 import type { Result } from './store'
+import { moveToTrash } from './trash'
 
 // Database singleton - reuse from store
 let db: Database | null = null
 
 async function getDb(): Promise<Database> {
   if (!db) {
-    db = await Database.load('sqlite:incito.db')
+    db = Database.get('sqlite:incito.db')
   }
   return db
 }
@@ -229,7 +230,7 @@ export async function deleteResource(id: string, promptsFolder: string): Promise
     const filePath = await join(resourcesDir, resourceResult.data.filePath)
 
     try {
-      await remove(filePath)
+      await moveToTrash(filePath)
     } catch {
       // File might not exist, continue with DB deletion
     }
@@ -0,0 +1,57 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+// Mirrors AppSettings in src-tauri/src/settings.rs, which owns the file
+export interface AppSettings {
+  server: {
+    claude_code_executable_path: string | null
+  }
+  updates: {
+    channel: 'stable' | 'beta' | 'nightly'
+    check_interval_hours: number
+    check_on_metered: boolean
+    skipped_versions: string[]
+  }
+  appearance: {
+    theme: 'light' | 'dark' | 'auto'
+    zoom_level: number
+    language: string | null
+  }
+  shortcuts: {
+    global: string | null
+    menu: Record<string, string>
+  }
+  window: {
+    close_to_tray: boolean
+  }
+  network: {
+    proxy: string | null
+    ca_bundle: string | null
+  }
+  retention: {
+    session_max_age_days: number | null
+    run_max_age_days: number | null
+    max_database_mb: number | null
+  }
+  tasks: {
+    dirty_worktree: 'allow' | 'prompt' | 'refuse'
+  }
+  telemetry_consent: boolean | null
+}
+
+type DeepPartial<T> = { [K in keyof T]?: T[K] extends object ? (T[K] extends unknown[] ? T[K] : DeepPartial<T[K]>) : T[K] }
+
+export type SettingsPatch = DeepPartial<AppSettings>
+
+export function getSettings(): Promise<AppSettings> {
+  return invoke<AppSettings>('get_settings')
+}
+
+// Only the fields in the patch change; nested objects are merged
+export function updateSettings(patch: SettingsPatch): Promise<AppSettings> {
+  return invoke<AppSettings>('update_settings', { patch })
+}
+
+export function onSettingsChanged(handler: (settings: AppSettings) => void): Promise<UnlistenFn> {
+  return listen<AppSettings>('settings-changed', (event) => handler(event.payload))
+}
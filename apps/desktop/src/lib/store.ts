@@ -1,4 +1,6 @@
 import Database from '@tauri-apps/plugin-sql'
+import { invoke } from '@tauri-apps/api/core'
+import { getSettings, updateSettings } from './settings'
 import type { Tag, TranslationSettings, TranslationCacheEntry, LanguageCode, TranslationConfidence } from '../types/prompt'
 import type {
   PromptRun,
@@ -191,456 +193,10 @@ let db: Database | null = null
 
 async function getDb(): Promise<Database> {
   if (!db) {
-    db = await Database.load('sqlite:incito.db')
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS settings (
-        key TEXT PRIMARY KEY,
-        value TEXT
-      )
-    `)
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS tags (
-        id TEXT PRIMARY KEY,
-        name TEXT UNIQUE NOT NULL,
-        color TEXT DEFAULT '#6b7280'
-      )
-    `)
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS prompt_tags (
-        prompt_path TEXT NOT NULL,
-        tag_id TEXT NOT NULL,
-        PRIMARY KEY (prompt_path, tag_id),
-        FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
-      )
-    `)
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS prompt_versions (
-        id TEXT PRIMARY KEY,
-        prompt_path TEXT NOT NULL,
-        version_number INTEGER NOT NULL,
-        content TEXT NOT NULL,
-        created_at TEXT NOT NULL,
-        description TEXT,
-        UNIQUE(prompt_path, version_number)
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_prompt_versions_path ON prompt_versions(prompt_path)
-    `)
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS prompt_drafts (
-        prompt_id TEXT PRIMARY KEY,
-        variable_values TEXT NOT NULL,
-        updated_at TEXT NOT NULL
-      )
-    `)
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS recent_prompts (
-        prompt_id TEXT PRIMARY KEY,
-        last_used_at TEXT NOT NULL
-      )
-    `)
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS pinned_prompts (
-        prompt_id TEXT PRIMARY KEY,
-        pinned_at TEXT NOT NULL
-      )
-    `)
-    // Translation cache table
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS translation_cache (
-        hash TEXT PRIMARY KEY,
-        source_lang TEXT NOT NULL,
-        target_lang TEXT NOT NULL,
-        source_text TEXT NOT NULL,
-        translated_text TEXT NOT NULL,
-        confidence TEXT NOT NULL,
-        created_at TEXT NOT NULL
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_translation_cache_created ON translation_cache(created_at)
-    `)
-
-    // Prompt runs table for tracking executions
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS prompt_runs (
-        id TEXT PRIMARY KEY,
-        prompt_id TEXT NOT NULL,
-        prompt_path TEXT NOT NULL,
-        prompt_name TEXT NOT NULL,
-        launcher_id TEXT NOT NULL,
-        status TEXT NOT NULL DEFAULT 'pending',
-        error_message TEXT,
-        started_at TEXT NOT NULL,
-        completed_at TEXT,
-        execution_time_ms INTEGER,
-        run_file_path TEXT,
-        created_at TEXT NOT NULL
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_prompt_runs_prompt_id ON prompt_runs(prompt_id)
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_prompt_runs_created_at ON prompt_runs(created_at)
-    `)
-
-    // Run variables table for storing variable values per run
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS run_variables (
-        id TEXT PRIMARY KEY,
-        run_id TEXT NOT NULL,
-        variable_key TEXT NOT NULL,
-        variable_value TEXT NOT NULL,
-        variable_type TEXT NOT NULL,
-        FOREIGN KEY (run_id) REFERENCES prompt_runs(id) ON DELETE CASCADE
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_run_variables_run_id ON run_variables(run_id)
-    `)
-
-    // Run analytics daily aggregation table
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS run_analytics_daily (
-        id TEXT PRIMARY KEY,
-        prompt_id TEXT NOT NULL,
-        date TEXT NOT NULL,
-        run_count INTEGER NOT NULL DEFAULT 0,
-        success_count INTEGER NOT NULL DEFAULT 0,
-        error_count INTEGER NOT NULL DEFAULT 0,
-        total_execution_time_ms INTEGER NOT NULL DEFAULT 0,
-        avg_execution_time_ms INTEGER NOT NULL DEFAULT 0,
-        UNIQUE(prompt_id, date)
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_run_analytics_prompt_id ON run_analytics_daily(prompt_id)
-    `)
-
-    // Add token columns to prompt_runs (migration for existing DBs)
-    // SQLite doesn't support IF NOT EXISTS for columns, so we use try/catch
-    try {
-      await db.execute(`ALTER TABLE prompt_runs ADD COLUMN input_tokens INTEGER`)
-    } catch { /* Column may already exist */ }
-    try {
-      await db.execute(`ALTER TABLE prompt_runs ADD COLUMN output_tokens INTEGER`)
-    } catch { /* Column may already exist */ }
-    try {
-      await db.execute(`ALTER TABLE prompt_runs ADD COLUMN total_tokens INTEGER`)
-    } catch { /* Column may already exist */ }
-    try {
-      await db.execute(`ALTER TABLE prompt_runs ADD COLUMN model_id TEXT`)
-    } catch { /* Column may already exist */ }
-    try {
-      await db.execute(`ALTER TABLE prompt_runs ADD COLUMN provider TEXT`)
-    } catch { /* Column may already exist */ }
-    try {
-      await db.execute(`ALTER TABLE prompt_runs ADD COLUMN estimated_cost_usd REAL`)
-    } catch { /* Column may already exist */ }
-
-    // Add token columns to run_analytics_daily (migration for existing DBs)
-    try {
-      await db.execute(`ALTER TABLE run_analytics_daily ADD COLUMN total_input_tokens INTEGER NOT NULL DEFAULT 0`)
-    } catch { /* Column may already exist */ }
-    try {
-      await db.execute(`ALTER TABLE run_analytics_daily ADD COLUMN total_output_tokens INTEGER NOT NULL DEFAULT 0`)
-    } catch { /* Column may already exist */ }
-    try {
-      await db.execute(`ALTER TABLE run_analytics_daily ADD COLUMN total_estimated_cost_usd REAL NOT NULL DEFAULT 0`)
-    } catch { /* Column may already exist */ }
-
-    // Chat sessions table for agent conversations
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS chat_sessions (
-        id TEXT PRIMARY KEY,
-        agent_id TEXT NOT NULL,
-        title TEXT NOT NULL,
-        created_at TEXT NOT NULL,
-        updated_at TEXT NOT NULL
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_chat_sessions_agent_id ON chat_sessions(agent_id)
-    `)
-
-    // Chat messages table for conversation history
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS chat_messages (
-        id TEXT PRIMARY KEY,
-        session_id TEXT NOT NULL,
-        role TEXT NOT NULL,
-        content TEXT NOT NULL,
-        timestamp TEXT NOT NULL,
-        tool_calls TEXT,
-        FOREIGN KEY (session_id) REFERENCES chat_sessions(id) ON DELETE CASCADE
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_chat_messages_session_id ON chat_messages(session_id)
-    `)
-
-    // Pinned agents table
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS pinned_agents (
-        agent_id TEXT PRIMARY KEY,
-        pinned_at TEXT NOT NULL
-      )
-    `)
-
-    // Resources table for asset management
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS resources (
-        id TEXT PRIMARY KEY,
-        file_name TEXT NOT NULL,
-        file_path TEXT NOT NULL UNIQUE,
-        file_type TEXT NOT NULL,
-        mime_type TEXT NOT NULL,
-        file_size INTEGER NOT NULL,
-        uploaded_at TEXT NOT NULL,
-        indexed INTEGER NOT NULL DEFAULT 0,
-        indexed_at TEXT,
-        chunk_count INTEGER DEFAULT 0,
-        thumbnail_base64 TEXT
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_resources_file_type ON resources(file_type)
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_resources_uploaded_at ON resources(uploaded_at)
-    `)
-
-    // Resource chunks table for RAG indexing
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS resource_chunks (
-        id TEXT PRIMARY KEY,
-        resource_id TEXT NOT NULL,
-        chunk_index INTEGER NOT NULL,
-        content TEXT NOT NULL,
-        embedding TEXT,
-        created_at TEXT NOT NULL,
-        FOREIGN KEY (resource_id) REFERENCES resources(id) ON DELETE CASCADE
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_resource_chunks_resource_id ON resource_chunks(resource_id)
-    `)
-
-    // Graders table (stores both Assertion and LLM Judge types)
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS graders (
-        id TEXT PRIMARY KEY,
-        name TEXT NOT NULL,
-        description TEXT,
-        type TEXT NOT NULL CHECK (type IN ('assertion', 'llm_judge')),
-        config TEXT NOT NULL,
-        is_builtin INTEGER NOT NULL DEFAULT 0,
-        enabled INTEGER NOT NULL DEFAULT 1,
-        created_at TEXT NOT NULL,
-        updated_at TEXT NOT NULL
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_graders_type ON graders(type)
-    `)
-
-    // Prompt-Grader associations (which graders to run for each prompt)
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS prompt_graders (
-        id TEXT PRIMARY KEY,
-        prompt_id TEXT NOT NULL,
-        grader_id TEXT NOT NULL,
-        enabled INTEGER NOT NULL DEFAULT 1,
-        created_at TEXT NOT NULL,
-        UNIQUE(prompt_id, grader_id)
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_prompt_graders_prompt ON prompt_graders(prompt_id)
-    `)
-
-    // Grader results linked to runs
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS grader_results (
-        id TEXT PRIMARY KEY,
-        run_id TEXT NOT NULL,
-        grader_id TEXT NOT NULL,
-        score REAL NOT NULL,
-        passed INTEGER NOT NULL,
-        reason TEXT,
-        raw_score REAL,
-        execution_time_ms INTEGER NOT NULL,
-        created_at TEXT NOT NULL,
-        FOREIGN KEY (run_id) REFERENCES prompt_runs(id) ON DELETE CASCADE,
-        FOREIGN KEY (grader_id) REFERENCES graders(id) ON DELETE CASCADE
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_grader_results_run ON grader_results(run_id)
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_grader_results_passed ON grader_results(passed)
-    `)
-
-    // Run feedback table for expert human feedback
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS run_feedback (
-        id TEXT PRIMARY KEY,
-        run_id TEXT NOT NULL UNIQUE,
-        rating INTEGER,
-        pass_fail TEXT CHECK (pass_fail IN ('pass', 'fail')),
-        notes TEXT,
-        tags TEXT,
-        time_spent_ms INTEGER,
-        reviewed_at TEXT NOT NULL,
-        created_at TEXT NOT NULL,
-        updated_at TEXT NOT NULL,
-        FOREIGN KEY (run_id) REFERENCES prompt_runs(id) ON DELETE CASCADE
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_run_feedback_run_id ON run_feedback(run_id)
-    `)
-
-    // Add run_config column to prompt_versions (migration for existing DBs)
-    try {
-      await db.execute(`ALTER TABLE prompt_versions ADD COLUMN run_config TEXT`)
-    } catch { /* Column may already exist */ }
-
-    // Playbooks table (stores behavior teaching containers)
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS playbooks (
-        id TEXT PRIMARY KEY,
-        name TEXT NOT NULL,
-        description TEXT,
-        enabled INTEGER NOT NULL DEFAULT 1,
-        rule_count INTEGER NOT NULL DEFAULT 0,
-        created_at TEXT NOT NULL,
-        updated_at TEXT NOT NULL
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_playbooks_enabled ON playbooks(enabled)
-    `)
-
-    // Playbook rules table (teaching units with before/after examples)
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS playbook_rules (
-        id TEXT PRIMARY KEY,
-        playbook_id TEXT NOT NULL,
-        trigger_context TEXT NOT NULL,
-        instruction TEXT NOT NULL,
-        bad_example_input TEXT,
-        bad_example_output TEXT,
-        golden_output TEXT,
-        source_run_id TEXT,
-        priority INTEGER NOT NULL DEFAULT 100,
-        enabled INTEGER NOT NULL DEFAULT 1,
-        created_at TEXT NOT NULL,
-        updated_at TEXT NOT NULL,
-        FOREIGN KEY (playbook_id) REFERENCES playbooks(id) ON DELETE CASCADE,
-        FOREIGN KEY (source_run_id) REFERENCES prompt_runs(id) ON DELETE SET NULL
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_playbook_rules_playbook ON playbook_rules(playbook_id)
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_playbook_rules_priority ON playbook_rules(priority)
-    `)
-
-    // Prompt-Playbook associations (which playbooks to apply for each prompt)
-    await db.execute(`
-      CREATE TABLE IF NOT EXISTS prompt_playbooks (
-        id TEXT PRIMARY KEY,
-        prompt_id TEXT NOT NULL,
-        playbook_id TEXT NOT NULL,
-        "order" INTEGER NOT NULL DEFAULT 0,
-        enabled INTEGER NOT NULL DEFAULT 1,
-        created_at TEXT NOT NULL,
-        UNIQUE(prompt_id, playbook_id),
-        FOREIGN KEY (playbook_id) REFERENCES playbooks(id) ON DELETE CASCADE
-      )
-    `)
-    await db.execute(`
-      CREATE INDEX IF NOT EXISTS idx_prompt_playbooks_prompt ON prompt_playbooks(prompt_id)
-    `)
-
-    // Tag timestamps migration (sync-ready)
-    try {
-      await db.execute(`ALTER TABLE tags ADD COLUMN created_at TEXT`)
-    } catch { /* Column may already exist */ }
-    try {
-      await db.execute(`ALTER TABLE tags ADD COLUMN updated_at TEXT`)
-    } catch { /* Column may already exist */ }
-    // Backfill existing tags with current timestamp
-    const now = new Date().toISOString()
-    await db.execute(
-      `UPDATE tags SET created_at = ?, updated_at = ? WHERE created_at IS NULL`,
-      [now, now]
-    )
-
-    // SyncId migration (sync-ready) - add immutable UUID for future cloud sync
-    // Tags
-    try {
-      await db.execute(`ALTER TABLE tags ADD COLUMN sync_id TEXT`)
-    } catch { /* Column may already exist */ }
-    // Graders
-    try {
-      await db.execute(`ALTER TABLE graders ADD COLUMN sync_id TEXT`)
-    } catch { /* Column may already exist */ }
-    // Playbooks
-    try {
-      await db.execute(`ALTER TABLE playbooks ADD COLUMN sync_id TEXT`)
-    } catch { /* Column may already exist */ }
-    // Playbook rules
-    try {
-      await db.execute(`ALTER TABLE playbook_rules ADD COLUMN sync_id TEXT`)
-    } catch { /* Column may already exist */ }
-    // Chat sessions
-    try {
-      await db.execute(`ALTER TABLE chat_sessions ADD COLUMN sync_id TEXT`)
-    } catch { /* Column may already exist */ }
-    // Prompt runs
-    try {
-      await db.execute(`ALTER TABLE prompt_runs ADD COLUMN sync_id TEXT`)
-    } catch { /* Column may already exist */ }
-
-    // Backfill syncId for existing rows
-    const tables = ['tags', 'graders', 'playbooks', 'playbook_rules', 'chat_sessions', 'prompt_runs']
-    for (const table of tables) {
-      const rows = await db.select<{ id: string }[]>(
-        `SELECT id FROM ${table} WHERE sync_id IS NULL`
-      )
-      for (const row of rows) {
-        await db.execute(
-          `UPDATE ${table} SET sync_id = ? WHERE id = ?`,
-          [crypto.randomUUID(), row.id]
-        )
-      }
-    }
-
-    // Create unique indexes for syncId columns
-    try {
-      await db.execute(`CREATE UNIQUE INDEX IF NOT EXISTS idx_tags_sync_id ON tags(sync_id)`)
-    } catch { /* Index may already exist */ }
-    try {
-      await db.execute(`CREATE UNIQUE INDEX IF NOT EXISTS idx_graders_sync_id ON graders(sync_id)`)
-    } catch { /* Index may already exist */ }
-    try {
-      await db.execute(`CREATE UNIQUE INDEX IF NOT EXISTS idx_playbooks_sync_id ON playbooks(sync_id)`)
-    } catch { /* Index may already exist */ }
-    try {
-      await db.execute(`CREATE UNIQUE INDEX IF NOT EXISTS idx_playbook_rules_sync_id ON playbook_rules(sync_id)`)
-    } catch { /* Index may already exist */ }
-    try {
-      await db.execute(`CREATE UNIQUE INDEX IF NOT EXISTS idx_chat_sessions_sync_id ON chat_sessions(sync_id)`)
-    } catch { /* Index may already exist */ }
-    try {
-      await db.execute(`CREATE UNIQUE INDEX IF NOT EXISTS idx_prompt_runs_sync_id ON prompt_runs(sync_id)`)
-    } catch { /* Index may already exist */ }
+    // The Rust side opens the pool at startup, unlocking it when it's
+    // encrypted, and migrates the schema (src-tauri/migrations) before this
+    // window is created. Loading it here would reconnect without the key.
+    db = Database.get('sqlite:incito.db')
   }
   return db
 }
@@ -857,18 +413,14 @@ export async function getTagUsageCounts(): Promise<Result<Map<string, number>>>
 
 // AI Settings operations
 
-// Store API key in SQLite settings table
-// Note: For enhanced security, consider using Tauri's secure-store plugin in the future
-const API_KEY_SETTING = 'secure_api_key'
+// The API key lives in the OS keychain. Older versions kept it in the
+// settings table, as 'ai_api_key' and then 'secure_api_key'.
+const API_KEY_SECRET = 'ai-api-key'
+const LEGACY_API_KEY_SETTINGS = ['secure_api_key', 'ai_api_key']
 
 async function getApiKey(): Promise<Result<string | null>> {
   try {
-    const database = await getDb()
-    const result = await database.select<{ value: string }[]>(
-      'SELECT value FROM settings WHERE key = ?',
-      [API_KEY_SETTING]
-    )
-    return { ok: true, data: result.length > 0 ? result[0].value : null }
+    return { ok: true, data: await invoke<string | null>('get_secret', { name: API_KEY_SECRET }) }
   } catch (err) {
     return { ok: false, error: `Failed to get API key: ${err instanceof Error ? err.message : String(err)}` }
   }
@@ -876,14 +428,10 @@ async function getApiKey(): Promise<Result<string | null>> {
 
 async function saveApiKey(apiKey: string | null): Promise<Result<void>> {
   try {
-    const database = await getDb()
     if (apiKey) {
-      await database.execute(
-        'INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)',
-        [API_KEY_SETTING, apiKey]
-      )
+      await invoke('set_secret', { name: API_KEY_SECRET, value: apiKey })
     } else {
-      await database.execute('DELETE FROM settings WHERE key = ?', [API_KEY_SETTING])
+      await invoke('delete_secret', { name: API_KEY_SECRET })
     }
     return { ok: true, data: undefined }
   } catch (err) {
@@ -891,6 +439,23 @@ async function saveApiKey(apiKey: string | null): Promise<Result<void>> {
   }
 }
 
+// Move a key saved by an older version into the keychain, and out of the
+// database either way
+async function migrateLegacyApiKey(database: Database, current: string | null): Promise<Result<string | null>> {
+  let apiKey = current
+  for (const key of LEGACY_API_KEY_SETTINGS) {
+    const result = await database.select<{ value: string }[]>('SELECT value FROM settings WHERE key = ?', [key])
+    if (result.length === 0) continue
+    if (!apiKey && result[0].value) {
+      const saved = await saveApiKey(result[0].value)
+      if (!saved.ok) return saved
+      apiKey = result[0].value
+    }
+    await database.execute('DELETE FROM settings WHERE key = ?', [key])
+  }
+  return { ok: true, data: apiKey }
+}
+
 export async function getAISettings(): Promise<Result<AISettings>> {
   try {
     const database = await getDb()
@@ -902,26 +467,11 @@ export async function getAISettings(): Promise<Result<AISettings>> {
       'SELECT value FROM settings WHERE key = ?',
       ['ai_model']
     )
-    const claudeCodePathResult = await database.select<{ value: string }[]>(
-      'SELECT value FROM settings WHERE key = ?',
-      ['claude_code_executable_path']
-    )
-
-    // Migrate from old 'ai_api_key' to new 'secure_api_key' if needed
-    const oldApiKeyResult = await database.select<{ value: string }[]>(
-      'SELECT value FROM settings WHERE key = ?',
-      ['ai_api_key']
-    )
-
-    let apiKey = await getApiKey()
-
-    if (apiKey.ok && !apiKey.data && oldApiKeyResult.length > 0) {
-      const oldApiKey = oldApiKeyResult[0].value
-      await saveApiKey(oldApiKey)
-      await database.execute('DELETE FROM settings WHERE key = ?', ['ai_api_key'])
-      apiKey = { ok: true, data: oldApiKey }
-    }
+    const appSettings = await getSettings()
 
+    const current = await getApiKey()
+    if (!current.ok) return current
+    const apiKey = await migrateLegacyApiKey(database, current.data)
     if (!apiKey.ok) return apiKey
 
     return {
@@ -930,7 +480,7 @@ export async function getAISettings(): Promise<Result<AISettings>> {
         provider: providerResult.length > 0 ? (providerResult[0].value as AIProvider) : null,
         apiKey: apiKey.data,
         model: modelResult.length > 0 ? modelResult[0].value : null,
-        claudeCodeExecutablePath: claudeCodePathResult.length > 0 ? claudeCodePathResult[0].value : null,
+        claudeCodeExecutablePath: appSettings.server.claude_code_executable_path,
       },
     }
   } catch (err) {
@@ -956,8 +506,10 @@ export async function saveAISettings(settings: Partial<AISettings>): Promise<Res
     if (settings.apiKey !== undefined) {
       const result = await saveApiKey(settings.apiKey)
       if (!result.ok) return result
-      // Ensure old key is removed from DB
-      await database.execute('DELETE FROM settings WHERE key = ?', ['ai_api_key'])
+      // Ensure no old copy is left in the DB
+      for (const key of LEGACY_API_KEY_SETTINGS) {
+        await database.execute('DELETE FROM settings WHERE key = ?', [key])
+      }
     }
 
     if (settings.model !== undefined) {
@@ -972,14 +524,9 @@ export async function saveAISettings(settings: Partial<AISettings>): Promise<Res
     }
 
     if (settings.claudeCodeExecutablePath !== undefined) {
-      if (settings.claudeCodeExecutablePath === null || settings.claudeCodeExecutablePath === '') {
-        await database.execute('DELETE FROM settings WHERE key = ?', ['claude_code_executable_path'])
-      } else {
-        await database.execute(
-          'INSERT OR REPLACE INTO settings (key, value) VALUES (?, ?)',
-          ['claude_code_executable_path', settings.claudeCodeExecutablePath]
-        )
-      }
+      await updateSettings({
+        server: { claude_code_executable_path: settings.claudeCodeExecutablePath || null },
+      })
     }
 
     return { ok: true, data: undefined }
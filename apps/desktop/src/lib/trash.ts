@@ -0,0 +1,21 @@
+import { invoke } from '@tauri-apps/api/core'
+
+// Mirrors TrashResult in src-tauri/src/trash.rs
+export interface TrashResult {
+  path: string
+  ok: boolean
+  error?: string
+}
+
+// Move files or folders to the Recycle Bin/Trash, one result per path
+export function trashPaths(paths: string[]): Promise<TrashResult[]> {
+  return invoke<TrashResult[]>('trash_paths', { paths })
+}
+
+// Deletes made from the app go to the trash so they can be undone
+export async function moveToTrash(path: string): Promise<void> {
+  const [result] = await trashPaths([path])
+  if (!result?.ok) {
+    throw new Error(result?.error ?? `Failed to move ${path} to the trash`)
+  }
+}
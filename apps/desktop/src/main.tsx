@@ -15,6 +15,7 @@ import { RunModeProvider } from './context/RunModeContext'
 import { AddonProvider } from './context/AddonContext'
 import { UpdateProvider } from './context/UpdateContext'
 import { ErrorBoundary } from './components/ErrorBoundary'
+import { AppLockGate } from './components/AppLockGate'
 import './i18n' // Initialize i18n
 import './index.css'
 
@@ -29,7 +30,9 @@ ReactDOM.createRoot(document.getElementById('root')!).render(
                 <RunModeProvider>
                   <AddonProvider>
                     <UpdateProvider>
-                      <RouterProvider router={router} />
+                      <AppLockGate>
+                        <RouterProvider router={router} />
+                      </AppLockGate>
                     </UpdateProvider>
                   </AddonProvider>
                 </RunModeProvider>